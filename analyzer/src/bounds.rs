@@ -0,0 +1,416 @@
+//! Static bounds on the number of iterations performed by `for` loops.
+//!
+//! Vyper only permits loops whose iteration count can be determined at
+//! compile-time.  This makes it possible to compute an upper bound on the gas
+//! used by any function.  A loop over `range(...)` is bounded if it has one of
+//! the following forms:
+//!
+//! * `range(CONST)` -- iterates `CONST` times.
+//! * `range(CONST_1, CONST_2)` -- iterates `CONST_2 - CONST_1` times.
+//! * `range(x, x + CONST)` -- iterates `CONST` times for any start value `x`.
+//!
+//! Here, `CONST` stands for any constant expression that can be folded with
+//! [`crate::const_eval::eval_const_expr`].
+//!
+//! A [`BoundReport`] lists the bound of every loop in a set of functions along
+//! with the worst-case number of loop body executions of each function, for
+//! review by auditors.  [`module_bounds`] computes the report for the function
+//! definitions of a module.
+//!
+//! Only loops over `range(...)` are accepted, so there are no loops over
+//! arrays to bound.  Byte arrays and strings are declared with a maximum size
+//! and their lengths are checked against it when they are decoded and stored
+//! rather than by this analysis.
+
+use std::fmt::Write;
+
+use num_bigint::BigInt;
+use num_traits::{
+    Signed,
+    ToPrimitive,
+};
+
+use vyper_parser::ast::{
+    ConstExpr,
+    Expr,
+    FuncStmt,
+    Module,
+    ModuleStmt,
+    Operator,
+};
+use vyper_parser::source_map::{
+    FileId,
+    SourceMap,
+};
+use vyper_parser::span::{
+    Span,
+    Spanned,
+};
+
+use crate::const_eval::{
+    eval_const_expr,
+    module_constants,
+    ConstEnv,
+};
+use crate::errors::SemanticError;
+
+/// Return true if the two given expressions are structurally equal.  Unlike
+/// the `PartialEq` implementation for `Spanned` nodes, source spans are not
+/// considered.
+pub fn same_expr(left: &Spanned<ConstExpr>, right: &Spanned<ConstExpr>) -> bool {
+    match (&left.node, &right.node) {
+        (
+            ConstExpr::BinOp {
+                left: left_1,
+                op: op_1,
+                right: right_1,
+            },
+            ConstExpr::BinOp {
+                left: left_2,
+                op: op_2,
+                right: right_2,
+            },
+        ) => op_1 == op_2 && same_expr(left_1, left_2) && same_expr(right_1, right_2),
+        (
+            ConstExpr::UnaryOp {
+                op: op_1,
+                operand: operand_1,
+            },
+            ConstExpr::UnaryOp {
+                op: op_2,
+                operand: operand_2,
+            },
+        ) => op_1 == op_2 && same_expr(operand_1, operand_2),
+        (ConstExpr::Name { name: name_1 }, ConstExpr::Name { name: name_2 }) => name_1 == name_2,
+        (ConstExpr::Num { num: num_1 }, ConstExpr::Num { num: num_2 }) => num_1 == num_2,
+        (
+            ConstExpr::Call {
+                func: func_1,
+                args: args_1,
+            },
+            ConstExpr::Call {
+                func: func_2,
+                args: args_2,
+            },
+        ) => {
+            func_1 == func_2
+                && args_1.len() == args_2.len()
+                && args_1.iter().zip(args_2).all(|(a, b)| same_expr(a, b))
+        }
+        _ => false,
+    }
+}
+
+/// Convert a folded iteration count into a `u64`.
+fn to_iterations(count: BigInt, span: Span) -> Result<u64, SemanticError> {
+    if count.is_negative() {
+        return Err(SemanticError::new(
+            "range must not have a negative length",
+            span,
+        ));
+    }
+
+    count
+        .to_u64()
+        .ok_or_else(|| SemanticError::new("range is too long", span))
+}
+
+/// Compute the number of iterations of a loop over a call to `range` with the
+/// arguments in `args`.  The span of the entire call is given in `span`.
+///
+/// Any form of `range` call that cannot be shown to iterate a constant number
+/// of times is rejected.
+pub fn range_iterations(
+    span: Span,
+    args: &[Spanned<ConstExpr>],
+    env: &ConstEnv,
+) -> Result<u64, SemanticError> {
+    match args {
+        [stop] => to_iterations(eval_const_expr(stop, env)?, stop.span),
+        [start, stop] => {
+            if let ConstExpr::BinOp {
+                left,
+                op: Operator::Add,
+                right,
+            } = &stop.node
+            {
+                if same_expr(start, left) {
+                    // range(x, x + CONST)
+                    return to_iterations(eval_const_expr(right, env)?, right.span);
+                }
+            }
+
+            // range(CONST_1, CONST_2)
+            let start_val = eval_const_expr(start, env)?;
+            let stop_val = eval_const_expr(stop, env)?;
+
+            to_iterations(stop_val - start_val, stop.span)
+        }
+        _ => Err(SemanticError::new(
+            "range expects one or two arguments",
+            span,
+        )),
+    }
+}
+
+/// Compute the worst-case number of times the body of the innermost loop in a
+/// nest of loops is executed, given the iteration counts of each enclosing
+/// loop.  Returns `None` if the count does not fit into a `u64`.
+pub fn nested_iterations(counts: &[u64]) -> Option<u64> {
+    counts
+        .iter()
+        .try_fold(1u64, |total, count| total.checked_mul(*count))
+}
+
+/// A `for` loop over a call to `range` and the loops nested in its body.
+#[derive(Debug, PartialEq)]
+pub struct Loop<'a> {
+    /// The span of the entire loop
+    pub span: Span,
+    /// The span of the call to `range`
+    pub range_span: Span,
+    /// The arguments of the call to `range`
+    pub range_args: Vec<Spanned<ConstExpr<'a>>>,
+    /// The loops in the body of the loop in the order of their definition
+    pub body: Vec<Loop<'a>>,
+}
+
+/// The loops at the top level of the body of a function, as collected by
+/// [`function_loops`].
+#[derive(Debug, PartialEq)]
+pub struct FunctionLoops<'a> {
+    pub name: &'a str,
+    pub span: Span,
+    pub loops: Vec<Loop<'a>>,
+}
+
+/// The bound on the number of iterations of a single loop.
+#[derive(Debug, PartialEq, Clone)]
+pub struct LoopBound {
+    pub span: Span,
+    /// The number of enclosing loops
+    pub depth: usize,
+    /// The number of iterations of the loop each time it is entered
+    pub iterations: u64,
+    /// The number of times the body of the loop is executed in the worst case
+    /// by a single call of the function, accounting for all enclosing loops
+    pub executions: u64,
+}
+
+/// The bounds on the loops of a single function.
+#[derive(Debug, PartialEq, Clone)]
+pub struct FunctionBound {
+    pub name: String,
+    pub span: Span,
+    /// The bounds of all loops in the function in pre-order
+    pub loops: Vec<LoopBound>,
+    /// The worst-case number of loop body executions in a single call of the
+    /// function, summed over all of its loops
+    pub executions: u64,
+}
+
+/// The worst-case loop bounds of a set of functions.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct BoundReport {
+    pub functions: Vec<FunctionBound>,
+}
+
+impl BoundReport {
+    /// Render the report as text with locations given as `path:line:col` in
+    /// the file `file` of `sources`.
+    pub fn render(&self, sources: &SourceMap, file: FileId) -> String {
+        let mut result = String::new();
+
+        for function in &self.functions {
+            writeln!(
+                result,
+                "{}: function {}: at most {} loop iterations",
+                sources.location(function.span.in_file(file)),
+                function.name,
+                function.executions,
+            )
+            .unwrap();
+
+            for bound in &function.loops {
+                writeln!(
+                    result,
+                    "{}{}: loop: {} iterations, {} in the worst case",
+                    "  ".repeat(bound.depth + 1),
+                    sources.location(bound.span.in_file(file)),
+                    bound.iterations,
+                    bound.executions,
+                )
+                .unwrap();
+            }
+        }
+
+        result
+    }
+}
+
+/// Compute the bounds of the loop `lp` and the loops nested inside of it,
+/// which is entered `entries` times in the worst case.  Bounds are appended to
+/// `bounds` in pre-order.
+fn loop_bounds(
+    lp: &Loop,
+    depth: usize,
+    entries: u64,
+    env: &ConstEnv,
+    bounds: &mut Vec<LoopBound>,
+) -> Result<(), SemanticError> {
+    let iterations = range_iterations(lp.range_span, &lp.range_args, env)?;
+    let executions = nested_iterations(&[entries, iterations])
+        .ok_or_else(|| SemanticError::new("loop nest iterates too many times", lp.span))?;
+
+    bounds.push(LoopBound {
+        span: lp.span,
+        depth,
+        iterations,
+        executions,
+    });
+
+    for inner in &lp.body {
+        loop_bounds(inner, depth + 1, executions, env, bounds)?;
+    }
+
+    Ok(())
+}
+
+/// Compute the worst-case loop bounds of the functions in `functions`.
+///
+/// Fails with the error of the first loop that is not bounded, or if the
+/// worst-case number of loop body executions of a function does not fit into
+/// a `u64`.
+pub fn bound_report(
+    functions: &[FunctionLoops],
+    env: &ConstEnv,
+) -> Result<BoundReport, SemanticError> {
+    let mut report = BoundReport::default();
+
+    for function in functions {
+        let mut loops = vec![];
+        for lp in &function.loops {
+            loop_bounds(lp, 0, 1, env, &mut loops)?;
+        }
+
+        let executions = loops
+            .iter()
+            .try_fold(0u64, |total, bound| total.checked_add(bound.executions))
+            .ok_or_else(|| SemanticError::new("function iterates too many times", function.span))?;
+
+        report.functions.push(FunctionBound {
+            name: function.name.to_string(),
+            span: function.span,
+            loops,
+            executions,
+        });
+    }
+
+    Ok(report)
+}
+
+/// Convert the expression `expr` in the body of a function into a constant
+/// expression.  Names are converted as-is, so whether they refer to constants
+/// is only known once the expression is evaluated.
+pub fn const_expr<'a>(expr: &Spanned<Expr<'a>>) -> Result<Spanned<ConstExpr<'a>>, SemanticError> {
+    let node = match &expr.node {
+        Expr::BinOp { left, op, right } => ConstExpr::BinOp {
+            left: Box::new(const_expr(left)?),
+            op: *op,
+            right: Box::new(const_expr(right)?),
+        },
+        Expr::UnaryOp { op, operand } => ConstExpr::UnaryOp {
+            op: *op,
+            operand: Box::new(const_expr(operand)?),
+        },
+        Expr::Name { name } => ConstExpr::Name { name },
+        Expr::Num { num } => ConstExpr::Num { num },
+        Expr::Call { func, args } => match func.node {
+            Expr::Name { name } => ConstExpr::Call {
+                func: name,
+                args: args.iter().map(const_expr).collect::<Result<_, _>>()?,
+            },
+            _ => {
+                return Err(SemanticError::new(
+                    "expression is not a compile-time constant",
+                    expr.span,
+                ))
+            }
+        },
+        _ => {
+            return Err(SemanticError::new(
+                "expression is not a compile-time constant",
+                expr.span,
+            ))
+        }
+    };
+
+    Ok(Spanned::new(node, expr.span))
+}
+
+/// Collect the loops at the top level of the statements `stmts`, including
+/// those in the branches of conditionals, into `loops`.
+fn collect_loops<'a>(
+    stmts: &[Spanned<FuncStmt<'a>>],
+    loops: &mut Vec<Loop<'a>>,
+) -> Result<(), SemanticError> {
+    for stmt in stmts {
+        match &stmt.node {
+            FuncStmt::For { iter, body, .. } => {
+                let args = match &iter.node {
+                    Expr::Call { func, args } if func.node == (Expr::Name { name: "range" }) => {
+                        args
+                    }
+                    _ => {
+                        return Err(SemanticError::new(
+                            "loop must iterate over a call to range",
+                            iter.span,
+                        ))
+                    }
+                };
+
+                let mut inner = vec![];
+                collect_loops(body, &mut inner)?;
+                loops.push(Loop {
+                    span: stmt.span,
+                    range_span: iter.span,
+                    range_args: args.iter().map(const_expr).collect::<Result<_, _>>()?,
+                    body: inner,
+                });
+            }
+            FuncStmt::If { body, or_else, .. } => {
+                collect_loops(body, loops)?;
+                collect_loops(or_else, loops)?;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Collect the loops of each function definition in `module` in order of
+/// definition.
+pub fn function_loops<'a>(module: &Module<'a>) -> Result<Vec<FunctionLoops<'a>>, SemanticError> {
+    let mut functions = vec![];
+
+    for stmt in &module.body {
+        if let ModuleStmt::FuncDef { name, body, .. } = &stmt.node {
+            let mut loops = vec![];
+            collect_loops(body, &mut loops)?;
+            functions.push(FunctionLoops {
+                name,
+                span: stmt.span,
+                loops,
+            });
+        }
+    }
+
+    Ok(functions)
+}
+
+/// Compute the worst-case loop bounds of the function definitions in
+/// `module`, folding constant expressions with the constants it defines.
+pub fn module_bounds(module: &Module) -> Result<BoundReport, SemanticError> {
+    bound_report(&function_loops(module)?, &module_constants(module)?)
+}
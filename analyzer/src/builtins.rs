@@ -0,0 +1,639 @@
+//! The catalogue of built-in functions.
+//!
+//! Every built-in function is described by an entry in `BUILTINS` giving its
+//! positional parameters, keyword parameters, return type and the contexts in
+//! which it may be called.  [`check_call`] checks the arguments of a call
+//! against the catalogue and computes the type of its result, so that type
+//! checking and code generation agree on how each built-in is used.
+
+use num_bigint::BigInt;
+use num_traits::{
+    One,
+    Signed,
+    ToPrimitive,
+    Zero,
+};
+
+use vyper_parser::span::Span;
+
+use crate::errors::SemanticError;
+use crate::types::{
+    Base,
+    Type,
+};
+
+/// The denominations accepted by `as_wei_value`.
+pub const DENOMINATIONS: &[&str] = &[
+    "wei",
+    "kwei",
+    "babbage",
+    "mwei",
+    "lovelace",
+    "gwei",
+    "shannon",
+    "microether",
+    "szabo",
+    "milliether",
+    "finney",
+    "ether",
+];
+
+/// The kinds of arguments accepted by a parameter of a built-in function.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ParamType {
+    /// A value of exactly the given base type.
+    Base(Base),
+    /// A value of any integer type.
+    Integer,
+    /// A value of any integer or decimal type.
+    Numeric,
+    /// A byte array of any size.
+    Bytes,
+    /// A byte array or string of any size.
+    BytesOrString,
+    /// A `bytes32` value, a byte array or a string.
+    ByteSequence,
+    /// A value of any type.
+    Any,
+    /// A type name such as `uint256` in `convert(x, uint256)`.
+    TypeName,
+    /// An integer literal such as the `len` of a slice, which determines the
+    /// size of the result.
+    IntLiteral,
+    /// A string literal naming a denomination of ether such as `"gwei"`.
+    Denomination,
+}
+
+/// A positional or keyword parameter of a built-in function.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Param {
+    pub name: &'static str,
+    pub typ: ParamType,
+    /// Whether a keyword parameter must be given.  Positional parameters are
+    /// always required.
+    pub required: bool,
+}
+
+/// The type of the result of a built-in function.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ReturnType {
+    /// The function does not return a value.
+    None,
+    Base(Base),
+    /// The common type of all positional arguments, as with `min`.
+    Common,
+    /// The type named by the positional argument with the given index.
+    TypeArg(usize),
+    /// A byte array or string as large as all arguments together.
+    Concat,
+    /// A byte array whose size is the integer literal given for the named
+    /// parameter.
+    SizedBy(&'static str),
+}
+
+/// Where a built-in function may be called.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Constness {
+    /// May be folded in constant expressions.
+    Constant,
+    /// Depends on the environment and may only be called at runtime.
+    Runtime,
+    /// Modifies state and may not be called from view functions.
+    Mutating,
+}
+
+/// The description of a built-in function.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Builtin {
+    pub name: &'static str,
+    pub params: &'static [Param],
+    /// Whether the last positional parameter accepts any number of further
+    /// arguments.
+    pub variadic: bool,
+    pub kwargs: &'static [Param],
+    pub returns: ReturnType,
+    pub constness: Constness,
+}
+
+/// The context of a call to a built-in function.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum CallContext {
+    /// A constant expression such as the value of a constant.
+    Constant,
+    /// The body of a function that may not modify state.
+    View,
+    /// The body of a function that may modify state.
+    Mutable,
+}
+
+/// An argument of a call to a built-in function.
+#[derive(Debug, PartialEq, Clone)]
+pub enum ArgValue {
+    /// An expression of the given type.
+    Expr(Type),
+    /// An integer literal, which may take any integer type it fits into.
+    Int(BigInt),
+    /// A string literal, which may take a byte array or string type.
+    Str(String),
+    /// A type name.
+    TypeName(Type),
+}
+
+/// An argument of a call with the span of its source text.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Arg {
+    pub value: ArgValue,
+    pub span: Span,
+}
+
+/// A keyword argument of a call.
+#[derive(Debug, PartialEq, Clone)]
+pub struct KeywordArg<'a> {
+    pub name: &'a str,
+    /// The span of the keyword.
+    pub name_span: Span,
+    pub arg: Arg,
+}
+
+const fn param(name: &'static str, typ: ParamType) -> Param {
+    Param {
+        name,
+        typ,
+        required: true,
+    }
+}
+
+const fn optional(name: &'static str, typ: ParamType) -> Param {
+    Param {
+        name,
+        typ,
+        required: false,
+    }
+}
+
+const ADDRESS: ParamType = ParamType::Base(Base::Address);
+const BOOL: ParamType = ParamType::Base(Base::Bool);
+const BYTES32: ParamType = ParamType::Base(Base::Bytes32);
+const DECIMAL: ParamType = ParamType::Base(Base::Decimal);
+const INT128: ParamType = ParamType::Base(Base::Int128);
+const UINT256: ParamType = ParamType::Base(Base::Uint256);
+
+/// Declare a built-in function.  Keyword parameters follow a `;` and the
+/// last positional parameter is variadic if the parameters are followed by
+/// `variadic`.
+macro_rules! builtin {
+    (
+        $name:literal($($param:expr),* $(; $($kwarg:expr),*)?) $($variadic:ident)?
+        -> $returns:expr, $constness:ident
+    ) => {
+        Builtin {
+            name: $name,
+            params: &[$($param),*],
+            variadic: builtin!(@variadic $($variadic)?),
+            kwargs: &[$($($kwarg),*)?],
+            returns: $returns,
+            constness: Constness::$constness,
+        }
+    };
+    (@variadic) => { false };
+    (@variadic $t:tt) => { true };
+}
+
+/// All built-in functions, sorted by name.
+pub const BUILTINS: &[Builtin] = &[
+    builtin!("as_wei_value"(
+        param("value", ParamType::Numeric),
+        param("unit", ParamType::Denomination)
+    ) -> ReturnType::Base(Base::Uint256), Constant),
+    builtin!("assert_modifiable"(param("cond", BOOL)) -> ReturnType::None, Runtime),
+    builtin!("bitwise_and"(param("x", UINT256), param("y", UINT256))
+        -> ReturnType::Base(Base::Uint256), Constant),
+    builtin!("bitwise_not"(param("x", UINT256)) -> ReturnType::Base(Base::Uint256), Constant),
+    builtin!("bitwise_or"(param("x", UINT256), param("y", UINT256))
+        -> ReturnType::Base(Base::Uint256), Constant),
+    builtin!("bitwise_xor"(param("x", UINT256), param("y", UINT256))
+        -> ReturnType::Base(Base::Uint256), Constant),
+    builtin!("blockhash"(param("block_num", UINT256))
+        -> ReturnType::Base(Base::Bytes32), Runtime),
+    builtin!("ceil"(param("value", DECIMAL)) -> ReturnType::Base(Base::Int128), Constant),
+    builtin!("concat"(
+        param("a", ParamType::ByteSequence),
+        param("b", ParamType::ByteSequence)
+    ) variadic -> ReturnType::Concat, Runtime),
+    builtin!("convert"(param("value", ParamType::Any), param("type", ParamType::TypeName))
+        -> ReturnType::TypeArg(1), Constant),
+    builtin!("create_forwarder_to"(param("target", ADDRESS); optional("value", UINT256))
+        -> ReturnType::Base(Base::Address), Mutating),
+    builtin!("ecrecover"(
+        param("hash", BYTES32),
+        param("v", UINT256),
+        param("r", UINT256),
+        param("s", UINT256)
+    ) -> ReturnType::Base(Base::Address), Runtime),
+    builtin!("empty"(param("type", ParamType::TypeName)) -> ReturnType::TypeArg(0), Constant),
+    builtin!("extract32"(param("b", ParamType::Bytes), param("start", ParamType::Integer))
+        -> ReturnType::Base(Base::Bytes32), Runtime),
+    builtin!("floor"(param("value", DECIMAL)) -> ReturnType::Base(Base::Int128), Constant),
+    builtin!("keccak256"(param("value", ParamType::ByteSequence))
+        -> ReturnType::Base(Base::Bytes32), Constant),
+    builtin!("len"(param("b", ParamType::BytesOrString))
+        -> ReturnType::Base(Base::Int128), Runtime),
+    builtin!("max"(param("a", ParamType::Numeric), param("b", ParamType::Numeric))
+        -> ReturnType::Common, Constant),
+    builtin!("min"(param("a", ParamType::Numeric), param("b", ParamType::Numeric))
+        -> ReturnType::Common, Constant),
+    builtin!("raw_call"(
+        param("to", ADDRESS),
+        param("data", ParamType::Bytes);
+        param("max_outsize", ParamType::IntLiteral),
+        optional("gas", UINT256),
+        optional("value", UINT256),
+        optional("delegate_call", BOOL)
+    ) -> ReturnType::SizedBy("max_outsize"), Mutating),
+    builtin!("selfdestruct"(param("to", ADDRESS)) -> ReturnType::None, Mutating),
+    builtin!("send"(param("to", ADDRESS), param("value", UINT256)) -> ReturnType::None, Mutating),
+    builtin!("sha256"(param("value", ParamType::ByteSequence))
+        -> ReturnType::Base(Base::Bytes32), Runtime),
+    builtin!("shift"(param("x", UINT256), param("bits", INT128))
+        -> ReturnType::Base(Base::Uint256), Constant),
+    builtin!("slice"(
+        param("b", ParamType::Bytes);
+        param("start", ParamType::Integer),
+        param("len", ParamType::IntLiteral)
+    ) -> ReturnType::SizedBy("len"), Runtime),
+    builtin!("uint256_addmod"(param("a", UINT256), param("b", UINT256), param("c", UINT256))
+        -> ReturnType::Base(Base::Uint256), Constant),
+    builtin!("uint256_mulmod"(param("a", UINT256), param("b", UINT256), param("c", UINT256))
+        -> ReturnType::Base(Base::Uint256), Constant),
+];
+
+/// Return the built-in function named `name`.
+pub fn builtin(name: &str) -> Option<&'static Builtin> {
+    BUILTINS
+        .binary_search_by_key(&name, |b| b.name)
+        .ok()
+        .map(|i| &BUILTINS[i])
+}
+
+impl ParamType {
+    /// Return a description of the arguments accepted by a parameter.
+    pub fn description(self) -> &'static str {
+        match self {
+            ParamType::Base(base) => base.name(),
+            ParamType::Integer => "integer",
+            ParamType::Numeric => "numeric",
+            ParamType::Bytes => "bytes",
+            ParamType::BytesOrString => "bytes or string",
+            ParamType::ByteSequence => "bytes32, bytes or string",
+            ParamType::Any => "any",
+            ParamType::TypeName => "type",
+            ParamType::IntLiteral => "integer literal",
+            ParamType::Denomination => "denomination",
+        }
+    }
+}
+
+impl Builtin {
+    /// Return the signature of a built-in function for documentation, e.g.
+    /// `slice(b: bytes, *, start: integer, len: integer literal) ->
+    /// bytes[len]`.
+    pub fn signature(&self) -> String {
+        let describe = |p: &Param| format!("{}: {}", p.name, p.typ.description());
+
+        let mut params: Vec<_> = self.params.iter().map(describe).collect();
+        if self.variadic {
+            params.push("...".to_string());
+        }
+        if !self.kwargs.is_empty() {
+            params.push("*".to_string());
+        }
+        for kwarg in self.kwargs {
+            let mut param = describe(kwarg);
+            if !kwarg.required {
+                param += " = ...";
+            }
+            params.push(param);
+        }
+
+        let returns = match self.returns {
+            ReturnType::None => return format!("{}({})", self.name, params.join(", ")),
+            ReturnType::Base(base) => base.name().to_string(),
+            ReturnType::Common => self.params[0].typ.description().to_string(),
+            ReturnType::TypeArg(i) => self.params[i].name.to_string(),
+            ReturnType::Concat => "bytes".to_string(),
+            ReturnType::SizedBy(name) => format!("bytes[{}]", name),
+        };
+
+        format!("{}({}) -> {}", self.name, params.join(", "), returns)
+    }
+}
+
+/// Return the bounds of the values of an integer type.
+fn int_bounds(base: Base) -> Option<(BigInt, BigInt)> {
+    match base {
+        Base::Int128 => Some((-(BigInt::one() << 127), (BigInt::one() << 127) - 1)),
+        Base::Uint256 => Some((BigInt::zero(), (BigInt::one() << 256) - 1)),
+        _ => None,
+    }
+}
+
+/// Return true if the integer literal `value` may be given where a value of
+/// type `base` is expected.
+fn int_fits(value: &BigInt, base: Base) -> bool {
+    match base {
+        Base::Decimal => int_fits(value, Base::Int128),
+        _ => int_bounds(base).is_some_and(|(min, max)| *value >= min && *value <= max),
+    }
+}
+
+fn is_numeric(base: Base) -> bool {
+    matches!(base, Base::Decimal | Base::Int128 | Base::Uint256)
+}
+
+/// Return the type of a literal argument where it is not determined by a
+/// parameter.
+fn literal_type(value: &ArgValue) -> Option<Type> {
+    match value {
+        ArgValue::Expr(typ) => Some(typ.clone()),
+        ArgValue::Int(value) if value.is_negative() => Some(Type::Base(Base::Int128)),
+        ArgValue::Int(_) => Some(Type::Base(Base::Uint256)),
+        ArgValue::Str(s) => Some(Type::Bytes {
+            max_size: s.len() as u32,
+        }),
+        ArgValue::TypeName(_) => None,
+    }
+}
+
+/// Check that the argument `arg` is accepted by a parameter of type `typ`.
+fn check_arg(arg: &Arg, typ: ParamType) -> Result<(), SemanticError> {
+    let wrong_type = || SemanticError::new("argument has the wrong type", arg.span);
+
+    let accepted = match (&arg.value, typ) {
+        (ArgValue::TypeName(_), ParamType::TypeName) => true,
+        (ArgValue::TypeName(_), _) => {
+            return Err(SemanticError::new(
+                "expected a value, found a type name",
+                arg.span,
+            ))
+        }
+        (_, ParamType::TypeName) => {
+            return Err(SemanticError::new("expected a type name", arg.span))
+        }
+        (ArgValue::Int(value), ParamType::IntLiteral) => {
+            if value.is_negative() || value.to_u32().is_none() {
+                return Err(SemanticError::new(
+                    "integer literal is out of bounds for the argument",
+                    arg.span,
+                ));
+            }
+            true
+        }
+        (_, ParamType::IntLiteral) => {
+            return Err(SemanticError::new("expected an integer literal", arg.span))
+        }
+        (ArgValue::Str(s), ParamType::Denomination) => {
+            if !DENOMINATIONS.contains(&s.as_str()) {
+                return Err(SemanticError::new("unknown denomination", arg.span));
+            }
+            true
+        }
+        (_, ParamType::Denomination) => {
+            return Err(SemanticError::new(
+                "expected a denomination such as \"ether\"",
+                arg.span,
+            ))
+        }
+        (_, ParamType::Any) => true,
+        (ArgValue::Int(value), ParamType::Base(base)) if int_bounds(base).is_some() => {
+            if !int_fits(value, base) {
+                return Err(SemanticError::new(
+                    "integer literal is out of bounds for the argument",
+                    arg.span,
+                ));
+            }
+            true
+        }
+        (ArgValue::Int(value), ParamType::Integer) | (ArgValue::Int(value), ParamType::Numeric) => {
+            if !int_fits(value, Base::Int128) && !int_fits(value, Base::Uint256) {
+                return Err(SemanticError::new(
+                    "integer literal is out of bounds for the argument",
+                    arg.span,
+                ));
+            }
+            true
+        }
+        (ArgValue::Int(_), _) => false,
+        (ArgValue::Str(_), ParamType::Bytes)
+        | (ArgValue::Str(_), ParamType::BytesOrString)
+        | (ArgValue::Str(_), ParamType::ByteSequence) => true,
+        (ArgValue::Str(_), _) => false,
+        (ArgValue::Expr(actual), typ) => match (actual, typ) {
+            (Type::Base(actual), ParamType::Base(expected)) => *actual == expected,
+            (Type::Base(base), ParamType::Integer) => matches!(base, Base::Int128 | Base::Uint256),
+            (Type::Base(base), ParamType::Numeric) => is_numeric(*base),
+            (Type::Base(Base::Bytes32), ParamType::ByteSequence) => true,
+            (Type::Bytes { .. }, ParamType::Bytes)
+            | (Type::Bytes { .. }, ParamType::BytesOrString)
+            | (Type::Bytes { .. }, ParamType::ByteSequence)
+            | (Type::String { .. }, ParamType::BytesOrString)
+            | (Type::String { .. }, ParamType::ByteSequence) => true,
+            _ => false,
+        },
+    };
+
+    if accepted {
+        Ok(())
+    } else {
+        Err(wrong_type())
+    }
+}
+
+/// Return the common type of the arguments of `min` or `max`.  Integer
+/// literals take the type of the other arguments.
+fn common_type(args: &[Arg]) -> Result<Type, SemanticError> {
+    let mut common: Option<(Type, Span)> = None;
+    for arg in args {
+        if let ArgValue::Expr(typ) = &arg.value {
+            match &common {
+                Some((common, _)) if common != typ => {
+                    return Err(SemanticError::new(
+                        "arguments must have the same type",
+                        arg.span,
+                    ))
+                }
+                _ => common = Some((typ.clone(), arg.span)),
+            }
+        }
+    }
+
+    let common = match common {
+        Some((typ, _)) => typ,
+        // Only literals were given
+        None => args
+            .iter()
+            .filter_map(|arg| literal_type(&arg.value))
+            .find(|typ| *typ == Type::Base(Base::Int128))
+            .unwrap_or(Type::Base(Base::Uint256)),
+    };
+
+    if let Type::Base(base) = common {
+        for arg in args {
+            if let ArgValue::Int(value) = &arg.value {
+                if !int_fits(value, base) {
+                    return Err(SemanticError::new(
+                        "integer literal is out of bounds for the argument",
+                        arg.span,
+                    ));
+                }
+            }
+        }
+    }
+
+    Ok(common)
+}
+
+/// Return the type of the result of `concat`.
+fn concat_type(args: &[Arg]) -> Result<Type, SemanticError> {
+    let mut max_size = 0u32;
+    let mut is_string = None;
+
+    for arg in args {
+        let (size, string) = match &arg.value {
+            ArgValue::Expr(Type::Bytes { max_size }) => (*max_size, false),
+            ArgValue::Expr(Type::String { max_size }) => (*max_size, true),
+            ArgValue::Str(s) => (s.len() as u32, is_string.unwrap_or(false)),
+            _ => (32, false),
+        };
+
+        match is_string {
+            Some(prev) if prev != string => {
+                return Err(SemanticError::new(
+                    "cannot concatenate strings and byte arrays",
+                    arg.span,
+                ))
+            }
+            _ => is_string = Some(string),
+        }
+        max_size = max_size.saturating_add(size);
+    }
+
+    Ok(if is_string == Some(true) {
+        Type::String { max_size }
+    } else {
+        Type::Bytes { max_size }
+    })
+}
+
+/// Check a call to the built-in function `name` with the positional arguments
+/// `args` and keyword arguments `kwargs` in the context `context`.  The span
+/// of the entire call is given in `span`.  Returns the type of the result of
+/// the call or `None` if it does not return a value.
+pub fn check_call(
+    span: Span,
+    name: &str,
+    args: &[Arg],
+    kwargs: &[KeywordArg],
+    context: CallContext,
+) -> Result<Option<Type>, SemanticError> {
+    let builtin = match builtin(name) {
+        Some(builtin) => builtin,
+        None => return Err(SemanticError::new("unknown built-in function", span)),
+    };
+
+    match (context, builtin.constness) {
+        (CallContext::Constant, Constness::Runtime)
+        | (CallContext::Constant, Constness::Mutating) => {
+            return Err(SemanticError::new(
+                "built-in function cannot be used in a constant expression",
+                span,
+            ))
+        }
+        (CallContext::View, Constness::Mutating) => {
+            return Err(SemanticError::new(
+                "built-in function modifies state and cannot be called from a view function",
+                span,
+            ))
+        }
+        _ => {}
+    }
+
+    if args.len() < builtin.params.len() {
+        return Err(SemanticError::new(
+            "too few arguments to built-in function",
+            span,
+        ));
+    }
+    if args.len() > builtin.params.len() && !builtin.variadic {
+        return Err(SemanticError::new(
+            "too many arguments to built-in function",
+            args[builtin.params.len()].span,
+        ));
+    }
+
+    let last = builtin.params.last().map(|p| p.typ);
+    for (i, arg) in args.iter().enumerate() {
+        let typ = builtin.params.get(i).map(|p| p.typ).or(last);
+        check_arg(arg, typ.expect("variadic built-ins have parameters"))?;
+    }
+
+    for (i, kwarg) in kwargs.iter().enumerate() {
+        let param = match builtin.kwargs.iter().find(|p| p.name == kwarg.name) {
+            Some(param) => param,
+            None => {
+                return Err(SemanticError::new(
+                    "unexpected keyword argument",
+                    kwarg.name_span,
+                ))
+            }
+        };
+        if kwargs[..i].iter().any(|prev| prev.name == kwarg.name) {
+            return Err(SemanticError::new(
+                "duplicate keyword argument",
+                kwarg.name_span,
+            ));
+        }
+
+        check_arg(&kwarg.arg, param.typ)?;
+    }
+
+    for param in builtin.kwargs.iter().filter(|p| p.required) {
+        if !kwargs.iter().any(|kwarg| kwarg.name == param.name) {
+            return Err(SemanticError::new(
+                "missing required keyword argument",
+                span,
+            ));
+        }
+    }
+
+    let typ = match builtin.returns {
+        ReturnType::None => return Ok(None),
+        ReturnType::Base(base) => Type::Base(base),
+        ReturnType::Common => common_type(args)?,
+        ReturnType::TypeArg(i) => match &args[i].value {
+            ArgValue::TypeName(typ) => typ.clone(),
+            _ => unreachable!("type arguments are checked above"),
+        },
+        ReturnType::Concat => concat_type(args)?,
+        ReturnType::SizedBy(name) => {
+            let position = builtin.params.iter().position(|p| p.name == name);
+            let arg = match position {
+                Some(i) => &args[i],
+                None => {
+                    &kwargs
+                        .iter()
+                        .find(|kwarg| kwarg.name == name)
+                        .expect("sizes are required keyword arguments")
+                        .arg
+                }
+            };
+            let max_size = match &arg.value {
+                ArgValue::Int(value) => value.to_u32().expect("sizes are checked above"),
+                _ => unreachable!("sizes are checked above"),
+            };
+
+            Type::Bytes { max_size }
+        }
+    };
+
+    Ok(Some(typ))
+}
@@ -0,0 +1,353 @@
+use std::collections::HashMap;
+
+use num_bigint::BigInt;
+use num_traits::{
+    Num,
+    One,
+    Signed,
+    ToPrimitive,
+    Zero,
+};
+
+use vyper_parser::ast::{
+    ConstExpr,
+    Module,
+    ModuleStmt,
+    Operator,
+    TypeDesc,
+    UnaryOp,
+};
+use vyper_parser::span::Spanned;
+
+use crate::builtins::{
+    check_call,
+    Arg,
+    ArgValue,
+    CallContext,
+};
+use crate::errors::SemanticError;
+use crate::types::{
+    Base,
+    Type,
+};
+
+/// The largest number of bits occupied by the value of any Vyper integer type.
+const MAX_BITS: usize = 256;
+
+/// The largest number of bits that may be occupied by an intermediate value
+/// during evaluation.  This permits expressions such as `2 ** 256 - 1` while
+/// keeping the cost of evaluating large powers and shifts bounded.
+const MAX_INTERMEDIATE_BITS: usize = 2 * MAX_BITS;
+
+/// Values of the named constants that may be referenced by a constant
+/// expression.
+pub type ConstEnv<'a> = HashMap<&'a str, BigInt>;
+
+/// Parse the string content of an integer number token.  Hex, octal, and
+/// binary prefixes are recognized and digit separators are ignored.  Returns
+/// `None` if the string does not contain an integer literal.
+pub fn parse_int_literal(num: &str) -> Option<BigInt> {
+    let digits = num.replace('_', "");
+
+    let (radix, digits) = match digits.get(..2) {
+        Some("0x") | Some("0X") => (16, &digits[2..]),
+        Some("0o") | Some("0O") => (8, &digits[2..]),
+        Some("0b") | Some("0B") => (2, &digits[2..]),
+        _ => (10, &digits[..]),
+    };
+
+    if digits.is_empty() || digits.starts_with('+') || digits.starts_with('-') {
+        return None;
+    }
+
+    BigInt::from_str_radix(digits, radix).ok()
+}
+
+/// Return true if `value` fits into at least one of the Vyper integer types.
+fn in_bounds(value: &BigInt) -> bool {
+    let limit = BigInt::one() << MAX_BITS;
+    let min = -(BigInt::one() << (MAX_BITS - 1));
+
+    *value >= min && *value < limit
+}
+
+/// Return true if `value` may appear as an intermediate result of evaluation.
+fn in_intermediate_bounds(value: &BigInt) -> bool {
+    value.bits() <= MAX_INTERMEDIATE_BITS
+}
+
+/// Convert a shift amount or exponent into a `usize` no greater than
+/// `MAX_BITS`.  Larger values are clamped to `MAX_BITS + 1` since they always
+/// produce an out of bounds result for operands other than 0, 1, or -1.
+fn to_small_usize(value: &BigInt) -> usize {
+    match value.to_usize() {
+        Some(v) if v <= MAX_BITS => v,
+        _ => MAX_BITS + 1,
+    }
+}
+
+/// Evaluate a constant expression to an integer value.  Names are looked up in
+/// `env`.
+///
+/// Evaluation fails if an expression references an unknown name, contains a
+/// non-integer literal, divides by zero, calls a built-in function that cannot
+/// be folded into an integer, or produces a value that does not fit into any
+/// Vyper integer type.
+pub fn eval_const_expr(expr: &Spanned<ConstExpr>, env: &ConstEnv) -> Result<BigInt, SemanticError> {
+    let value = fold(expr, env)?;
+
+    if in_bounds(&value) {
+        Ok(value)
+    } else {
+        Err(SemanticError::new(
+            "constant expression value is out of bounds",
+            expr.span,
+        ))
+    }
+}
+
+/// Return true if a field with the type description `typ` is a constant,
+/// i.e. if it is annotated as `constant`.
+pub fn is_constant(typ: &TypeDesc) -> bool {
+    typ.annotations.contains(&"constant")
+}
+
+/// Evaluate the constants defined in the module `module` in order of
+/// definition.  Each constant may reference the constants defined before it.
+///
+/// Fails if a constant has no value or a value that cannot be evaluated, if a
+/// constant is defined twice, or if a field that is not a constant is
+/// assigned a value.
+pub fn module_constants<'a>(module: &Module<'a>) -> Result<ConstEnv<'a>, SemanticError> {
+    let mut env = ConstEnv::new();
+
+    for stmt in &module.body {
+        if let ModuleStmt::FieldDef { name, typ, value } = &stmt.node {
+            match (is_constant(&typ.node), value) {
+                (true, Some(value)) => {
+                    let value = eval_const_expr(value, &env)?;
+
+                    if env.insert(name, value).is_some() {
+                        return Err(SemanticError::new(
+                            "duplicate constant definition",
+                            stmt.span,
+                        ));
+                    }
+                }
+                (true, None) => {
+                    return Err(SemanticError::new("constants require a value", stmt.span))
+                }
+                (false, Some(value)) => {
+                    return Err(SemanticError::new(
+                        "only constants may be assigned a value",
+                        value.span,
+                    ))
+                }
+                (false, None) => {}
+            }
+        }
+    }
+
+    Ok(env)
+}
+
+/// Fold a constant expression into an intermediate value.
+fn fold(expr: &Spanned<ConstExpr>, env: &ConstEnv) -> Result<BigInt, SemanticError> {
+    let value = match &expr.node {
+        ConstExpr::Name { name } => match env.get(name) {
+            Some(value) => value.clone(),
+            None => {
+                return Err(SemanticError::new(
+                    "name is not a compile-time constant",
+                    expr.span,
+                ))
+            }
+        },
+        ConstExpr::Num { num } => match parse_int_literal(num) {
+            Some(value) => value,
+            None => return Err(SemanticError::new("expected an integer literal", expr.span)),
+        },
+        ConstExpr::UnaryOp { op, operand } => {
+            let operand = fold(operand, env)?;
+
+            match op {
+                UnaryOp::UAdd => operand,
+                UnaryOp::USub => -operand,
+                UnaryOp::Invert => -operand - 1,
+                UnaryOp::Not => {
+                    return Err(SemanticError::new(
+                        "boolean operator in integer constant expression",
+                        expr.span,
+                    ))
+                }
+            }
+        }
+        ConstExpr::BinOp { left, op, right } => {
+            let left_val = fold(left, env)?;
+            let right_val = fold(right, env)?;
+
+            eval_bin_op(left_val, op, right_val, expr)?
+        }
+        ConstExpr::Call { func, args } => fold_call(func, args, env, expr)?,
+    };
+
+    if in_intermediate_bounds(&value) {
+        Ok(value)
+    } else {
+        Err(SemanticError::new(
+            "constant expression value is out of bounds",
+            expr.span,
+        ))
+    }
+}
+
+/// Fold a call of the expression `expr` to the built-in function `func` with
+/// the arguments `args`.  The call is checked as a call in a constant
+/// expression.  Names of base types such as `uint256` are passed as type names
+/// unless they are the names of constants.
+fn fold_call(
+    func: &str,
+    args: &[Spanned<ConstExpr>],
+    env: &ConstEnv,
+    expr: &Spanned<ConstExpr>,
+) -> Result<BigInt, SemanticError> {
+    let args = args
+        .iter()
+        .map(|arg| {
+            let value = match &arg.node {
+                ConstExpr::Name { name } if !env.contains_key(name) => {
+                    match Base::from_name(name) {
+                        Some(base) => ArgValue::TypeName(Type::Base(base)),
+                        None => ArgValue::Int(fold(arg, env)?),
+                    }
+                }
+                _ => ArgValue::Int(fold(arg, env)?),
+            };
+
+            Ok(Arg {
+                value,
+                span: arg.span,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let typ = check_call(expr.span, func, &args, &[], CallContext::Constant)?;
+    let not_foldable = || {
+        SemanticError::new(
+            "built-in function cannot be folded into an integer",
+            expr.span,
+        )
+    };
+    // The inclusive lower and exclusive upper bound of the result
+    let (min, limit) = match typ {
+        Some(Type::Base(Base::Int128)) => (-(BigInt::one() << 127), BigInt::one() << 127),
+        Some(Type::Base(Base::Uint256)) => (BigInt::zero(), BigInt::one() << MAX_BITS),
+        _ => return Err(not_foldable()),
+    };
+
+    let ints: Vec<&BigInt> = args
+        .iter()
+        .filter_map(|arg| match &arg.value {
+            ArgValue::Int(value) => Some(value),
+            _ => None,
+        })
+        .collect();
+
+    let value = match (func, &ints[..]) {
+        ("bitwise_and", [x, y]) => *x & *y,
+        ("bitwise_or", [x, y]) => *x | *y,
+        ("bitwise_xor", [x, y]) => *x ^ *y,
+        ("bitwise_not", [x]) => &limit - 1 - *x,
+        // Shifts of unsigned words discard the bits shifted out, as with the
+        // EVM's `SHL` and `SHR` opcodes
+        ("shift", [x, bits]) => match bits.to_isize() {
+            Some(bits) if bits >= 0 && bits < MAX_BITS as isize => (*x << bits as usize) % &limit,
+            Some(bits) if bits < 0 && bits > -(MAX_BITS as isize) => *x >> (-bits) as usize,
+            _ => BigInt::zero(),
+        },
+        ("min", [a, b]) => (*a).min(*b).clone(),
+        ("max", [a, b]) => (*a).max(*b).clone(),
+        ("uint256_addmod", [_, _, c]) | ("uint256_mulmod", [_, _, c]) if c.is_zero() => {
+            return Err(SemanticError::new("division by zero", expr.span))
+        }
+        ("uint256_addmod", [a, b, c]) => (*a + *b) % *c,
+        ("uint256_mulmod", [a, b, c]) => (*a * *b) % *c,
+        ("convert", [value]) => (*value).clone(),
+        ("empty", []) => BigInt::zero(),
+        _ => return Err(not_foldable()),
+    };
+
+    if value >= min && value < limit {
+        Ok(value)
+    } else {
+        Err(SemanticError::new(
+            "constant expression value is out of bounds",
+            expr.span,
+        ))
+    }
+}
+
+/// Apply a binary operator to two evaluated operands of the expression `expr`.
+fn eval_bin_op(
+    left: BigInt,
+    op: &Operator,
+    right: BigInt,
+    expr: &Spanned<ConstExpr>,
+) -> Result<BigInt, SemanticError> {
+    let out_of_bounds =
+        || SemanticError::new("constant expression value is out of bounds", expr.span);
+
+    match op {
+        Operator::Add => Ok(left + right),
+        Operator::Sub => Ok(left - right),
+        Operator::Mult => Ok(left * right),
+        Operator::Div | Operator::Mod if right.is_zero() => {
+            Err(SemanticError::new("division by zero", expr.span))
+        }
+        // Integer division and modulus both truncate towards zero as with the
+        // EVM's signed arithmetic opcodes
+        Operator::Div => Ok(left / right),
+        Operator::Mod => Ok(left % right),
+        Operator::Pow => {
+            if right.is_negative() {
+                return Err(SemanticError::new("negative exponent", expr.span));
+            }
+            if right.is_zero() {
+                return Ok(BigInt::one());
+            }
+            if left.abs() <= BigInt::one() {
+                // 0, 1, or -1 raised to any positive power
+                let is_odd = (&right % 2u8).is_one();
+
+                return Ok(if left.is_negative() && !is_odd {
+                    BigInt::one()
+                } else {
+                    left
+                });
+            }
+
+            match to_small_usize(&right) {
+                exp if exp <= MAX_BITS => Ok(num_traits::pow(left, exp)),
+                _ => Err(out_of_bounds()),
+            }
+        }
+        Operator::LShift | Operator::RShift if right.is_negative() => {
+            Err(SemanticError::new("negative shift amount", expr.span))
+        }
+        Operator::LShift => match to_small_usize(&right) {
+            _ if left.is_zero() => Ok(left),
+            shift if shift <= MAX_BITS => Ok(left << shift),
+            _ => Err(out_of_bounds()),
+        },
+        // Shifting right by at least the width of the operand leaves only its
+        // sign, as with the EVM's `SAR` opcode
+        Operator::RShift => match right.to_usize() {
+            Some(shift) if shift < left.bits() => Ok(left >> shift),
+            _ if left.is_negative() => Ok(-BigInt::one()),
+            _ => Ok(BigInt::zero()),
+        },
+        Operator::BitOr => Ok(left | right),
+        Operator::BitXor => Ok(left ^ right),
+        Operator::BitAnd => Ok(left & right),
+    }
+}
@@ -0,0 +1,16 @@
+use vyper_parser::span::Span;
+
+/// An error found during semantic analysis of a parsed module.
+#[derive(Debug, PartialEq)]
+pub struct SemanticError {
+    /// A description of the error.
+    pub msg: &'static str,
+    /// The span of source text at which the error was found.
+    pub span: Span,
+}
+
+impl SemanticError {
+    pub fn new(msg: &'static str, span: Span) -> Self {
+        SemanticError { msg, span }
+    }
+}
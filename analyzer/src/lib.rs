@@ -0,0 +1,5 @@
+pub mod bounds;
+pub mod builtins;
+pub mod const_eval;
+pub mod errors;
+pub mod types;
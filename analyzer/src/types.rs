@@ -0,0 +1,218 @@
+use num_traits::{
+    Signed,
+    ToPrimitive,
+};
+
+use vyper_parser::ast::{
+    ConstExpr,
+    TypeDesc,
+};
+use vyper_parser::span::Spanned;
+
+use crate::const_eval::{
+    eval_const_expr,
+    ConstEnv,
+};
+use crate::errors::SemanticError;
+
+/// A Vyper base type whose values occupy a single 32 byte word.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Base {
+    Address,
+    Bool,
+    Bytes32,
+    Decimal,
+    Int128,
+    Uint256,
+}
+
+/// A resolved Vyper type.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Type {
+    Base(Base),
+    /// A fixed-size list of values such as `uint256[3]`.
+    Array {
+        inner: Box<Type>,
+        dimension: u32,
+    },
+    /// A byte array with a maximum size such as `bytes[100]`.
+    Bytes {
+        max_size: u32,
+    },
+    /// A string with a maximum size such as `string[100]`.
+    String {
+        max_size: u32,
+    },
+    /// An ordered group of values of possibly different types.
+    Tuple(Vec<Type>),
+    /// A struct with named fields.  Structs are ABI encoded as tuples.
+    Struct {
+        name: String,
+        fields: Vec<(String, Type)>,
+    },
+}
+
+impl Base {
+    /// Resolve the name of a base type.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "address" => Some(Base::Address),
+            "bool" => Some(Base::Bool),
+            "bytes32" => Some(Base::Bytes32),
+            "decimal" => Some(Base::Decimal),
+            "int128" => Some(Base::Int128),
+            "uint256" => Some(Base::Uint256),
+            _ => None,
+        }
+    }
+
+    /// Return the name of a base type as written in Vyper source code.
+    pub fn name(self) -> &'static str {
+        match self {
+            Base::Address => "address",
+            Base::Bool => "bool",
+            Base::Bytes32 => "bytes32",
+            Base::Decimal => "decimal",
+            Base::Int128 => "int128",
+            Base::Uint256 => "uint256",
+        }
+    }
+
+    /// Return the canonical ABI name of a base type.
+    pub fn abi_name(self) -> &'static str {
+        match self {
+            Base::Address => "address",
+            Base::Bool => "bool",
+            Base::Bytes32 => "bytes32",
+            Base::Decimal => "fixed168x10",
+            Base::Int128 => "int128",
+            Base::Uint256 => "uint256",
+        }
+    }
+}
+
+impl Type {
+    /// Return the name of a type as written in Vyper source code.  Structs are
+    /// referred to by name.
+    pub fn name(&self) -> String {
+        match self {
+            Type::Base(base) => base.name().to_string(),
+            Type::Array { inner, dimension } => format!("{}[{}]", inner.name(), dimension),
+            Type::Bytes { max_size } => format!("bytes[{}]", max_size),
+            Type::String { max_size } => format!("string[{}]", max_size),
+            Type::Tuple(items) => {
+                let names: Vec<_> = items.iter().map(Type::name).collect();
+                format!("({})", names.join(", "))
+            }
+            Type::Struct { name, .. } => name.clone(),
+        }
+    }
+
+    /// Return the canonical ABI name of a type.  This is the name used in
+    /// function and event signatures.
+    pub fn abi_name(&self) -> String {
+        match self {
+            Type::Base(base) => base.abi_name().to_string(),
+            Type::Array { inner, dimension } => format!("{}[{}]", inner.abi_name(), dimension),
+            Type::Bytes { .. } => "bytes".to_string(),
+            Type::String { .. } => "string".to_string(),
+            Type::Tuple(items) => tuple_abi_name(items.iter()),
+            Type::Struct { fields, .. } => tuple_abi_name(fields.iter().map(|(_, typ)| typ)),
+        }
+    }
+
+    /// Return true if values of a type are ABI encoded with a dynamic size.
+    pub fn is_dynamic(&self) -> bool {
+        match self {
+            Type::Base(_) => false,
+            Type::Array { inner, .. } => inner.is_dynamic(),
+            Type::Bytes { .. } | Type::String { .. } => true,
+            Type::Tuple(items) => items.iter().any(Type::is_dynamic),
+            Type::Struct { fields, .. } => fields.iter().any(|(_, typ)| typ.is_dynamic()),
+        }
+    }
+}
+
+/// Return the canonical ABI name of a tuple with the given item types, e.g.
+/// `(uint256,bool)`.
+fn tuple_abi_name<'a>(items: impl Iterator<Item = &'a Type>) -> String {
+    let names: Vec<_> = items.map(Type::abi_name).collect();
+
+    format!("({})", names.join(","))
+}
+
+/// Resolve a type description into a type.  Annotations such as `indexed` or
+/// `public` are not part of a type and are ignored.  Array dimensions and
+/// maximum sizes are folded with the named constants in `env`.  Map types only
+/// describe storage and are rejected.
+pub fn resolve_type_desc(desc: &Spanned<TypeDesc>, env: &ConstEnv) -> Result<Type, SemanticError> {
+    let TypeDesc {
+        base,
+        dimensions,
+        params,
+        ..
+    } = &desc.node;
+
+    if !params.is_empty() {
+        return Err(SemanticError::new(
+            "map types can only be used for storage fields",
+            desc.span,
+        ));
+    }
+
+    let mut dims = dimensions.iter().map(|dim| eval_dimension(desc, dim, env));
+
+    let mut typ = match *base {
+        "bytes" | "string" => {
+            let max_size = match dims.next() {
+                Some(size) => size?,
+                None => {
+                    return Err(SemanticError::new(
+                        "byte array types require a maximum size",
+                        desc.span,
+                    ))
+                }
+            };
+
+            if *base == "bytes" {
+                Type::Bytes { max_size }
+            } else {
+                Type::String { max_size }
+            }
+        }
+        _ => match Base::from_name(base) {
+            Some(base) => Type::Base(base),
+            None => return Err(SemanticError::new("unknown type", desc.span)),
+        },
+    };
+
+    for dim in dims {
+        typ = Type::Array {
+            inner: Box::new(typ),
+            dimension: dim?,
+        };
+    }
+
+    Ok(typ)
+}
+
+/// Fold the array dimension or maximum size `dim` of the type description
+/// `desc`.
+fn eval_dimension(
+    desc: &Spanned<TypeDesc>,
+    dim: &Spanned<ConstExpr>,
+    env: &ConstEnv,
+) -> Result<u32, SemanticError> {
+    let value = eval_const_expr(dim, env)?;
+
+    if !value.is_positive() {
+        return Err(SemanticError::new(
+            "array dimensions must be greater than zero",
+            desc.span,
+        ));
+    }
+
+    value
+        .to_u32()
+        .ok_or_else(|| SemanticError::new("array dimension is too large", dim.span))
+}
@@ -0,0 +1,380 @@
+extern crate wasm_bindgen_test;
+
+use num_bigint::BigInt;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use vyper_analyzer::builtins::{
+    builtin,
+    check_call,
+    Arg,
+    ArgValue,
+    CallContext,
+    KeywordArg,
+    BUILTINS,
+};
+use vyper_analyzer::errors::SemanticError;
+use vyper_analyzer::types::{
+    Base,
+    Type,
+};
+use vyper_parser::span::Span;
+
+/// A call with its name, arguments and keyword arguments and the type of its
+/// result.
+type TypedCall<'a> = (&'a str, Vec<Arg>, Vec<KeywordArg<'a>>, Option<Type>);
+
+/// A call with the message and span of the error it produces.
+type BadCall<'a> = (&'a str, Vec<Arg>, Vec<KeywordArg<'a>>, &'a str, Span);
+
+const CALL: Span = Span { start: 0, end: 100 };
+
+/// Return an argument with a distinct span for each position `i`.
+fn arg(i: usize, value: ArgValue) -> Arg {
+    Arg {
+        value,
+        span: Span::new(10 * i, 10 * i + 5),
+    }
+}
+
+fn expr(i: usize, typ: Type) -> Arg {
+    arg(i, ArgValue::Expr(typ))
+}
+
+fn int(i: usize, value: i64) -> Arg {
+    arg(i, ArgValue::Int(BigInt::from(value)))
+}
+
+fn kwarg(i: usize, name: &str, value: ArgValue) -> KeywordArg<'_> {
+    KeywordArg {
+        name,
+        name_span: Span::new(10 * i, 10 * i + 2),
+        arg: arg(i, value),
+    }
+}
+
+fn base(base: Base) -> Type {
+    Type::Base(base)
+}
+
+fn check(name: &str, args: &[Arg], kwargs: &[KeywordArg]) -> Result<Option<Type>, SemanticError> {
+    check_call(CALL, name, args, kwargs, CallContext::Mutable)
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_catalogue() {
+    // Built-ins are sorted so that they can be looked up by binary search
+    for pair in BUILTINS.windows(2) {
+        assert!(pair[0].name < pair[1].name, "{}", pair[1].name);
+    }
+    for b in BUILTINS {
+        assert_eq!(builtin(b.name), Some(b));
+    }
+    assert_eq!(builtin("range"), None);
+
+    assert_eq!(
+        builtin("slice").unwrap().signature(),
+        "slice(b: bytes, *, start: integer, len: integer literal) -> bytes[len]",
+    );
+    assert_eq!(
+        builtin("raw_call").unwrap().signature(),
+        "raw_call(to: address, data: bytes, *, max_outsize: integer literal, gas: uint256 = ..., value: uint256 = ..., delegate_call: bool = ...) -> bytes[max_outsize]",
+    );
+    assert_eq!(
+        builtin("concat").unwrap().signature(),
+        "concat(a: bytes32, bytes or string, b: bytes32, bytes or string, ...) -> bytes",
+    );
+    assert_eq!(
+        builtin("send").unwrap().signature(),
+        "send(to: address, value: uint256)"
+    );
+    assert_eq!(
+        builtin("convert").unwrap().signature(),
+        "convert(value: any, type: type) -> type",
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_return_types() {
+    let uint256 = base(Base::Uint256);
+    let int128 = base(Base::Int128);
+
+    let examples: Vec<TypedCall> = vec![
+        (
+            "len",
+            vec![expr(0, Type::String { max_size: 10 })],
+            vec![],
+            Some(int128.clone()),
+        ),
+        (
+            "as_wei_value",
+            vec![int(0, 5), arg(1, ArgValue::Str("gwei".into()))],
+            vec![],
+            Some(uint256.clone()),
+        ),
+        (
+            "min",
+            vec![expr(0, int128.clone()), int(1, -3)],
+            vec![],
+            Some(int128.clone()),
+        ),
+        (
+            "max",
+            vec![int(0, 1), int(1, 2)],
+            vec![],
+            Some(uint256.clone()),
+        ),
+        (
+            "max",
+            vec![int(0, 1), int(1, -2)],
+            vec![],
+            Some(int128.clone()),
+        ),
+        (
+            "convert",
+            vec![
+                expr(0, int128.clone()),
+                arg(1, ArgValue::TypeName(uint256.clone())),
+            ],
+            vec![],
+            Some(uint256.clone()),
+        ),
+        (
+            "empty",
+            vec![arg(0, ArgValue::TypeName(Type::Bytes { max_size: 7 }))],
+            vec![],
+            Some(Type::Bytes { max_size: 7 }),
+        ),
+        (
+            "concat",
+            vec![
+                expr(0, Type::Bytes { max_size: 10 }),
+                expr(1, base(Base::Bytes32)),
+                arg(2, ArgValue::Str("abc".into())),
+            ],
+            vec![],
+            Some(Type::Bytes { max_size: 45 }),
+        ),
+        (
+            "concat",
+            vec![
+                expr(0, Type::String { max_size: 10 }),
+                arg(1, ArgValue::Str("abc".into())),
+            ],
+            vec![],
+            Some(Type::String { max_size: 13 }),
+        ),
+        (
+            "slice",
+            vec![expr(0, Type::Bytes { max_size: 100 })],
+            vec![
+                kwarg(1, "start", ArgValue::Expr(uint256.clone())),
+                kwarg(2, "len", ArgValue::Int(BigInt::from(32))),
+            ],
+            Some(Type::Bytes { max_size: 32 }),
+        ),
+        (
+            "raw_call",
+            vec![
+                expr(0, base(Base::Address)),
+                expr(1, Type::Bytes { max_size: 4 }),
+            ],
+            vec![
+                kwarg(2, "max_outsize", ArgValue::Int(BigInt::from(64))),
+                kwarg(3, "gas", ArgValue::Int(BigInt::from(50000))),
+            ],
+            Some(Type::Bytes { max_size: 64 }),
+        ),
+        (
+            "keccak256",
+            vec![expr(0, base(Base::Bytes32))],
+            vec![],
+            Some(base(Base::Bytes32)),
+        ),
+        (
+            "send",
+            vec![expr(0, base(Base::Address)), int(1, 1)],
+            vec![],
+            None,
+        ),
+    ];
+
+    for (name, args, kwargs, expected) in examples {
+        assert_eq!(check(name, &args, &kwargs), Ok(expected), "{}", name);
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_misuse() {
+    let uint256 = base(Base::Uint256);
+    let address = expr(0, base(Base::Address));
+    let data = expr(1, Type::Bytes { max_size: 4 });
+    let outsize = kwarg(2, "max_outsize", ArgValue::Int(BigInt::from(32)));
+
+    let examples: Vec<BadCall> = vec![
+        (
+            "range",
+            vec![int(0, 1)],
+            vec![],
+            "unknown built-in function",
+            CALL,
+        ),
+        (
+            "len",
+            vec![],
+            vec![],
+            "too few arguments to built-in function",
+            CALL,
+        ),
+        (
+            "len",
+            vec![expr(0, Type::Bytes { max_size: 1 }), int(1, 1)],
+            vec![],
+            "too many arguments to built-in function",
+            Span::new(10, 15),
+        ),
+        (
+            "len",
+            vec![expr(0, uint256.clone())],
+            vec![],
+            "argument has the wrong type",
+            Span::new(0, 5),
+        ),
+        (
+            "ecrecover",
+            vec![
+                expr(0, base(Base::Bytes32)),
+                int(1, 27),
+                int(2, -1),
+                int(3, 0),
+            ],
+            vec![],
+            "integer literal is out of bounds for the argument",
+            Span::new(20, 25),
+        ),
+        (
+            "convert",
+            vec![int(0, 1), int(1, 2)],
+            vec![],
+            "expected a type name",
+            Span::new(10, 15),
+        ),
+        (
+            "len",
+            vec![arg(0, ArgValue::TypeName(uint256.clone()))],
+            vec![],
+            "expected a value, found a type name",
+            Span::new(0, 5),
+        ),
+        (
+            "as_wei_value",
+            vec![int(0, 1), arg(1, ArgValue::Str("eth".into()))],
+            vec![],
+            "unknown denomination",
+            Span::new(10, 15),
+        ),
+        (
+            "min",
+            vec![expr(0, uint256.clone()), expr(1, base(Base::Int128))],
+            vec![],
+            "arguments must have the same type",
+            Span::new(10, 15),
+        ),
+        (
+            "min",
+            vec![
+                expr(0, base(Base::Int128)),
+                arg(1, ArgValue::Int(BigInt::from(1u128 << 127))),
+            ],
+            vec![],
+            "integer literal is out of bounds for the argument",
+            Span::new(10, 15),
+        ),
+        (
+            "concat",
+            vec![
+                expr(0, Type::String { max_size: 1 }),
+                expr(1, Type::Bytes { max_size: 1 }),
+            ],
+            vec![],
+            "cannot concatenate strings and byte arrays",
+            Span::new(10, 15),
+        ),
+        (
+            "slice",
+            vec![expr(0, Type::Bytes { max_size: 100 })],
+            vec![
+                kwarg(1, "start", ArgValue::Int(BigInt::from(0))),
+                kwarg(2, "len", ArgValue::Expr(uint256.clone())),
+            ],
+            "expected an integer literal",
+            Span::new(20, 25),
+        ),
+        (
+            "raw_call",
+            vec![address.clone(), data.clone()],
+            vec![],
+            "missing required keyword argument",
+            CALL,
+        ),
+        (
+            "raw_call",
+            vec![address.clone(), data.clone()],
+            vec![
+                outsize.clone(),
+                kwarg(3, "gas_limit", ArgValue::Int(BigInt::from(1))),
+            ],
+            "unexpected keyword argument",
+            Span::new(30, 32),
+        ),
+        (
+            "raw_call",
+            vec![address, data],
+            vec![outsize.clone(), outsize],
+            "duplicate keyword argument",
+            Span::new(20, 22),
+        ),
+    ];
+
+    for (name, args, kwargs, msg, span) in examples {
+        assert_eq!(
+            check(name, &args, &kwargs),
+            Err(SemanticError::new(msg, span)),
+            "{}",
+            name,
+        );
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_call_contexts() {
+    let hash = [expr(0, Type::Bytes { max_size: 3 })];
+    assert!(check_call(CALL, "keccak256", &hash, &[], CallContext::Constant).is_ok());
+    assert_eq!(
+        check_call(CALL, "sha256", &hash, &[], CallContext::Constant),
+        Err(SemanticError::new(
+            "built-in function cannot be used in a constant expression",
+            CALL,
+        )),
+    );
+    assert!(check_call(CALL, "sha256", &hash, &[], CallContext::View).is_ok());
+
+    let send = [expr(0, base(Base::Address)), int(1, 1)];
+    assert_eq!(
+        check_call(CALL, "send", &send, &[], CallContext::View),
+        Err(SemanticError::new(
+            "built-in function modifies state and cannot be called from a view function",
+            CALL,
+        )),
+    );
+    assert_eq!(
+        check_call(CALL, "send", &send, &[], CallContext::Constant),
+        Err(SemanticError::new(
+            "built-in function cannot be used in a constant expression",
+            CALL,
+        )),
+    );
+}
@@ -0,0 +1,715 @@
+extern crate wasm_bindgen_test;
+
+use std::collections::HashMap;
+
+use nom::error::ErrorKind;
+use num_bigint::BigInt;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use vyper_analyzer::bounds::{
+    bound_report,
+    function_loops,
+    module_bounds,
+    nested_iterations,
+    range_iterations,
+    same_expr,
+    FunctionLoops,
+    Loop,
+};
+use vyper_analyzer::const_eval::{
+    eval_const_expr,
+    module_constants,
+    parse_int_literal,
+    ConstEnv,
+};
+use vyper_analyzer::errors::SemanticError;
+use vyper_parser::ast::{
+    ConstExpr,
+    Operator,
+};
+use vyper_parser::parsers::{
+    const_expr,
+    file_input,
+    get_parse_tokens,
+};
+use vyper_parser::source_map::SourceMap;
+use vyper_parser::span::{
+    Span,
+    Spanned,
+};
+use vyper_parser::tokenizer::Token;
+
+type SimpleError<I> = (I, ErrorKind);
+
+/// Parse comma-separated constant expressions from the start of `input`.
+/// Returns the expressions and the remaining tokens.
+fn parse_const_exprs<'a>(
+    mut input: &'a [Token<'a>],
+) -> (Vec<Spanned<ConstExpr<'a>>>, &'a [Token<'a>]) {
+    let mut exprs = vec![];
+
+    loop {
+        let (rest, expr) = const_expr::<SimpleError<_>>(input).unwrap();
+        exprs.push(expr);
+
+        match rest.first() {
+            Some(tok) if tok.string == "," => input = &rest[1..],
+            _ => return (exprs, rest),
+        }
+    }
+}
+
+/// Parse each of the comma-separated constant expressions in `source` and
+/// pass them to `f`.
+fn with_const_exprs<F, R>(source: &str, f: F) -> R
+where
+    F: FnOnce(Vec<Spanned<ConstExpr>>) -> R,
+{
+    let tokens = get_parse_tokens(source).unwrap();
+    let (exprs, rest) = parse_const_exprs(&tokens[..]);
+
+    // Only the trailing NEWLINE and ENDMARKER tokens should remain
+    assert_eq!(rest.len(), 2, "could not parse {}", source);
+
+    f(exprs)
+}
+
+fn get_env() -> ConstEnv<'static> {
+    let mut env = HashMap::new();
+    env.insert("FOO", BigInt::from(10));
+    env.insert("BAR", BigInt::from(-3));
+    env
+}
+
+fn eval(source: &str) -> Result<BigInt, SemanticError> {
+    with_const_exprs(source, |exprs| eval_const_expr(&exprs[0], &get_env()))
+}
+
+/// Evaluate a binary operation on the constant expressions `left` and `right`.
+/// Shift operators are not part of the constant expression grammar, so the
+/// operation is built by hand.
+fn eval_bin_op(left: &str, op: Operator, right: &str) -> Result<BigInt, SemanticError> {
+    let source = format!("{}, {}", left, right);
+
+    with_const_exprs(&source, |mut exprs| {
+        let right = exprs.pop().unwrap();
+        let left = exprs.pop().unwrap();
+        let expr = Spanned::new(
+            ConstExpr::BinOp {
+                left: Box::new(left),
+                op,
+                right: Box::new(right),
+            },
+            Span::new(0, source.len()),
+        );
+
+        eval_const_expr(&expr, &get_env())
+    })
+}
+
+fn range(source: &str) -> Result<u64, SemanticError> {
+    with_const_exprs(source, |exprs| {
+        range_iterations(Span::new(0, source.len()), &exprs, &get_env())
+    })
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_parse_int_literal() {
+    let examples = vec![
+        ("0", Some(0)),
+        ("42", Some(42)),
+        ("1_000", Some(1000)),
+        ("0xff", Some(255)),
+        ("0XF_F", Some(255)),
+        ("0o17", Some(15)),
+        ("0b101", Some(5)),
+        ("1.5", None),
+        ("1e3", None),
+        ("0x", None),
+    ];
+
+    for (inp, expected) in examples {
+        assert_eq!(
+            parse_int_literal(inp),
+            expected.map(BigInt::from),
+            "{}",
+            inp
+        );
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_eval_const_expr_ok() {
+    let examples = vec![
+        ("1 + 2 * 3", 7),
+        ("(1 + 2) * 3", 9),
+        ("FOO - BAR", 13),
+        ("-FOO", -10),
+        ("~FOO", -11),
+        ("+FOO", 10),
+        ("2 ** 3 ** 2", 512),
+        ("-2 ** 2", -4),
+        ("(-1) ** 3", -1),
+        ("(-1) ** 4", 1),
+        ("0 ** 0", 1),
+        ("7 / 2", 3),
+        ("-7 / 2", -3),
+        ("7 % FOO", 7),
+        ("-7 % 3", -1),
+    ];
+
+    for (inp, expected) in examples {
+        assert_eq!(eval(inp), Ok(BigInt::from(expected)), "{}", inp);
+    }
+
+    assert_eq!(eval("2 ** 256 - 1"), Ok((BigInt::from(1) << 256) - 1),);
+    assert_eq!(eval("1 ** 100000000000000000000"), Ok(BigInt::from(1)));
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_eval_shifts() {
+    let examples = vec![
+        ("FOO", Operator::LShift, "2", Ok(40)),
+        ("FOO", Operator::RShift, "1", Ok(5)),
+        ("BAR", Operator::RShift, "1", Ok(-2)),
+        ("FOO", Operator::RShift, "4", Ok(0)),
+        ("BAR", Operator::RShift, "4", Ok(-1)),
+        ("FOO", Operator::RShift, "300", Ok(0)),
+        ("BAR", Operator::RShift, "300", Ok(-1)),
+        ("FOO", Operator::RShift, "2 ** 255", Ok(0)),
+        ("0", Operator::LShift, "2 ** 255", Ok(0)),
+        ("FOO", Operator::RShift, "BAR", Err("negative shift amount")),
+        (
+            "FOO",
+            Operator::LShift,
+            "256",
+            Err("constant expression value is out of bounds"),
+        ),
+    ];
+
+    for (left, op, right, expected) in examples {
+        let source = format!("{}, {}", left, right);
+        let expected = expected
+            .map(BigInt::from)
+            .map_err(|msg| SemanticError::new(msg, Span::new(0, source.len())));
+
+        assert_eq!(eval_bin_op(left, op, right), expected, "{}", source);
+    }
+
+    // Intermediate values may be wider than 256 bits
+    assert_eq!(
+        eval_bin_op("2 ** 256 * 2 ** 200", Operator::RShift, "300"),
+        Ok(BigInt::from(1) << 156)
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_eval_calls() {
+    let examples = vec![
+        ("max(FOO, 3)", 10),
+        ("min(BAR, 1)", -3),
+        ("shift(1, 8)", 256),
+        ("shift(256, -4)", 16),
+        ("shift(1, 256)", 0),
+        ("bitwise_and(12, 10)", 8),
+        ("bitwise_or(12, 10)", 14),
+        ("bitwise_xor(12, 10)", 6),
+        ("uint256_addmod(5, 6, 4)", 3),
+        ("uint256_mulmod(5, 6, 4)", 2),
+        ("convert(FOO, uint256)", 10),
+        ("empty(int128)", 0),
+        ("max(1, 2) * shift(FOO, 1) + 1", 41),
+    ];
+
+    for (inp, expected) in examples {
+        assert_eq!(eval(inp), Ok(BigInt::from(expected)), "{}", inp);
+    }
+
+    assert_eq!(eval("bitwise_not(0)"), Ok((BigInt::from(1) << 256) - 1));
+
+    let errors = vec![
+        (
+            "len(FOO)",
+            "built-in function cannot be used in a constant expression",
+            Span::new(0, 8),
+        ),
+        ("foo(1)", "unknown built-in function", Span::new(0, 6)),
+        (
+            "max(1)",
+            "too few arguments to built-in function",
+            Span::new(0, 6),
+        ),
+        (
+            "convert(1, bool)",
+            "built-in function cannot be folded into an integer",
+            Span::new(0, 16),
+        ),
+        (
+            "uint256_addmod(1, 2, 0)",
+            "division by zero",
+            Span::new(0, 23),
+        ),
+        (
+            "convert(BAR, uint256)",
+            "constant expression value is out of bounds",
+            Span::new(0, 21),
+        ),
+        (
+            "max(UNKNOWN, 1)",
+            "name is not a compile-time constant",
+            Span::new(4, 11),
+        ),
+    ];
+
+    for (inp, msg, span) in errors {
+        assert_eq!(eval(inp), Err(SemanticError::new(msg, span)), "{}", inp);
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_eval_const_expr_errors() {
+    let examples = vec![
+        (
+            "UNKNOWN + 1",
+            "name is not a compile-time constant",
+            Span::new(0, 7),
+        ),
+        ("1 + 1.5", "expected an integer literal", Span::new(4, 7)),
+        ("FOO / (FOO - 10)", "division by zero", Span::new(0, 16)),
+        ("FOO % 0", "division by zero", Span::new(0, 7)),
+        ("2 ** BAR", "negative exponent", Span::new(0, 8)),
+        (
+            "2 ** 256",
+            "constant expression value is out of bounds",
+            Span::new(0, 8),
+        ),
+        (
+            "2 ** 257 - 2",
+            "constant expression value is out of bounds",
+            Span::new(0, 8),
+        ),
+        (
+            "2 ** 256 * 2 - 2",
+            "constant expression value is out of bounds",
+            Span::new(0, 16),
+        ),
+        (
+            "2 ** 256 ** 2",
+            "constant expression value is out of bounds",
+            Span::new(0, 13),
+        ),
+        (
+            "(2 ** 256) ** 2 + 1",
+            "constant expression value is out of bounds",
+            Span::new(0, 15),
+        ),
+        (
+            "-(2 ** 255) - 1",
+            "constant expression value is out of bounds",
+            Span::new(0, 15),
+        ),
+    ];
+
+    for (inp, msg, span) in examples {
+        assert_eq!(eval(inp), Err(SemanticError::new(msg, span)), "{}", inp);
+    }
+}
+
+/// Evaluate the constants defined in the module `source` and return them in
+/// order of name.
+fn constants(source: &str) -> Result<Vec<(String, BigInt)>, SemanticError> {
+    let tokens = get_parse_tokens(source).unwrap();
+    let (_, module) = file_input::<SimpleError<_>>(&tokens[..]).unwrap();
+
+    let mut constants: Vec<_> = module_constants(&module.node)?
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value))
+        .collect();
+    constants.sort();
+
+    Ok(constants)
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_module_constants() {
+    assert_eq!(
+        constants(
+            "DECIMALS: constant(int128) = 18\n\
+             total: uint256\n\
+             SUPPLY: constant(uint256) = 10 ** DECIMALS * 2\n\
+             MASK: constant(uint256) = shift(1, DECIMALS) - 1\n"
+        ),
+        Ok(vec![
+            ("DECIMALS".to_string(), BigInt::from(18)),
+            ("MASK".to_string(), BigInt::from(262143)),
+            ("SUPPLY".to_string(), "2000000000000000000".parse().unwrap()),
+        ])
+    );
+
+    let examples = vec![
+        (
+            "A: constant(uint256) = B\nB: constant(uint256) = 1\n",
+            "name is not a compile-time constant",
+            Span::new(23, 24),
+        ),
+        (
+            "A: constant(uint256) = 1\nA: constant(uint256) = 2\n",
+            "duplicate constant definition",
+            Span::new(25, 49),
+        ),
+        (
+            "A: constant(uint256)\n",
+            "constants require a value",
+            Span::new(0, 20),
+        ),
+        (
+            "a: uint256 = 1\n",
+            "only constants may be assigned a value",
+            Span::new(13, 14),
+        ),
+    ];
+
+    for (inp, msg, span) in examples {
+        assert_eq!(
+            constants(inp),
+            Err(SemanticError::new(msg, span)),
+            "{}",
+            inp
+        );
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_same_expr() {
+    with_const_exprs("a + b * 2, a+b*2, a + b * 3, (a + b) * 2", |exprs| {
+        assert!(same_expr(&exprs[0], &exprs[1]));
+        assert!(!same_expr(&exprs[0], &exprs[2]));
+        assert!(!same_expr(&exprs[0], &exprs[3]));
+    });
+
+    with_const_exprs("max(a, 2), max( a,2 ), min(a, 2), max(a, 3)", |exprs| {
+        assert!(same_expr(&exprs[0], &exprs[1]));
+        assert!(!same_expr(&exprs[0], &exprs[2]));
+        assert!(!same_expr(&exprs[0], &exprs[3]));
+    });
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_range_iterations_ok() {
+    let examples = vec![
+        ("10", 10),
+        ("FOO * 2", 20),
+        ("0", 0),
+        ("BAR, FOO", 13),
+        ("x, x + FOO", 10),
+        ("x * 2 - 1, x * 2 - 1 + 5", 5),
+    ];
+
+    for (inp, expected) in examples {
+        assert_eq!(range(inp), Ok(expected), "{}", inp);
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_range_iterations_unbounded() {
+    let examples = vec![
+        ("x", "name is not a compile-time constant", Span::new(0, 1)),
+        (
+            "0, x",
+            "name is not a compile-time constant",
+            Span::new(3, 4),
+        ),
+        (
+            "x, y + 10",
+            "name is not a compile-time constant",
+            Span::new(0, 1),
+        ),
+        (
+            "x, x + y",
+            "name is not a compile-time constant",
+            Span::new(7, 8),
+        ),
+        (
+            "BAR",
+            "range must not have a negative length",
+            Span::new(0, 3),
+        ),
+        (
+            "FOO, BAR",
+            "range must not have a negative length",
+            Span::new(5, 8),
+        ),
+        (
+            "x, x + BAR",
+            "range must not have a negative length",
+            Span::new(7, 10),
+        ),
+        ("2 ** 64", "range is too long", Span::new(0, 7)),
+        (
+            "1, 2, 3",
+            "range expects one or two arguments",
+            Span::new(0, 7),
+        ),
+    ];
+
+    for (inp, msg, span) in examples {
+        assert_eq!(range(inp), Err(SemanticError::new(msg, span)), "{}", inp);
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_nested_iterations() {
+    assert_eq!(nested_iterations(&[]), Some(1));
+    assert_eq!(nested_iterations(&[10, 20, 3]), Some(600));
+    assert_eq!(nested_iterations(&[10, 0]), Some(0));
+    assert_eq!(nested_iterations(&[u64::MAX, 2]), None);
+}
+
+const LOOPS: &str = "def foo():
+    for i in range(10):
+        for j in range(i, i + FOO):
+            pass
+    for k in range(BAR, 4):
+        pass
+
+def bar():
+    pass
+
+def baz():
+    for i in range(FOO):
+        pass
+
+def qux(n: uint256):
+    for i in range(n):
+        pass
+";
+
+/// Build the loop over the call to `range` starting at the `n`th occurrence
+/// of `range` in `tokens`.  Loops end at the next `pass`.
+fn range_loop<'a>(tokens: &'a [Token<'a>], n: usize, body: Vec<Loop<'a>>) -> Loop<'a> {
+    let start = tokens
+        .iter()
+        .enumerate()
+        .filter(|(_, tok)| tok.string == "range")
+        .nth(n)
+        .unwrap()
+        .0;
+    let (range_args, rest) = parse_const_exprs(&tokens[start + 2..]);
+    let pass = tokens[start..]
+        .iter()
+        .find(|tok| tok.string == "pass")
+        .unwrap();
+
+    Loop {
+        span: Span::new(tokens[start - 3].span.start, pass.span.end),
+        range_span: Span::new(tokens[start].span.start, rest[0].span.end),
+        range_args,
+        body,
+    }
+}
+
+/// Return the span of the definition of the function `name` in `LOOPS`.
+fn function_span(name: &str) -> Span {
+    let start = LOOPS.find(&format!("def {}", name)).unwrap();
+    let end = LOOPS[start..]
+        .find("\n\n")
+        .map_or(LOOPS.trim_end().len(), |end| start + end);
+
+    Span::new(start, end)
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_bound_report() {
+    let tokens = get_parse_tokens(LOOPS).unwrap();
+    let functions = vec![
+        FunctionLoops {
+            name: "foo",
+            span: function_span("foo"),
+            loops: vec![
+                range_loop(&tokens, 0, vec![range_loop(&tokens, 1, vec![])]),
+                range_loop(&tokens, 2, vec![]),
+            ],
+        },
+        FunctionLoops {
+            name: "bar",
+            span: function_span("bar"),
+            loops: vec![],
+        },
+        FunctionLoops {
+            name: "baz",
+            span: function_span("baz"),
+            loops: vec![range_loop(&tokens, 3, vec![])],
+        },
+    ];
+
+    let report = bound_report(&functions, &get_env()).unwrap();
+    let foo = &report.functions[0];
+    assert_eq!(foo.executions, 10 + 100 + 7);
+    assert_eq!(
+        foo.loops
+            .iter()
+            .map(|bound| (bound.depth, bound.iterations, bound.executions))
+            .collect::<Vec<_>>(),
+        vec![(0, 10, 10), (1, 10, 100), (0, 7, 7)],
+    );
+    assert_eq!(foo.loops[1].span, functions[0].loops[0].body[0].span);
+
+    let mut sources = SourceMap::new();
+    let file = sources.add_file("loops.vy", LOOPS.to_string());
+    assert_eq!(
+        report.render(&sources, file),
+        "loops.vy:1:1: function foo: at most 117 loop iterations
+  loops.vy:2:5: loop: 10 iterations, 10 in the worst case
+    loops.vy:3:9: loop: 10 iterations, 100 in the worst case
+  loops.vy:5:5: loop: 7 iterations, 7 in the worst case
+loops.vy:8:1: function bar: at most 0 loop iterations
+loops.vy:11:1: function baz: at most 10 loop iterations
+  loops.vy:12:5: loop: 10 iterations, 10 in the worst case
+",
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_bound_report_errors() {
+    let tokens = get_parse_tokens(LOOPS).unwrap();
+    let unbounded = FunctionLoops {
+        name: "qux",
+        span: function_span("qux"),
+        loops: vec![range_loop(&tokens, 4, vec![])],
+    };
+
+    let n = LOOPS.find("range(n)").unwrap() + 6;
+    assert_eq!(
+        bound_report(&[unbounded], &get_env()),
+        Err(SemanticError::new(
+            "name is not a compile-time constant",
+            Span::new(n, n + 1),
+        )),
+    );
+
+    let mut env = get_env();
+    env.insert("FOO", BigInt::from(u64::MAX));
+    let too_long = FunctionLoops {
+        name: "foo",
+        span: function_span("foo"),
+        loops: vec![range_loop(&tokens, 0, vec![range_loop(&tokens, 1, vec![])])],
+    };
+
+    let inner_span = too_long.loops[0].body[0].span;
+    assert_eq!(
+        bound_report(&[too_long], &env),
+        Err(SemanticError::new(
+            "loop nest iterates too many times",
+            inner_span
+        )),
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_function_loops() {
+    let tokens = get_parse_tokens(LOOPS).unwrap();
+    let (_, module) = file_input::<SimpleError<_>>(&tokens[..]).unwrap();
+    let functions = function_loops(&module.node).unwrap();
+
+    assert_eq!(
+        functions
+            .iter()
+            .map(|function| (function.name, function.span))
+            .collect::<Vec<_>>(),
+        ["foo", "bar", "baz", "qux"]
+            .iter()
+            .map(|name| (*name, function_span(name)))
+            .collect::<Vec<_>>(),
+    );
+    assert_eq!(
+        functions[0].loops,
+        vec![
+            range_loop(&tokens, 0, vec![range_loop(&tokens, 1, vec![])]),
+            range_loop(&tokens, 2, vec![]),
+        ],
+    );
+    assert_eq!(functions[1].loops, vec![]);
+    assert_eq!(functions[2].loops, vec![range_loop(&tokens, 3, vec![])]);
+    assert_eq!(functions[3].loops, vec![range_loop(&tokens, 4, vec![])]);
+}
+
+/// Compute the loop bounds of the module in `source`, returning the name and
+/// worst-case number of loop iterations of each function.
+fn module_iterations(source: &str) -> Result<Vec<(String, u64)>, SemanticError> {
+    let tokens = get_parse_tokens(source).unwrap();
+    let (_, module) = file_input::<SimpleError<_>>(&tokens[..]).unwrap();
+
+    Ok(module_bounds(&module.node)?
+        .functions
+        .into_iter()
+        .map(|function| (function.name, function.executions))
+        .collect())
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_module_bounds() {
+    assert_eq!(
+        module_iterations(
+            "STEPS: constant(int128) = 4
+
+@external
+def f(x: int128):
+    if x > 0:
+        for i in range(STEPS * 2):
+            pass
+    else:
+        for i in range(x, x + STEPS):
+            for j in range(-1, STEPS):
+                pass
+
+@external
+def g():
+    pass
+"
+        ),
+        Ok(vec![("f".to_string(), 8 + 4 + 4 * 5), ("g".to_string(), 0)]),
+    );
+
+    let examples = [
+        (
+            "def f():\n    for i in self.items:\n        pass\n",
+            "loop must iterate over a call to range",
+            "self.items",
+        ),
+        (
+            "def f():\n    for i in range(self.n):\n        pass\n",
+            "expression is not a compile-time constant",
+            "self.n",
+        ),
+        (
+            "def f(n: int128):\n    for i in range(n):\n        pass\n",
+            "name is not a compile-time constant",
+            "n)",
+        ),
+    ];
+
+    for (source, msg, at) in examples {
+        let start = source.find(at).unwrap();
+        let end = start + at.trim_end_matches(')').len();
+        assert_eq!(
+            module_iterations(source),
+            Err(SemanticError::new(msg, Span::new(start, end))),
+            "{}",
+            source
+        );
+    }
+}
@@ -0,0 +1,169 @@
+extern crate wasm_bindgen_test;
+
+use nom::error::ErrorKind;
+use num_bigint::BigInt;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use vyper_analyzer::const_eval::ConstEnv;
+use vyper_analyzer::errors::SemanticError;
+use vyper_analyzer::types::{
+    resolve_type_desc,
+    Base,
+    Type,
+};
+use vyper_parser::parsers::{
+    ann_type_desc,
+    get_parse_tokens,
+};
+use vyper_parser::span::Span;
+
+type SimpleError<I> = (I, ErrorKind);
+
+fn resolve(source: &str) -> Result<Type, SemanticError> {
+    let tokens = get_parse_tokens(source).unwrap();
+    let (_, desc) = ann_type_desc::<SimpleError<_>>(&tokens[..]).unwrap();
+
+    let mut env = ConstEnv::new();
+    env.insert("N", BigInt::from(3));
+
+    resolve_type_desc(&desc, &env)
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_resolve_type_desc_ok() {
+    let examples = vec![
+        ("address", Type::Base(Base::Address), "address", false),
+        ("bool", Type::Base(Base::Bool), "bool", false),
+        ("bytes32", Type::Base(Base::Bytes32), "bytes32", false),
+        ("decimal", Type::Base(Base::Decimal), "fixed168x10", false),
+        ("indexed(int128)", Type::Base(Base::Int128), "int128", false),
+        ("uint256", Type::Base(Base::Uint256), "uint256", false),
+        ("bytes[100]", Type::Bytes { max_size: 100 }, "bytes", true),
+        ("string[5]", Type::String { max_size: 5 }, "string", true),
+        (
+            "uint256[3][2]",
+            Type::Array {
+                inner: Box::new(Type::Array {
+                    inner: Box::new(Type::Base(Base::Uint256)),
+                    dimension: 3,
+                }),
+                dimension: 2,
+            },
+            "uint256[3][2]",
+            false,
+        ),
+        (
+            "bytes[10][2]",
+            Type::Array {
+                inner: Box::new(Type::Bytes { max_size: 10 }),
+                dimension: 2,
+            },
+            "bytes[2]",
+            true,
+        ),
+    ];
+
+    for (inp, expected, abi_name, is_dynamic) in examples {
+        let typ = resolve(inp).unwrap();
+
+        assert_eq!(typ, expected, "{}", inp);
+        assert_eq!(
+            typ.name(),
+            inp.trim_start_matches("indexed(").trim_end_matches(')')
+        );
+        assert_eq!(typ.abi_name(), abi_name, "{}", inp);
+        assert_eq!(typ.is_dynamic(), is_dynamic, "{}", inp);
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_resolve_constant_dimensions() {
+    assert_eq!(
+        resolve("uint256[N * 2]"),
+        Ok(Type::Array {
+            inner: Box::new(Type::Base(Base::Uint256)),
+            dimension: 6,
+        })
+    );
+    assert_eq!(
+        resolve("bytes[2 ** 8 - N]"),
+        Ok(Type::Bytes { max_size: 253 })
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_resolve_type_desc_errors() {
+    let examples = vec![
+        ("uint8", "unknown type", Span::new(0, 5)),
+        (
+            "bytes",
+            "byte array types require a maximum size",
+            Span::new(0, 5),
+        ),
+        (
+            "uint256[0]",
+            "array dimensions must be greater than zero",
+            Span::new(0, 10),
+        ),
+        (
+            "uint256[N - 4]",
+            "array dimensions must be greater than zero",
+            Span::new(0, 14),
+        ),
+        (
+            "bytes[0]",
+            "array dimensions must be greater than zero",
+            Span::new(0, 8),
+        ),
+        (
+            "uint256[2 ** 32]",
+            "array dimension is too large",
+            Span::new(8, 15),
+        ),
+        (
+            "uint256[M]",
+            "name is not a compile-time constant",
+            Span::new(8, 9),
+        ),
+        (
+            "public(map(address, uint256))",
+            "map types can only be used for storage fields",
+            Span::new(0, 29),
+        ),
+    ];
+
+    for (inp, msg, span) in examples {
+        assert_eq!(resolve(inp), Err(SemanticError::new(msg, span)), "{}", inp);
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_tuple_and_struct_types() {
+    let tuple = Type::Tuple(vec![
+        Type::Base(Base::Uint256),
+        Type::Array {
+            inner: Box::new(Type::Base(Base::Bool)),
+            dimension: 2,
+        },
+    ]);
+    assert_eq!(tuple.abi_name(), "(uint256,bool[2])");
+    assert_eq!(tuple.name(), "(uint256, bool[2])");
+    assert!(!tuple.is_dynamic());
+
+    let point = Type::Struct {
+        name: "Point".to_string(),
+        fields: vec![
+            ("label".to_string(), Type::String { max_size: 10 }),
+            ("inner".to_string(), tuple),
+        ],
+    };
+    assert_eq!(point.abi_name(), "(string,(uint256,bool[2]))");
+    assert_eq!(point.name(), "Point");
+    assert!(point.is_dynamic());
+
+    assert_eq!(Type::Tuple(vec![]).abi_name(), "()");
+}
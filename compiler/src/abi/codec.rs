@@ -0,0 +1,374 @@
+//! ABI encoding and decoding of values of Vyper types.
+//!
+//! Values are encoded as described in the [Solidity ABI
+//! specification](https://solidity.readthedocs.io/en/v0.5.13/abi-spec.html).
+//! A sequence of values is encoded as a head containing static values in place
+//! and an offset for each dynamic value, followed by a tail containing the
+//! encodings of the dynamic values.  Offsets are relative to the start of the
+//! sequence.  Fixed-size lists, tuples and structs are encoded as nested
+//! sequences.
+
+use num_bigint::{
+    BigInt,
+    BigUint,
+    Sign,
+};
+use num_traits::{
+    One,
+    ToPrimitive,
+};
+
+use vyper_analyzer::types::{
+    Base,
+    Type,
+};
+
+use crate::abi::DECIMAL_PLACES;
+use crate::errors::AbiError;
+
+/// The size in bytes of an ABI word.
+const WORD_SIZE: usize = 32;
+
+/// A value of a Vyper type.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Value {
+    Address([u8; 20]),
+    Bool(bool),
+    Bytes32([u8; 32]),
+    /// A decimal given as an integer scaled by 10^10.
+    Decimal(BigInt),
+    Int(BigInt),
+    Uint(BigUint),
+    Bytes(Vec<u8>),
+    String(String),
+    /// The items of a fixed-size list.
+    Array(Vec<Value>),
+    /// The items of a tuple or the fields of a struct in order of definition.
+    Tuple(Vec<Value>),
+}
+
+/// An iterator over the item types of a type that is encoded as a sequence of
+/// values.
+#[derive(Clone)]
+enum ItemTypes<'a> {
+    /// The items of a fixed-size list given by their type and the number of
+    /// items left.
+    Array(&'a Type, usize),
+    Tuple(std::slice::Iter<'a, Type>),
+    Struct(std::slice::Iter<'a, (String, Type)>),
+}
+
+impl<'a> Iterator for ItemTypes<'a> {
+    type Item = &'a Type;
+
+    fn next(&mut self) -> Option<&'a Type> {
+        match self {
+            ItemTypes::Array(_, 0) => None,
+            ItemTypes::Array(inner, count) => {
+                *count -= 1;
+                Some(inner)
+            }
+            ItemTypes::Tuple(items) => items.next(),
+            ItemTypes::Struct(fields) => fields.next().map(|(_, typ)| typ),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = match self {
+            ItemTypes::Array(_, count) => *count,
+            ItemTypes::Tuple(items) => items.len(),
+            ItemTypes::Struct(fields) => fields.len(),
+        };
+
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for ItemTypes<'_> {}
+
+/// Return the item types of a type that is encoded as a sequence of values.
+fn item_types(typ: &Type) -> Option<ItemTypes<'_>> {
+    match typ {
+        Type::Array { inner, dimension } => Some(ItemTypes::Array(inner, *dimension as usize)),
+        Type::Tuple(items) => Some(ItemTypes::Tuple(items.iter())),
+        Type::Struct { fields, .. } => Some(ItemTypes::Struct(fields.iter())),
+        _ => None,
+    }
+}
+
+/// Return the number of bytes that a value of type `typ` occupies in the head
+/// of an encoded sequence.  Dynamic values occupy a single word holding the
+/// offset of their encoding in the tail.  Fails if the size does not fit in a
+/// `usize`.
+pub fn head_size(typ: &Type) -> Result<usize, AbiError> {
+    if typ.is_dynamic() {
+        return Ok(WORD_SIZE);
+    }
+
+    match typ {
+        Type::Array { inner, dimension } => head_size(inner)?
+            .checked_mul(*dimension as usize)
+            .ok_or_else(|| AbiError::new("type too large")),
+        _ => match item_types(typ) {
+            Some(items) => sequence_head_size(items),
+            None => Ok(WORD_SIZE),
+        },
+    }
+}
+
+/// Return the total head size of a sequence of values with the given types.
+fn sequence_head_size<'a>(mut types: impl Iterator<Item = &'a Type>) -> Result<usize, AbiError> {
+    types.try_fold(0usize, |size, typ| {
+        size.checked_add(head_size(typ)?)
+            .ok_or_else(|| AbiError::new("type too large"))
+    })
+}
+
+/// Return the smallest and largest values of a signed integer with the given
+/// number of bits.
+pub fn int_bounds(bits: usize) -> (BigInt, BigInt) {
+    let max = (BigInt::one() << (bits - 1)) - 1;
+    (-&max - 1, max)
+}
+
+/// Return the smallest and largest decimals as integers scaled by 10^10.
+/// Decimals range over the values of an int128 with ten decimal places, so the
+/// scaled values take up to 168 bits.
+pub fn decimal_bounds() -> (BigInt, BigInt) {
+    let scale = num_traits::pow(BigInt::from(10), DECIMAL_PLACES);
+    let (min, max) = int_bounds(128);
+    (min * &scale, max * scale)
+}
+
+/// Encode the unsigned integer `value` as a word.
+fn uint_word(value: &BigUint) -> Result<Vec<u8>, AbiError> {
+    if value.bits() > 256 {
+        return Err(AbiError::new("value out of range"));
+    }
+
+    let bytes = value.to_bytes_be();
+    let mut word = vec![0; WORD_SIZE - bytes.len()];
+    word.extend(bytes);
+
+    Ok(word)
+}
+
+/// Encode the signed integer `value` between the bounds `(min, max)` as a
+/// word.
+fn int_word(value: &BigInt, (min, max): (BigInt, BigInt)) -> Result<Vec<u8>, AbiError> {
+    if *value > max || *value < min {
+        return Err(AbiError::new("value out of range"));
+    }
+
+    let bytes = value.to_signed_bytes_be();
+    let fill = if value.sign() == Sign::Minus { 0xff } else { 0 };
+    let mut word = vec![fill; WORD_SIZE - bytes.len()];
+    word.extend(bytes);
+
+    Ok(word)
+}
+
+/// Encode the length-prefixed byte sequence `bytes` padded to a multiple of
+/// the word size.
+fn encode_bytes(bytes: &[u8], max_size: u32) -> Result<Vec<u8>, AbiError> {
+    if bytes.len() > max_size as usize {
+        return Err(AbiError::new("value exceeds maximum size"));
+    }
+
+    let mut result = uint_word(&bytes.len().into())?;
+    result.extend_from_slice(bytes);
+    result.resize(
+        result.len() + (WORD_SIZE - bytes.len() % WORD_SIZE) % WORD_SIZE,
+        0,
+    );
+
+    Ok(result)
+}
+
+/// Encode a sequence of values with the given types.
+fn encode_sequence<'a>(
+    types: impl ExactSizeIterator<Item = &'a Type> + Clone,
+    values: &[Value],
+) -> Result<Vec<u8>, AbiError> {
+    if types.len() != values.len() {
+        return Err(AbiError::new("wrong number of values"));
+    }
+
+    let heads_size = sequence_head_size(types.clone())?;
+    let mut head = vec![];
+    let mut tail = vec![];
+
+    for (typ, value) in types.zip(values) {
+        let encoded = encode_value(typ, value)?;
+
+        if typ.is_dynamic() {
+            head.extend(uint_word(&(heads_size + tail.len()).into())?);
+            tail.extend(encoded);
+        } else {
+            head.extend(encoded);
+        }
+    }
+
+    head.extend(tail);
+    Ok(head)
+}
+
+/// Encode the value `value` of type `typ`.
+fn encode_value(typ: &Type, value: &Value) -> Result<Vec<u8>, AbiError> {
+    match (typ, value) {
+        (Type::Base(Base::Address), Value::Address(address)) => {
+            let mut word = vec![0; WORD_SIZE - address.len()];
+            word.extend_from_slice(address);
+            Ok(word)
+        }
+        (Type::Base(Base::Bool), Value::Bool(value)) => uint_word(&(*value as u8).into()),
+        (Type::Base(Base::Bytes32), Value::Bytes32(bytes)) => Ok(bytes.to_vec()),
+        (Type::Base(Base::Decimal), Value::Decimal(value)) => int_word(value, decimal_bounds()),
+        (Type::Base(Base::Int128), Value::Int(value)) => int_word(value, int_bounds(128)),
+        (Type::Base(Base::Uint256), Value::Uint(value)) => uint_word(value),
+        (Type::Bytes { max_size }, Value::Bytes(bytes)) => encode_bytes(bytes, *max_size),
+        (Type::String { max_size }, Value::String(string)) => {
+            encode_bytes(string.as_bytes(), *max_size)
+        }
+        (Type::Array { .. }, Value::Array(items))
+        | (Type::Tuple(_), Value::Tuple(items))
+        | (Type::Struct { .. }, Value::Tuple(items)) => {
+            encode_sequence(item_types(typ).unwrap(), items)
+        }
+        _ => Err(AbiError::new("value does not match type")),
+    }
+}
+
+/// ABI encode the values `values` with the types `types`, e.g. as the
+/// arguments of a function call or its return values.
+pub fn encode(types: &[Type], values: &[Value]) -> Result<Vec<u8>, AbiError> {
+    encode_sequence(types.iter(), values)
+}
+
+/// Return the word at byte offset `offset` in `data`.
+fn read_word(data: &[u8], offset: usize) -> Result<&[u8], AbiError> {
+    data.get(offset..offset + WORD_SIZE)
+        .ok_or_else(|| AbiError::new("data too short"))
+}
+
+/// Read an offset or length at byte offset `offset` in `data`.
+fn read_usize(data: &[u8], offset: usize) -> Result<usize, AbiError> {
+    BigUint::from_bytes_be(read_word(data, offset)?)
+        .to_usize()
+        .filter(|value| *value <= data.len())
+        .ok_or_else(|| AbiError::new("offset or length out of range"))
+}
+
+/// Decode a length-prefixed byte sequence padded to a multiple of the word
+/// size at the start of `data`.  The padding must be zero.
+fn decode_bytes(data: &[u8], max_size: u32) -> Result<Vec<u8>, AbiError> {
+    let len = read_usize(data, 0)?;
+    if len > max_size as usize {
+        return Err(AbiError::new("value exceeds maximum size"));
+    }
+
+    let padded = data
+        .get(WORD_SIZE..WORD_SIZE + len.div_ceil(WORD_SIZE) * WORD_SIZE)
+        .ok_or_else(|| AbiError::new("data too short"))?;
+    let (bytes, padding) = padded.split_at(len);
+    if padding.iter().any(|b| *b != 0) {
+        return Err(AbiError::new("non-zero padding"));
+    }
+
+    Ok(bytes.to_vec())
+}
+
+/// Decode a signed integer between the bounds `(min, max)` from the word
+/// `word`.
+fn decode_int(word: &[u8], (min, max): (BigInt, BigInt)) -> Result<BigInt, AbiError> {
+    let value = BigInt::from_signed_bytes_be(word);
+
+    if value > max || value < min {
+        return Err(AbiError::new("value out of range"));
+    }
+
+    Ok(value)
+}
+
+/// Decode a sequence of values with the given types from `data`, which starts
+/// at the beginning of the sequence's encoding.
+fn decode_sequence<'a>(
+    types: impl Iterator<Item = &'a Type>,
+    data: &[u8],
+) -> Result<Vec<Value>, AbiError> {
+    let mut offset = 0;
+    let mut values = vec![];
+
+    for typ in types {
+        if typ.is_dynamic() {
+            let start = read_usize(data, offset)?;
+            values.push(decode_value(typ, &data[start..])?);
+        } else {
+            let start = data
+                .get(offset..)
+                .ok_or_else(|| AbiError::new("data too short"))?;
+            values.push(decode_value(typ, start)?);
+        }
+
+        offset = offset
+            .checked_add(head_size(typ)?)
+            .ok_or_else(|| AbiError::new("type too large"))?;
+    }
+
+    Ok(values)
+}
+
+/// Decode a value of type `typ` from the start of `data`.
+fn decode_value(typ: &Type, data: &[u8]) -> Result<Value, AbiError> {
+    if let Some(items) = item_types(typ) {
+        let values = decode_sequence(items, data)?;
+
+        return Ok(match typ {
+            Type::Array { .. } => Value::Array(values),
+            _ => Value::Tuple(values),
+        });
+    }
+
+    match typ {
+        Type::Bytes { max_size } => return Ok(Value::Bytes(decode_bytes(data, *max_size)?)),
+        Type::String { max_size } => {
+            return String::from_utf8(decode_bytes(data, *max_size)?)
+                .map(Value::String)
+                .map_err(|_| AbiError::new("invalid UTF-8 string"));
+        }
+        _ => {}
+    }
+
+    let word = read_word(data, 0)?;
+    match typ {
+        Type::Base(Base::Address) => {
+            if word[..12].iter().any(|b| *b != 0) {
+                return Err(AbiError::new("value out of range"));
+            }
+
+            let mut address = [0; 20];
+            address.copy_from_slice(&word[12..]);
+            Ok(Value::Address(address))
+        }
+        Type::Base(Base::Bool) => match BigUint::from_bytes_be(word).to_u8() {
+            Some(0) => Ok(Value::Bool(false)),
+            Some(1) => Ok(Value::Bool(true)),
+            _ => Err(AbiError::new("value out of range")),
+        },
+        Type::Base(Base::Bytes32) => {
+            let mut bytes = [0; 32];
+            bytes.copy_from_slice(word);
+            Ok(Value::Bytes32(bytes))
+        }
+        Type::Base(Base::Decimal) => Ok(Value::Decimal(decode_int(word, decimal_bounds())?)),
+        Type::Base(Base::Int128) => Ok(Value::Int(decode_int(word, int_bounds(128))?)),
+        Type::Base(Base::Uint256) => Ok(Value::Uint(BigUint::from_bytes_be(word))),
+        _ => unreachable!(),
+    }
+}
+
+/// ABI decode values with the types `types` from `data`, e.g. the arguments of
+/// a function call or its return values.
+pub fn decode(types: &[Type], data: &[u8]) -> Result<Vec<Value>, AbiError> {
+    decode_sequence(types.iter(), data)
+}
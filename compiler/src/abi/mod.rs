@@ -0,0 +1,342 @@
+//! Generation of the JSON ABI of a contract.
+//!
+//! The ABI lists every item of a contract that is visible to callers, i.e.
+//! the constructor, external functions, getters of storage fields annotated as
+//! `public`, and events.
+
+use num_bigint::BigInt;
+use num_traits::pow;
+use serde::Serialize;
+
+use vyper_analyzer::const_eval::{
+    module_constants,
+    ConstEnv,
+};
+use vyper_analyzer::types::{
+    resolve_type_desc,
+    Base,
+    Type,
+};
+use vyper_parser::ast::{
+    EventField,
+    Module,
+    ModuleStmt,
+};
+use vyper_parser::span::{
+    Span,
+    Spanned,
+};
+
+use crate::abi::codec::{
+    encode,
+    Value,
+};
+use crate::errors::CompileError;
+use crate::functions::{
+    module_signatures,
+    resolve_signature,
+    Signature,
+    Visibility,
+    CONSTRUCTOR,
+};
+use crate::signatures::check_selectors;
+use crate::storage::{
+    resolve_storage_type,
+    StorageType,
+};
+
+pub mod codec;
+
+/// The number of decimal places of a `decimal`.
+pub(crate) const DECIMAL_PLACES: usize = 10;
+
+/// An entry of a contract ABI.
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum AbiItem {
+    Function(Function),
+    Constructor(Constructor),
+    Event(Event),
+}
+
+/// A public function or storage field getter.
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Function {
+    pub name: String,
+    pub inputs: Vec<Param>,
+    pub outputs: Vec<Param>,
+    pub state_mutability: StateMutability,
+    pub payable: bool,
+    /// True if the function does not modify state.  Kept for consumers of the
+    /// legacy ABI format.
+    pub constant: bool,
+}
+
+impl Function {
+    /// Return the canonical signature of a function, e.g.
+    /// `balanceOf(address)`.
+    pub fn signature(&self) -> String {
+        let types: Vec<_> = self.inputs.iter().map(|p| p.typ.as_str()).collect();
+
+        format!("{}({})", self.name, types.join(","))
+    }
+}
+
+/// The constructor of the contract.
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Constructor {
+    pub inputs: Vec<Param>,
+    pub state_mutability: StateMutability,
+    pub payable: bool,
+}
+
+/// The way in which a function may interact with the state of the chain.
+#[derive(Serialize, Debug, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum StateMutability {
+    Pure,
+    View,
+    Nonpayable,
+    Payable,
+}
+
+/// A function input or output.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Param {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub typ: String,
+}
+
+/// An event that may be logged by the contract.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Event {
+    pub name: String,
+    pub inputs: Vec<EventParam>,
+    pub anonymous: bool,
+}
+
+/// A field of an event.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct EventParam {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub typ: String,
+    pub indexed: bool,
+}
+
+/// Build the ABI entry for the event with the given name and fields.  Field
+/// types are resolved with the named constants in `env`.
+fn event(
+    name: &str,
+    fields: &[Spanned<EventField>],
+    env: &ConstEnv,
+) -> Result<Event, CompileError> {
+    let mut inputs = vec![];
+    for field in fields {
+        let typ = resolve_type_desc(&field.node.typ, env)?;
+
+        inputs.push(EventParam {
+            name: field.node.name.to_string(),
+            typ: typ.abi_name(),
+            indexed: field.node.typ.node.annotations.contains(&"indexed"),
+        });
+    }
+
+    Ok(Event {
+        name: name.to_string(),
+        inputs,
+        anonymous: false,
+    })
+}
+
+/// Build the ABI entry for the getter of a public field with the given name
+/// and storage type.  Getters of maps take one key per map from the outermost
+/// in.  Getters of arrays then take one `uint256` index per dimension from the
+/// outermost in and return a single item.
+pub fn getter(name: &str, typ: &StorageType) -> Function {
+    let mut inputs = vec![];
+    let mut value = typ;
+
+    let mut item = loop {
+        match value {
+            StorageType::Map { key, value: inner } => {
+                inputs.push(Param {
+                    name: format!("arg{}", inputs.len()),
+                    typ: key.abi_name(),
+                });
+                value = inner;
+            }
+            StorageType::Value(typ) => break typ,
+        }
+    };
+
+    while let Type::Array { inner, .. } = item {
+        inputs.push(Param {
+            name: format!("arg{}", inputs.len()),
+            typ: "uint256".to_string(),
+        });
+        item = inner;
+    }
+
+    Function {
+        name: name.to_string(),
+        inputs,
+        outputs: vec![Param {
+            name: "".to_string(),
+            typ: item.abi_name(),
+        }],
+        state_mutability: StateMutability::View,
+        payable: false,
+        constant: true,
+    }
+}
+
+/// Return the inputs of the function with the signature `sig`.
+fn inputs(sig: &Signature) -> Vec<Param> {
+    sig.params
+        .iter()
+        .map(|(name, typ)| Param {
+            name: name.clone(),
+            typ: typ.abi_name(),
+        })
+        .collect()
+}
+
+/// Build the ABI entry for the external function with the signature `sig`.
+pub fn function(sig: &Signature) -> Function {
+    Function {
+        name: sig.name.clone(),
+        inputs: inputs(sig),
+        outputs: sig
+            .return_type
+            .iter()
+            .map(|typ| Param {
+                name: "".to_string(),
+                typ: typ.abi_name(),
+            })
+            .collect(),
+        state_mutability: sig.mutability,
+        payable: sig.mutability == StateMutability::Payable,
+        constant: sig.is_view(),
+    }
+}
+
+/// Build the ABI entry for the constructor with the signature `sig`.
+fn constructor(sig: &Signature) -> Constructor {
+    Constructor {
+        inputs: inputs(sig),
+        state_mutability: sig.mutability,
+        payable: sig.mutability == StateMutability::Payable,
+    }
+}
+
+/// Return the value returned by the getter of the constant of type `typ` with
+/// the value `value` defined at `span`.  Decimal constants are integers scaled
+/// to the number of decimal places of a `decimal`.
+///
+/// Fails if the constant does not have a numeric type or if its value is out
+/// of bounds for its type.
+pub fn constant_value(typ: &Type, value: &BigInt, span: Span) -> Result<Value, CompileError> {
+    let out_of_bounds = || CompileError::new("constant value is out of bounds for its type", span);
+
+    let value = match typ {
+        Type::Base(Base::Int128) => Value::Int(value.clone()),
+        Type::Base(Base::Decimal) => Value::Decimal(value * pow(BigInt::from(10), DECIMAL_PLACES)),
+        Type::Base(Base::Uint256) => Value::Uint(value.to_biguint().ok_or_else(out_of_bounds)?),
+        _ => {
+            return Err(CompileError::new(
+                "public constants must have a numeric type",
+                span,
+            ))
+        }
+    };
+
+    match encode(std::slice::from_ref(typ), std::slice::from_ref(&value)) {
+        Ok(_) => Ok(value),
+        Err(_) => Err(out_of_bounds()),
+    }
+}
+
+/// Return the functions of the module `module` that are visible to callers
+/// with the spans of their definitions, in order of definition.  Types are
+/// resolved with the named constants in `env`.
+///
+/// Fails if the signature of a function cannot be resolved or if the selectors
+/// of two functions collide.
+pub fn public_functions(
+    module: &Module,
+    env: &ConstEnv,
+) -> Result<Vec<(Function, Span)>, CompileError> {
+    let mut functions = vec![];
+
+    for stmt in &module.body {
+        match &stmt.node {
+            ModuleStmt::FieldDef { name, typ, .. } if typ.node.annotations.contains(&"public") => {
+                let resolved = resolve_storage_type(typ, env)?;
+                functions.push((getter(name, &resolved), stmt.span));
+            }
+            ModuleStmt::FuncDef {
+                decorators,
+                name,
+                args,
+                return_type,
+                ..
+            } => {
+                let sig = resolve_signature(decorators, name, args, return_type, stmt.span, env)?;
+                if sig.visibility == Visibility::External && !sig.is_constructor() {
+                    functions.push((function(&sig), stmt.span));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let signatures: Vec<_> = functions
+        .iter()
+        .map(|(func, span)| (func.signature(), *span))
+        .collect();
+    check_selectors(&signatures)?;
+
+    Ok(functions)
+}
+
+/// Build the ABI of the module `module`.  Items are listed in the order in
+/// which they are defined.
+pub fn build_abi(module: &Module) -> Result<Vec<AbiItem>, CompileError> {
+    let env = module_constants(module)?;
+    let signatures = module_signatures(module, &env)?;
+    let mut functions = public_functions(module, &env)?.into_iter().peekable();
+    let mut abi = vec![];
+
+    for stmt in &module.body {
+        match &stmt.node {
+            ModuleStmt::EventDef { name, fields } => {
+                abi.push(AbiItem::Event(event(name, fields, &env)?));
+            }
+            ModuleStmt::FuncDef { name, .. } if *name == CONSTRUCTOR => {
+                let sig = signatures
+                    .iter()
+                    .find(|sig| sig.is_constructor())
+                    .expect("signatures are resolved");
+                abi.push(AbiItem::Constructor(constructor(sig)));
+            }
+            ModuleStmt::FieldDef { .. } | ModuleStmt::FuncDef { .. } => {
+                if let Some((func, _)) = functions.next_if(|(_, span)| *span == stmt.span) {
+                    abi.push(AbiItem::Function(func));
+                }
+            }
+        }
+    }
+
+    Ok(abi)
+}
+
+/// Build the ABI of the module `module` as pretty-printed JSON.
+pub fn build_abi_json(module: &Module) -> Result<String, CompileError> {
+    let abi = build_abi(module)?;
+
+    Ok(serde_json::to_string_pretty(&abi).expect("ABI serialization failed"))
+}
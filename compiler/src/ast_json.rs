@@ -0,0 +1,375 @@
+//! Export of the AST as versioned JSON for external tools.
+//!
+//! Unlike the serde representation of the AST, which follows its Rust types,
+//! the JSON AST is a stable format described by the JSON Schema in
+//! `compiler/schemas/ast.schema.json`.  Changes to the format bump
+//! `AST_JSON_VERSION` following semantic versioning.
+//!
+//! The output is an object holding the format version, the path of the source
+//! file and the root `Module` node.  Every node is an object with the fields:
+//!
+//! * `id` -- an integer that is unique within the output.  It is the `NodeId`
+//!   of the node, so ids are not contiguous and tools should not rely on their
+//!   order.
+//! * `nodeType` -- the kind of the node such as `EventDef`.
+//! * `src` -- the byte offsets of the start and end of the node.
+//! * `loc` -- the 1-indexed line and column in characters of the start and end
+//!   of the node.
+//!
+//! The remaining fields depend on the kind of node.
+
+use serde_json::{
+    json,
+    Map,
+    Value,
+};
+
+use vyper_parser::ast::{
+    ConstExpr,
+    EventField,
+    Expr,
+    FuncArg,
+    FuncStmt,
+    Module,
+    ModuleStmt,
+    TypeDesc,
+};
+use vyper_parser::line_index::{
+    ColumnUnit,
+    LineIndex,
+};
+use vyper_parser::span::Spanned;
+use vyper_parser::unparse::{
+    binary_op_string,
+    bool_op_string,
+    comp_op_string,
+    unary_op_string,
+};
+
+/// The version of the JSON AST format.
+pub const AST_JSON_VERSION: &str = "2.5.0";
+
+/// Builds JSON nodes for the AST of a single source file.
+pub struct AstJsonBuilder {
+    line_index: LineIndex,
+}
+
+impl AstJsonBuilder {
+    pub fn new(source: &str) -> Self {
+        AstJsonBuilder {
+            line_index: LineIndex::new(source),
+        }
+    }
+
+    fn position(&self, offset: usize) -> Value {
+        let pos = self
+            .line_index
+            .line_col(offset, ColumnUnit::Char)
+            .unwrap_or_default();
+
+        json!({"line": pos.line + 1, "column": pos.col + 1})
+    }
+
+    /// Return a JSON node of type `node_type` for the AST node `spanned` with
+    /// the fields in `fields`.
+    fn node<T>(&self, spanned: &Spanned<T>, node_type: &str, fields: Value) -> Value {
+        let span = spanned.span;
+        let mut node = Map::new();
+        node.insert("id".to_string(), json!(spanned.id));
+        node.insert("nodeType".to_string(), json!(node_type));
+        node.insert(
+            "src".to_string(),
+            json!({"start": span.start, "end": span.end}),
+        );
+        node.insert(
+            "loc".to_string(),
+            json!({"start": self.position(span.start), "end": self.position(span.end)}),
+        );
+        if let Value::Object(fields) = fields {
+            node.extend(fields);
+        }
+
+        Value::Object(node)
+    }
+
+    pub fn module(&self, module: &Spanned<Module>) -> Value {
+        let body: Vec<_> = module
+            .node
+            .body
+            .iter()
+            .map(|stmt| self.module_stmt(stmt))
+            .collect();
+
+        self.node(module, "Module", json!({ "body": body }))
+    }
+
+    pub fn module_stmt(&self, stmt: &Spanned<ModuleStmt>) -> Value {
+        match &stmt.node {
+            ModuleStmt::EventDef { name, fields } => {
+                let fields: Vec<_> = fields.iter().map(|f| self.event_field(f)).collect();
+
+                self.node(stmt, "EventDef", json!({"name": name, "fields": fields}))
+            }
+            ModuleStmt::FieldDef { name, typ, value } => {
+                let typ = self.type_desc(typ);
+                let value = value.as_ref().map(|value| self.const_expr(value));
+
+                self.node(
+                    stmt,
+                    "FieldDef",
+                    json!({"name": name, "typeDesc": typ, "value": value}),
+                )
+            }
+            ModuleStmt::FuncDef {
+                decorators,
+                name,
+                args,
+                return_type,
+                body,
+            } => {
+                let decorators: Vec<_> = decorators.iter().map(|dec| dec.node).collect();
+                let args: Vec<_> = args.iter().map(|arg| self.func_arg(arg)).collect();
+                let return_type = return_type.as_ref().map(|typ| self.type_desc(typ));
+
+                self.node(
+                    stmt,
+                    "FuncDef",
+                    json!({
+                        "name": name,
+                        "decorators": decorators,
+                        "args": args,
+                        "returnType": return_type,
+                        "body": self.func_stmts(body),
+                    }),
+                )
+            }
+        }
+    }
+
+    pub fn func_arg(&self, arg: &Spanned<FuncArg>) -> Value {
+        let typ = self.type_desc(&arg.node.typ);
+
+        self.node(
+            arg,
+            "FuncArg",
+            json!({"name": arg.node.name, "typeDesc": typ}),
+        )
+    }
+
+    fn func_stmts(&self, body: &[Spanned<FuncStmt>]) -> Vec<Value> {
+        body.iter().map(|stmt| self.func_stmt(stmt)).collect()
+    }
+
+    fn opt_expr(&self, expr: &Option<Spanned<Expr>>) -> Value {
+        json!(expr.as_ref().map(|expr| self.expr(expr)))
+    }
+
+    pub fn func_stmt(&self, stmt: &Spanned<FuncStmt>) -> Value {
+        match &stmt.node {
+            FuncStmt::Return { value } => {
+                self.node(stmt, "Return", json!({ "value": self.opt_expr(value) }))
+            }
+            FuncStmt::VarDecl { name, typ, value } => self.node(
+                stmt,
+                "VarDecl",
+                json!({
+                    "name": name,
+                    "typeDesc": self.type_desc(typ),
+                    "value": self.opt_expr(value),
+                }),
+            ),
+            FuncStmt::Assign { target, value } => self.node(
+                stmt,
+                "Assign",
+                json!({"target": self.expr(target), "value": self.expr(value)}),
+            ),
+            FuncStmt::AugAssign { target, op, value } => self.node(
+                stmt,
+                "AugAssign",
+                json!({
+                    "target": self.expr(target),
+                    "operator": binary_op_string(op),
+                    "value": self.expr(value),
+                }),
+            ),
+            FuncStmt::For { target, iter, body } => self.node(
+                stmt,
+                "For",
+                json!({
+                    "target": target,
+                    "iter": self.expr(iter),
+                    "body": self.func_stmts(body),
+                }),
+            ),
+            FuncStmt::If {
+                test,
+                body,
+                or_else,
+            } => self.node(
+                stmt,
+                "If",
+                json!({
+                    "test": self.expr(test),
+                    "body": self.func_stmts(body),
+                    "orElse": self.func_stmts(or_else),
+                }),
+            ),
+            FuncStmt::Assert { test, msg } => self.node(
+                stmt,
+                "Assert",
+                json!({"test": self.expr(test), "msg": self.opt_expr(msg)}),
+            ),
+            FuncStmt::Raise { msg } => {
+                self.node(stmt, "Raise", json!({ "msg": self.opt_expr(msg) }))
+            }
+            FuncStmt::Log { event, args } => {
+                let args: Vec<_> = args.iter().map(|arg| self.expr(arg)).collect();
+
+                self.node(stmt, "Log", json!({"event": event, "args": args}))
+            }
+            FuncStmt::Expr { value } => {
+                self.node(stmt, "ExprStmt", json!({ "value": self.expr(value) }))
+            }
+            FuncStmt::Pass => self.node(stmt, "Pass", json!({})),
+            FuncStmt::Break => self.node(stmt, "Break", json!({})),
+            FuncStmt::Continue => self.node(stmt, "Continue", json!({})),
+        }
+    }
+
+    pub fn event_field(&self, field: &Spanned<EventField>) -> Value {
+        let typ = self.type_desc(&field.node.typ);
+
+        self.node(
+            field,
+            "EventField",
+            json!({"name": field.node.name, "typeDesc": typ}),
+        )
+    }
+
+    pub fn type_desc(&self, typ: &Spanned<TypeDesc>) -> Value {
+        let dimensions: Vec<_> = typ
+            .node
+            .dimensions
+            .iter()
+            .map(|dim| self.const_expr(dim))
+            .collect();
+        let params: Vec<_> = typ
+            .node
+            .params
+            .iter()
+            .map(|param| self.type_desc(param))
+            .collect();
+
+        self.node(
+            typ,
+            "TypeDesc",
+            json!({
+                "base": typ.node.base,
+                "dimensions": dimensions,
+                "annotations": typ.node.annotations,
+                "params": params,
+            }),
+        )
+    }
+
+    pub fn const_expr(&self, expr: &Spanned<ConstExpr>) -> Value {
+        match &expr.node {
+            ConstExpr::BinOp { left, op, right } => {
+                let left = self.const_expr(left);
+                let right = self.const_expr(right);
+
+                self.node(
+                    expr,
+                    "BinOp",
+                    json!({"left": left, "operator": binary_op_string(op), "right": right}),
+                )
+            }
+            ConstExpr::UnaryOp { op, operand } => {
+                let operand = self.const_expr(operand);
+
+                self.node(
+                    expr,
+                    "UnaryOp",
+                    json!({"operator": unary_op_string(op), "operand": operand}),
+                )
+            }
+            ConstExpr::Name { name } => self.node(expr, "Name", json!({ "name": name })),
+            ConstExpr::Num { num } => self.node(expr, "Num", json!({ "value": num })),
+            ConstExpr::Call { func, args } => {
+                let args: Vec<_> = args.iter().map(|arg| self.const_expr(arg)).collect();
+
+                self.node(expr, "Call", json!({"func": func, "args": args}))
+            }
+        }
+    }
+
+    /// Return the JSON node of an expression in a function body.  Node types
+    /// shared with constant expressions have the same fields.
+    pub fn expr(&self, expr: &Spanned<Expr>) -> Value {
+        match &expr.node {
+            Expr::BoolOp { left, op, right } => self.node(
+                expr,
+                "BoolOp",
+                json!({
+                    "left": self.expr(left),
+                    "operator": bool_op_string(op),
+                    "right": self.expr(right),
+                }),
+            ),
+            Expr::BinOp { left, op, right } => self.node(
+                expr,
+                "BinOp",
+                json!({
+                    "left": self.expr(left),
+                    "operator": binary_op_string(op),
+                    "right": self.expr(right),
+                }),
+            ),
+            Expr::UnaryOp { op, operand } => self.node(
+                expr,
+                "UnaryOp",
+                json!({"operator": unary_op_string(op), "operand": self.expr(operand)}),
+            ),
+            Expr::CompOp { left, op, right } => self.node(
+                expr,
+                "Compare",
+                json!({
+                    "left": self.expr(left),
+                    "operator": comp_op_string(op),
+                    "right": self.expr(right),
+                }),
+            ),
+            Expr::Call { func, args } => {
+                let args: Vec<_> = args.iter().map(|arg| self.expr(arg)).collect();
+
+                self.node(expr, "Call", json!({"func": self.expr(func), "args": args}))
+            }
+            Expr::Attribute { value, attr } => self.node(
+                expr,
+                "Attribute",
+                json!({"value": self.expr(value), "attr": attr}),
+            ),
+            Expr::Subscript { value, index } => self.node(
+                expr,
+                "Subscript",
+                json!({"value": self.expr(value), "index": self.expr(index)}),
+            ),
+            Expr::Name { name } => self.node(expr, "Name", json!({ "name": name })),
+            Expr::Num { num } => self.node(expr, "Num", json!({ "value": num })),
+            Expr::Str { string } => self.node(expr, "Str", json!({ "value": string })),
+        }
+    }
+}
+
+/// Return the JSON AST of the module `module` parsed from `source`, which was
+/// loaded from `path`.
+pub fn build_ast_json(path: &str, source: &str, module: &Spanned<Module>) -> String {
+    let root = AstJsonBuilder::new(source).module(module);
+    let ast = json!({
+        "version": AST_JSON_VERSION,
+        "sourcePath": path,
+        "root": root,
+    });
+
+    serde_json::to_string_pretty(&ast).expect("AST serialization failed")
+}
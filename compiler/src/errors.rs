@@ -0,0 +1,70 @@
+use vyper_analyzer::errors::SemanticError;
+use vyper_parser::span::Span;
+
+/// An error encountered while compiling a parsed module.
+#[derive(Debug, PartialEq)]
+pub struct CompileError {
+    /// A description of the error.
+    pub msg: String,
+    /// The span of source text at which the error was found.
+    pub span: Span,
+    /// Secondary spans related to the error, e.g. an earlier definition that
+    /// conflicts with the one at `span`.
+    pub labels: Vec<Label>,
+}
+
+/// A span of source text related to an error with a description of its role.
+#[derive(Debug, PartialEq)]
+pub struct Label {
+    pub msg: String,
+    pub span: Span,
+}
+
+impl CompileError {
+    pub fn new(msg: impl Into<String>, span: Span) -> Self {
+        CompileError {
+            msg: msg.into(),
+            span,
+            labels: vec![],
+        }
+    }
+
+    /// Attach a secondary label described by `msg` at `span` to the error.
+    pub fn with_label(mut self, msg: impl Into<String>, span: Span) -> Self {
+        self.labels.push(Label {
+            msg: msg.into(),
+            span,
+        });
+        self
+    }
+}
+
+impl From<SemanticError> for CompileError {
+    fn from(err: SemanticError) -> Self {
+        CompileError::new(err.msg, err.span)
+    }
+}
+
+/// An error encountered while ABI encoding or decoding values.
+#[derive(Debug, PartialEq)]
+pub struct AbiError {
+    /// A description of the error.
+    pub msg: &'static str,
+}
+
+impl AbiError {
+    pub fn new(msg: &'static str) -> Self {
+        AbiError { msg }
+    }
+}
+
+/// An error encountered while executing a contract with the reference
+/// interpreter.
+#[derive(Debug, PartialEq, Clone)]
+pub enum RuntimeError {
+    /// Execution reverted, e.g. on overflow or a failed `assert`.  Reverts
+    /// caused by `assert` or `raise` may carry a reason.
+    Revert(Option<String>),
+    /// The operation is not defined for the given values or types.
+    Invalid(&'static str),
+}
@@ -0,0 +1,1302 @@
+//! Semantic analysis of function definitions.
+//!
+//! The signature of every function is resolved from its decorators and type
+//! descriptions, and its body is type checked.  The results are recorded in
+//! side tables keyed by node id, so that code generation and the reference
+//! interpreter agree on the meaning of every expression:
+//!
+//! * the type of every expression that has a value,
+//! * the value of every literal, constant, boolean and call to `empty` as the
+//!   integer held by its word, with decimals scaled,
+//! * the content of every string literal, and
+//! * the declared type of every local and loop variable.
+//!
+//! Function bodies may use values of base types, byte arrays and strings.
+//! Storage fields are accessed through `self`, including the items of maps
+//! and fixed-size lists.  Integer literals take the type expected by their
+//! context, or `int128` if it is not determined and the value fits, as with
+//! `range(10)`.  Arithmetic is defined for `uint256` and `int128` values and
+//! reverts on overflow.  Operands are evaluated from left to right.  Loops
+//! iterate over `range(stop)` or `range(start, stop)` a number of times known
+//! at compile-time, as computed by [`vyper_analyzer::bounds`].
+
+use std::collections::HashMap;
+
+use num_bigint::BigInt;
+use num_traits::{
+    One,
+    Zero,
+};
+
+use vyper_analyzer::bounds::{
+    const_expr,
+    range_iterations,
+};
+use vyper_analyzer::builtins::{
+    builtin,
+    check_call,
+    Arg,
+    ArgValue,
+    CallContext,
+};
+use vyper_analyzer::const_eval::{
+    is_constant,
+    parse_int_literal,
+    ConstEnv,
+};
+use vyper_analyzer::types::{
+    resolve_type_desc,
+    Base,
+    Type,
+};
+use vyper_parser::ast::{
+    CompOperator,
+    Expr,
+    FuncArg,
+    FuncStmt,
+    Module,
+    ModuleStmt,
+    Operator,
+    TypeDesc,
+    UnaryOp,
+};
+use vyper_parser::node_id::NodeMap;
+use vyper_parser::span::{
+    Span,
+    Spanned,
+};
+
+use crate::abi::codec::Value;
+use crate::abi::{
+    constant_value,
+    StateMutability,
+};
+use crate::errors::CompileError;
+use crate::signatures::signature;
+use crate::storage::{
+    StorageField,
+    StorageType,
+};
+
+/// The name of the constructor.
+pub const CONSTRUCTOR: &str = "__init__";
+
+/// The built-in functions that may be called in function bodies.
+const SUPPORTED_BUILTINS: &[&str] = &["empty", "len"];
+
+/// Names that refer to the environment and cannot be declared.
+const RESERVED_NAMES: &[&str] = &["block", "msg", "self", "True", "False"];
+
+/// Whether a function may be called by other contracts.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Visibility {
+    External,
+    Internal,
+}
+
+/// The resolved signature of a function definition.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Signature {
+    pub name: String,
+    pub params: Vec<(String, Type)>,
+    pub return_type: Option<Type>,
+    pub visibility: Visibility,
+    pub mutability: StateMutability,
+    pub span: Span,
+}
+
+impl Signature {
+    /// Return true if the function is the constructor.
+    pub fn is_constructor(&self) -> bool {
+        self.name == CONSTRUCTOR
+    }
+
+    /// Return true if the function may not modify state.
+    pub fn is_view(&self) -> bool {
+        matches!(
+            self.mutability,
+            StateMutability::View | StateMutability::Pure
+        )
+    }
+
+    /// Return the canonical signature of the function, e.g.
+    /// `transfer(address,uint256)`.
+    pub fn abi_signature(&self) -> String {
+        let types: Vec<_> = self.params.iter().map(|(_, typ)| typ.clone()).collect();
+
+        signature(&self.name, &types)
+    }
+}
+
+/// A function definition whose body has been checked.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CheckedFunction<'m, 'a> {
+    pub signature: Signature,
+    pub body: &'m [Spanned<FuncStmt<'a>>],
+}
+
+/// The function definitions of a module with the side tables computed by type
+/// checking their bodies.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Functions<'m, 'a> {
+    /// The functions in order of definition.
+    pub functions: Vec<CheckedFunction<'m, 'a>>,
+    /// The type of every expression that has a value.
+    pub types: NodeMap<Type>,
+    /// The word held by every literal, constant, boolean and call to `empty`
+    /// as an integer.  Decimals are scaled by their number of decimal places.
+    pub values: NodeMap<BigInt>,
+    /// The content of every string literal.
+    pub strings: NodeMap<Vec<u8>>,
+    /// The type of every local variable by the id of its declaration, and of
+    /// every loop variable by the id of its `for` statement.
+    pub locals: NodeMap<Type>,
+}
+
+impl<'m, 'a> Functions<'m, 'a> {
+    /// Return the function named `name`.
+    pub fn get(&self, name: &str) -> Option<&CheckedFunction<'m, 'a>> {
+        self.functions.iter().find(|f| f.signature.name == name)
+    }
+}
+
+/// Return true if values of the type `typ` may be held by arguments, return
+/// values and local variables.
+fn is_supported(typ: &Type) -> bool {
+    matches!(
+        typ,
+        Type::Base(_) | Type::Bytes { .. } | Type::String { .. }
+    )
+}
+
+/// Resolve the type description `desc` of an argument, return value or local
+/// variable with the named constants in `env`.
+fn resolve_value_type(desc: &Spanned<TypeDesc>, env: &ConstEnv) -> Result<Type, CompileError> {
+    let typ = resolve_type_desc(desc, env)?;
+
+    if is_supported(&typ) {
+        Ok(typ)
+    } else {
+        Err(CompileError::new(
+            "only base types, byte arrays and strings are supported in functions",
+            desc.span,
+        ))
+    }
+}
+
+/// Resolve the signature of the function with the given decorators, name,
+/// arguments and return type defined at `span`.  Types are resolved with the
+/// named constants in `env`.
+///
+/// Fails if the decorators do not give exactly one visibility and at most one
+/// state mutability, if two arguments have the same name, or if the
+/// constructor is not an external function that may modify state and returns
+/// nothing.
+pub fn resolve_signature(
+    decorators: &[Spanned<&str>],
+    name: &str,
+    args: &[Spanned<FuncArg>],
+    return_type: &Option<Spanned<TypeDesc>>,
+    span: Span,
+    env: &ConstEnv,
+) -> Result<Signature, CompileError> {
+    let mut visibility = None;
+    let mut mutability = None;
+
+    for decorator in decorators {
+        match decorator.node {
+            "external" | "internal" => {
+                let value = if decorator.node == "external" {
+                    Visibility::External
+                } else {
+                    Visibility::Internal
+                };
+                if visibility.replace(value).is_some() {
+                    return Err(CompileError::new(
+                        "functions may have only one visibility",
+                        decorator.span,
+                    ));
+                }
+            }
+            "view" | "pure" | "payable" | "nonpayable" => {
+                let value = match decorator.node {
+                    "view" => StateMutability::View,
+                    "pure" => StateMutability::Pure,
+                    "payable" => StateMutability::Payable,
+                    _ => StateMutability::Nonpayable,
+                };
+                if mutability.replace(value).is_some() {
+                    return Err(CompileError::new(
+                        "functions may have at most one state mutability",
+                        decorator.span,
+                    ));
+                }
+            }
+            _ => return Err(CompileError::new("unknown decorator", decorator.span)),
+        }
+    }
+
+    let visibility = visibility.ok_or_else(|| {
+        CompileError::new(
+            "functions must be decorated with @external or @internal",
+            span,
+        )
+    })?;
+    let mutability = mutability.unwrap_or(StateMutability::Nonpayable);
+
+    let mut params: Vec<(String, Type)> = vec![];
+    for arg in args {
+        if params.iter().any(|(name, _)| name == arg.node.name) {
+            return Err(CompileError::new("duplicate argument", arg.span));
+        }
+        if RESERVED_NAMES.contains(&arg.node.name) {
+            return Err(CompileError::new("argument name is reserved", arg.span));
+        }
+        params.push((
+            arg.node.name.to_string(),
+            resolve_value_type(&arg.node.typ, env)?,
+        ));
+    }
+
+    let return_type = match return_type {
+        Some(desc) => Some(resolve_value_type(desc, env)?),
+        None => None,
+    };
+
+    let sig = Signature {
+        name: name.to_string(),
+        params,
+        return_type,
+        visibility,
+        mutability,
+        span,
+    };
+
+    if sig.is_constructor() {
+        if sig.visibility != Visibility::External {
+            return Err(CompileError::new("the constructor must be external", span));
+        }
+        if sig.is_view() {
+            return Err(CompileError::new(
+                "the constructor cannot be view or pure",
+                span,
+            ));
+        }
+        if sig.return_type.is_some() {
+            return Err(CompileError::new(
+                "the constructor cannot return a value",
+                span,
+            ));
+        }
+    }
+
+    Ok(sig)
+}
+
+/// Return the signatures of the functions defined in the module `module` in
+/// order of definition.  Types are resolved with the named constants in `env`.
+///
+/// Fails if a signature cannot be resolved or if two functions have the same
+/// name.
+pub fn module_signatures(module: &Module, env: &ConstEnv) -> Result<Vec<Signature>, CompileError> {
+    let mut signatures: Vec<Signature> = vec![];
+
+    for stmt in &module.body {
+        if let ModuleStmt::FuncDef {
+            decorators,
+            name,
+            args,
+            return_type,
+            ..
+        } = &stmt.node
+        {
+            if let Some(prev) = signatures.iter().find(|sig| sig.name == *name) {
+                return Err(
+                    CompileError::new("duplicate function definition", stmt.span)
+                        .with_label("first defined here", prev.span),
+                );
+            }
+            signatures.push(resolve_signature(
+                decorators,
+                name,
+                args,
+                return_type,
+                stmt.span,
+                env,
+            )?);
+        }
+    }
+
+    Ok(signatures)
+}
+
+/// Return true if executing the statements `body` never continues after the
+/// last of them, i.e. if every path ends with `return` or `raise`.
+fn terminates(body: &[Spanned<FuncStmt>]) -> bool {
+    match body.last().map(|stmt| &stmt.node) {
+        Some(FuncStmt::Return { .. }) | Some(FuncStmt::Raise { .. }) => true,
+        Some(FuncStmt::If { body, or_else, .. }) => terminates(body) && terminates(or_else),
+        _ => false,
+    }
+}
+
+/// Return the value of the bytes or string literal written as `raw` in source
+/// code, including any prefix and its quotes, and whether it is a bytes
+/// literal.  Returns `None` if the literal contains an unsupported escape
+/// sequence or prefix.
+fn string_literal(raw: &str) -> Option<(bool, Vec<u8>)> {
+    let quote = raw.find(['"', '\''])?;
+    let prefix = raw[..quote].to_lowercase();
+    if prefix.contains('f') {
+        return None;
+    }
+    let is_bytes = prefix.contains('b');
+    let is_raw = prefix.contains('r');
+
+    let quoted = &raw[quote..];
+    let delimiter = if quoted.starts_with("\"\"\"") || quoted.starts_with("'''") {
+        &quoted[..3]
+    } else {
+        &quoted[..1]
+    };
+    let content = quoted.strip_prefix(delimiter)?.strip_suffix(delimiter)?;
+
+    if is_raw {
+        return (!is_bytes || content.is_ascii()).then(|| (is_bytes, content.as_bytes().to_vec()));
+    }
+
+    let mut value = vec![];
+    let mut chars = content.chars();
+    while let Some(c) = chars.next() {
+        let c = match c {
+            '\\' => match chars.next()? {
+                '\\' => '\\',
+                '\'' => '\'',
+                '"' => '"',
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                '0' => '\0',
+                'x' => {
+                    let digits: String = chars.by_ref().take(2).collect();
+                    let byte = u8::from_str_radix(&digits, 16)
+                        .ok()
+                        .filter(|_| digits.len() == 2)?;
+                    if is_bytes {
+                        value.push(byte);
+                        continue;
+                    }
+                    char::from(byte)
+                }
+                _ => return None,
+            },
+            c if is_bytes && !c.is_ascii() => return None,
+            c => c,
+        };
+        let mut buf = [0; 4];
+        value.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+    }
+
+    Some((is_bytes, value))
+}
+
+/// Return true if a value of the type `from` may be given where a value of the
+/// type `to` is expected.  Byte arrays and strings fit into larger ones.
+pub fn is_assignable(from: &Type, to: &Type) -> bool {
+    match (from, to) {
+        (Type::Bytes { max_size: from }, Type::Bytes { max_size: to })
+        | (Type::String { max_size: from }, Type::String { max_size: to }) => from <= to,
+        _ => from == to,
+    }
+}
+
+/// Return true if the expression `expr` is an integer literal, possibly
+/// negated.
+fn is_int_literal(expr: &Spanned<Expr>) -> bool {
+    match &expr.node {
+        Expr::Num { .. } => true,
+        Expr::UnaryOp {
+            op: UnaryOp::USub,
+            operand,
+        } => matches!(operand.node, Expr::Num { .. }),
+        _ => false,
+    }
+}
+
+/// Return true if `value` lies within the range of the integer type `base`.
+fn int_fits(value: &BigInt, base: Base) -> bool {
+    let (min, max) = match base {
+        Base::Int128 => (-(BigInt::one() << 127), (BigInt::one() << 127) - 1),
+        Base::Uint256 => (BigInt::zero(), (BigInt::one() << 256) - 1),
+        _ => return false,
+    };
+
+    *value >= min && *value <= max
+}
+
+/// Return the word held by the constant value `value` as an integer.
+fn value_int(value: Value) -> BigInt {
+    match value {
+        Value::Int(value) | Value::Decimal(value) => value,
+        Value::Uint(value) => value.into(),
+        _ => unreachable!("constants have numeric types"),
+    }
+}
+
+/// The way in which a local name may be used.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum LocalKind {
+    Variable,
+    Argument,
+    LoopVariable,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+struct Local {
+    typ: Type,
+    kind: LocalKind,
+}
+
+struct Checker<'c, 'm, 'a> {
+    env: &'c ConstEnv<'a>,
+    constants: HashMap<&'a str, (Type, Span)>,
+    fields: HashMap<&'c str, &'c StorageType>,
+    events: HashMap<&'a str, Vec<Type>>,
+    signatures: &'c [Signature],
+    result: Functions<'m, 'a>,
+    /// The signature of the function being checked.
+    current: Option<Signature>,
+    scopes: Vec<HashMap<&'a str, Local>>,
+    loop_depth: usize,
+}
+
+impl<'c, 'm, 'a> Checker<'c, 'm, 'a> {
+    fn current(&self) -> &Signature {
+        self.current.as_ref().expect("no function is being checked")
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Local> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    /// Declare the local name `name` at `span` in the innermost scope.
+    fn declare(
+        &mut self,
+        name: &'a str,
+        typ: Type,
+        kind: LocalKind,
+        span: Span,
+    ) -> Result<(), CompileError> {
+        if RESERVED_NAMES.contains(&name) {
+            return Err(CompileError::new("variable name is reserved", span));
+        }
+        if self.constants.contains_key(name) {
+            return Err(CompileError::new("local variable shadows a constant", span));
+        }
+        if self.lookup(name).is_some() {
+            return Err(CompileError::new(
+                "local variable is already declared",
+                span,
+            ));
+        }
+
+        self.scopes
+            .last_mut()
+            .expect("locals are declared in a scope")
+            .insert(name, Local { typ, kind });
+        Ok(())
+    }
+
+    /// Fail if the function being checked may not read state.
+    fn check_reads_state(&self, span: Span) -> Result<(), CompileError> {
+        if self.current().mutability == StateMutability::Pure {
+            return Err(CompileError::new(
+                "pure functions cannot read the state or the environment",
+                span,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Fail if the function being checked may not modify state.
+    fn check_modifies_state(&self, span: Span) -> Result<(), CompileError> {
+        if self.current().is_view() {
+            return Err(CompileError::new(
+                "view and pure functions cannot modify state",
+                span,
+            ));
+        }
+        Ok(())
+    }
+
+    fn record(&mut self, expr: &Spanned<Expr>, typ: Type) -> Type {
+        self.result.types.insert(expr.id, typ.clone());
+        typ
+    }
+
+    /// Check the expression `expr` and require a value of the type `typ`.
+    fn expect(&mut self, expr: &Spanned<Expr<'a>>, typ: &Type) -> Result<(), CompileError> {
+        let actual = self.expr(expr, Some(typ))?;
+
+        if is_assignable(&actual, typ) {
+            Ok(())
+        } else {
+            Err(CompileError::new(
+                format!(
+                    "expected a value of type {}, found {}",
+                    typ.name(),
+                    actual.name()
+                ),
+                expr.span,
+            ))
+        }
+    }
+
+    /// Check the integer literal `expr` with the value `value`.  The literal
+    /// takes the type `expected` if it is numeric.
+    fn int_literal(
+        &mut self,
+        expr: &Spanned<Expr>,
+        value: BigInt,
+        expected: Option<&Type>,
+    ) -> Result<Type, CompileError> {
+        let out_of_bounds =
+            || CompileError::new("integer literal is out of bounds for its type", expr.span);
+
+        let (base, word) = match expected {
+            Some(Type::Base(Base::Decimal)) => {
+                if !int_fits(&value, Base::Int128) {
+                    return Err(out_of_bounds());
+                }
+                let scaled = value_int(
+                    constant_value(&Type::Base(Base::Decimal), &value, expr.span)
+                        .map_err(|_| out_of_bounds())?,
+                );
+                (Base::Decimal, scaled)
+            }
+            Some(Type::Base(base @ Base::Int128)) | Some(Type::Base(base @ Base::Uint256)) => {
+                if !int_fits(&value, *base) {
+                    return Err(out_of_bounds());
+                }
+                (*base, value)
+            }
+            _ if int_fits(&value, Base::Int128) => (Base::Int128, value),
+            _ if int_fits(&value, Base::Uint256) => (Base::Uint256, value),
+            _ => return Err(out_of_bounds()),
+        };
+
+        self.result.values.insert(expr.id, word);
+        Ok(self.record(expr, Type::Base(base)))
+    }
+
+    /// Check the operands of a binary operation or comparison.  An integer
+    /// literal takes the type of the other operand.  Returns the common type of
+    /// the operands.
+    fn operands(
+        &mut self,
+        left: &Spanned<Expr<'a>>,
+        right: &Spanned<Expr<'a>>,
+        expected: Option<&Type>,
+    ) -> Result<Type, CompileError> {
+        let (left_type, right_type) = if is_int_literal(left) && !is_int_literal(right) {
+            let right_type = self.expr(right, expected)?;
+            (self.expr(left, Some(&right_type))?, right_type)
+        } else {
+            let left_type = self.expr(left, expected)?;
+            (left_type.clone(), self.expr(right, Some(&left_type))?)
+        };
+
+        if left_type != right_type {
+            return Err(CompileError::new(
+                "operands must have the same type",
+                (&left.span, &right.span).into(),
+            ));
+        }
+
+        Ok(left_type)
+    }
+
+    /// Return the base type of the operands of an arithmetic operation on
+    /// values of the type `typ`, or fail if it is not defined.
+    fn arithmetic_type(typ: &Type, op: Operator, span: Span) -> Result<Base, CompileError> {
+        match (typ, op) {
+            (Type::Base(Base::Decimal), _) => Err(CompileError::new(
+                "arithmetic on decimal values is not supported yet",
+                span,
+            )),
+            (Type::Base(base @ Base::Uint256), _) => Ok(*base),
+            (Type::Base(base @ Base::Int128), _)
+                if !matches!(
+                    op,
+                    Operator::BitAnd
+                        | Operator::BitOr
+                        | Operator::BitXor
+                        | Operator::LShift
+                        | Operator::RShift
+                ) =>
+            {
+                Ok(*base)
+            }
+            _ => Err(CompileError::new(
+                format!("operator is not defined for type {}", typ.name()),
+                span,
+            )),
+        }
+    }
+
+    /// Check the binary operation `left op right` and return the type of its
+    /// result.
+    fn bin_op(
+        &mut self,
+        left: &Spanned<Expr<'a>>,
+        op: Operator,
+        right: &Spanned<Expr<'a>>,
+        expected: Option<&Type>,
+        span: Span,
+    ) -> Result<Type, CompileError> {
+        let typ = match op {
+            Operator::LShift | Operator::RShift => {
+                let typ = self.expr(left, expected)?;
+                self.expect(right, &Type::Base(Base::Uint256))?;
+                typ
+            }
+            _ => self.operands(left, right, expected)?,
+        };
+
+        Checker::arithmetic_type(&typ, op, span)?;
+        Ok(typ)
+    }
+
+    /// Check a reference to storage and return the type of the value it
+    /// refers to, or `None` if `expr` does not refer to storage.  Storage is
+    /// referred to by `self.field` followed by map keys and array indexes.
+    fn storage_ref(
+        &mut self,
+        expr: &Spanned<Expr<'a>>,
+    ) -> Result<Option<StorageType>, CompileError> {
+        match &expr.node {
+            Expr::Attribute { value, attr }
+                if matches!(value.node, Expr::Name { name: "self" }) =>
+            {
+                match self.fields.get(attr) {
+                    Some(typ) => Ok(Some((*typ).clone())),
+                    None if self.signatures.iter().any(|sig| sig.name == *attr) => {
+                        Err(CompileError::new("functions must be called", expr.span))
+                    }
+                    None => Err(CompileError::new("unknown storage field", expr.span)),
+                }
+            }
+            Expr::Subscript { value, index } => {
+                let typ = match self.storage_ref(value)? {
+                    Some(typ) => typ,
+                    None => return Ok(None),
+                };
+
+                match typ {
+                    StorageType::Map { key, value } => {
+                        self.expect(index, &key)?;
+                        Ok(Some(*value))
+                    }
+                    StorageType::Value(Type::Array { inner, .. }) => {
+                        let index_type = self.expr(index, Some(&Type::Base(Base::Uint256)))?;
+                        if !matches!(index_type, Type::Base(Base::Uint256 | Base::Int128)) {
+                            return Err(CompileError::new(
+                                "array indexes must be integers",
+                                index.span,
+                            ));
+                        }
+                        Ok(Some(StorageType::Value(*inner)))
+                    }
+                    StorageType::Value(_) => {
+                        Err(CompileError::new("value cannot be indexed", value.span))
+                    }
+                }
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Check a read of storage and return the type of the value read, or
+    /// `None` if `expr` does not refer to storage.
+    fn storage_read(&mut self, expr: &Spanned<Expr<'a>>) -> Result<Option<Type>, CompileError> {
+        let typ = match self.storage_ref(expr)? {
+            Some(StorageType::Value(typ)) if is_supported(&typ) => typ,
+            Some(StorageType::Value(_)) => {
+                return Err(CompileError::new(
+                    "arrays cannot be used as values yet",
+                    expr.span,
+                ))
+            }
+            Some(StorageType::Map { .. }) => {
+                return Err(CompileError::new(
+                    "maps cannot be used as values",
+                    expr.span,
+                ))
+            }
+            None => return Ok(None),
+        };
+        self.check_reads_state(expr.span)?;
+
+        Ok(Some(typ))
+    }
+
+    /// Check the call `expr` and return the type of its result, or `None` if
+    /// it does not return a value.
+    fn call(&mut self, expr: &Spanned<Expr<'a>>) -> Result<Option<Type>, CompileError> {
+        let (func, args) = match &expr.node {
+            Expr::Call { func, args } => (func, args),
+            _ => unreachable!("expression is a call"),
+        };
+
+        match &func.node {
+            Expr::Attribute { value, attr }
+                if matches!(value.node, Expr::Name { name: "self" }) =>
+            {
+                let sig = match self.signatures.iter().find(|sig| sig.name == *attr) {
+                    Some(sig) => sig.clone(),
+                    None => return Err(CompileError::new("unknown function", func.span)),
+                };
+                if sig.visibility != Visibility::Internal {
+                    return Err(CompileError::new(
+                        "only internal functions can be called from the contract",
+                        func.span,
+                    ));
+                }
+                if self.current().is_constructor() {
+                    return Err(CompileError::new(
+                        "the constructor cannot call internal functions",
+                        func.span,
+                    ));
+                }
+                match (self.current().mutability, sig.mutability) {
+                    (StateMutability::View, StateMutability::Nonpayable)
+                    | (StateMutability::View, StateMutability::Payable)
+                    | (StateMutability::Pure, StateMutability::Nonpayable)
+                    | (StateMutability::Pure, StateMutability::Payable) => {
+                        return Err(CompileError::new(
+                            "view and pure functions cannot call functions that modify state",
+                            func.span,
+                        ))
+                    }
+                    (StateMutability::Pure, StateMutability::View) => {
+                        return Err(CompileError::new(
+                            "pure functions cannot call view functions",
+                            func.span,
+                        ))
+                    }
+                    _ => {}
+                }
+                if args.len() != sig.params.len() {
+                    return Err(CompileError::new("wrong number of arguments", expr.span));
+                }
+                for (arg, (_, typ)) in args.iter().zip(&sig.params) {
+                    self.expect(arg, typ)?;
+                }
+
+                Ok(sig.return_type)
+            }
+            Expr::Name { name } => {
+                if builtin(name).is_none() {
+                    let msg = if self.signatures.iter().any(|sig| sig.name == *name) {
+                        "internal functions are called as attributes of self"
+                    } else {
+                        "unknown function"
+                    };
+                    return Err(CompileError::new(msg, func.span));
+                }
+                if !SUPPORTED_BUILTINS.contains(name) {
+                    return Err(CompileError::new(
+                        "built-in function is not supported in functions yet",
+                        func.span,
+                    ));
+                }
+
+                let mut builtin_args = vec![];
+                for arg in args {
+                    let value = match &arg.node {
+                        Expr::Name { name }
+                            if self.lookup(name).is_none()
+                                && !self.constants.contains_key(name) =>
+                        {
+                            match Base::from_name(name) {
+                                Some(base) => ArgValue::TypeName(Type::Base(base)),
+                                None => ArgValue::Expr(self.expr(arg, None)?),
+                            }
+                        }
+                        _ => ArgValue::Expr(self.expr(arg, None)?),
+                    };
+                    builtin_args.push(Arg {
+                        value,
+                        span: arg.span,
+                    });
+                }
+
+                let context = if self.current().is_view() {
+                    CallContext::View
+                } else {
+                    CallContext::Mutable
+                };
+                let typ = check_call(expr.span, name, &builtin_args, &[], context)?;
+
+                if *name == "empty" {
+                    self.result.values.insert(expr.id, BigInt::zero());
+                }
+                Ok(typ)
+            }
+            _ => Err(CompileError::new(
+                "only internal and built-in functions can be called",
+                func.span,
+            )),
+        }
+    }
+
+    /// Check the expression `expr` and return its type.  Integer literals
+    /// take the type `expected` if it is numeric.
+    fn expr(
+        &mut self,
+        expr: &Spanned<Expr<'a>>,
+        expected: Option<&Type>,
+    ) -> Result<Type, CompileError> {
+        let bool_type = Type::Base(Base::Bool);
+
+        let typ = match &expr.node {
+            Expr::Num { num } => {
+                if num.contains('.') {
+                    return Err(CompileError::new(
+                        "decimal literals are not supported yet",
+                        expr.span,
+                    ));
+                }
+                let value = parse_int_literal(num)
+                    .ok_or_else(|| CompileError::new("invalid number literal", expr.span))?;
+
+                return self.int_literal(expr, value, expected);
+            }
+            Expr::Str { string } => {
+                let (is_bytes, value) = string_literal(string)
+                    .ok_or_else(|| CompileError::new("unsupported string literal", expr.span))?;
+                let max_size = value.len() as u32;
+
+                self.result.strings.insert(expr.id, value);
+                if is_bytes {
+                    Type::Bytes { max_size }
+                } else {
+                    Type::String { max_size }
+                }
+            }
+            Expr::Name { name } => match *name {
+                "True" | "False" => {
+                    let value = if *name == "True" { 1 } else { 0 };
+                    self.result.values.insert(expr.id, BigInt::from(value));
+                    bool_type
+                }
+                "self" => {
+                    self.check_reads_state(expr.span)?;
+                    Type::Base(Base::Address)
+                }
+                _ => match (self.lookup(name), self.constants.get(name)) {
+                    (Some(local), _) => local.typ.clone(),
+                    (None, Some((typ, span))) => {
+                        let typ = typ.clone();
+                        let value = constant_value(&typ, &self.env[name], *span)?;
+
+                        self.result.values.insert(expr.id, value_int(value));
+                        typ
+                    }
+                    (None, None) => return Err(CompileError::new("unknown name", expr.span)),
+                },
+            },
+            Expr::Attribute { value, attr } => {
+                let env_name = match &value.node {
+                    Expr::Name { name } if *name == "msg" || *name == "block" => Some(*name),
+                    _ => None,
+                };
+
+                match (env_name, *attr) {
+                    (Some("msg"), "sender") => {
+                        self.check_reads_state(expr.span)?;
+                        Type::Base(Base::Address)
+                    }
+                    (Some("msg"), "value")
+                    | (Some("block"), "timestamp")
+                    | (Some("block"), "number") => {
+                        self.check_reads_state(expr.span)?;
+                        Type::Base(Base::Uint256)
+                    }
+                    (Some(_), _) => {
+                        return Err(CompileError::new("unknown environment variable", expr.span))
+                    }
+                    (None, _) => match self.storage_read(expr)? {
+                        Some(typ) => typ,
+                        None => return Err(CompileError::new("unknown attribute", expr.span)),
+                    },
+                }
+            }
+            Expr::Subscript { value, .. } => match self.storage_read(expr)? {
+                Some(typ) => typ,
+                None => {
+                    return Err(CompileError::new(
+                        "only storage values can be indexed",
+                        value.span,
+                    ))
+                }
+            },
+            Expr::Call { .. } => match self.call(expr)? {
+                Some(typ) => typ,
+                None => {
+                    return Err(CompileError::new(
+                        "function does not return a value",
+                        expr.span,
+                    ))
+                }
+            },
+            Expr::BinOp { left, op, right } => {
+                self.bin_op(left, *op, right, expected, expr.span)?
+            }
+            Expr::CompOp { left, op, right } => {
+                let typ = self.operands(left, right, None)?;
+
+                match (op, &typ) {
+                    (CompOperator::Eq, Type::Base(_)) | (CompOperator::NotEq, Type::Base(_)) => {}
+                    (_, Type::Base(Base::Uint256 | Base::Int128 | Base::Decimal)) => {}
+                    _ => {
+                        return Err(CompileError::new(
+                            format!("comparison is not defined for type {}", typ.name()),
+                            expr.span,
+                        ))
+                    }
+                }
+                bool_type
+            }
+            Expr::BoolOp { left, right, .. } => {
+                self.expect(left, &bool_type)?;
+                self.expect(right, &bool_type)?;
+                bool_type
+            }
+            Expr::UnaryOp { op, operand } => match op {
+                UnaryOp::USub if matches!(operand.node, Expr::Num { .. }) => {
+                    let num = match &operand.node {
+                        Expr::Num { num } => *num,
+                        _ => unreachable!(),
+                    };
+                    if num.contains('.') {
+                        return Err(CompileError::new(
+                            "decimal literals are not supported yet",
+                            operand.span,
+                        ));
+                    }
+                    let value = parse_int_literal(num)
+                        .ok_or_else(|| CompileError::new("invalid number literal", operand.span))?;
+
+                    return self.int_literal(expr, -value, expected);
+                }
+                UnaryOp::Not => {
+                    self.expect(operand, &bool_type)?;
+                    bool_type
+                }
+                UnaryOp::USub | UnaryOp::UAdd => {
+                    let typ = self.expr(operand, expected)?;
+                    match (op, &typ) {
+                        (UnaryOp::USub, Type::Base(Base::Int128))
+                        | (UnaryOp::UAdd, Type::Base(Base::Int128 | Base::Uint256)) => {}
+                        _ => {
+                            return Err(CompileError::new(
+                                format!("operator is not defined for type {}", typ.name()),
+                                expr.span,
+                            ))
+                        }
+                    }
+                    typ
+                }
+                UnaryOp::Invert => {
+                    let typ = Type::Base(Base::Uint256);
+                    self.expect(operand, &typ)?;
+                    typ
+                }
+            },
+        };
+
+        Ok(self.record(expr, typ))
+    }
+
+    /// Check the target of an assignment and return the type of the value it
+    /// holds.
+    fn target(&mut self, target: &Spanned<Expr<'a>>) -> Result<Type, CompileError> {
+        if let Expr::Name { name } = &target.node {
+            return match self.lookup(name) {
+                Some(Local {
+                    typ,
+                    kind: LocalKind::Variable,
+                }) => Ok(typ.clone()),
+                Some(Local {
+                    kind: LocalKind::Argument,
+                    ..
+                }) => Err(CompileError::new(
+                    "function arguments cannot be modified",
+                    target.span,
+                )),
+                Some(Local {
+                    kind: LocalKind::LoopVariable,
+                    ..
+                }) => Err(CompileError::new(
+                    "loop variables cannot be modified",
+                    target.span,
+                )),
+                None => Err(CompileError::new("unknown name", target.span)),
+            };
+        }
+
+        match self.storage_ref(target)? {
+            Some(StorageType::Value(typ)) if is_supported(&typ) => {
+                self.check_modifies_state(target.span)?;
+                Ok(typ)
+            }
+            Some(_) => Err(CompileError::new(
+                "only values of base types, byte arrays and strings can be assigned",
+                target.span,
+            )),
+            None => Err(CompileError::new("invalid assignment target", target.span)),
+        }
+    }
+
+    fn block(&mut self, body: &[Spanned<FuncStmt<'a>>]) -> Result<(), CompileError> {
+        self.scopes.push(HashMap::new());
+        for stmt in body {
+            self.stmt(stmt)?;
+        }
+        self.scopes.pop();
+
+        Ok(())
+    }
+
+    fn stmt(&mut self, stmt: &Spanned<FuncStmt<'a>>) -> Result<(), CompileError> {
+        let bool_type = Type::Base(Base::Bool);
+
+        match &stmt.node {
+            FuncStmt::Return { value } => match (value, self.current().return_type.clone()) {
+                (Some(value), Some(typ)) => self.expect(value, &typ)?,
+                (None, None) => {}
+                (Some(value), None) => {
+                    return Err(CompileError::new(
+                        "function does not return a value",
+                        value.span,
+                    ))
+                }
+                (None, Some(_)) => {
+                    return Err(CompileError::new("missing return value", stmt.span))
+                }
+            },
+            FuncStmt::VarDecl { name, typ, value } => {
+                let typ = resolve_value_type(typ, self.env)?;
+                if let Some(value) = value {
+                    self.expect(value, &typ)?;
+                }
+
+                self.result.locals.insert(stmt.id, typ.clone());
+                self.declare(name, typ, LocalKind::Variable, stmt.span)?;
+            }
+            FuncStmt::Assign { target, value } => {
+                let typ = self.target(target)?;
+                self.expect(value, &typ)?;
+            }
+            FuncStmt::AugAssign { target, op, value } => {
+                let typ = self.target(target)?;
+                match op {
+                    Operator::LShift | Operator::RShift => {
+                        self.expect(value, &Type::Base(Base::Uint256))?
+                    }
+                    _ => self.expect(value, &typ)?,
+                }
+                Checker::arithmetic_type(&typ, *op, stmt.span)?;
+            }
+            FuncStmt::For { target, iter, body } => {
+                let args = match &iter.node {
+                    Expr::Call { func, args }
+                        if matches!(func.node, Expr::Name { name: "range" })
+                            && (args.len() == 1 || args.len() == 2) =>
+                    {
+                        args
+                    }
+                    _ => {
+                        return Err(CompileError::new(
+                            "only loops over range() are supported",
+                            iter.span,
+                        ))
+                    }
+                };
+
+                let typ = match args.as_slice() {
+                    [stop] => self.expr(stop, None)?,
+                    [start, stop] => self.operands(start, stop, None)?,
+                    _ => unreachable!("range takes one or two arguments"),
+                };
+                if !matches!(typ, Type::Base(Base::Uint256 | Base::Int128)) {
+                    return Err(CompileError::new(
+                        "range bounds must be integers",
+                        iter.span,
+                    ));
+                }
+                // Loops must iterate a number of times known at compile-time
+                let range_args = args.iter().map(const_expr).collect::<Result<Vec<_>, _>>()?;
+                range_iterations(iter.span, &range_args, self.env)?;
+
+                self.result.locals.insert(stmt.id, typ.clone());
+                self.scopes.push(HashMap::new());
+                self.declare(target, typ, LocalKind::LoopVariable, stmt.span)?;
+                self.loop_depth += 1;
+                self.block(body)?;
+                self.loop_depth -= 1;
+                self.scopes.pop();
+            }
+            FuncStmt::If {
+                test,
+                body,
+                or_else,
+            } => {
+                self.expect(test, &bool_type)?;
+                self.block(body)?;
+                self.block(or_else)?;
+            }
+            FuncStmt::Assert { test, msg } => {
+                self.expect(test, &bool_type)?;
+                if let Some(msg) = msg {
+                    self.reason(msg)?;
+                }
+            }
+            FuncStmt::Raise { msg } => {
+                if let Some(msg) = msg {
+                    self.reason(msg)?;
+                }
+            }
+            FuncStmt::Log { event, args } => {
+                self.check_modifies_state(stmt.span)?;
+
+                let types = match self.events.get(event) {
+                    Some(types) => types.clone(),
+                    None => return Err(CompileError::new("unknown event", stmt.span)),
+                };
+                if args.len() != types.len() {
+                    return Err(CompileError::new("wrong number of event fields", stmt.span));
+                }
+                for (arg, typ) in args.iter().zip(&types) {
+                    self.expect(arg, typ)?;
+                }
+            }
+            FuncStmt::Expr { value } => match &value.node {
+                Expr::Call { .. } => {
+                    if let Some(typ) = self.call(value)? {
+                        self.record(value, typ);
+                    }
+                }
+                _ => {
+                    return Err(CompileError::new(
+                        "expression statements must be calls",
+                        value.span,
+                    ))
+                }
+            },
+            FuncStmt::Pass => {}
+            FuncStmt::Break | FuncStmt::Continue => {
+                if self.loop_depth == 0 {
+                    return Err(CompileError::new(
+                        "break and continue must be inside a loop",
+                        stmt.span,
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check the reason of an `assert` or `raise` statement.
+    fn reason(&mut self, msg: &Spanned<Expr<'a>>) -> Result<(), CompileError> {
+        match self.expr(msg, None)? {
+            Type::String { .. } if matches!(msg.node, Expr::Str { .. }) => Ok(()),
+            _ => Err(CompileError::new(
+                "reasons must be string literals",
+                msg.span,
+            )),
+        }
+    }
+}
+
+/// Check the function definitions of the module `module` with the storage
+/// fields `fields`.  Types are resolved with the named constants in `env`.
+///
+/// Fails if a signature cannot be resolved or if a function body uses a
+/// name, type or operation incorrectly or in a way that is not supported.
+pub fn check_functions<'m, 'a>(
+    module: &'m Module<'a>,
+    env: &ConstEnv<'a>,
+    fields: &[StorageField],
+) -> Result<Functions<'m, 'a>, CompileError> {
+    let signatures = module_signatures(module, env)?;
+    let mut constants = HashMap::new();
+    let mut events = HashMap::new();
+
+    for stmt in &module.body {
+        match &stmt.node {
+            ModuleStmt::FieldDef { name, typ, .. } if is_constant(&typ.node) => {
+                constants.insert(*name, (resolve_type_desc(typ, env)?, stmt.span));
+            }
+            ModuleStmt::EventDef { name, fields } => {
+                let types = fields
+                    .iter()
+                    .map(|field| resolve_type_desc(&field.node.typ, env))
+                    .collect::<Result<_, _>>()?;
+                events.insert(*name, types);
+            }
+            _ => {}
+        }
+    }
+
+    let mut checker = Checker {
+        env,
+        constants,
+        fields: fields
+            .iter()
+            .map(|field| (field.name.as_str(), &field.typ))
+            .collect(),
+        events,
+        signatures: &signatures,
+        result: Functions {
+            functions: vec![],
+            types: NodeMap::new(),
+            values: NodeMap::new(),
+            strings: NodeMap::new(),
+            locals: NodeMap::new(),
+        },
+        current: None,
+        scopes: vec![],
+        loop_depth: 0,
+    };
+
+    let defs = module.body.iter().filter_map(|stmt| match &stmt.node {
+        ModuleStmt::FuncDef { args, body, .. } => Some((args, body)),
+        _ => None,
+    });
+
+    for (sig, (args, body)) in signatures.iter().zip(defs) {
+        checker.current = Some(sig.clone());
+        checker.scopes = vec![HashMap::new()];
+        for (arg, (_, typ)) in args.iter().zip(&sig.params) {
+            checker.declare(arg.node.name, typ.clone(), LocalKind::Argument, arg.span)?;
+        }
+
+        checker.block(body)?;
+        if sig.return_type.is_some() && !terminates(body) {
+            return Err(CompileError::new(
+                "function is missing a return statement",
+                sig.span,
+            ));
+        }
+
+        checker.result.functions.push(CheckedFunction {
+            signature: sig.clone(),
+            body,
+        });
+    }
+
+    Ok(checker.result)
+}
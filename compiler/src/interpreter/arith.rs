@@ -0,0 +1,169 @@
+//! Arithmetic on values of numeric types with Vyper's overflow semantics.
+//!
+//! Any result outside the range of its type reverts, as does division by zero.
+//! Division truncates towards zero and the result of the modulo operation has
+//! the sign of the dividend.  Decimals are integers scaled by 10^10, so their
+//! products and quotients are truncated towards zero to ten decimal places.
+
+use std::cmp::Ordering;
+
+use num_bigint::BigInt;
+use num_traits::{
+    One,
+    Signed,
+    ToPrimitive,
+    Zero,
+};
+
+use vyper_analyzer::types::Base;
+
+use crate::abi::codec::{
+    decimal_bounds,
+    int_bounds,
+    Value,
+};
+use crate::errors::RuntimeError;
+
+/// The factor by which decimals are scaled.
+pub const DECIMAL_SCALE: u64 = 10_000_000_000;
+
+/// Return the smallest and largest values of the numeric type `typ`.
+fn bounds(typ: Base) -> Result<(BigInt, BigInt), RuntimeError> {
+    match typ {
+        Base::Uint256 => Ok((BigInt::zero(), (BigInt::one() << 256) - 1)),
+        Base::Int128 => Ok(int_bounds(128)),
+        Base::Decimal => Ok(decimal_bounds()),
+        _ => Err(RuntimeError::Invalid("arithmetic is not defined for type")),
+    }
+}
+
+/// Return the integer held by the value `value` of the numeric type `typ`.
+/// Decimals are returned scaled.
+fn to_int(typ: Base, value: &Value) -> Result<BigInt, RuntimeError> {
+    match (typ, value) {
+        (Base::Uint256, Value::Uint(value)) => Ok(BigInt::from(value.clone())),
+        (Base::Int128, Value::Int(value)) | (Base::Decimal, Value::Decimal(value)) => {
+            Ok(value.clone())
+        }
+        _ => Err(RuntimeError::Invalid("value does not match type")),
+    }
+}
+
+/// Return the value of the numeric type `typ` holding the integer `value`, or
+/// revert if it is out of range.
+fn from_int(typ: Base, value: BigInt) -> Result<Value, RuntimeError> {
+    let (min, max) = bounds(typ)?;
+    if value < min || value > max {
+        return Err(RuntimeError::Revert(None));
+    }
+
+    Ok(match typ {
+        Base::Uint256 => Value::Uint(value.to_biguint().expect("value is not negative")),
+        Base::Int128 => Value::Int(value),
+        _ => Value::Decimal(value),
+    })
+}
+
+/// Apply the operation `op` to the integers held by `a` and `b`.
+fn binary_op(
+    typ: Base,
+    a: &Value,
+    b: &Value,
+    op: impl Fn(BigInt, BigInt) -> Result<BigInt, RuntimeError>,
+) -> Result<Value, RuntimeError> {
+    bounds(typ)?;
+
+    from_int(typ, op(to_int(typ, a)?, to_int(typ, b)?)?)
+}
+
+/// Revert if `divisor` is zero.
+fn check_divisor(divisor: &BigInt) -> Result<(), RuntimeError> {
+    if divisor.is_zero() {
+        Err(RuntimeError::Revert(None))
+    } else {
+        Ok(())
+    }
+}
+
+pub fn add(typ: Base, a: &Value, b: &Value) -> Result<Value, RuntimeError> {
+    binary_op(typ, a, b, |a, b| Ok(a + b))
+}
+
+pub fn sub(typ: Base, a: &Value, b: &Value) -> Result<Value, RuntimeError> {
+    binary_op(typ, a, b, |a, b| Ok(a - b))
+}
+
+pub fn mul(typ: Base, a: &Value, b: &Value) -> Result<Value, RuntimeError> {
+    binary_op(typ, a, b, |a, b| match typ {
+        Base::Decimal => Ok(a * b / DECIMAL_SCALE),
+        _ => Ok(a * b),
+    })
+}
+
+pub fn div(typ: Base, a: &Value, b: &Value) -> Result<Value, RuntimeError> {
+    binary_op(typ, a, b, |a, b| {
+        check_divisor(&b)?;
+
+        match typ {
+            Base::Decimal => Ok(a * DECIMAL_SCALE / b),
+            _ => Ok(a / b),
+        }
+    })
+}
+
+pub fn modulo(typ: Base, a: &Value, b: &Value) -> Result<Value, RuntimeError> {
+    binary_op(typ, a, b, |a, b| {
+        check_divisor(&b)?;
+
+        Ok(a % b)
+    })
+}
+
+/// Raise `base` to the power `exponent`.  Exponentiation is defined for
+/// integers only and negative exponents revert.
+pub fn pow(typ: Base, base: &Value, exponent: &Value) -> Result<Value, RuntimeError> {
+    if typ == Base::Decimal {
+        return Err(RuntimeError::Invalid(
+            "exponentiation is not defined for type",
+        ));
+    }
+
+    binary_op(typ, base, exponent, |base, exponent| {
+        if exponent.is_negative() {
+            return Err(RuntimeError::Revert(None));
+        }
+
+        // Powers of integers other than -1, 0 and 1 overflow for large
+        // exponents, so they need not be computed
+        if base.abs() <= BigInt::one() {
+            let even = (&exponent % 2u8).is_zero();
+
+            return Ok(if exponent.is_zero() || (base.is_negative() && even) {
+                BigInt::one()
+            } else {
+                base
+            });
+        }
+        match exponent.to_u32().filter(|exponent| *exponent <= 256) {
+            Some(exponent) => Ok(num_traits::pow(base, exponent as usize)),
+            None => Err(RuntimeError::Revert(None)),
+        }
+    })
+}
+
+/// Negate `value`.  Negating unsigned integers is not defined.
+pub fn neg(typ: Base, value: &Value) -> Result<Value, RuntimeError> {
+    if typ == Base::Uint256 {
+        return Err(RuntimeError::Invalid("negation is not defined for type"));
+    }
+    bounds(typ)?;
+
+    from_int(typ, -to_int(typ, value)?)
+}
+
+/// Compare the values `a` and `b` of the numeric type `typ`.
+pub fn compare(typ: Base, a: &Value, b: &Value) -> Result<Ordering, RuntimeError> {
+    bounds(typ)?;
+
+    Ok(to_int(typ, a)?.cmp(&to_int(typ, b)?))
+}
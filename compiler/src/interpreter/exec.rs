@@ -0,0 +1,635 @@
+//! Execution of function bodies.
+//!
+//! Local variables and arguments hold values of base types, byte arrays and
+//! strings.  Storage is accessed through the operations of the interpreter, so
+//! a reference such as `self.balances[owner]` loads the field `balances` under
+//! the key `owner`.  Operands, arguments and keys are evaluated from left to
+//! right, and the value assigned to storage before the keys of the target, as
+//! in compiled code.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use num_bigint::{
+    BigInt,
+    BigUint,
+};
+use num_traits::{
+    One,
+    ToPrimitive,
+    Zero,
+};
+
+use vyper_analyzer::types::{
+    Base,
+    Type,
+};
+use vyper_parser::ast::{
+    BoolOperator,
+    CompOperator,
+    Expr,
+    FuncStmt,
+    Operator,
+    UnaryOp,
+};
+use vyper_parser::span::Spanned;
+
+use crate::abi::codec::Value;
+use crate::abi::StateMutability;
+use crate::errors::RuntimeError;
+use crate::functions::{
+    Signature,
+    Visibility,
+};
+use crate::interpreter::{
+    arith,
+    slot_word,
+    value_word,
+    Context,
+    Interpreter,
+    Log,
+};
+use crate::storage::StorageType;
+
+/// The largest number of nested calls of internal functions.
+const MAX_CALL_DEPTH: usize = 1024;
+
+/// The way in which execution continues after a statement.
+enum Flow {
+    Next,
+    Break,
+    Continue,
+    Return(Option<Value>),
+}
+
+/// The state of a function being executed.
+struct Frame<'c> {
+    context: &'c Context,
+    scopes: Vec<HashMap<String, Value>>,
+    depth: usize,
+}
+
+impl<'c> Frame<'c> {
+    fn lookup(&mut self, name: &str) -> &mut Value {
+        self.scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.get_mut(name))
+            .expect("names are checked")
+    }
+
+    fn declare(&mut self, name: &str, value: Value) {
+        self.scopes
+            .last_mut()
+            .expect("locals are declared in a scope")
+            .insert(name.to_string(), value);
+    }
+}
+
+/// Return the value of the type `typ` held by the word `word`.
+fn word_value(typ: &Type, word: &BigInt) -> Value {
+    let bytes = || slot_word(&word.to_biguint().expect("word is not negative"));
+
+    match typ {
+        Type::Base(Base::Uint256) => Value::Uint(word.to_biguint().expect("word is not negative")),
+        Type::Base(Base::Int128) => Value::Int(word.clone()),
+        Type::Base(Base::Decimal) => Value::Decimal(word.clone()),
+        Type::Base(Base::Bool) => Value::Bool(!word.is_zero()),
+        Type::Base(Base::Address) => {
+            let mut address = [0; 20];
+            address.copy_from_slice(&bytes()[12..]);
+            Value::Address(address)
+        }
+        Type::Base(Base::Bytes32) => Value::Bytes32(bytes()),
+        // Only empty byte arrays and strings are given as words
+        Type::Bytes { .. } => Value::Bytes(vec![]),
+        Type::String { .. } => Value::String(String::new()),
+        _ => unreachable!("values in functions are checked"),
+    }
+}
+
+fn as_bool(value: &Value) -> Result<bool, RuntimeError> {
+    match value {
+        Value::Bool(value) => Ok(*value),
+        _ => Err(RuntimeError::Invalid("value does not match type")),
+    }
+}
+
+fn as_uint(value: &Value) -> Result<&BigUint, RuntimeError> {
+    match value {
+        Value::Uint(value) => Ok(value),
+        _ => Err(RuntimeError::Invalid("value does not match type")),
+    }
+}
+
+/// Apply the bitwise operation or shift `op` to the `uint256` values `a` and
+/// `b`.  Bits shifted beyond the width of a word are lost.
+fn bitwise(op: Operator, a: &Value, b: &Value) -> Result<Value, RuntimeError> {
+    let (a, b) = (as_uint(a)?, as_uint(b)?);
+    let shift = b.to_usize().filter(|shift| *shift < 256);
+
+    let result = match op {
+        Operator::BitAnd => a & b,
+        Operator::BitOr => a | b,
+        Operator::BitXor => a ^ b,
+        Operator::LShift => match shift {
+            Some(shift) => (a << shift) % (BigUint::one() << 256),
+            None => BigUint::zero(),
+        },
+        Operator::RShift => match shift {
+            Some(shift) => a >> shift,
+            None => BigUint::zero(),
+        },
+        _ => unreachable!("operator is bitwise"),
+    };
+
+    Ok(Value::Uint(result))
+}
+
+/// Apply the binary operation `op` to the values `a` and `b` of the type
+/// `base`.
+fn binary(op: Operator, base: Base, a: &Value, b: &Value) -> Result<Value, RuntimeError> {
+    match op {
+        Operator::Add => arith::add(base, a, b),
+        Operator::Sub => arith::sub(base, a, b),
+        Operator::Mult => arith::mul(base, a, b),
+        Operator::Div => arith::div(base, a, b),
+        Operator::Mod => arith::modulo(base, a, b),
+        Operator::Pow => arith::pow(base, a, b),
+        _ => bitwise(op, a, b),
+    }
+}
+
+/// Return the array index held by the value `value`.  Negative indexes are
+/// out of bounds.
+fn index(value: Value) -> Result<Value, RuntimeError> {
+    match value {
+        Value::Int(index) => match index.to_biguint() {
+            Some(index) => Ok(Value::Uint(index)),
+            None => Err(RuntimeError::Revert(None)),
+        },
+        _ => Ok(value),
+    }
+}
+
+/// Check that the value `value` may be passed as an argument of the type
+/// `typ`.  Byte arrays and strings longer than their maximum size revert, as
+/// they do when decoded from calldata.
+fn check_arg(typ: &Type, value: &Value) -> Result<(), RuntimeError> {
+    let len = match (typ, value) {
+        (Type::Bytes { max_size }, Value::Bytes(bytes)) => Some((bytes.len(), max_size)),
+        (Type::String { max_size }, Value::String(string)) => Some((string.len(), max_size)),
+        _ => None,
+    };
+
+    match len {
+        Some((len, max_size)) if len > *max_size as usize => Err(RuntimeError::Revert(None)),
+        Some(_) => Ok(()),
+        None => value_word(typ, value).map(|_| ()),
+    }
+}
+
+impl<'m, 'a> Interpreter<'m, 'a> {
+    /// Return the signatures of the functions of the contract in order of
+    /// definition.
+    pub fn signatures(&self) -> Vec<&Signature> {
+        self.functions
+            .functions
+            .iter()
+            .map(|function| &function.signature)
+            .collect()
+    }
+
+    /// Deploy the contract by calling its constructor, if any, with the
+    /// arguments `args` as a transaction.  Returns the logs emitted.
+    pub fn deploy(&mut self, context: &Context, args: &[Value]) -> Result<Vec<Log>, RuntimeError> {
+        let sig = match self
+            .signatures()
+            .into_iter()
+            .find(|sig| sig.is_constructor())
+        {
+            Some(sig) => sig.clone(),
+            None if args.is_empty() => return Ok(vec![]),
+            None => return Err(RuntimeError::Invalid("wrong number of arguments")),
+        };
+
+        self.invoke(context, &sig, args).map(|(_, logs)| logs)
+    }
+
+    /// Call the external function `name` with the arguments `args` as a
+    /// transaction.  Returns the value returned by the function, if any, and
+    /// the logs emitted.
+    pub fn call(
+        &mut self,
+        context: &Context,
+        name: &str,
+        args: &[Value],
+    ) -> Result<(Option<Value>, Vec<Log>), RuntimeError> {
+        let sig = match self.functions.get(name) {
+            Some(function)
+                if function.signature.visibility == Visibility::External
+                    && !function.signature.is_constructor() =>
+            {
+                function.signature.clone()
+            }
+            _ => return Err(RuntimeError::Invalid("unknown external function")),
+        };
+
+        self.invoke(context, &sig, args)
+    }
+
+    fn invoke(
+        &mut self,
+        context: &Context,
+        sig: &Signature,
+        args: &[Value],
+    ) -> Result<(Option<Value>, Vec<Log>), RuntimeError> {
+        if sig.mutability != StateMutability::Payable && !context.value.is_zero() {
+            return Err(RuntimeError::Revert(None));
+        }
+        if args.len() != sig.params.len() {
+            return Err(RuntimeError::Invalid("wrong number of arguments"));
+        }
+        for ((_, typ), arg) in sig.params.iter().zip(args) {
+            check_arg(typ, arg)?;
+        }
+
+        let mut result = None;
+        let logs = self.transact(|contract| {
+            result = contract.run(context, &sig.name, args.to_vec(), 0)?;
+            Ok(())
+        })?;
+
+        Ok((result, logs))
+    }
+
+    /// Execute the body of the function `name` with the arguments `args` and
+    /// return its result.
+    fn run(
+        &mut self,
+        context: &Context,
+        name: &str,
+        args: Vec<Value>,
+        depth: usize,
+    ) -> Result<Option<Value>, RuntimeError> {
+        if depth > MAX_CALL_DEPTH {
+            return Err(RuntimeError::Invalid("call depth exceeded"));
+        }
+
+        let function = self
+            .functions
+            .get(name)
+            .ok_or(RuntimeError::Invalid("unknown function"))?;
+        let body = function.body;
+        let return_type = function.signature.return_type.clone();
+        let scope = function
+            .signature
+            .params
+            .iter()
+            .map(|(name, _)| name.clone())
+            .zip(args)
+            .collect();
+
+        let mut frame = Frame {
+            context,
+            scopes: vec![scope],
+            depth,
+        };
+        match self.block(&mut frame, body)? {
+            Flow::Return(value) => Ok(value),
+            // Functions that return a value end with a return statement
+            _ => Ok(return_type.map(|typ| word_value(&typ, &BigInt::zero()))),
+        }
+    }
+
+    fn block(
+        &mut self,
+        frame: &mut Frame,
+        body: &'m [Spanned<FuncStmt<'a>>],
+    ) -> Result<Flow, RuntimeError> {
+        frame.scopes.push(HashMap::new());
+
+        let mut flow = Flow::Next;
+        for stmt in body {
+            flow = self.stmt(frame, stmt)?;
+            if !matches!(flow, Flow::Next) {
+                break;
+            }
+        }
+
+        frame.scopes.pop();
+        Ok(flow)
+    }
+
+    fn typ(&self, expr: &Spanned<Expr>) -> Type {
+        self.functions
+            .types
+            .get(&expr.id)
+            .cloned()
+            .expect("expressions are checked")
+    }
+
+    fn base(&self, expr: &Spanned<Expr>) -> Base {
+        match self.typ(expr) {
+            Type::Base(base) => base,
+            _ => unreachable!("operands have base types"),
+        }
+    }
+
+    /// Evaluate a reference to storage into the name of the field, the keys
+    /// under which the value is held and the type of the value.
+    fn storage_ref(
+        &mut self,
+        frame: &mut Frame,
+        expr: &'m Spanned<Expr<'a>>,
+    ) -> Result<(String, Vec<Value>, StorageType), RuntimeError> {
+        match &expr.node {
+            Expr::Attribute { attr, .. } => {
+                let field = self
+                    .fields
+                    .get(*attr)
+                    .ok_or(RuntimeError::Invalid("unknown storage field"))?;
+                Ok((attr.to_string(), vec![], field.typ.clone()))
+            }
+            Expr::Subscript { value, index: key } => {
+                let (name, mut keys, typ) = self.storage_ref(frame, value)?;
+                let key = self.expr(frame, key)?;
+
+                let typ = match typ {
+                    StorageType::Map { value, .. } => {
+                        keys.push(key);
+                        *value
+                    }
+                    StorageType::Value(Type::Array { inner, .. }) => {
+                        keys.push(index(key)?);
+                        StorageType::Value(*inner)
+                    }
+                    StorageType::Value(_) => unreachable!("indexed values are checked"),
+                };
+                Ok((name, keys, typ))
+            }
+            _ => unreachable!("storage references are checked"),
+        }
+    }
+
+    fn stmt(
+        &mut self,
+        frame: &mut Frame,
+        stmt: &'m Spanned<FuncStmt<'a>>,
+    ) -> Result<Flow, RuntimeError> {
+        match &stmt.node {
+            FuncStmt::Return { value } => {
+                let value = match value {
+                    Some(value) => Some(self.expr(frame, value)?),
+                    None => None,
+                };
+                return Ok(Flow::Return(value));
+            }
+            FuncStmt::VarDecl { name, value, .. } => {
+                let value = match value {
+                    Some(value) => self.expr(frame, value)?,
+                    None => word_value(&self.functions.locals[&stmt.id], &BigInt::zero()),
+                };
+                frame.declare(name, value);
+            }
+            FuncStmt::Assign { target, value } => {
+                let value = self.expr(frame, value)?;
+
+                match &target.node {
+                    Expr::Name { name } => *frame.lookup(name) = value,
+                    _ => {
+                        let (name, keys, _) = self.storage_ref(frame, target)?;
+                        self.store(&name, &keys, value)?;
+                    }
+                }
+            }
+            FuncStmt::AugAssign { target, op, value } => {
+                let base = match op {
+                    Operator::LShift | Operator::RShift => Base::Uint256,
+                    _ => self.base(value),
+                };
+
+                match &target.node {
+                    Expr::Name { name } => {
+                        let value = self.expr(frame, value)?;
+                        let current = frame.lookup(name);
+                        *current = binary(*op, base, current, &value)?;
+                    }
+                    _ => {
+                        let (name, keys, _) = self.storage_ref(frame, target)?;
+                        let current = self.load(&name, &keys)?;
+                        let value = self.expr(frame, value)?;
+                        self.store(&name, &keys, binary(*op, base, &current, &value)?)?;
+                    }
+                }
+            }
+            FuncStmt::For { target, iter, body } => {
+                let typ = self.functions.locals[&stmt.id].clone();
+                let base = match typ {
+                    Type::Base(base) => base,
+                    _ => unreachable!("loop variables are integers"),
+                };
+                let (start, stop) = match &iter.node {
+                    Expr::Call { args, .. } => match args.as_slice() {
+                        [stop] => (word_value(&typ, &BigInt::zero()), self.expr(frame, stop)?),
+                        [start, stop] => (self.expr(frame, start)?, self.expr(frame, stop)?),
+                        _ => unreachable!("loops are checked"),
+                    },
+                    _ => unreachable!("loops are checked"),
+                };
+
+                // The loop variable is less than the end of the range, so
+                // incrementing it cannot overflow
+                let one = word_value(&typ, &BigInt::one());
+                let mut i = start;
+                while arith::compare(base, &i, &stop)? == Ordering::Less {
+                    frame.scopes.push(HashMap::new());
+                    frame.declare(target, i.clone());
+                    let flow = self.block(frame, body)?;
+                    frame.scopes.pop();
+
+                    match flow {
+                        Flow::Break => break,
+                        Flow::Return(value) => return Ok(Flow::Return(value)),
+                        Flow::Next | Flow::Continue => {}
+                    }
+                    i = arith::add(base, &i, &one)?;
+                }
+            }
+            FuncStmt::If {
+                test,
+                body,
+                or_else,
+            } => {
+                let test = self.expr(frame, test)?;
+                let body = if as_bool(&test)? { body } else { or_else };
+
+                return self.block(frame, body);
+            }
+            FuncStmt::Assert { test, msg } => {
+                let test = self.expr(frame, test)?;
+                if !as_bool(&test)? {
+                    return Err(RuntimeError::Revert(self.reason(frame, msg)?));
+                }
+            }
+            FuncStmt::Raise { msg } => return Err(RuntimeError::Revert(self.reason(frame, msg)?)),
+            FuncStmt::Log { event, args } => {
+                let mut values = vec![];
+                for arg in args {
+                    values.push(self.expr(frame, arg)?);
+                }
+                self.emit(event, &values)?;
+            }
+            FuncStmt::Expr { value } => {
+                self.call_expr(frame, value)?;
+            }
+            FuncStmt::Pass => {}
+            FuncStmt::Break => return Ok(Flow::Break),
+            FuncStmt::Continue => return Ok(Flow::Continue),
+        }
+
+        Ok(Flow::Next)
+    }
+
+    /// Evaluate the reason of an `assert` or `raise` statement.
+    fn reason(
+        &mut self,
+        frame: &mut Frame,
+        msg: &'m Option<Spanned<Expr<'a>>>,
+    ) -> Result<Option<String>, RuntimeError> {
+        match msg {
+            Some(msg) => match self.expr(frame, msg)? {
+                Value::String(reason) => Ok(Some(reason)),
+                _ => unreachable!("reasons are checked"),
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Evaluate the call `expr` and return its result, if any.
+    fn call_expr(
+        &mut self,
+        frame: &mut Frame,
+        expr: &'m Spanned<Expr<'a>>,
+    ) -> Result<Option<Value>, RuntimeError> {
+        let (func, args) = match &expr.node {
+            Expr::Call { func, args } => (func, args),
+            _ => unreachable!("expression is a call"),
+        };
+
+        let mut values = vec![];
+        for arg in args {
+            values.push(self.expr(frame, arg)?);
+        }
+
+        match &func.node {
+            Expr::Attribute { attr, .. } => self.run(frame.context, attr, values, frame.depth + 1),
+            Expr::Name { name: "len" } => {
+                let len = match &values[0] {
+                    Value::Bytes(bytes) => bytes.len(),
+                    Value::String(string) => string.len(),
+                    _ => return Err(RuntimeError::Invalid("value does not match type")),
+                };
+                Ok(Some(word_value(&self.typ(expr), &BigInt::from(len))))
+            }
+            _ => unreachable!("calls are checked"),
+        }
+    }
+
+    fn expr(
+        &mut self,
+        frame: &mut Frame,
+        expr: &'m Spanned<Expr<'a>>,
+    ) -> Result<Value, RuntimeError> {
+        if let Some(word) = self.functions.values.get(&expr.id) {
+            return Ok(word_value(&self.typ(expr), word));
+        }
+        if let Some(content) = self.functions.strings.get(&expr.id) {
+            return Ok(match self.typ(expr) {
+                Type::Bytes { .. } => Value::Bytes(content.clone()),
+                _ => Value::String(
+                    String::from_utf8(content.clone()).expect("string literals are checked"),
+                ),
+            });
+        }
+
+        let value = match &expr.node {
+            Expr::Name { name: "self" } => Value::Address(frame.context.address),
+            Expr::Name { name } => frame.lookup(name).clone(),
+            Expr::Attribute { value, attr } => match (&value.node, *attr) {
+                (Expr::Name { name: "msg" }, "sender") => Value::Address(frame.context.sender),
+                (Expr::Name { name: "msg" }, "value") => Value::Uint(frame.context.value.clone()),
+                (Expr::Name { name: "block" }, "timestamp") => {
+                    Value::Uint(BigUint::from(frame.context.timestamp))
+                }
+                (Expr::Name { name: "block" }, "number") => {
+                    Value::Uint(BigUint::from(frame.context.number))
+                }
+                _ => {
+                    let (name, keys, _) = self.storage_ref(frame, expr)?;
+                    self.load(&name, &keys)?
+                }
+            },
+            Expr::Subscript { .. } => {
+                let (name, keys, _) = self.storage_ref(frame, expr)?;
+                self.load(&name, &keys)?
+            }
+            Expr::BinOp { left, op, right } => {
+                let base = self.base(left);
+                let left = self.expr(frame, left)?;
+                let right = self.expr(frame, right)?;
+
+                binary(*op, base, &left, &right)?
+            }
+            Expr::CompOp { left, op, right } => {
+                let base = self.base(left);
+                let left = self.expr(frame, left)?;
+                let right = self.expr(frame, right)?;
+
+                let result = match op {
+                    CompOperator::Eq => left == right,
+                    CompOperator::NotEq => left != right,
+                    _ => {
+                        let ordering = arith::compare(base, &left, &right)?;
+                        match op {
+                            CompOperator::Lt => ordering == Ordering::Less,
+                            CompOperator::LtE => ordering != Ordering::Greater,
+                            CompOperator::Gt => ordering == Ordering::Greater,
+                            _ => ordering != Ordering::Less,
+                        }
+                    }
+                };
+                Value::Bool(result)
+            }
+            Expr::BoolOp { left, op, right } => {
+                let left = as_bool(&self.expr(frame, left)?)?;
+
+                // The right operand is only evaluated if the left one does not
+                // determine the result
+                match (op, left) {
+                    (BoolOperator::And, false) | (BoolOperator::Or, true) => Value::Bool(left),
+                    _ => Value::Bool(as_bool(&self.expr(frame, right)?)?),
+                }
+            }
+            Expr::UnaryOp { op, operand } => {
+                let value = self.expr(frame, operand)?;
+
+                match op {
+                    UnaryOp::Not => Value::Bool(!as_bool(&value)?),
+                    UnaryOp::USub => arith::neg(Base::Int128, &value)?,
+                    UnaryOp::UAdd => value,
+                    UnaryOp::Invert => {
+                        let max = (BigUint::one() << 256) - 1u8;
+                        Value::Uint(max - as_uint(&value)?)
+                    }
+                }
+            }
+            Expr::Call { .. } => self
+                .call_expr(frame, expr)?
+                .expect("calls in expressions return a value"),
+            Expr::Num { .. } | Expr::Str { .. } => unreachable!("literals are checked"),
+        };
+
+        Ok(value)
+    }
+}
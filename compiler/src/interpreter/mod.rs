@@ -0,0 +1,554 @@
+//! A reference interpreter that executes Vyper contracts directly from the
+//! AST.
+//!
+//! The interpreter defines the intended semantics of compiled code and is used
+//! for differential testing: executing a contract with the interpreter and its
+//! bytecode on an EVM should give identical results.  Storage uses the slots
+//! of the contract's storage layout, and emitted logs have the same topics and
+//! data as those of compiled code.
+//!
+//! The events, storage fields, constants and functions of a contract are taken
+//! from its parsed module.  Contracts are deployed and their external
+//! functions called with `deploy` and `call`, which execute function bodies
+//! statement by statement.  Bodies are checked as for compilation and the side
+//! tables of checking give the types and values of expressions, so operands
+//! are evaluated in the same order as in compiled code.  Contracts may also be
+//! driven directly through the operations that statements are built from,
+//! e.g. `emit`, `load` and `store` for storage access, and `assert`.
+//! Arithmetic is provided by the `arith` module.
+
+use std::collections::HashMap;
+
+use num_bigint::{
+    BigInt,
+    BigUint,
+};
+use num_traits::{
+    One,
+    ToPrimitive,
+};
+
+use vyper_analyzer::const_eval::{
+    is_constant,
+    module_constants,
+};
+use vyper_analyzer::types::{
+    resolve_type_desc,
+    Type,
+};
+use vyper_parser::ast::{
+    Module,
+    ModuleStmt,
+};
+use vyper_parser::span::Span;
+
+use crate::abi::codec::{
+    decode,
+    encode,
+    Value,
+};
+use crate::abi::constant_value;
+use crate::errors::{
+    CompileError,
+    RuntimeError,
+};
+use crate::functions::{
+    check_functions,
+    Functions,
+};
+use crate::signatures::{
+    event_topic,
+    keccak256,
+    signature,
+};
+use crate::storage::{
+    compute_layout,
+    map_value_slot,
+    storage_fields,
+    value_slots,
+    StorageField,
+    StorageType,
+};
+
+pub mod arith;
+mod exec;
+
+/// The environment in which a function is called.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Context {
+    /// The address of the contract.
+    pub address: [u8; 20],
+    /// The address of the caller.
+    pub sender: [u8; 20],
+    /// The value sent with the call.
+    pub value: BigUint,
+    pub number: u64,
+    pub timestamp: u64,
+}
+
+/// A log emitted by a contract.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Log {
+    pub topics: Vec<[u8; 32]>,
+    pub data: Vec<u8>,
+}
+
+/// A field of an event definition.
+#[derive(Debug, PartialEq, Clone)]
+struct EventParam {
+    typ: Type,
+    indexed: bool,
+}
+
+/// A constant with its type, value, and the span of its definition.
+#[derive(Debug, PartialEq, Clone)]
+struct Constant {
+    typ: Type,
+    value: BigInt,
+    span: Span,
+}
+
+/// A storage field and the first slot it occupies.
+#[derive(Debug, PartialEq, Clone)]
+struct FieldSlot {
+    typ: StorageType,
+    slot: u64,
+}
+
+/// Return the word that a value of the type `typ` is encoded as in a topic or
+/// map key.  Byte arrays and strings are encoded as the hash of their content.
+fn value_word(typ: &Type, value: &Value) -> Result<[u8; 32], RuntimeError> {
+    let invalid = |_| RuntimeError::Invalid("value does not match type");
+
+    if typ.is_dynamic() {
+        return match (typ, value) {
+            (Type::Bytes { .. }, Value::Bytes(bytes)) => Ok(keccak256(bytes)),
+            (Type::String { .. }, Value::String(string)) => Ok(keccak256(string.as_bytes())),
+            _ => Err(RuntimeError::Invalid("value does not match type")),
+        };
+    }
+
+    let encoded =
+        encode(std::slice::from_ref(typ), std::slice::from_ref(value)).map_err(invalid)?;
+    if encoded.len() != 32 {
+        return Err(RuntimeError::Invalid("value does not fit in a word"));
+    }
+
+    let mut word = [0; 32];
+    word.copy_from_slice(&encoded);
+    Ok(word)
+}
+
+/// Return the storage slot `slot` as a word.  Slots wrap around at `2 ** 256`
+/// as on an EVM.
+fn slot_word(slot: &BigUint) -> [u8; 32] {
+    let bytes = (slot % (BigUint::one() << 256)).to_bytes_be();
+    let mut word = [0; 32];
+    word[32 - bytes.len()..].copy_from_slice(&bytes);
+
+    word
+}
+
+/// Return the number of slots occupied by a value of the type `typ` held in a
+/// field of the storage layout.
+fn slots(typ: &Type) -> BigUint {
+    // The storage layout is computed with checked arithmetic
+    BigUint::from(value_slots(typ).expect("storage field is too large"))
+}
+
+/// The state of a contract executed by the reference interpreter.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Interpreter<'m, 'a> {
+    functions: Functions<'m, 'a>,
+    events: HashMap<String, (String, Vec<EventParam>)>,
+    fields: HashMap<String, FieldSlot>,
+    constants: HashMap<String, Constant>,
+    /// The non-zero words in storage by slot.
+    storage: HashMap<[u8; 32], [u8; 32]>,
+    logs: Vec<Log>,
+}
+
+impl<'m, 'a> Interpreter<'m, 'a> {
+    /// Create an interpreter for the module `module` with empty storage.
+    pub fn new(module: &'m Module<'a>) -> Result<Self, CompileError> {
+        Interpreter::with_storage_fields(module, storage_fields(module)?)
+    }
+
+    /// Create an interpreter for the module `module` with the storage fields
+    /// `fields` given in order of definition.
+    pub fn with_storage_fields(
+        module: &'m Module<'a>,
+        fields: Vec<StorageField>,
+    ) -> Result<Self, CompileError> {
+        let env = module_constants(module)?;
+        let functions = check_functions(module, &env, &fields)?;
+        let mut events = HashMap::new();
+        let mut constants = HashMap::new();
+
+        for stmt in &module.body {
+            match &stmt.node {
+                ModuleStmt::EventDef { name, fields } => {
+                    let mut params = vec![];
+                    for field in fields {
+                        params.push(EventParam {
+                            typ: resolve_type_desc(&field.node.typ, &env)?,
+                            indexed: field.node.typ.node.annotations.contains(&"indexed"),
+                        });
+                    }
+
+                    let types: Vec<_> = params.iter().map(|p| p.typ.clone()).collect();
+                    let sig = signature(name, &types);
+
+                    if events.insert(name.to_string(), (sig, params)).is_some() {
+                        return Err(CompileError::new("duplicate event definition", stmt.span));
+                    }
+                }
+                ModuleStmt::FieldDef { name, typ, .. } if is_constant(&typ.node) => {
+                    let constant = Constant {
+                        typ: resolve_type_desc(typ, &env)?,
+                        value: env[name].clone(),
+                        span: stmt.span,
+                    };
+                    constants.insert(name.to_string(), constant);
+                }
+                ModuleStmt::FieldDef { .. } | ModuleStmt::FuncDef { .. } => {}
+            }
+        }
+
+        let fields = compute_layout(&fields)?
+            .into_iter()
+            .zip(fields)
+            .map(|(layout, field)| {
+                (
+                    field.name,
+                    FieldSlot {
+                        typ: field.typ,
+                        slot: layout.slot,
+                    },
+                )
+            })
+            .collect();
+
+        Ok(Interpreter {
+            functions,
+            events,
+            fields,
+            constants,
+            storage: HashMap::new(),
+            logs: vec![],
+        })
+    }
+
+    /// Execute the function `body` as a transaction and return the logs it
+    /// emitted.  If the transaction reverts its changes to storage and logs
+    /// are discarded.
+    pub fn transact<F>(&mut self, body: F) -> Result<Vec<Log>, RuntimeError>
+    where
+        F: FnOnce(&mut Self) -> Result<(), RuntimeError>,
+    {
+        let storage = self.storage.clone();
+        let logs = std::mem::take(&mut self.logs);
+
+        let result = body(self);
+        let emitted = std::mem::replace(&mut self.logs, logs);
+
+        match result {
+            Ok(()) => Ok(emitted),
+            Err(err) => {
+                self.storage = storage;
+                Err(err)
+            }
+        }
+    }
+
+    /// Emit a log for the event `name` with the field values `args`.
+    pub fn emit(&mut self, name: &str, args: &[Value]) -> Result<(), RuntimeError> {
+        let (sig, params) = self
+            .events
+            .get(name)
+            .ok_or(RuntimeError::Invalid("unknown event"))?;
+        if params.len() != args.len() {
+            return Err(RuntimeError::Invalid("wrong number of values"));
+        }
+
+        let mut topics = vec![event_topic(sig)];
+        let mut data_types = vec![];
+        let mut data_values = vec![];
+
+        for (param, arg) in params.iter().zip(args) {
+            if param.indexed {
+                topics.push(value_word(&param.typ, arg)?);
+            } else {
+                data_types.push(param.typ.clone());
+                data_values.push(arg.clone());
+            }
+        }
+
+        let data = encode(&data_types, &data_values)
+            .map_err(|_| RuntimeError::Invalid("value does not match type"))?;
+        self.logs.push(Log { topics, data });
+
+        Ok(())
+    }
+
+    /// Revert with the reason `reason` unless `condition` holds.
+    pub fn assert(&self, condition: bool, reason: Option<&str>) -> Result<(), RuntimeError> {
+        if condition {
+            Ok(())
+        } else {
+            Err(RuntimeError::Revert(reason.map(str::to_string)))
+        }
+    }
+
+    /// Revert with the reason `reason`.
+    pub fn raise(&self, reason: &str) -> Result<(), RuntimeError> {
+        Err(RuntimeError::Revert(Some(reason.to_string())))
+    }
+
+    /// Return the type and first slot of the value held in the storage field
+    /// `name` under the keys `keys`.  Keys are map keys followed by indexes
+    /// into arrays from the outermost in.  Indexes out of bounds revert.
+    fn resolve(&self, name: &str, keys: &[Value]) -> Result<(Type, BigUint), RuntimeError> {
+        let field = self
+            .fields
+            .get(name)
+            .ok_or(RuntimeError::Invalid("unknown storage field"))?;
+
+        let mut storage_type = &field.typ;
+        let mut slot = BigUint::from(field.slot);
+        let mut keys = keys.iter();
+
+        let mut typ = loop {
+            match storage_type {
+                StorageType::Map {
+                    key: key_type,
+                    value,
+                } => {
+                    let key = keys
+                        .next()
+                        .ok_or(RuntimeError::Invalid("wrong number of map keys"))?;
+                    let slot_key = map_value_slot(&slot_word(&slot), &value_word(key_type, key)?);
+
+                    slot = BigUint::from_bytes_be(&slot_key);
+                    storage_type = value;
+                }
+                StorageType::Value(typ) => break typ,
+            }
+        };
+
+        for key in keys {
+            match (typ, key) {
+                (Type::Array { inner, dimension }, Value::Uint(index)) => {
+                    if *index >= BigUint::from(*dimension) {
+                        return Err(RuntimeError::Revert(None));
+                    }
+
+                    slot += index * slots(inner);
+                    typ = inner;
+                }
+                (Type::Array { .. }, _) => {
+                    return Err(RuntimeError::Invalid(
+                        "array indexes must be uint256 values",
+                    ))
+                }
+                _ => return Err(RuntimeError::Invalid("too many keys")),
+            }
+        }
+
+        Ok((typ.clone(), slot))
+    }
+
+    /// Return the word held in the storage slot `slot`.
+    fn word(&self, slot: &BigUint) -> [u8; 32] {
+        self.storage
+            .get(&slot_word(slot))
+            .copied()
+            .unwrap_or([0; 32])
+    }
+
+    /// Set the word held in the storage slot `slot` to `word`.
+    fn set_word(&mut self, slot: &BigUint, word: [u8; 32]) {
+        if word == [0; 32] {
+            self.storage.remove(&slot_word(slot));
+        } else {
+            self.storage.insert(slot_word(slot), word);
+        }
+    }
+
+    /// Return the item types of a value of the type `typ` held in storage
+    /// from the slot `slot` on with the slots of the items, or `None` if the
+    /// type is not a sequence of items.
+    fn items<'t>(typ: &'t Type, slot: &BigUint) -> Option<Vec<(&'t Type, BigUint)>> {
+        let mut types: Vec<&Type> = match typ {
+            Type::Array { inner, dimension } => {
+                let size = slots(inner);
+                return Some(
+                    (0..*dimension)
+                        .map(|i| (&**inner, slot + &size * i))
+                        .collect(),
+                );
+            }
+            Type::Tuple(items) => items.iter().collect(),
+            Type::Struct { fields, .. } => fields.iter().map(|(_, typ)| typ).collect(),
+            _ => return None,
+        };
+
+        let mut next = slot.clone();
+        Some(
+            types
+                .drain(..)
+                .map(|typ| {
+                    let item_slot = next.clone();
+                    next += slots(typ);
+                    (typ, item_slot)
+                })
+                .collect(),
+        )
+    }
+
+    /// Load the value of the type `typ` held in storage from the slot `slot`
+    /// on.
+    fn load_value(&self, typ: &Type, slot: &BigUint) -> Result<Value, RuntimeError> {
+        let invalid = || RuntimeError::Invalid("storage holds an invalid value");
+
+        if let Some(items) = Interpreter::items(typ, slot) {
+            let values = items
+                .iter()
+                .map(|(typ, slot)| self.load_value(typ, slot))
+                .collect::<Result<_, _>>()?;
+
+            return Ok(match typ {
+                Type::Array { .. } => Value::Array(values),
+                _ => Value::Tuple(values),
+            });
+        }
+
+        match typ {
+            Type::Bytes { max_size } | Type::String { max_size } => {
+                let len = BigUint::from_bytes_be(&self.word(slot))
+                    .to_usize()
+                    .filter(|len| *len <= *max_size as usize)
+                    .ok_or_else(invalid)?;
+
+                let mut content = vec![];
+                let mut content_slot = slot + 1u8;
+                while content.len() < len {
+                    content.extend_from_slice(&self.word(&content_slot));
+                    content_slot += 1u8;
+                }
+                content.truncate(len);
+
+                match typ {
+                    Type::Bytes { .. } => Ok(Value::Bytes(content)),
+                    _ => String::from_utf8(content)
+                        .map(Value::String)
+                        .map_err(|_| invalid()),
+                }
+            }
+            _ => {
+                let mut values =
+                    decode(std::slice::from_ref(typ), &self.word(slot)).map_err(|_| invalid())?;
+                Ok(values.remove(0))
+            }
+        }
+    }
+
+    /// Store the value `value` of the type `typ` in storage from the slot
+    /// `slot` on.  All slots of the value are written, so byte arrays and
+    /// strings leave no trace of longer values held before.
+    fn store_value(
+        &mut self,
+        typ: &Type,
+        slot: &BigUint,
+        value: &Value,
+    ) -> Result<(), RuntimeError> {
+        let mismatch = || RuntimeError::Invalid("value does not match type");
+
+        if let Some(items) = Interpreter::items(typ, slot) {
+            let values = match (typ, value) {
+                (Type::Array { .. }, Value::Array(values)) => values,
+                (Type::Tuple(_), Value::Tuple(values))
+                | (Type::Struct { .. }, Value::Tuple(values)) => values,
+                _ => return Err(mismatch()),
+            };
+            if values.len() != items.len() {
+                return Err(mismatch());
+            }
+
+            for ((typ, slot), value) in items.iter().zip(values) {
+                self.store_value(typ, slot, value)?;
+            }
+            return Ok(());
+        }
+
+        let content = match (typ, value) {
+            (Type::Bytes { max_size }, Value::Bytes(bytes)) => Some((bytes.as_slice(), max_size)),
+            (Type::String { max_size }, Value::String(string)) => {
+                Some((string.as_bytes(), max_size))
+            }
+            (Type::Bytes { .. }, _) | (Type::String { .. }, _) => return Err(mismatch()),
+            _ => None,
+        };
+
+        match content {
+            Some((bytes, max_size)) => {
+                if bytes.len() > *max_size as usize {
+                    return Err(RuntimeError::Invalid("value exceeds maximum size"));
+                }
+
+                self.set_word(slot, slot_word(&BigUint::from(bytes.len())));
+                let words = (*max_size as usize).div_ceil(32);
+                for i in 0..words {
+                    let mut word = [0; 32];
+                    let chunk = bytes.get(32 * i..).unwrap_or(&[]);
+                    let len = chunk.len().min(32);
+                    word[..len].copy_from_slice(&chunk[..len]);
+
+                    self.set_word(&(slot + 1u8 + i), word);
+                }
+            }
+            None => {
+                let word = value_word(typ, value)?;
+                self.set_word(slot, word);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Load the value held in the storage field or constant `name` under the
+    /// keys `keys`.  Keys are map keys followed by indexes into arrays.
+    pub fn load(&self, name: &str, keys: &[Value]) -> Result<Value, RuntimeError> {
+        if let Some(constant) = self.constants.get(name) {
+            if !keys.is_empty() {
+                return Err(RuntimeError::Invalid("too many keys"));
+            }
+
+            return constant_value(&constant.typ, &constant.value, constant.span)
+                .map_err(|_| RuntimeError::Invalid("constant value is not valid for its type"));
+        }
+
+        let (typ, slot) = self.resolve(name, keys)?;
+        self.load_value(&typ, &slot)
+    }
+
+    /// Store the value `value` in the storage field `name` under the keys
+    /// `keys`.  Keys are map keys followed by indexes into arrays.
+    pub fn store(&mut self, name: &str, keys: &[Value], value: Value) -> Result<(), RuntimeError> {
+        if self.constants.contains_key(name) {
+            return Err(RuntimeError::Invalid("constants cannot be modified"));
+        }
+
+        let (typ, slot) = self.resolve(name, keys)?;
+        self.store_value(&typ, &slot, &value)
+    }
+
+    /// Return the non-zero storage words by slot as integers, in the form in
+    /// which an EVM holds them.
+    pub fn storage(&self) -> HashMap<BigUint, BigUint> {
+        self.storage
+            .iter()
+            .map(|(slot, word)| (BigUint::from_bytes_be(slot), BigUint::from_bytes_be(word)))
+            .collect()
+    }
+}
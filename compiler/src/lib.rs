@@ -0,0 +1,8 @@
+pub mod abi;
+pub mod ast_json;
+pub mod errors;
+pub mod functions;
+pub mod interpreter;
+pub mod signatures;
+pub mod storage;
+pub mod yul;
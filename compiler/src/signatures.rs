@@ -0,0 +1,116 @@
+//! Canonical signatures of ABI-visible items and the hashes derived from them.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use vyper_analyzer::const_eval::ConstEnv;
+use vyper_analyzer::types::{
+    resolve_type_desc,
+    Type,
+};
+use vyper_parser::ast::EventField;
+use vyper_parser::span::{
+    Span,
+    Spanned,
+};
+
+use crate::errors::CompileError;
+
+pub use vyper_evm::state::keccak256;
+
+/// Build the canonical signature of a function or event with the given name
+/// and parameter types, e.g. `Transfer(address,address,uint256)`.
+pub fn signature(name: &str, types: &[Type]) -> String {
+    let type_names: Vec<_> = types.iter().map(Type::abi_name).collect();
+
+    format!("{}({})", name, type_names.join(","))
+}
+
+/// Compute the topic used to identify logs of an event with the given
+/// signature.
+pub fn event_topic(signature: &str) -> [u8; 32] {
+    keccak256(signature.as_bytes())
+}
+
+/// Compute the selector used to identify calls to a function with the given
+/// signature, i.e. the first four bytes of its hash.
+pub fn selector(signature: &str) -> u32 {
+    let hash = keccak256(signature.as_bytes());
+
+    u32::from_be_bytes([hash[0], hash[1], hash[2], hash[3]])
+}
+
+/// Build the canonical signature of the event with the given name and fields.
+/// Field types are resolved with the named constants in `env`.
+pub fn event_signature(
+    name: &str,
+    fields: &[Spanned<EventField>],
+    env: &ConstEnv,
+) -> Result<String, CompileError> {
+    let types = fields
+        .iter()
+        .map(|field| resolve_type_desc(&field.node.typ, env))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(signature(name, &types))
+}
+
+/// Two functions of a contract with the same selector.
+#[derive(Debug, PartialEq)]
+pub struct SelectorCollision {
+    pub selector: u32,
+    /// The signature and span of the function defined first.
+    pub first: (String, Span),
+    /// The signature and span of the function defined second.
+    pub second: (String, Span),
+}
+
+impl fmt::Display for SelectorCollision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "function selector 0x{:08x} of {} collides with {}",
+            self.selector, self.second.0, self.first.0,
+        )
+    }
+}
+
+/// Find all pairs of functions with colliding selectors among the functions
+/// with the given signatures and spans.  Functions are expected in the order
+/// of their definition.
+pub fn selector_collisions(functions: &[(String, Span)]) -> Vec<SelectorCollision> {
+    let mut seen: HashMap<u32, &(String, Span)> = HashMap::new();
+    let mut collisions = vec![];
+
+    for function in functions {
+        let selector = selector(&function.0);
+
+        match seen.get(&selector) {
+            Some(first) => collisions.push(SelectorCollision {
+                selector,
+                first: (*first).clone(),
+                second: function.clone(),
+            }),
+            None => {
+                seen.insert(selector, function);
+            }
+        }
+    }
+
+    collisions
+}
+
+/// Fail if any two of the functions with the given signatures and spans have
+/// the same selector.  The error is located at the function of the first
+/// collision that is defined second and labels the function defined first.
+/// Functions are expected in the order of their definition.
+pub fn check_selectors(functions: &[(String, Span)]) -> Result<(), CompileError> {
+    match selector_collisions(functions).first() {
+        Some(collision) => Err(CompileError::new(collision.to_string(), collision.second.1)
+            .with_label(
+                format!("{} is defined here", collision.first.0),
+                collision.first.1,
+            )),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,235 @@
+//! Computation of the storage layout of a contract.
+//!
+//! Storage fields are assigned consecutive slots in order of definition
+//! starting at slot zero.  Values are not packed, so every field starts at
+//! offset zero of its first slot and occupies a whole number of slots:
+//!
+//! * Base types occupy a single slot.
+//! * Fixed-size lists and structs occupy the slots of their items in order.
+//! * Byte arrays and strings occupy a slot holding their length followed by
+//!   enough slots to hold their maximum size.
+//! * Maps occupy a single slot that holds no value.  The value for a key is
+//!   stored at `keccak256(slot ++ key)` where `slot` is the slot of the map and
+//!   `key` is the key encoded as a word.  Byte array and string keys are
+//!   encoded as the hash of their content.
+
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use vyper_analyzer::const_eval::{
+    is_constant,
+    module_constants,
+    ConstEnv,
+};
+use vyper_analyzer::types::{
+    resolve_type_desc,
+    Type,
+};
+use vyper_parser::ast::{
+    Module,
+    ModuleStmt,
+    TypeDesc,
+};
+use vyper_parser::span::{
+    Span,
+    Spanned,
+};
+
+use crate::errors::CompileError;
+use crate::signatures::keccak256;
+
+/// The size in bytes of a storage slot.
+const SLOT_SIZE: u64 = 32;
+
+/// The type of a value held in storage.
+#[derive(Debug, PartialEq, Clone)]
+pub enum StorageType {
+    Value(Type),
+    /// A mapping from keys to values such as `map(address, uint256)`.
+    Map {
+        key: Type,
+        value: Box<StorageType>,
+    },
+}
+
+impl StorageType {
+    /// Return the name of a storage type as written in Vyper source code.
+    pub fn name(&self) -> String {
+        match self {
+            StorageType::Value(typ) => typ.name(),
+            StorageType::Map { key, value } => format!("map({}, {})", key.name(), value.name()),
+        }
+    }
+
+    /// Return the number of consecutive slots occupied by values of a type, or
+    /// `None` if the number does not fit into a `u64`.
+    pub fn slots(&self) -> Option<u64> {
+        match self {
+            StorageType::Value(typ) => value_slots(typ),
+            StorageType::Map { .. } => Some(1),
+        }
+    }
+}
+
+/// Return the number of consecutive slots occupied by values of the type
+/// `typ`, or `None` if the number does not fit into a `u64`.
+pub fn value_slots(typ: &Type) -> Option<u64> {
+    match typ {
+        Type::Base(_) => Some(1),
+        Type::Array { inner, dimension } => value_slots(inner)?.checked_mul(u64::from(*dimension)),
+        Type::Bytes { max_size } | Type::String { max_size } => {
+            Some(1 + u64::from(*max_size).div_ceil(SLOT_SIZE))
+        }
+        Type::Tuple(items) => sum_slots(items.iter()),
+        Type::Struct { fields, .. } => sum_slots(fields.iter().map(|(_, typ)| typ)),
+    }
+}
+
+/// Return the total number of slots occupied by values of the types `types`,
+/// or `None` if the number does not fit into a `u64`.
+fn sum_slots<'a>(mut types: impl Iterator<Item = &'a Type>) -> Option<u64> {
+    types.try_fold(0u64, |total, typ| total.checked_add(value_slots(typ)?))
+}
+
+/// A storage field definition.
+#[derive(Debug, PartialEq, Clone)]
+pub struct StorageField {
+    pub name: String,
+    pub typ: StorageType,
+    pub span: Span,
+}
+
+/// The location of a storage field.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct FieldLayout {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub typ: String,
+    /// The first slot occupied by the field.
+    pub slot: u64,
+    /// The byte offset of the field in its first slot.
+    pub offset: u64,
+    /// The number of consecutive slots occupied by the field.
+    pub slots: u64,
+    pub span: Span,
+}
+
+/// Compute the layout of the storage fields `fields` given in order of
+/// definition.
+///
+/// Fails if the slots occupied by a field or by all fields together cannot be
+/// numbered with a `u64`.
+pub fn compute_layout(fields: &[StorageField]) -> Result<Vec<FieldLayout>, CompileError> {
+    let mut slot: u64 = 0;
+
+    fields
+        .iter()
+        .map(|field| {
+            let slots = field
+                .typ
+                .slots()
+                .ok_or_else(|| CompileError::new("storage field is too large", field.span))?;
+
+            let layout = FieldLayout {
+                name: field.name.clone(),
+                typ: field.typ.name(),
+                slot,
+                offset: 0,
+                slots,
+                span: field.span,
+            };
+
+            slot = slot
+                .checked_add(slots)
+                .ok_or_else(|| CompileError::new("storage layout is too large", field.span))?;
+            Ok(layout)
+        })
+        .collect()
+}
+
+/// Return the slot number `slot` as a word.
+pub fn slot_word(slot: u64) -> [u8; 32] {
+    let mut word = [0; 32];
+    word[24..].copy_from_slice(&slot.to_be_bytes());
+
+    word
+}
+
+/// Return the slot holding the value for the key encoded as `key` in the map
+/// at the slot `slot`.
+pub fn map_value_slot(slot: &[u8; 32], key: &[u8; 32]) -> [u8; 32] {
+    let mut data = [0; 64];
+    data[..32].copy_from_slice(slot);
+    data[32..].copy_from_slice(key);
+
+    keccak256(&data)
+}
+
+/// Resolve the type description `desc` of a storage field into a storage type.
+/// Map keys must have a base type or be byte arrays or strings.  Types are
+/// resolved with the named constants in `env`.
+pub fn resolve_storage_type(
+    desc: &Spanned<TypeDesc>,
+    env: &ConstEnv,
+) -> Result<StorageType, CompileError> {
+    match desc.node.params.as_slice() {
+        [] => Ok(StorageType::Value(resolve_type_desc(desc, env)?)),
+        [key_desc, value] => {
+            let key = resolve_type_desc(key_desc, env)?;
+            if !matches!(
+                key,
+                Type::Base(_) | Type::Bytes { .. } | Type::String { .. }
+            ) {
+                return Err(CompileError::new(
+                    "map keys must have a base type or be byte arrays or strings",
+                    key_desc.span,
+                ));
+            }
+
+            Ok(StorageType::Map {
+                key,
+                value: Box::new(resolve_storage_type(value, env)?),
+            })
+        }
+        _ => Err(CompileError::new(
+            "map types require a key and a value type",
+            desc.span,
+        )),
+    }
+}
+
+/// Return the storage fields defined in the module `module` in order of
+/// definition.  Constants are not stored and are skipped.  Types are resolved
+/// with the values of the module's constants.
+pub fn storage_fields(module: &Module) -> Result<Vec<StorageField>, CompileError> {
+    let env = module_constants(module)?;
+    let mut names = HashSet::new();
+    let mut fields = vec![];
+
+    for stmt in &module.body {
+        if let ModuleStmt::FieldDef { name, typ, .. } = &stmt.node {
+            if !names.insert(*name) {
+                return Err(CompileError::new("duplicate field definition", stmt.span));
+            }
+            if is_constant(&typ.node) {
+                continue;
+            }
+
+            fields.push(StorageField {
+                name: name.to_string(),
+                typ: resolve_storage_type(typ, &env)?,
+                span: stmt.span,
+            });
+        }
+    }
+
+    Ok(fields)
+}
+
+/// Compute the storage layout of the module `module` as pretty-printed JSON.
+pub fn build_storage_layout_json(module: &Module) -> Result<String, CompileError> {
+    let layout = compute_layout(&storage_fields(module)?)?;
+
+    Ok(serde_json::to_string_pretty(&layout).expect("storage layout serialization failed"))
+}
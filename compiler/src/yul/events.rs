@@ -0,0 +1,173 @@
+//! Lowering of event definitions into YUL functions that emit logs.
+
+use std::collections::HashSet;
+
+use vyper_analyzer::const_eval::ConstEnv;
+use vyper_analyzer::types::{
+    resolve_type_desc,
+    Type,
+};
+use vyper_parser::ast::EventField;
+use vyper_parser::span::Spanned;
+use vyper_yul::ast::{
+    Expression,
+    Statement,
+};
+
+use crate::errors::CompileError;
+use crate::signatures::{
+    event_signature,
+    event_topic,
+};
+
+/// The largest number of indexed fields in an event.  The EVM allows logs with
+/// at most four topics and the first topic identifies the event.
+const MAX_INDEXED: usize = 3;
+
+/// Return the name of the YUL function that emits an event.
+pub fn emit_function_name(event_name: &str) -> String {
+    format!("emit_{}", event_name)
+}
+
+/// Return the name of the YUL variable holding the value of an event field.
+fn arg_name(field_name: &str) -> String {
+    format!("arg_{}", field_name)
+}
+
+/// Return true if an event field is annotated as `indexed`.
+fn is_indexed(field: &Spanned<EventField>) -> bool {
+    field.node.typ.node.annotations.contains(&"indexed")
+}
+
+/// Build a YUL function that emits a log for the event with the given name and
+/// fields.  The function accepts one argument per field.  Fields with a base
+/// type are passed by value.  Byte arrays and strings are passed as a pointer
+/// to memory holding a length word followed by the content.
+///
+/// Field types are resolved with the named constants in `env`.  Also returns
+/// the names of any runtime helper functions that are used.
+pub fn emit_function(
+    name: &str,
+    fields: &[Spanned<EventField>],
+    env: &ConstEnv,
+) -> Result<(Statement, Vec<&'static str>), CompileError> {
+    let mut names = HashSet::new();
+    let mut types = vec![];
+    for field in fields {
+        if !names.insert(field.node.name) {
+            return Err(CompileError::new("duplicate event field", field.span));
+        }
+
+        let typ = resolve_type_desc(&field.node.typ, env)?;
+
+        if let Type::Array { .. } = typ {
+            return Err(CompileError::new(
+                "event fields with array types are not supported",
+                field.node.typ.span,
+            ));
+        }
+        types.push(typ);
+    }
+
+    let topic = event_topic(&event_signature(name, fields, env)?);
+    let mut topics = vec![Expression::hex_num(&topic)];
+
+    let mut body = vec![Statement::declare(
+        "ptr",
+        Expression::call("mload", vec![Expression::num(64)]),
+    )];
+    let mut helpers = vec![];
+
+    let data_fields: Vec<_> = fields
+        .iter()
+        .zip(types.iter())
+        .filter(|(field, _)| !is_indexed(field))
+        .collect();
+    let head_size = 32 * data_fields.len() as u64;
+    let has_dynamic = data_fields.iter().any(|(_, typ)| typ.is_dynamic());
+
+    if has_dynamic {
+        body.push(Statement::declare("size", Expression::num(head_size)));
+    }
+
+    // Encode non-indexed fields as log data
+    for (i, (field, typ)) in data_fields.iter().enumerate() {
+        let arg = Expression::ident(&arg_name(field.node.name));
+        let head = match i {
+            0 => Expression::ident("ptr"),
+            _ => Expression::call(
+                "add",
+                vec![Expression::ident("ptr"), Expression::num(32 * i as u64)],
+            ),
+        };
+
+        if typ.is_dynamic() {
+            let tail = Expression::call(
+                "add",
+                vec![Expression::ident("ptr"), Expression::ident("size")],
+            );
+
+            body.push(Expression::call("mstore", vec![head, Expression::ident("size")]).into());
+            body.push(Statement::assign(
+                "size",
+                Expression::call(
+                    "add",
+                    vec![
+                        Expression::ident("size"),
+                        Expression::call("abi_encode_bytes", vec![arg, tail]),
+                    ],
+                ),
+            ));
+            helpers.push("abi_encode_bytes");
+        } else {
+            body.push(Expression::call("mstore", vec![head, arg]).into());
+        }
+    }
+
+    // Pass indexed fields as topics
+    for (field, typ) in fields.iter().zip(types.iter()) {
+        if !is_indexed(field) {
+            continue;
+        }
+        if topics.len() > MAX_INDEXED {
+            return Err(CompileError::new(
+                "events may have at most three indexed fields",
+                field.span,
+            ));
+        }
+
+        let arg = Expression::ident(&arg_name(field.node.name));
+
+        topics.push(if typ.is_dynamic() {
+            // Dynamic values are indexed by the hash of their content
+            Expression::call(
+                "keccak256",
+                vec![
+                    Expression::call("add", vec![arg.clone(), Expression::num(32)]),
+                    Expression::call("mload", vec![arg]),
+                ],
+            )
+        } else {
+            arg
+        });
+    }
+
+    let size = if has_dynamic {
+        Expression::ident("size")
+    } else {
+        Expression::num(head_size)
+    };
+
+    let mut log_args = vec![Expression::ident("ptr"), size];
+    let log_name = format!("log{}", topics.len());
+    log_args.extend(topics);
+    body.push(Expression::call(&log_name, log_args).into());
+
+    let params: Vec<_> = fields.iter().map(|f| arg_name(f.node.name)).collect();
+    let params: Vec<_> = params.iter().map(String::as_str).collect();
+
+    Ok((
+        Statement::function(&emit_function_name(name), &params, &[], body),
+        helpers,
+    ))
+}
@@ -0,0 +1,926 @@
+//! Lowering of function definitions into YUL functions.
+//!
+//! Every function other than the constructor is lowered into a YUL function
+//! named `fn_<name>` that takes one parameter per argument and returns `r` if
+//! the function returns a value.  External functions are called through a
+//! case of the dispatcher that decodes their arguments from calldata and ABI
+//! encodes their result.  The constructor is inlined into the deployment code
+//! and decodes its arguments from the end of the code.
+//!
+//! Values of base types are held in words.  Byte arrays and strings are held
+//! in memory as a length word followed by the content, and passed around as
+//! pointers.  Storage is read and written through the slots of the storage
+//! layout.
+//!
+//! YUL evaluates the arguments of a call from right to left, so the value of
+//! an operand is moved into a temporary variable before the operands to its
+//! right are evaluated if those may have any effect.  This keeps the order of
+//! evaluation from left to right.
+
+use std::collections::HashMap;
+
+use num_bigint::BigInt;
+use num_traits::{
+    One,
+    Signed,
+    ToPrimitive,
+};
+
+use vyper_analyzer::types::{
+    Base,
+    Type,
+};
+use vyper_parser::ast::{
+    BoolOperator,
+    CompOperator,
+    Expr,
+    FuncStmt,
+    Operator,
+    UnaryOp,
+};
+use vyper_parser::span::Spanned;
+use vyper_yul::ast::{
+    Block,
+    Case,
+    Expression,
+    Literal,
+    Statement,
+};
+
+use crate::abi::StateMutability;
+use crate::functions::{
+    CheckedFunction,
+    Functions,
+    Signature,
+};
+use crate::storage::{
+    value_slots,
+    StorageType,
+};
+use crate::yul::events::emit_function_name;
+use crate::yul::getters::decoder_name;
+use crate::yul::runtime::revert;
+
+/// The memory address of a word that is always zero.  It also serves as an
+/// empty byte array.
+const ZERO_POINTER: u64 = 96;
+
+/// The name of the YUL variable holding the return value of a function.
+const RETURN_VARIABLE: &str = "r";
+
+/// The first slot and the type of every storage field by name.
+pub type FieldSlots<'f> = HashMap<&'f str, (u64, &'f StorageType)>;
+
+fn call(name: &str, args: Vec<Expression>) -> Expression {
+    Expression::call(name, args)
+}
+
+fn id(name: &str) -> Expression {
+    Expression::ident(name)
+}
+
+fn num(value: u64) -> Expression {
+    Expression::num(value)
+}
+
+/// Return the name of the YUL function that a function is lowered into.
+pub fn function_name(name: &str) -> String {
+    format!("fn_{}", name)
+}
+
+/// Return the name of the YUL variable holding a local variable or argument.
+fn local_name(name: &str) -> String {
+    format!("v_{}", name)
+}
+
+/// Return the literal word holding the integer `value`.  Negative values are
+/// held in two's complement.
+fn word(value: &BigInt) -> Expression {
+    if let Some(value) = value.to_u64() {
+        return num(value);
+    }
+
+    let value = if value.is_negative() {
+        (BigInt::one() << 256) + value
+    } else {
+        value.clone()
+    };
+    Expression::hex_num(&value.to_bytes_be().1)
+}
+
+/// The built-in YUL functions that neither have effects nor depend on state
+/// that may be changed by evaluating other expressions.
+const PURE_BUILTINS: &[&str] = &[
+    "add",
+    "address",
+    "and",
+    "caller",
+    "callvalue",
+    "eq",
+    "gt",
+    "iszero",
+    "lt",
+    "mul",
+    "not",
+    "number",
+    "or",
+    "sgt",
+    "shl",
+    "shr",
+    "slt",
+    "sub",
+    "timestamp",
+    "xor",
+];
+
+/// Return true if evaluating the expression `expr` has no effect and gives the
+/// same value at any point of a statement.
+fn is_pure(expr: &Expression) -> bool {
+    match expr {
+        Expression::Literal(_) | Expression::Identifier(_) => true,
+        Expression::FunctionCall { name, args } => {
+            PURE_BUILTINS.contains(&name.as_str()) && args.iter().all(is_pure)
+        }
+    }
+}
+
+/// The code computing a value: statements to execute first and an expression
+/// giving the value.
+struct Lowered {
+    prelude: Vec<Statement>,
+    value: Expression,
+}
+
+impl Lowered {
+    fn pure(value: Expression) -> Self {
+        Lowered {
+            prelude: vec![],
+            value,
+        }
+    }
+
+    /// Return true if the code has any effect.
+    fn has_effects(&self) -> bool {
+        !self.prelude.is_empty() || !is_pure(&self.value)
+    }
+}
+
+/// Return the statements `stmts` preceded by `prelude`, in a block of their
+/// own so that temporary variables are released afterwards.
+fn with_prelude(mut prelude: Vec<Statement>, stmts: Vec<Statement>) -> Vec<Statement> {
+    if prelude.is_empty() {
+        return stmts;
+    }
+
+    prelude.extend(stmts);
+    vec![Statement::Block(Block::new(prelude))]
+}
+
+/// Return the name of the runtime helper performing the arithmetic operation
+/// `op` on values of the type `base`, or of the built-in YUL function
+/// performing a bitwise operation.
+fn operator_function(op: Operator, base: Base) -> &'static str {
+    match (op, base) {
+        (Operator::BitAnd, _) => "and",
+        (Operator::BitOr, _) => "or",
+        (Operator::BitXor, _) => "xor",
+        (Operator::LShift, _) => "shl",
+        (Operator::RShift, _) => "shr",
+        (Operator::Add, Base::Int128) => "checked_add_int128",
+        (Operator::Sub, Base::Int128) => "checked_sub_int128",
+        (Operator::Mult, Base::Int128) => "checked_mul_int128",
+        (Operator::Div, Base::Int128) => "checked_div_int128",
+        (Operator::Mod, Base::Int128) => "checked_mod_int128",
+        (Operator::Pow, Base::Int128) => "checked_pow_int128",
+        (Operator::Add, _) => "checked_add_uint256",
+        (Operator::Sub, _) => "checked_sub_uint256",
+        (Operator::Mult, _) => "checked_mul_uint256",
+        (Operator::Div, _) => "checked_div_uint256",
+        (Operator::Mod, _) => "checked_mod_uint256",
+        (Operator::Pow, _) => "checked_pow_uint256",
+    }
+}
+
+/// Return the name of the runtime helper decoding a value of the type `base`
+/// from memory.
+fn memory_decoder_name(base: Base) -> &'static str {
+    match base {
+        Base::Address => "decode_address_from_memory",
+        Base::Bool => "decode_bool_from_memory",
+        Base::Bytes32 => "decode_bytes32_from_memory",
+        Base::Decimal => "decode_decimal_from_memory",
+        Base::Int128 => "decode_int128_from_memory",
+        Base::Uint256 => "decode_uint256_from_memory",
+    }
+}
+
+/// Return true if values of the type `typ` are held in memory.
+fn in_memory(typ: &Type) -> bool {
+    matches!(typ, Type::Bytes { .. } | Type::String { .. })
+}
+
+struct Lowering<'l, 'm, 'a> {
+    functions: &'l Functions<'m, 'a>,
+    fields: &'l FieldSlots<'l>,
+    helpers: Vec<&'static str>,
+    /// The number of temporary variables declared in the current function.
+    temps: usize,
+}
+
+impl<'l, 'm, 'a> Lowering<'l, 'm, 'a> {
+    fn helper(&mut self, name: &'static str) {
+        self.helpers.push(name);
+    }
+
+    /// Return the name of a new temporary variable.
+    fn temp(&mut self) -> String {
+        self.temps += 1;
+        format!("t_{}", self.temps)
+    }
+
+    fn typ(&self, expr: &Spanned<Expr>) -> &'l Type {
+        self.functions
+            .types
+            .get(&expr.id)
+            .expect("expressions are type checked")
+    }
+
+    fn base(&self, expr: &Spanned<Expr>) -> Base {
+        match self.typ(expr) {
+            Type::Base(base) => *base,
+            _ => unreachable!("operands have base types"),
+        }
+    }
+
+    /// Combine the code computing several values so that they are computed
+    /// from left to right when the resulting expressions are passed to a call.
+    fn sequence(&mut self, items: Vec<Lowered>) -> (Vec<Statement>, Vec<Expression>) {
+        let mut later_effects = vec![false; items.len()];
+        for i in (0..items.len().saturating_sub(1)).rev() {
+            later_effects[i] = later_effects[i + 1] || items[i + 1].has_effects();
+        }
+
+        let mut prelude = vec![];
+        let mut values = vec![];
+        for (item, later_effects) in items.into_iter().zip(later_effects) {
+            prelude.extend(item.prelude);
+
+            if later_effects && !is_pure(&item.value) {
+                let temp = self.temp();
+                prelude.push(Statement::declare(&temp, item.value));
+                values.push(id(&temp));
+            } else {
+                values.push(item.value);
+            }
+        }
+
+        (prelude, values)
+    }
+
+    /// Lower the expressions `exprs` into values computed from left to right.
+    fn exprs(&mut self, exprs: &[Spanned<Expr>]) -> (Vec<Statement>, Vec<Expression>) {
+        let items = exprs.iter().map(|expr| self.expr(expr)).collect();
+
+        self.sequence(items)
+    }
+
+    /// Lower the operands of a binary operation or comparison.
+    fn operands(
+        &mut self,
+        left: &Spanned<Expr>,
+        right: &Spanned<Expr>,
+    ) -> (Vec<Statement>, Vec<Expression>) {
+        let items = vec![self.expr(left), self.expr(right)];
+
+        self.sequence(items)
+    }
+
+    /// Lower a string literal with the content `content` into a byte array in
+    /// memory.
+    fn string_literal(&mut self, content: &[u8]) -> Lowered {
+        let temp = self.temp();
+        let words = content.len().div_ceil(32) as u64;
+        self.helper("allocate");
+
+        let mut prelude = vec![
+            Statement::declare(&temp, call("allocate", vec![num(32 + 32 * words)])),
+            call("mstore", vec![id(&temp), num(content.len() as u64)]).into(),
+        ];
+        for (i, chunk) in content.chunks(32).enumerate() {
+            let mut word = chunk.to_vec();
+            word.resize(32, 0);
+
+            prelude.push(
+                call(
+                    "mstore",
+                    vec![
+                        call("add", vec![id(&temp), num(32 * (i as u64 + 1))]),
+                        Expression::hex_num(&word),
+                    ],
+                )
+                .into(),
+            );
+        }
+
+        Lowered {
+            prelude,
+            value: id(&temp),
+        }
+    }
+
+    /// Lower a reference to storage into the slot it refers to and the type of
+    /// the value held there.
+    fn slot(&mut self, expr: &Spanned<Expr>) -> (Lowered, StorageType) {
+        match &expr.node {
+            Expr::Attribute { attr, .. } => {
+                let (slot, typ) = self.fields[attr];
+                (Lowered::pure(num(slot)), typ.clone())
+            }
+            Expr::Subscript { value, index } => {
+                let (base, typ) = self.slot(value);
+                let index_value = self.expr(index);
+                let (prelude, mut values) = self.sequence(vec![base, index_value]);
+                let index_value = values.pop().unwrap();
+                let base = values.pop().unwrap();
+
+                let (value, typ) = match typ {
+                    StorageType::Map { key, value } => {
+                        // Byte array and string keys are hashed
+                        let key = if in_memory(&key) {
+                            self.helper("hash_bytes");
+                            call("hash_bytes", vec![index_value])
+                        } else {
+                            index_value
+                        };
+                        self.helper("map_slot");
+
+                        (call("map_slot", vec![base, key]), *value)
+                    }
+                    StorageType::Value(Type::Array { inner, dimension }) => {
+                        // The storage layout is computed with checked
+                        // arithmetic, so offsets into a field cannot overflow
+                        let item_slots = value_slots(&inner).expect("storage field is too large");
+                        self.helper("array_slot");
+
+                        (
+                            call(
+                                "array_slot",
+                                vec![
+                                    base,
+                                    index_value,
+                                    num(u64::from(dimension)),
+                                    num(item_slots),
+                                ],
+                            ),
+                            StorageType::Value(*inner),
+                        )
+                    }
+                    StorageType::Value(_) => unreachable!("indexed values are checked"),
+                };
+
+                (Lowered { prelude, value }, typ)
+            }
+            _ => unreachable!("storage references are checked"),
+        }
+    }
+
+    /// Lower a read of the storage referred to by `expr`.
+    fn storage_read(&mut self, expr: &Spanned<Expr>) -> Lowered {
+        let (slot, typ) = self.slot(expr);
+
+        let value = match typ {
+            StorageType::Value(typ) if in_memory(&typ) => {
+                self.helper("load_bytes");
+                call("load_bytes", vec![slot.value])
+            }
+            _ => call("sload", vec![slot.value]),
+        };
+
+        Lowered {
+            prelude: slot.prelude,
+            value,
+        }
+    }
+
+    /// Lower the binary operation `op` on the values `left` and `right` of
+    /// the type `base`.
+    fn binary(
+        &mut self,
+        op: Operator,
+        base: Base,
+        left: Expression,
+        right: Expression,
+    ) -> Expression {
+        let func = operator_function(op, base);
+
+        match op {
+            Operator::LShift | Operator::RShift => call(func, vec![right, left]),
+            Operator::BitAnd | Operator::BitOr | Operator::BitXor => call(func, vec![left, right]),
+            _ => {
+                self.helper(func);
+                call(func, vec![left, right])
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &Spanned<Expr>) -> Lowered {
+        if let Some(value) = self.functions.values.get(&expr.id) {
+            return Lowered::pure(word(value));
+        }
+        if let Some(content) = self.functions.strings.get(&expr.id) {
+            return self.string_literal(content);
+        }
+
+        match &expr.node {
+            Expr::Name { name: "self" } => Lowered::pure(call("address", vec![])),
+            Expr::Name { name } => Lowered::pure(id(&local_name(name))),
+            Expr::Attribute { value, attr } => match (&value.node, *attr) {
+                (Expr::Name { name: "msg" }, "sender") => Lowered::pure(call("caller", vec![])),
+                (Expr::Name { name: "msg" }, "value") => Lowered::pure(call("callvalue", vec![])),
+                (Expr::Name { name: "block" }, "timestamp") => {
+                    Lowered::pure(call("timestamp", vec![]))
+                }
+                (Expr::Name { name: "block" }, "number") => Lowered::pure(call("number", vec![])),
+                _ => self.storage_read(expr),
+            },
+            Expr::Subscript { .. } => self.storage_read(expr),
+            Expr::BinOp { left, op, right } => {
+                let base = self.base(left);
+                let (prelude, mut values) = self.operands(left, right);
+                let right = values.pop().unwrap();
+                let left = values.pop().unwrap();
+
+                Lowered {
+                    prelude,
+                    value: self.binary(*op, base, left, right),
+                }
+            }
+            Expr::CompOp { left, op, right } => {
+                let signed = matches!(self.base(left), Base::Int128 | Base::Decimal);
+                let (prelude, mut values) = self.operands(left, right);
+                let right = values.pop().unwrap();
+                let left = values.pop().unwrap();
+
+                let (lt, gt) = if signed { ("slt", "sgt") } else { ("lt", "gt") };
+                let value = match op {
+                    CompOperator::Eq => call("eq", vec![left, right]),
+                    CompOperator::NotEq => call("iszero", vec![call("eq", vec![left, right])]),
+                    CompOperator::Lt => call(lt, vec![left, right]),
+                    CompOperator::Gt => call(gt, vec![left, right]),
+                    CompOperator::LtE => call("iszero", vec![call(gt, vec![left, right])]),
+                    CompOperator::GtE => call("iszero", vec![call(lt, vec![left, right])]),
+                };
+
+                Lowered { prelude, value }
+            }
+            Expr::BoolOp { left, op, right } => {
+                let left = self.expr(left);
+                let right = self.expr(right);
+                let func = match op {
+                    BoolOperator::And => "and",
+                    BoolOperator::Or => "or",
+                };
+
+                // The right operand may only be evaluated if the left one does
+                // not determine the result
+                if !right.has_effects() {
+                    return Lowered {
+                        prelude: left.prelude,
+                        value: call(func, vec![left.value, right.value]),
+                    };
+                }
+
+                let temp = self.temp();
+                let condition = match op {
+                    BoolOperator::And => id(&temp),
+                    BoolOperator::Or => call("iszero", vec![id(&temp)]),
+                };
+                let mut body = right.prelude;
+                body.push(Statement::assign(&temp, right.value));
+
+                let mut prelude = left.prelude;
+                prelude.push(Statement::declare(&temp, left.value));
+                prelude.push(Statement::if_(condition, body));
+
+                Lowered {
+                    prelude,
+                    value: id(&temp),
+                }
+            }
+            Expr::UnaryOp { op, operand } => {
+                let operand = self.expr(operand);
+                let value = match op {
+                    UnaryOp::Not => call("iszero", vec![operand.value]),
+                    UnaryOp::USub => {
+                        self.helper("checked_sub_int128");
+                        call("checked_sub_int128", vec![num(0), operand.value])
+                    }
+                    UnaryOp::UAdd => operand.value,
+                    UnaryOp::Invert => call("not", vec![operand.value]),
+                };
+
+                Lowered {
+                    prelude: operand.prelude,
+                    value,
+                }
+            }
+            Expr::Call { func, args } => {
+                let (prelude, values) = self.exprs(args);
+
+                let value = match &func.node {
+                    Expr::Attribute { attr, .. } => call(&function_name(attr), values),
+                    Expr::Name { name: "len" } => call("mload", values),
+                    _ => unreachable!("calls are checked"),
+                };
+
+                Lowered { prelude, value }
+            }
+            Expr::Num { .. } | Expr::Str { .. } => {
+                unreachable!("literals are checked")
+            }
+        }
+    }
+
+    fn block(&mut self, body: &[Spanned<FuncStmt>]) -> Block {
+        Block::new(body.iter().flat_map(|stmt| self.stmt(stmt)).collect())
+    }
+
+    /// Lower a revert with the reason `msg`.
+    fn revert(&mut self, msg: &Option<Spanned<Expr>>) -> Vec<Statement> {
+        match msg {
+            Some(msg) => {
+                let msg = self.expr(msg);
+                self.helper("revert_with_reason");
+
+                with_prelude(
+                    msg.prelude,
+                    vec![call("revert_with_reason", vec![msg.value]).into()],
+                )
+            }
+            None => vec![revert()],
+        }
+    }
+
+    fn stmt(&mut self, stmt: &Spanned<FuncStmt>) -> Vec<Statement> {
+        let mut stmts = vec![Statement::SourceLocation(Some(stmt.span))];
+
+        stmts.extend(match &stmt.node {
+            FuncStmt::Return { value } => match value {
+                Some(value) => {
+                    let value = self.expr(value);
+
+                    with_prelude(
+                        value.prelude,
+                        vec![
+                            Statement::assign(RETURN_VARIABLE, value.value),
+                            Statement::Leave,
+                        ],
+                    )
+                }
+                None => vec![Statement::Leave],
+            },
+            FuncStmt::VarDecl { name, value, .. } => {
+                let name = local_name(name);
+
+                match value {
+                    Some(value) => {
+                        let value = self.expr(value);
+
+                        if value.prelude.is_empty() {
+                            vec![Statement::declare(&name, value.value)]
+                        } else {
+                            let mut stmts = vec![Statement::VariableDeclaration {
+                                names: vec![name.clone()],
+                                value: None,
+                            }];
+                            stmts.extend(with_prelude(
+                                value.prelude,
+                                vec![Statement::assign(&name, value.value)],
+                            ));
+                            stmts
+                        }
+                    }
+                    None => {
+                        let zero = if in_memory(&self.functions.locals[&stmt.id]) {
+                            num(ZERO_POINTER)
+                        } else {
+                            num(0)
+                        };
+                        vec![Statement::declare(&name, zero)]
+                    }
+                }
+            }
+            FuncStmt::Assign { target, value } => {
+                let value = self.expr(value);
+
+                if let Expr::Name { name } = &target.node {
+                    return with_prelude(
+                        value.prelude,
+                        vec![Statement::assign(&local_name(name), value.value)],
+                    );
+                }
+
+                let (slot, typ) = self.slot(target);
+                let (prelude, mut values) = self.sequence(vec![value, slot]);
+                let slot = values.pop().unwrap();
+                let value = values.pop().unwrap();
+
+                let store = match typ {
+                    StorageType::Value(Type::Bytes { max_size })
+                    | StorageType::Value(Type::String { max_size }) => {
+                        self.helper("store_bytes");
+                        call("store_bytes", vec![slot, value, num(u64::from(max_size))])
+                    }
+                    _ => call("sstore", vec![slot, value]),
+                };
+                with_prelude(prelude, vec![store.into()])
+            }
+            FuncStmt::AugAssign { target, op, value } => {
+                let base = match self.typ(value) {
+                    _ if matches!(op, Operator::LShift | Operator::RShift) => Base::Uint256,
+                    Type::Base(base) => *base,
+                    _ => unreachable!("operands have base types"),
+                };
+
+                if let Expr::Name { name } = &target.node {
+                    let name = local_name(name);
+                    let value = self.expr(value);
+                    let result = self.binary(*op, base, id(&name), value.value);
+
+                    return with_prelude(value.prelude, vec![Statement::assign(&name, result)]);
+                }
+
+                // The slot and current value are computed before the value of
+                // the right hand side
+                let (slot, _) = self.slot(target);
+                let slot_temp = self.temp();
+                let current = self.temp();
+
+                let mut prelude = slot.prelude;
+                prelude.push(Statement::declare(&slot_temp, slot.value));
+                prelude.push(Statement::declare(
+                    &current,
+                    call("sload", vec![id(&slot_temp)]),
+                ));
+
+                let value = self.expr(value);
+                prelude.extend(value.prelude);
+                let result = self.binary(*op, base, id(&current), value.value);
+
+                vec![Statement::Block(Block::new({
+                    prelude.push(call("sstore", vec![id(&slot_temp), result]).into());
+                    prelude
+                }))]
+            }
+            FuncStmt::For { target, iter, body } => {
+                let args = match &iter.node {
+                    Expr::Call { args, .. } => args,
+                    _ => unreachable!("loops are checked"),
+                };
+                let signed = self.functions.locals[&stmt.id] == Type::Base(Base::Int128);
+
+                let items = match args.as_slice() {
+                    [stop] => vec![Lowered::pure(num(0)), self.expr(stop)],
+                    [start, stop] => vec![self.expr(start), self.expr(stop)],
+                    _ => unreachable!("loops are checked"),
+                };
+                let (mut prelude, mut values) = self.sequence(items);
+                let stop = values.pop().unwrap();
+                let start = values.pop().unwrap();
+
+                let end = self.temp();
+                let var = local_name(target);
+                let lt = if signed { "slt" } else { "lt" };
+
+                // The loop variable is less than the end of the range, so
+                // incrementing it cannot overflow
+                prelude.push(Statement::declare(&end, stop));
+                prelude.push(Statement::ForLoop {
+                    pre: Block::new(vec![Statement::declare(&var, start)]),
+                    condition: call(lt, vec![id(&var), id(&end)]),
+                    post: Block::new(vec![Statement::assign(
+                        &var,
+                        call("add", vec![id(&var), num(1)]),
+                    )]),
+                    body: self.block(body),
+                });
+
+                vec![Statement::Block(Block::new(prelude))]
+            }
+            FuncStmt::If {
+                test,
+                body,
+                or_else,
+            } => {
+                let test = self.expr(test);
+                let body = self.block(body);
+
+                let stmt = if or_else.is_empty() {
+                    Statement::If {
+                        condition: test.value,
+                        body,
+                    }
+                } else {
+                    Statement::Switch {
+                        expression: test.value,
+                        cases: vec![Case {
+                            literal: Literal::Number("0".to_string()),
+                            body: self.block(or_else),
+                        }],
+                        default: Some(body),
+                    }
+                };
+                with_prelude(test.prelude, vec![stmt])
+            }
+            FuncStmt::Assert { test, msg } => {
+                let test = self.expr(test);
+                let fail = self.revert(msg);
+
+                with_prelude(
+                    test.prelude,
+                    vec![Statement::if_(call("iszero", vec![test.value]), fail)],
+                )
+            }
+            FuncStmt::Raise { msg } => self.revert(msg),
+            FuncStmt::Log { event, args } => {
+                let (prelude, values) = self.exprs(args);
+
+                with_prelude(
+                    prelude,
+                    vec![call(&emit_function_name(event), values).into()],
+                )
+            }
+            FuncStmt::Expr { value } => {
+                let returns = self.functions.types.contains_key(&value.id);
+                let value = self.expr(value);
+                let call_stmt = if returns {
+                    call("pop", vec![value.value])
+                } else {
+                    value.value
+                };
+
+                with_prelude(value.prelude, vec![call_stmt.into()])
+            }
+            FuncStmt::Pass => vec![],
+            FuncStmt::Break => vec![Statement::Break],
+            FuncStmt::Continue => vec![Statement::Continue],
+        });
+
+        stmts
+    }
+}
+
+/// Lower the checked function `function` into a YUL function.  Storage fields
+/// are found in `fields` and the side tables of type checking in `functions`.
+/// Also returns the names of any runtime helper functions that are used.
+pub fn lower_function(
+    function: &CheckedFunction,
+    functions: &Functions,
+    fields: &FieldSlots,
+) -> (Statement, Vec<&'static str>) {
+    let mut lowering = Lowering {
+        functions,
+        fields,
+        helpers: vec![],
+        temps: 0,
+    };
+    let sig = &function.signature;
+
+    let params: Vec<_> = sig
+        .params
+        .iter()
+        .map(|(name, _)| local_name(name))
+        .collect();
+    let params: Vec<_> = params.iter().map(String::as_str).collect();
+    let returns: &[&str] = if sig.return_type.is_some() {
+        &[RETURN_VARIABLE]
+    } else {
+        &[]
+    };
+    let body = lowering.block(function.body);
+
+    (
+        Statement::function(&function_name(&sig.name), &params, returns, body.statements),
+        lowering.helpers,
+    )
+}
+
+/// Return the statement reverting if the function with the signature `sig`
+/// is called with value it does not accept.
+fn value_check(sig: &Signature) -> Option<Statement> {
+    if sig.mutability == StateMutability::Payable {
+        return None;
+    }
+
+    Some(Statement::if_(call("callvalue", vec![]), vec![revert()]))
+}
+
+/// Build the case of the dispatcher that calls the external function with the
+/// signature `sig`.  The arguments are decoded from calldata and the result is
+/// ABI encoded.  Also returns the names of any runtime helper functions that
+/// are used.
+pub fn dispatch_case(sig: &Signature) -> (Block, Vec<&'static str>) {
+    let mut body: Vec<Statement> = value_check(sig).into_iter().collect();
+    let mut helpers = vec![];
+
+    let mut args = vec![];
+    for (i, (_, typ)) in sig.params.iter().enumerate() {
+        let offset = num(4 + 32 * i as u64);
+        args.push(match typ {
+            Type::Base(base) => {
+                helpers.push(decoder_name(*base));
+                call(decoder_name(*base), vec![offset])
+            }
+            Type::Bytes { max_size } | Type::String { max_size } => {
+                helpers.push("decode_bytes");
+                call("decode_bytes", vec![offset, num(u64::from(*max_size))])
+            }
+            _ => unreachable!("signatures are checked"),
+        });
+    }
+    let result = call(&function_name(&sig.name), args);
+
+    match &sig.return_type {
+        None => {
+            body.push(result.into());
+            body.push(call("stop", vec![]).into());
+        }
+        Some(Type::Bytes { .. }) | Some(Type::String { .. }) => {
+            let content = call("add", vec![id("ptr"), num(32)]);
+            helpers.push("abi_encode_bytes");
+
+            body.extend(vec![
+                Statement::declare("result", result),
+                Statement::declare("ptr", call("mload", vec![num(64)])),
+                call("mstore", vec![id("ptr"), num(32)]).into(),
+                Statement::declare(
+                    "size",
+                    call("abi_encode_bytes", vec![id("result"), content]),
+                ),
+                call(
+                    "return",
+                    vec![id("ptr"), call("add", vec![num(32), id("size")])],
+                )
+                .into(),
+            ]);
+        }
+        Some(_) => {
+            body.push(call("mstore", vec![num(0), result]).into());
+            body.push(call("return", vec![num(0), num(32)]).into());
+        }
+    }
+
+    (Block::new(body), helpers)
+}
+
+/// Build the statements of the deployment code that call the constructor with
+/// the signature `sig`.  The arguments are appended to the code after the
+/// runtime object named `runtime_name` and are decoded from a copy in memory.
+/// Also returns the names of any runtime helper functions that are used.
+pub fn constructor_call(
+    sig: &Signature,
+    runtime_name: &str,
+) -> (Vec<Statement>, Vec<&'static str>) {
+    let mut body: Vec<Statement> = value_check(sig).into_iter().collect();
+    let mut helpers = vec!["allocate"];
+
+    let runtime_end = call(
+        "add",
+        vec![
+            call("dataoffset", vec![Expression::string(runtime_name)]),
+            call("datasize", vec![Expression::string(runtime_name)]),
+        ],
+    );
+    body.extend(vec![
+        Statement::declare("args_offset", runtime_end),
+        Statement::declare(
+            "size",
+            call("sub", vec![call("codesize", vec![]), id("args_offset")]),
+        ),
+        Statement::declare("args", call("allocate", vec![id("size")])),
+        call("codecopy", vec![id("args"), id("args_offset"), id("size")]).into(),
+        Statement::declare("end", call("add", vec![id("args"), id("size")])),
+    ]);
+
+    let mut args = vec![];
+    for (i, (_, typ)) in sig.params.iter().enumerate() {
+        let offset = call("add", vec![id("args"), num(32 * i as u64)]);
+        args.push(match typ {
+            Type::Base(base) => {
+                helpers.push(memory_decoder_name(*base));
+                call(memory_decoder_name(*base), vec![offset, id("end")])
+            }
+            Type::Bytes { max_size } | Type::String { max_size } => {
+                helpers.push("decode_bytes_from_memory");
+                call(
+                    "decode_bytes_from_memory",
+                    vec![offset, id("args"), id("end"), num(u64::from(*max_size))],
+                )
+            }
+            _ => unreachable!("signatures are checked"),
+        });
+    }
+    body.push(call(&function_name(&sig.name), args).into());
+
+    (body, helpers)
+}
@@ -0,0 +1,221 @@
+//! Lowering of public fields into the getters called through the dispatcher.
+//!
+//! Getters of maps decode one key per map from the outermost in and hash it
+//! with the slot of the map to find the slot of its value.  Getters of arrays
+//! then decode one `uint256` index per dimension from the outermost in and
+//! revert if an index is out of bounds.  Values of base types are returned as
+//! a single word.  Byte arrays and strings are ABI encoded from the slot
+//! holding their length and the slots holding their content.
+
+use num_bigint::BigInt;
+
+use vyper_analyzer::types::{
+    Base,
+    Type,
+};
+use vyper_parser::span::Span;
+use vyper_yul::ast::{
+    Block,
+    Expression,
+    Statement,
+};
+
+use crate::abi::codec::encode;
+use crate::abi::constant_value;
+use crate::errors::CompileError;
+use crate::storage::{
+    value_slots,
+    StorageType,
+};
+
+fn call(name: &str, args: Vec<Expression>) -> Expression {
+    Expression::call(name, args)
+}
+
+fn id(name: &str) -> Expression {
+    Expression::ident(name)
+}
+
+fn num(value: u64) -> Expression {
+    Expression::num(value)
+}
+
+/// Return the name of the YUL variable holding the argument at position `arg`
+/// of a getter.
+fn arg_name(arg: usize) -> String {
+    format!("arg{}", arg)
+}
+
+/// Return the name of the runtime helper decoding a value of the base type
+/// `base` from calldata.
+pub fn decoder_name(base: Base) -> &'static str {
+    match base {
+        Base::Address => "decode_address",
+        Base::Bool => "decode_bool",
+        Base::Bytes32 => "decode_bytes32",
+        Base::Decimal => "decode_decimal",
+        Base::Int128 => "decode_int128",
+        Base::Uint256 => "decode_uint256",
+    }
+}
+
+/// Build the body of the getter of the storage field of type `typ` stored from
+/// the slot `slot` on.  Also returns the names of any runtime helper functions
+/// that are used.
+pub fn storage_getter(slot: u64, typ: &StorageType) -> (Block, Vec<&'static str>) {
+    let mut body = vec![Statement::declare("slot", num(slot))];
+    let mut helpers = vec![];
+    let mut arg = 0;
+    let mut value = typ;
+
+    let mut item = loop {
+        let (key, inner) = match value {
+            StorageType::Value(typ) => break typ,
+            StorageType::Map { key, value } => (key, value),
+        };
+        let name = arg_name(arg);
+        let offset = num(4 + 32 * arg as u64);
+        // Keys are hashed as words, byte arrays and strings as the hash of
+        // their content
+        let (decoded, helper) = match key {
+            Type::Base(base) => (call(decoder_name(*base), vec![offset]), decoder_name(*base)),
+            Type::Bytes { max_size } | Type::String { max_size } => (
+                call(
+                    "hash_calldata_bytes",
+                    vec![offset, num(u64::from(*max_size))],
+                ),
+                "hash_calldata_bytes",
+            ),
+            _ => unreachable!("invalid map key type"),
+        };
+
+        body.extend(vec![
+            Statement::declare(&name, decoded),
+            call("mstore", vec![num(0), id("slot")]).into(),
+            call("mstore", vec![num(32), id(&name)]).into(),
+            Statement::assign("slot", call("keccak256", vec![num(0), num(64)])),
+        ]);
+        helpers.push(helper);
+
+        value = inner;
+        arg += 1;
+    };
+
+    while let Type::Array { inner, dimension } = item {
+        let index = arg_name(arg);
+        // The storage layout is computed with checked arithmetic, so offsets
+        // into a field cannot overflow
+        let item_slots = value_slots(inner).expect("storage field is too large");
+
+        body.push(Statement::declare(
+            &index,
+            call("decode_uint256", vec![num(4 + 32 * arg as u64)]),
+        ));
+        body.push(Statement::if_(
+            call(
+                "iszero",
+                vec![call("lt", vec![id(&index), num(u64::from(*dimension))])],
+            ),
+            vec![call("revert", vec![num(0), num(0)]).into()],
+        ));
+        body.push(Statement::assign(
+            "slot",
+            call(
+                "add",
+                vec![id("slot"), call("mul", vec![id(&index), num(item_slots)])],
+            ),
+        ));
+        helpers.push("decode_uint256");
+
+        item = inner;
+        arg += 1;
+    }
+
+    match item {
+        Type::Bytes { .. } | Type::String { .. } => {
+            let content = |offset: u64| call("add", vec![id("ptr"), num(offset)]);
+
+            body.extend(vec![
+                Statement::declare("ptr", call("mload", vec![num(64)])),
+                Statement::declare("len", call("sload", vec![id("slot")])),
+                call("mstore", vec![id("ptr"), num(32)]).into(),
+                call("mstore", vec![content(32), id("len")]).into(),
+                Statement::ForLoop {
+                    pre: Block::new(vec![Statement::declare("i", num(0))]),
+                    condition: call("lt", vec![id("i"), id("len")]),
+                    post: Block::new(vec![Statement::assign(
+                        "i",
+                        call("add", vec![id("i"), num(32)]),
+                    )]),
+                    body: Block::new(vec![call(
+                        "mstore",
+                        vec![
+                            call("add", vec![content(64), id("i")]),
+                            call(
+                                "sload",
+                                vec![call(
+                                    "add",
+                                    vec![
+                                        call("add", vec![id("slot"), num(1)]),
+                                        call("div", vec![id("i"), num(32)]),
+                                    ],
+                                )],
+                            ),
+                        ],
+                    )
+                    .into()]),
+                },
+                // Zero the padding after the last partial word
+                call(
+                    "mstore",
+                    vec![call("add", vec![content(64), id("len")]), num(0)],
+                )
+                .into(),
+                call(
+                    "return",
+                    vec![
+                        id("ptr"),
+                        call(
+                            "add",
+                            vec![
+                                num(64),
+                                call(
+                                    "mul",
+                                    vec![
+                                        call(
+                                            "div",
+                                            vec![call("add", vec![id("len"), num(31)]), num(32)],
+                                        ),
+                                        num(32),
+                                    ],
+                                ),
+                            ],
+                        ),
+                    ],
+                )
+                .into(),
+            ]);
+        }
+        _ => {
+            body.push(call("mstore", vec![num(0), call("sload", vec![id("slot")])]).into());
+            body.push(call("return", vec![num(0), num(32)]).into());
+        }
+    }
+
+    (Block::new(body), helpers)
+}
+
+/// Build the body of the getter of the constant of type `typ` with the value
+/// `value` defined at `span`.
+///
+/// Fails if the constant does not have a numeric type or if its value is out
+/// of bounds for its type.
+pub fn constant_getter(typ: &Type, value: &BigInt, span: Span) -> Result<Block, CompileError> {
+    let value = constant_value(typ, value, span)?;
+    let word = encode(std::slice::from_ref(typ), &[value]).expect("constant value out of bounds");
+
+    Ok(Block::new(vec![
+        call("mstore", vec![num(0), Expression::hex_num(&word)]).into(),
+        call("return", vec![num(0), num(32)]).into(),
+    ]))
+}
@@ -0,0 +1,243 @@
+//! Lowering of parsed Vyper modules into YUL objects.
+//!
+//! A module is compiled into an object with deployment code that returns the
+//! runtime code held in a nested `<name>_deployed` object.  The runtime code
+//! initializes the free memory pointer, dispatches on the function selector in
+//! calldata to the getters of public fields and to external functions, and
+//! contains a YUL function for each function definition and for each event
+//! that emits its logs.  If the module defines a constructor, the deployment
+//! code calls it with the arguments appended to the code before returning the
+//! runtime code.
+//! Code generated from a definition is annotated with the definition's source
+//! location so that source maps can be produced during assembly.
+
+use std::collections::HashSet;
+
+use vyper_analyzer::const_eval::module_constants;
+use vyper_analyzer::types::resolve_type_desc;
+use vyper_parser::ast::{
+    Module,
+    ModuleStmt,
+};
+use vyper_yul::ast::{
+    Block,
+    Expression,
+    Object,
+    Statement,
+};
+
+use crate::abi::public_functions;
+use crate::errors::CompileError;
+use crate::functions::{
+    check_functions,
+    CONSTRUCTOR,
+};
+use crate::signatures::selector;
+use crate::storage::{
+    compute_layout,
+    storage_fields,
+};
+use crate::yul::functions::FieldSlots;
+
+pub mod events;
+pub mod functions;
+pub mod getters;
+pub mod runtime;
+
+/// The memory address at which the free memory pointer is stored.
+const FREE_MEMORY_POINTER: u64 = 64;
+
+/// The first memory address available for allocation.
+const FREE_MEMORY_START: u64 = 128;
+
+/// Return the name of the YUL object holding the runtime code of the contract
+/// `name`.
+pub fn runtime_object_name(name: &str) -> String {
+    format!("{}_deployed", name)
+}
+
+/// Build deployment code that copies the runtime object with the given name
+/// into memory and returns it.
+fn deploy_code(runtime_name: &str) -> Block {
+    let datasize = Expression::call("datasize", vec![Expression::string(runtime_name)]);
+    let dataoffset = Expression::call("dataoffset", vec![Expression::string(runtime_name)]);
+
+    Block::new(vec![
+        Expression::call(
+            "datacopy",
+            vec![Expression::num(0), dataoffset, datasize.clone()],
+        )
+        .into(),
+        Expression::call("return", vec![Expression::num(0), datasize]).into(),
+    ])
+}
+
+/// Append the definitions of the runtime helpers `helpers` and of the helpers
+/// they call to `statements`, each once.
+fn include_helpers(statements: &mut Vec<Statement>, helpers: Vec<&'static str>) {
+    // Runtime helpers are shared between definitions and have no location
+    let mut included = HashSet::new();
+    let mut pending = helpers;
+    pending.reverse();
+
+    while let Some(helper) = pending.pop() {
+        if included.is_empty() {
+            statements.push(Statement::SourceLocation(None));
+        }
+        if included.insert(helper) {
+            statements.push(runtime::helper(helper).expect("unknown runtime helper"));
+            pending.extend(runtime::dependencies(helper).iter().rev());
+        }
+    }
+}
+
+/// Compile the module `module` into a YUL object named `name`.
+pub fn compile_module(name: &str, module: &Module) -> Result<Object, CompileError> {
+    let init_memory: Statement = Expression::call(
+        "mstore",
+        vec![
+            Expression::num(FREE_MEMORY_POINTER),
+            Expression::num(FREE_MEMORY_START),
+        ],
+    )
+    .into();
+    let mut statements = vec![init_memory.clone()];
+
+    let env = module_constants(module)?;
+    let fields = storage_fields(module)?;
+    let layout = compute_layout(&fields)?;
+    let checked = check_functions(module, &env, &fields)?;
+    let field_slots: FieldSlots = fields
+        .iter()
+        .zip(&layout)
+        .map(|(field, location)| (field.name.as_str(), (location.slot, &field.typ)))
+        .collect();
+    let mut helpers = vec![];
+
+    // Public functions are dispatched to in order of definition
+    let public = public_functions(module, &env)?;
+    let mut cases = vec![];
+
+    for stmt in &module.body {
+        let func = match public.iter().find(|(_, span)| *span == stmt.span) {
+            Some((func, _)) => func,
+            None => continue,
+        };
+
+        let mut body = vec![Statement::SourceLocation(Some(stmt.span))];
+
+        match &stmt.node {
+            ModuleStmt::FieldDef { name, typ, .. } => {
+                // Fields without a storage location are constants
+                match fields
+                    .iter()
+                    .zip(&layout)
+                    .find(|(field, _)| field.span == stmt.span)
+                {
+                    Some((field, location)) => {
+                        let (getter, getter_helpers) =
+                            getters::storage_getter(location.slot, &field.typ);
+                        body.extend(getter.statements);
+                        helpers.extend(getter_helpers);
+                    }
+                    None => {
+                        let typ = resolve_type_desc(typ, &env)?;
+                        let getter = getters::constant_getter(&typ, &env[name], stmt.span)?;
+                        body.extend(getter.statements);
+                    }
+                }
+            }
+            ModuleStmt::FuncDef { name, .. } => {
+                let sig = &checked.get(name).expect("functions are checked").signature;
+                let (case, case_helpers) = functions::dispatch_case(sig);
+                body.extend(case.statements);
+                helpers.extend(case_helpers);
+            }
+            ModuleStmt::EventDef { .. } => continue,
+        }
+
+        cases.push((selector(&func.signature()), Block::new(body)));
+    }
+    statements.extend(runtime::dispatcher(cases));
+
+    let mut event_names = HashSet::new();
+    let mut emit_functions = vec![];
+    let mut event_helpers = vec![];
+    let mut constructor = None;
+
+    for stmt in &module.body {
+        match &stmt.node {
+            ModuleStmt::EventDef { name, fields } => {
+                if !event_names.insert(*name) {
+                    return Err(CompileError::new("duplicate event definition", stmt.span));
+                }
+
+                let (func, func_helpers) = events::emit_function(name, fields, &env)?;
+
+                emit_functions.push(Statement::SourceLocation(Some(stmt.span)));
+                emit_functions.push(func);
+                event_helpers.extend(func_helpers);
+            }
+            ModuleStmt::FieldDef { .. } => {}
+            ModuleStmt::FuncDef { name, .. } => {
+                let function = checked.get(name).expect("functions are checked");
+                let (func, func_helpers) =
+                    functions::lower_function(function, &checked, &field_slots);
+
+                // The constructor is only part of the deployment code
+                if function.signature.is_constructor() {
+                    constructor = Some((stmt.span, func, func_helpers));
+                    continue;
+                }
+                statements.push(Statement::SourceLocation(Some(stmt.span)));
+                statements.push(func);
+                helpers.extend(func_helpers);
+            }
+        }
+    }
+    statements.extend(emit_functions.iter().cloned());
+    helpers.extend(event_helpers.iter().copied());
+    include_helpers(&mut statements, helpers);
+
+    let runtime_name = runtime_object_name(name);
+    let code = match constructor {
+        Some((span, func, mut deploy_helpers)) => {
+            let sig = &checked
+                .get(CONSTRUCTOR)
+                .expect("functions are checked")
+                .signature;
+            let (call, call_helpers) = functions::constructor_call(sig, &runtime_name);
+            deploy_helpers.extend(call_helpers);
+            deploy_helpers.extend(event_helpers);
+
+            let mut deploy = vec![init_memory, Statement::SourceLocation(Some(span))];
+            deploy.extend(call);
+            deploy.push(Statement::SourceLocation(None));
+            deploy.extend(deploy_code(&runtime_name).statements);
+            deploy.push(Statement::SourceLocation(Some(span)));
+            deploy.push(func);
+            deploy.extend(emit_functions);
+            include_helpers(&mut deploy, deploy_helpers);
+            Block::new(deploy)
+        }
+        None => deploy_code(&runtime_name),
+    };
+
+    Ok(Object {
+        name: name.to_string(),
+        code,
+        objects: vec![Object {
+            name: runtime_name,
+            code: Block::new(statements),
+            objects: vec![],
+            data: vec![],
+        }],
+        data: vec![],
+    })
+}
+
+/// Compile the module `module` into YUL source code for an object named
+/// `name`.
+pub fn compile_module_to_string(name: &str, module: &Module) -> Result<String, CompileError> {
+    Ok(compile_module(name, module)?.to_string())
+}
@@ -0,0 +1,761 @@
+//! Library of YUL helper functions used by compiled contracts.
+//!
+//! Helpers are referenced by name during lowering and only the helpers
+//! actually used by a contract are included in its runtime code.
+
+use vyper_yul::ast::{
+    Block,
+    Case,
+    Expression,
+    Literal,
+    Statement,
+};
+
+/// The selector of `Error(string)`, which encodes revert reasons.
+const ERROR_SELECTOR: &str = "0x08c379a0";
+
+/// The largest value of an `int128`.
+const MAX_INT128: &str = "0x7fffffffffffffffffffffffffffffff";
+
+/// The largest value of a `decimal` scaled by its number of decimal places.
+const MAX_DECIMAL: &str = "0x12a05f1fffffffffffffffffffffffffdabf41c00";
+
+/// The smallest value of a `decimal` scaled by its number of decimal places.
+const MIN_DECIMAL: &str = "0xfffffffffffffffffffffffed5fa0e0000000000000000000000000000000000";
+
+fn call(name: &str, args: Vec<Expression>) -> Expression {
+    Expression::call(name, args)
+}
+
+fn id(name: &str) -> Expression {
+    Expression::ident(name)
+}
+
+fn num(value: u64) -> Expression {
+    Expression::num(value)
+}
+
+/// Revert with no return data.
+pub fn revert() -> Statement {
+    call("revert", vec![num(0), num(0)]).into()
+}
+
+/// Revert if `condition` is non-zero.
+fn revert_if(condition: Expression) -> Statement {
+    Statement::if_(condition, vec![revert()])
+}
+
+/// Expression that is non-zero if `value` lies outside the range of an
+/// `int128`.
+fn out_of_int128_range(value: Expression) -> Expression {
+    call(
+        "or",
+        vec![
+            call(
+                "sgt",
+                vec![
+                    value.clone(),
+                    Expression::Literal(Literal::Number(MAX_INT128.into())),
+                ],
+            ),
+            call(
+                "slt",
+                vec![
+                    value,
+                    call(
+                        "not",
+                        vec![Expression::Literal(Literal::Number(MAX_INT128.into()))],
+                    ),
+                ],
+            ),
+        ],
+    )
+}
+
+/// A function of two arguments `a` and `b` returning `r`.
+fn binary_function(name: &str, body: Vec<Statement>) -> Statement {
+    Statement::function(name, &["a", "b"], &["r"], body)
+}
+
+/// A function that decodes a word from calldata at the byte offset `offset`
+/// and reverts if the word is not a valid encoding of a value of some type.
+fn decoder(name: &str, is_invalid: Option<Expression>) -> Statement {
+    let mut body = vec![
+        revert_if(call(
+            "lt",
+            vec![
+                call("calldatasize", vec![]),
+                call("add", vec![id("offset"), num(32)]),
+            ],
+        )),
+        Statement::assign("v", call("calldataload", vec![id("offset")])),
+    ];
+    if let Some(is_invalid) = is_invalid {
+        body.push(revert_if(is_invalid));
+    }
+
+    Statement::function(name, &["offset"], &["v"], body)
+}
+
+/// A function that decodes a word from memory at the address `offset` and
+/// reverts if the word does not end before `end` or is not a valid encoding of
+/// a value of some type.
+fn memory_decoder(name: &str, is_invalid: Option<Expression>) -> Statement {
+    let mut body = vec![
+        revert_if(call(
+            "gt",
+            vec![call("add", vec![id("offset"), num(32)]), id("end")],
+        )),
+        Statement::assign("v", call("mload", vec![id("offset")])),
+    ];
+    if let Some(is_invalid) = is_invalid {
+        body.push(revert_if(is_invalid));
+    }
+
+    Statement::function(name, &["offset", "end"], &["v"], body)
+}
+
+/// Return an expression that is non-zero if the word `v` is not a valid
+/// encoding of a value of the base type named `typ`, `Some(None)` if every
+/// word is valid, or `None` if there is no such type.
+fn word_check(typ: &str) -> Option<Option<Expression>> {
+    let check = match typ {
+        "address" => call("shr", vec![num(160), id("v")]),
+        "bool" => call("gt", vec![id("v"), num(1)]),
+        "bytes32" | "uint256" => return Some(None),
+        "decimal" => call(
+            "or",
+            vec![
+                call(
+                    "sgt",
+                    vec![
+                        id("v"),
+                        Expression::Literal(Literal::Number(MAX_DECIMAL.into())),
+                    ],
+                ),
+                call(
+                    "slt",
+                    vec![
+                        id("v"),
+                        Expression::Literal(Literal::Number(MIN_DECIMAL.into())),
+                    ],
+                ),
+            ],
+        ),
+        "int128" => call(
+            "iszero",
+            vec![call(
+                "eq",
+                vec![id("v"), call("signextend", vec![num(15), id("v")])],
+            )],
+        ),
+        _ => return None,
+    };
+
+    Some(Some(check))
+}
+
+/// The number of bytes taken by `len` bytes padded to a whole number of
+/// words.
+fn padded(len: Expression) -> Expression {
+    call(
+        "mul",
+        vec![
+            call("div", vec![call("add", vec![len, num(31)]), num(32)]),
+            num(32),
+        ],
+    )
+}
+
+/// Return the definition of the helper function with the given name or `None`
+/// if no such helper exists.
+pub fn helper(name: &str) -> Option<Statement> {
+    let a = || id("a");
+    let b = || id("b");
+    let r = || id("r");
+
+    let func = match name {
+        // Checked arithmetic for `uint256` values
+        "checked_add_uint256" => binary_function(
+            name,
+            vec![
+                Statement::assign("r", call("add", vec![a(), b()])),
+                revert_if(call("lt", vec![r(), a()])),
+            ],
+        ),
+        "checked_sub_uint256" => binary_function(
+            name,
+            vec![
+                revert_if(call("lt", vec![a(), b()])),
+                Statement::assign("r", call("sub", vec![a(), b()])),
+            ],
+        ),
+        "checked_mul_uint256" => binary_function(
+            name,
+            vec![
+                Statement::assign("r", call("mul", vec![a(), b()])),
+                revert_if(call(
+                    "iszero",
+                    vec![call(
+                        "or",
+                        vec![
+                            call("iszero", vec![a()]),
+                            call("eq", vec![call("div", vec![r(), a()]), b()]),
+                        ],
+                    )],
+                )),
+            ],
+        ),
+        "checked_div_uint256" => binary_function(
+            name,
+            vec![
+                revert_if(call("iszero", vec![b()])),
+                Statement::assign("r", call("div", vec![a(), b()])),
+            ],
+        ),
+        "checked_mod_uint256" => binary_function(
+            name,
+            vec![
+                revert_if(call("iszero", vec![b()])),
+                Statement::assign("r", call("mod", vec![a(), b()])),
+            ],
+        ),
+        // Checked arithmetic for `int128` values.  Operands are assumed to be
+        // valid `int128` values so that no intermediate result can overflow
+        // 256 bits.
+        "checked_add_int128" => binary_function(
+            name,
+            vec![
+                Statement::assign("r", call("add", vec![a(), b()])),
+                revert_if(out_of_int128_range(r())),
+            ],
+        ),
+        "checked_sub_int128" => binary_function(
+            name,
+            vec![
+                Statement::assign("r", call("sub", vec![a(), b()])),
+                revert_if(out_of_int128_range(r())),
+            ],
+        ),
+        "checked_mul_int128" => binary_function(
+            name,
+            vec![
+                Statement::assign("r", call("mul", vec![a(), b()])),
+                revert_if(out_of_int128_range(r())),
+            ],
+        ),
+        "checked_div_int128" => binary_function(
+            name,
+            vec![
+                revert_if(call("iszero", vec![b()])),
+                Statement::assign("r", call("sdiv", vec![a(), b()])),
+                revert_if(out_of_int128_range(r())),
+            ],
+        ),
+        "checked_mod_int128" => binary_function(
+            name,
+            vec![
+                revert_if(call("iszero", vec![b()])),
+                Statement::assign("r", call("smod", vec![a(), b()])),
+            ],
+        ),
+        // Decoding of byte arrays and strings into memory.  Values in memory
+        // are held as a length word followed by the content.
+        "decode_bytes" => {
+            let start = || call("add", vec![num(4), id("o")]);
+            let content = || call("add", vec![start(), num(32)]);
+
+            Statement::function(
+                name,
+                &["offset", "max_size"],
+                &["ptr"],
+                vec![
+                    revert_if(call(
+                        "lt",
+                        vec![
+                            call("calldatasize", vec![]),
+                            call("add", vec![id("offset"), num(32)]),
+                        ],
+                    )),
+                    Statement::declare("o", call("calldataload", vec![id("offset")])),
+                    // Bound the offset first so that adding to it cannot wrap
+                    revert_if(call("gt", vec![id("o"), call("calldatasize", vec![])])),
+                    revert_if(call("gt", vec![content(), call("calldatasize", vec![])])),
+                    Statement::declare("len", call("calldataload", vec![start()])),
+                    revert_if(call("gt", vec![id("len"), id("max_size")])),
+                    revert_if(call(
+                        "gt",
+                        vec![
+                            call("add", vec![content(), id("len")]),
+                            call("calldatasize", vec![]),
+                        ],
+                    )),
+                    Statement::assign(
+                        "ptr",
+                        call(
+                            "allocate",
+                            vec![call("add", vec![num(32), padded(id("len"))])],
+                        ),
+                    ),
+                    call("mstore", vec![id("ptr"), id("len")]).into(),
+                    call(
+                        "calldatacopy",
+                        vec![call("add", vec![id("ptr"), num(32)]), content(), id("len")],
+                    )
+                    .into(),
+                ],
+            )
+        }
+        // The arguments of the constructor are decoded from a copy in memory
+        // that ends before `end`.  Byte arrays and strings are returned as a
+        // pointer into the copy, whose head is at `start`.
+        "decode_bytes_from_memory" => {
+            let content = || {
+                call(
+                    "add",
+                    vec![call("add", vec![id("start"), id("o")]), num(32)],
+                )
+            };
+
+            Statement::function(
+                name,
+                &["offset", "start", "end", "max_size"],
+                &["ptr"],
+                vec![
+                    revert_if(call(
+                        "gt",
+                        vec![call("add", vec![id("offset"), num(32)]), id("end")],
+                    )),
+                    Statement::declare("o", call("mload", vec![id("offset")])),
+                    // Bound the offset first so that adding to it cannot wrap
+                    revert_if(call(
+                        "gt",
+                        vec![id("o"), call("sub", vec![id("end"), id("start")])],
+                    )),
+                    revert_if(call("gt", vec![content(), id("end")])),
+                    Statement::assign("ptr", call("add", vec![id("start"), id("o")])),
+                    Statement::declare("len", call("mload", vec![id("ptr")])),
+                    revert_if(call("gt", vec![id("len"), id("max_size")])),
+                    revert_if(call(
+                        "gt",
+                        vec![call("add", vec![content(), id("len")]), id("end")],
+                    )),
+                ],
+            )
+        }
+        // Hash of the content of a byte array or string argument whose head is
+        // at the byte offset `offset` of calldata.  Reverts if the argument is
+        // longer than `max_size` bytes.
+        "hash_calldata_bytes" => {
+            let start = || call("add", vec![num(4), id("o")]);
+            let content = || call("add", vec![start(), num(32)]);
+
+            Statement::function(
+                name,
+                &["offset", "max_size"],
+                &["h"],
+                vec![
+                    revert_if(call(
+                        "lt",
+                        vec![
+                            call("calldatasize", vec![]),
+                            call("add", vec![id("offset"), num(32)]),
+                        ],
+                    )),
+                    Statement::declare("o", call("calldataload", vec![id("offset")])),
+                    // Bound the offset first so that adding to it cannot wrap
+                    revert_if(call("gt", vec![id("o"), call("calldatasize", vec![])])),
+                    revert_if(call("gt", vec![content(), call("calldatasize", vec![])])),
+                    Statement::declare("len", call("calldataload", vec![start()])),
+                    revert_if(call("gt", vec![id("len"), id("max_size")])),
+                    revert_if(call(
+                        "gt",
+                        vec![
+                            call("add", vec![content(), id("len")]),
+                            call("calldatasize", vec![]),
+                        ],
+                    )),
+                    Statement::declare("ptr", call("mload", vec![num(64)])),
+                    call("calldatacopy", vec![id("ptr"), content(), id("len")]).into(),
+                    Statement::assign("h", call("keccak256", vec![id("ptr"), id("len")])),
+                ],
+            )
+        }
+        // ABI encoding of a byte array stored in memory as a length word
+        // followed by its content.  Returns the number of bytes written.
+        "abi_encode_bytes" => Statement::function(
+            name,
+            &["src", "dst"],
+            &["size"],
+            vec![
+                Statement::declare("len", call("mload", vec![id("src")])),
+                call("mstore", vec![id("dst"), id("len")]).into(),
+                Statement::ForLoop {
+                    pre: Block::new(vec![Statement::declare("i", num(0))]),
+                    condition: call("lt", vec![id("i"), id("len")]),
+                    post: Block::new(vec![Statement::assign(
+                        "i",
+                        call("add", vec![id("i"), num(32)]),
+                    )]),
+                    body: Block::new(vec![call(
+                        "mstore",
+                        vec![
+                            call("add", vec![call("add", vec![id("dst"), num(32)]), id("i")]),
+                            call(
+                                "mload",
+                                vec![call(
+                                    "add",
+                                    vec![call("add", vec![id("src"), num(32)]), id("i")],
+                                )],
+                            ),
+                        ],
+                    )
+                    .into()]),
+                },
+                // Zero the padding after the last partial word
+                call(
+                    "mstore",
+                    vec![
+                        call(
+                            "add",
+                            vec![call("add", vec![id("dst"), num(32)]), id("len")],
+                        ),
+                        num(0),
+                    ],
+                )
+                .into(),
+                Statement::assign(
+                    "size",
+                    call(
+                        "add",
+                        vec![
+                            num(32),
+                            call(
+                                "mul",
+                                vec![
+                                    call(
+                                        "div",
+                                        vec![call("add", vec![id("len"), num(31)]), num(32)],
+                                    ),
+                                    num(32),
+                                ],
+                            ),
+                        ],
+                    ),
+                ),
+            ],
+        ),
+        "checked_pow_uint256" | "checked_pow_int128" => {
+            let (mul, mut body) = if name == "checked_pow_uint256" {
+                ("checked_mul_uint256", vec![])
+            } else {
+                // Negative exponents are not defined
+                (
+                    "checked_mul_int128",
+                    vec![revert_if(call("slt", vec![b(), num(0)]))],
+                )
+            };
+
+            // Square and multiply.  The base is not squared after the last bit
+            // of the exponent so that it cannot overflow needlessly.
+            body.push(Statement::assign("r", num(1)));
+            body.push(Statement::ForLoop {
+                pre: Block::default(),
+                condition: b(),
+                post: Block::new(vec![Statement::assign("b", call("shr", vec![num(1), b()]))]),
+                body: Block::new(vec![
+                    Statement::if_(
+                        call("and", vec![b(), num(1)]),
+                        vec![Statement::assign("r", call(mul, vec![r(), a()]))],
+                    ),
+                    Statement::if_(
+                        call("gt", vec![b(), num(1)]),
+                        vec![Statement::assign("a", call(mul, vec![a(), a()]))],
+                    ),
+                ]),
+            });
+
+            binary_function(name, body)
+        }
+        // Allocation of `size` bytes of memory
+        "allocate" => Statement::function(
+            name,
+            &["size"],
+            &["ptr"],
+            vec![
+                Statement::assign("ptr", call("mload", vec![num(64)])),
+                call(
+                    "mstore",
+                    vec![num(64), call("add", vec![id("ptr"), id("size")])],
+                )
+                .into(),
+            ],
+        ),
+        // The slot of the value for the key encoded as the word `key` in the
+        // map at the slot `slot`
+        "map_slot" => Statement::function(
+            name,
+            &["slot", "key"],
+            &["s"],
+            vec![
+                call("mstore", vec![num(0), id("slot")]).into(),
+                call("mstore", vec![num(32), id("key")]).into(),
+                Statement::assign("s", call("keccak256", vec![num(0), num(64)])),
+            ],
+        ),
+        // The slot of the item at `index` of the array at the slot `slot`.
+        // Reverts if the index is out of bounds.
+        "array_slot" => Statement::function(
+            name,
+            &["slot", "index", "dimension", "item_slots"],
+            &["s"],
+            vec![
+                revert_if(call(
+                    "iszero",
+                    vec![call("lt", vec![id("index"), id("dimension")])],
+                )),
+                Statement::assign(
+                    "s",
+                    call(
+                        "add",
+                        vec![id("slot"), call("mul", vec![id("index"), id("item_slots")])],
+                    ),
+                ),
+            ],
+        ),
+        // Hash of the content of a byte array or string in memory
+        "hash_bytes" => Statement::function(
+            name,
+            &["ptr"],
+            &["h"],
+            vec![Statement::assign(
+                "h",
+                call(
+                    "keccak256",
+                    vec![
+                        call("add", vec![id("ptr"), num(32)]),
+                        call("mload", vec![id("ptr")]),
+                    ],
+                ),
+            )],
+        ),
+        // Copy of the byte array or string held in storage from the slot
+        // `slot` on into memory
+        "load_bytes" => Statement::function(
+            name,
+            &["slot"],
+            &["ptr"],
+            vec![
+                Statement::declare("len", call("sload", vec![id("slot")])),
+                Statement::assign(
+                    "ptr",
+                    call(
+                        "allocate",
+                        vec![call("add", vec![num(32), padded(id("len"))])],
+                    ),
+                ),
+                call("mstore", vec![id("ptr"), id("len")]).into(),
+                Statement::ForLoop {
+                    pre: Block::new(vec![Statement::declare("i", num(0))]),
+                    condition: call("lt", vec![id("i"), id("len")]),
+                    post: Block::new(vec![Statement::assign(
+                        "i",
+                        call("add", vec![id("i"), num(32)]),
+                    )]),
+                    body: Block::new(vec![call(
+                        "mstore",
+                        vec![
+                            call("add", vec![call("add", vec![id("ptr"), num(32)]), id("i")]),
+                            call(
+                                "sload",
+                                vec![call(
+                                    "add",
+                                    vec![
+                                        call("add", vec![id("slot"), num(1)]),
+                                        call("div", vec![id("i"), num(32)]),
+                                    ],
+                                )],
+                            ),
+                        ],
+                    )
+                    .into()]),
+                },
+            ],
+        ),
+        // Store the byte array or string in memory at `ptr` in storage from
+        // the slot `slot` on.  All slots up to the maximum size are written,
+        // so no trace of a longer value held before is left.
+        "store_bytes" => {
+            let rest = || call("sub", vec![id("len"), id("i")]);
+            // Keeps the first `rest` bytes of a word
+            let mask = call(
+                "not",
+                vec![call(
+                    "sub",
+                    vec![
+                        call(
+                            "shl",
+                            vec![
+                                call("mul", vec![num(8), call("sub", vec![num(32), rest()])]),
+                                num(1),
+                            ],
+                        ),
+                        num(1),
+                    ],
+                )],
+            );
+            let item = |i: Expression| {
+                call(
+                    "add",
+                    vec![
+                        call("add", vec![id("slot"), num(1)]),
+                        call("div", vec![i, num(32)]),
+                    ],
+                )
+            };
+
+            Statement::function(
+                name,
+                &["slot", "ptr", "max_size"],
+                &[],
+                vec![
+                    Statement::declare("len", call("mload", vec![id("ptr")])),
+                    call("sstore", vec![id("slot"), id("len")]).into(),
+                    Statement::ForLoop {
+                        pre: Block::new(vec![Statement::declare("i", num(0))]),
+                        condition: call("lt", vec![id("i"), id("max_size")]),
+                        post: Block::new(vec![Statement::assign(
+                            "i",
+                            call("add", vec![id("i"), num(32)]),
+                        )]),
+                        body: Block::new(vec![
+                            Statement::declare("w", num(0)),
+                            Statement::if_(
+                                call("lt", vec![id("i"), id("len")]),
+                                vec![
+                                    Statement::assign(
+                                        "w",
+                                        call(
+                                            "mload",
+                                            vec![call(
+                                                "add",
+                                                vec![
+                                                    call("add", vec![id("ptr"), num(32)]),
+                                                    id("i"),
+                                                ],
+                                            )],
+                                        ),
+                                    ),
+                                    // Clear the bytes after the end of the
+                                    // content in the last word
+                                    Statement::if_(
+                                        call("lt", vec![rest(), num(32)]),
+                                        vec![Statement::assign(
+                                            "w",
+                                            call("and", vec![id("w"), mask]),
+                                        )],
+                                    ),
+                                ],
+                            ),
+                            call("sstore", vec![item(id("i")), id("w")]).into(),
+                        ]),
+                    },
+                ],
+            )
+        }
+        // Revert with the reason held in memory at `msg`, encoded as a call to
+        // `Error(string)`
+        "revert_with_reason" => Statement::function(
+            name,
+            &["msg"],
+            &[],
+            vec![
+                Statement::declare("ptr", call("mload", vec![num(64)])),
+                call(
+                    "mstore",
+                    vec![
+                        id("ptr"),
+                        call(
+                            "shl",
+                            vec![
+                                num(224),
+                                Expression::Literal(Literal::Number(ERROR_SELECTOR.into())),
+                            ],
+                        ),
+                    ],
+                )
+                .into(),
+                call(
+                    "mstore",
+                    vec![call("add", vec![id("ptr"), num(4)]), num(32)],
+                )
+                .into(),
+                Statement::declare(
+                    "size",
+                    call(
+                        "abi_encode_bytes",
+                        vec![id("msg"), call("add", vec![id("ptr"), num(36)])],
+                    ),
+                ),
+                call(
+                    "revert",
+                    vec![id("ptr"), call("add", vec![num(36), id("size")])],
+                )
+                .into(),
+            ],
+        ),
+        _ => {
+            let typ = name.strip_prefix("decode_")?;
+            match typ.strip_suffix("_from_memory") {
+                Some(typ) => memory_decoder(name, word_check(typ)?),
+                None => decoder(name, word_check(typ)?),
+            }
+        }
+    };
+
+    Some(func)
+}
+
+/// Return the names of the helper functions called by the helper function with
+/// the given name.
+pub fn dependencies(name: &str) -> &'static [&'static str] {
+    match name {
+        "checked_pow_uint256" => &["checked_mul_uint256"],
+        "checked_pow_int128" => &["checked_mul_int128"],
+        "decode_bytes" | "load_bytes" => &["allocate"],
+        "revert_with_reason" => &["abi_encode_bytes"],
+        _ => &[],
+    }
+}
+
+/// Build the statements that dispatch a call to one of the given bodies based
+/// on the 4-byte function selector at the start of calldata.  Each body is
+/// expected to end execution.  Calls that do not match any selector revert.
+pub fn dispatcher(functions: Vec<(u32, Block)>) -> Vec<Statement> {
+    if functions.is_empty() {
+        return vec![revert()];
+    }
+
+    let cases = functions
+        .into_iter()
+        .map(|(selector, body)| Case {
+            literal: Literal::Number(format!("0x{:08x}", selector)),
+            body,
+        })
+        .collect();
+
+    vec![
+        Statement::if_(
+            call(
+                "iszero",
+                vec![call("lt", vec![call("calldatasize", vec![]), num(4)])],
+            ),
+            vec![Statement::Switch {
+                expression: call("shr", vec![num(224), call("calldataload", vec![num(0)])]),
+                cases,
+                default: None,
+            }],
+        ),
+        revert(),
+    ]
+}
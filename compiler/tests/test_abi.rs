@@ -0,0 +1,159 @@
+extern crate wasm_bindgen_test;
+
+#[macro_use]
+extern crate vyper_test_utils;
+
+use nom::error::VerboseError;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use vyper_analyzer::types::{
+    Base,
+    Type,
+};
+use vyper_compiler::abi::{
+    build_abi,
+    build_abi_json,
+    getter,
+    AbiItem,
+    Function,
+    Param,
+    StateMutability,
+};
+use vyper_compiler::errors::CompileError;
+use vyper_compiler::storage::StorageType;
+use vyper_parser::ast::Module;
+use vyper_parser::parsers::{
+    file_input,
+    get_parse_tokens,
+};
+use vyper_parser::span::Span;
+
+/// Parse the module in `source` and build its JSON ABI.
+fn abi_json(source: &str) -> Result<String, CompileError> {
+    let tokens = get_parse_tokens(source).unwrap();
+    let (_, module) = file_input::<VerboseError<_>>(&tokens[..]).unwrap();
+
+    build_abi_json(&module.node)
+}
+
+fn assert_fixture_abi(filename: &str, input: &str, expected: &str) {
+    assert_strings_eq!(
+        abi_json(input).unwrap(),
+        expected,
+        "\nABI did not match for {}",
+        filename,
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_abi_fixtures() {
+    do_with_fixtures!(
+        assert_fixture_abi,
+        "fixtures/abi/erc20_events.json",
+        "fixtures/abi/functions.json",
+        "fixtures/abi/mixed_events.json",
+        "fixtures/abi/public_getters.json",
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_abi_empty() {
+    assert_eq!(build_abi(&Module { body: vec![] }), Ok(vec![]));
+    assert_eq!(abi_json(""), Ok("[]".to_string()));
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_abi_errors() {
+    assert_eq!(
+        abi_json("event Foo:\n    a: uint8\n"),
+        Err(CompileError::new("unknown type", Span::new(18, 23)))
+    );
+    assert_eq!(
+        abi_json("owner: public(uint8)\n"),
+        Err(CompileError::new("unknown type", Span::new(7, 20)))
+    );
+
+    // The getters `f8491()` and `f130736()` share the selector 0x62018627.
+    // Only public fields have getters.
+    assert_eq!(
+        abi_json("f8491: public(uint256)\nowner: address\nf130736: public(bool)\n"),
+        Err(CompileError::new(
+            "function selector 0x62018627 of f130736() collides with f8491()",
+            Span::new(38, 59)
+        )
+        .with_label("f8491() is defined here", Span::new(0, 22)))
+    );
+    assert!(abi_json("f8491: public(uint256)\nf130736: bool\n").is_ok());
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_getter() {
+    let grid = Type::Array {
+        inner: Box::new(Type::Array {
+            inner: Box::new(Type::Base(Base::Decimal)),
+            dimension: 3,
+        }),
+        dimension: 2,
+    };
+    let func = getter("grid", &StorageType::Value(grid));
+
+    let inputs: Vec<_> = func
+        .inputs
+        .iter()
+        .map(|p| (p.name.as_str(), p.typ.as_str()))
+        .collect();
+    assert_eq!(inputs, vec![("arg0", "uint256"), ("arg1", "uint256")]);
+    assert_eq!(func.outputs[0].typ, "fixed168x10");
+    assert_eq!(func.signature(), "grid(uint256,uint256)");
+    assert_eq!(func.state_mutability, StateMutability::View);
+
+    assert!(
+        getter("total", &StorageType::Value(Type::Base(Base::Uint256)))
+            .inputs
+            .is_empty()
+    );
+
+    // Map keys come before array indexes
+    let allowed = StorageType::Map {
+        key: Type::Base(Base::Address),
+        value: Box::new(StorageType::Map {
+            key: Type::String { max_size: 8 },
+            value: Box::new(StorageType::Value(Type::Array {
+                inner: Box::new(Type::Base(Base::Bool)),
+                dimension: 2,
+            })),
+        }),
+    };
+    let func = getter("allowed", &allowed);
+    assert_eq!(func.signature(), "allowed(address,string,uint256)");
+    assert_eq!(func.inputs[2].name, "arg2");
+    assert_eq!(func.outputs[0].typ, "bool");
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_function_serialization() {
+    let func = AbiItem::Function(Function {
+        name: "balanceOf".to_string(),
+        inputs: vec![Param {
+            name: "owner".to_string(),
+            typ: "address".to_string(),
+        }],
+        outputs: vec![Param {
+            name: "".to_string(),
+            typ: "uint256".to_string(),
+        }],
+        state_mutability: StateMutability::View,
+        payable: false,
+        constant: true,
+    });
+
+    assert_eq!(
+        serde_json::to_string(&func).unwrap(),
+        r#"{"type":"function","name":"balanceOf","inputs":[{"name":"owner","type":"address"}],"outputs":[{"name":"","type":"uint256"}],"stateMutability":"view","payable":false,"constant":true}"#,
+    );
+}
@@ -0,0 +1,363 @@
+extern crate wasm_bindgen_test;
+
+use num_bigint::{
+    BigInt,
+    BigUint,
+};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use vyper_analyzer::types::{
+    Base,
+    Type,
+};
+use vyper_compiler::abi::codec::*;
+use vyper_compiler::errors::AbiError;
+
+/// Return the word holding the number given as hex digits in `hex`.
+fn num(hex: &str) -> String {
+    format!("{:0>64}", hex)
+}
+
+/// Return the word holding the bytes of `text` aligned to the left.
+fn text(text: &str) -> String {
+    let hex: String = text.bytes().map(|b| format!("{:02x}", b)).collect();
+
+    format!("{:0<64}", hex)
+}
+
+/// Return the bytes given as hex digits in the concatenated words `words`.
+fn words(words: &[String]) -> Vec<u8> {
+    let hex = words.concat();
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+fn uint(value: u64) -> Value {
+    Value::Uint(BigUint::from(value))
+}
+
+fn string_type(max_size: u32) -> Type {
+    Type::String { max_size }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_encode_decode() {
+    let examples = vec![
+        // Static values are encoded in place
+        (
+            vec![Type::Base(Base::Uint256), Type::Base(Base::Bool)],
+            vec![uint(69), Value::Bool(true)],
+            vec![num("45"), num("1")],
+        ),
+        (
+            vec![
+                Type::Base(Base::Int128),
+                Type::Base(Base::Decimal),
+                Type::Base(Base::Address),
+                Type::Base(Base::Bytes32),
+            ],
+            vec![
+                Value::Int(BigInt::from(-1)),
+                Value::Decimal(BigInt::from(25_000_000_000u64)),
+                Value::Address([0x11; 20]),
+                Value::Bytes32([0xab; 32]),
+            ],
+            vec![
+                "f".repeat(64),
+                num("5d21dba00"),
+                num(&"11".repeat(20)),
+                "ab".repeat(32),
+            ],
+        ),
+        // Dynamic values are encoded in the tail
+        (
+            vec![Type::Bytes { max_size: 10 }, Type::Base(Base::Bool)],
+            vec![Value::Bytes(b"dave".to_vec()), Value::Bool(true)],
+            vec![num("40"), num("1"), num("4"), text("dave")],
+        ),
+        (
+            vec![string_type(40)],
+            vec![Value::String("a".repeat(33))],
+            vec![num("20"), num("21"), text(&"a".repeat(32)), text("a")],
+        ),
+        (
+            vec![string_type(0)],
+            vec![Value::String("".to_string())],
+            vec![num("20"), num("0")],
+        ),
+        // Static lists and tuples are encoded in place
+        (
+            vec![
+                Type::Tuple(vec![
+                    Type::Base(Base::Uint256),
+                    Type::Array {
+                        inner: Box::new(Type::Base(Base::Bool)),
+                        dimension: 2,
+                    },
+                ]),
+                Type::Base(Base::Uint256),
+            ],
+            vec![
+                Value::Tuple(vec![
+                    uint(1),
+                    Value::Array(vec![Value::Bool(true), Value::Bool(false)]),
+                ]),
+                uint(2),
+            ],
+            vec![num("1"), num("1"), num("0"), num("2")],
+        ),
+        // Lists of dynamic values are dynamic with offsets relative to the
+        // start of the list
+        (
+            vec![Type::Array {
+                inner: Box::new(string_type(3)),
+                dimension: 2,
+            }],
+            vec![Value::Array(vec![
+                Value::String("one".to_string()),
+                Value::String("two".to_string()),
+            ])],
+            vec![
+                num("20"),
+                num("40"),
+                num("80"),
+                num("3"),
+                text("one"),
+                num("3"),
+                text("two"),
+            ],
+        ),
+        // Structs are encoded as tuples
+        (
+            vec![
+                Type::Base(Base::Uint256),
+                Type::Struct {
+                    name: "Person".to_string(),
+                    fields: vec![
+                        ("id".to_string(), Type::Base(Base::Uint256)),
+                        ("name".to_string(), string_type(10)),
+                    ],
+                },
+            ],
+            vec![
+                uint(3),
+                Value::Tuple(vec![uint(7), Value::String("x".to_string())]),
+            ],
+            vec![
+                num("3"),
+                num("40"),
+                num("7"),
+                num("40"),
+                num("1"),
+                text("x"),
+            ],
+        ),
+    ];
+
+    for (types, values, expected) in examples {
+        let encoded = encode(&types, &values).unwrap();
+
+        assert_eq!(encoded, words(&expected), "{:?}", values);
+        assert_eq!(decode(&types, &encoded), Ok(values));
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_head_size() {
+    let static_tuple = Type::Tuple(vec![
+        Type::Base(Base::Bool),
+        Type::Array {
+            inner: Box::new(Type::Base(Base::Uint256)),
+            dimension: 3,
+        },
+    ]);
+
+    assert_eq!(head_size(&Type::Base(Base::Address)), Ok(32));
+    assert_eq!(head_size(&string_type(100)), Ok(32));
+    assert_eq!(head_size(&static_tuple), Ok(128));
+    assert_eq!(
+        head_size(&Type::Tuple(vec![static_tuple, string_type(1)])),
+        Ok(32)
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_head_size_overflow() {
+    let huge = |inner| Type::Array {
+        inner: Box::new(inner),
+        dimension: u32::MAX,
+    };
+    let typ = huge(huge(huge(Type::Base(Base::Uint256))));
+
+    assert_eq!(head_size(&typ), Err(AbiError::new("type too large")));
+    assert_eq!(
+        encode(std::slice::from_ref(&typ), &[Value::Array(vec![])]),
+        Err(AbiError::new("type too large"))
+    );
+    assert_eq!(
+        decode(&[typ], &[0; 32]),
+        Err(AbiError::new("data too short"))
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_decimal_bounds() {
+    let scale = BigInt::from(10_000_000_000u64);
+    let (min, max) = decimal_bounds();
+
+    assert_eq!(min, -(BigInt::from(1u8) << 127) * &scale);
+    assert_eq!(max, ((BigInt::from(1u8) << 127) - 1) * &scale);
+
+    // Scaled decimals exceed the range of an int128
+    for value in [min, max] {
+        let values = vec![Value::Decimal(value)];
+        let types = [Type::Base(Base::Decimal)];
+        let encoded = encode(&types, &values).unwrap();
+
+        assert_eq!(decode(&types, &encoded), Ok(values));
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_encode_errors() {
+    let examples = vec![
+        (
+            Type::Base(Base::Uint256),
+            Value::Uint(BigUint::from(1u8) << 256),
+            "value out of range",
+        ),
+        (
+            Type::Base(Base::Int128),
+            Value::Int(BigInt::from(1u8) << 127),
+            "value out of range",
+        ),
+        (
+            Type::Base(Base::Decimal),
+            Value::Decimal(decimal_bounds().0 - 1),
+            "value out of range",
+        ),
+        (
+            Type::Base(Base::Decimal),
+            Value::Decimal(decimal_bounds().1 + 1),
+            "value out of range",
+        ),
+        (
+            Type::Bytes { max_size: 3 },
+            Value::Bytes(b"dave".to_vec()),
+            "value exceeds maximum size",
+        ),
+        (
+            Type::Base(Base::Uint256),
+            Value::Int(BigInt::from(1)),
+            "value does not match type",
+        ),
+        (
+            Type::Array {
+                inner: Box::new(Type::Base(Base::Bool)),
+                dimension: 2,
+            },
+            Value::Array(vec![Value::Bool(true)]),
+            "wrong number of values",
+        ),
+        // Item types of large lists are not materialized
+        (
+            Type::Array {
+                inner: Box::new(Type::Base(Base::Uint256)),
+                dimension: u32::MAX,
+            },
+            Value::Array(vec![uint(1)]),
+            "wrong number of values",
+        ),
+    ];
+
+    for (typ, value, msg) in examples {
+        assert_eq!(
+            encode(&[typ], std::slice::from_ref(&value)),
+            Err(AbiError::new(msg)),
+            "{:?}",
+            value
+        );
+    }
+
+    assert_eq!(
+        encode(&[Type::Base(Base::Bool)], &[]),
+        Err(AbiError::new("wrong number of values"))
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_decode_errors() {
+    let examples = vec![
+        (
+            Type::Base(Base::Uint256),
+            vec![num("1")[2..].to_string()],
+            "data too short",
+        ),
+        (Type::Base(Base::Bool), vec![num("2")], "value out of range"),
+        (
+            Type::Base(Base::Address),
+            vec![format!("01{}", &num("1")[2..])],
+            "value out of range",
+        ),
+        // Values of type int128 must be sign extended
+        (
+            Type::Base(Base::Int128),
+            vec![num(&format!("1{}", "0".repeat(32)))],
+            "value out of range",
+        ),
+        (
+            Type::Base(Base::Decimal),
+            vec![num(&format!("1{}", "0".repeat(42)))],
+            "value out of range",
+        ),
+        (
+            string_type(10),
+            vec![num("20"), num("1"), text("\u{ff}")[2..].to_string() + "00"],
+            "invalid UTF-8 string",
+        ),
+        (
+            string_type(10),
+            vec![num("40")],
+            "offset or length out of range",
+        ),
+        (
+            string_type(10),
+            vec![num("20"), num("40")],
+            "offset or length out of range",
+        ),
+        (
+            string_type(1),
+            vec![num("20"), num("2"), text("ab")],
+            "value exceeds maximum size",
+        ),
+        (string_type(10), vec![num("20"), num("2")], "data too short"),
+        (
+            string_type(10),
+            vec![num("20"), num("2"), text("abc")],
+            "non-zero padding",
+        ),
+        (
+            Type::Bytes { max_size: 40 },
+            vec![num("20"), num("21"), text(&"a".repeat(32)), num("1")],
+            "non-zero padding",
+        ),
+    ];
+
+    for (typ, data, msg) in examples {
+        assert_eq!(
+            decode(&[typ], &words(&data)),
+            Err(AbiError::new(msg)),
+            "{:?}",
+            data
+        );
+    }
+}
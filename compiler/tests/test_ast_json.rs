@@ -0,0 +1,250 @@
+use std::collections::HashSet;
+
+use jsonschema::JSONSchema;
+use nom::error::VerboseError;
+use serde_json::{
+    json,
+    Value,
+};
+
+use vyper_compiler::ast_json::{
+    build_ast_json,
+    AstJsonBuilder,
+    AST_JSON_VERSION,
+};
+use vyper_parser::parsers::{
+    const_expr,
+    file_input,
+    get_parse_tokens,
+};
+
+const SCHEMA: &str = include_str!("../schemas/ast.schema.json");
+
+const SOURCE: &str = r#"# Événements
+event Transfer:
+    sender: indexed(address)
+    value: uint256[2][3 * 1]
+
+event Empty:
+    memo: bytes[100]
+
+MAX: constant(uint256) = 3
+owner: address
+"#;
+
+fn schema() -> Value {
+    serde_json::from_str(SCHEMA).unwrap()
+}
+
+/// Assert that `instance` is valid according to `schema`.
+fn assert_valid(schema: &Value, instance: &Value) {
+    let compiled = JSONSchema::compile(schema).unwrap();
+
+    let result = compiled.validate(instance).map_err(|errors| {
+        errors
+            .map(|e| format!("{} at {}", e, e.instance_path))
+            .collect::<Vec<_>>()
+    });
+
+    if let Err(errors) = result {
+        panic!("invalid JSON AST:\n{}", errors.join("\n"));
+    }
+}
+
+/// Collect the ids of all nodes in `value`.
+fn collect_ids(value: &Value, ids: &mut Vec<u64>) {
+    match value {
+        Value::Object(map) => {
+            if let (Some(id), Some(_)) = (map.get("id"), map.get("nodeType")) {
+                ids.push(id.as_u64().unwrap());
+            }
+            for child in map.values() {
+                collect_ids(child, ids);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                collect_ids(item, ids);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn module_json(source: &str) -> Value {
+    let tokens = get_parse_tokens(source).unwrap();
+    let (_, module) = file_input::<VerboseError<_>>(&tokens[..]).unwrap();
+
+    serde_json::from_str(&build_ast_json("token.vy", source, &module)).unwrap()
+}
+
+#[test]
+fn test_schema_is_valid() {
+    // Schemas are checked against their meta-schema when compiled
+    let schema = schema();
+    assert!(JSONSchema::compile(&schema).is_ok());
+    assert_eq!(
+        schema["properties"]["version"]["pattern"],
+        "^2\\.[0-9]+\\.[0-9]+$"
+    );
+}
+
+#[test]
+fn test_module_json() {
+    let ast = module_json(SOURCE);
+    assert_valid(&schema(), &ast);
+
+    assert_eq!(ast["version"], AST_JSON_VERSION);
+    assert_eq!(ast["sourcePath"], "token.vy");
+
+    let root = &ast["root"];
+    assert_eq!(root["nodeType"], "Module");
+
+    let transfer = &root["body"][0];
+    assert_eq!(transfer["nodeType"], "EventDef");
+    assert_eq!(transfer["name"], "Transfer");
+    assert_eq!(
+        transfer["loc"],
+        json!({"start": {"line": 2, "column": 1}, "end": {"line": 4, "column": 29}})
+    );
+
+    let value = &transfer["fields"][1];
+    assert_eq!(value["nodeType"], "EventField");
+    assert_eq!(value["name"], "value");
+    let typ = &value["typeDesc"];
+    assert_eq!(typ["nodeType"], "TypeDesc");
+    assert_eq!(typ["base"], "uint256");
+    assert_eq!(typ["dimensions"][0]["nodeType"], "Num");
+    assert_eq!(typ["dimensions"][0]["value"], "2");
+    assert_eq!(typ["dimensions"][1]["nodeType"], "BinOp");
+    assert_eq!(typ["dimensions"][1]["operator"], "*");
+    assert_eq!(
+        &SOURCE[typ["src"]["start"].as_u64().unwrap() as usize
+            ..typ["src"]["end"].as_u64().unwrap() as usize],
+        "uint256[2][3 * 1]"
+    );
+    assert_eq!(
+        transfer["fields"][0]["typeDesc"]["annotations"],
+        json!(["indexed"])
+    );
+
+    let max = &root["body"][2];
+    assert_eq!(max["nodeType"], "FieldDef");
+    assert_eq!(max["name"], "MAX");
+    assert_eq!(max["typeDesc"]["annotations"], json!(["constant"]));
+    assert_eq!(max["value"]["value"], "3");
+    assert_eq!(root["body"][3]["name"], "owner");
+    assert_eq!(root["body"][3]["value"], Value::Null);
+
+    // Ids are the unique ids of the nodes
+    let mut ids = vec![];
+    collect_ids(&ast, &mut ids);
+    assert_eq!(ids.len(), 19);
+    assert_eq!(ids.iter().collect::<HashSet<_>>().len(), ids.len());
+
+    let tokens = get_parse_tokens(SOURCE).unwrap();
+    let (_, module) = file_input::<VerboseError<_>>(&tokens[..]).unwrap();
+    let ast = AstJsonBuilder::new(SOURCE).module(&module);
+    assert_eq!(ast["id"], json!(module.id));
+    assert_eq!(ast["body"][1]["id"], json!(module.node.body[1].id));
+}
+
+#[test]
+fn test_empty_module_json() {
+    let ast = module_json("");
+    assert_valid(&schema(), &ast);
+    assert_eq!(ast["root"]["body"], json!([]));
+}
+
+#[test]
+fn test_const_expr_json() {
+    let source = "-(a + 2) * b ** 3";
+    let tokens = get_parse_tokens(source).unwrap();
+    let (_, expr) = const_expr::<VerboseError<_>>(&tokens[..]).unwrap();
+    let value = AstJsonBuilder::new(source).const_expr(&expr);
+
+    // Validate against the definition of constant expressions only
+    let mut schema = schema();
+    schema["$ref"] = json!("#/definitions/ConstExpr");
+    for key in &["type", "required", "properties", "additionalProperties"] {
+        schema.as_object_mut().unwrap().remove(*key);
+    }
+    assert_valid(&schema, &value);
+
+    assert_eq!(value["nodeType"], "BinOp");
+    assert_eq!(value["operator"], "*");
+    assert_eq!(value["left"]["nodeType"], "UnaryOp");
+    assert_eq!(value["left"]["operator"], "-");
+    assert_eq!(value["left"]["operand"]["right"]["value"], "2");
+    assert_eq!(value["right"]["operator"], "**");
+    assert_eq!(value["right"]["left"]["name"], "b");
+
+    // A node missing a required field is rejected
+    let mut invalid = value.clone();
+    invalid["right"].as_object_mut().unwrap().remove("operator");
+    assert!(!JSONSchema::compile(&schema).unwrap().is_valid(&invalid));
+
+    // Calls to built-in functions list their arguments
+    let source = "max(a, 2)";
+    let tokens = get_parse_tokens(source).unwrap();
+    let (_, expr) = const_expr::<VerboseError<_>>(&tokens[..]).unwrap();
+    let value = AstJsonBuilder::new(source).const_expr(&expr);
+
+    assert_valid(&schema, &value);
+    assert_eq!(value["nodeType"], "Call");
+    assert_eq!(value["func"], "max");
+    assert_eq!(value["args"][0]["name"], "a");
+    assert_eq!(value["args"][1]["value"], "2");
+}
+
+#[test]
+fn test_func_def_json() {
+    let source = r#"@external
+def transfer(_to: address, _value: uint256) -> bool:
+    for i in range(3):
+        if not self.paused[i] or i >= 2:
+            continue
+        else:
+            break
+    self.balances[msg.sender] -= _value
+    assert _value > 0, "no value"
+    log Transfer(msg.sender, _to, _value)
+    return True
+"#;
+    let ast = module_json(source);
+    assert_valid(&schema(), &ast);
+
+    let func = &ast["root"]["body"][0];
+    assert_eq!(func["nodeType"], "FuncDef");
+    assert_eq!(func["name"], "transfer");
+    assert_eq!(func["decorators"], json!(["external"]));
+    assert_eq!(func["args"][1]["nodeType"], "FuncArg");
+    assert_eq!(func["args"][1]["typeDesc"]["base"], "uint256");
+    assert_eq!(func["returnType"]["base"], "bool");
+    assert_eq!(func["loc"]["start"], json!({"line": 1, "column": 1}));
+
+    let body = &func["body"];
+    let for_loop = &body[0];
+    assert_eq!(for_loop["nodeType"], "For");
+    assert_eq!(for_loop["target"], "i");
+    assert_eq!(for_loop["iter"]["func"]["name"], "range");
+    let test = &for_loop["body"][0]["test"];
+    assert_eq!(test["nodeType"], "BoolOp");
+    assert_eq!(test["operator"], "or");
+    assert_eq!(test["left"]["operand"]["nodeType"], "Subscript");
+    assert_eq!(test["left"]["operand"]["value"]["attr"], "paused");
+    assert_eq!(test["right"]["nodeType"], "Compare");
+    assert_eq!(for_loop["body"][0]["orElse"][0]["nodeType"], "Break");
+
+    assert_eq!(body[1]["nodeType"], "AugAssign");
+    assert_eq!(body[1]["operator"], "-");
+    assert_eq!(body[2]["msg"]["nodeType"], "Str");
+    assert_eq!(body[2]["msg"]["value"], "\"no value\"");
+    assert_eq!(body[3]["event"], "Transfer");
+    assert_eq!(body[3]["args"].as_array().unwrap().len(), 3);
+    assert_eq!(body[4]["value"]["name"], "True");
+
+    let mut ids = vec![];
+    collect_ids(&ast, &mut ids);
+    assert_eq!(ids.iter().collect::<HashSet<_>>().len(), ids.len());
+}
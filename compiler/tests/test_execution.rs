@@ -0,0 +1,876 @@
+extern crate wasm_bindgen_test;
+
+use nom::error::VerboseError;
+use num_bigint::{
+    BigInt,
+    BigUint,
+};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use vyper_analyzer::types::{
+    Base,
+    Type,
+};
+use vyper_compiler::abi::codec::{
+    decode,
+    encode,
+    Value,
+};
+use vyper_compiler::interpreter::Interpreter;
+use vyper_compiler::signatures::{
+    event_topic,
+    keccak256,
+    selector,
+};
+use vyper_compiler::yul::compile_module;
+use vyper_evm::interpreter::Status;
+use vyper_evm::state::Log;
+use vyper_evm::vm::{
+    Outcome,
+    Transaction,
+    Vm,
+};
+use vyper_parser::parsers::{
+    file_input,
+    get_parse_tokens,
+};
+use vyper_yul::assembler::assemble;
+use vyper_yul::ast::Object;
+use vyper_yul::parsers::block;
+use vyper_yul::tokenizer::tokenize;
+
+const SENDER: [u8; 20] = [0x11; 20];
+
+/// Compile the module in `source` into a YUL object.
+fn compile(source: &str) -> Object {
+    let tokens = get_parse_tokens(source).unwrap();
+    let (_, module) = file_input::<VerboseError<_>>(&tokens[..]).unwrap();
+
+    compile_module("Test", &module.node).unwrap()
+}
+
+/// Insert the YUL statements in `driver` into the runtime code of `object`
+/// after the free memory pointer is initialized and before calls are
+/// dispatched.  This allows event emit functions to be called directly.
+fn with_driver(mut object: Object, driver: &str) -> Object {
+    let source = format!("{{ {} stop() }}", driver);
+    let tokens = tokenize(&source).unwrap();
+    let (_, driver) = block::<VerboseError<_>>(&tokens[..]).unwrap();
+
+    let statements = &mut object.objects[0].code.statements;
+    let rest = statements.split_off(1);
+    statements.extend(driver.node.statements);
+    statements.extend(rest);
+
+    object
+}
+
+/// Deploy the object `object` and call it without data.
+fn deploy_and_call(object: &Object) -> (Outcome, [u8; 20]) {
+    let mut vm = Vm::new();
+    let deployed = vm.deploy(SENDER, &assemble(object).unwrap());
+    assert!(deployed.is_success(), "{:?}", deployed);
+
+    let address = deployed.address.unwrap();
+    (vm.call(SENDER, address, &[]), address)
+}
+
+/// Return the word holding the bytes of `text` aligned to the left as hex
+/// digits.
+fn text(text: &str) -> String {
+    let hex: String = text.bytes().map(|b| format!("{:02x}", b)).collect();
+
+    format!("{:0<64}", hex)
+}
+
+fn address_word(byte: u8) -> [u8; 32] {
+    let mut word = [0; 32];
+    word[12..].copy_from_slice(&[byte; 20]);
+
+    word
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_dispatcher_rejects_calls() {
+    let object = compile("event Greet:\n    name: bytes32\n");
+    let (outcome, _) = deploy_and_call(&object);
+
+    assert_eq!(outcome.status, Status::Revert);
+    assert!(outcome.output.is_empty());
+    assert_eq!(outcome.revert_reason(), None);
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_emit_static_event() {
+    let object = compile(
+        r#"
+event Transfer:
+    sender: indexed(address)
+    receiver: indexed(address)
+    value: uint256
+"#,
+    );
+    let object = with_driver(
+        object,
+        &format!(
+            "emit_Transfer(0x{}, 0x{}, 1000)",
+            "aa".repeat(20),
+            "bb".repeat(20)
+        ),
+    );
+    let (outcome, address) = deploy_and_call(&object);
+
+    assert!(outcome.is_success(), "{:?}", outcome);
+    assert_eq!(outcome.logs.len(), 1);
+
+    let Log {
+        address: log_address,
+        topics,
+        data,
+    } = &outcome.logs[0];
+
+    assert_eq!(*log_address, address);
+    assert_eq!(
+        topics,
+        &vec![
+            event_topic("Transfer(address,address,uint256)"),
+            address_word(0xaa),
+            address_word(0xbb),
+        ]
+    );
+    assert_eq!(
+        decode(&[Type::Base(Base::Uint256)], data),
+        Ok(vec![Value::Uint(BigUint::from(1000u32))])
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_emit_dynamic_event() {
+    let object = compile(
+        r#"
+event Note:
+    author: indexed(address)
+    topic: indexed(string[32])
+    count: int128
+    memo: bytes[100]
+    flag: bool
+    title: string[64]
+"#,
+    );
+
+    // Byte arrays and strings are passed as pointers to a length word followed
+    // by the content
+    let object = with_driver(
+        object,
+        &format!(
+            r#"
+            mstore(0x1000, 2)
+            mstore(0x1020, 0x{})
+            mstore(0x2000, 4)
+            mstore(0x2020, 0x{})
+            mstore(0x3000, 5)
+            mstore(0x3020, 0x{})
+            emit_Note(0x{}, 0x1000, sub(0, 5), 0x2000, 1, 0x3000)
+            "#,
+            text("hi"),
+            text("memo"),
+            text("title"),
+            "aa".repeat(20),
+        ),
+    );
+    let (outcome, _) = deploy_and_call(&object);
+
+    assert!(outcome.is_success(), "{:?}", outcome);
+    assert_eq!(outcome.logs.len(), 1);
+
+    let log = &outcome.logs[0];
+    assert_eq!(
+        log.topics,
+        vec![
+            event_topic("Note(address,string,int128,bytes,bool,string)"),
+            address_word(0xaa),
+            keccak256(b"hi"),
+        ]
+    );
+    assert_eq!(
+        decode(
+            &[
+                Type::Base(Base::Int128),
+                Type::Bytes { max_size: 100 },
+                Type::Base(Base::Bool),
+                Type::String { max_size: 64 },
+            ],
+            &log.data
+        ),
+        Ok(vec![
+            Value::Int(BigInt::from(-5)),
+            Value::Bytes(b"memo".to_vec()),
+            Value::Bool(true),
+            Value::String("title".to_string()),
+        ])
+    );
+}
+
+/// Return calldata calling the function with the signature `signature` with
+/// the `uint256` arguments `args`.
+fn calldata(signature: &str, args: &[u64]) -> Vec<u8> {
+    let mut data = selector(signature).to_be_bytes().to_vec();
+    for arg in args {
+        data.extend_from_slice(&[0; 24]);
+        data.extend_from_slice(&arg.to_be_bytes());
+    }
+
+    data
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_getters() {
+    let object = compile(
+        r#"
+LIMIT: public(constant(int128)) = -5
+owner: public(address)
+balances: public(uint256[3][2])
+name: public(string[40])
+secret: uint256
+"#,
+    );
+    let mut vm = Vm::new();
+    let deployed = vm.deploy(SENDER, &assemble(&object).unwrap());
+    assert!(deployed.is_success(), "{:?}", deployed);
+    let address = deployed.address.unwrap();
+
+    // `owner` occupies slot 0, `balances` slots 1 to 6 with the items of
+    // `balances[i]` starting at slot 1 + 3 * i, and `name` slots 7 to 9
+    let name = "Hello, Vyper! This string is long.";
+    let mut content = name.as_bytes().to_vec();
+    content.resize(64, 0);
+    for (slot, word) in [
+        (0, address_word(0xaa).to_vec()),
+        (6, vec![42]),
+        (7, vec![name.len() as u8]),
+        (8, content[..32].to_vec()),
+        (9, content[32..].to_vec()),
+        (10, vec![7]),
+    ] {
+        vm.state.set_storage(
+            &address,
+            BigUint::from(slot as u32),
+            BigUint::from_bytes_be(&word),
+        );
+    }
+
+    let mut call = |data: Vec<u8>| vm.call(SENDER, address, &data);
+    let uint256 = Type::Base(Base::Uint256);
+
+    let outcome = call(calldata("LIMIT()", &[]));
+    assert_eq!(
+        decode(&[Type::Base(Base::Int128)], &outcome.output),
+        Ok(vec![Value::Int(BigInt::from(-5))])
+    );
+
+    let outcome = call(calldata("owner()", &[]));
+    assert_eq!(
+        decode(&[Type::Base(Base::Address)], &outcome.output),
+        Ok(vec![Value::Address([0xaa; 20])])
+    );
+
+    let outcome = call(calldata("balances(uint256,uint256)", &[1, 2]));
+    assert!(outcome.is_success(), "{:?}", outcome);
+    assert_eq!(
+        decode(std::slice::from_ref(&uint256), &outcome.output),
+        Ok(vec![Value::Uint(BigUint::from(42u8))])
+    );
+    let outcome = call(calldata("balances(uint256,uint256)", &[0, 0]));
+    assert_eq!(
+        decode(&[uint256], &outcome.output),
+        Ok(vec![Value::Uint(BigUint::from(0u8))])
+    );
+
+    let outcome = call(calldata("name()", &[]));
+    assert_eq!(outcome.output.len(), 128);
+    assert_eq!(
+        decode(&[Type::String { max_size: 40 }], &outcome.output),
+        Ok(vec![Value::String(name.to_string())])
+    );
+
+    // Indexes out of bounds, missing indexes, and fields that are not public
+    // are rejected
+    for data in [
+        calldata("balances(uint256,uint256)", &[2, 0]),
+        calldata("balances(uint256,uint256)", &[0, 3]),
+        calldata("balances(uint256,uint256)", &[1]),
+        calldata("secret()", &[]),
+    ] {
+        assert_eq!(call(data).status, Status::Revert);
+    }
+}
+
+/// Return calldata calling the function with the signature `signature` with
+/// the arguments `args` of the types `types`.
+fn encoded_calldata(signature: &str, types: &[Type], args: &[Value]) -> Vec<u8> {
+    let mut data = selector(signature).to_be_bytes().to_vec();
+    data.extend(encode(types, args).unwrap());
+
+    data
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_map_getters() {
+    let source = r#"
+total: public(uint256)
+balances: public(map(address, uint256))
+allowances: public(map(address, map(address, uint256[2])))
+names: public(map(string[8], bytes32))
+prices: public(map(decimal, int128))
+"#;
+    let tokens = get_parse_tokens(source).unwrap();
+    let (_, module) = file_input::<VerboseError<_>>(&tokens[..]).unwrap();
+    let object = compile(source);
+
+    // Values are stored by the interpreter so that the getters read them
+    // from the slots that the interpreter hashes keys to
+    let mut interpreter = Interpreter::new(&module.node).unwrap();
+    let uint = |value: u32| Value::Uint(BigUint::from(value));
+    let stored = vec![
+        ("balances", vec![Value::Address([0xaa; 20])], uint(5)),
+        (
+            "allowances",
+            vec![
+                Value::Address([0xaa; 20]),
+                Value::Address([0xbb; 20]),
+                uint(1),
+            ],
+            uint(7),
+        ),
+        (
+            "names",
+            vec![Value::String("vyper".to_string())],
+            Value::Bytes32([0xcc; 32]),
+        ),
+        (
+            "prices",
+            vec![Value::Decimal(BigInt::from(-25_000_000_000i64))],
+            Value::Int(BigInt::from(-3)),
+        ),
+    ];
+    for (name, keys, value) in &stored {
+        interpreter.store(name, keys, value.clone()).unwrap();
+    }
+
+    let mut vm = Vm::new();
+    let deployed = vm.deploy(SENDER, &assemble(&object).unwrap());
+    assert!(deployed.is_success(), "{:?}", deployed);
+    let address = deployed.address.unwrap();
+    for (slot, word) in interpreter.storage() {
+        vm.state.set_storage(&address, slot, word);
+    }
+
+    let mut call = |data: Vec<u8>| vm.call(SENDER, address, &data);
+    let address_type = Type::Base(Base::Address);
+    let uint256 = Type::Base(Base::Uint256);
+    let string = Type::String { max_size: 8 };
+    let decimal = Type::Base(Base::Decimal);
+
+    let signatures = [
+        (
+            "balances(address)",
+            vec![address_type.clone()],
+            uint256.clone(),
+        ),
+        (
+            "allowances(address,address,uint256)",
+            vec![address_type.clone(), address_type.clone(), uint256.clone()],
+            uint256.clone(),
+        ),
+        (
+            "names(string)",
+            vec![string.clone()],
+            Type::Base(Base::Bytes32),
+        ),
+        (
+            "prices(fixed168x10)",
+            vec![decimal.clone()],
+            Type::Base(Base::Int128),
+        ),
+    ];
+    for ((signature, types, output), (name, keys, _)) in signatures.iter().zip(&stored) {
+        let outcome = call(encoded_calldata(signature, types, keys));
+        assert!(outcome.is_success(), "{}: {:?}", signature, outcome);
+        assert_eq!(
+            decode(std::slice::from_ref(output), &outcome.output),
+            Ok(vec![interpreter.load(name, keys).unwrap()]),
+            "{}",
+            signature
+        );
+    }
+
+    // Keys that were never written hold zero
+    let outcome = call(encoded_calldata(
+        "balances(address)",
+        std::slice::from_ref(&address_type),
+        &[Value::Address([0xbb; 20])],
+    ));
+    assert_eq!(
+        decode(std::slice::from_ref(&uint256), &outcome.output),
+        Ok(vec![uint(0)])
+    );
+
+    // Invalid keys, strings longer than their maximum size, and indexes out of
+    // bounds are rejected
+    let mut bad_address = selector("balances(address)").to_be_bytes().to_vec();
+    bad_address.extend_from_slice(&[0xff; 32]);
+    let mut bad_decimal = selector("prices(fixed168x10)").to_be_bytes().to_vec();
+    bad_decimal.extend_from_slice(&[0x7f; 32]);
+
+    for data in [
+        bad_address,
+        bad_decimal,
+        encoded_calldata(
+            "names(string)",
+            &[Type::String { max_size: 9 }],
+            &[Value::String("too long!".to_string())],
+        ),
+        encoded_calldata(
+            "allowances(address,address,uint256)",
+            &[address_type.clone(), address_type, uint256],
+            &[
+                Value::Address([0xaa; 20]),
+                Value::Address([0xbb; 20]),
+                uint(2),
+            ],
+        ),
+    ] {
+        assert_eq!(call(data).status, Status::Revert);
+    }
+}
+
+/// Deploy the object `object` with the constructor arguments `args` of the
+/// types `types` appended to its code.
+fn deploy_with_args(vm: &mut Vm, object: &Object, types: &[Type], args: &[Value]) -> Outcome {
+    let mut code = assemble(object).unwrap();
+    code.extend(encode(types, args).unwrap());
+
+    let deployed = vm.deploy(SENDER, &code);
+    assert!(deployed.is_success(), "{:?}", deployed);
+
+    deployed
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_greeter() {
+    let object = compile(
+        r#"
+greeting: string[100]
+
+@external
+def __init__(_greeting: string[100]):
+    self.greeting = _greeting
+
+@external
+@view
+def greet() -> string[100]:
+    return self.greeting
+
+@external
+def set_greeting(_greeting: string[100]):
+    self.greeting = _greeting
+"#,
+    );
+    let string = Type::String { max_size: 100 };
+    let greeting = |text: &str| Value::String(text.to_string());
+
+    let mut vm = Vm::new();
+    let address = deploy_with_args(
+        &mut vm,
+        &object,
+        std::slice::from_ref(&string),
+        &[greeting("Hello")],
+    )
+    .address
+    .unwrap();
+    let greet = |vm: &mut Vm| {
+        let outcome = vm.call(SENDER, address, &calldata("greet()", &[]));
+        assert!(outcome.is_success(), "{:?}", outcome);
+
+        decode(std::slice::from_ref(&string), &outcome.output).unwrap()
+    };
+    assert_eq!(greet(&mut vm), vec![greeting("Hello")]);
+
+    // A longer greeting spans several slots and a shorter one clears them
+    let long = "Hello, world! ".repeat(7);
+    for text in [long.as_str(), "Hi", ""] {
+        let outcome = vm.call(
+            SENDER,
+            address,
+            &encoded_calldata(
+                "set_greeting(string)",
+                std::slice::from_ref(&string),
+                &[greeting(text)],
+            ),
+        );
+        assert!(outcome.is_success(), "{:?}", outcome);
+        assert!(outcome.output.is_empty());
+        assert_eq!(greet(&mut vm), vec![greeting(text)]);
+    }
+
+    // Greetings longer than the maximum size are rejected
+    let outcome = vm.call(
+        SENDER,
+        address,
+        &encoded_calldata(
+            "set_greeting(string)",
+            &[Type::String { max_size: 101 }],
+            &[greeting(&"a".repeat(101))],
+        ),
+    );
+    assert_eq!(outcome.status, Status::Revert);
+    assert_eq!(greet(&mut vm), vec![greeting("")]);
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_erc20() {
+    let object = compile(
+        r#"
+event Transfer:
+    sender: indexed(address)
+    receiver: indexed(address)
+    value: uint256
+
+event Approval:
+    owner: indexed(address)
+    spender: indexed(address)
+    value: uint256
+
+name: public(string[64])
+symbol: public(string[32])
+decimals: public(uint256)
+totalSupply: public(uint256)
+balanceOf: public(map(address, uint256))
+allowance: public(map(address, map(address, uint256)))
+
+@external
+def __init__(_name: string[64], _symbol: string[32], _decimals: uint256, _supply: uint256):
+    init_supply: uint256 = _supply * 10 ** _decimals
+    self.name = _name
+    self.symbol = _symbol
+    self.decimals = _decimals
+    self.balanceOf[msg.sender] = init_supply
+    self.totalSupply = init_supply
+    log Transfer(empty(address), msg.sender, init_supply)
+
+@external
+def transfer(_to: address, _value: uint256) -> bool:
+    self.balanceOf[msg.sender] -= _value
+    self.balanceOf[_to] += _value
+    log Transfer(msg.sender, _to, _value)
+    return True
+
+@external
+def transferFrom(_from: address, _to: address, _value: uint256) -> bool:
+    self.balanceOf[_from] -= _value
+    self.balanceOf[_to] += _value
+    self.allowance[_from][msg.sender] -= _value
+    log Transfer(_from, _to, _value)
+    return True
+
+@external
+def approve(_spender: address, _value: uint256) -> bool:
+    self.allowance[msg.sender][_spender] = _value
+    log Approval(msg.sender, _spender, _value)
+    return True
+"#,
+    );
+    let address_type = Type::Base(Base::Address);
+    let uint256 = Type::Base(Base::Uint256);
+    let uint = |value: u64| Value::Uint(BigUint::from(value));
+    let other = [0x22; 20];
+
+    let mut vm = Vm::new();
+    let deployed = deploy_with_args(
+        &mut vm,
+        &object,
+        &[
+            Type::String { max_size: 64 },
+            Type::String { max_size: 32 },
+            uint256.clone(),
+            uint256.clone(),
+        ],
+        &[
+            Value::String("Token".to_string()),
+            Value::String("TOK".to_string()),
+            uint(2),
+            uint(1000),
+        ],
+    );
+    let address = deployed.address.unwrap();
+
+    // The whole supply is minted to the creator
+    assert_eq!(deployed.logs.len(), 1);
+    assert_eq!(
+        deployed.logs[0].topics,
+        vec![
+            event_topic("Transfer(address,address,uint256)"),
+            [0; 32],
+            address_word(0x11),
+        ]
+    );
+    assert_eq!(
+        decode(std::slice::from_ref(&uint256), &deployed.logs[0].data),
+        Ok(vec![uint(100_000)])
+    );
+
+    let mut call = |from: [u8; 20], data: Vec<u8>| {
+        let outcome = vm.call(from, address, &data);
+        (outcome.status, outcome.output, outcome.logs)
+    };
+    let balance_of = |owner: [u8; 20]| {
+        encoded_calldata(
+            "balanceOf(address)",
+            std::slice::from_ref(&address_type),
+            &[Value::Address(owner)],
+        )
+    };
+    let word = |value: u64| encode(std::slice::from_ref(&uint256), &[uint(value)]).unwrap();
+
+    let (_, output, _) = call(SENDER, calldata("name()", &[]));
+    assert_eq!(
+        decode(&[Type::String { max_size: 64 }], &output),
+        Ok(vec![Value::String("Token".to_string())])
+    );
+    let (_, output, _) = call(SENDER, calldata("symbol()", &[]));
+    assert_eq!(
+        decode(&[Type::String { max_size: 32 }], &output),
+        Ok(vec![Value::String("TOK".to_string())])
+    );
+    let (_, output, _) = call(SENDER, calldata("totalSupply()", &[]));
+    assert_eq!(
+        decode(std::slice::from_ref(&uint256), &output),
+        Ok(vec![uint(100_000)])
+    );
+    assert_eq!(call(SENDER, balance_of(SENDER)).1, word(100_000));
+
+    // Transfers move tokens and log them
+    let transfer = |to: [u8; 20], value: u64| {
+        encoded_calldata(
+            "transfer(address,uint256)",
+            &[address_type.clone(), uint256.clone()],
+            &[Value::Address(to), uint(value)],
+        )
+    };
+    let (status, output, logs) = call(SENDER, transfer(other, 300));
+    assert_eq!(status, Status::Success);
+    assert_eq!(
+        decode(&[Type::Base(Base::Bool)], &output),
+        Ok(vec![Value::Bool(true)])
+    );
+    assert_eq!(logs.len(), 1);
+    assert_eq!(
+        logs[0].topics,
+        vec![
+            event_topic("Transfer(address,address,uint256)"),
+            address_word(0x11),
+            address_word(0x22),
+        ]
+    );
+    assert_eq!(call(SENDER, balance_of(SENDER)).1, word(99_700));
+    assert_eq!(call(SENDER, balance_of(other)).1, word(300));
+
+    // Transfers of more than the balance revert without effect
+    let (status, _, logs) = call(other, transfer(SENDER, 301));
+    assert_eq!(status, Status::Revert);
+    assert!(logs.is_empty());
+    assert_eq!(call(SENDER, balance_of(other)).1, word(300));
+
+    // Transfers on behalf of an owner are limited by the allowance
+    let (status, _, logs) = call(
+        other,
+        encoded_calldata(
+            "approve(address,uint256)",
+            &[address_type.clone(), uint256.clone()],
+            &[Value::Address(SENDER), uint(200)],
+        ),
+    );
+    assert_eq!(status, Status::Success);
+    assert_eq!(
+        logs[0].topics,
+        vec![
+            event_topic("Approval(address,address,uint256)"),
+            address_word(0x22),
+            address_word(0x11),
+        ]
+    );
+    let transfer_from = |value: u64| {
+        encoded_calldata(
+            "transferFrom(address,address,uint256)",
+            &[address_type.clone(), address_type.clone(), uint256.clone()],
+            &[Value::Address(other), Value::Address(SENDER), uint(value)],
+        )
+    };
+    assert_eq!(call(SENDER, transfer_from(150)).0, Status::Success);
+    assert_eq!(call(SENDER, transfer_from(51)).0, Status::Revert);
+    assert_eq!(call(SENDER, balance_of(other)).1, word(150));
+    assert_eq!(call(SENDER, balance_of(SENDER)).1, word(99_850));
+
+    let (_, output, _) = call(
+        SENDER,
+        encoded_calldata(
+            "allowance(address,address)",
+            &[address_type.clone(), address_type.clone()],
+            &[Value::Address(other), Value::Address(SENDER)],
+        ),
+    );
+    assert_eq!(
+        decode(std::slice::from_ref(&uint256), &output),
+        Ok(vec![uint(50)])
+    );
+
+    // Functions that are not payable reject value
+    vm.state.account_mut(&SENDER).balance = BigUint::from(1u32);
+    let outcome = vm.transact(&Transaction {
+        from: SENDER,
+        to: Some(address),
+        value: BigUint::from(1u32),
+        data: transfer(other, 1),
+        gas_limit: 1_000_000,
+    });
+    assert_eq!(outcome.status, Status::Revert);
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_function_bodies() {
+    let object = compile(
+        r#"
+MAX: constant(int128) = 10
+counter: uint256
+items: int128[4]
+
+@internal
+@pure
+def square(x: int128) -> int128:
+    return x * x
+
+@internal
+def bump() -> uint256:
+    self.counter += 1
+    return self.counter
+
+@external
+def sum_squares(n: int128) -> int128:
+    assert n <= MAX, "too many"
+    total: int128 = 0
+    for i in range(MAX):
+        if i >= n:
+            break
+        if i == 3:
+            continue
+        total += self.square(i)
+    return total
+
+@external
+def store(i: uint256, value: int128) -> int128:
+    self.items[i] = value
+    return self.items[i] - 1
+
+@external
+def order() -> uint256:
+    # Operands are evaluated from left to right
+    return self.bump() * 10 + self.bump()
+
+@external
+def short_circuit(x: bool) -> uint256:
+    if x or self.bump() > 100:
+        return 1
+    elif not x and self.counter < 2 ** 8:
+        return 2
+    return 3
+
+@external
+@pure
+def signed(a: int128, b: int128) -> int128:
+    if a < b:
+        return -(a % b)
+    return a / b
+"#,
+    );
+    let mut vm = Vm::new();
+    let deployed = vm.deploy(SENDER, &assemble(&object).unwrap());
+    assert!(deployed.is_success(), "{:?}", deployed);
+    let address = deployed.address.unwrap();
+
+    let int128 = Type::Base(Base::Int128);
+    let uint256 = Type::Base(Base::Uint256);
+    let int = |value: i64| Value::Int(BigInt::from(value));
+    let uint = |value: u64| Value::Uint(BigUint::from(value));
+    // Call a function and decode its result of the type `output`, or return
+    // the reason of the revert
+    let mut call = |data: Vec<u8>, output: &Type| {
+        let outcome = vm.call(SENDER, address, &data);
+        match outcome.status {
+            Status::Success => Ok(decode(std::slice::from_ref(output), &outcome.output)
+                .unwrap()
+                .remove(0)),
+            _ => Err(outcome.revert_reason()),
+        }
+    };
+    let sum_squares = |n: i64| {
+        encoded_calldata(
+            "sum_squares(int128)",
+            std::slice::from_ref(&int128),
+            &[int(n)],
+        )
+    };
+
+    // 0 + 1 + 4 + 16 + 25
+    assert_eq!(call(sum_squares(6), &int128), Ok(int(46)));
+    assert_eq!(call(sum_squares(-1), &int128), Ok(int(0)));
+    assert_eq!(
+        call(sum_squares(11), &int128),
+        Err(Some("too many".to_string()))
+    );
+
+    let store = |i: u64, value: i64| {
+        encoded_calldata(
+            "store(uint256,int128)",
+            &[uint256.clone(), int128.clone()],
+            &[uint(i), int(value)],
+        )
+    };
+    assert_eq!(call(store(3, -7), &int128), Ok(int(-8)));
+    assert_eq!(call(store(4, 1), &int128), Err(None));
+
+    // The counter is 1 and then 2, and 3 and then 4 in the second call
+    assert_eq!(call(calldata("order()", &[]), &uint256), Ok(uint(12)));
+    assert_eq!(call(calldata("order()", &[]), &uint256), Ok(uint(34)));
+
+    // The right operand of `or` is only evaluated if the left one is false
+    let short_circuit = |x: bool| {
+        encoded_calldata(
+            "short_circuit(bool)",
+            &[Type::Base(Base::Bool)],
+            &[Value::Bool(x)],
+        )
+    };
+    assert_eq!(call(short_circuit(true), &uint256), Ok(uint(1)));
+    assert_eq!(call(calldata("order()", &[]), &uint256), Ok(uint(56)));
+    assert_eq!(call(short_circuit(false), &uint256), Ok(uint(2)));
+    assert_eq!(call(calldata("order()", &[]), &uint256), Ok(uint(89)));
+
+    let signed = |a: i64, b: i64| {
+        encoded_calldata(
+            "signed(int128,int128)",
+            &[int128.clone(), int128.clone()],
+            &[int(a), int(b)],
+        )
+    };
+    assert_eq!(call(signed(-7, 2), &int128), Ok(int(1)));
+    assert_eq!(call(signed(7, -2), &int128), Ok(int(-3)));
+    assert_eq!(call(signed(7, 0), &int128), Err(None));
+}
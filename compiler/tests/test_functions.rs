@@ -0,0 +1,166 @@
+extern crate wasm_bindgen_test;
+
+use nom::error::VerboseError;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use vyper_analyzer::const_eval::module_constants;
+use vyper_analyzer::types::{
+    Base,
+    Type,
+};
+use vyper_compiler::abi::StateMutability;
+use vyper_compiler::functions::{
+    check_functions,
+    Visibility,
+};
+use vyper_compiler::storage::storage_fields;
+use vyper_parser::parsers::{
+    file_input,
+    get_parse_tokens,
+};
+
+/// Parse and check the functions of the module in `source`.  Returns the
+/// names of the functions or the message of the first error.
+fn check(source: &str) -> Result<Vec<String>, String> {
+    let tokens = get_parse_tokens(source).unwrap();
+    let (_, module) = file_input::<VerboseError<_>>(&tokens[..]).unwrap();
+    let env = module_constants(&module.node).unwrap();
+    let fields = storage_fields(&module.node).unwrap();
+
+    match check_functions(&module.node, &env, &fields) {
+        Ok(functions) => Ok(functions
+            .functions
+            .iter()
+            .map(|f| f.signature.name.clone())
+            .collect()),
+        Err(err) => Err(err.msg),
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_signatures() {
+    let source = r#"
+@external
+@payable
+def deposit(amount: uint256, memo: bytes[10]) -> int128:
+    return -1
+"#;
+    let tokens = get_parse_tokens(source).unwrap();
+    let (_, module) = file_input::<VerboseError<_>>(&tokens[..]).unwrap();
+    let env = module_constants(&module.node).unwrap();
+    let functions = check_functions(&module.node, &env, &[]).unwrap();
+
+    let sig = &functions.get("deposit").unwrap().signature;
+    assert_eq!(sig.visibility, Visibility::External);
+    assert_eq!(sig.mutability, StateMutability::Payable);
+    assert_eq!(sig.return_type, Some(Type::Base(Base::Int128)));
+    assert_eq!(sig.abi_signature(), "deposit(uint256,bytes)");
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_check_errors() {
+    let cases = [
+        (
+            "def f():\n    pass\n",
+            "functions must be decorated with @external or @internal",
+        ),
+        (
+            "@external\n@view\n@pure\ndef f():\n    pass\n",
+            "functions may have at most one state mutability",
+        ),
+        (
+            "@external\ndef f():\n    pass\n@internal\ndef f():\n    pass\n",
+            "duplicate function definition",
+        ),
+        (
+            "@internal\ndef __init__():\n    pass\n",
+            "the constructor must be external",
+        ),
+        (
+            "@external\ndef f() -> uint256:\n    pass\n",
+            "function is missing a return statement",
+        ),
+        (
+            "@external\ndef f(a: uint256):\n    a = 1\n",
+            "function arguments cannot be modified",
+        ),
+        (
+            "@external\ndef f() -> uint256:\n    return True\n",
+            "expected a value of type uint256, found bool",
+        ),
+        (
+            "@external\ndef f(a: uint256, b: int128) -> bool:\n    return a < b\n",
+            "operands must have the same type",
+        ),
+        (
+            "x: uint256\n@external\n@view\ndef f():\n    self.x = 1\n",
+            "view and pure functions cannot modify state",
+        ),
+        (
+            "x: uint256\n@external\n@pure\ndef f() -> uint256:\n    return self.x\n",
+            "pure functions cannot read the state or the environment",
+        ),
+        (
+            "@external\ndef f():\n    break\n",
+            "break and continue must be inside a loop",
+        ),
+        (
+            "@external\ndef f():\n    self.g()\n@external\ndef g():\n    pass\n",
+            "only internal functions can be called from the contract",
+        ),
+        (
+            "@external\ndef f() -> int128:\n    return 170141183460469231731687303715884105728\n",
+            "integer literal is out of bounds for its type",
+        ),
+        (
+            "@external\ndef f(n: int128):\n    for i in range(n):\n        pass\n",
+            "name is not a compile-time constant",
+        ),
+        (
+            "@external\ndef f(n: int128):\n    for i in range(n, n - 1):\n        pass\n",
+            "name is not a compile-time constant",
+        ),
+    ];
+
+    for (source, message) in cases {
+        assert_eq!(check(source), Err(message.to_string()), "{}", source);
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_check_valid() {
+    assert_eq!(
+        check(
+            r#"
+owners: map(address, bool)
+count: int128
+
+@internal
+@view
+def is_owner(a: address) -> bool:
+    return self.owners[a]
+
+@external
+def add(a: address):
+    assert not self.is_owner(a), "already an owner"
+    self.owners[a] = True
+    self.count += 1
+
+@external
+def count_from(start: int128) -> int128:
+    total: int128 = 0
+    for i in range(start, start + 4):
+        total += i
+    return total
+"#
+        ),
+        Ok(vec![
+            "is_owner".to_string(),
+            "add".to_string(),
+            "count_from".to_string()
+        ])
+    );
+}
@@ -0,0 +1,1016 @@
+extern crate wasm_bindgen_test;
+
+use nom::error::VerboseError;
+use num_bigint::{
+    BigInt,
+    BigUint,
+};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use vyper_analyzer::types::{
+    Base,
+    Type,
+};
+use vyper_compiler::abi::codec::{
+    decimal_bounds,
+    encode,
+    Value,
+};
+use vyper_compiler::errors::RuntimeError;
+use vyper_compiler::functions::Visibility;
+use vyper_compiler::interpreter::arith::*;
+use vyper_compiler::interpreter::{
+    Context,
+    Interpreter,
+    Log,
+};
+use vyper_compiler::signatures::{
+    event_topic,
+    selector,
+};
+use vyper_compiler::storage::{
+    map_value_slot,
+    slot_word,
+    StorageField,
+    StorageType,
+};
+use vyper_compiler::yul::compile_module;
+use vyper_evm::interpreter::Status;
+use vyper_evm::vm::{
+    Outcome,
+    Vm,
+};
+use vyper_parser::ast::Module;
+use vyper_parser::parsers::{
+    file_input,
+    get_parse_tokens,
+};
+use vyper_parser::span::Span;
+use vyper_yul::assembler::assemble;
+use vyper_yul::parsers::block;
+use vyper_yul::tokenizer::tokenize;
+
+const EVENTS: &str = r#"
+event Transfer:
+    sender: indexed(address)
+    receiver: indexed(address)
+    value: uint256
+event Note:
+    author: indexed(address)
+    topic: indexed(string[32])
+    count: int128
+    memo: bytes[100]
+    flag: bool
+    title: string[64]
+event Price:
+    amount: indexed(uint256)
+    price: decimal
+    key: indexed(bytes32)
+    tag: bytes32
+"#;
+
+/// Parse the module in `source` and pass it to `f`.
+fn with_module<T>(source: &str, f: impl FnOnce(&Module) -> T) -> T {
+    let tokens = get_parse_tokens(source).unwrap();
+    let (_, module) = file_input::<VerboseError<_>>(&tokens[..]).unwrap();
+
+    f(&module.node)
+}
+
+fn uint(value: u64) -> Value {
+    Value::Uint(BigUint::from(value))
+}
+
+fn int(value: i64) -> Value {
+    Value::Int(BigInt::from(value))
+}
+
+fn decimal(value: i64) -> Value {
+    Value::Decimal(BigInt::from(value))
+}
+
+fn revert() -> Result<Value, RuntimeError> {
+    Err(RuntimeError::Revert(None))
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_arith() {
+    let max_uint = Value::Uint((BigUint::from(1u8) << 256) - 1u8);
+    let max_int = Value::Int((BigInt::from(1) << 127) - 1);
+    let min_int = Value::Int(-(BigInt::from(1) << 127));
+    let scale = DECIMAL_SCALE as i64;
+    let (min_decimal, max_decimal) = decimal_bounds();
+
+    let examples = vec![
+        (add(Base::Uint256, &uint(2), &uint(3)), Ok(uint(5))),
+        (add(Base::Uint256, &max_uint, &uint(1)), revert()),
+        (sub(Base::Uint256, &uint(2), &uint(3)), revert()),
+        (sub(Base::Int128, &int(2), &int(3)), Ok(int(-1))),
+        (sub(Base::Int128, &min_int, &int(1)), revert()),
+        (add(Base::Int128, &max_int, &int(1)), revert()),
+        (mul(Base::Uint256, &max_uint, &uint(2)), revert()),
+        (mul(Base::Int128, &int(-4), &int(3)), Ok(int(-12))),
+        (div(Base::Uint256, &uint(7), &uint(2)), Ok(uint(3))),
+        (div(Base::Uint256, &uint(7), &uint(0)), revert()),
+        (div(Base::Int128, &int(-7), &int(2)), Ok(int(-3))),
+        (div(Base::Int128, &min_int, &int(-1)), revert()),
+        (modulo(Base::Int128, &int(-7), &int(3)), Ok(int(-1))),
+        (modulo(Base::Uint256, &uint(7), &uint(0)), revert()),
+        (
+            pow(Base::Uint256, &uint(2), &uint(255)),
+            Ok(Value::Uint(BigUint::from(1u8) << 255)),
+        ),
+        (pow(Base::Uint256, &uint(2), &uint(256)), revert()),
+        (pow(Base::Uint256, &uint(0), &uint(0)), Ok(uint(1))),
+        (pow(Base::Uint256, &uint(1), &max_uint), Ok(uint(1))),
+        (pow(Base::Int128, &int(-1), &int(3)), Ok(int(-1))),
+        (pow(Base::Int128, &int(-2), &int(127)), Ok(min_int.clone())),
+        (pow(Base::Int128, &int(2), &int(127)), revert()),
+        (pow(Base::Int128, &int(2), &int(-1)), revert()),
+        (neg(Base::Int128, &int(5)), Ok(int(-5))),
+        (neg(Base::Int128, &min_int), revert()),
+        // 1.5 * 2.5 = 3.75
+        (
+            mul(
+                Base::Decimal,
+                &decimal(15 * scale / 10),
+                &decimal(25 * scale / 10),
+            ),
+            Ok(decimal(375 * scale / 100)),
+        ),
+        // 1 / 3 is truncated to ten decimal places
+        (
+            div(Base::Decimal, &decimal(scale), &decimal(3 * scale)),
+            Ok(decimal(3_333_333_333)),
+        ),
+        (
+            div(Base::Decimal, &decimal(-scale), &decimal(3 * scale)),
+            Ok(decimal(-3_333_333_333)),
+        ),
+        // Decimals range over the values of an int128 before scaling
+        (
+            mul(
+                Base::Decimal,
+                &Value::Decimal(BigInt::from(1) << 120),
+                &decimal(2 * scale),
+            ),
+            Ok(Value::Decimal(BigInt::from(1) << 121)),
+        ),
+        (
+            sub(
+                Base::Decimal,
+                &Value::Decimal(min_decimal.clone()),
+                &decimal(0),
+            ),
+            Ok(Value::Decimal(min_decimal)),
+        ),
+        (
+            add(Base::Decimal, &Value::Decimal(max_decimal), &decimal(1)),
+            revert(),
+        ),
+        (
+            add(Base::Uint256, &int(1), &uint(1)),
+            Err(RuntimeError::Invalid("value does not match type")),
+        ),
+        (
+            add(Base::Bool, &Value::Bool(true), &Value::Bool(true)),
+            Err(RuntimeError::Invalid("arithmetic is not defined for type")),
+        ),
+        (
+            pow(Base::Decimal, &decimal(scale), &decimal(scale)),
+            Err(RuntimeError::Invalid(
+                "exponentiation is not defined for type",
+            )),
+        ),
+        (
+            neg(Base::Uint256, &uint(1)),
+            Err(RuntimeError::Invalid("negation is not defined for type")),
+        ),
+    ];
+
+    for (i, (actual, expected)) in examples.into_iter().enumerate() {
+        assert_eq!(actual, expected, "example {}", i);
+    }
+
+    assert!(compare(Base::Int128, &int(-1), &int(1)).unwrap().is_lt());
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_storage() {
+    let fields = vec![
+        StorageField {
+            name: "owner".to_string(),
+            typ: StorageType::Value(Type::Base(Base::Address)),
+            span: Span::new(0, 1),
+        },
+        StorageField {
+            name: "balances".to_string(),
+            typ: StorageType::Map {
+                key: Type::Base(Base::Address),
+                value: Box::new(StorageType::Value(Type::Base(Base::Uint256))),
+            },
+            span: Span::new(1, 2),
+        },
+        StorageField {
+            name: "names".to_string(),
+            typ: StorageType::Value(Type::String { max_size: 10 }),
+            span: Span::new(2, 3),
+        },
+    ];
+    with_module("", |module| {
+        let mut interpreter = Interpreter::with_storage_fields(module, fields).unwrap();
+        let holder = [Value::Address([0xaa; 20])];
+
+        assert_eq!(interpreter.load("balances", &holder), Ok(uint(0)));
+
+        interpreter
+            .transact(|contract| {
+                contract.store("owner", &[], Value::Address([0x11; 20]))?;
+                contract.store("balances", &holder, uint(500))
+            })
+            .unwrap();
+
+        assert_eq!(interpreter.load("balances", &holder), Ok(uint(500)));
+        assert_eq!(
+            interpreter.load("owner", &[]),
+            Ok(Value::Address([0x11; 20]))
+        );
+
+        // Storage uses the slots of the storage layout
+        let mut key = [0; 32];
+        key[12..].copy_from_slice(&[0xaa; 20]);
+        let slot = BigUint::from_bytes_be(&map_value_slot(&slot_word(1), &key));
+        let storage = interpreter.storage();
+
+        assert_eq!(storage.len(), 2);
+        assert_eq!(storage[&slot], BigUint::from(500u32));
+
+        // Reverted transactions discard their writes
+        let result = interpreter.transact(|contract| {
+            contract.store("balances", &holder, uint(0))?;
+            contract.raise("frozen")
+        });
+        assert_eq!(
+            result,
+            Err(RuntimeError::Revert(Some("frozen".to_string())))
+        );
+        assert_eq!(interpreter.load("balances", &holder), Ok(uint(500)));
+
+        let errors = vec![
+            (interpreter.load("total", &[]), "unknown storage field"),
+            (
+                interpreter.load("balances", &[]),
+                "wrong number of map keys",
+            ),
+            (interpreter.load("owner", &holder), "too many keys"),
+            (
+                interpreter.load("balances", &[uint(1)]),
+                "value does not match type",
+            ),
+        ];
+        for (result, msg) in errors {
+            assert_eq!(result, Err(RuntimeError::Invalid(msg)));
+        }
+    });
+}
+
+const FIELDS: &str = r#"
+SIZE: constant(int128) = 3
+LIMIT: public(constant(int128)) = -5
+RATE: public(constant(decimal)) = 2
+owner: public(address)
+balances: public(uint256[SIZE][2])
+memos: public(bytes[40][2])
+name: public(string[70])
+flags: public(bool[4])
+"#;
+
+#[test]
+#[wasm_bindgen_test]
+fn test_storage_sequences() {
+    with_module(FIELDS, |module| {
+        let mut interpreter = Interpreter::new(module).unwrap();
+
+        assert_eq!(
+            interpreter.load("balances", &[]),
+            Ok(Value::Array(vec![
+                Value::Array(vec![uint(0), uint(0), uint(0)]),
+                Value::Array(vec![uint(0), uint(0), uint(0)]),
+            ]))
+        );
+        assert_eq!(
+            interpreter.load("name", &[]),
+            Ok(Value::String(String::new()))
+        );
+
+        let name = "Hello, Vyper! This string is longer than two words.";
+        interpreter
+            .transact(|contract| {
+                contract.store("balances", &[uint(1), uint(2)], uint(42))?;
+                contract.store(
+                    "balances",
+                    &[uint(0)],
+                    Value::Array(vec![uint(1), uint(2), uint(3)]),
+                )?;
+                contract.store("memos", &[uint(1)], Value::Bytes(vec![0xab; 33]))?;
+                contract.store("name", &[], Value::String(name.to_string()))
+            })
+            .unwrap();
+
+        assert_eq!(
+            interpreter.load("balances", &[]),
+            Ok(Value::Array(vec![
+                Value::Array(vec![uint(1), uint(2), uint(3)]),
+                Value::Array(vec![uint(0), uint(0), uint(42)]),
+            ]))
+        );
+        assert_eq!(
+            interpreter.load("memos", &[uint(1)]),
+            Ok(Value::Bytes(vec![0xab; 33]))
+        );
+        assert_eq!(
+            interpreter.load("name", &[]),
+            Ok(Value::String(name.to_string()))
+        );
+        assert_eq!(interpreter.load("LIMIT", &[]), Ok(int(-5)));
+        assert_eq!(interpreter.load("RATE", &[]), Ok(decimal(20_000_000_000)));
+
+        // `owner` occupies slot 0, `balances` slots 1 to 6, `memos` slots 7 to 12
+        // with `memos[1]` from slot 10 on, and `name` slots 13 to 16
+        let storage = interpreter.storage();
+        let word = |slot: u32| storage.get(&BigUint::from(slot)).cloned();
+        assert_eq!(word(6), Some(BigUint::from(42u32)));
+        assert_eq!(word(10), Some(BigUint::from(33u32)));
+        assert_eq!(word(12), Some(BigUint::from(0xabu32) << 248));
+        assert_eq!(word(13), Some(BigUint::from(name.len())));
+        assert_eq!(word(16), None);
+
+        // Shorter values clear the content of longer ones
+        interpreter
+            .transact(|contract| contract.store("name", &[], Value::String("Hi".to_string())))
+            .unwrap();
+        assert_eq!(
+            interpreter.load("name", &[]),
+            Ok(Value::String("Hi".to_string()))
+        );
+        assert_eq!(interpreter.storage().get(&BigUint::from(15u32)), None);
+
+        assert_eq!(interpreter.load("balances", &[uint(2)]), revert());
+        assert_eq!(interpreter.load("flags", &[uint(4)]), revert());
+
+        let errors = vec![
+            (
+                interpreter.transact(|contract| contract.store("LIMIT", &[], int(1))),
+                "constants cannot be modified",
+            ),
+            (
+                interpreter.transact(|contract| {
+                    contract.store("name", &[], Value::String("a".repeat(71)))
+                }),
+                "value exceeds maximum size",
+            ),
+            (
+                interpreter.transact(|contract| {
+                    contract.store("balances", &[uint(0)], Value::Array(vec![uint(1)]))
+                }),
+                "value does not match type",
+            ),
+            (
+                interpreter.transact(|contract| contract.store("memos", &[uint(0)], uint(1))),
+                "value does not match type",
+            ),
+        ];
+        for (result, msg) in errors {
+            assert_eq!(result, Err(RuntimeError::Invalid(msg)));
+        }
+        assert_eq!(
+            interpreter.load("flags", &[int(1)]),
+            Err(RuntimeError::Invalid(
+                "array indexes must be uint256 values"
+            ))
+        );
+        assert_eq!(
+            interpreter.load("LIMIT", &[uint(0)]),
+            Err(RuntimeError::Invalid("too many keys"))
+        );
+    });
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_events_and_asserts() {
+    with_module(EVENTS, |module| {
+        let mut interpreter = Interpreter::new(module).unwrap();
+        let transfer = vec![
+            Value::Address([0xaa; 20]),
+            Value::Address([0xbb; 20]),
+            uint(5),
+        ];
+
+        let logs = interpreter
+            .transact(|contract| {
+                contract.assert(true, None)?;
+                contract.emit("Transfer", &transfer)
+            })
+            .unwrap();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(
+            logs[0].topics[0],
+            event_topic("Transfer(address,address,uint256)")
+        );
+        assert_eq!(logs[0].data, slot_word(5).to_vec());
+
+        // Logs of reverted transactions are discarded
+        let result = interpreter.transact(|contract| {
+            contract.emit("Transfer", &transfer)?;
+            contract.assert(false, Some("nope"))
+        });
+        assert_eq!(result, Err(RuntimeError::Revert(Some("nope".to_string()))));
+        assert_eq!(
+            interpreter.transact(|contract| contract.assert(false, None)),
+            Err(RuntimeError::Revert(None))
+        );
+
+        assert_eq!(
+            interpreter.transact(|contract| contract.emit("Approval", &[])),
+            Err(RuntimeError::Invalid("unknown event"))
+        );
+        assert_eq!(
+            interpreter.transact(|contract| contract.emit("Transfer", &transfer[..2])),
+            Err(RuntimeError::Invalid("wrong number of values"))
+        );
+    });
+}
+
+/// A xorshift random number generator, seeded so that failures are
+/// reproducible.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn bytes(&mut self, len: usize) -> Vec<u8> {
+        (0..len).map(|_| self.next() as u8).collect()
+    }
+
+    /// Return a random value of the type `typ`.
+    fn value(&mut self, typ: &Type) -> Value {
+        match typ {
+            Type::Base(Base::Address) => {
+                let mut address = [0; 20];
+                address.copy_from_slice(&self.bytes(20));
+                Value::Address(address)
+            }
+            Type::Base(Base::Bool) => Value::Bool(self.next() & 1 == 0),
+            Type::Base(Base::Bytes32) => {
+                let mut bytes = [0; 32];
+                bytes.copy_from_slice(&self.bytes(32));
+                Value::Bytes32(bytes)
+            }
+            Type::Base(Base::Uint256) => Value::Uint(BigUint::from_bytes_be(&self.bytes(32))),
+            Type::Base(Base::Int128) => Value::Int(BigInt::from(self.next() as i64) << 63),
+            Type::Base(Base::Decimal) => Value::Decimal(BigInt::from(self.next() as i64)),
+            Type::Bytes { max_size } => {
+                let len = self.next() as usize % (*max_size as usize + 1);
+                Value::Bytes(self.bytes(len))
+            }
+            Type::String { max_size } => {
+                let len = self.next() as usize % (*max_size as usize + 1);
+                Value::String(
+                    (0..len)
+                        .map(|_| (b'a' + self.next() as u8 % 26) as char)
+                        .collect(),
+                )
+            }
+            _ => unimplemented!(),
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Return YUL statements that place the value `value` in memory at `ptr` if it
+/// is a byte array or string, along with an expression for the value.
+fn yul_arg(value: &Value, ptr: u64) -> (String, String) {
+    let signed = |value: &BigInt| match value.to_biguint() {
+        Some(value) => format!("{}", value),
+        None => format!("sub(0, {})", -value),
+    };
+
+    match value {
+        Value::Address(bytes) => (String::new(), format!("0x{}", hex(bytes))),
+        Value::Bool(value) => (String::new(), format!("{}", *value as u8)),
+        Value::Bytes32(bytes) => (String::new(), format!("0x{}", hex(bytes))),
+        Value::Uint(value) => (String::new(), format!("{}", value)),
+        Value::Int(value) | Value::Decimal(value) => (String::new(), signed(value)),
+        Value::Bytes(_) | Value::String(_) => {
+            let bytes = match value {
+                Value::Bytes(bytes) => bytes.clone(),
+                Value::String(string) => string.as_bytes().to_vec(),
+                _ => unreachable!(),
+            };
+
+            let mut setup = format!("mstore({}, {})\n", ptr, bytes.len());
+            for (i, chunk) in bytes.chunks(32).enumerate() {
+                setup.push_str(&format!(
+                    "mstore({}, 0x{:0<64})\n",
+                    ptr + 32 * (i as u64 + 1),
+                    hex(chunk)
+                ));
+            }
+            (setup, format!("{}", ptr))
+        }
+        _ => unimplemented!(),
+    }
+}
+
+/// Emit the event `name` with the arguments `args` by running compiled code on
+/// an EVM and return the emitted logs.
+fn emit_compiled(module: &Module, name: &str, args: &[Value]) -> Vec<Log> {
+    let mut setup = String::new();
+    let mut exprs = vec![];
+    for (i, arg) in args.iter().enumerate() {
+        let (code, expr) = yul_arg(arg, 0x1000 * (i as u64 + 1));
+        setup.push_str(&code);
+        exprs.push(expr);
+    }
+
+    let source = format!("{{ {} emit_{}({}) stop() }}", setup, name, exprs.join(", "));
+    let tokens = tokenize(&source).unwrap();
+    let (_, driver) = block::<VerboseError<_>>(&tokens[..]).unwrap();
+
+    let mut object = compile_module("Test", module).unwrap();
+    let statements = &mut object.objects[0].code.statements;
+    let rest = statements.split_off(1);
+    statements.extend(driver.node.statements);
+    statements.extend(rest);
+
+    let mut vm = Vm::new();
+    let sender = [0x11; 20];
+    let address = vm
+        .deploy(sender, &assemble(&object).unwrap())
+        .address
+        .unwrap();
+    let outcome = vm.call(sender, address, &[]);
+    assert!(outcome.is_success(), "{:?}", outcome);
+
+    outcome
+        .logs
+        .into_iter()
+        .map(|log| Log {
+            topics: log.topics,
+            data: log.data,
+        })
+        .collect()
+}
+
+#[test]
+fn test_differential_getters() {
+    with_module(FIELDS, check_getters_match);
+}
+
+/// Check that the getters of the public fields in `module` return the values
+/// held in random storage set by the interpreter when compiled.
+fn check_getters_match(module: &Module) {
+    let fields = vec![
+        ("owner", vec![], Type::Base(Base::Address)),
+        ("balances", vec![2, 3], Type::Base(Base::Uint256)),
+        ("memos", vec![2], Type::Bytes { max_size: 40 }),
+        ("name", vec![], Type::String { max_size: 70 }),
+        ("flags", vec![4], Type::Base(Base::Bool)),
+    ];
+    let object = compile_module("Test", module).unwrap();
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+
+    for _ in 0..4 {
+        let mut interpreter = Interpreter::new(module).unwrap();
+        interpreter
+            .transact(|contract| {
+                for (name, dimensions, typ) in &fields {
+                    for indexes in all_indexes(dimensions) {
+                        let keys: Vec<_> = indexes.iter().map(|i| uint(*i)).collect();
+                        contract.store(name, &keys, rng.value(typ))?;
+                    }
+                }
+                Ok(())
+            })
+            .unwrap();
+
+        let mut vm = Vm::new();
+        let sender = [0x11; 20];
+        let address = vm
+            .deploy(sender, &assemble(&object).unwrap())
+            .address
+            .unwrap();
+        for (slot, value) in interpreter.storage() {
+            vm.state.set_storage(&address, slot, value);
+        }
+
+        let mut checks: Vec<_> = fields
+            .iter()
+            .flat_map(|(name, dimensions, typ)| {
+                // Indexes one past the end of each dimension revert
+                let mut out_of_bounds = vec![0; dimensions.len()];
+                let mut indexes = all_indexes(dimensions);
+                for (dim, dimension) in dimensions.iter().enumerate() {
+                    out_of_bounds[dim] = *dimension;
+                    indexes.push(out_of_bounds.clone());
+                    out_of_bounds[dim] = 0;
+                }
+                indexes
+                    .into_iter()
+                    .map(move |indexes| (*name, indexes, typ.clone()))
+            })
+            .collect();
+        checks.push(("LIMIT", vec![], Type::Base(Base::Int128)));
+        checks.push(("RATE", vec![], Type::Base(Base::Decimal)));
+
+        for (name, indexes, typ) in checks {
+            let keys: Vec<_> = indexes.iter().map(|i| uint(*i)).collect();
+            let signature = format!("{}({})", name, vec!["uint256"; indexes.len()].join(","));
+            let mut data = selector(&signature).to_be_bytes().to_vec();
+            for index in &indexes {
+                data.extend_from_slice(&slot_word(*index));
+            }
+
+            let outcome = vm.call(sender, address, &data);
+            match interpreter.load(name, &keys) {
+                Ok(value) => {
+                    assert!(outcome.is_success(), "{} {:?}", name, indexes);
+                    assert_eq!(
+                        outcome.output,
+                        encode(&[typ], &[value]).unwrap(),
+                        "{} {:?}",
+                        name,
+                        indexes
+                    );
+                }
+                Err(err) => {
+                    assert_eq!(err, RuntimeError::Revert(None));
+                    assert!(!outcome.is_success(), "{} {:?}", name, indexes);
+                }
+            }
+        }
+    }
+}
+
+/// Return all combinations of indexes into arrays with the dimensions
+/// `dimensions` from the outermost in.
+fn all_indexes(dimensions: &[u64]) -> Vec<Vec<u64>> {
+    match dimensions.split_first() {
+        None => vec![vec![]],
+        Some((dimension, rest)) => (0..*dimension)
+            .flat_map(|i| {
+                all_indexes(rest).into_iter().map(move |mut indexes| {
+                    indexes.insert(0, i);
+                    indexes
+                })
+            })
+            .collect(),
+    }
+}
+
+#[test]
+fn test_differential_events() {
+    with_module(EVENTS, check_events_match);
+}
+
+/// Check that random emits of the events in `module` give the same logs when
+/// interpreted and when compiled.
+fn check_events_match(module: &Module) {
+    let events = vec![
+        (
+            "Transfer",
+            vec![
+                Type::Base(Base::Address),
+                Type::Base(Base::Address),
+                Type::Base(Base::Uint256),
+            ],
+        ),
+        (
+            "Note",
+            vec![
+                Type::Base(Base::Address),
+                Type::String { max_size: 32 },
+                Type::Base(Base::Int128),
+                Type::Bytes { max_size: 100 },
+                Type::Base(Base::Bool),
+                Type::String { max_size: 64 },
+            ],
+        ),
+        (
+            "Price",
+            vec![
+                Type::Base(Base::Uint256),
+                Type::Base(Base::Decimal),
+                Type::Base(Base::Bytes32),
+                Type::Base(Base::Bytes32),
+            ],
+        ),
+    ];
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+
+    for _ in 0..8 {
+        for (name, types) in &events {
+            let args: Vec<_> = types.iter().map(|typ| rng.value(typ)).collect();
+
+            let mut interpreter = Interpreter::new(module).unwrap();
+            let expected = interpreter
+                .transact(|contract| contract.emit(name, &args))
+                .unwrap();
+
+            assert_eq!(
+                emit_compiled(module, name, &args),
+                expected,
+                "{} {:?}",
+                name,
+                args
+            );
+        }
+    }
+}
+
+const TOKEN: &str = r#"
+event Transfer:
+    sender: indexed(address)
+    receiver: indexed(address)
+    value: uint256
+
+event Approval:
+    owner: indexed(address)
+    spender: indexed(address)
+    value: uint256
+
+name: public(string[64])
+symbol: public(string[32])
+decimals: public(uint256)
+totalSupply: public(uint256)
+balanceOf: public(map(address, uint256))
+allowance: public(map(address, map(address, uint256)))
+
+@external
+def __init__(_name: string[64], _symbol: string[32], _decimals: uint256, _supply: uint256):
+    init_supply: uint256 = _supply * 10 ** _decimals
+    self.name = _name
+    self.symbol = _symbol
+    self.decimals = _decimals
+    self.balanceOf[msg.sender] = init_supply
+    self.totalSupply = init_supply
+    log Transfer(empty(address), msg.sender, init_supply)
+
+@external
+def transfer(_to: address, _value: uint256) -> bool:
+    self.balanceOf[msg.sender] -= _value
+    self.balanceOf[_to] += _value
+    log Transfer(msg.sender, _to, _value)
+    return True
+
+@external
+def transferFrom(_from: address, _to: address, _value: uint256) -> bool:
+    self.balanceOf[_from] -= _value
+    self.balanceOf[_to] += _value
+    self.allowance[_from][msg.sender] -= _value
+    log Transfer(_from, _to, _value)
+    return True
+
+@external
+def approve(_spender: address, _value: uint256) -> bool:
+    self.allowance[msg.sender][_spender] = _value
+    log Approval(msg.sender, _spender, _value)
+    return True
+"#;
+
+const COMPUTE: &str = r#"
+event Recorded:
+    caller: indexed(address)
+    label: indexed(string[16])
+    size: int128
+    note: bytes[40]
+
+MAX_STEPS: constant(int128) = 8
+
+owner: address
+total: public(uint256)
+history: public(int128[4])
+amounts: map(string[16], uint256)
+last: public(address)
+
+@external
+def __init__():
+    self.owner = msg.sender
+
+@internal
+@pure
+def clamp(x: int128, lo: int128, hi: int128) -> int128:
+    if x < lo:
+        return lo
+    elif x > hi:
+        return hi
+    return x
+
+@external
+@pure
+def uint_ops(a: uint256, b: uint256) -> uint256:
+    r: uint256 = a + b * 2
+    r = r - a % (b + 1)
+    r += a / (b + 1)
+    return r ** 2 + (a & b | a ^ b) + (~a >> 250) + (b << 3)
+
+@external
+def int_ops(a: int128, b: int128) -> int128:
+    c: int128 = -a + b * 3
+    if b != 0:
+        c = c / b + a % b
+    return self.clamp(c ** 2 - c, -1000, 1000)
+
+@external
+def steps(n: int128, skip: int128) -> int128:
+    assert n <= MAX_STEPS, "too many steps"
+    acc: int128 = 0
+    for i in range(-2, MAX_STEPS):
+        if i >= n:
+            break
+        if i == skip:
+            continue
+        if acc > 50:
+            break
+        acc += i * i
+        self.history[(i + 2) % 4] = acc
+    return acc
+
+@external
+def record(label: string[16], amount: uint256, note: bytes[40]) -> uint256:
+    self.amounts[label] += amount
+    self.total += amount
+    self.last = msg.sender
+    log Recorded(msg.sender, label, len(note), note)
+    return self.amounts[label]
+
+@external
+@view
+def compare(a: uint256, b: uint256, x: bool, d: decimal, e: decimal, h: bytes32) -> bool:
+    return (a >= b and x) or (d < e and h != empty(bytes32)) or not (a == b or x)
+
+@external
+def guarded(amount: uint256) -> uint256:
+    if msg.sender != self.owner:
+        raise "not the owner"
+    assert amount < 100
+    self.total = amount
+    return self.total
+
+@external
+@view
+def describe(flag: bool) -> string[32]:
+    if flag:
+        return "set"
+    else:
+        return "unset"
+
+@external
+@view
+def stamp() -> uint256:
+    return block.number + block.timestamp
+"#;
+
+/// The accounts that call the contracts in the differential tests, the first
+/// of which deploys them.
+const SENDERS: [[u8; 20]; 3] = [[0x11; 20], [0x22; 20], [0x33; 20]];
+
+impl Rng {
+    /// Return a random argument of the type `typ`, biased towards small
+    /// numbers and known accounts so that calls succeed as well as revert.
+    fn arg(&mut self, typ: &Type) -> Value {
+        match (typ, self.next() % 3) {
+            (Type::Base(Base::Address), 0) => Value::Address(SENDERS[self.next() as usize % 3]),
+            (Type::Base(Base::Uint256), 0) => uint(self.next() % 8),
+            (Type::Base(Base::Int128), 0) => int((self.next() % 16) as i64 - 4),
+            (Type::Base(Base::Decimal), 0) => decimal((self.next() % 8) as i64 - 4),
+            _ => self.value(typ),
+        }
+    }
+}
+
+#[test]
+fn test_differential_functions() {
+    let uint256 = Type::Base(Base::Uint256);
+    with_module(TOKEN, |module| {
+        check_functions_match(
+            module,
+            &[
+                Type::String { max_size: 64 },
+                Type::String { max_size: 32 },
+                uint256.clone(),
+                uint256.clone(),
+            ],
+            &[
+                Value::String("Token".to_string()),
+                Value::String("TOK".to_string()),
+                uint(1),
+                uint(2),
+            ],
+        )
+    });
+    with_module(COMPUTE, |module| check_functions_match(module, &[], &[]));
+}
+
+/// Check that calls of the external functions of the contract in `module` with
+/// random arguments give the same results, logs and storage when interpreted
+/// and when compiled, after deploying it with the arguments `args` of the
+/// types `types`.
+fn check_functions_match(module: &Module, types: &[Type], args: &[Value]) {
+    let mut interpreter = Interpreter::new(module).unwrap();
+    let mut vm = Vm::new();
+    let object = compile_module("Test", module).unwrap();
+    let mut code = assemble(&object).unwrap();
+    code.extend(encode(types, args).unwrap());
+
+    let deployed = vm.deploy(SENDERS[0], &code);
+    assert!(deployed.is_success(), "{:?}", deployed);
+    let address = deployed.address.unwrap();
+    let context = |vm: &Vm, sender: [u8; 20]| Context {
+        address,
+        sender,
+        value: BigUint::from(0u32),
+        number: vm.block.number,
+        timestamp: vm.block.timestamp,
+    };
+    let logs = |outcome: &Outcome| -> Vec<_> {
+        outcome
+            .logs
+            .iter()
+            .map(|log| Log {
+                topics: log.topics.clone(),
+                data: log.data.clone(),
+            })
+            .collect()
+    };
+
+    let expected = interpreter.deploy(&context(&vm, SENDERS[0]), args).unwrap();
+    assert_eq!(logs(&deployed), expected);
+
+    let signatures: Vec<_> = interpreter
+        .signatures()
+        .into_iter()
+        .filter(|sig| sig.visibility == Visibility::External && !sig.is_constructor())
+        .cloned()
+        .collect();
+    let mut rng = Rng(0x853c_49e6_748f_ea9b);
+
+    for _ in 0..24 {
+        for sig in &signatures {
+            let sender = SENDERS[rng.next() as usize % SENDERS.len()];
+            let types: Vec<_> = sig.params.iter().map(|(_, typ)| typ.clone()).collect();
+            let args: Vec<_> = types.iter().map(|typ| rng.arg(typ)).collect();
+
+            let mut data = selector(&sig.abi_signature()).to_be_bytes().to_vec();
+            data.extend(encode(&types, &args).unwrap());
+            let outcome = vm.call(sender, address, &data);
+
+            match interpreter.call(&context(&vm, sender), &sig.name, &args) {
+                Ok((value, expected)) => {
+                    assert!(
+                        outcome.is_success(),
+                        "{} {:?} {:?}",
+                        sig.name,
+                        args,
+                        outcome
+                    );
+                    let output = match (&sig.return_type, value) {
+                        (Some(typ), Some(value)) => {
+                            encode(std::slice::from_ref(typ), &[value]).unwrap()
+                        }
+                        _ => vec![],
+                    };
+                    assert_eq!(outcome.output, output, "{} {:?}", sig.name, args);
+                    assert_eq!(logs(&outcome), expected, "{} {:?}", sig.name, args);
+                }
+                Err(RuntimeError::Revert(reason)) => {
+                    assert_eq!(outcome.status, Status::Revert, "{} {:?}", sig.name, args);
+                    assert_eq!(outcome.revert_reason(), reason, "{} {:?}", sig.name, args);
+                }
+                Err(err) => panic!("{} {:?}: {:?}", sig.name, args, err),
+            }
+
+            // The EVM only keeps storage slots holding non-zero values
+            let mut storage = interpreter.storage();
+            storage.retain(|_, value| *value != BigUint::from(0u32));
+            assert_eq!(
+                &storage,
+                &vm.state.account(&address).unwrap().storage,
+                "{} {:?}",
+                sig.name,
+                args
+            );
+        }
+    }
+}
@@ -0,0 +1,142 @@
+extern crate wasm_bindgen_test;
+
+use nom::error::VerboseError;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use vyper_analyzer::const_eval::module_constants;
+use vyper_analyzer::types::{
+    Base,
+    Type,
+};
+use vyper_compiler::errors::CompileError;
+use vyper_compiler::signatures::*;
+use vyper_parser::ast::ModuleStmt;
+use vyper_parser::parsers::{
+    file_input,
+    get_parse_tokens,
+};
+use vyper_parser::span::Span;
+
+/// Parse the module in `source` and build the signature of its first event
+/// with the module's constants.
+fn first_event_signature(source: &str) -> Result<String, CompileError> {
+    let tokens = get_parse_tokens(source).unwrap();
+    let (_, module) = file_input::<VerboseError<_>>(&tokens[..]).unwrap();
+    let env = module_constants(&module.node)?;
+
+    module
+        .node
+        .body
+        .iter()
+        .find_map(|stmt| match &stmt.node {
+            ModuleStmt::EventDef { name, fields } => Some(event_signature(name, fields, &env)),
+            _ => None,
+        })
+        .expect("no event definition")
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_signature() {
+    assert_eq!(
+        signature(
+            "transfer",
+            &[Type::Base(Base::Address), Type::Base(Base::Uint256)]
+        ),
+        "transfer(address,uint256)"
+    );
+    assert_eq!(signature("totalSupply", &[]), "totalSupply()");
+    assert_eq!(
+        signature(
+            "f",
+            &[
+                Type::Array {
+                    inner: Box::new(Type::Base(Base::Bool)),
+                    dimension: 2,
+                },
+                Type::Bytes { max_size: 10 },
+                Type::String { max_size: 10 },
+            ]
+        ),
+        "f(bool[2],bytes,string)"
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_selector() {
+    assert_eq!(selector("transfer(address,uint256)"), 0xa905_9cbb);
+    assert_eq!(selector("balanceOf(address)"), 0x70a0_8231);
+    assert_eq!(selector("totalSupply()"), 0x1816_0ddd);
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_event_signature_and_topic() {
+    let sig = first_event_signature(
+        "event Transfer:\n    sender: indexed(address)\n    receiver: indexed(address)\n    value: uint256\n",
+    )
+    .unwrap();
+
+    assert_eq!(sig, "Transfer(address,address,uint256)");
+    assert_eq!(
+        event_topic(&sig)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>(),
+        "ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"
+    );
+
+    assert_eq!(
+        first_event_signature(
+            "SIZE: constant(int128) = 2 * 16\nevent Foo:\n    a: bytes[SIZE][2]\n"
+        ),
+        Ok("Foo(bytes[2])".to_string())
+    );
+    assert_eq!(
+        first_event_signature("event Foo:\n    a: uint8\n"),
+        Err(CompileError::new("unknown type", Span::new(18, 23)))
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_selector_collisions() {
+    let functions = vec![
+        ("burn(uint256)".to_string(), Span::new(0, 10)),
+        ("transfer(address,uint256)".to_string(), Span::new(10, 20)),
+        (
+            "collate_propagate_storage(bytes16)".to_string(),
+            Span::new(20, 30),
+        ),
+    ];
+
+    let collisions = selector_collisions(&functions);
+    assert_eq!(
+        collisions,
+        vec![SelectorCollision {
+            selector: 0x4296_6c68,
+            first: functions[0].clone(),
+            second: functions[2].clone(),
+        }]
+    );
+    assert_eq!(
+        collisions[0].to_string(),
+        "function selector 0x42966c68 of collate_propagate_storage(bytes16) collides with \
+         burn(uint256)"
+    );
+
+    assert_eq!(selector_collisions(&functions[..2]), vec![]);
+
+    let err = check_selectors(&functions).unwrap_err();
+    assert_eq!(
+        err,
+        CompileError::new(collisions[0].to_string(), Span::new(20, 30))
+            .with_label("burn(uint256) is defined here", Span::new(0, 10))
+    );
+    assert!(err.msg.contains("collate_propagate_storage(bytes16)"));
+    assert!(err.msg.contains("burn(uint256)"));
+    assert_eq!(err.span, functions[2].1);
+    assert_eq!(err.labels[0].span, functions[0].1);
+    assert_eq!(check_selectors(&functions[..2]), Ok(()));
+}
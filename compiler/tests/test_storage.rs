@@ -0,0 +1,330 @@
+extern crate wasm_bindgen_test;
+
+#[macro_use]
+extern crate vyper_test_utils;
+
+use nom::error::VerboseError;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use vyper_analyzer::types::{
+    Base,
+    Type,
+};
+use vyper_compiler::errors::CompileError;
+use vyper_compiler::storage::*;
+use vyper_parser::ast::Module;
+use vyper_parser::parsers::{
+    file_input,
+    get_parse_tokens,
+};
+use vyper_parser::span::Span;
+
+/// Parse the module in `source` and build its storage layout as JSON.
+fn storage_layout_json(source: &str) -> Result<String, CompileError> {
+    let tokens = get_parse_tokens(source).unwrap();
+    let (_, module) = file_input::<VerboseError<_>>(&tokens[..]).unwrap();
+
+    build_storage_layout_json(&module.node)
+}
+
+fn assert_fixture_storage_layout(filename: &str, input: &str, expected: &str) {
+    assert_strings_eq!(
+        storage_layout_json(input).unwrap(),
+        expected,
+        "\nstorage layout did not match for {}",
+        filename,
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_storage_layout_fixtures() {
+    do_with_fixtures!(
+        assert_fixture_storage_layout,
+        "fixtures/storage_layout/maps.json",
+    );
+}
+
+fn field(name: &str, typ: StorageType, start: usize) -> StorageField {
+    StorageField {
+        name: name.to_string(),
+        typ,
+        span: Span::new(start, start + 10),
+    }
+}
+
+fn uint256() -> Type {
+    Type::Base(Base::Uint256)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_compute_layout() {
+    let point = Type::Struct {
+        name: "Point".to_string(),
+        fields: vec![
+            ("x".to_string(), uint256()),
+            (
+                "tags".to_string(),
+                Type::Array {
+                    inner: Box::new(Type::Base(Base::Bytes32)),
+                    dimension: 2,
+                },
+            ),
+        ],
+    };
+
+    let fields = vec![
+        field("owner", StorageType::Value(Type::Base(Base::Address)), 0),
+        field(
+            "balances",
+            StorageType::Map {
+                key: Type::Base(Base::Address),
+                value: Box::new(StorageType::Value(uint256())),
+            },
+            10,
+        ),
+        field(
+            "grid",
+            StorageType::Value(Type::Array {
+                inner: Box::new(Type::Array {
+                    inner: Box::new(uint256()),
+                    dimension: 3,
+                }),
+                dimension: 2,
+            }),
+            20,
+        ),
+        field(
+            "name",
+            StorageType::Value(Type::String { max_size: 33 }),
+            30,
+        ),
+        field("origin", StorageType::Value(point), 40),
+        field(
+            "allowances",
+            StorageType::Map {
+                key: Type::Base(Base::Address),
+                value: Box::new(StorageType::Map {
+                    key: Type::Bytes { max_size: 10 },
+                    value: Box::new(StorageType::Value(Type::Base(Base::Bool))),
+                }),
+            },
+            50,
+        ),
+        field("total", StorageType::Value(uint256()), 60),
+    ];
+
+    let layout = compute_layout(&fields).unwrap();
+    let summary: Vec<_> = layout
+        .iter()
+        .map(|f| (f.name.as_str(), f.typ.as_str(), f.slot, f.offset, f.slots))
+        .collect();
+
+    assert_eq!(
+        summary,
+        vec![
+            ("owner", "address", 0, 0, 1),
+            ("balances", "map(address, uint256)", 1, 0, 1),
+            ("grid", "uint256[3][2]", 2, 0, 6),
+            ("name", "string[33]", 8, 0, 3),
+            ("origin", "Point", 11, 0, 3),
+            ("allowances", "map(address, map(bytes[10], bool))", 14, 0, 1),
+            ("total", "uint256", 15, 0, 1),
+        ]
+    );
+    assert_eq!(layout[3].span, Span::new(30, 40));
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_layout_json() {
+    let layout = compute_layout(&[field("total", StorageType::Value(uint256()), 5)]).unwrap();
+
+    assert_eq!(
+        serde_json::to_string(&layout).unwrap(),
+        r#"[{"name":"total","type":"uint256","slot":0,"offset":0,"slots":1,"span":{"start":5,"end":15}}]"#,
+    );
+
+    assert_eq!(
+        build_storage_layout_json(&Module { body: vec![] }),
+        Ok("[]".to_string())
+    );
+}
+
+/// Parse the module in `source` and return its storage fields.
+fn parse_storage_fields(source: &str) -> Result<Vec<StorageField>, CompileError> {
+    let tokens = get_parse_tokens(source).unwrap();
+    let (_, module) = file_input::<VerboseError<_>>(&tokens[..]).unwrap();
+
+    storage_fields(&module.node)
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_storage_fields() {
+    let source = "SIZE: constant(uint256) = 2\n\
+                  owner: address\n\
+                  name: public(string[SIZE * 16])\n\
+                  event Transfer:\n    value: uint256\n\
+                  balances: uint256[SIZE][3]\n";
+    let fields = parse_storage_fields(source).unwrap();
+
+    assert_eq!(
+        fields,
+        vec![
+            StorageField {
+                name: "owner".to_string(),
+                typ: StorageType::Value(Type::Base(Base::Address)),
+                span: Span::new(28, 42),
+            },
+            StorageField {
+                name: "name".to_string(),
+                typ: StorageType::Value(Type::String { max_size: 32 }),
+                span: Span::new(43, 74),
+            },
+            StorageField {
+                name: "balances".to_string(),
+                typ: StorageType::Value(Type::Array {
+                    inner: Box::new(Type::Array {
+                        inner: Box::new(uint256()),
+                        dimension: 2,
+                    }),
+                    dimension: 3,
+                }),
+                span: Span::new(110, 136),
+            },
+        ]
+    );
+
+    let slots: Vec<_> = compute_layout(&fields)
+        .unwrap()
+        .iter()
+        .map(|f| (f.slot, f.slots))
+        .collect();
+    assert_eq!(slots, vec![(0, 1), (1, 2), (3, 6)]);
+
+    assert_eq!(
+        parse_storage_fields("owner: address\nowner: uint256\n"),
+        Err(CompileError::new(
+            "duplicate field definition",
+            Span::new(15, 29)
+        ))
+    );
+    assert_eq!(
+        parse_storage_fields("owner: address = 1\n"),
+        Err(CompileError::new(
+            "only constants may be assigned a value",
+            Span::new(17, 18)
+        ))
+    );
+    assert_eq!(
+        parse_storage_fields("owner: foo\n"),
+        Err(CompileError::new("unknown type", Span::new(7, 10)))
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_map_storage_fields() {
+    let fields = parse_storage_fields(
+        "allowed: map(address, map(bytes[4], uint256[2]))
+",
+    )
+    .unwrap();
+
+    assert_eq!(
+        fields,
+        vec![StorageField {
+            name: "allowed".to_string(),
+            typ: StorageType::Map {
+                key: Type::Base(Base::Address),
+                value: Box::new(StorageType::Map {
+                    key: Type::Bytes { max_size: 4 },
+                    value: Box::new(StorageType::Value(Type::Array {
+                        inner: Box::new(uint256()),
+                        dimension: 2,
+                    })),
+                }),
+            },
+            span: Span::new(0, 48),
+        }]
+    );
+    assert_eq!(
+        fields[0].typ.name(),
+        "map(address, map(bytes[4], uint256[2]))"
+    );
+
+    assert_eq!(
+        parse_storage_fields(
+            "allowed: map(uint256[2], bool)
+"
+        ),
+        Err(CompileError::new(
+            "map keys must have a base type or be byte arrays or strings",
+            Span::new(13, 23)
+        ))
+    );
+    assert_eq!(
+        parse_storage_fields(
+            "allowed: map(address, foo)
+"
+        ),
+        Err(CompileError::new("unknown type", Span::new(22, 25)))
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_layout_overflow() {
+    // Nested arrays of at most 2 ** 32 - 1 items each overflow a u64
+    let too_large =
+        parse_storage_fields("grid: uint256[4294967295][4294967295][4294967295]\n").unwrap();
+    assert_eq!(
+        compute_layout(&too_large),
+        Err(CompileError::new(
+            "storage field is too large",
+            Span::new(0, 49)
+        ))
+    );
+
+    // Every field fits but the fields together do not
+    let fields = parse_storage_fields(
+        "a: uint256[4294967295][4294967295]\n\
+         b: uint256[4294967295][4294967295]\n",
+    )
+    .unwrap();
+    assert!(compute_layout(&fields[..1]).is_ok());
+    assert_eq!(
+        compute_layout(&fields),
+        Err(CompileError::new(
+            "storage layout is too large",
+            Span::new(35, 69)
+        ))
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_map_value_slot() {
+    assert_eq!(
+        hex(&slot_word(258)),
+        "0000000000000000000000000000000000000000000000000000000000000102"
+    );
+
+    // The value for key zero of a map at slot zero
+    assert_eq!(
+        hex(&map_value_slot(&slot_word(0), &[0; 32])),
+        "ad3228b676f7d3cd4284a5443f17f1962b36e491b30a40b2405849e597ba5fb5"
+    );
+
+    // The slot comes before the key
+    assert_ne!(
+        map_value_slot(&slot_word(1), &slot_word(2)),
+        map_value_slot(&slot_word(2), &slot_word(1))
+    );
+}
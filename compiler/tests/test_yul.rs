@@ -0,0 +1,257 @@
+extern crate wasm_bindgen_test;
+
+#[macro_use]
+extern crate vyper_test_utils;
+
+use nom::error::VerboseError;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use vyper_compiler::errors::CompileError;
+use vyper_compiler::yul::runtime::{
+    dispatcher,
+    helper,
+};
+use vyper_compiler::yul::{
+    compile_module,
+    compile_module_to_string,
+};
+use vyper_parser::parsers::{
+    file_input,
+    get_parse_tokens,
+};
+use vyper_parser::span::Span;
+use vyper_yul::assembler::assemble;
+use vyper_yul::ast::{
+    Block,
+    Statement,
+};
+use vyper_yul::parsers::object_input;
+use vyper_yul::tokenizer::tokenize;
+
+/// Parse the module in `source` and compile it into YUL source code.
+fn compile(source: &str) -> Result<String, CompileError> {
+    let tokens = get_parse_tokens(source).unwrap();
+    let (_, module) = file_input::<VerboseError<_>>(&tokens[..]).unwrap();
+
+    compile_module_to_string("Test", &module.node)
+}
+
+fn assert_fixture_compiled(filename: &str, input: &str, expected: &str) {
+    let actual = compile(input).unwrap();
+
+    assert_strings_eq!(
+        actual.trim_end(),
+        expected,
+        "\nCompilation results did not match for {}",
+        filename,
+    );
+
+    // The expected YUL should parse back into the compiled object
+    let tokens = get_parse_tokens(input).unwrap();
+    let (_, module) = file_input::<VerboseError<_>>(&tokens[..]).unwrap();
+    let yul_tokens = tokenize(expected).unwrap();
+    let (_, parsed) = object_input::<VerboseError<_>>(&yul_tokens[..]).unwrap();
+
+    let object = compile_module("Test", &module.node).unwrap();
+    assert_eq!(parsed.node, object);
+
+    // Compiled objects can be assembled into bytecode
+    assemble(&object).unwrap();
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_compile_module_fixtures() {
+    do_with_fixtures!(
+        assert_fixture_compiled,
+        "fixtures/yul/greeter_events.yul",
+        "fixtures/yul/erc20_events.yul",
+        "fixtures/yul/dynamic_events.yul",
+        "fixtures/yul/public_getters.yul",
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_compile_module_empty() {
+    let tokens = get_parse_tokens("").unwrap();
+    let (_, module) = file_input::<VerboseError<_>>(&tokens[..]).unwrap();
+    let object = compile_module("Empty", &module.node).unwrap();
+
+    assert_eq!(object.name, "Empty");
+    assert_eq!(object.objects.len(), 1);
+    assert_eq!(object.objects[0].name, "Empty_deployed");
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_compile_module_errors() {
+    let examples = vec![
+        (
+            "event Foo:\n    a: uint256\nevent Foo:\n    b: uint256\n",
+            CompileError::new("duplicate event definition", Span::new(26, 51)),
+        ),
+        (
+            "event Foo:\n    a: uint8\n",
+            CompileError::new("unknown type", Span::new(18, 23)),
+        ),
+        (
+            "event Foo:\n    a: uint256[2]\n",
+            CompileError::new(
+                "event fields with array types are not supported",
+                Span::new(18, 28),
+            ),
+        ),
+        (
+            r#"event Foo:
+    a: indexed(uint256)
+    b: indexed(uint256)
+    c: indexed(uint256)
+    d: indexed(uint256)
+"#,
+            CompileError::new(
+                "events may have at most three indexed fields",
+                Span::new(87, 106),
+            ),
+        ),
+        (
+            "event Foo:\n    a: uint256\n    a: bool\n",
+            CompileError::new("duplicate event field", Span::new(30, 37)),
+        ),
+        (
+            "FLAG: public(constant(bool)) = 1\n",
+            CompileError::new(
+                "public constants must have a numeric type",
+                Span::new(0, 32),
+            ),
+        ),
+        (
+            "MIN: public(constant(uint256)) = -1\n",
+            CompileError::new(
+                "constant value is out of bounds for its type",
+                Span::new(0, 35),
+            ),
+        ),
+        (
+            "MAX: public(constant(int128)) = 2 ** 127\n",
+            CompileError::new(
+                "constant value is out of bounds for its type",
+                Span::new(0, 40),
+            ),
+        ),
+        (
+            "grid: uint256[4294967295][4294967295][4294967295]\n",
+            CompileError::new("storage field is too large", Span::new(0, 49)),
+        ),
+        (
+            // The getters `f8491()` and `f130736()` share the selector 0x62018627
+            "f8491: public(uint256)\nf130736: public(bool)\n",
+            CompileError::new(
+                "function selector 0x62018627 of f130736() collides with f8491()",
+                Span::new(23, 44),
+            )
+            .with_label("f8491() is defined here", Span::new(0, 22)),
+        ),
+    ];
+
+    for (inp, expected) in examples {
+        assert_eq!(compile(inp), Err(expected), "{}", inp);
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_runtime_helpers() {
+    assert_eq!(helper("unknown"), None);
+
+    assert_eq!(
+        helper("checked_add_uint256").unwrap().to_string(),
+        r#"function checked_add_uint256(a, b) -> r {
+    r := add(a, b)
+    if lt(r, a) {
+        revert(0, 0)
+    }
+}
+"#,
+    );
+    assert_eq!(
+        helper("checked_div_int128").unwrap().to_string(),
+        r#"function checked_div_int128(a, b) -> r {
+    if iszero(b) {
+        revert(0, 0)
+    }
+    r := sdiv(a, b)
+    if or(sgt(r, 0x7fffffffffffffffffffffffffffffff), slt(r, not(0x7fffffffffffffffffffffffffffffff))) {
+        revert(0, 0)
+    }
+}
+"#,
+    );
+    assert_eq!(
+        helper("decode_address").unwrap().to_string(),
+        r#"function decode_address(offset) -> v {
+    if lt(calldatasize(), add(offset, 32)) {
+        revert(0, 0)
+    }
+    v := calldataload(offset)
+    if shr(160, v) {
+        revert(0, 0)
+    }
+}
+"#,
+    );
+
+    for name in &[
+        "checked_add_uint256",
+        "checked_sub_uint256",
+        "checked_mul_uint256",
+        "checked_div_uint256",
+        "checked_mod_uint256",
+        "checked_add_int128",
+        "checked_sub_int128",
+        "checked_mul_int128",
+        "checked_div_int128",
+        "checked_mod_int128",
+        "decode_address",
+        "decode_bool",
+        "decode_bytes32",
+        "decode_int128",
+        "decode_uint256",
+        "abi_encode_bytes",
+    ] {
+        match helper(name) {
+            Some(Statement::FunctionDefinition {
+                name: func_name, ..
+            }) => {
+                assert_eq!(&func_name, name)
+            }
+            _ => panic!("no function definition for helper {}", name),
+        }
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_dispatcher() {
+    let stmts = dispatcher(vec![]);
+    assert_eq!(Block::new(stmts).to_string(), "{\n    revert(0, 0)\n}\n");
+
+    let stmts = dispatcher(vec![
+        (0xa9059cbb, Block::new(vec![Statement::Leave])),
+        (0x70a08231, Block::default()),
+    ]);
+    assert_eq!(
+        Block::new(stmts).to_string(),
+        r#"{
+    if iszero(lt(calldatasize(), 4)) {
+        switch shr(224, calldataload(0))
+        case 0xa9059cbb {
+            leave
+        }
+        case 0x70a08231 { }
+    }
+    revert(0, 0)
+}
+"#,
+    );
+}
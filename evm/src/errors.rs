@@ -0,0 +1,15 @@
+/// An error that halts execution exceptionally.  All state changes are
+/// reverted and all remaining gas is consumed.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ExecutionError {
+    /// A description of the error.
+    pub msg: &'static str,
+    /// The position in the code at which the error occurred.
+    pub pc: usize,
+}
+
+impl ExecutionError {
+    pub fn new(msg: &'static str, pc: usize) -> Self {
+        ExecutionError { msg, pc }
+    }
+}
@@ -0,0 +1,67 @@
+//! Gas costs of EVM operations as of the Istanbul hard fork.
+//!
+//! Storage writes are charged without refunds: setting a zero slot to a
+//! non-zero value costs `SSTORE_SET` and every other write costs
+//! `SSTORE_RESET`.
+
+pub const BASE: u64 = 2;
+pub const VERY_LOW: u64 = 3;
+pub const LOW: u64 = 5;
+pub const MID: u64 = 8;
+pub const HIGH: u64 = 10;
+pub const JUMPDEST: u64 = 1;
+
+pub const EXP: u64 = 10;
+pub const EXP_BYTE: u64 = 50;
+pub const KECCAK256: u64 = 30;
+pub const KECCAK256_WORD: u64 = 6;
+pub const COPY_WORD: u64 = 3;
+
+pub const BALANCE: u64 = 700;
+pub const EXTCODE: u64 = 700;
+pub const BLOCKHASH: u64 = 20;
+pub const SELFBALANCE: u64 = 5;
+
+pub const SLOAD: u64 = 800;
+pub const SSTORE_SET: u64 = 20000;
+pub const SSTORE_RESET: u64 = 5000;
+
+pub const LOG: u64 = 375;
+pub const LOG_TOPIC: u64 = 375;
+pub const LOG_DATA: u64 = 8;
+
+pub const MEMORY_WORD: u64 = 3;
+pub const MEMORY_QUAD_DIVISOR: u64 = 512;
+
+pub const TRANSACTION: u64 = 21000;
+pub const TRANSACTION_CREATE: u64 = 32000;
+pub const TRANSACTION_ZERO_BYTE: u64 = 4;
+pub const TRANSACTION_NON_ZERO_BYTE: u64 = 16;
+pub const CODE_DEPOSIT_BYTE: u64 = 200;
+
+/// Return the number of words needed to hold `size` bytes.
+pub fn words(size: u64) -> u64 {
+    size.div_ceil(32)
+}
+
+/// Return the total cost of a memory of `words` words.
+pub fn memory(words: u64) -> u64 {
+    MEMORY_WORD * words + words * words / MEMORY_QUAD_DIVISOR
+}
+
+/// Return the intrinsic cost of a transaction with the data `data`.
+pub fn intrinsic(data: &[u8], create: bool) -> u64 {
+    let base = if create {
+        TRANSACTION + TRANSACTION_CREATE
+    } else {
+        TRANSACTION
+    };
+
+    data.iter().fold(base, |cost, byte| {
+        cost + if *byte == 0 {
+            TRANSACTION_ZERO_BYTE
+        } else {
+            TRANSACTION_NON_ZERO_BYTE
+        }
+    })
+}
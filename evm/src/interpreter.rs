@@ -0,0 +1,749 @@
+//! Execution of EVM bytecode in a single call frame.
+//!
+//! Words are represented as unsigned integers less than 2^256.  Signed
+//! operations interpret words as two's complement integers.
+
+use num_bigint::{
+    BigInt,
+    BigUint,
+    Sign,
+};
+use num_traits::{
+    One,
+    ToPrimitive,
+    Zero,
+};
+
+use crate::errors::ExecutionError;
+use crate::gas;
+use crate::state::{
+    keccak256,
+    Address,
+    Block,
+    Log,
+    State,
+};
+
+/// The largest number of values on the stack.
+const MAX_STACK_SIZE: usize = 1024;
+
+/// The largest memory offset or size that is accepted.  Larger values would
+/// require more gas than is available in any block.
+const MAX_MEMORY_SIZE: u64 = 1 << 32;
+
+/// Return 2^256.
+fn modulus() -> BigUint {
+    BigUint::one() << 256
+}
+
+/// Return the word `value` interpreted as a two's complement integer.
+fn to_signed(value: &BigUint) -> BigInt {
+    if value.bits() == 256 {
+        BigInt::from(value.clone()) - BigInt::from(modulus())
+    } else {
+        BigInt::from(value.clone())
+    }
+}
+
+/// Return the word holding the two's complement representation of `value`.
+fn from_signed(value: &BigInt) -> BigUint {
+    let modulus = BigInt::from(modulus());
+
+    (((value % &modulus) + &modulus) % &modulus)
+        .to_biguint()
+        .expect("word is not negative")
+}
+
+/// Return the 32 big-endian bytes of the word `value`.
+pub fn word_bytes(value: &BigUint) -> [u8; 32] {
+    let bytes = value.to_bytes_be();
+    let mut word = [0; 32];
+    word[32 - bytes.len()..].copy_from_slice(&bytes);
+
+    word
+}
+
+/// Return the word that starts at byte offset `offset` of `data`, padded with
+/// zeros if `data` is too short.
+fn read_padded(data: &[u8], offset: &BigUint, size: usize) -> Vec<u8> {
+    let mut result = vec![0; size];
+
+    if let Some(offset) = offset.to_usize() {
+        if offset < data.len() {
+            let end = data.len().min(offset.saturating_add(size));
+            result[..end - offset].copy_from_slice(&data[offset..end]);
+        }
+    }
+
+    result
+}
+
+/// Return a table indicating which positions in `code` are valid jump
+/// destinations, i.e. `JUMPDEST` opcodes that are not part of push data.
+fn jump_destinations(code: &[u8]) -> Vec<bool> {
+    let mut valid = vec![false; code.len()];
+    let mut pc = 0;
+
+    while pc < code.len() {
+        match code[pc] {
+            0x5b => valid[pc] = true,
+            op @ 0x60..=0x7f => pc += (op - 0x5f) as usize,
+            _ => {}
+        }
+        pc += 1;
+    }
+
+    valid
+}
+
+/// The context in which code is executed.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Environment<'a> {
+    /// The address of the account whose code is executed.
+    pub address: Address,
+    pub caller: Address,
+    /// The sender of the transaction.
+    pub origin: Address,
+    pub value: BigUint,
+    pub calldata: &'a [u8],
+    pub gas_price: BigUint,
+    pub block: &'a Block,
+}
+
+/// The manner in which the execution of a frame ended.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Status {
+    /// Execution stopped or returned normally.
+    Success,
+    /// Execution was reverted by the `REVERT` opcode.  Remaining gas is
+    /// refunded.
+    Revert,
+    /// Execution halted with an error.
+    Halt(ExecutionError),
+}
+
+/// The result of executing a frame.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Execution {
+    pub status: Status,
+    /// The data returned by `RETURN` or `REVERT`.
+    pub output: Vec<u8>,
+    pub gas_left: u64,
+    /// The logs emitted by a successful execution.
+    pub logs: Vec<Log>,
+}
+
+/// The state of a frame during execution.
+struct Frame<'a, 'b> {
+    code: &'a [u8],
+    env: &'a Environment<'a>,
+    state: &'b mut State,
+    jump_destinations: Vec<bool>,
+    pc: usize,
+    stack: Vec<BigUint>,
+    memory: Vec<u8>,
+    gas_left: u64,
+    logs: Vec<Log>,
+}
+
+/// The way in which the execution of an instruction ends.
+enum Step {
+    Continue,
+    Stop,
+    Return(Vec<u8>),
+    Revert(Vec<u8>),
+}
+
+impl<'a, 'b> Frame<'a, 'b> {
+    fn error(&self, msg: &'static str) -> ExecutionError {
+        ExecutionError::new(msg, self.pc)
+    }
+
+    fn charge(&mut self, cost: u64) -> Result<(), ExecutionError> {
+        if cost > self.gas_left {
+            return Err(self.error("out of gas"));
+        }
+
+        self.gas_left -= cost;
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<BigUint, ExecutionError> {
+        self.stack
+            .pop()
+            .ok_or_else(|| self.error("stack underflow"))
+    }
+
+    fn push(&mut self, value: BigUint) -> Result<(), ExecutionError> {
+        if self.stack.len() == MAX_STACK_SIZE {
+            return Err(self.error("stack overflow"));
+        }
+
+        self.stack.push(value);
+        Ok(())
+    }
+
+    fn push_bool(&mut self, value: bool) -> Result<(), ExecutionError> {
+        self.push(BigUint::from(value as u8))
+    }
+
+    fn push_bytes(&mut self, bytes: &[u8]) -> Result<(), ExecutionError> {
+        self.push(BigUint::from_bytes_be(bytes))
+    }
+
+    fn push_signed(&mut self, value: &BigInt) -> Result<(), ExecutionError> {
+        self.push(from_signed(value))
+    }
+
+    /// Convert a memory offset or size to an integer.
+    fn memory_arg(&self, value: &BigUint) -> Result<u64, ExecutionError> {
+        value
+            .to_u64()
+            .filter(|value| *value <= MAX_MEMORY_SIZE)
+            .ok_or_else(|| self.error("out of gas"))
+    }
+
+    /// Expand memory to include the `size` bytes at offset `offset`, charging
+    /// for any new words, and return the range as integers.  Ranges of size
+    /// zero do not expand memory.
+    fn expand(
+        &mut self,
+        offset: &BigUint,
+        size: &BigUint,
+    ) -> Result<(usize, usize), ExecutionError> {
+        if size.is_zero() {
+            return Ok((0, 0));
+        }
+
+        let offset = self.memory_arg(offset)?;
+        let size = self.memory_arg(size)?;
+        let old_words = gas::words(self.memory.len() as u64);
+        let new_words = gas::words(offset + size);
+
+        if new_words > old_words {
+            self.charge(gas::memory(new_words) - gas::memory(old_words))?;
+            self.memory.resize(new_words as usize * 32, 0);
+        }
+
+        Ok((offset as usize, size as usize))
+    }
+
+    /// Pop a memory offset and size and return the memory in that range.
+    fn pop_memory_range(&mut self) -> Result<Vec<u8>, ExecutionError> {
+        let offset = self.pop()?;
+        let size = self.pop()?;
+        let (offset, size) = self.expand(&offset, &size)?;
+
+        Ok(self.memory[offset..offset + size].to_vec())
+    }
+
+    /// Copy the bytes of `data` at the offset on the stack into memory, padding
+    /// with zeros past the end of `data`.
+    fn copy_to_memory(&mut self, data: &[u8]) -> Result<(), ExecutionError> {
+        let dest = self.pop()?;
+        let offset = self.pop()?;
+        let size = self.pop()?;
+
+        let (dest, size) = self.expand(&dest, &size)?;
+        self.charge(gas::COPY_WORD * gas::words(size as u64))?;
+
+        let bytes = read_padded(data, &offset, size);
+        self.memory[dest..dest + size].copy_from_slice(&bytes);
+
+        Ok(())
+    }
+
+    fn pop_address(&mut self) -> Result<Address, ExecutionError> {
+        let word = word_bytes(&self.pop()?);
+        let mut address = [0; 20];
+        address.copy_from_slice(&word[12..]);
+
+        Ok(address)
+    }
+
+    fn binary_op(
+        &mut self,
+        op: impl Fn(BigUint, BigUint) -> BigUint,
+    ) -> Result<(), ExecutionError> {
+        let a = self.pop()?;
+        let b = self.pop()?;
+
+        self.push(op(a, b) % modulus())
+    }
+
+    fn signed_op(&mut self, op: impl Fn(BigInt, BigInt) -> BigInt) -> Result<(), ExecutionError> {
+        let a = to_signed(&self.pop()?);
+        let b = to_signed(&self.pop()?);
+
+        self.push_signed(&op(a, b))
+    }
+
+    fn jump(&mut self, dest: &BigUint) -> Result<(), ExecutionError> {
+        match dest.to_usize() {
+            Some(dest) if dest < self.code.len() && self.jump_destinations[dest] => {
+                self.pc = dest;
+                Ok(())
+            }
+            _ => Err(self.error("invalid jump destination")),
+        }
+    }
+
+    /// Execute the instruction at the current position.
+    fn step(&mut self) -> Result<Step, ExecutionError> {
+        let op = match self.code.get(self.pc) {
+            Some(op) => *op,
+            None => return Ok(Step::Stop),
+        };
+
+        match op {
+            0x00 => return Ok(Step::Stop),
+            0x01 => {
+                self.charge(gas::VERY_LOW)?;
+                self.binary_op(|a, b| a + b)?;
+            }
+            0x02 => {
+                self.charge(gas::LOW)?;
+                self.binary_op(|a, b| a * b)?;
+            }
+            0x03 => {
+                self.charge(gas::VERY_LOW)?;
+                self.binary_op(|a, b| a + modulus() - b)?;
+            }
+            0x04 => {
+                self.charge(gas::LOW)?;
+                self.binary_op(|a, b| if b.is_zero() { b } else { a / b })?;
+            }
+            0x05 => {
+                self.charge(gas::LOW)?;
+                // Division truncates towards zero
+                self.signed_op(|a, b| if b.is_zero() { b } else { a / b })?;
+            }
+            0x06 => {
+                self.charge(gas::LOW)?;
+                self.binary_op(|a, b| if b.is_zero() { b } else { a % b })?;
+            }
+            0x07 => {
+                self.charge(gas::LOW)?;
+                // The result has the sign of the dividend
+                self.signed_op(|a, b| if b.is_zero() { b } else { a % b })?;
+            }
+            0x08 | 0x09 => {
+                self.charge(gas::MID)?;
+                let a = self.pop()?;
+                let b = self.pop()?;
+                let n = self.pop()?;
+
+                if n.is_zero() {
+                    self.push(n)?;
+                } else if op == 0x08 {
+                    self.push((a + b) % n)?;
+                } else {
+                    self.push((a * b) % n)?;
+                }
+            }
+            0x0a => {
+                let base = self.pop()?;
+                let exponent = self.pop()?;
+
+                let exponent_bytes = (exponent.bits() as u64).div_ceil(8);
+                self.charge(gas::EXP + gas::EXP_BYTE * exponent_bytes)?;
+                self.push(base.modpow(&exponent, &modulus()))?;
+            }
+            0x0b => {
+                self.charge(gas::LOW)?;
+                let size = self.pop()?;
+                let value = self.pop()?;
+
+                match size.to_usize().filter(|size| *size < 31) {
+                    Some(size) => {
+                        let bits = (size + 1) * 8;
+                        let low = &value & ((BigUint::one() << bits) - 1u8);
+
+                        if low.bits() == bits {
+                            let high = modulus() - (BigUint::one() << bits);
+                            self.push(high | low)?;
+                        } else {
+                            self.push(low)?;
+                        }
+                    }
+                    None => self.push(value)?,
+                }
+            }
+            0x10..=0x14 => {
+                self.charge(gas::VERY_LOW)?;
+                let a = self.pop()?;
+                let b = self.pop()?;
+
+                let result = match op {
+                    0x10 => a < b,
+                    0x11 => a > b,
+                    0x12 => to_signed(&a) < to_signed(&b),
+                    0x13 => to_signed(&a) > to_signed(&b),
+                    _ => a == b,
+                };
+                self.push_bool(result)?;
+            }
+            0x15 => {
+                self.charge(gas::VERY_LOW)?;
+                let a = self.pop()?;
+                self.push_bool(a.is_zero())?;
+            }
+            0x16 => {
+                self.charge(gas::VERY_LOW)?;
+                self.binary_op(|a, b| a & b)?;
+            }
+            0x17 => {
+                self.charge(gas::VERY_LOW)?;
+                self.binary_op(|a, b| a | b)?;
+            }
+            0x18 => {
+                self.charge(gas::VERY_LOW)?;
+                self.binary_op(|a, b| a ^ b)?;
+            }
+            0x19 => {
+                self.charge(gas::VERY_LOW)?;
+                let a = self.pop()?;
+                self.push(modulus() - 1u8 - a)?;
+            }
+            0x1a => {
+                self.charge(gas::VERY_LOW)?;
+                let index = self.pop()?;
+                let value = self.pop()?;
+
+                let byte = match index.to_usize() {
+                    Some(index) if index < 32 => word_bytes(&value)[index],
+                    _ => 0,
+                };
+                self.push(BigUint::from(byte))?;
+            }
+            0x1b..=0x1d => {
+                self.charge(gas::VERY_LOW)?;
+                let shift = self.pop()?.to_usize().filter(|shift| *shift < 256);
+                let value = self.pop()?;
+
+                let result = match (op, shift) {
+                    (0x1b, Some(shift)) => (value << shift) % modulus(),
+                    (0x1c, Some(shift)) => value >> shift,
+                    (0x1d, shift) => {
+                        // Arithmetic shifts round towards negative infinity
+                        let value = to_signed(&value);
+                        let shifted = match shift {
+                            Some(shift) => value >> shift,
+                            None if value.sign() == Sign::Minus => BigInt::from(-1),
+                            None => BigInt::zero(),
+                        };
+                        from_signed(&shifted)
+                    }
+                    _ => BigUint::zero(),
+                };
+                self.push(result)?;
+            }
+            0x20 => {
+                let offset = self.pop()?;
+                let size = self.pop()?;
+                let (offset, size) = self.expand(&offset, &size)?;
+
+                self.charge(gas::KECCAK256 + gas::KECCAK256_WORD * gas::words(size as u64))?;
+                let hash = keccak256(&self.memory[offset..offset + size]);
+                self.push_bytes(&hash)?;
+            }
+            0x30 => {
+                self.charge(gas::BASE)?;
+                self.push_bytes(&self.env.address)?;
+            }
+            0x31 => {
+                self.charge(gas::BALANCE)?;
+                let address = self.pop_address()?;
+                self.push(self.state.balance(&address))?;
+            }
+            0x32 => {
+                self.charge(gas::BASE)?;
+                self.push_bytes(&self.env.origin)?;
+            }
+            0x33 => {
+                self.charge(gas::BASE)?;
+                self.push_bytes(&self.env.caller)?;
+            }
+            0x34 => {
+                self.charge(gas::BASE)?;
+                self.push(self.env.value.clone())?;
+            }
+            0x35 => {
+                self.charge(gas::VERY_LOW)?;
+                let offset = self.pop()?;
+                let word = read_padded(self.env.calldata, &offset, 32);
+                self.push_bytes(&word)?;
+            }
+            0x36 => {
+                self.charge(gas::BASE)?;
+                self.push(BigUint::from(self.env.calldata.len()))?;
+            }
+            0x37 => {
+                self.charge(gas::VERY_LOW)?;
+                self.copy_to_memory(self.env.calldata)?;
+            }
+            0x38 => {
+                self.charge(gas::BASE)?;
+                self.push(BigUint::from(self.code.len()))?;
+            }
+            0x39 => {
+                self.charge(gas::VERY_LOW)?;
+                self.copy_to_memory(self.code)?;
+            }
+            0x3a => {
+                self.charge(gas::BASE)?;
+                self.push(self.env.gas_price.clone())?;
+            }
+            0x3b => {
+                self.charge(gas::EXTCODE)?;
+                let address = self.pop_address()?;
+                self.push(BigUint::from(self.state.code(&address).len()))?;
+            }
+            0x3c => {
+                self.charge(gas::EXTCODE)?;
+                let address = self.pop_address()?;
+                let code = self.state.code(&address).to_vec();
+                self.copy_to_memory(&code)?;
+            }
+            0x3d => {
+                // Calls are not supported so the return data is always empty
+                self.charge(gas::BASE)?;
+                self.push(BigUint::zero())?;
+            }
+            0x3e => {
+                self.charge(gas::VERY_LOW)?;
+                let dest = self.pop()?;
+                let offset = self.pop()?;
+                let size = self.pop()?;
+
+                if !(offset.is_zero() && size.is_zero()) {
+                    return Err(self.error("return data out of bounds"));
+                }
+                self.expand(&dest, &size)?;
+            }
+            0x3f => {
+                self.charge(gas::EXTCODE)?;
+                let address = self.pop_address()?;
+
+                match self.state.account(&address) {
+                    Some(account) => {
+                        let hash = keccak256(&account.code);
+                        self.push_bytes(&hash)?;
+                    }
+                    None => self.push(BigUint::zero())?,
+                }
+            }
+            0x40 => {
+                // Hashes of previous blocks are not available
+                self.charge(gas::BLOCKHASH)?;
+                self.pop()?;
+                self.push(BigUint::zero())?;
+            }
+            0x41 => {
+                self.charge(gas::BASE)?;
+                self.push_bytes(&self.env.block.coinbase)?;
+            }
+            0x42 => {
+                self.charge(gas::BASE)?;
+                self.push(BigUint::from(self.env.block.timestamp))?;
+            }
+            0x43 => {
+                self.charge(gas::BASE)?;
+                self.push(BigUint::from(self.env.block.number))?;
+            }
+            0x44 => {
+                self.charge(gas::BASE)?;
+                self.push(self.env.block.difficulty.clone())?;
+            }
+            0x45 => {
+                self.charge(gas::BASE)?;
+                self.push(BigUint::from(self.env.block.gas_limit))?;
+            }
+            0x46 => {
+                self.charge(gas::BASE)?;
+                self.push(BigUint::from(self.env.block.chain_id))?;
+            }
+            0x47 => {
+                self.charge(gas::SELFBALANCE)?;
+                self.push(self.state.balance(&self.env.address))?;
+            }
+            0x48 => {
+                self.charge(gas::BASE)?;
+                self.push(self.env.block.base_fee.clone())?;
+            }
+            0x50 => {
+                self.charge(gas::BASE)?;
+                self.pop()?;
+            }
+            0x51 => {
+                self.charge(gas::VERY_LOW)?;
+                let offset = self.pop()?;
+                let (offset, _) = self.expand(&offset, &BigUint::from(32u8))?;
+                let word = self.memory[offset..offset + 32].to_vec();
+                self.push_bytes(&word)?;
+            }
+            0x52 => {
+                self.charge(gas::VERY_LOW)?;
+                let offset = self.pop()?;
+                let value = self.pop()?;
+                let (offset, _) = self.expand(&offset, &BigUint::from(32u8))?;
+                self.memory[offset..offset + 32].copy_from_slice(&word_bytes(&value));
+            }
+            0x53 => {
+                self.charge(gas::VERY_LOW)?;
+                let offset = self.pop()?;
+                let value = self.pop()?;
+                let (offset, _) = self.expand(&offset, &BigUint::one())?;
+                self.memory[offset] = word_bytes(&value)[31];
+            }
+            0x54 => {
+                self.charge(gas::SLOAD)?;
+                let slot = self.pop()?;
+                self.push(self.state.storage(&self.env.address, &slot))?;
+            }
+            0x55 => {
+                let slot = self.pop()?;
+                let value = self.pop()?;
+
+                let current = self.state.storage(&self.env.address, &slot);
+                if current.is_zero() && !value.is_zero() {
+                    self.charge(gas::SSTORE_SET)?;
+                } else {
+                    self.charge(gas::SSTORE_RESET)?;
+                }
+                self.state.set_storage(&self.env.address, slot, value);
+            }
+            0x56 => {
+                self.charge(gas::MID)?;
+                let dest = self.pop()?;
+                self.jump(&dest)?;
+                return Ok(Step::Continue);
+            }
+            0x57 => {
+                self.charge(gas::HIGH)?;
+                let dest = self.pop()?;
+                let condition = self.pop()?;
+
+                if !condition.is_zero() {
+                    self.jump(&dest)?;
+                    return Ok(Step::Continue);
+                }
+            }
+            0x58 => {
+                self.charge(gas::BASE)?;
+                self.push(BigUint::from(self.pc))?;
+            }
+            0x59 => {
+                self.charge(gas::BASE)?;
+                self.push(BigUint::from(self.memory.len()))?;
+            }
+            0x5a => {
+                self.charge(gas::BASE)?;
+                self.push(BigUint::from(self.gas_left))?;
+            }
+            0x5b => self.charge(gas::JUMPDEST)?,
+            0x60..=0x7f => {
+                self.charge(gas::VERY_LOW)?;
+                let size = (op - 0x5f) as usize;
+                let value = read_padded(self.code, &BigUint::from(self.pc + 1), size);
+
+                self.push_bytes(&value)?;
+                self.pc += size;
+            }
+            0x80..=0x8f => {
+                self.charge(gas::VERY_LOW)?;
+                let depth = (op - 0x7f) as usize;
+                if depth > self.stack.len() {
+                    return Err(self.error("stack underflow"));
+                }
+
+                let value = self.stack[self.stack.len() - depth].clone();
+                self.push(value)?;
+            }
+            0x90..=0x9f => {
+                self.charge(gas::VERY_LOW)?;
+                let depth = (op - 0x8f) as usize;
+                if depth >= self.stack.len() {
+                    return Err(self.error("stack underflow"));
+                }
+
+                let top = self.stack.len() - 1;
+                self.stack.swap(top, top - depth);
+            }
+            0xa0..=0xa4 => {
+                let offset = self.pop()?;
+                let size = self.pop()?;
+                let count = (op - 0xa0) as usize;
+
+                let mut topics = vec![];
+                for _ in 0..count {
+                    topics.push(word_bytes(&self.pop()?));
+                }
+
+                let (offset, size) = self.expand(&offset, &size)?;
+                self.charge(
+                    gas::LOG + gas::LOG_TOPIC * count as u64 + gas::LOG_DATA * size as u64,
+                )?;
+                self.logs.push(Log {
+                    address: self.env.address,
+                    topics,
+                    data: self.memory[offset..offset + size].to_vec(),
+                });
+            }
+            0xf3 => return Ok(Step::Return(self.pop_memory_range()?)),
+            0xfd => return Ok(Step::Revert(self.pop_memory_range()?)),
+            0xf0..=0xf2 | 0xf4 | 0xf5 | 0xfa | 0xff => {
+                return Err(self.error("unsupported opcode"));
+            }
+            _ => return Err(self.error("invalid opcode")),
+        }
+
+        self.pc += 1;
+        Ok(Step::Continue)
+    }
+}
+
+/// Execute the code `code` in the environment `env` with `gas` gas.  Storage
+/// writes are applied to `state` even if execution does not succeed.
+pub fn execute(code: &[u8], env: &Environment, state: &mut State, gas: u64) -> Execution {
+    let mut frame = Frame {
+        code,
+        env,
+        state,
+        jump_destinations: jump_destinations(code),
+        pc: 0,
+        stack: vec![],
+        memory: vec![],
+        gas_left: gas,
+        logs: vec![],
+    };
+
+    loop {
+        let (status, output) = match frame.step() {
+            Ok(Step::Continue) => continue,
+            Ok(Step::Stop) => (Status::Success, vec![]),
+            Ok(Step::Return(output)) => (Status::Success, output),
+            Ok(Step::Revert(output)) => (Status::Revert, output),
+            Err(err) => {
+                return Execution {
+                    status: Status::Halt(err),
+                    output: vec![],
+                    gas_left: 0,
+                    logs: vec![],
+                }
+            }
+        };
+
+        let logs = match status {
+            Status::Success => frame.logs,
+            _ => vec![],
+        };
+
+        return Execution {
+            status,
+            output,
+            gas_left: frame.gas_left,
+            logs,
+        };
+    }
+}
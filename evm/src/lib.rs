@@ -0,0 +1,12 @@
+//! An in-process EVM interpreter for executing compiled contracts in tests.
+//!
+//! The interpreter supports the opcodes emitted by the compiler along with
+//! in-memory account storage, logs, calldata and return data, gas accounting,
+//! and revert reasons.  Calls to other contracts and contract creation from
+//! within running code are not supported.
+
+pub mod errors;
+pub mod gas;
+pub mod interpreter;
+pub mod state;
+pub mod vm;
@@ -0,0 +1,158 @@
+//! Accounts, storage, logs, and the block environment of the interpreter.
+
+use std::collections::HashMap;
+
+use num_bigint::BigUint;
+use num_traits::Zero;
+use tiny_keccak::{
+    Hasher,
+    Keccak,
+};
+
+/// The address of an account.
+pub type Address = [u8; 20];
+
+/// Return the keccak256 hash of `data`.
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak::v256();
+    let mut output = [0; 32];
+
+    hasher.update(data);
+    hasher.finalize(&mut output);
+
+    output
+}
+
+/// Return the RLP encoding of the unsigned integer `value`.
+fn rlp_uint(value: u64) -> Vec<u8> {
+    let bytes: Vec<u8> = value
+        .to_be_bytes()
+        .iter()
+        .copied()
+        .skip_while(|b| *b == 0)
+        .collect();
+
+    match bytes.as_slice() {
+        [] => vec![0x80],
+        [b] if *b < 0x80 => vec![*b],
+        _ => {
+            let mut result = vec![0x80 + bytes.len() as u8];
+            result.extend(bytes);
+            result
+        }
+    }
+}
+
+/// Return the address of the contract created by the account `sender` when
+/// its nonce is `nonce`.  This is the last 20 bytes of the hash of the RLP
+/// encoded list `[sender, nonce]`.
+pub fn create_address(sender: &Address, nonce: u64) -> Address {
+    let mut items = vec![0x80 + sender.len() as u8];
+    items.extend_from_slice(sender);
+    items.extend(rlp_uint(nonce));
+
+    let mut encoded = vec![0xc0 + items.len() as u8];
+    encoded.extend(items);
+
+    let mut address = [0; 20];
+    address.copy_from_slice(&keccak256(&encoded)[12..]);
+
+    address
+}
+
+/// The state of an account.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Account {
+    pub nonce: u64,
+    pub balance: BigUint,
+    pub code: Vec<u8>,
+    /// The non-zero values in the account's storage by slot.
+    pub storage: HashMap<BigUint, BigUint>,
+}
+
+/// A log emitted during execution.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Log {
+    /// The address of the account whose code emitted the log.
+    pub address: Address,
+    pub topics: Vec<[u8; 32]>,
+    pub data: Vec<u8>,
+}
+
+/// The properties of the block in which transactions are executed.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Block {
+    pub number: u64,
+    pub timestamp: u64,
+    pub coinbase: Address,
+    pub difficulty: BigUint,
+    pub gas_limit: u64,
+    pub chain_id: u64,
+    pub base_fee: BigUint,
+}
+
+impl Default for Block {
+    fn default() -> Self {
+        Block {
+            number: 1,
+            timestamp: 1,
+            coinbase: [0; 20],
+            difficulty: BigUint::zero(),
+            gas_limit: 10_000_000,
+            chain_id: 1,
+            base_fee: BigUint::zero(),
+        }
+    }
+}
+
+/// The accounts of the world state by address.  Accounts that do not exist
+/// behave as empty accounts.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct State {
+    accounts: HashMap<Address, Account>,
+}
+
+impl State {
+    /// Return the account at the address `address` if it exists.
+    pub fn account(&self, address: &Address) -> Option<&Account> {
+        self.accounts.get(address)
+    }
+
+    /// Return the account at the address `address`, creating it if it does not
+    /// exist.
+    pub fn account_mut(&mut self, address: &Address) -> &mut Account {
+        self.accounts.entry(*address).or_default()
+    }
+
+    pub fn balance(&self, address: &Address) -> BigUint {
+        self.account(address)
+            .map(|account| account.balance.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn code(&self, address: &Address) -> &[u8] {
+        self.account(address)
+            .map(|account| account.code.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Return the value in the storage slot `slot` of the account at the
+    /// address `address`.
+    pub fn storage(&self, address: &Address, slot: &BigUint) -> BigUint {
+        self.account(address)
+            .and_then(|account| account.storage.get(slot).cloned())
+            .unwrap_or_default()
+    }
+
+    /// Set the value in the storage slot `slot` of the account at the address
+    /// `address`.
+    pub fn set_storage(&mut self, address: &Address, slot: BigUint, value: BigUint) {
+        let storage = &mut self.account_mut(address).storage;
+
+        if value.is_zero() {
+            storage.remove(&slot);
+        } else {
+            storage.insert(slot, value);
+        }
+    }
+}
@@ -0,0 +1,233 @@
+//! Execution of transactions that deploy and call contracts.
+//!
+//! Transactions are executed immediately against an in-memory world state.
+//! The state changes of a transaction that does not succeed are discarded.
+//! Gas is accounted for but not paid for, so senders need no balance unless
+//! they transfer value.
+
+use num_bigint::BigUint;
+use num_traits::{
+    ToPrimitive,
+    Zero,
+};
+
+use crate::errors::ExecutionError;
+use crate::gas;
+use crate::interpreter::{
+    execute,
+    Environment,
+    Status,
+};
+use crate::state::{
+    create_address,
+    Address,
+    Block,
+    Log,
+    State,
+};
+
+/// The largest size in bytes of deployed code.
+pub const MAX_CODE_SIZE: usize = 24576;
+
+/// The selector of `Error(string)` with which revert reasons are encoded.
+pub const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+
+/// Return the revert reason ABI encoded in the output `output` of a reverted
+/// execution as a call to `Error(string)`.
+pub fn revert_reason(output: &[u8]) -> Option<String> {
+    if output.get(..4)? != ERROR_SELECTOR {
+        return None;
+    }
+
+    let data = &output[4..];
+    let word = |offset: usize| -> Option<usize> {
+        BigUint::from_bytes_be(data.get(offset..offset.checked_add(32)?)?).to_usize()
+    };
+
+    let offset = word(0)?;
+    let len = word(offset)?;
+    let start = offset.checked_add(32)?;
+    let bytes = data.get(start..start.checked_add(len)?)?;
+
+    String::from_utf8(bytes.to_vec()).ok()
+}
+
+/// The result of executing a transaction.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Outcome {
+    pub status: Status,
+    /// The data returned by `RETURN` or `REVERT`.  For a successful
+    /// deployment this is the deployed code.
+    pub output: Vec<u8>,
+    /// The logs emitted by a successful transaction in order of emission.
+    pub logs: Vec<Log>,
+    /// The gas used including the intrinsic cost of the transaction.
+    pub gas_used: u64,
+    /// The address of the contract created by a successful deployment.
+    pub address: Option<Address>,
+}
+
+impl Outcome {
+    pub fn is_success(&self) -> bool {
+        self.status == Status::Success
+    }
+
+    /// Return the revert reason of a reverted transaction.
+    pub fn revert_reason(&self) -> Option<String> {
+        match self.status {
+            Status::Revert => revert_reason(&self.output),
+            _ => None,
+        }
+    }
+
+    /// Create the outcome of a transaction that halted with the error `msg`
+    /// before any code was executed.
+    fn halted(msg: &'static str, gas_limit: u64) -> Self {
+        Outcome {
+            status: Status::Halt(ExecutionError::new(msg, 0)),
+            output: vec![],
+            logs: vec![],
+            gas_used: gas_limit,
+            address: None,
+        }
+    }
+}
+
+/// A transaction sent to the virtual machine.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Transaction {
+    pub from: Address,
+    /// The called account, or `None` to deploy a contract whose init code is
+    /// the transaction data.
+    pub to: Option<Address>,
+    pub value: BigUint,
+    pub data: Vec<u8>,
+    pub gas_limit: u64,
+}
+
+/// An EVM with an in-memory world state.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Vm {
+    pub state: State,
+    pub block: Block,
+}
+
+impl Vm {
+    pub fn new() -> Self {
+        Vm::default()
+    }
+
+    /// Execute the transaction `tx` and apply its state changes if it
+    /// succeeds.
+    pub fn transact(&mut self, tx: &Transaction) -> Outcome {
+        let intrinsic = gas::intrinsic(&tx.data, tx.to.is_none());
+        if intrinsic > tx.gas_limit {
+            return Outcome::halted("out of gas", tx.gas_limit);
+        }
+
+        // The sender's nonce is incremented even if execution fails
+        let nonce = self.state.account(&tx.from).map_or(0, |a| a.nonce);
+        self.state.account_mut(&tx.from).nonce += 1;
+
+        let (address, code, calldata) = match tx.to {
+            Some(to) => (to, self.state.code(&to).to_vec(), tx.data.as_slice()),
+            None => (create_address(&tx.from, nonce), tx.data.clone(), &[][..]),
+        };
+
+        let snapshot = self.state.clone();
+
+        if !tx.value.is_zero() {
+            if self.state.balance(&tx.from) < tx.value {
+                return Outcome::halted("insufficient balance", tx.gas_limit);
+            }
+            self.state.account_mut(&tx.from).balance -= &tx.value;
+            self.state.account_mut(&address).balance += &tx.value;
+        }
+        if tx.to.is_none() {
+            self.state.account_mut(&address).nonce = 1;
+        }
+
+        let env = Environment {
+            address,
+            caller: tx.from,
+            origin: tx.from,
+            value: tx.value.clone(),
+            calldata,
+            gas_price: BigUint::zero(),
+            block: &self.block,
+        };
+        let mut execution = execute(&code, &env, &mut self.state, tx.gas_limit - intrinsic);
+
+        // Deployed code is paid for out of the remaining gas
+        if tx.to.is_none() && execution.status == Status::Success {
+            let deposit = gas::CODE_DEPOSIT_BYTE * execution.output.len() as u64;
+
+            let error = if execution.output.len() > MAX_CODE_SIZE {
+                Some("code size limit exceeded")
+            } else if deposit > execution.gas_left {
+                Some("out of gas")
+            } else {
+                None
+            };
+
+            match error {
+                Some(msg) => {
+                    execution.status = Status::Halt(ExecutionError::new(msg, 0));
+                    execution.gas_left = 0;
+                    execution.logs = vec![];
+                }
+                None => {
+                    execution.gas_left -= deposit;
+                    self.state.account_mut(&address).code = execution.output.clone();
+                }
+            }
+        }
+
+        let success = execution.status == Status::Success;
+        if !success {
+            self.state = snapshot;
+        }
+
+        Outcome {
+            status: execution.status,
+            output: execution.output,
+            logs: execution.logs,
+            gas_used: tx.gas_limit - execution.gas_left,
+            address: if success && tx.to.is_none() {
+                Some(address)
+            } else {
+                None
+            },
+        }
+    }
+
+    /// Deploy a contract with the init code `code` from the account `from`
+    /// using the block gas limit.
+    pub fn deploy(&mut self, from: Address, code: &[u8]) -> Outcome {
+        self.transact(&Transaction {
+            from,
+            to: None,
+            value: BigUint::zero(),
+            data: code.to_vec(),
+            gas_limit: self.block.gas_limit,
+        })
+    }
+
+    /// Call the contract at the address `to` with the data `data` from the
+    /// account `from` using the block gas limit.
+    pub fn call(&mut self, from: Address, to: Address, data: &[u8]) -> Outcome {
+        self.transact(&Transaction {
+            from,
+            to: Some(to),
+            value: BigUint::zero(),
+            data: data.to_vec(),
+            gas_limit: self.block.gas_limit,
+        })
+    }
+
+    /// Return the value in the storage slot `slot` of the account at the
+    /// address `address`.
+    pub fn storage(&self, address: &Address, slot: &BigUint) -> BigUint {
+        self.state.storage(address, slot)
+    }
+}
@@ -0,0 +1,299 @@
+extern crate wasm_bindgen_test;
+
+use nom::error::VerboseError;
+use num_bigint::BigUint;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use vyper_evm::errors::ExecutionError;
+use vyper_evm::interpreter::*;
+use vyper_evm::state::{
+    Block,
+    Log,
+    State,
+};
+use vyper_yul::assembler::assemble;
+use vyper_yul::parsers::object_input;
+use vyper_yul::tokenizer::tokenize;
+
+const ADDRESS: [u8; 20] = [0xaa; 20];
+
+/// Assemble the YUL statements in `code` into bytecode.
+fn assemble_code(code: &str) -> Vec<u8> {
+    let source = format!("object \"Test\" {{ code {{ {} }} }}", code);
+    let tokens = tokenize(&source).unwrap();
+    let (_, object) = object_input::<VerboseError<_>>(&tokens[..]).unwrap();
+
+    assemble(&object.node).unwrap()
+}
+
+/// Execute the bytecode `code` with the calldata `calldata` and `gas` gas.
+fn run(code: &[u8], calldata: &[u8], gas: u64) -> (Execution, State) {
+    let block = Block::default();
+    let env = Environment {
+        address: ADDRESS,
+        caller: [0xbb; 20],
+        origin: [0xbb; 20],
+        value: BigUint::from(0u8),
+        calldata,
+        gas_price: BigUint::from(0u8),
+        block: &block,
+    };
+    let mut state = State::default();
+    let execution = execute(code, &env, &mut state, gas);
+
+    (execution, state)
+}
+
+/// Return the word given as hex digits in `hex`.
+fn word(hex: &str) -> Vec<u8> {
+    let hex = format!("{:0>64}", hex);
+
+    (0..64)
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_expressions() {
+    let ones = "f".repeat(64);
+    let min = format!("8{}", "0".repeat(63));
+    let minus_three = format!("{}d", "f".repeat(63));
+    let minus_sixteen = format!("{}0", "f".repeat(63));
+    let address = "aa".repeat(20);
+    let caller = "bb".repeat(20);
+
+    let examples = vec![
+        ("add(1, 2)", "3"),
+        ("add(not(0), 2)", "1"),
+        ("sub(0, 1)", &ones),
+        ("mul(shl(255, 1), 2)", "0"),
+        ("div(7, 2)", "3"),
+        ("div(7, 0)", "0"),
+        ("sdiv(sub(0, 7), 2)", &minus_three),
+        ("sdiv(shl(255, 1), sub(0, 1))", &min),
+        ("mod(7, 3)", "1"),
+        ("smod(sub(0, 7), 3)", &ones),
+        ("smod(7, 0)", "0"),
+        ("addmod(not(0), 2, 7)", "3"),
+        ("mulmod(not(0), not(0), 12)", "9"),
+        ("exp(2, 255)", &min),
+        ("exp(2, 256)", "0"),
+        ("signextend(0, 0xff)", &ones),
+        ("signextend(0, 0x017f)", "7f"),
+        ("signextend(31, 0xff)", "ff"),
+        ("lt(1, 2)", "1"),
+        ("gt(1, 2)", "0"),
+        ("slt(sub(0, 1), 0)", "1"),
+        ("sgt(sub(0, 1), 0)", "0"),
+        ("eq(5, 5)", "1"),
+        ("iszero(5)", "0"),
+        ("and(0x0f, 0x3c)", "c"),
+        ("or(0x0f, 0x30)", "3f"),
+        ("xor(0x0f, 0x3c)", "33"),
+        ("byte(31, 0x1234)", "34"),
+        ("byte(32, 0x1234)", "0"),
+        ("shl(4, 1)", "10"),
+        ("shl(256, 1)", "0"),
+        ("shr(4, 0x100)", "10"),
+        ("sar(4, sub(0, 0x100))", &minus_sixteen),
+        ("sar(300, sub(0, 1))", &ones),
+        ("sar(300, 1)", "0"),
+        (
+            "keccak256(0, 0)",
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470",
+        ),
+        ("address()", &address),
+        ("caller()", &caller),
+        ("calldatasize()", "24"),
+        ("calldataload(0)", "1234"),
+        ("calldataload(4)", "123456780000"),
+        ("calldataload(not(0))", "0"),
+        ("chainid()", "1"),
+        ("number()", "1"),
+    ];
+
+    for (expr, expected) in examples {
+        let code = assemble_code(&format!("mstore(0, {}) return(0, 32)", expr));
+        let mut calldata = word("1234");
+        calldata.extend_from_slice(&[0x56, 0x78, 0, 0]);
+
+        let (execution, _) = run(&code, &calldata, 100_000);
+        assert_eq!(execution.status, Status::Success, "{}", expr);
+        assert_eq!(execution.output, word(expected), "{}", expr);
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_control_flow() {
+    let code = assemble_code(
+        r#"
+        function sum(n) -> result {
+            for { let i := 1 } lt(i, add(n, 1)) { i := add(i, 1) } {
+                result := add(result, i)
+            }
+        }
+        mstore(0, sum(10))
+        if gt(mload(0), 50) { return(0, 32) }
+        revert(0, 0)
+        "#,
+    );
+    let (execution, _) = run(&code, &[], 100_000);
+
+    assert_eq!(execution.status, Status::Success);
+    assert_eq!(execution.output, word("37"));
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_memory() {
+    let code = assemble_code(
+        r#"
+        mstore8(1, 0x1234)
+        pop(mload(100))
+        mstore(32, msize())
+        calldatacopy(64, 2, 4)
+        return(0, 96)
+        "#,
+    );
+    let (execution, _) = run(&code, &[1, 2, 3, 4], 100_000);
+
+    let mut expected = vec![0; 96];
+    expected[1] = 0x34;
+    expected[63] = 160;
+    expected[64] = 3;
+    expected[65] = 4;
+
+    assert_eq!(execution.output, expected);
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_storage_and_logs() {
+    let code = assemble_code(
+        r#"
+        sstore(1, 42)
+        sstore(2, add(sload(1), 1))
+        sstore(1, 0)
+        mstore(0, 7)
+        log2(0, 32, 0xabcd, caller())
+        "#,
+    );
+    let (execution, state) = run(&code, &[], 100_000);
+
+    assert_eq!(execution.status, Status::Success);
+    assert_eq!(
+        state.storage(&ADDRESS, &BigUint::from(1u8)),
+        BigUint::from(0u8)
+    );
+    assert_eq!(
+        state.storage(&ADDRESS, &BigUint::from(2u8)),
+        BigUint::from(43u8)
+    );
+    assert_eq!(
+        state.account(&ADDRESS).unwrap().storage.len(),
+        1,
+        "zero values are not stored"
+    );
+
+    let mut caller = [0; 32];
+    caller[12..].copy_from_slice(&[0xbb; 20]);
+    let mut topic = [0; 32];
+    topic[30..].copy_from_slice(&[0xab, 0xcd]);
+
+    assert_eq!(
+        execution.logs,
+        vec![Log {
+            address: ADDRESS,
+            topics: vec![topic, caller],
+            data: word("7"),
+        }]
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_gas() {
+    // Three pushes, a store and a word of memory
+    let (execution, _) = run(&assemble_code("mstore(0, 1)"), &[], 100);
+    assert_eq!(execution.status, Status::Success);
+    assert_eq!(execution.gas_left, 88);
+
+    // Storage writes depend on the current value
+    let (execution, _) = run(&assemble_code("sstore(0, 1) sstore(0, 2)"), &[], 100_000);
+    assert_eq!(execution.gas_left, 100_000 - 4 * 3 - 20000 - 5000);
+
+    // Reverting refunds the remaining gas
+    let (execution, _) = run(&assemble_code("revert(0, 0)"), &[], 100);
+    assert_eq!(execution.status, Status::Revert);
+    assert_eq!(execution.gas_left, 94);
+
+    // Running out of gas consumes all gas and discards logs
+    let (execution, _) = run(&assemble_code("log0(0, 0) for { } 1 { } { }"), &[], 10_000);
+    match execution.status {
+        Status::Halt(err) => assert_eq!(err.msg, "out of gas"),
+        status => panic!("unexpected status {:?}", status),
+    }
+    assert_eq!(execution.gas_left, 0);
+    assert_eq!(execution.logs, vec![]);
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_errors() {
+    let examples = vec![
+        // ADD with an empty stack
+        (vec![0x01], "stack underflow", 0),
+        // JUMP to the data of PUSH1 0x5b
+        (
+            vec![0x60, 0x5b, 0x60, 0x01, 0x56],
+            "invalid jump destination",
+            4,
+        ),
+        // JUMP past the end of the code
+        (vec![0x60, 0x10, 0x56], "invalid jump destination", 2),
+        (assemble_code("invalid()"), "invalid opcode", 0),
+        (
+            assemble_code("pop(call(0, 0, 0, 0, 0, 0, 0))"),
+            "unsupported opcode",
+            14,
+        ),
+        (assemble_code("mstore(not(0), 1)"), "out of gas", 5),
+        (
+            assemble_code("returndatacopy(0, 0, 1)"),
+            "return data out of bounds",
+            6,
+        ),
+    ];
+
+    for (code, msg, pc) in examples {
+        let (execution, _) = run(&code, &[], 100_000);
+
+        assert_eq!(
+            execution.status,
+            Status::Halt(ExecutionError::new(msg, pc)),
+            "{:?}",
+            code
+        );
+        assert_eq!(execution.gas_left, 0);
+    }
+
+    // A loop that pushes a value in each iteration overflows the stack after
+    // 1024 values
+    let (execution, _) = run(&[0x5b, 0x58, 0x60, 0x00, 0x56], &[], 100_000);
+    assert_eq!(
+        execution.status,
+        Status::Halt(ExecutionError::new("stack overflow", 2))
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_code() {
+    let code = assemble_code("codecopy(0, 0, codesize()) return(0, codesize())");
+    let (execution, _) = run(&code, &[], 100_000);
+
+    assert_eq!(execution.output, code);
+}
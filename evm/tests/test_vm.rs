@@ -0,0 +1,222 @@
+extern crate wasm_bindgen_test;
+
+use nom::error::VerboseError;
+use num_bigint::BigUint;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use vyper_evm::errors::ExecutionError;
+use vyper_evm::interpreter::Status;
+use vyper_evm::state::create_address;
+use vyper_evm::vm::*;
+use vyper_yul::assembler::assemble;
+use vyper_yul::parsers::object_input;
+use vyper_yul::tokenizer::tokenize;
+
+const SENDER: [u8; 20] = [0x11; 20];
+
+/// Return the init code of a contract with the runtime code `runtime`.
+fn init_code(runtime: &str) -> Vec<u8> {
+    let source = format!(
+        r#"
+        object "Test" {{
+            code {{
+                datacopy(0, dataoffset("Runtime"), datasize("Runtime"))
+                return(0, datasize("Runtime"))
+            }}
+            object "Runtime" {{
+                code {{ {} }}
+            }}
+        }}
+        "#,
+        runtime
+    );
+    let tokens = tokenize(&source).unwrap();
+    let (_, object) = object_input::<VerboseError<_>>(&tokens[..]).unwrap();
+
+    assemble(&object.node).unwrap()
+}
+
+/// Return the calldata holding the single word `value`.
+fn word(value: u8) -> Vec<u8> {
+    let mut data = vec![0; 32];
+    data[31] = value;
+
+    data
+}
+
+fn topic(value: u8) -> [u8; 32] {
+    let mut topic = [0; 32];
+    topic[31] = value;
+
+    topic
+}
+
+fn hex_address(hex: &str) -> [u8; 20] {
+    let mut address = [0; 20];
+    for (i, byte) in address.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap();
+    }
+
+    address
+}
+
+/// A contract that stores the word in the calldata, logs the old value and
+/// returns it.  Storing zero reverts with a reason.
+const COUNTER: &str = r#"
+    let value := calldataload(0)
+    if iszero(value) {
+        sstore(0, 99)
+        mstore(0, shl(224, 0x08c379a0))
+        mstore(4, 32)
+        mstore(36, 4)
+        mstore(68, shl(224, 0x7a65726f))
+        revert(0, 100)
+    }
+    mstore(0, sload(0))
+    sstore(0, value)
+    log1(0, 32, value)
+    return(0, 32)
+"#;
+
+#[test]
+#[wasm_bindgen_test]
+fn test_deploy_and_call() {
+    let mut vm = Vm::new();
+
+    let deployed = vm.deploy(SENDER, &init_code(COUNTER));
+    assert!(deployed.is_success());
+    let address = deployed.address.unwrap();
+    assert_eq!(address, create_address(&SENDER, 0));
+    assert_eq!(vm.state.code(&address), deployed.output.as_slice());
+
+    let first = vm.call(SENDER, address, &word(5));
+    assert!(first.is_success());
+    assert_eq!(first.output, word(0));
+    assert_eq!(first.logs.len(), 1);
+    assert_eq!(first.logs[0].address, address);
+    assert_eq!(first.logs[0].topics, vec![topic(5)]);
+
+    let second = vm.call(SENDER, address, &word(7));
+    assert_eq!(second.output, word(5));
+    assert_eq!(
+        vm.storage(&address, &BigUint::from(0u8)),
+        BigUint::from(7u8)
+    );
+
+    // The nonce of the sender is incremented by every transaction
+    let other = vm.deploy(SENDER, &init_code(COUNTER)).address.unwrap();
+    assert_eq!(other, create_address(&SENDER, 3));
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_revert() {
+    let mut vm = Vm::new();
+    let address = vm.deploy(SENDER, &init_code(COUNTER)).address.unwrap();
+    vm.call(SENDER, address, &word(3));
+
+    let reverted = vm.call(SENDER, address, &word(0));
+    assert_eq!(reverted.status, Status::Revert);
+    assert_eq!(reverted.revert_reason(), Some("zero".to_string()));
+    assert_eq!(reverted.logs, vec![]);
+
+    // The storage write before the revert is discarded
+    assert_eq!(
+        vm.storage(&address, &BigUint::from(0u8)),
+        BigUint::from(3u8)
+    );
+
+    // Calls to accounts without code succeed without output
+    let empty = vm.call(SENDER, [0x22; 20], &[]);
+    assert!(empty.is_success());
+    assert_eq!(empty.revert_reason(), None);
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_revert_reason() {
+    let mut output = ERROR_SELECTOR.to_vec();
+    output.extend(word(32));
+    output.extend(word(2));
+    output.extend(b"no");
+    output.extend(vec![0; 30]);
+
+    assert_eq!(revert_reason(&output), Some("no".to_string()));
+    assert_eq!(revert_reason(&output[..40]), None);
+    assert_eq!(revert_reason(&output[4..]), None);
+    assert_eq!(revert_reason(&[]), None);
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_gas_used() {
+    let mut vm = Vm::new();
+    let address = vm.deploy(SENDER, &init_code("stop()")).address.unwrap();
+
+    // The intrinsic cost depends on the number of zero and non-zero bytes
+    let outcome = vm.call(SENDER, address, &[0, 1]);
+    assert_eq!(outcome.gas_used, 21000 + 4 + 16);
+
+    let outcome = vm.transact(&Transaction {
+        from: SENDER,
+        to: Some(address),
+        value: BigUint::from(0u8),
+        data: vec![],
+        gas_limit: 20000,
+    });
+    assert_eq!(
+        outcome.status,
+        Status::Halt(ExecutionError::new("out of gas", 0))
+    );
+    assert_eq!(outcome.gas_used, 20000);
+
+    // Deployed code is charged per byte
+    let short = vm.deploy(SENDER, &init_code("stop()")).gas_used;
+    let long = vm.deploy(SENDER, &init_code("stop() stop()")).gas_used;
+    assert!(long >= short + 200);
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_value_transfer() {
+    let mut vm = Vm::new();
+    let address = vm
+        .deploy(SENDER, &init_code("mstore(0, selfbalance()) return(0, 32)"))
+        .address
+        .unwrap();
+    vm.state.account_mut(&SENDER).balance = BigUint::from(10u8);
+
+    let transfer = |value: u8| Transaction {
+        from: SENDER,
+        to: Some(address),
+        value: BigUint::from(value),
+        data: vec![],
+        gas_limit: 100_000,
+    };
+
+    let outcome = vm.transact(&transfer(4));
+    assert_eq!(outcome.output, word(4));
+    assert_eq!(vm.state.balance(&SENDER), BigUint::from(6u8));
+
+    let outcome = vm.transact(&transfer(7));
+    assert_eq!(
+        outcome.status,
+        Status::Halt(ExecutionError::new("insufficient balance", 0))
+    );
+    assert_eq!(vm.state.balance(&address), BigUint::from(4u8));
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_create_address() {
+    let sender = hex_address("6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0");
+
+    assert_eq!(
+        create_address(&sender, 0),
+        hex_address("cd234a471b72ba2f1ccf0a70fcaba648a5eecd8d")
+    );
+    assert_eq!(
+        create_address(&sender, 1),
+        hex_address("343c43a37d37dff08ae8c4a11544c718abb4fcf8")
+    );
+}
@@ -0,0 +1,539 @@
+//! Language features computed from the text of a document.
+//!
+//! Positions are lines and UTF-16 code unit offsets into lines, as required by
+//! the protocol.  Features that need a syntax tree are empty for documents that
+//! do not parse, but semantic tokens only require a document to tokenize.
+
+use lsp_types::{
+    Diagnostic,
+    DiagnosticSeverity,
+    DocumentSymbol,
+    Position,
+    Range,
+    SemanticToken,
+    SemanticTokenType,
+    SymbolKind,
+};
+
+use vyper_analyzer::builtins::builtin;
+use vyper_analyzer::const_eval::{
+    is_constant,
+    module_constants,
+    ConstEnv,
+};
+use vyper_analyzer::types::{
+    resolve_type_desc,
+    Base,
+};
+use vyper_compiler::errors::CompileError;
+use vyper_compiler::signatures::event_signature;
+use vyper_compiler::storage::resolve_storage_type;
+use vyper_compiler::yul::compile_module;
+use vyper_parser::ast::{
+    FuncArg,
+    ModuleStmt,
+    TypeDesc,
+};
+use vyper_parser::cst::{
+    Cst,
+    SyntaxKind,
+};
+use vyper_parser::line_index::{
+    ColumnUnit,
+    LineCol,
+    LineIndex,
+};
+use vyper_parser::span::{
+    Span,
+    Spanned,
+};
+use vyper_parser::symbol::Symbol;
+use vyper_parser::tokenizer::{
+    tokenize,
+    Token,
+    TokenType,
+};
+
+/// The types of semantic tokens in the order of their indices.
+pub const TOKEN_TYPES: &[SemanticTokenType] = &[
+    SemanticTokenType::KEYWORD,
+    SemanticTokenType::TYPE,
+    SemanticTokenType::MODIFIER,
+    SemanticTokenType::EVENT,
+    SemanticTokenType::PROPERTY,
+    SemanticTokenType::VARIABLE,
+    SemanticTokenType::DECORATOR,
+    SemanticTokenType::NUMBER,
+    SemanticTokenType::STRING,
+    SemanticTokenType::COMMENT,
+    SemanticTokenType::OPERATOR,
+    SemanticTokenType::FUNCTION,
+];
+
+/// Names that wrap types to annotate declarations.
+const ANNOTATIONS: &[&str] = &["constant", "indexed", "public"];
+
+/// Operator tokens that only delimit other tokens.
+const PUNCTUATION: &[&str] = &["(", ")", "[", "]", "{", "}", ",", ":", ".", ";", "@", "->"];
+
+/// A version of an open document and the index of its lines, which is built
+/// once per version.
+pub struct Document {
+    pub text: String,
+    pub line_index: LineIndex,
+}
+
+impl Document {
+    pub fn new(text: impl Into<String>) -> Self {
+        let text = text.into();
+        let line_index = LineIndex::new(&text);
+
+        Document { text, line_index }
+    }
+}
+
+/// Return the position of the byte offset `offset` in the document `doc`.
+/// Offsets past the end of the document are placed at its end and offsets
+/// inside a character at its start.
+pub fn position(doc: &Document, offset: usize) -> Position {
+    let pos = (0..=offset.min(doc.text.len()))
+        .rev()
+        .find_map(|offset| doc.line_index.line_col(offset, ColumnUnit::Utf16))
+        .unwrap_or_default();
+
+    Position::new(pos.line as u32, pos.col as u32)
+}
+
+/// Return the byte offset of the position `position` in the document `doc`.
+/// Positions past the end of a line are placed at its end and positions inside
+/// a character after it.
+pub fn offset(doc: &Document, position: Position) -> Option<usize> {
+    let index = &doc.line_index;
+    let line = position.line as usize;
+    let line_end = index.line_end(line)?;
+    let line_len = index.line_col(line_end, ColumnUnit::Utf16)?.col;
+
+    Some(
+        (position.character as usize..line_len)
+            .find_map(|col| index.offset(LineCol::new(line, col), ColumnUnit::Utf16))
+            .unwrap_or(line_end),
+    )
+}
+
+/// Return the range of the span `span` in the document `doc`.
+pub fn range(doc: &Document, span: Span) -> Range {
+    Range::new(position(doc, span.start), position(doc, span.end))
+}
+
+/// An event, event field, storage field or constant defined in a document.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Definition<'a> {
+    pub name: &'a str,
+    pub kind: SymbolKind,
+    /// The signature of an event or the resolved type of a field or constant.
+    pub detail: Option<String>,
+    pub name_span: Span,
+    pub span: Span,
+    pub children: Vec<Definition<'a>>,
+}
+
+/// Return the name of the resolved type of the field or constant whose type is
+/// described by `typ`.  Only storage fields may have map types.
+fn field_type_name(typ: &Spanned<TypeDesc>, env: &ConstEnv) -> Result<String, CompileError> {
+    if is_constant(&typ.node) {
+        Ok(resolve_type_desc(typ, env)?.name())
+    } else {
+        Ok(resolve_storage_type(typ, env)?.name())
+    }
+}
+
+/// Return the signature of a function with its argument and return types,
+/// e.g. `transfer(address, uint256) -> bool`.
+fn function_detail(
+    name: &str,
+    args: &[Spanned<FuncArg>],
+    return_type: Option<&Spanned<TypeDesc>>,
+    env: &ConstEnv,
+) -> Result<String, CompileError> {
+    let mut types = vec![];
+    for arg in args {
+        types.push(resolve_type_desc(&arg.node.typ, env)?.name());
+    }
+
+    let mut detail = format!("{}({})", name, types.join(", "));
+    if let Some(return_type) = return_type {
+        detail += &format!(" -> {}", resolve_type_desc(return_type, env)?.name());
+    }
+
+    Ok(detail)
+}
+
+/// Return the definitions in the tree `cst`.
+pub fn definitions<'a>(cst: &Cst<'a>) -> Vec<Definition<'a>> {
+    let module = cst.to_module();
+    let env = module_constants(&module).unwrap_or_default();
+    let nodes = cst.root.child_nodes().filter(|node| {
+        matches!(
+            node.kind,
+            SyntaxKind::EventDef | SyntaxKind::FieldDef | SyntaxKind::FuncDef
+        )
+    });
+
+    module
+        .body
+        .iter()
+        .zip(nodes)
+        .map(|(stmt, node)| match &stmt.node {
+            ModuleStmt::EventDef { name, fields } => {
+                let children = fields
+                    .iter()
+                    .zip(node.child_nodes())
+                    .map(|(field, field_node)| {
+                        let typ = &field.node.typ;
+                        let detail = resolve_type_desc(typ, &env).ok().map(|resolved| {
+                            if typ.node.annotations.contains(&"indexed") {
+                                format!("indexed({})", resolved.name())
+                            } else {
+                                resolved.name()
+                            }
+                        });
+
+                        Definition {
+                            name: field.node.name,
+                            kind: SymbolKind::FIELD,
+                            detail,
+                            name_span: cst.significant_tokens(field_node)[0].span,
+                            span: field.span,
+                            children: vec![],
+                        }
+                    })
+                    .collect();
+
+                Definition {
+                    name,
+                    kind: SymbolKind::EVENT,
+                    detail: event_signature(name, fields, &env).ok(),
+                    name_span: cst.significant_tokens(node)[1].span,
+                    span: stmt.span,
+                    children,
+                }
+            }
+            ModuleStmt::FieldDef { name, typ, .. } => Definition {
+                name,
+                kind: if is_constant(&typ.node) {
+                    SymbolKind::CONSTANT
+                } else {
+                    SymbolKind::FIELD
+                },
+                detail: field_type_name(typ, &env).ok(),
+                name_span: cst.significant_tokens(node)[0].span,
+                span: stmt.span,
+                children: vec![],
+            },
+            ModuleStmt::FuncDef {
+                decorators,
+                name,
+                args,
+                return_type,
+                ..
+            } => {
+                // The name follows the decorators and the `def` keyword
+                let tokens = cst.significant_tokens(node);
+                let def = tokens
+                    .iter()
+                    .position(|tok| tok.string == "def")
+                    .unwrap_or(2 * decorators.len());
+
+                Definition {
+                    name,
+                    kind: SymbolKind::FUNCTION,
+                    detail: function_detail(name, args, return_type.as_ref(), &env).ok(),
+                    name_span: tokens[def + 1].span,
+                    span: stmt.span,
+                    children: vec![],
+                }
+            }
+        })
+        .collect()
+}
+
+/// Return the diagnostics for the document `doc`.
+pub fn diagnostics(doc: &Document) -> Vec<Diagnostic> {
+    let text = doc.text.as_str();
+    let diagnostic = |span: Span, msg: &str| Diagnostic {
+        range: range(doc, span),
+        severity: Some(DiagnosticSeverity::ERROR),
+        source: Some("vyper".to_string()),
+        message: msg.to_string(),
+        ..Diagnostic::default()
+    };
+
+    let cst = match Cst::parse(text) {
+        Ok(cst) => cst,
+        Err(err) => {
+            // Underline the token at which the error was found
+            let span = tokenize(text)
+                .ok()
+                .and_then(|tokens| {
+                    tokens
+                        .into_iter()
+                        .find(|t| t.span.start == err.offset && t.span.end > t.span.start)
+                })
+                .map_or(Span::new(err.offset, err.offset), |t| t.span);
+
+            return vec![diagnostic(span, err.msg)];
+        }
+    };
+    let module = cst.to_module();
+    let mut diagnostics = vec![];
+
+    let env = match module_constants(&module) {
+        Ok(env) => env,
+        Err(err) => return vec![diagnostic(err.span, err.msg)],
+    };
+
+    for stmt in &module.body {
+        let errors: Vec<CompileError> = match &stmt.node {
+            ModuleStmt::EventDef { fields, .. } => fields
+                .iter()
+                .filter_map(|f| resolve_type_desc(&f.node.typ, &env).err())
+                .map(CompileError::from)
+                .collect(),
+            ModuleStmt::FieldDef { typ, .. } => {
+                field_type_name(typ, &env).err().into_iter().collect()
+            }
+            ModuleStmt::FuncDef {
+                args, return_type, ..
+            } => args
+                .iter()
+                .map(|arg| &arg.node.typ)
+                .chain(return_type)
+                .filter_map(|typ| resolve_type_desc(typ, &env).err())
+                .map(CompileError::from)
+                .collect(),
+        };
+
+        for err in errors {
+            diagnostics.push(diagnostic(err.span, &err.msg));
+        }
+    }
+
+    if diagnostics.is_empty() {
+        if let Err(err) = compile_module("Contract", &module) {
+            diagnostics.push(diagnostic(err.span, &err.msg));
+        }
+    }
+
+    diagnostics
+}
+
+/// Return the name token at the byte offset `offset` in the tree `cst`.
+fn name_at<'a, 'b>(cst: &'b Cst<'a>, offset: usize) -> Option<&'b Token<'a>> {
+    cst.tokens
+        .iter()
+        .find(|t| t.typ == TokenType::NAME && t.span.start <= offset && offset <= t.span.end)
+}
+
+/// Return true if the token at index `i` in `tokens` is followed by an opening
+/// parenthesis, i.e. if it is the name of a called function.
+fn is_called(tokens: &[Token], i: usize) -> bool {
+    tokens[i + 1..]
+        .iter()
+        .find(|t| t.typ != TokenType::NL && t.typ != TokenType::COMMENT)
+        .is_some_and(|t| t.string == "(")
+}
+
+/// Apply `f` to the definition referred to at the position `pos` in the
+/// document `doc` and the event defining it if it is an event field.
+fn resolve<T>(
+    doc: &Document,
+    pos: Position,
+    f: impl FnOnce(&Definition, Option<&Definition>) -> T,
+) -> Option<T> {
+    let cst = Cst::parse(&doc.text).ok()?;
+    let offset = offset(doc, pos)?;
+    let defs = definitions(&cst);
+
+    // Fields are referred to by their name or type
+    for event in &defs {
+        for field in &event.children {
+            if field.span.start <= offset && offset <= field.span.end {
+                return Some(f(field, Some(event)));
+            }
+        }
+    }
+
+    let name = name_at(&cst, offset)?;
+    let event = defs.iter().find(|event| event.name == name.string)?;
+
+    Some(f(event, None))
+}
+
+/// Return the span of the definition of the name at the position `pos` in the
+/// document `doc`.
+pub fn definition(doc: &Document, pos: Position) -> Option<Range> {
+    resolve(doc, pos, |def, _| range(doc, def.name_span))
+}
+
+/// Return the hover text in markdown for the position `pos` in the document
+/// `doc`.
+pub fn hover(doc: &Document, pos: Position) -> Option<String> {
+    let builtin_hover = || {
+        let cst = Cst::parse(&doc.text).ok()?;
+        let offset = offset(doc, pos)?;
+        let name = name_at(&cst, offset)?;
+        let i = cst.tokens.iter().position(|t| std::ptr::eq(t, name))?;
+        let builtin = builtin(name.string).filter(|_| is_called(&cst.tokens, i))?;
+
+        Some(format!(
+            "```vyper\n{}\n```\nBuilt-in function",
+            builtin.signature()
+        ))
+    };
+
+    let resolved = resolve(doc, pos, |def, parent| {
+        let detail = def.detail.as_deref().unwrap_or("unknown type");
+
+        match parent {
+            Some(event) => format!(
+                "```vyper\n{}: {}\n```\nField of event `{}`",
+                def.name, detail, event.name
+            ),
+            None if def.kind == SymbolKind::EVENT => {
+                format!("```vyper\nevent {}\n```\nSignature: `{}`", def.name, detail)
+            }
+            None if def.kind == SymbolKind::FUNCTION => {
+                let signature = def.detail.as_deref().unwrap_or(def.name);
+                format!("```vyper\ndef {}\n```\nFunction", signature)
+            }
+            None if def.kind == SymbolKind::CONSTANT => {
+                format!(
+                    "```vyper\n{}: constant({})\n```\nConstant",
+                    def.name, detail
+                )
+            }
+            None => format!("```vyper\n{}: {}\n```\nStorage field", def.name, detail),
+        }
+    });
+
+    resolved.or_else(builtin_hover)
+}
+
+/// Return the symbols of the document `doc` in a hierarchy.
+#[allow(deprecated)]
+pub fn document_symbols(doc: &Document) -> Vec<DocumentSymbol> {
+    fn symbol(doc: &Document, def: &Definition) -> DocumentSymbol {
+        DocumentSymbol {
+            name: def.name.to_string(),
+            detail: def.detail.clone(),
+            kind: def.kind,
+            tags: None,
+            deprecated: None,
+            range: range(doc, def.span),
+            selection_range: range(doc, def.name_span),
+            children: Some(def.children.iter().map(|c| symbol(doc, c)).collect()),
+        }
+    }
+
+    match Cst::parse(&doc.text) {
+        Ok(cst) => definitions(&cst).iter().map(|d| symbol(doc, d)).collect(),
+        Err(_) => vec![],
+    }
+}
+
+/// Return the semantic token type of the token at index `i` in `tokens`, or
+/// None if the token is not highlighted.
+fn token_type(tokens: &[Token], i: usize, defs: &[Definition]) -> Option<SemanticTokenType> {
+    let tok = &tokens[i];
+
+    Some(match tok.typ {
+        TokenType::NAME => {
+            let prev = tokens[..i]
+                .iter()
+                .rev()
+                .find(|t| t.typ != TokenType::NL && t.typ != TokenType::COMMENT);
+            let field = |d: &Definition| d.children.iter().any(|f| f.name_span == tok.span);
+
+            if tok.sym.is_some_and(Symbol::is_keyword) {
+                SemanticTokenType::KEYWORD
+            } else if prev.is_some_and(|t| t.string == "@") {
+                SemanticTokenType::DECORATOR
+            } else if ANNOTATIONS.contains(&tok.string) {
+                SemanticTokenType::MODIFIER
+            } else if builtin(tok.string).is_some() && is_called(tokens, i) {
+                SemanticTokenType::FUNCTION
+            } else if ["bytes", "string"].contains(&tok.string)
+                || Base::from_name(tok.string).is_some()
+            {
+                SemanticTokenType::TYPE
+            } else if defs
+                .iter()
+                .any(|d| d.kind == SymbolKind::EVENT && d.name == tok.string)
+            {
+                SemanticTokenType::EVENT
+            } else if defs
+                .iter()
+                .any(|d| d.kind == SymbolKind::FUNCTION && d.name_span == tok.span)
+            {
+                SemanticTokenType::FUNCTION
+            } else if defs.iter().any(field) || defs.iter().any(|d| d.name_span == tok.span) {
+                SemanticTokenType::PROPERTY
+            } else {
+                SemanticTokenType::VARIABLE
+            }
+        }
+        TokenType::NUMBER => SemanticTokenType::NUMBER,
+        TokenType::STRING => SemanticTokenType::STRING,
+        TokenType::COMMENT => SemanticTokenType::COMMENT,
+        TokenType::OP if !PUNCTUATION.contains(&tok.string) => SemanticTokenType::OPERATOR,
+        _ => return None,
+    })
+}
+
+/// Return the semantic tokens of the document `doc` encoded relative to each
+/// other.  Tokens spanning several lines are split into one token per line.
+pub fn semantic_tokens(doc: &Document) -> Vec<SemanticToken> {
+    let text = doc.text.as_str();
+    let tokens = match tokenize(text) {
+        Ok(tokens) => tokens,
+        Err(_) => return vec![],
+    };
+    let cst = Cst::parse(text).ok();
+    let defs = cst.as_ref().map(definitions).unwrap_or_default();
+
+    let mut result = vec![];
+    let mut last = Position::new(0, 0);
+
+    for (i, tok) in tokens.iter().enumerate() {
+        let typ = match token_type(&tokens, i, &defs) {
+            Some(typ) => typ,
+            None => continue,
+        };
+        let index = TOKEN_TYPES.iter().position(|t| *t == typ).unwrap() as u32;
+
+        let mut start = tok.span.start;
+        for line in text[tok.span.start..tok.span.end].split_inclusive('\n') {
+            let content = line.trim_end_matches(&['\r', '\n'][..]);
+            let pos = position(doc, start);
+            start += line.len();
+            if content.is_empty() {
+                continue;
+            }
+
+            result.push(SemanticToken {
+                delta_line: pos.line - last.line,
+                delta_start: if pos.line == last.line {
+                    pos.character - last.character
+                } else {
+                    pos.character
+                },
+                length: content.encode_utf16().count() as u32,
+                token_type: index,
+                token_modifiers_bitset: 0,
+            });
+            last = pos;
+        }
+    }
+
+    result
+}
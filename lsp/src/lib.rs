@@ -0,0 +1,11 @@
+//! A language server for Vyper.
+//!
+//! The server speaks the Language Server Protocol and provides diagnostics,
+//! go-to-definition, hover with resolved types, document symbols and semantic
+//! tokens.  The parser only supports event and field definitions so far, so
+//! symbols, definitions and hovers cover events, their fields, storage fields
+//! and constants.  Hovers over calls to built-in functions show their
+//! signatures.
+
+pub mod analysis;
+pub mod server;
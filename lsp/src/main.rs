@@ -0,0 +1,15 @@
+use std::process;
+
+use lsp_server::Connection;
+
+use vyper_lsp::server::Server;
+
+fn main() {
+    let (connection, io_threads) = Connection::stdio();
+
+    let result = Server::new(connection).run();
+    if let Err(err) = result.and_then(|_| io_threads.join().map_err(Into::into)) {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,272 @@
+//! The main loop of the language server.
+//!
+//! Documents are synchronized in full on every change, after which their
+//! diagnostics are published.  All requests are answered from the current
+//! version of a document, whose line index is built once when it is stored.
+
+use std::collections::HashMap;
+use std::error::Error;
+
+use lsp_server::{
+    Connection,
+    ErrorCode,
+    Message,
+    Notification,
+    Request,
+    Response,
+};
+use lsp_types::notification::{
+    DidChangeTextDocument,
+    DidCloseTextDocument,
+    DidOpenTextDocument,
+    Notification as _,
+    PublishDiagnostics,
+};
+use lsp_types::request::{
+    DocumentSymbolRequest,
+    GotoDefinition,
+    HoverRequest,
+    Request as _,
+    SemanticTokensFullRequest,
+};
+use lsp_types::{
+    DidChangeTextDocumentParams,
+    DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams,
+    DocumentSymbolParams,
+    DocumentSymbolResponse,
+    GotoDefinitionParams,
+    GotoDefinitionResponse,
+    Hover,
+    HoverContents,
+    HoverParams,
+    HoverProviderCapability,
+    Location,
+    MarkupContent,
+    MarkupKind,
+    OneOf,
+    PublishDiagnosticsParams,
+    SemanticTokens,
+    SemanticTokensFullOptions,
+    SemanticTokensLegend,
+    SemanticTokensOptions,
+    SemanticTokensParams,
+    SemanticTokensResult,
+    SemanticTokensServerCapabilities,
+    ServerCapabilities,
+    TextDocumentSyncCapability,
+    TextDocumentSyncKind,
+    Url,
+};
+use serde_json::Value;
+
+use crate::analysis::{
+    self,
+    Document,
+};
+
+pub type ServerResult<T> = Result<T, Box<dyn Error + Sync + Send>>;
+
+/// Return the capabilities announced by the server.
+pub fn capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        definition_provider: Some(OneOf::Left(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        semantic_tokens_provider: Some(SemanticTokensServerCapabilities::SemanticTokensOptions(
+            SemanticTokensOptions {
+                legend: SemanticTokensLegend {
+                    token_types: analysis::TOKEN_TYPES.to_vec(),
+                    token_modifiers: vec![],
+                },
+                full: Some(SemanticTokensFullOptions::Bool(true)),
+                ..SemanticTokensOptions::default()
+            },
+        )),
+        ..ServerCapabilities::default()
+    }
+}
+
+/// A language server and the documents opened by its client.
+pub struct Server {
+    connection: Connection,
+    documents: HashMap<Url, Document>,
+}
+
+impl Server {
+    pub fn new(connection: Connection) -> Self {
+        Server {
+            connection,
+            documents: HashMap::new(),
+        }
+    }
+
+    /// Initialize the connection and handle messages until the client shuts
+    /// the server down.
+    pub fn run(mut self) -> ServerResult<()> {
+        self.connection
+            .initialize(serde_json::to_value(capabilities())?)?;
+
+        while let Ok(msg) = self.connection.receiver.recv() {
+            match msg {
+                Message::Request(req) => {
+                    if self.connection.handle_shutdown(&req)? {
+                        return Ok(());
+                    }
+                    let response = self.handle_request(req);
+                    self.connection.sender.send(response.into())?;
+                }
+                Message::Notification(notification) => self.handle_notification(notification)?,
+                Message::Response(_) => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Return the current version of the document at `uri` if it is open.
+    fn document(&self, uri: &Url) -> Option<&Document> {
+        self.documents.get(uri)
+    }
+
+    fn handle_request(&self, req: Request) -> Response {
+        let id = req.id.clone();
+        let result = match req.method.as_str() {
+            GotoDefinition::METHOD => self.definition(req.params),
+            HoverRequest::METHOD => self.hover(req.params),
+            DocumentSymbolRequest::METHOD => self.document_symbols(req.params),
+            SemanticTokensFullRequest::METHOD => self.semantic_tokens(req.params),
+            _ => {
+                return Response::new_err(
+                    id,
+                    ErrorCode::MethodNotFound as i32,
+                    format!("unsupported request {}", req.method),
+                )
+            }
+        };
+
+        match result {
+            Ok(value) => Response::new_ok(id, value),
+            Err(err) => Response::new_err(id, ErrorCode::InvalidParams as i32, err.to_string()),
+        }
+    }
+
+    fn definition(&self, params: Value) -> ServerResult<Value> {
+        let params: GotoDefinitionParams = serde_json::from_value(params)?;
+        let doc = params.text_document_position_params;
+        let uri = doc.text_document.uri;
+        let position = doc.position;
+
+        let location = self
+            .document(&uri)
+            .and_then(|document| analysis::definition(document, position))
+            .map(|range| {
+                GotoDefinitionResponse::Scalar(Location {
+                    uri: uri.clone(),
+                    range,
+                })
+            });
+
+        Ok(serde_json::to_value(location)?)
+    }
+
+    fn hover(&self, params: Value) -> ServerResult<Value> {
+        let params: HoverParams = serde_json::from_value(params)?;
+        let doc = params.text_document_position_params;
+
+        let hover = self
+            .document(&doc.text_document.uri)
+            .and_then(|document| analysis::hover(document, doc.position))
+            .map(|value| Hover {
+                contents: HoverContents::Markup(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value,
+                }),
+                range: None,
+            });
+
+        Ok(serde_json::to_value(hover)?)
+    }
+
+    fn document_symbols(&self, params: Value) -> ServerResult<Value> {
+        let params: DocumentSymbolParams = serde_json::from_value(params)?;
+        let symbols = self
+            .document(&params.text_document.uri)
+            .map_or(vec![], analysis::document_symbols);
+
+        Ok(serde_json::to_value(DocumentSymbolResponse::Nested(
+            symbols,
+        ))?)
+    }
+
+    fn semantic_tokens(&self, params: Value) -> ServerResult<Value> {
+        let params: SemanticTokensParams = serde_json::from_value(params)?;
+        let data = self
+            .document(&params.text_document.uri)
+            .map_or(vec![], analysis::semantic_tokens);
+
+        Ok(serde_json::to_value(SemanticTokensResult::Tokens(
+            SemanticTokens {
+                result_id: None,
+                data,
+            },
+        ))?)
+    }
+
+    fn handle_notification(&mut self, notification: Notification) -> ServerResult<()> {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: DidOpenTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                let doc = params.text_document;
+
+                self.documents
+                    .insert(doc.uri.clone(), Document::new(doc.text));
+                self.publish_diagnostics(doc.uri, Some(doc.version))
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: DidChangeTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                let doc = params.text_document;
+
+                // Documents are synchronized in full, so the last change holds
+                // the whole text
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    self.documents
+                        .insert(doc.uri.clone(), Document::new(change.text));
+                }
+                self.publish_diagnostics(doc.uri, Some(doc.version))
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: DidCloseTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                let uri = params.text_document.uri;
+
+                self.documents.remove(&uri);
+                self.publish_diagnostics(uri, None)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Publish the diagnostics of the document at `uri`.  Closed documents
+    /// have no diagnostics.
+    fn publish_diagnostics(&self, uri: Url, version: Option<i32>) -> ServerResult<()> {
+        let diagnostics = match self.documents.get(&uri) {
+            Some(document) => analysis::diagnostics(document),
+            None => vec![],
+        };
+        let params = PublishDiagnosticsParams {
+            uri,
+            diagnostics,
+            version,
+        };
+
+        self.connection
+            .sender
+            .send(Notification::new(PublishDiagnostics::METHOD.to_string(), params).into())?;
+
+        Ok(())
+    }
+}
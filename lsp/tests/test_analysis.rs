@@ -0,0 +1,219 @@
+use lsp_types::{
+    Position,
+    SemanticToken,
+    SemanticTokenType,
+};
+
+use vyper_lsp::analysis::{
+    definition,
+    diagnostics,
+    hover,
+    offset,
+    position,
+    semantic_tokens,
+    Document,
+    TOKEN_TYPES,
+};
+
+fn doc(text: &str) -> Document {
+    Document::new(text)
+}
+
+fn token_type(typ: SemanticTokenType) -> u32 {
+    TOKEN_TYPES.iter().position(|t| *t == typ).unwrap() as u32
+}
+
+#[test]
+fn test_positions() {
+    // "é" is one UTF-16 code unit and "𝔸" is two
+    let text = "# é𝔸x\nevent\n";
+
+    assert_eq!(position(&doc(text), 0), Position::new(0, 0));
+    assert_eq!(position(&doc(text), 4), Position::new(0, 3));
+    assert_eq!(position(&doc(text), 8), Position::new(0, 5));
+    assert_eq!(position(&doc(text), 10), Position::new(1, 0));
+    assert_eq!(position(&doc(text), 100), Position::new(2, 0));
+
+    assert_eq!(offset(&doc(text), Position::new(0, 3)), Some(4));
+    assert_eq!(offset(&doc(text), Position::new(0, 5)), Some(8));
+    assert_eq!(offset(&doc(text), Position::new(1, 2)), Some(12));
+    assert_eq!(offset(&doc(text), Position::new(1, 99)), Some(15));
+    assert_eq!(offset(&doc(text), Position::new(5, 0)), None);
+}
+
+#[test]
+fn test_diagnostics() {
+    assert!(diagnostics(&doc("event A:\n    x: int128\n")).is_empty());
+
+    let parse_error = diagnostics(&doc("event A:\n    x int128\n"));
+    assert_eq!(parse_error.len(), 1);
+    assert_eq!(parse_error[0].message, "invalid syntax");
+    assert_eq!(parse_error[0].range.start, Position::new(1, 6));
+    assert_eq!(parse_error[0].range.end, Position::new(1, 12));
+
+    let semantic = diagnostics(&doc("event A:\n    x: foo\n    y: bytes\n"));
+    let messages: Vec<_> = semantic.iter().map(|d| d.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec!["unknown type", "byte array types require a maximum size"]
+    );
+
+    let duplicate = diagnostics(&doc("event A:\n    x: int128\nevent A:\n    y: bool\n"));
+    assert_eq!(duplicate.len(), 1);
+    assert_eq!(duplicate[0].range.start, Position::new(2, 0));
+
+    let fields = diagnostics(&doc(
+        "N: constant(uint256) = 2\nx: bytes[N]\ny: uint256[M]\n",
+    ));
+    let messages: Vec<_> = fields.iter().map(|d| d.message.as_str()).collect();
+    assert_eq!(messages, vec!["name is not a compile-time constant"]);
+    assert_eq!(fields[0].range.start, Position::new(2, 11));
+
+    // Only storage fields may have map types
+    assert!(diagnostics(&doc("x: public(map(address, bytes[4]))\n")).is_empty());
+    let maps = diagnostics(&doc(
+        "N: constant(map(address, uint256)) = 1\nx: map(uint256[2], bool)\n",
+    ));
+    let messages: Vec<_> = maps.iter().map(|d| d.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "map types can only be used for storage fields",
+            "map keys must have a base type or be byte arrays or strings",
+        ]
+    );
+
+    // Argument and return types of functions are resolved
+    let func = diagnostics(&doc("def f(x: bytes) -> foo:\n    pass\n"));
+    let messages: Vec<_> = func.iter().map(|d| d.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec!["byte array types require a maximum size", "unknown type"]
+    );
+
+    let constant = diagnostics(&doc("N: constant(uint256)\n"));
+    assert_eq!(constant[0].message, "constants require a value");
+
+    let call = diagnostics(&doc("N: constant(uint256) = max(1, 2)\nx: bytes[len(N)]\n"));
+    assert_eq!(call.len(), 1);
+    assert_eq!(
+        call[0].message,
+        "built-in function cannot be used in a constant expression"
+    );
+    assert_eq!(call[0].range.start, Position::new(1, 9));
+}
+
+#[test]
+fn test_definition_and_hover() {
+    let text = "event Greet:\n    name: bytes32\n    tags: indexed(string[10])\n";
+
+    assert_eq!(definition(&doc(text), Position::new(0, 2)), None);
+    assert_eq!(
+        definition(&doc(text), Position::new(0, 8)).map(|r| r.start),
+        Some(Position::new(0, 6))
+    );
+    assert_eq!(
+        hover(&doc(text), Position::new(0, 8)),
+        Some("```vyper\nevent Greet\n```\nSignature: `Greet(bytes32,string)`".to_string())
+    );
+    assert_eq!(
+        hover(&doc(text), Position::new(2, 20)),
+        Some("```vyper\ntags: indexed(string[10])\n```\nField of event `Greet`".to_string())
+    );
+    assert_eq!(hover(&doc("event"), Position::new(0, 1)), None);
+
+    let text = "SIZE: constant(uint256) = 4\nnames: public(string[SIZE])\n";
+    assert_eq!(
+        hover(&doc(text), Position::new(1, 2)),
+        Some("```vyper\nnames: string[4]\n```\nStorage field".to_string())
+    );
+    assert_eq!(
+        hover(&doc(text), Position::new(0, 2)),
+        Some("```vyper\nSIZE: constant(uint256)\n```\nConstant".to_string())
+    );
+    assert_eq!(
+        definition(&doc(text), Position::new(1, 23)).map(|r| r.start),
+        Some(Position::new(0, 0))
+    );
+
+    let text = "allowed: map(address, map(string[4], uint256))\n";
+    assert_eq!(
+        hover(&doc(text), Position::new(0, 2)),
+        Some(
+            "```vyper\nallowed: map(address, map(string[4], uint256))\n```\nStorage field"
+                .to_string()
+        )
+    );
+
+    let text = "SIZE: constant(uint256) = shift(1, 4)\n";
+    assert_eq!(
+        hover(&doc(text), Position::new(0, 27)),
+        Some(
+            "```vyper\nshift(x: uint256, bits: int128) -> uint256\n```\nBuilt-in function"
+                .to_string()
+        )
+    );
+    assert_eq!(hover(&doc(text), Position::new(0, 33)), None);
+
+    let text = "@external\n@view\ndef balance(owner: address) -> uint256:\n    return 0\n";
+    assert_eq!(
+        definition(&doc(text), Position::new(2, 6)).map(|r| r.start),
+        Some(Position::new(2, 4))
+    );
+    assert_eq!(
+        hover(&doc(text), Position::new(2, 6)),
+        Some("```vyper\ndef balance(address) -> uint256\n```\nFunction".to_string())
+    );
+}
+
+#[test]
+fn test_semantic_tokens() {
+    let text = "event A:  # an event\n    x: indexed(uint256[2])\n";
+    let token = |delta_line, delta_start, length, typ| SemanticToken {
+        delta_line,
+        delta_start,
+        length,
+        token_type: token_type(typ),
+        token_modifiers_bitset: 0,
+    };
+
+    assert_eq!(
+        semantic_tokens(&doc(text)),
+        vec![
+            token(0, 0, 5, SemanticTokenType::KEYWORD),
+            token(0, 6, 1, SemanticTokenType::EVENT),
+            token(0, 4, 10, SemanticTokenType::COMMENT),
+            token(1, 4, 1, SemanticTokenType::PROPERTY),
+            token(0, 3, 7, SemanticTokenType::MODIFIER),
+            token(0, 8, 7, SemanticTokenType::TYPE),
+            token(0, 8, 1, SemanticTokenType::NUMBER),
+        ]
+    );
+
+    // Names of called built-in functions are highlighted as functions
+    let text = "N: constant(uint256) = max(1, 2)\n";
+    assert_eq!(
+        semantic_tokens(&doc(text)),
+        vec![
+            token(0, 0, 1, SemanticTokenType::PROPERTY),
+            token(0, 3, 8, SemanticTokenType::MODIFIER),
+            token(0, 9, 7, SemanticTokenType::TYPE),
+            token(0, 9, 1, SemanticTokenType::OPERATOR),
+            token(0, 2, 3, SemanticTokenType::FUNCTION),
+            token(0, 4, 1, SemanticTokenType::NUMBER),
+            token(0, 3, 1, SemanticTokenType::NUMBER),
+        ]
+    );
+
+    // Tokens spanning several lines are split
+    let text = "x = \"\"\"a\nbc\"\"\"\n";
+    assert_eq!(
+        semantic_tokens(&doc(text)),
+        vec![
+            token(0, 0, 1, SemanticTokenType::VARIABLE),
+            token(0, 2, 1, SemanticTokenType::OPERATOR),
+            token(0, 2, 4, SemanticTokenType::STRING),
+            token(1, 0, 5, SemanticTokenType::STRING),
+        ]
+    );
+}
@@ -0,0 +1,277 @@
+use std::thread::{
+    self,
+    JoinHandle,
+};
+
+use lsp_server::{
+    Connection,
+    Message,
+    Notification,
+    Request,
+    RequestId,
+};
+use lsp_types::notification::{
+    DidChangeTextDocument,
+    DidCloseTextDocument,
+    DidOpenTextDocument,
+    Exit,
+    Initialized,
+    Notification as LspNotification,
+    PublishDiagnostics,
+};
+use lsp_types::request::{
+    DocumentSymbolRequest,
+    GotoDefinition,
+    HoverRequest,
+    Initialize,
+    Request as LspRequest,
+    SemanticTokensFullRequest,
+    Shutdown,
+};
+use lsp_types::{
+    DidChangeTextDocumentParams,
+    DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams,
+    DocumentSymbolParams,
+    DocumentSymbolResponse,
+    GotoDefinitionParams,
+    GotoDefinitionResponse,
+    HoverContents,
+    HoverParams,
+    InitializeParams,
+    InitializeResult,
+    InitializedParams,
+    Position,
+    PublishDiagnosticsParams,
+    Range,
+    SemanticTokensParams,
+    SemanticTokensResult,
+    SymbolKind,
+    TextDocumentContentChangeEvent,
+    TextDocumentIdentifier,
+    TextDocumentItem,
+    TextDocumentPositionParams,
+    Url,
+    VersionedTextDocumentIdentifier,
+};
+
+use vyper_lsp::server::Server;
+
+const TOKEN: &str = r#"# Token events
+event Transfer:
+    sender: indexed(address)
+    value: uint256
+
+event Note:
+    memo: bytes[100]
+"#;
+
+/// A client connected to a server running on another thread.
+struct Client {
+    connection: Connection,
+    server: Option<JoinHandle<()>>,
+    next_id: i32,
+}
+
+impl Client {
+    /// Start a server and initialize it.
+    fn start() -> Self {
+        let (server, connection) = Connection::memory();
+        let server = thread::spawn(move || Server::new(server).run().unwrap());
+        let mut client = Client {
+            connection,
+            server: Some(server),
+            next_id: 0,
+        };
+
+        let result: InitializeResult = client.request::<Initialize>(InitializeParams::default());
+        assert!(result.capabilities.hover_provider.is_some());
+        client.notify::<Initialized>(InitializedParams {});
+
+        client
+    }
+
+    fn request<R: LspRequest>(&mut self, params: R::Params) -> R::Result {
+        self.next_id += 1;
+        let id = RequestId::from(self.next_id);
+        let request = Request::new(id.clone(), R::METHOD.to_string(), params);
+        self.connection.sender.send(request.into()).unwrap();
+
+        loop {
+            match self.connection.receiver.recv().unwrap() {
+                Message::Response(response) if response.id == id => {
+                    assert!(response.error.is_none(), "{:?}", response.error);
+                    return serde_json::from_value(response.result.unwrap()).unwrap();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn notify<N: LspNotification>(&self, params: N::Params) {
+        let notification = Notification::new(N::METHOD.to_string(), params);
+        self.connection.sender.send(notification.into()).unwrap();
+    }
+
+    /// Wait for the server to publish diagnostics.
+    fn diagnostics(&self) -> PublishDiagnosticsParams {
+        loop {
+            match self.connection.receiver.recv().unwrap() {
+                Message::Notification(n) if n.method == PublishDiagnostics::METHOD => {
+                    return serde_json::from_value(n.params).unwrap()
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn open(&self, uri: &Url, text: &str) -> PublishDiagnosticsParams {
+        self.notify::<DidOpenTextDocument>(DidOpenTextDocumentParams {
+            text_document: TextDocumentItem::new(
+                uri.clone(),
+                "vyper".to_string(),
+                1,
+                text.to_string(),
+            ),
+        });
+
+        self.diagnostics()
+    }
+
+    fn shutdown(mut self) {
+        self.request::<Shutdown>(());
+        self.notify::<Exit>(());
+        self.server.take().unwrap().join().unwrap();
+    }
+}
+
+fn uri() -> Url {
+    Url::parse("file:///token.vy").unwrap()
+}
+
+fn position_params(line: u32, character: u32) -> TextDocumentPositionParams {
+    TextDocumentPositionParams::new(
+        TextDocumentIdentifier::new(uri()),
+        Position::new(line, character),
+    )
+}
+
+#[test]
+fn test_diagnostics_on_change() {
+    let mut client = Client::start();
+
+    let published = client.open(&uri(), TOKEN);
+    assert_eq!(published.version, Some(1));
+    assert!(published.diagnostics.is_empty());
+
+    client.notify::<DidChangeTextDocument>(DidChangeTextDocumentParams {
+        text_document: VersionedTextDocumentIdentifier::new(uri(), 2),
+        content_changes: vec![TextDocumentContentChangeEvent {
+            range: None,
+            range_length: None,
+            text: "event Transfer:\n    memo: bytes\n".to_string(),
+        }],
+    });
+    let published = client.diagnostics();
+    assert_eq!(published.version, Some(2));
+    assert_eq!(published.diagnostics.len(), 1);
+    assert_eq!(
+        published.diagnostics[0].message,
+        "byte array types require a maximum size"
+    );
+    assert_eq!(
+        published.diagnostics[0].range,
+        Range::new(Position::new(1, 10), Position::new(1, 15))
+    );
+
+    client.notify::<DidCloseTextDocument>(DidCloseTextDocumentParams {
+        text_document: TextDocumentIdentifier::new(uri()),
+    });
+    assert!(client.diagnostics().diagnostics.is_empty());
+
+    // Requests for unknown documents are answered as for empty documents
+    let hover = client.request::<HoverRequest>(HoverParams {
+        text_document_position_params: position_params(1, 8),
+        work_done_progress_params: Default::default(),
+    });
+    assert_eq!(hover, None);
+
+    client.shutdown();
+}
+
+#[test]
+fn test_navigation() {
+    let mut client = Client::start();
+    client.open(&uri(), TOKEN);
+
+    let definition = client.request::<GotoDefinition>(GotoDefinitionParams {
+        text_document_position_params: position_params(5, 8),
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    });
+    match definition {
+        Some(GotoDefinitionResponse::Scalar(location)) => {
+            assert_eq!(location.uri, uri());
+            assert_eq!(
+                location.range,
+                Range::new(Position::new(5, 6), Position::new(5, 10))
+            );
+        }
+        other => panic!("unexpected definition {:?}", other),
+    }
+
+    let hover = client.request::<HoverRequest>(HoverParams {
+        text_document_position_params: position_params(2, 20),
+        work_done_progress_params: Default::default(),
+    });
+    match hover.map(|h| h.contents) {
+        Some(HoverContents::Markup(markup)) => assert_eq!(
+            markup.value,
+            "```vyper\nsender: indexed(address)\n```\nField of event `Transfer`"
+        ),
+        other => panic!("unexpected hover {:?}", other),
+    }
+
+    let symbols = client.request::<DocumentSymbolRequest>(DocumentSymbolParams {
+        text_document: TextDocumentIdentifier::new(uri()),
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    });
+    match symbols {
+        Some(DocumentSymbolResponse::Nested(symbols)) => {
+            let names: Vec<_> = symbols
+                .iter()
+                .map(|s| (s.name.as_str(), s.kind, s.detail.as_deref()))
+                .collect();
+            assert_eq!(
+                names,
+                vec![
+                    (
+                        "Transfer",
+                        SymbolKind::EVENT,
+                        Some("Transfer(address,uint256)")
+                    ),
+                    ("Note", SymbolKind::EVENT, Some("Note(bytes)")),
+                ]
+            );
+
+            let fields = symbols[1].children.as_ref().unwrap();
+            assert_eq!(fields[0].name, "memo");
+            assert_eq!(fields[0].kind, SymbolKind::FIELD);
+            assert_eq!(fields[0].detail.as_deref(), Some("bytes[100]"));
+        }
+        other => panic!("unexpected symbols {:?}", other),
+    }
+
+    let tokens = client.request::<SemanticTokensFullRequest>(SemanticTokensParams {
+        text_document: TextDocumentIdentifier::new(uri()),
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    });
+    match tokens {
+        Some(SemanticTokensResult::Tokens(tokens)) => assert!(!tokens.data.is_empty()),
+        other => panic!("unexpected semantic tokens {:?}", other),
+    }
+
+    client.shutdown();
+}
@@ -20,25 +20,47 @@ pub enum ModuleStmt<'a> {
         name: &'a str,
         fields: Vec<Spanned<EventField<'a>>>,
     },
+    /// A storage field, or a constant if its type is annotated with
+    /// `constant`.
+    FieldDef {
+        name: &'a str,
+        typ: Spanned<TypeDesc<'a>>,
+        value: Option<Spanned<ConstExpr<'a>>>,
+    },
+    /// A function definition.  The span of a function includes its
+    /// decorators.
+    FuncDef {
+        decorators: Vec<Spanned<&'a str>>,
+        name: &'a str,
+        args: Vec<Spanned<FuncArg<'a>>>,
+        return_type: Option<Spanned<TypeDesc<'a>>>,
+        body: Vec<Spanned<FuncStmt<'a>>>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub struct TypeDesc<'a> {
     pub base: &'a str,
-    pub dimensions: Vec<u32>,
+    #[serde(borrow)]
+    pub dimensions: Vec<Spanned<ConstExpr<'a>>>,
     pub annotations: Vec<&'a str>,
+    /// The key and value types of a map type such as `map(address, uint256)`,
+    /// whose base is `map`.
+    #[serde(borrow, default, skip_serializing_if = "Vec::is_empty")]
+    pub params: Vec<Spanned<TypeDesc<'a>>>,
 }
 
 impl<'a> From<&'a Token<'a>> for Spanned<TypeDesc<'a>> {
     fn from(token: &'a Token<'a>) -> Self {
-        Spanned {
-            node: TypeDesc {
+        Spanned::new(
+            TypeDesc {
                 base: token.string,
                 dimensions: vec![],
                 annotations: vec![],
+                params: vec![],
             },
-            span: token.span,
-        }
+            token.span,
+        )
     }
 }
 
@@ -49,6 +71,65 @@ pub struct EventField<'a> {
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct FuncArg<'a> {
+    pub name: &'a str,
+    pub typ: Spanned<TypeDesc<'a>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub enum FuncStmt<'a> {
+    Return {
+        value: Option<Spanned<Expr<'a>>>,
+    },
+    /// A local variable declaration such as `total: uint256 = 0`.
+    VarDecl {
+        name: &'a str,
+        typ: Spanned<TypeDesc<'a>>,
+        value: Option<Spanned<Expr<'a>>>,
+    },
+    Assign {
+        target: Spanned<Expr<'a>>,
+        value: Spanned<Expr<'a>>,
+    },
+    /// An augmented assignment such as `total += 1`.
+    AugAssign {
+        target: Spanned<Expr<'a>>,
+        op: Operator,
+        value: Spanned<Expr<'a>>,
+    },
+    For {
+        target: &'a str,
+        iter: Spanned<Expr<'a>>,
+        body: Vec<Spanned<FuncStmt<'a>>>,
+    },
+    /// A conditional statement.  An `elif` clause is an `If` statement that
+    /// is the only statement in `or_else`.
+    If {
+        test: Spanned<Expr<'a>>,
+        body: Vec<Spanned<FuncStmt<'a>>>,
+        or_else: Vec<Spanned<FuncStmt<'a>>>,
+    },
+    Assert {
+        test: Spanned<Expr<'a>>,
+        msg: Option<Spanned<Expr<'a>>>,
+    },
+    Raise {
+        msg: Option<Spanned<Expr<'a>>>,
+    },
+    /// A `log` statement such as `log Transfer(sender, receiver, value)`.
+    Log {
+        event: &'a str,
+        args: Vec<Spanned<Expr<'a>>>,
+    },
+    Expr {
+        value: Spanned<Expr<'a>>,
+    },
+    Pass,
+    Break,
+    Continue,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Copy, Clone)]
 pub enum Operator {
     Add,
     Sub,
@@ -85,7 +166,61 @@ impl TryFrom<&str> for Operator {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+impl Operator {
+    /// Return the binary operator of an augmented assignment operator such as
+    /// `+=`.
+    pub fn from_aug_assign(string: &str) -> Option<Self> {
+        Operator::try_from(string.strip_suffix('=')?).ok()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Copy, Clone)]
+pub enum BoolOperator {
+    And,
+    Or,
+}
+
+impl TryFrom<&str> for BoolOperator {
+    type Error = &'static str;
+
+    #[cfg_attr(tarpaulin, skip)]
+    fn try_from(string: &str) -> Result<Self, Self::Error> {
+        match string {
+            "and" => Ok(Self::And),
+            "or" => Ok(Self::Or),
+            _ => Err("unrecognized boolean operator string"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Copy, Clone)]
+pub enum CompOperator {
+    Eq,
+    NotEq,
+    Lt,
+    LtE,
+    Gt,
+    GtE,
+}
+
+impl TryFrom<&str> for CompOperator {
+    type Error = &'static str;
+
+    #[cfg_attr(tarpaulin, skip)]
+    fn try_from(string: &str) -> Result<Self, Self::Error> {
+        match string {
+            "==" => Ok(Self::Eq),
+            "!=" => Ok(Self::NotEq),
+            "<" => Ok(Self::Lt),
+            "<=" => Ok(Self::LtE),
+            ">" => Ok(Self::Gt),
+            ">=" => Ok(Self::GtE),
+            _ => Err("unrecognized comparison operator string"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Copy, Clone)]
 pub enum UnaryOp {
     Invert,
     Not,
@@ -125,4 +260,56 @@ pub enum ConstExpr<'a> {
     Num {
         num: &'a str,
     },
+    /// A call to a built-in function such as `shift(1, 8)`.
+    Call {
+        func: &'a str,
+        args: Vec<Spanned<ConstExpr<'a>>>,
+    },
+}
+
+/// An expression in the body of a function.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub enum Expr<'a> {
+    BoolOp {
+        left: Box<Spanned<Expr<'a>>>,
+        op: BoolOperator,
+        right: Box<Spanned<Expr<'a>>>,
+    },
+    BinOp {
+        left: Box<Spanned<Expr<'a>>>,
+        op: Operator,
+        right: Box<Spanned<Expr<'a>>>,
+    },
+    UnaryOp {
+        op: UnaryOp,
+        operand: Box<Spanned<Expr<'a>>>,
+    },
+    /// A comparison.  Comparisons cannot be chained.
+    CompOp {
+        left: Box<Spanned<Expr<'a>>>,
+        op: CompOperator,
+        right: Box<Spanned<Expr<'a>>>,
+    },
+    Call {
+        func: Box<Spanned<Expr<'a>>>,
+        args: Vec<Spanned<Expr<'a>>>,
+    },
+    Attribute {
+        value: Box<Spanned<Expr<'a>>>,
+        attr: &'a str,
+    },
+    Subscript {
+        value: Box<Spanned<Expr<'a>>>,
+        index: Box<Spanned<Expr<'a>>>,
+    },
+    Name {
+        name: &'a str,
+    },
+    Num {
+        num: &'a str,
+    },
+    /// A string literal as written in source code, including its quotes.
+    Str {
+        string: &'a str,
+    },
 }
@@ -0,0 +1,746 @@
+//! A lossless concrete syntax tree.
+//!
+//! The leaves of the tree are all tokens of a source file, including comments
+//! and non-logical newlines, and the whitespace between them, so the text of a
+//! tree is exactly the source it was parsed from.  Leaves refer to tokens by
+//! their index into the token list of a `Cst`, which makes nodes independent
+//! of the lifetime of the source.
+//!
+//! Trivia is attached to nodes as follows:
+//!
+//! * Comment lines, blank lines and the indentation directly before a statement
+//!   lead its node.  Comment lines at the start of a block come before the
+//!   block's `INDENT` token, which holds the indentation of the block's first
+//!   statement, and so belong to the block instead.
+//! * A comment on the same line as the end of a statement belongs to the
+//!   statement, as does the newline ending it.
+//! * The `DEDENT` tokens closing a block belong to the node that opened it.
+//!   They have no width and are placed directly after the newline ending the
+//!   block, before any comment lines following it.
+//!
+//! Trees are built from the spans of the AST produced by the parser, and can be
+//! projected back to an AST with `Cst::to_module`.
+
+use std::fmt;
+
+use nom::error::VerboseError;
+
+use crate::ast::{
+    ConstExpr,
+    EventField,
+    Expr,
+    FuncArg,
+    FuncStmt,
+    Module,
+    ModuleStmt,
+    TypeDesc,
+};
+use crate::parsers::{
+    ann_type_desc,
+    const_expr,
+    file_input,
+    func_def,
+    get_parse_tokens,
+};
+use crate::span::{
+    Span,
+    Spanned,
+};
+use crate::tokenizer::{
+    tokenize,
+    Token,
+    TokenType,
+    TokenizeError,
+};
+
+#[derive(Debug, PartialEq)]
+pub struct CstError {
+    pub msg: &'static str,
+    pub offset: usize,
+}
+
+impl From<TokenizeError> for CstError {
+    fn from(err: TokenizeError) -> Self {
+        CstError {
+            msg: err.msg,
+            offset: err.offset,
+        }
+    }
+}
+
+/// The syntactic construct represented by a node.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum SyntaxKind {
+    Module,
+    EventDef,
+    FieldDef,
+    FuncDef,
+    EventField,
+    TypeDesc,
+}
+
+/// A child of a node.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Element {
+    Node(Node),
+    /// The token at an index into the token list of a tree.
+    Token(usize),
+    /// Whitespace between tokens, including line continuations.
+    Whitespace(Span),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Node {
+    pub kind: SyntaxKind,
+    pub children: Vec<Element>,
+}
+
+impl Node {
+    /// Return the child nodes of this node.
+    pub fn child_nodes(&self) -> impl Iterator<Item = &Node> {
+        self.children.iter().filter_map(|el| match el {
+            Element::Node(node) => Some(node),
+            _ => None,
+        })
+    }
+
+    /// Return the indices of all tokens in this node in source order.
+    pub fn token_indices(&self) -> Vec<usize> {
+        let mut indices = vec![];
+        for el in &self.children {
+            match el {
+                Element::Node(node) => indices.extend(node.token_indices()),
+                Element::Token(i) => indices.push(*i),
+                Element::Whitespace(_) => {}
+            }
+        }
+
+        indices
+    }
+}
+
+/// A lossless concrete syntax tree for a source file.
+#[derive(Debug, PartialEq)]
+pub struct Cst<'a> {
+    pub source: &'a str,
+    pub tokens: Vec<Token<'a>>,
+    pub root: Node,
+}
+
+/// Return true if tokens of type `typ` carry no syntactic meaning.
+fn is_trivia(typ: TokenType) -> bool {
+    typ == TokenType::COMMENT || typ == TokenType::NL
+}
+
+/// Return true if tokens of type `typ` are neither trivia nor structural.
+fn is_significant(typ: TokenType) -> bool {
+    !is_trivia(typ)
+        && ![
+            TokenType::INDENT,
+            TokenType::DEDENT,
+            TokenType::NEWLINE,
+            TokenType::ENDMARKER,
+        ]
+        .contains(&typ)
+}
+
+/// Return the leaves covering `source` with the tokens `tokens`.
+fn leaves(source: &str, tokens: &[Token]) -> Vec<Element> {
+    let mut leaves = vec![];
+    let mut pos = 0;
+
+    for (i, tok) in tokens.iter().enumerate() {
+        if tok.span.start > pos {
+            leaves.push(Element::Whitespace(Span::new(pos, tok.span.start)));
+        }
+        pos = pos.max(tok.span.end);
+
+        if tok.typ == TokenType::DEDENT && tok.span.start == tok.span.end {
+            // Place dedents before the trivia that precedes them
+            let mut at = leaves.len();
+            while at > 0 {
+                match leaves[at - 1] {
+                    Element::Whitespace(_) => {}
+                    Element::Token(j) if is_trivia(tokens[j].typ) => {}
+                    _ => break,
+                }
+                at -= 1;
+            }
+            leaves.insert(at, Element::Token(i));
+        } else {
+            leaves.push(Element::Token(i));
+        }
+    }
+
+    if pos < source.len() {
+        leaves.push(Element::Whitespace(Span::new(pos, source.len())));
+    }
+
+    leaves
+}
+
+/// Rebuild the constant expression `expr`, which was parsed from tokens of
+/// `source` with a shorter lifetime, with names and numbers borrowed from
+/// `source` itself.
+fn rebase_const_expr<'a>(source: &'a str, expr: &Spanned<ConstExpr>) -> Spanned<ConstExpr<'a>> {
+    let rebase = |expr| Box::new(rebase_const_expr(source, expr));
+    let text = &source[expr.span.start..expr.span.end];
+
+    let node = match &expr.node {
+        ConstExpr::BinOp { left, op, right } => ConstExpr::BinOp {
+            left: rebase(left),
+            op: *op,
+            right: rebase(right),
+        },
+        ConstExpr::UnaryOp { op, operand } => ConstExpr::UnaryOp {
+            op: *op,
+            operand: rebase(operand),
+        },
+        ConstExpr::Name { .. } => ConstExpr::Name { name: text },
+        ConstExpr::Num { .. } => ConstExpr::Num { num: text },
+        ConstExpr::Call { func, args } => ConstExpr::Call {
+            func: &text[..func.len()],
+            args: args
+                .iter()
+                .map(|arg| rebase_const_expr(source, arg))
+                .collect(),
+        },
+    };
+
+    Spanned::new(node, expr.span)
+}
+
+/// Return the string `string`, which is a slice of `source` borrowed with a
+/// shorter lifetime, borrowed from `source` itself.
+fn rebase_str<'a>(source: &'a str, string: &str) -> &'a str {
+    let start = string.as_ptr() as usize - source.as_ptr() as usize;
+
+    &source[start..start + string.len()]
+}
+
+/// Rebuild the type description `typ`, which was parsed from tokens of
+/// `source` with a shorter lifetime, with names borrowed from `source` itself.
+fn rebase_type_desc<'a>(source: &'a str, typ: &Spanned<TypeDesc>) -> Spanned<TypeDesc<'a>> {
+    let node = TypeDesc {
+        base: rebase_str(source, typ.node.base),
+        dimensions: typ
+            .node
+            .dimensions
+            .iter()
+            .map(|dim| rebase_const_expr(source, dim))
+            .collect(),
+        annotations: typ
+            .node
+            .annotations
+            .iter()
+            .map(|ann| rebase_str(source, ann))
+            .collect(),
+        params: typ
+            .node
+            .params
+            .iter()
+            .map(|param| rebase_type_desc(source, param))
+            .collect(),
+    };
+
+    Spanned::new(node, typ.span)
+}
+
+/// Rebuild the expression `expr`, which was parsed from tokens of `source`
+/// with a shorter lifetime, with strings borrowed from `source` itself.
+fn rebase_expr<'a>(source: &'a str, expr: &Spanned<Expr>) -> Spanned<Expr<'a>> {
+    let rebase = |expr| Box::new(rebase_expr(source, expr));
+
+    let node = match &expr.node {
+        Expr::BoolOp { left, op, right } => Expr::BoolOp {
+            left: rebase(left),
+            op: *op,
+            right: rebase(right),
+        },
+        Expr::BinOp { left, op, right } => Expr::BinOp {
+            left: rebase(left),
+            op: *op,
+            right: rebase(right),
+        },
+        Expr::UnaryOp { op, operand } => Expr::UnaryOp {
+            op: *op,
+            operand: rebase(operand),
+        },
+        Expr::CompOp { left, op, right } => Expr::CompOp {
+            left: rebase(left),
+            op: *op,
+            right: rebase(right),
+        },
+        Expr::Call { func, args } => Expr::Call {
+            func: rebase(func),
+            args: args.iter().map(|arg| rebase_expr(source, arg)).collect(),
+        },
+        Expr::Attribute { value, attr } => Expr::Attribute {
+            value: rebase(value),
+            attr: rebase_str(source, attr),
+        },
+        Expr::Subscript { value, index } => Expr::Subscript {
+            value: rebase(value),
+            index: rebase(index),
+        },
+        Expr::Name { name } => Expr::Name {
+            name: rebase_str(source, name),
+        },
+        Expr::Num { num } => Expr::Num {
+            num: rebase_str(source, num),
+        },
+        Expr::Str { string } => Expr::Str {
+            string: rebase_str(source, string),
+        },
+    };
+
+    Spanned::new(node, expr.span)
+}
+
+/// Rebuild the statement `stmt`, which was parsed from tokens of `source`
+/// with a shorter lifetime, with strings borrowed from `source` itself.
+fn rebase_func_stmt<'a>(source: &'a str, stmt: &Spanned<FuncStmt>) -> Spanned<FuncStmt<'a>> {
+    let expr = |expr| rebase_expr(source, expr);
+    let opt_expr = |expr: &Option<Spanned<Expr>>| expr.as_ref().map(|e| rebase_expr(source, e));
+    let stmts = |body: &[Spanned<FuncStmt>]| {
+        body.iter()
+            .map(|stmt| rebase_func_stmt(source, stmt))
+            .collect()
+    };
+
+    let node = match &stmt.node {
+        FuncStmt::Return { value } => FuncStmt::Return {
+            value: opt_expr(value),
+        },
+        FuncStmt::VarDecl { name, typ, value } => FuncStmt::VarDecl {
+            name: rebase_str(source, name),
+            typ: rebase_type_desc(source, typ),
+            value: opt_expr(value),
+        },
+        FuncStmt::Assign { target, value } => FuncStmt::Assign {
+            target: expr(target),
+            value: expr(value),
+        },
+        FuncStmt::AugAssign { target, op, value } => FuncStmt::AugAssign {
+            target: expr(target),
+            op: *op,
+            value: expr(value),
+        },
+        FuncStmt::For { target, iter, body } => FuncStmt::For {
+            target: rebase_str(source, target),
+            iter: expr(iter),
+            body: stmts(body),
+        },
+        FuncStmt::If {
+            test,
+            body,
+            or_else,
+        } => FuncStmt::If {
+            test: expr(test),
+            body: stmts(body),
+            or_else: stmts(or_else),
+        },
+        FuncStmt::Assert { test, msg } => FuncStmt::Assert {
+            test: expr(test),
+            msg: opt_expr(msg),
+        },
+        FuncStmt::Raise { msg } => FuncStmt::Raise { msg: opt_expr(msg) },
+        FuncStmt::Log { event, args } => FuncStmt::Log {
+            event: rebase_str(source, event),
+            args: args.iter().map(expr).collect(),
+        },
+        FuncStmt::Expr { value } => FuncStmt::Expr { value: expr(value) },
+        FuncStmt::Pass => FuncStmt::Pass,
+        FuncStmt::Break => FuncStmt::Break,
+        FuncStmt::Continue => FuncStmt::Continue,
+    };
+
+    Spanned::new(node, stmt.span)
+}
+
+/// Rebuild the function definition `stmt`, which was parsed from tokens of
+/// `source` with a shorter lifetime, with strings borrowed from `source`
+/// itself.
+fn rebase_func_def<'a>(source: &'a str, stmt: &Spanned<ModuleStmt>) -> Spanned<ModuleStmt<'a>> {
+    let node = match &stmt.node {
+        ModuleStmt::FuncDef {
+            decorators,
+            name,
+            args,
+            return_type,
+            body,
+        } => ModuleStmt::FuncDef {
+            decorators: decorators
+                .iter()
+                .map(|dec| Spanned::new(rebase_str(source, dec.node), dec.span))
+                .collect(),
+            name: rebase_str(source, name),
+            args: args
+                .iter()
+                .map(|arg| {
+                    let node = FuncArg {
+                        name: rebase_str(source, arg.node.name),
+                        typ: rebase_type_desc(source, &arg.node.typ),
+                    };
+
+                    Spanned::new(node, arg.span)
+                })
+                .collect(),
+            return_type: return_type
+                .as_ref()
+                .map(|typ| rebase_type_desc(source, typ)),
+            body: body
+                .iter()
+                .map(|stmt| rebase_func_stmt(source, stmt))
+                .collect(),
+        },
+        _ => unreachable!("not a function definition"),
+    };
+
+    Spanned::new(node, stmt.span)
+}
+
+/// Builds the nodes of a tree from the spans of AST nodes.
+struct Builder<'a, 'b> {
+    tokens: &'b [Token<'a>],
+    leaves: Vec<Element>,
+}
+
+impl<'a, 'b> Builder<'a, 'b> {
+    fn token(&self, leaf: usize) -> Option<&Token<'a>> {
+        match self.leaves.get(leaf)? {
+            Element::Token(i) => Some(&self.tokens[*i]),
+            _ => None,
+        }
+    }
+
+    /// Return the range of the leaves of the significant tokens in `span`.
+    fn span_range(&self, span: Span) -> (usize, usize) {
+        let significant = |leaf: &usize| {
+            self.token(*leaf).is_some_and(|t| {
+                is_significant(t.typ) && t.span.start >= span.start && t.span.end <= span.end
+            })
+        };
+        let lo = (0..self.leaves.len()).find(significant).unwrap();
+        let hi = (0..self.leaves.len()).rev().find(significant).unwrap();
+
+        (lo, hi + 1)
+    }
+
+    /// Return the range of the leaves of the statement in `span` including its
+    /// trivia.
+    fn stmt_range(&self, span: Span) -> (usize, usize) {
+        let (mut lo, mut hi) = self.span_range(span);
+
+        while lo > 0 {
+            match self.token(lo - 1) {
+                None => {}
+                Some(tok) if is_trivia(tok.typ) => {}
+                _ => break,
+            }
+            lo -= 1;
+        }
+
+        while hi < self.leaves.len() {
+            match self.token(hi) {
+                None => {}
+                Some(tok) if tok.typ == TokenType::COMMENT => {}
+                Some(tok) if tok.typ == TokenType::NEWLINE => {
+                    hi += 1;
+                    break;
+                }
+                _ => break,
+            }
+            hi += 1;
+        }
+
+        // Include the dedents closing the blocks opened by the statement
+        let count = |typ| {
+            (lo..hi)
+                .filter(|leaf| self.token(*leaf).is_some_and(|t| t.typ == typ))
+                .count()
+        };
+        let open = count(TokenType::INDENT) - count(TokenType::DEDENT);
+        for _ in 0..open {
+            if self.token(hi).is_some_and(|t| t.typ == TokenType::DEDENT) {
+                hi += 1;
+            }
+        }
+
+        (lo, hi)
+    }
+
+    /// Return a node of kind `kind` covering the leaves in `lo..hi` with the
+    /// child nodes `children` given with their ranges in order.
+    fn node(
+        &self,
+        kind: SyntaxKind,
+        (lo, hi): (usize, usize),
+        children: Vec<((usize, usize), Node)>,
+    ) -> Node {
+        let mut elements = vec![];
+        let mut pos = lo;
+
+        for ((child_lo, child_hi), child) in children {
+            elements.extend_from_slice(&self.leaves[pos..child_lo]);
+            elements.push(Element::Node(child));
+            pos = child_hi;
+        }
+        elements.extend_from_slice(&self.leaves[pos..hi]);
+
+        Node {
+            kind,
+            children: elements,
+        }
+    }
+
+    fn type_desc(&self, typ: &Spanned<TypeDesc>) -> ((usize, usize), Node) {
+        let range = self.span_range(typ.span);
+
+        (range, self.node(SyntaxKind::TypeDesc, range, vec![]))
+    }
+
+    fn module_stmt(&self, stmt: &Spanned<ModuleStmt>) -> ((usize, usize), Node) {
+        let range = self.stmt_range(stmt.span);
+
+        match &stmt.node {
+            ModuleStmt::EventDef { fields, .. } => {
+                let fields = fields
+                    .iter()
+                    .map(|field| {
+                        let range = self.stmt_range(field.span);
+                        let typ = self.type_desc(&field.node.typ);
+
+                        (range, self.node(SyntaxKind::EventField, range, vec![typ]))
+                    })
+                    .collect();
+
+                (range, self.node(SyntaxKind::EventDef, range, fields))
+            }
+            ModuleStmt::FieldDef { typ, .. } => {
+                let typ = self.type_desc(typ);
+
+                (range, self.node(SyntaxKind::FieldDef, range, vec![typ]))
+            }
+            ModuleStmt::FuncDef {
+                args, return_type, ..
+            } => {
+                let types = args
+                    .iter()
+                    .map(|arg| &arg.node.typ)
+                    .chain(return_type)
+                    .map(|typ| self.type_desc(typ))
+                    .collect();
+
+                (range, self.node(SyntaxKind::FuncDef, range, types))
+            }
+        }
+    }
+}
+
+impl<'a> Cst<'a> {
+    /// Parse the source code in `source` into a tree.
+    pub fn parse(source: &'a str) -> Result<Self, CstError> {
+        let tokens = tokenize(source)?;
+        let parse_tokens = get_parse_tokens(source)?;
+
+        let root = {
+            let (_, module) = file_input::<VerboseError<_>>(&parse_tokens[..]).map_err(|e| {
+                let offset = match e {
+                    nom::Err::Error(e) | nom::Err::Failure(e) => e
+                        .errors
+                        .first()
+                        .and_then(|(input, _)| input.first())
+                        .map(|tok| tok.span.start),
+                    nom::Err::Incomplete(_) => None,
+                };
+
+                CstError {
+                    msg: "invalid syntax",
+                    offset: offset.unwrap_or(source.len()),
+                }
+            })?;
+
+            let builder = Builder {
+                leaves: leaves(source, &tokens),
+                tokens: &tokens,
+            };
+            let stmts = module
+                .node
+                .body
+                .iter()
+                .map(|stmt| builder.module_stmt(stmt))
+                .collect();
+
+            builder.node(SyntaxKind::Module, (0, builder.leaves.len()), stmts)
+        };
+
+        Ok(Cst {
+            source,
+            tokens,
+            root,
+        })
+    }
+
+    /// Return the significant tokens directly or indirectly in `node`.
+    pub fn significant_tokens(&self, node: &Node) -> Vec<&Token<'a>> {
+        node.token_indices()
+            .into_iter()
+            .map(|i| &self.tokens[i])
+            .filter(|t| is_significant(t.typ))
+            .collect()
+    }
+
+    /// Return the span from the first to the last significant token in
+    /// `node`.
+    pub fn span(&self, node: &Node) -> Option<Span> {
+        let tokens = self.significant_tokens(node);
+
+        match (tokens.first(), tokens.last()) {
+            (Some(first), Some(last)) => Some((&first.span, &last.span).into()),
+            _ => None,
+        }
+    }
+
+    /// Return the comments that lead `node`, e.g. the comment lines directly
+    /// above a statement.
+    pub fn leading_comments(&self, node: &Node) -> Vec<&'a str> {
+        let mut comments = vec![];
+
+        for el in &node.children {
+            match el {
+                Element::Token(i) if self.tokens[*i].typ == TokenType::COMMENT => {
+                    comments.push(self.tokens[*i].string)
+                }
+                Element::Token(i) if is_trivia(self.tokens[*i].typ) => {}
+                Element::Whitespace(_) => {}
+                _ => break,
+            }
+        }
+
+        comments
+    }
+
+    fn type_desc(&self, node: &Node) -> Spanned<TypeDesc<'a>> {
+        let tokens: Vec<_> = self
+            .significant_tokens(node)
+            .into_iter()
+            .map(|tok| Token { ..*tok })
+            .collect();
+        let (_, typ) = ann_type_desc::<VerboseError<_>>(&tokens[..]).expect("type was parsed");
+
+        rebase_type_desc(self.source, &typ)
+    }
+
+    /// Parse the constant expression at the start of `tokens`.  Returns the
+    /// expression and the number of tokens it covers.
+    fn const_expr(&self, tokens: &[&Token<'a>]) -> (Spanned<ConstExpr<'a>>, usize) {
+        let tokens: Vec<_> = tokens.iter().map(|tok| Token { ..**tok }).collect();
+        let (rest, expr) =
+            const_expr::<VerboseError<_>>(&tokens[..]).expect("expression was parsed");
+
+        (
+            rebase_const_expr(self.source, &expr),
+            tokens.len() - rest.len(),
+        )
+    }
+
+    /// Parse the function definition in `node`.  Unlike other statements,
+    /// functions are parsed from their structural tokens as well because their
+    /// bodies are indented blocks.
+    fn func_def(&self, node: &Node) -> Spanned<ModuleStmt<'a>> {
+        let tokens: Vec<_> = node
+            .token_indices()
+            .into_iter()
+            .map(|i| &self.tokens[i])
+            .filter(|tok| !is_trivia(tok.typ))
+            .map(|tok| Token { ..*tok })
+            .collect();
+        let (_, stmt) = func_def::<VerboseError<_>>(&tokens[..]).expect("function was parsed");
+
+        rebase_func_def(self.source, &stmt)
+    }
+
+    fn event_field(&self, node: &Node) -> Spanned<EventField<'a>> {
+        let typ = node.child_nodes().next().expect("field has a type");
+
+        Spanned::new(
+            EventField {
+                name: self.significant_tokens(node)[0].string,
+                typ: self.type_desc(typ),
+            },
+            self.span(node).unwrap(),
+        )
+    }
+
+    /// Project the tree to an AST.
+    pub fn to_module(&self) -> Module<'a> {
+        let body = self
+            .root
+            .child_nodes()
+            .map(|node| match node.kind {
+                SyntaxKind::EventDef => {
+                    let fields = node
+                        .child_nodes()
+                        .map(|field| self.event_field(field))
+                        .collect();
+
+                    Spanned::new(
+                        ModuleStmt::EventDef {
+                            name: self.significant_tokens(node)[1].string,
+                            fields,
+                        },
+                        self.span(node).unwrap(),
+                    )
+                }
+                SyntaxKind::FieldDef => {
+                    let tokens = self.significant_tokens(node);
+                    let typ = node.child_nodes().next().expect("field has a type");
+                    let value = tokens
+                        .iter()
+                        .position(|tok| tok.string == "=")
+                        .map(|i| self.const_expr(&tokens[i + 1..]).0);
+
+                    Spanned::new(
+                        ModuleStmt::FieldDef {
+                            name: tokens[0].string,
+                            typ: self.type_desc(typ),
+                            value,
+                        },
+                        self.span(node).unwrap(),
+                    )
+                }
+                SyntaxKind::FuncDef => self.func_def(node),
+                kind => panic!("unexpected module statement {:?}", kind),
+            })
+            .collect();
+
+        Module { body }
+    }
+
+    fn write_element(&self, f: &mut fmt::Formatter, el: &Element) -> fmt::Result {
+        match el {
+            Element::Node(node) => {
+                for child in &node.children {
+                    self.write_element(f, child)?;
+                }
+                Ok(())
+            }
+            Element::Token(i) => {
+                let span = self.tokens[*i].span;
+                write!(f, "{}", &self.source[span.start..span.end])
+            }
+            Element::Whitespace(span) => write!(f, "{}", &self.source[span.start..span.end]),
+        }
+    }
+}
+
+impl<'a> fmt::Display for Cst<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for el in &self.root.children {
+            self.write_element(f, el)?;
+        }
+
+        Ok(())
+    }
+}
@@ -9,8 +9,15 @@ use nom::{
     IResult,
 };
 
+use crate::line_index::{
+    ColumnUnit,
+    LineIndex,
+};
 use crate::parsers::TokenSlice;
-use crate::string_utils::StringPositions;
+use crate::source_map::{
+    FileId,
+    SourceMap,
+};
 
 pub fn make_error<I, O, E>(input: I, kind: ErrorKind) -> IResult<I, O, E>
 where
@@ -24,9 +31,7 @@ where
 /// Inspired by nom internals:
 /// https://github.com/Geal/nom/blob/c326e077b83c62f81b717c80a281cb453cb914e7/src/error.rs#L141
 pub fn format_debug_error(input: &str, e: VerboseError<TokenSlice>) -> String {
-    use std::iter::repeat;
-
-    let mut string_positions = StringPositions::new(input);
+    let line_index = LineIndex::new(input);
     let lines: Vec<_> = input.lines().map(String::from).collect();
 
     let mut result = String::new();
@@ -37,9 +42,11 @@ pub fn format_debug_error(input: &str, e: VerboseError<TokenSlice>) -> String {
         result += &format!("{}: ", err_no);
 
         if let Some(tok) = first_token {
-            let pos = string_positions.get_pos(tok.span.start).unwrap();
+            let pos = line_index
+                .line_col(tok.span.start, ColumnUnit::Byte)
+                .unwrap();
 
-            result += &format!("at line {} col {}", pos.line, pos.col);
+            result += &format!("at line {} col {}", pos.line + 1, pos.col);
 
             match err_kind {
                 VerboseErrorKind::Char(c) => {
@@ -57,10 +64,10 @@ pub fn format_debug_error(input: &str, e: VerboseError<TokenSlice>) -> String {
                 }
             }
 
-            result += &lines[pos.line - 1];
+            result += &lines[pos.line];
             result += "\n";
             if pos.col > 0 {
-                result += &repeat(' ').take(pos.col).collect::<String>();
+                result += &" ".repeat(pos.col);
             }
             result += "^\n\n";
         } else {
@@ -84,53 +91,55 @@ pub fn format_debug_error(input: &str, e: VerboseError<TokenSlice>) -> String {
 #[derive(Debug, PartialEq)]
 pub enum FormatError {
     NoContextFound(String),
+    NoLineFound(String),
 }
 
 /// Format a verbose error into a user-facing syntax error message.
 ///
 /// Uses the last (outermost) context error string in a verbose error to build a
-/// user-facing error message and position.
+/// user-facing error message and position.  The position is rendered as
+/// `path:line:col` using the path under which `file` was loaded into `sources`.
 ///
 /// Inspired by nom internals:
 /// https://github.com/Geal/nom/blob/c326e077b83c62f81b717c80a281cb453cb914e7/src/error.rs#L141
 pub fn format_user_error(
-    input: &str,
+    sources: &SourceMap,
+    file: FileId,
     err: VerboseError<TokenSlice>,
 ) -> Result<String, FormatError> {
-    use std::iter::repeat;
-
     let last_context_error = err
         .errors
         .iter()
         .rev()
-        .skip_while(|e| match e {
-            (_, VerboseErrorKind::Context(_)) => false,
-            _ => true,
-        })
-        .next();
+        .find(|e| matches!(e, (_, VerboseErrorKind::Context(_))));
 
     if let Some((parser_input, VerboseErrorKind::Context(context_string))) = last_context_error {
-        let mut string_positions = StringPositions::new(input);
-        let lines: Vec<_> = input.lines().map(String::from).collect();
-
         let mut result = String::new();
         let first_token = parser_input.iter().next();
 
         if let Some(tok) = first_token {
-            let pos = string_positions.get_pos(tok.span.start).unwrap();
+            let span = tok.span.in_file(file);
+            let (pos, line) = match (sources.line_col(span), sources.line(span)) {
+                (Some(pos), Some(line)) => (pos, line),
+                _ => {
+                    return Err(FormatError::NoLineFound(format!(
+                        "no source line found at {}",
+                        sources.location(span)
+                    )))
+                }
+            };
 
-            result += &format!(
-                "at line {} col {}, expected {}:\n",
-                pos.line, pos.col, context_string
-            );
-            result += &lines[pos.line - 1];
+            result += &format!("{}: expected {}:\n", sources.location(span), context_string);
+            result += line;
             result += "\n";
-            if pos.col > 0 {
-                result += &repeat(' ').take(pos.col).collect::<String>();
-            }
+            result += &" ".repeat(line[..pos.col].chars().count());
             result += "^\n";
         } else {
-            result += &format!("expected {}, got empty input\n", context_string);
+            result += &format!(
+                "{}: expected {}, got empty input\n",
+                sources.file(file).path,
+                context_string
+            );
         }
         Ok(result)
     } else {
@@ -0,0 +1,448 @@
+//! An opinionated formatter for Vyper source code.
+//!
+//! The formatter works on the full token stream, including comments and
+//! non-logical newlines, so it preserves comments and does not require source
+//! to parse.  It normalizes:
+//!
+//! * Indentation to four spaces per level.  Lines continued inside brackets are
+//!   indented by four more spaces per open bracket.
+//! * Blank lines.  Top-level definitions are separated from their surroundings
+//!   by exactly one blank line, comments directly above a definition stay
+//!   attached to it, blocks and files start without blank lines, and at most
+//!   one blank line is kept anywhere else.
+//! * Spacing around operators, commas, colons and brackets.  Keyword arguments
+//!   are written without spaces around `=` unless the parameter is annotated.
+//! * Decorators, which are placed on their own lines directly above the
+//!   decorated definition.
+//!
+//! Lines continued with a backslash are joined.  Formatting is idempotent.
+
+use crate::symbol::{
+    sym,
+    Symbol,
+};
+use crate::tokenizer::{
+    tokenize,
+    Token,
+    TokenType,
+    TokenizeError,
+};
+
+const INDENT: &str = "    ";
+
+/// Names that start a definition when they begin a top-level line.
+const DEFINITION_KEYWORDS: &[Symbol] = &[
+    sym::CONTRACT,
+    sym::DEF,
+    sym::EVENT,
+    sym::INTERFACE,
+    sym::STRUCT,
+];
+
+/// Keywords after which an opening bracket starts a new expression rather than
+/// a call or subscript, and after which `-`, `+` and `~` are unary.
+const KEYWORDS: &[Symbol] = &[
+    sym::AND,
+    sym::AS,
+    sym::ASSERT,
+    sym::ELIF,
+    sym::ELSE,
+    sym::FOR,
+    sym::IF,
+    sym::IN,
+    sym::IS,
+    sym::NOT,
+    sym::OR,
+    sym::RAISE,
+    sym::RETURN,
+    sym::WHILE,
+];
+
+/// Return true if the token `tok` is one of the keywords `keywords`.
+fn is_keyword(tok: &Token, keywords: &[Symbol]) -> bool {
+    tok.sym.is_some_and(|sym| keywords.contains(&sym))
+}
+
+#[derive(Debug, PartialEq)]
+pub struct FormatError {
+    pub msg: &'static str,
+    pub offset: usize,
+}
+
+impl From<TokenizeError> for FormatError {
+    fn from(err: TokenizeError) -> Self {
+        FormatError {
+            msg: err.msg,
+            offset: err.offset,
+        }
+    }
+}
+
+/// A physical or logical line of source code.
+enum Line<'a, 'b> {
+    Blank,
+    /// A line that contains only a comment.
+    Comment {
+        text: &'a str,
+        level: usize,
+    },
+    /// The tokens of a logical line up to its `NEWLINE` token, including any
+    /// comments and non-logical newlines inside brackets.
+    Code {
+        tokens: Vec<&'b Token<'a>>,
+        level: usize,
+    },
+}
+
+impl<'a, 'b> Line<'a, 'b> {
+    fn level(&self) -> usize {
+        match self {
+            Line::Blank => 0,
+            Line::Comment { level, .. } | Line::Code { level, .. } => *level,
+        }
+    }
+
+    fn first_token(&self) -> Option<&Token<'a>> {
+        match self {
+            Line::Code { tokens, .. } => tokens.first().copied(),
+            _ => None,
+        }
+    }
+
+    fn is_decorator(&self) -> bool {
+        self.first_token().is_some_and(|t| t.string == "@")
+    }
+
+    /// Return true if the line starts a definition or decorates one.
+    fn is_definition(&self) -> bool {
+        match self.first_token() {
+            Some(tok) => tok.string == "@" || is_keyword(tok, DEFINITION_KEYWORDS),
+            None => false,
+        }
+    }
+
+    /// Return true if the line ends with a colon and so opens a block.
+    fn opens_block(&self) -> bool {
+        match self {
+            Line::Code { tokens, .. } => tokens
+                .iter()
+                .rev()
+                .find(|t| t.typ != TokenType::COMMENT && t.typ != TokenType::NEWLINE)
+                .is_some_and(|t| t.string == ":"),
+            _ => false,
+        }
+    }
+}
+
+/// Return the column at which the token `tok` starts on its line.
+fn column(tok: &Token) -> usize {
+    tok.line.len() - tok.line.trim_start().len()
+}
+
+/// Group the tokens `tokens` into lines.
+fn split_lines<'a, 'b>(tokens: &'b [Token<'a>]) -> Result<Vec<Line<'a, 'b>>, FormatError> {
+    // The tokenizer also yields error tokens for whitespace before invalid
+    // characters
+    let invalid = tokens
+        .iter()
+        .find(|t| t.typ == TokenType::ERRORTOKEN && !t.string.trim().is_empty());
+    if let Some(tok) = invalid {
+        return Err(FormatError {
+            msg: "invalid token",
+            offset: tok.span.start,
+        });
+    }
+
+    let mut lines = vec![];
+    let mut level = 0;
+    // The columns of the indentation levels of open blocks
+    let mut indents = vec![0];
+    let mut i = 0;
+
+    while i < tokens.len() {
+        let tok = &tokens[i];
+
+        match tok.typ {
+            TokenType::NL => {
+                // Newlines after comments end the comment line
+                if i == 0 || tokens[i - 1].typ != TokenType::COMMENT {
+                    lines.push(Line::Blank);
+                }
+            }
+            TokenType::COMMENT => {
+                let col = column(tok);
+                let mut comment_level = indents[1..].iter().filter(|c| **c <= col).count();
+
+                // Comments at the start of a block precede its INDENT token
+                let after_opener = lines
+                    .iter()
+                    .rev()
+                    .find(|l| matches!(l, Line::Code { .. }))
+                    .is_some_and(Line::opens_block);
+                if after_opener && col > *indents.last().unwrap() {
+                    comment_level = level + 1;
+                }
+
+                lines.push(Line::Comment {
+                    text: tok.string.trim_end(),
+                    level: comment_level,
+                });
+            }
+            TokenType::INDENT => {
+                level += 1;
+                indents.push(tok.string.len());
+            }
+            TokenType::DEDENT => {
+                level -= 1;
+                indents.pop();
+            }
+            TokenType::NEWLINE | TokenType::ENDMARKER => {}
+            _ => {
+                let start = i;
+                while i < tokens.len() && tokens[i].typ != TokenType::NEWLINE {
+                    i += 1;
+                }
+
+                lines.push(Line::Code {
+                    tokens: tokens[start..i].iter().collect(),
+                    level,
+                });
+            }
+        }
+
+        i += 1;
+    }
+
+    Ok(lines)
+}
+
+/// Return true if the token `tok` following the token `prev` is a unary
+/// operator.
+fn is_unary(tok: &Token, prev: Option<&Token>) -> bool {
+    if !["-", "+", "~"].contains(&tok.string) {
+        return false;
+    }
+
+    match prev {
+        None => true,
+        Some(prev) => match prev.typ {
+            TokenType::OP => ![")", "]", "}"].contains(&prev.string),
+            TokenType::NAME => is_keyword(prev, KEYWORDS),
+            _ => false,
+        },
+    }
+}
+
+/// An open bracket and whether the current item inside it is annotated.
+struct Bracket<'a> {
+    string: &'a str,
+    annotated: bool,
+}
+
+/// Return the spacing between the tokens `prev` and `tok` on the same line.
+fn spacing(prev: &Token, prev_unary: bool, tok: &Token, brackets: &[Bracket]) -> &'static str {
+    let innermost = brackets.last().map(|b| b.string);
+
+    match (prev.string, tok.string) {
+        (_, ")") | (_, "]") | (_, "}") | (_, ",") | (_, ".") | (_, ":") => "",
+        ("(", _) | ("[", _) | ("{", _) | (".", _) | ("@", _) => "",
+        (":", _) if innermost == Some("[") => "",
+        (_, "(") | (_, "[") => match prev.typ {
+            TokenType::NAME if !is_keyword(prev, KEYWORDS) => "",
+            TokenType::STRING => "",
+            _ if prev.string == ")" || prev.string == "]" => "",
+            _ => " ",
+        },
+        ("=", _) | (_, "=") if innermost == Some("(") && !brackets.last().unwrap().annotated => "",
+        _ if prev_unary => "",
+        _ => " ",
+    }
+}
+
+/// Accumulates formatted lines.
+struct Formatter {
+    out: String,
+}
+
+impl Formatter {
+    fn comment(&mut self, text: &str, level: usize) {
+        self.out.push_str(&INDENT.repeat(level));
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    fn code(&mut self, tokens: &[&Token], level: usize) {
+        let mut line = String::new();
+        let mut line_started = false;
+        let mut brackets: Vec<Bracket> = vec![];
+        let mut prev: Option<&Token> = None;
+        let mut prev_unary = false;
+
+        for tok in tokens {
+            match tok.typ {
+                TokenType::NL => {
+                    self.out.push_str(line.trim_end());
+                    self.out.push('\n');
+                    line.clear();
+                    line_started = false;
+                    prev = None;
+                    continue;
+                }
+                TokenType::COMMENT => {
+                    if line_started {
+                        line.push_str("  ");
+                    } else {
+                        line.push_str(&INDENT.repeat(level + brackets.len()));
+                        line_started = true;
+                    }
+                    line.push_str(tok.string.trim_end());
+                    continue;
+                }
+                _ => {}
+            }
+
+            let closing = [")", "]", "}"].contains(&tok.string);
+            if !line_started {
+                let depth = brackets.len()
+                    - if closing && !brackets.is_empty() {
+                        1
+                    } else {
+                        0
+                    };
+                line.push_str(&INDENT.repeat(level + depth));
+                line_started = true;
+            } else if let Some(prev) = prev {
+                line.push_str(spacing(prev, prev_unary, tok, &brackets));
+            }
+            line.push_str(tok.string);
+
+            match tok.string {
+                "(" | "[" | "{" => brackets.push(Bracket {
+                    string: tok.string,
+                    annotated: false,
+                }),
+                ")" | "]" | "}" => {
+                    brackets.pop();
+                }
+                "," => {
+                    if let Some(bracket) = brackets.last_mut() {
+                        bracket.annotated = false;
+                    }
+                }
+                ":" => {
+                    if let Some(bracket) = brackets.last_mut() {
+                        bracket.annotated = true;
+                    }
+                }
+                _ => {}
+            }
+
+            prev_unary = is_unary(tok, prev);
+            prev = Some(tok);
+        }
+
+        self.out.push_str(line.trim_end());
+        self.out.push('\n');
+    }
+}
+
+/// Return the number of blank lines to place before the line at index `i` of
+/// `lines`.
+fn blank_lines_before(lines: &[Line], i: usize) -> usize {
+    let line = &lines[i];
+    let blanks = lines[..i]
+        .iter()
+        .rev()
+        .take_while(|l| matches!(l, Line::Blank))
+        .count();
+    let mut previous = lines[..i]
+        .iter()
+        .rev()
+        .filter(|l| !matches!(l, Line::Blank));
+
+    let prev = match previous.next() {
+        Some(prev) => prev,
+        None => return 0,
+    };
+    if prev.opens_block() || prev.is_decorator() {
+        return 0;
+    }
+    if line.level() > 0 {
+        return blanks.min(1);
+    }
+
+    // Comments directly above a top-level definition belong to it
+    let starts_definition = lines[i..]
+        .iter()
+        .find(|l| !matches!(l, Line::Comment { level: 0, .. }))
+        .is_some_and(Line::is_definition);
+    let attached = matches!(prev, Line::Comment { level: 0, .. }) && blanks == 0;
+
+    // The previous top-level line that is not a comment
+    let after_definition = lines[..i]
+        .iter()
+        .rev()
+        .find(|l| matches!(l, Line::Code { level: 0, .. }))
+        .is_some_and(Line::is_definition);
+
+    if starts_definition {
+        if attached {
+            0
+        } else {
+            1
+        }
+    } else if after_definition {
+        1
+    } else {
+        blanks.min(1)
+    }
+}
+
+/// Return the significant content of the tokens `tokens` for checking that
+/// formatting preserves the meaning of source code.
+fn significant<'a>(tokens: &[Token<'a>]) -> Vec<(TokenType, &'a str)> {
+    tokens
+        .iter()
+        .filter(|t| t.typ != TokenType::NL)
+        .map(|t| match t.typ {
+            TokenType::COMMENT => (t.typ, t.string.trim_end()),
+            TokenType::INDENT | TokenType::DEDENT | TokenType::NEWLINE => (t.typ, ""),
+            _ => (t.typ, t.string),
+        })
+        .collect()
+}
+
+/// Format the Vyper source code in `source`.
+pub fn format_source(source: &str) -> Result<String, FormatError> {
+    let tokens = tokenize(source)?;
+    let lines = split_lines(&tokens)?;
+    let mut formatter = Formatter { out: String::new() };
+
+    for (i, line) in lines.iter().enumerate() {
+        match line {
+            Line::Blank => continue,
+            Line::Comment { text, level } => {
+                formatter
+                    .out
+                    .push_str(&"\n".repeat(blank_lines_before(&lines, i)));
+                formatter.comment(text, *level);
+            }
+            Line::Code { tokens, level } => {
+                formatter
+                    .out
+                    .push_str(&"\n".repeat(blank_lines_before(&lines, i)));
+                formatter.code(tokens, *level);
+            }
+        }
+    }
+
+    let formatted = formatter.out;
+    if significant(&tokenize(&formatted)?) != significant(&tokens) {
+        return Err(FormatError {
+            msg: "formatting would change the meaning of the source",
+            offset: 0,
+        });
+    }
+
+    Ok(formatted)
+}
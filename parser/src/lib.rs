@@ -1,8 +1,17 @@
 extern crate regex;
 
 pub mod ast;
+pub mod cst;
 pub mod errors;
+pub mod formatter;
+pub mod line_index;
+pub mod node_id;
+pub mod owned_ast;
 pub mod parsers;
+pub mod source_map;
 pub mod span;
 pub mod string_utils;
+pub mod symbol;
 pub mod tokenizer;
+pub mod unparse;
+pub mod visit;
@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+/// The unit in which the columns of a line are counted.  Language server
+/// clients count in UTF-16 code units by default.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+pub enum ColumnUnit {
+    Byte,
+    Char,
+    Utf16,
+}
+
+/// A position in a source file specified by a 0-indexed line number and a
+/// 0-indexed column in some unit.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct LineCol {
+    pub line: usize,
+    pub col: usize,
+}
+
+impl LineCol {
+    pub fn new(line: usize, col: usize) -> Self {
+        LineCol { line, col }
+    }
+}
+
+/// A character that is encoded with more than one byte.
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct WideChar {
+    /// The byte offset of the character into its line
+    start: usize,
+    len_utf8: usize,
+    len_utf16: usize,
+}
+
+impl WideChar {
+    fn len(&self, unit: ColumnUnit) -> usize {
+        match unit {
+            ColumnUnit::Byte => self.len_utf8,
+            ColumnUnit::Char => 1,
+            ColumnUnit::Utf16 => self.len_utf16,
+        }
+    }
+}
+
+/// Converts between byte offsets in a string and line and column positions.
+/// An index is built once per string and answers lookups in logarithmic time
+/// using the offsets at which lines start.  Lines end after each `\n`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct LineIndex {
+    /// The byte offsets at which lines start
+    line_starts: Vec<usize>,
+    /// The multi-byte characters of each line that contains any
+    wide_chars: HashMap<usize, Vec<WideChar>>,
+    /// The length of the indexed string in bytes
+    len: usize,
+}
+
+impl LineIndex {
+    /// Create an index of the lines in `input`.
+    pub fn new(input: &str) -> Self {
+        let mut line_starts = vec![0];
+        let mut wide_chars: HashMap<usize, Vec<WideChar>> = HashMap::new();
+
+        for (offset, chr) in input.char_indices() {
+            if chr == '\n' {
+                line_starts.push(offset + 1);
+            } else if chr.len_utf8() > 1 {
+                let line = line_starts.len() - 1;
+
+                wide_chars.entry(line).or_default().push(WideChar {
+                    start: offset - line_starts[line],
+                    len_utf8: chr.len_utf8(),
+                    len_utf16: chr.len_utf16(),
+                });
+            }
+        }
+
+        LineIndex {
+            line_starts,
+            wide_chars,
+            len: input.len(),
+        }
+    }
+
+    /// Return the number of lines.  A string ending with a newline has an
+    /// empty last line.
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Return the byte offset at which the line `line` starts.
+    pub fn line_start(&self, line: usize) -> Option<usize> {
+        self.line_starts.get(line).copied()
+    }
+
+    /// Return the byte offset at which the line `line` ends, excluding its
+    /// newline.
+    pub fn line_end(&self, line: usize) -> Option<usize> {
+        self.line_start(line)?;
+
+        Some(
+            self.line_starts
+                .get(line + 1)
+                .map_or(self.len, |next| next - 1),
+        )
+    }
+
+    /// Return the position of the byte offset `offset` with a column in the
+    /// unit `unit`.  Offsets past the end of the string or inside a character
+    /// have no position.
+    pub fn line_col(&self, offset: usize, unit: ColumnUnit) -> Option<LineCol> {
+        if offset > self.len {
+            return None;
+        }
+
+        let line = self.line_starts.partition_point(|start| *start <= offset) - 1;
+        let mut col = offset - self.line_starts[line];
+
+        for chr in self.wide_chars.get(&line).into_iter().flatten() {
+            if chr.start >= offset - self.line_starts[line] {
+                break;
+            }
+            if offset - self.line_starts[line] < chr.start + chr.len_utf8 {
+                return None;
+            }
+            col -= chr.len_utf8 - chr.len(unit);
+        }
+
+        Some(LineCol::new(line, col))
+    }
+
+    /// Return the byte offset of the position `pos` with a column in the unit
+    /// `unit`.  Positions past the end of a line or inside a character have no
+    /// offset.
+    pub fn offset(&self, pos: LineCol, unit: ColumnUnit) -> Option<usize> {
+        let start = self.line_start(pos.line)?;
+        let mut bytes = pos.col;
+
+        for chr in self.wide_chars.get(&pos.line).into_iter().flatten() {
+            // The column of the character in the unit
+            let chr_col = chr.start + pos.col - bytes;
+            if chr_col >= pos.col {
+                break;
+            }
+            if pos.col < chr_col + chr.len(unit) {
+                return None;
+            }
+            bytes += chr.len_utf8 - chr.len(unit);
+        }
+
+        if start + bytes > self.line_end(pos.line)? {
+            return None;
+        }
+
+        Some(start + bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lines() {
+        let index = LineIndex::new("ab\n\ncd");
+        assert_eq!(index.line_count(), 3);
+        assert_eq!(index.line_start(1), Some(3));
+        assert_eq!(index.line_end(0), Some(2));
+        assert_eq!(index.line_end(1), Some(3));
+        assert_eq!(index.line_end(2), Some(6));
+        assert_eq!(index.line_start(3), None);
+        assert_eq!(index.line_end(3), None);
+
+        let index = LineIndex::new("ab\n");
+        assert_eq!(index.line_count(), 2);
+        assert_eq!(index.line_end(1), Some(3));
+
+        let index = LineIndex::new("");
+        assert_eq!(index.line_count(), 1);
+        assert_eq!(
+            index.line_col(0, ColumnUnit::Byte),
+            Some(LineCol::new(0, 0))
+        );
+        assert_eq!(index.offset(LineCol::new(0, 0), ColumnUnit::Byte), Some(0));
+    }
+
+    #[test]
+    fn test_ascii() {
+        let input = "event A:\n    x: int128\n";
+        let index = LineIndex::new(input);
+
+        for unit in &[ColumnUnit::Byte, ColumnUnit::Char, ColumnUnit::Utf16] {
+            for offset in 0..=input.len() {
+                let pos = index.line_col(offset, *unit).unwrap();
+                assert_eq!(index.offset(pos, *unit), Some(offset));
+            }
+        }
+
+        assert_eq!(
+            index.line_col(8, ColumnUnit::Byte),
+            Some(LineCol::new(0, 8))
+        );
+        assert_eq!(
+            index.line_col(9, ColumnUnit::Byte),
+            Some(LineCol::new(1, 0))
+        );
+        assert_eq!(
+            index.line_col(23, ColumnUnit::Byte),
+            Some(LineCol::new(2, 0))
+        );
+        assert_eq!(index.line_col(24, ColumnUnit::Byte), None);
+        assert_eq!(index.offset(LineCol::new(0, 9), ColumnUnit::Byte), None);
+        assert_eq!(index.offset(LineCol::new(3, 0), ColumnUnit::Byte), None);
+    }
+
+    #[test]
+    fn test_wide_chars() {
+        // "é" is 2 bytes and 1 UTF-16 code unit, "𝔸" is 4 bytes and 2 UTF-16
+        // code units
+        let input = "x\n# é𝔸y\nz";
+        let index = LineIndex::new(input);
+        let y = input.find('y').unwrap();
+
+        assert_eq!(
+            index.line_col(y, ColumnUnit::Byte),
+            Some(LineCol::new(1, 8))
+        );
+        assert_eq!(
+            index.line_col(y, ColumnUnit::Char),
+            Some(LineCol::new(1, 4))
+        );
+        assert_eq!(
+            index.line_col(y, ColumnUnit::Utf16),
+            Some(LineCol::new(1, 5))
+        );
+
+        for unit in &[ColumnUnit::Byte, ColumnUnit::Char, ColumnUnit::Utf16] {
+            for (offset, _) in input.char_indices() {
+                let pos = index.line_col(offset, *unit).unwrap();
+                assert_eq!(index.offset(pos, *unit), Some(offset));
+            }
+        }
+
+        // Offsets and columns inside characters
+        assert_eq!(index.line_col(5, ColumnUnit::Byte), None);
+        assert_eq!(index.offset(LineCol::new(1, 5), ColumnUnit::Byte), None);
+        assert_eq!(index.offset(LineCol::new(1, 4), ColumnUnit::Utf16), None);
+        assert_eq!(
+            index.offset(LineCol::new(1, 3), ColumnUnit::Utf16),
+            Some(input.find('𝔸').unwrap())
+        );
+        assert_eq!(index.offset(LineCol::new(1, 7), ColumnUnit::Char), None);
+    }
+}
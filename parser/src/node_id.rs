@@ -0,0 +1,219 @@
+//! Node identities and side tables.
+//!
+//! Every `Spanned` node is given a `NodeId` when it is created.  Ids are unique
+//! within a process, which allows later passes to attach information such as
+//! types or constant values to nodes in a `NodeMap` without changing the AST.
+//! A `ParentMap` records the parent of each node in a module for navigating
+//! upwards from a node.
+
+use std::collections::HashMap;
+use std::sync::atomic::{
+    AtomicU32,
+    Ordering,
+};
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::ast::{
+    ConstExpr,
+    EventField,
+    Expr,
+    FuncArg,
+    FuncStmt,
+    Module,
+    ModuleStmt,
+    TypeDesc,
+};
+use crate::span::{
+    Span,
+    Spanned,
+};
+use crate::visit::{
+    walk_const_expr,
+    walk_event_field,
+    walk_expr,
+    walk_func_arg,
+    walk_func_stmt,
+    walk_module,
+    walk_module_stmt,
+    walk_type_desc,
+    Visitor,
+};
+
+/// The identity of an AST node.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Copy, Clone)]
+pub struct NodeId(u32);
+
+impl NodeId {
+    /// Return an id that has not been returned before.
+    pub fn fresh() -> Self {
+        static NEXT: AtomicU32 = AtomicU32::new(0);
+
+        NodeId(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// A side table holding information about AST nodes.
+pub type NodeMap<T> = HashMap<NodeId, T>;
+
+/// The kinds of AST nodes.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum NodeKind {
+    Module,
+    EventDef,
+    FieldDef,
+    FuncDef,
+    EventField,
+    FuncArg,
+    FuncStmt,
+    TypeDesc,
+    ConstExpr,
+    Expr,
+}
+
+/// The parents, kinds and spans of the nodes in an AST.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct ParentMap {
+    parents: NodeMap<NodeId>,
+    nodes: NodeMap<(NodeKind, Span)>,
+    /// The nodes in pre-order
+    order: Vec<NodeId>,
+}
+
+impl ParentMap {
+    /// Record the nodes of the module `module`.
+    pub fn from_module(module: &Spanned<Module>) -> Self {
+        let mut builder = ParentMapBuilder::default();
+        builder.visit_module(module);
+
+        builder.map
+    }
+
+    /// Record the nodes of the constant expression `expr`.
+    pub fn from_const_expr(expr: &Spanned<ConstExpr>) -> Self {
+        let mut builder = ParentMapBuilder::default();
+        builder.visit_const_expr(expr);
+
+        builder.map
+    }
+
+    /// Return the parent of the node with id `id`.  Root nodes have no parent.
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.parents.get(&id).copied()
+    }
+
+    /// Return the ancestors of the node with id `id` from its parent up.
+    pub fn ancestors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        std::iter::successors(self.parent(id), move |id| self.parent(*id))
+    }
+
+    pub fn kind(&self, id: NodeId) -> Option<NodeKind> {
+        self.nodes.get(&id).map(|(kind, _)| *kind)
+    }
+
+    pub fn span(&self, id: NodeId) -> Option<Span> {
+        self.nodes.get(&id).map(|(_, span)| *span)
+    }
+
+    /// Return the innermost node whose span contains the byte offset `offset`.
+    /// Spans contain their end so that a cursor right after a node is inside
+    /// it.
+    pub fn node_at(&self, offset: usize) -> Option<NodeId> {
+        // Children follow their parents in pre-order, so the last node
+        // containing the offset is the innermost
+        self.order
+            .iter()
+            .rev()
+            .find(|id| {
+                let span = self.nodes[id].1;
+                span.start <= offset && offset <= span.end
+            })
+            .copied()
+    }
+
+    /// Return the innermost node of kind `kind` containing the byte offset
+    /// `offset`.
+    pub fn enclosing(&self, offset: usize, kind: NodeKind) -> Option<NodeId> {
+        let node = self.node_at(offset)?;
+
+        std::iter::once(node)
+            .chain(self.ancestors(node))
+            .find(|id| self.kind(*id) == Some(kind))
+    }
+}
+
+#[derive(Default)]
+struct ParentMapBuilder {
+    map: ParentMap,
+    /// The ids of the nodes being visited from the root down
+    stack: Vec<NodeId>,
+}
+
+impl ParentMapBuilder {
+    /// Record a node and visit its children with `walk`.
+    fn record(&mut self, id: NodeId, kind: NodeKind, span: Span, walk: impl FnOnce(&mut Self)) {
+        if let Some(parent) = self.stack.last() {
+            self.map.parents.insert(id, *parent);
+        }
+        self.map.nodes.insert(id, (kind, span));
+        self.map.order.push(id);
+
+        self.stack.push(id);
+        walk(self);
+        self.stack.pop();
+    }
+}
+
+impl<'a> Visitor<'a> for ParentMapBuilder {
+    fn visit_module(&mut self, module: &Spanned<Module<'a>>) {
+        self.record(module.id, NodeKind::Module, module.span, |b| {
+            walk_module(b, module)
+        });
+    }
+
+    fn visit_module_stmt(&mut self, stmt: &Spanned<ModuleStmt<'a>>) {
+        let kind = match stmt.node {
+            ModuleStmt::EventDef { .. } => NodeKind::EventDef,
+            ModuleStmt::FieldDef { .. } => NodeKind::FieldDef,
+            ModuleStmt::FuncDef { .. } => NodeKind::FuncDef,
+        };
+        self.record(stmt.id, kind, stmt.span, |b| walk_module_stmt(b, stmt));
+    }
+
+    fn visit_event_field(&mut self, field: &Spanned<EventField<'a>>) {
+        self.record(field.id, NodeKind::EventField, field.span, |b| {
+            walk_event_field(b, field)
+        });
+    }
+
+    fn visit_type_desc(&mut self, typ: &Spanned<TypeDesc<'a>>) {
+        self.record(typ.id, NodeKind::TypeDesc, typ.span, |b| {
+            walk_type_desc(b, typ)
+        });
+    }
+
+    fn visit_const_expr(&mut self, expr: &Spanned<ConstExpr<'a>>) {
+        self.record(expr.id, NodeKind::ConstExpr, expr.span, |b| {
+            walk_const_expr(b, expr)
+        });
+    }
+
+    fn visit_func_arg(&mut self, arg: &Spanned<FuncArg<'a>>) {
+        self.record(arg.id, NodeKind::FuncArg, arg.span, |b| {
+            walk_func_arg(b, arg)
+        });
+    }
+
+    fn visit_func_stmt(&mut self, stmt: &Spanned<FuncStmt<'a>>) {
+        self.record(stmt.id, NodeKind::FuncStmt, stmt.span, |b| {
+            walk_func_stmt(b, stmt)
+        });
+    }
+
+    fn visit_expr(&mut self, expr: &Spanned<Expr<'a>>) {
+        self.record(expr.id, NodeKind::Expr, expr.span, |b| walk_expr(b, expr));
+    }
+}
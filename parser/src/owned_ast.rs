@@ -0,0 +1,670 @@
+//! An AST that owns its strings.
+//!
+//! The AST produced by the parsers borrows its names from the source code and
+//! can therefore not outlive it.  The types in this module mirror those in
+//! `ast` with interned names and owned literals so that parsed modules can be
+//! cached, sent across threads or kept after their source has been dropped.  A
+//! borrowed AST is converted with `From` and an owned AST is viewed as a
+//! borrowed one with `as_borrowed`, which allows passing it to code that works
+//! on the borrowed AST.
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::ast;
+use crate::ast::{
+    BoolOperator,
+    CompOperator,
+    Operator,
+    UnaryOp,
+};
+use crate::span::Spanned;
+use crate::symbol::Symbol;
+
+/// Apply `f` to the node of `spanned` and keep its span and id.
+fn map_spanned<'a, T, U>(spanned: &'a Spanned<T>, f: impl FnOnce(&'a T) -> U) -> Spanned<U> {
+    Spanned {
+        node: f(&spanned.node),
+        span: spanned.span,
+        id: spanned.id,
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Module {
+    pub body: Vec<Spanned<ModuleStmt>>,
+}
+
+impl From<&ast::Module<'_>> for Module {
+    fn from(module: &ast::Module<'_>) -> Self {
+        Module {
+            body: module
+                .body
+                .iter()
+                .map(|stmt| map_spanned(stmt, ModuleStmt::from))
+                .collect(),
+        }
+    }
+}
+
+impl Module {
+    pub fn as_borrowed(&self) -> ast::Module<'_> {
+        ast::Module {
+            body: self
+                .body
+                .iter()
+                .map(|stmt| map_spanned(stmt, ModuleStmt::as_borrowed))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub enum ModuleStmt {
+    EventDef {
+        name: Symbol,
+        fields: Vec<Spanned<EventField>>,
+    },
+    FieldDef {
+        name: Symbol,
+        typ: Spanned<TypeDesc>,
+        value: Option<Spanned<ConstExpr>>,
+    },
+    FuncDef {
+        decorators: Vec<Spanned<Symbol>>,
+        name: Symbol,
+        args: Vec<Spanned<FuncArg>>,
+        return_type: Option<Spanned<TypeDesc>>,
+        body: Vec<Spanned<FuncStmt>>,
+    },
+}
+
+impl From<&ast::ModuleStmt<'_>> for ModuleStmt {
+    fn from(stmt: &ast::ModuleStmt<'_>) -> Self {
+        match stmt {
+            ast::ModuleStmt::EventDef { name, fields } => ModuleStmt::EventDef {
+                name: Symbol::intern(name),
+                fields: fields
+                    .iter()
+                    .map(|field| map_spanned(field, EventField::from))
+                    .collect(),
+            },
+            ast::ModuleStmt::FieldDef { name, typ, value } => ModuleStmt::FieldDef {
+                name: Symbol::intern(name),
+                typ: map_spanned(typ, TypeDesc::from),
+                value: value
+                    .as_ref()
+                    .map(|value| map_spanned(value, ConstExpr::from)),
+            },
+            ast::ModuleStmt::FuncDef {
+                decorators,
+                name,
+                args,
+                return_type,
+                body,
+            } => ModuleStmt::FuncDef {
+                decorators: decorators
+                    .iter()
+                    .map(|dec| map_spanned(dec, |name| Symbol::intern(name)))
+                    .collect(),
+                name: Symbol::intern(name),
+                args: args
+                    .iter()
+                    .map(|arg| map_spanned(arg, FuncArg::from))
+                    .collect(),
+                return_type: return_type
+                    .as_ref()
+                    .map(|typ| map_spanned(typ, TypeDesc::from)),
+                body: body
+                    .iter()
+                    .map(|stmt| map_spanned(stmt, FuncStmt::from))
+                    .collect(),
+            },
+        }
+    }
+}
+
+impl ModuleStmt {
+    pub fn as_borrowed(&self) -> ast::ModuleStmt<'_> {
+        match self {
+            ModuleStmt::EventDef { name, fields } => ast::ModuleStmt::EventDef {
+                name: name.as_str(),
+                fields: fields
+                    .iter()
+                    .map(|field| map_spanned(field, EventField::as_borrowed))
+                    .collect(),
+            },
+            ModuleStmt::FieldDef { name, typ, value } => ast::ModuleStmt::FieldDef {
+                name: name.as_str(),
+                typ: map_spanned(typ, TypeDesc::as_borrowed),
+                value: value
+                    .as_ref()
+                    .map(|value| map_spanned(value, ConstExpr::as_borrowed)),
+            },
+            ModuleStmt::FuncDef {
+                decorators,
+                name,
+                args,
+                return_type,
+                body,
+            } => ast::ModuleStmt::FuncDef {
+                decorators: decorators
+                    .iter()
+                    .map(|dec| map_spanned(dec, |name| name.as_str()))
+                    .collect(),
+                name: name.as_str(),
+                args: args
+                    .iter()
+                    .map(|arg| map_spanned(arg, FuncArg::as_borrowed))
+                    .collect(),
+                return_type: return_type
+                    .as_ref()
+                    .map(|typ| map_spanned(typ, TypeDesc::as_borrowed)),
+                body: body
+                    .iter()
+                    .map(|stmt| map_spanned(stmt, FuncStmt::as_borrowed))
+                    .collect(),
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct TypeDesc {
+    pub base: Symbol,
+    pub dimensions: Vec<Spanned<ConstExpr>>,
+    pub annotations: Vec<Symbol>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub params: Vec<Spanned<TypeDesc>>,
+}
+
+impl From<&ast::TypeDesc<'_>> for TypeDesc {
+    fn from(typ: &ast::TypeDesc<'_>) -> Self {
+        TypeDesc {
+            base: Symbol::intern(typ.base),
+            dimensions: typ
+                .dimensions
+                .iter()
+                .map(|dim| map_spanned(dim, ConstExpr::from))
+                .collect(),
+            annotations: typ.annotations.iter().map(|a| Symbol::intern(a)).collect(),
+            params: typ
+                .params
+                .iter()
+                .map(|param| map_spanned(param, TypeDesc::from))
+                .collect(),
+        }
+    }
+}
+
+impl TypeDesc {
+    pub fn as_borrowed(&self) -> ast::TypeDesc<'_> {
+        ast::TypeDesc {
+            base: self.base.as_str(),
+            dimensions: self
+                .dimensions
+                .iter()
+                .map(|dim| map_spanned(dim, ConstExpr::as_borrowed))
+                .collect(),
+            annotations: self.annotations.iter().map(|a| a.as_str()).collect(),
+            params: self
+                .params
+                .iter()
+                .map(|param| map_spanned(param, TypeDesc::as_borrowed))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct EventField {
+    pub name: Symbol,
+    pub typ: Spanned<TypeDesc>,
+}
+
+impl From<&ast::EventField<'_>> for EventField {
+    fn from(field: &ast::EventField<'_>) -> Self {
+        EventField {
+            name: Symbol::intern(field.name),
+            typ: map_spanned(&field.typ, TypeDesc::from),
+        }
+    }
+}
+
+impl EventField {
+    pub fn as_borrowed(&self) -> ast::EventField<'_> {
+        ast::EventField {
+            name: self.name.as_str(),
+            typ: map_spanned(&self.typ, TypeDesc::as_borrowed),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub enum ConstExpr {
+    BinOp {
+        left: Box<Spanned<ConstExpr>>,
+        op: Operator,
+        right: Box<Spanned<ConstExpr>>,
+    },
+    UnaryOp {
+        op: UnaryOp,
+        operand: Box<Spanned<ConstExpr>>,
+    },
+    Name {
+        name: Symbol,
+    },
+    Num {
+        num: String,
+    },
+    Call {
+        func: Symbol,
+        args: Vec<Spanned<ConstExpr>>,
+    },
+}
+
+impl From<&ast::ConstExpr<'_>> for ConstExpr {
+    fn from(expr: &ast::ConstExpr<'_>) -> Self {
+        let boxed = |expr: &Spanned<ast::ConstExpr>| Box::new(map_spanned(expr, ConstExpr::from));
+
+        match expr {
+            ast::ConstExpr::BinOp { left, op, right } => ConstExpr::BinOp {
+                left: boxed(left),
+                op: *op,
+                right: boxed(right),
+            },
+            ast::ConstExpr::UnaryOp { op, operand } => ConstExpr::UnaryOp {
+                op: *op,
+                operand: boxed(operand),
+            },
+            ast::ConstExpr::Name { name } => ConstExpr::Name {
+                name: Symbol::intern(name),
+            },
+            ast::ConstExpr::Num { num } => ConstExpr::Num {
+                num: num.to_string(),
+            },
+            ast::ConstExpr::Call { func, args } => ConstExpr::Call {
+                func: Symbol::intern(func),
+                args: args
+                    .iter()
+                    .map(|arg| map_spanned(arg, ConstExpr::from))
+                    .collect(),
+            },
+        }
+    }
+}
+
+impl ConstExpr {
+    pub fn as_borrowed(&self) -> ast::ConstExpr<'_> {
+        fn boxed(expr: &Spanned<ConstExpr>) -> Box<Spanned<ast::ConstExpr<'_>>> {
+            Box::new(map_spanned(expr, ConstExpr::as_borrowed))
+        }
+
+        match self {
+            ConstExpr::BinOp { left, op, right } => ast::ConstExpr::BinOp {
+                left: boxed(left),
+                op: *op,
+                right: boxed(right),
+            },
+            ConstExpr::UnaryOp { op, operand } => ast::ConstExpr::UnaryOp {
+                op: *op,
+                operand: boxed(operand),
+            },
+            ConstExpr::Name { name } => ast::ConstExpr::Name {
+                name: name.as_str(),
+            },
+            ConstExpr::Num { num } => ast::ConstExpr::Num { num },
+            ConstExpr::Call { func, args } => ast::ConstExpr::Call {
+                func: func.as_str(),
+                args: args
+                    .iter()
+                    .map(|arg| map_spanned(arg, ConstExpr::as_borrowed))
+                    .collect(),
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct FuncArg {
+    pub name: Symbol,
+    pub typ: Spanned<TypeDesc>,
+}
+
+impl From<&ast::FuncArg<'_>> for FuncArg {
+    fn from(arg: &ast::FuncArg<'_>) -> Self {
+        FuncArg {
+            name: Symbol::intern(arg.name),
+            typ: map_spanned(&arg.typ, TypeDesc::from),
+        }
+    }
+}
+
+impl FuncArg {
+    pub fn as_borrowed(&self) -> ast::FuncArg<'_> {
+        ast::FuncArg {
+            name: self.name.as_str(),
+            typ: map_spanned(&self.typ, TypeDesc::as_borrowed),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub enum FuncStmt {
+    Return {
+        value: Option<Spanned<Expr>>,
+    },
+    VarDecl {
+        name: Symbol,
+        typ: Spanned<TypeDesc>,
+        value: Option<Spanned<Expr>>,
+    },
+    Assign {
+        target: Spanned<Expr>,
+        value: Spanned<Expr>,
+    },
+    AugAssign {
+        target: Spanned<Expr>,
+        op: Operator,
+        value: Spanned<Expr>,
+    },
+    For {
+        target: Symbol,
+        iter: Spanned<Expr>,
+        body: Vec<Spanned<FuncStmt>>,
+    },
+    If {
+        test: Spanned<Expr>,
+        body: Vec<Spanned<FuncStmt>>,
+        or_else: Vec<Spanned<FuncStmt>>,
+    },
+    Assert {
+        test: Spanned<Expr>,
+        msg: Option<Spanned<Expr>>,
+    },
+    Raise {
+        msg: Option<Spanned<Expr>>,
+    },
+    Log {
+        event: Symbol,
+        args: Vec<Spanned<Expr>>,
+    },
+    Expr {
+        value: Spanned<Expr>,
+    },
+    Pass,
+    Break,
+    Continue,
+}
+
+impl From<&ast::FuncStmt<'_>> for FuncStmt {
+    fn from(stmt: &ast::FuncStmt<'_>) -> Self {
+        let expr = |expr: &Spanned<ast::Expr>| map_spanned(expr, Expr::from);
+        let opt_expr = |expr: &Option<Spanned<ast::Expr>>| expr.as_ref().map(expr_owned);
+        let stmts = |body: &[Spanned<ast::FuncStmt>]| {
+            body.iter()
+                .map(|stmt| map_spanned(stmt, FuncStmt::from))
+                .collect()
+        };
+
+        match stmt {
+            ast::FuncStmt::Return { value } => FuncStmt::Return {
+                value: opt_expr(value),
+            },
+            ast::FuncStmt::VarDecl { name, typ, value } => FuncStmt::VarDecl {
+                name: Symbol::intern(name),
+                typ: map_spanned(typ, TypeDesc::from),
+                value: opt_expr(value),
+            },
+            ast::FuncStmt::Assign { target, value } => FuncStmt::Assign {
+                target: expr(target),
+                value: expr(value),
+            },
+            ast::FuncStmt::AugAssign { target, op, value } => FuncStmt::AugAssign {
+                target: expr(target),
+                op: *op,
+                value: expr(value),
+            },
+            ast::FuncStmt::For { target, iter, body } => FuncStmt::For {
+                target: Symbol::intern(target),
+                iter: expr(iter),
+                body: stmts(body),
+            },
+            ast::FuncStmt::If {
+                test,
+                body,
+                or_else,
+            } => FuncStmt::If {
+                test: expr(test),
+                body: stmts(body),
+                or_else: stmts(or_else),
+            },
+            ast::FuncStmt::Assert { test, msg } => FuncStmt::Assert {
+                test: expr(test),
+                msg: opt_expr(msg),
+            },
+            ast::FuncStmt::Raise { msg } => FuncStmt::Raise { msg: opt_expr(msg) },
+            ast::FuncStmt::Log { event, args } => FuncStmt::Log {
+                event: Symbol::intern(event),
+                args: args.iter().map(expr_owned).collect(),
+            },
+            ast::FuncStmt::Expr { value } => FuncStmt::Expr { value: expr(value) },
+            ast::FuncStmt::Pass => FuncStmt::Pass,
+            ast::FuncStmt::Break => FuncStmt::Break,
+            ast::FuncStmt::Continue => FuncStmt::Continue,
+        }
+    }
+}
+
+impl FuncStmt {
+    pub fn as_borrowed(&self) -> ast::FuncStmt<'_> {
+        fn expr(expr: &Spanned<Expr>) -> Spanned<ast::Expr<'_>> {
+            map_spanned(expr, Expr::as_borrowed)
+        }
+        fn opt_expr(expr: &Option<Spanned<Expr>>) -> Option<Spanned<ast::Expr<'_>>> {
+            expr.as_ref().map(|e| map_spanned(e, Expr::as_borrowed))
+        }
+        fn stmts(body: &[Spanned<FuncStmt>]) -> Vec<Spanned<ast::FuncStmt<'_>>> {
+            body.iter()
+                .map(|stmt| map_spanned(stmt, FuncStmt::as_borrowed))
+                .collect()
+        }
+
+        match self {
+            FuncStmt::Return { value } => ast::FuncStmt::Return {
+                value: opt_expr(value),
+            },
+            FuncStmt::VarDecl { name, typ, value } => ast::FuncStmt::VarDecl {
+                name: name.as_str(),
+                typ: map_spanned(typ, TypeDesc::as_borrowed),
+                value: opt_expr(value),
+            },
+            FuncStmt::Assign { target, value } => ast::FuncStmt::Assign {
+                target: expr(target),
+                value: expr(value),
+            },
+            FuncStmt::AugAssign { target, op, value } => ast::FuncStmt::AugAssign {
+                target: expr(target),
+                op: *op,
+                value: expr(value),
+            },
+            FuncStmt::For { target, iter, body } => ast::FuncStmt::For {
+                target: target.as_str(),
+                iter: expr(iter),
+                body: stmts(body),
+            },
+            FuncStmt::If {
+                test,
+                body,
+                or_else,
+            } => ast::FuncStmt::If {
+                test: expr(test),
+                body: stmts(body),
+                or_else: stmts(or_else),
+            },
+            FuncStmt::Assert { test, msg } => ast::FuncStmt::Assert {
+                test: expr(test),
+                msg: opt_expr(msg),
+            },
+            FuncStmt::Raise { msg } => ast::FuncStmt::Raise { msg: opt_expr(msg) },
+            FuncStmt::Log { event, args } => ast::FuncStmt::Log {
+                event: event.as_str(),
+                args: args.iter().map(expr).collect(),
+            },
+            FuncStmt::Expr { value } => ast::FuncStmt::Expr { value: expr(value) },
+            FuncStmt::Pass => ast::FuncStmt::Pass,
+            FuncStmt::Break => ast::FuncStmt::Break,
+            FuncStmt::Continue => ast::FuncStmt::Continue,
+        }
+    }
+}
+
+/// Convert a borrowed expression with its span and id.
+fn expr_owned(expr: &Spanned<ast::Expr>) -> Spanned<Expr> {
+    map_spanned(expr, Expr::from)
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub enum Expr {
+    BoolOp {
+        left: Box<Spanned<Expr>>,
+        op: BoolOperator,
+        right: Box<Spanned<Expr>>,
+    },
+    BinOp {
+        left: Box<Spanned<Expr>>,
+        op: Operator,
+        right: Box<Spanned<Expr>>,
+    },
+    UnaryOp {
+        op: UnaryOp,
+        operand: Box<Spanned<Expr>>,
+    },
+    CompOp {
+        left: Box<Spanned<Expr>>,
+        op: CompOperator,
+        right: Box<Spanned<Expr>>,
+    },
+    Call {
+        func: Box<Spanned<Expr>>,
+        args: Vec<Spanned<Expr>>,
+    },
+    Attribute {
+        value: Box<Spanned<Expr>>,
+        attr: Symbol,
+    },
+    Subscript {
+        value: Box<Spanned<Expr>>,
+        index: Box<Spanned<Expr>>,
+    },
+    Name {
+        name: Symbol,
+    },
+    Num {
+        num: String,
+    },
+    Str {
+        string: String,
+    },
+}
+
+impl From<&ast::Expr<'_>> for Expr {
+    fn from(expr: &ast::Expr<'_>) -> Self {
+        let boxed = |expr: &Spanned<ast::Expr>| Box::new(expr_owned(expr));
+
+        match expr {
+            ast::Expr::BoolOp { left, op, right } => Expr::BoolOp {
+                left: boxed(left),
+                op: *op,
+                right: boxed(right),
+            },
+            ast::Expr::BinOp { left, op, right } => Expr::BinOp {
+                left: boxed(left),
+                op: *op,
+                right: boxed(right),
+            },
+            ast::Expr::UnaryOp { op, operand } => Expr::UnaryOp {
+                op: *op,
+                operand: boxed(operand),
+            },
+            ast::Expr::CompOp { left, op, right } => Expr::CompOp {
+                left: boxed(left),
+                op: *op,
+                right: boxed(right),
+            },
+            ast::Expr::Call { func, args } => Expr::Call {
+                func: boxed(func),
+                args: args.iter().map(expr_owned).collect(),
+            },
+            ast::Expr::Attribute { value, attr } => Expr::Attribute {
+                value: boxed(value),
+                attr: Symbol::intern(attr),
+            },
+            ast::Expr::Subscript { value, index } => Expr::Subscript {
+                value: boxed(value),
+                index: boxed(index),
+            },
+            ast::Expr::Name { name } => Expr::Name {
+                name: Symbol::intern(name),
+            },
+            ast::Expr::Num { num } => Expr::Num {
+                num: num.to_string(),
+            },
+            ast::Expr::Str { string } => Expr::Str {
+                string: string.to_string(),
+            },
+        }
+    }
+}
+
+impl Expr {
+    pub fn as_borrowed(&self) -> ast::Expr<'_> {
+        fn boxed(expr: &Spanned<Expr>) -> Box<Spanned<ast::Expr<'_>>> {
+            Box::new(map_spanned(expr, Expr::as_borrowed))
+        }
+
+        match self {
+            Expr::BoolOp { left, op, right } => ast::Expr::BoolOp {
+                left: boxed(left),
+                op: *op,
+                right: boxed(right),
+            },
+            Expr::BinOp { left, op, right } => ast::Expr::BinOp {
+                left: boxed(left),
+                op: *op,
+                right: boxed(right),
+            },
+            Expr::UnaryOp { op, operand } => ast::Expr::UnaryOp {
+                op: *op,
+                operand: boxed(operand),
+            },
+            Expr::CompOp { left, op, right } => ast::Expr::CompOp {
+                left: boxed(left),
+                op: *op,
+                right: boxed(right),
+            },
+            Expr::Call { func, args } => ast::Expr::Call {
+                func: boxed(func),
+                args: args
+                    .iter()
+                    .map(|arg| map_spanned(arg, Expr::as_borrowed))
+                    .collect(),
+            },
+            Expr::Attribute { value, attr } => ast::Expr::Attribute {
+                value: boxed(value),
+                attr: attr.as_str(),
+            },
+            Expr::Subscript { value, index } => ast::Expr::Subscript {
+                value: boxed(value),
+                index: boxed(index),
+            },
+            Expr::Name { name } => ast::Expr::Name {
+                name: name.as_str(),
+            },
+            Expr::Num { num } => ast::Expr::Num { num },
+            Expr::Str { string } => ast::Expr::Str { string },
+        }
+    }
+}
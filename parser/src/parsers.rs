@@ -3,6 +3,7 @@ use std::convert::TryFrom;
 use nom::branch::alt;
 use nom::combinator::{
     map,
+    opt,
     verify,
 };
 use nom::error::{
@@ -13,10 +14,14 @@ use nom::error::{
 use nom::multi::{
     many0,
     many1,
+    separated_nonempty_list,
 };
 use nom::sequence::{
     pair,
+    preceded,
     separated_pair,
+    terminated,
+    tuple,
 };
 use nom::IResult;
 
@@ -24,6 +29,10 @@ use crate::ast::ModuleStmt::*;
 use crate::ast::*;
 use crate::errors::make_error;
 use crate::span::Spanned;
+use crate::symbol::{
+    sym,
+    Symbol,
+};
 use crate::tokenizer::tokenize::{
     tokenize,
     TokenizeError,
@@ -48,7 +57,7 @@ pub fn get_parse_tokens<'a>(source: &'a str) -> Result<Vec<Token<'a>>, TokenizeE
 }
 
 /// Parse a single token from a token slice.
-pub fn one_token<'a, E>(input: TokenSlice<'a>) -> TokenResult<&Token, E>
+pub fn one_token<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, &'a Token<'a>, E>
 where
     E: ParseError<TokenSlice<'a>>,
 {
@@ -59,7 +68,7 @@ where
 }
 
 /// Parse a token of a specific type from a token slice.
-pub fn token<'a, E>(typ: TokenType) -> impl Fn(TokenSlice<'a>) -> TokenResult<&Token, E>
+pub fn token<'a, E>(typ: TokenType) -> impl Fn(TokenSlice<'a>) -> TokenResult<'a, &'a Token<'a>, E>
 where
     E: ParseError<TokenSlice<'a>>,
 {
@@ -67,23 +76,36 @@ where
 }
 
 /// Parse a name token from a token slice.
-pub fn name_token<'a, E>(input: TokenSlice<'a>) -> TokenResult<&Token, E>
+pub fn name_token<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, &'a Token<'a>, E>
 where
     E: ParseError<TokenSlice<'a>>,
 {
     token(TokenType::NAME)(input)
 }
 
-/// Parse a name token containing a specific string from a token slice.
-pub fn name_string<'a, E>(string: &'a str) -> impl Fn(TokenSlice<'a>) -> TokenResult<&Token, E>
+/// Parse a name token that is not a keyword.
+pub fn identifier<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, &'a Token<'a>, E>
 where
     E: ParseError<TokenSlice<'a>>,
 {
-    verify(name_token, move |t: &Token| t.string == string)
+    verify(name_token, |t: &Token| {
+        !t.sym.is_some_and(|sym| sym.is_keyword())
+    })(input)
+}
+
+/// Parse a name token with a specific interned string, such as a keyword,
+/// from a token slice.
+pub fn name_symbol<'a, E>(
+    symbol: Symbol,
+) -> impl Fn(TokenSlice<'a>) -> TokenResult<'a, &'a Token<'a>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    verify(name_token, move |t: &Token| t.sym == Some(symbol))
 }
 
 /// Parse an op token from a token slice.
-pub fn op_token<'a, E>(input: TokenSlice<'a>) -> TokenResult<&Token, E>
+pub fn op_token<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, &'a Token<'a>, E>
 where
     E: ParseError<TokenSlice<'a>>,
 {
@@ -91,7 +113,9 @@ where
 }
 
 /// Parse an op token containing a specific string from a token slice.
-pub fn op_string<'a, E>(string: &'a str) -> impl Fn(TokenSlice<'a>) -> TokenResult<&Token, E>
+pub fn op_string<'a, E>(
+    string: &'a str,
+) -> impl Fn(TokenSlice<'a>) -> TokenResult<'a, &'a Token<'a>, E>
 where
     E: ParseError<TokenSlice<'a>>,
 {
@@ -99,7 +123,7 @@ where
 }
 
 /// Parse a number token from a token slice.
-pub fn number_token<'a, E>(input: TokenSlice<'a>) -> TokenResult<&Token, E>
+pub fn number_token<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, &'a Token<'a>, E>
 where
     E: ParseError<TokenSlice<'a>>,
 {
@@ -107,7 +131,7 @@ where
 }
 
 /// Parse a string token from a token slice.
-pub fn string_token<'a, E>(input: TokenSlice<'a>) -> TokenResult<&Token, E>
+pub fn string_token<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, &'a Token<'a>, E>
 where
     E: ParseError<TokenSlice<'a>>,
 {
@@ -115,7 +139,7 @@ where
 }
 
 /// Parse an indent token from a token slice.
-pub fn indent_token<'a, E>(input: TokenSlice<'a>) -> TokenResult<&Token, E>
+pub fn indent_token<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, &'a Token<'a>, E>
 where
     E: ParseError<TokenSlice<'a>>,
 {
@@ -123,7 +147,7 @@ where
 }
 
 /// Parse a dedent token from a token slice.
-pub fn dedent_token<'a, E>(input: TokenSlice<'a>) -> TokenResult<&Token, E>
+pub fn dedent_token<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, &'a Token<'a>, E>
 where
     E: ParseError<TokenSlice<'a>>,
 {
@@ -131,7 +155,7 @@ where
 }
 
 /// Parse a grammatically significant newline token from a token slice.
-pub fn newline_token<'a, E>(input: TokenSlice<'a>) -> TokenResult<&Token, E>
+pub fn newline_token<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, &'a Token<'a>, E>
 where
     E: ParseError<TokenSlice<'a>>,
 {
@@ -139,7 +163,7 @@ where
 }
 
 /// Parse an endmarker token from a token slice.
-pub fn endmarker_token<'a, E>(input: TokenSlice<'a>) -> TokenResult<&Token, E>
+pub fn endmarker_token<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, &'a Token<'a>, E>
 where
     E: ParseError<TokenSlice<'a>>,
 {
@@ -147,7 +171,7 @@ where
 }
 
 /// Parse a module definition.
-pub fn file_input<'a, E>(input: TokenSlice<'a>) -> TokenResult<Spanned<Module>, E>
+pub fn file_input<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<Module<'a>>, E>
 where
     E: ParseError<TokenSlice<'a>>,
 {
@@ -155,24 +179,18 @@ where
 }
 
 /// Parse an empty module definition.
-pub fn empty_file_input<'a, E>(input: TokenSlice<'a>) -> TokenResult<Spanned<Module>, E>
+pub fn empty_file_input<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<Module<'a>>, E>
 where
     E: ParseError<TokenSlice<'a>>,
 {
     // ENDMARKER
     let (input, end_tok) = endmarker_token(input)?;
 
-    Ok((
-        input,
-        Spanned {
-            node: Module { body: vec![] },
-            span: end_tok.span,
-        },
-    ))
+    Ok((input, Spanned::new(Module { body: vec![] }, end_tok.span)))
 }
 
 /// Parse a non-empty module definition.
-pub fn non_empty_file_input<'a, E>(input: TokenSlice<'a>) -> TokenResult<Spanned<Module>, E>
+pub fn non_empty_file_input<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<Module<'a>>, E>
 where
     E: ParseError<TokenSlice<'a>>,
 {
@@ -189,32 +207,34 @@ where
         (&first_span, &last_span).into()
     };
 
-    Ok((
-        input,
-        Spanned {
-            node: Module { body },
-            span,
-        },
-    ))
+    Ok((input, Spanned::new(Module { body }, span)))
 }
 
-/// Parse a module statement, such as a contract definition.
-pub fn module_stmt<'a, E>(input: TokenSlice<'a>) -> TokenResult<Spanned<ModuleStmt>, E>
+/// Parse a module statement, such as an event or field definition.
+/// Statements are told apart by their first token so that errors are reported
+/// for the kind of statement being parsed.
+pub fn module_stmt<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<ModuleStmt<'a>>, E>
 where
     E: ParseError<TokenSlice<'a>>,
 {
-    let (input, module_stmt) = context("event definition", event_def)(input)?;
+    let (input, module_stmt) = match input.first() {
+        Some(tok) if tok.sym == Some(sym::EVENT) => context("event definition", event_def)(input)?,
+        Some(tok) if tok.sym == Some(sym::DEF) || tok.string == "@" => {
+            context("function definition", func_def)(input)?
+        }
+        _ => context("field definition", field_def)(input)?,
+    };
 
     Ok((input, module_stmt))
 }
 
 /// Parse an event definition statement.
-pub fn event_def<'a, E>(input: TokenSlice<'a>) -> TokenResult<Spanned<ModuleStmt>, E>
+pub fn event_def<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<ModuleStmt<'a>>, E>
 where
     E: ParseError<TokenSlice<'a>>,
 {
     // "event" name ":" NEWLINE
-    let (input, event_kw) = name_string("event")(input)?;
+    let (input, event_kw) = name_symbol(sym::EVENT)(input)?;
     let (input, name) = name_token(input)?;
     let (input, _) = op_string(":")(input)?;
     let (input, _) = newline_token(input)?;
@@ -229,40 +249,807 @@ where
 
     Ok((
         input,
-        Spanned {
-            node: EventDef {
+        Spanned::new(
+            EventDef {
                 name: name.string,
                 fields,
             },
             span,
-        },
+        ),
     ))
 }
 
 /// Parse an event field definition.
-pub fn event_field<'a, E>(input: TokenSlice<'a>) -> TokenResult<Spanned<EventField>, E>
+pub fn event_field<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<EventField<'a>>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    let (input, name) = name_token(input)?;
+    let (input, _) = op_string(":")(input)?;
+    let (input, typ) = ann_type_desc(input)?;
+    let (input, _) = newline_token(input)?;
+
+    let span = (&name.span, &typ.span).into();
+
+    Ok((
+        input,
+        Spanned::new(
+            EventField {
+                name: name.string,
+                typ,
+            },
+            span,
+        ),
+    ))
+}
+
+/// Parse a field definition with an optional value.
+pub fn field_def<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<ModuleStmt<'a>>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    // NAME ':' ann_type_desc ['=' const_expr] NEWLINE
+    let (input, name) = name_token(input)?;
+    let (input, _) = op_string(":")(input)?;
+    let (input, typ) = ann_type_desc(input)?;
+    let (input, value) = opt(preceded(op_string("="), const_expr))(input)?;
+    let (input, _) = newline_token(input)?;
+
+    let span = match &value {
+        Some(value) => (&name.span, &value.span).into(),
+        None => (&name.span, &typ.span).into(),
+    };
+
+    Ok((
+        input,
+        Spanned::new(
+            FieldDef {
+                name: name.string,
+                typ,
+                value,
+            },
+            span,
+        ),
+    ))
+}
+
+/// Parse a function definition with its decorators.
+pub fn func_def<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<ModuleStmt<'a>>, E>
 where
     E: ParseError<TokenSlice<'a>>,
 {
+    // decorator* 'def' NAME '(' [func_arg (',' func_arg)* [',']] ')'
+    //     ['->' base_type_desc] ':' block
+    let (input, decorators) = many0(decorator)(input)?;
+    let (input, def_kw) = name_symbol(sym::DEF)(input)?;
     let (input, name) = name_token(input)?;
+    let (input, _) = op_string("(")(input)?;
+    let (input, args) = opt(terminated(
+        separated_nonempty_list(op_string(","), func_arg),
+        opt(op_string(",")),
+    ))(input)?;
+    let (input, _) = op_string(")")(input)?;
+    let (input, return_type) = opt(preceded(op_string("->"), base_type_desc))(input)?;
     let (input, _) = op_string(":")(input)?;
-    let (input, typ) = name_token(input)?;
+    let (input, body) = block(input)?;
+
+    let first_span = decorators.first().map_or(def_kw.span, |dec| dec.span);
+    let span = (&first_span, &body.last().unwrap().span).into();
+
+    Ok((
+        input,
+        Spanned::new(
+            FuncDef {
+                decorators,
+                name: name.string,
+                args: args.unwrap_or_default(),
+                return_type,
+                body,
+            },
+            span,
+        ),
+    ))
+}
+
+/// Parse a function decorator such as `@external` on its own line.
+pub fn decorator<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<&'a str>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    // '@' NAME NEWLINE
+    let (input, at) = op_string("@")(input)?;
+    let (input, name) = name_token(input)?;
     let (input, _) = newline_token(input)?;
 
     Ok((
         input,
-        Spanned {
-            node: EventField {
+        Spanned::new(name.string, (&at.span, &name.span).into()),
+    ))
+}
+
+/// Parse a function argument definition.
+pub fn func_arg<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<FuncArg<'a>>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    // NAME ':' base_type_desc
+    let (input, name) = name_token(input)?;
+    let (input, _) = op_string(":")(input)?;
+    let (input, typ) = base_type_desc(input)?;
+
+    let span = (&name.span, &typ.span).into();
+
+    Ok((
+        input,
+        Spanned::new(
+            FuncArg {
                 name: name.string,
-                typ: typ.into(),
+                typ,
             },
-            span: (&name.span, &typ.span).into(),
+            span,
+        ),
+    ))
+}
+
+/// Parse an indented block of statements.
+pub fn block<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Vec<Spanned<FuncStmt<'a>>>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    // NEWLINE INDENT func_stmt+ DEDENT
+    let (input, _) = newline_token(input)?;
+    let (input, _) = indent_token(input)?;
+    let (input, body) = many1(func_stmt)(input)?;
+    let (input, _) = dedent_token(input)?;
+
+    Ok((input, body))
+}
+
+/// Parse a statement in the body of a function.  Statements that start with a
+/// keyword are told apart by it.  Simple statements end with a newline, which
+/// is not part of their span.
+pub fn func_stmt<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<FuncStmt<'a>>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    let keyword = input.first().and_then(|tok| tok.sym);
+
+    match keyword {
+        Some(sym::IF) => return if_stmt(sym::IF)(input),
+        Some(sym::FOR) => return for_stmt(input),
+        _ => {}
+    }
+
+    let (input, stmt) = match keyword {
+        Some(sym::RETURN) => return_stmt(input)?,
+        Some(sym::ASSERT) => assert_stmt(input)?,
+        Some(sym::RAISE) => raise_stmt(input)?,
+        Some(sym::LOG) => log_stmt(input)?,
+        Some(kw @ (sym::PASS | sym::BREAK | sym::CONTINUE)) => {
+            let (input, tok) = name_symbol(kw)(input)?;
+            let stmt = match kw {
+                sym::PASS => FuncStmt::Pass,
+                sym::BREAK => FuncStmt::Break,
+                _ => FuncStmt::Continue,
+            };
+
+            (input, Spanned::new(stmt, tok.span))
+        }
+        _ => alt((var_decl, assign_stmt))(input)?,
+    };
+    let (input, _) = newline_token(input)?;
+
+    Ok((input, stmt))
+}
+
+/// Parse a return statement.
+pub fn return_stmt<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<FuncStmt<'a>>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    // 'return' [expr]
+    let (input, return_kw) = name_symbol(sym::RETURN)(input)?;
+    let (input, value) = opt(expr)(input)?;
+
+    let span = match &value {
+        Some(value) => (&return_kw.span, &value.span).into(),
+        None => return_kw.span,
+    };
+
+    Ok((input, Spanned::new(FuncStmt::Return { value }, span)))
+}
+
+/// Parse an assert statement with an optional reason.
+pub fn assert_stmt<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<FuncStmt<'a>>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    // 'assert' expr [',' expr]
+    let (input, assert_kw) = name_symbol(sym::ASSERT)(input)?;
+    let (input, test) = expr(input)?;
+    let (input, msg) = opt(preceded(op_string(","), expr))(input)?;
+
+    let span = match &msg {
+        Some(msg) => (&assert_kw.span, &msg.span).into(),
+        None => (&assert_kw.span, &test.span).into(),
+    };
+
+    Ok((input, Spanned::new(FuncStmt::Assert { test, msg }, span)))
+}
+
+/// Parse a raise statement with an optional reason.
+pub fn raise_stmt<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<FuncStmt<'a>>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    // 'raise' [expr]
+    let (input, raise_kw) = name_symbol(sym::RAISE)(input)?;
+    let (input, msg) = opt(expr)(input)?;
+
+    let span = match &msg {
+        Some(msg) => (&raise_kw.span, &msg.span).into(),
+        None => raise_kw.span,
+    };
+
+    Ok((input, Spanned::new(FuncStmt::Raise { msg }, span)))
+}
+
+/// Parse a log statement.
+pub fn log_stmt<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<FuncStmt<'a>>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    // 'log' NAME '(' [call_args] ')'
+    let (input, log_kw) = name_symbol(sym::LOG)(input)?;
+    let (input, event) = name_token(input)?;
+    let (input, _) = op_string("(")(input)?;
+    let (input, args) = call_args(input)?;
+    let (input, r_paren) = op_string(")")(input)?;
+
+    Ok((
+        input,
+        Spanned::new(
+            FuncStmt::Log {
+                event: event.string,
+                args,
+            },
+            (&log_kw.span, &r_paren.span).into(),
+        ),
+    ))
+}
+
+/// Parse a local variable declaration with an optional value.
+pub fn var_decl<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<FuncStmt<'a>>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    // NAME ':' base_type_desc ['=' expr]
+    let (input, name) = name_token(input)?;
+    let (input, _) = op_string(":")(input)?;
+    let (input, typ) = base_type_desc(input)?;
+    let (input, value) = opt(preceded(op_string("="), expr))(input)?;
+
+    let span = match &value {
+        Some(value) => (&name.span, &value.span).into(),
+        None => (&name.span, &typ.span).into(),
+    };
+
+    Ok((
+        input,
+        Spanned::new(
+            FuncStmt::VarDecl {
+                name: name.string,
+                typ,
+                value,
+            },
+            span,
+        ),
+    ))
+}
+
+/// Parse an assignment, an augmented assignment or an expression statement.
+pub fn assign_stmt<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<FuncStmt<'a>>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    // expr [('=' | augassign) expr]
+    let (input, target) = expr(input)?;
+    let op_tok = match input.first() {
+        Some(tok) if tok.typ == TokenType::OP && tok.string.ends_with('=') => tok,
+        _ => {
+            let span = target.span;
+            return Ok((input, Spanned::new(FuncStmt::Expr { value: target }, span)));
+        }
+    };
+
+    let aug_op = Operator::from_aug_assign(op_tok.string);
+    if op_tok.string != "=" && aug_op.is_none() {
+        return make_error(input, ErrorKind::Tag);
+    }
+
+    let (input, value) = expr(&input[1..])?;
+    let span = (&target.span, &value.span).into();
+    let stmt = match aug_op {
+        Some(op) => FuncStmt::AugAssign { target, op, value },
+        None => FuncStmt::Assign { target, value },
+    };
+
+    Ok((input, Spanned::new(stmt, span)))
+}
+
+/// Return a parser for an if statement introduced by the keyword `keyword`,
+/// which is `if` or `elif`.
+pub fn if_stmt<'a, E>(
+    keyword: Symbol,
+) -> impl Fn(TokenSlice<'a>) -> TokenResult<'a, Spanned<FuncStmt<'a>>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    move |input| {
+        // keyword expr ':' block ('elif' ... | ['else' ':' block])
+        let (input, if_kw) = name_symbol(keyword)(input)?;
+        let (input, test) = expr(input)?;
+        let (input, _) = op_string(":")(input)?;
+        let (input, body) = block(input)?;
+
+        let (input, or_else) = match input.first().and_then(|tok| tok.sym) {
+            Some(sym::ELIF) => {
+                let (input, elif) = if_stmt(sym::ELIF)(input)?;
+                (input, vec![elif])
+            }
+            Some(sym::ELSE) => {
+                let (input, _) = name_symbol(sym::ELSE)(input)?;
+                let (input, _) = op_string(":")(input)?;
+                block(input)?
+            }
+            _ => (input, vec![]),
+        };
+
+        let last = or_else.last().unwrap_or_else(|| body.last().unwrap());
+        let span = (&if_kw.span, &last.span).into();
+
+        Ok((
+            input,
+            Spanned::new(
+                FuncStmt::If {
+                    test,
+                    body,
+                    or_else,
+                },
+                span,
+            ),
+        ))
+    }
+}
+
+/// Parse a for loop.
+pub fn for_stmt<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<FuncStmt<'a>>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    // 'for' NAME 'in' expr ':' block
+    let (input, for_kw) = name_symbol(sym::FOR)(input)?;
+    let (input, target) = name_token(input)?;
+    let (input, _) = name_symbol(sym::IN)(input)?;
+    let (input, iter) = expr(input)?;
+    let (input, _) = op_string(":")(input)?;
+    let (input, body) = block(input)?;
+
+    let span = (&for_kw.span, &body.last().unwrap().span).into();
+
+    Ok((
+        input,
+        Spanned::new(
+            FuncStmt::For {
+                target: target.string,
+                iter,
+                body,
+            },
+            span,
+        ),
+    ))
+}
+
+/// Parse a possibly empty list of call arguments with an optional trailing
+/// comma.
+pub fn call_args<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Vec<Spanned<Expr<'a>>>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    // [expr (',' expr)* [',']]
+    let (input, args) = opt(terminated(
+        separated_nonempty_list(op_string(","), expr),
+        opt(op_string(",")),
+    ))(input)?;
+
+    Ok((input, args.unwrap_or_default()))
+}
+
+/// Parse a left-associative chain of operands parsed by `operand` that are
+/// separated by any of the operators `ops`, and fold it into nested nodes
+/// built by `make`.
+fn left_assoc<'a, E, F, M>(
+    input: TokenSlice<'a>,
+    ops: &[&str],
+    operand: F,
+    make: M,
+) -> TokenResult<'a, Spanned<Expr<'a>>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+    F: Fn(TokenSlice<'a>) -> TokenResult<'a, Spanned<Expr<'a>>, E>,
+    M: Fn(&'a str, Box<Spanned<Expr<'a>>>, Box<Spanned<Expr<'a>>>) -> Expr<'a>,
+{
+    let (mut input, mut left) = operand(input)?;
+
+    while let Some(op_tok) = input.first().filter(|tok| {
+        (tok.typ == TokenType::OP || tok.typ == TokenType::NAME) && ops.contains(&tok.string)
+    }) {
+        let (rest, right) = operand(&input[1..])?;
+        let span = (&left.span, &right.span).into();
+
+        left = Spanned::new(make(op_tok.string, Box::new(left), Box::new(right)), span);
+        input = rest;
+    }
+
+    Ok((input, left))
+}
+
+/// Return a node combining `left` and `right` with the binary operator `op`.
+fn bin_op<'a>(op: &str, left: Box<Spanned<Expr<'a>>>, right: Box<Spanned<Expr<'a>>>) -> Expr<'a> {
+    Expr::BinOp {
+        left,
+        op: Operator::try_from(op).unwrap(),
+        right,
+    }
+}
+
+/// Parse an expression in the body of a function.
+pub fn expr<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<Expr<'a>>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    // and_test ('or' and_test)*
+    left_assoc(input, &["or"], and_test, |op, left, right| Expr::BoolOp {
+        left,
+        op: BoolOperator::try_from(op).unwrap(),
+        right,
+    })
+}
+
+/// Parse a conjunction that may appear as the operand of `or`.
+pub fn and_test<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<Expr<'a>>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    // not_test ('and' not_test)*
+    left_assoc(input, &["and"], not_test, |op, left, right| Expr::BoolOp {
+        left,
+        op: BoolOperator::try_from(op).unwrap(),
+        right,
+    })
+}
+
+/// Parse a negation that may appear as the operand of `and`.
+pub fn not_test<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<Expr<'a>>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    // 'not' not_test | comparison
+    let negation = map(
+        pair(name_symbol(sym::NOT), not_test),
+        |(not_kw, operand)| {
+            let span = (&not_kw.span, &operand.span).into();
+
+            Spanned::new(
+                Expr::UnaryOp {
+                    op: UnaryOp::Not,
+                    operand: Box::new(operand),
+                },
+                span,
+            )
+        },
+    );
+
+    alt((negation, comparison))(input)
+}
+
+/// Parse a comparison that may appear as the operand of `not`.
+pub fn comparison<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<Expr<'a>>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    // bit_or [('==' | '!=' | '<' | '<=' | '>' | '>=') bit_or]
+    let (input, left) = bit_or(input)?;
+    let op_tok = match input.first() {
+        Some(tok) if tok.typ == TokenType::OP && CompOperator::try_from(tok.string).is_ok() => tok,
+        _ => return Ok((input, left)),
+    };
+    let (input, right) = bit_or(&input[1..])?;
+
+    let span = (&left.span, &right.span).into();
+
+    Ok((
+        input,
+        Spanned::new(
+            Expr::CompOp {
+                left: Box::new(left),
+                op: CompOperator::try_from(op_tok.string).unwrap(),
+                right: Box::new(right),
+            },
+            span,
+        ),
+    ))
+}
+
+/// Parse a bitwise or expression.
+pub fn bit_or<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<Expr<'a>>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    left_assoc(input, &["|"], bit_xor, bin_op)
+}
+
+/// Parse a bitwise exclusive or expression.
+pub fn bit_xor<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<Expr<'a>>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    left_assoc(input, &["^"], bit_and, bin_op)
+}
+
+/// Parse a bitwise and expression.
+pub fn bit_and<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<Expr<'a>>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    left_assoc(input, &["&"], shift_expr, bin_op)
+}
+
+/// Parse a shift expression.
+pub fn shift_expr<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<Expr<'a>>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    left_assoc(input, &["<<", ">>"], arith_expr, bin_op)
+}
+
+/// Parse an addition or subtraction.
+pub fn arith_expr<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<Expr<'a>>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    left_assoc(input, &["+", "-"], term, bin_op)
+}
+
+/// Parse a multiplication, division or modulus.
+pub fn term<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<Expr<'a>>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    left_assoc(input, &["*", "/", "%"], factor, bin_op)
+}
+
+/// Parse a factor that may appear as the operand of a multiplication,
+/// division, modulus, or unary op or as the exponent of a power expression.
+pub fn factor<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<Expr<'a>>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    let unary_op = map(
+        pair(
+            alt((op_string("+"), op_string("-"), op_string("~"))),
+            factor,
+        ),
+        |(op_tok, operand)| {
+            let span = (&op_tok.span, &operand.span).into();
+
+            Spanned::new(
+                Expr::UnaryOp {
+                    op: UnaryOp::try_from(op_tok.string).unwrap(),
+                    operand: Box::new(operand),
+                },
+                span,
+            )
         },
+    );
+
+    alt((unary_op, power))(input)
+}
+
+/// Parse a power expression that may appear in the position of a factor.
+pub fn power<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<Expr<'a>>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    // primary ['**' factor]
+    let (input, base) = primary(input)?;
+    let (input, exponent) = opt(preceded(op_string("**"), factor))(input)?;
+
+    match exponent {
+        Some(exponent) => {
+            let span = (&base.span, &exponent.span).into();
+
+            Ok((
+                input,
+                Spanned::new(bin_op("**", Box::new(base), Box::new(exponent)), span),
+            ))
+        }
+        None => Ok((input, base)),
+    }
+}
+
+/// Parse an atom followed by any number of calls, subscripts and attribute
+/// accesses.
+pub fn primary<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<Expr<'a>>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    // atom ('(' call_args ')' | '[' expr ']' | '.' NAME)*
+    let (mut input, mut value) = atom(input)?;
+
+    loop {
+        let (rest, node, end) = match input.first().map(|tok| tok.string) {
+            Some("(") => {
+                let (rest, args) = call_args(&input[1..])?;
+                let (rest, r_paren) = op_string(")")(rest)?;
+                let call = Expr::Call {
+                    func: Box::new(value),
+                    args,
+                };
+
+                (rest, call, r_paren.span)
+            }
+            Some("[") => {
+                let (rest, index) = expr(&input[1..])?;
+                let (rest, r_bracket) = op_string("]")(rest)?;
+                let subscript = Expr::Subscript {
+                    value: Box::new(value),
+                    index: Box::new(index),
+                };
+
+                (rest, subscript, r_bracket.span)
+            }
+            Some(".") => {
+                let (rest, attr) = name_token(&input[1..])?;
+                let attribute = Expr::Attribute {
+                    value: Box::new(value),
+                    attr: attr.string,
+                };
+
+                (rest, attribute, attr.span)
+            }
+            _ => break,
+        };
+
+        let start = input_start(&node);
+        value = Spanned::new(node, (&start, &end).into());
+        input = rest;
+    }
+
+    Ok((input, value))
+}
+
+/// Return the span of the innermost value at the start of a call, subscript
+/// or attribute access.
+fn input_start(node: &Expr) -> crate::span::Span {
+    match node {
+        Expr::Call { func: value, .. }
+        | Expr::Subscript { value, .. }
+        | Expr::Attribute { value, .. } => value.span,
+        _ => unreachable!(),
+    }
+}
+
+/// Parse an atom that may appear in the position of a primary expression.
+pub fn atom<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<Expr<'a>>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    // '(' expr ')' | NAME | NUMBER | STRING
+    let group = map(
+        tuple((op_string("("), expr, op_string(")"))),
+        |(l_paren, spanned_expr, r_paren)| {
+            Spanned::new(spanned_expr.node, (&l_paren.span, &r_paren.span).into())
+        },
+    );
+
+    alt((
+        group,
+        map(identifier, |t| {
+            Spanned::new(Expr::Name { name: t.string }, t.span)
+        }),
+        map(number_token, |t| {
+            Spanned::new(Expr::Num { num: t.string }, t.span)
+        }),
+        map(string_token, |t| {
+            Spanned::new(Expr::Str { string: t.string }, t.span)
+        }),
+    ))(input)
+}
+
+/// Parse a type description that may be wrapped in annotations such as
+/// `indexed(...)` or `public(...)`.  Annotations are listed from outermost to
+/// innermost.
+pub fn ann_type_desc<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<TypeDesc<'a>>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    let annotated = |input: TokenSlice<'a>| {
+        // NAME '(' ann_type_desc ')'
+        let (input, ann) = name_token(input)?;
+        let (input, _) = op_string("(")(input)?;
+        let (input, inner) = ann_type_desc(input)?;
+        let (input, r_paren) = op_string(")")(input)?;
+
+        let mut typ = inner.node;
+        typ.annotations.insert(0, ann.string);
+
+        Ok((input, Spanned::new(typ, (&ann.span, &r_paren.span).into())))
+    };
+
+    alt((map_type_desc, annotated, base_type_desc))(input)
+}
+
+/// Parse a map type description such as `map(address, uint256)`.  Keys have
+/// a base type and values may be maps themselves.
+pub fn map_type_desc<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<TypeDesc<'a>>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    // 'map' '(' base_type_desc ',' (map_type_desc | base_type_desc) [','] ')'
+    let (input, map_kw) = name_symbol(sym::MAP)(input)?;
+    let (input, _) = op_string("(")(input)?;
+    let (input, key) = base_type_desc(input)?;
+    let (input, _) = op_string(",")(input)?;
+    let (input, value) = alt((map_type_desc, base_type_desc))(input)?;
+    let (input, _) = opt(op_string(","))(input)?;
+    let (input, r_paren) = op_string(")")(input)?;
+
+    Ok((
+        input,
+        Spanned::new(
+            TypeDesc {
+                base: map_kw.string,
+                dimensions: vec![],
+                annotations: vec![],
+                params: vec![key, value],
+            },
+            (&map_kw.span, &r_paren.span).into(),
+        ),
+    ))
+}
+
+/// Parse a base type description with optional array dimensions such as
+/// `uint256` or `bytes[100]`.
+pub fn base_type_desc<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<TypeDesc<'a>>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    // NAME ('[' const_expr ']')*
+    let (input, base) = name_token(input)?;
+    let (input, dims) = many0(tuple((op_string("["), const_expr, op_string("]"))))(input)?;
+
+    let span = match dims.last() {
+        Some((_, _, r_bracket)) => (&base.span, &r_bracket.span).into(),
+        None => base.span,
+    };
+
+    Ok((
+        input,
+        Spanned::new(
+            TypeDesc {
+                base: base.string,
+                dimensions: dims.into_iter().map(|(_, dim, _)| dim).collect(),
+                annotations: vec![],
+                params: vec![],
+            },
+            span,
+        ),
     ))
 }
 
 /// Parse a constant expression that can be evaluated at compile-time.
-pub fn const_expr<'a, E>(input: TokenSlice<'a>) -> TokenResult<Spanned<ConstExpr>, E>
+pub fn const_expr<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<ConstExpr<'a>>, E>
 where
     E: ParseError<TokenSlice<'a>>,
 {
@@ -276,14 +1063,14 @@ where
     for (op_tok, right_expr) in tail {
         let span = (&left_expr.span, &right_expr.span).into();
 
-        left_expr = Spanned {
-            node: ConstExpr::BinOp {
+        left_expr = Spanned::new(
+            ConstExpr::BinOp {
                 left: Box::new(left_expr),
                 op: Operator::try_from(op_tok.string).unwrap(),
                 right: Box::new(right_expr),
             },
             span,
-        };
+        );
     }
 
     Ok((input, left_expr))
@@ -291,7 +1078,7 @@ where
 
 /// Parse a constant term that may appear as the operand of an addition or
 /// subtraction.
-pub fn const_term<'a, E>(input: TokenSlice<'a>) -> TokenResult<Spanned<ConstExpr>, E>
+pub fn const_term<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<ConstExpr<'a>>, E>
 where
     E: ParseError<TokenSlice<'a>>,
 {
@@ -306,14 +1093,14 @@ where
     for (op_tok, right_expr) in tail {
         let span = (&left_expr.span, &right_expr.span).into();
 
-        left_expr = Spanned {
-            node: ConstExpr::BinOp {
+        left_expr = Spanned::new(
+            ConstExpr::BinOp {
                 left: Box::new(left_expr),
                 op: Operator::try_from(op_tok.string).unwrap(),
                 right: Box::new(right_expr),
             },
             span,
-        };
+        );
     }
 
     Ok((input, left_expr))
@@ -321,7 +1108,7 @@ where
 
 /// Parse a constant factor that may appear as the operand of a multiplication,
 /// division, modulus, or unary op or as the exponent of a power expression.
-pub fn const_factor<'a, E>(input: TokenSlice<'a>) -> TokenResult<Spanned<ConstExpr>, E>
+pub fn const_factor<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<ConstExpr<'a>>, E>
 where
     E: ParseError<TokenSlice<'a>>,
 {
@@ -334,13 +1121,13 @@ where
             let (op_tok, operand) = res;
             let span = (&op_tok.span, &operand.span).into();
 
-            Spanned {
-                node: ConstExpr::UnaryOp {
+            Spanned::new(
+                ConstExpr::UnaryOp {
                     op: UnaryOp::try_from(op_tok.string).unwrap(),
                     operand: Box::new(operand),
                 },
                 span,
-            }
+            )
         },
     );
 
@@ -349,7 +1136,7 @@ where
 
 /// Parse a constant power expression that may appear in the position of a
 /// constant factor.
-pub fn const_power<'a, E>(input: TokenSlice<'a>) -> TokenResult<Spanned<ConstExpr>, E>
+pub fn const_power<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<ConstExpr<'a>>, E>
 where
     E: ParseError<TokenSlice<'a>>,
 {
@@ -359,14 +1146,14 @@ where
             let (left, right) = res;
             let span = (&left.span, &right.span).into();
 
-            Spanned {
-                node: ConstExpr::BinOp {
+            Spanned::new(
+                ConstExpr::BinOp {
                     left: Box::new(left),
                     op: Operator::Pow,
                     right: Box::new(right),
                 },
                 span,
-            }
+            )
         },
     );
 
@@ -375,26 +1162,52 @@ where
 
 /// Parse a constant atom expression that may appear in the position of a
 /// constant power or as the base of a constant power expression.
-pub fn const_atom<'a, E>(input: TokenSlice<'a>) -> TokenResult<Spanned<ConstExpr>, E>
+pub fn const_atom<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<ConstExpr<'a>>, E>
 where
     E: ParseError<TokenSlice<'a>>,
 {
     alt((
         const_group,
-        map(name_token, |t| Spanned {
-            node: ConstExpr::Name { name: t.string },
-            span: t.span,
+        const_call,
+        map(name_token, |t| {
+            Spanned::new(ConstExpr::Name { name: t.string }, t.span)
         }),
-        map(number_token, |t| Spanned {
-            node: ConstExpr::Num { num: t.string },
-            span: t.span,
+        map(number_token, |t| {
+            Spanned::new(ConstExpr::Num { num: t.string }, t.span)
         }),
     ))(input)
 }
 
+/// Parse a call to a built-in function with constant arguments that may
+/// appear in the position of a constant atom.
+pub fn const_call<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<ConstExpr<'a>>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    // NAME '(' [const_expr (',' const_expr)* [',']] ')'
+    let (input, func) = name_token(input)?;
+    let (input, _) = op_string("(")(input)?;
+    let (input, args) = opt(terminated(
+        separated_nonempty_list(op_string(","), const_expr),
+        opt(op_string(",")),
+    ))(input)?;
+    let (input, r_paren) = op_string(")")(input)?;
+
+    Ok((
+        input,
+        Spanned::new(
+            ConstExpr::Call {
+                func: func.string,
+                args: args.unwrap_or_default(),
+            },
+            (&func.span, &r_paren.span).into(),
+        ),
+    ))
+}
+
 /// Parse a parenthesized constant group that may appear in the position of a
 /// constant atom.
-pub fn const_group<'a, E>(input: TokenSlice<'a>) -> TokenResult<Spanned<ConstExpr>, E>
+pub fn const_group<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<ConstExpr<'a>>, E>
 where
     E: ParseError<TokenSlice<'a>>,
 {
@@ -404,9 +1217,6 @@ where
 
     Ok((
         input,
-        Spanned {
-            node: spanned_expr.node,
-            span: (&l_paren.span, &r_paren.span).into(),
-        },
+        Spanned::new(spanned_expr.node, (&l_paren.span, &r_paren.span).into()),
     ))
 }
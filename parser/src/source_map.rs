@@ -0,0 +1,140 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::line_index::{
+    ColumnUnit,
+    LineCol,
+    LineIndex,
+};
+use crate::span::{
+    FileSpan,
+    Span,
+};
+
+/// An identifier for a file loaded into a `SourceMap`.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Copy, Clone)]
+pub struct FileId(u32);
+
+/// A source file and the index of its lines.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SourceFile {
+    pub path: String,
+    pub source: String,
+    pub line_index: LineIndex,
+}
+
+/// The source files loaded during a compilation.  Files are never removed, so
+/// the ids handed out by a map stay valid for its lifetime.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        SourceMap::default()
+    }
+
+    /// Add the source `source` loaded from `path` and return its id.
+    pub fn add_file(&mut self, path: &str, source: String) -> FileId {
+        let id = FileId(self.files.len() as u32);
+        let line_index = LineIndex::new(&source);
+
+        self.files.push(SourceFile {
+            path: path.to_string(),
+            source,
+            line_index,
+        });
+
+        id
+    }
+
+    /// Return the id of the file loaded from `path`.
+    pub fn find_file(&self, path: &str) -> Option<FileId> {
+        self.files
+            .iter()
+            .position(|file| file.path == path)
+            .map(|i| FileId(i as u32))
+    }
+
+    /// Return the file with id `id`.
+    ///
+    /// Panics if `id` was handed out by another map.
+    pub fn file(&self, id: FileId) -> &SourceFile {
+        &self.files[id.0 as usize]
+    }
+
+    /// Return the source code spanned by `span`.
+    pub fn text(&self, span: FileSpan) -> &str {
+        &self.file(span.file).source[span.span.start..span.span.end]
+    }
+
+    /// Return the 0-indexed line and byte column of the start of `span`.
+    pub fn line_col(&self, span: FileSpan) -> Option<LineCol> {
+        self.file(span.file)
+            .line_index
+            .line_col(span.span.start, ColumnUnit::Byte)
+    }
+
+    /// Return the line containing the start of `span` without its line ending.
+    pub fn line(&self, span: FileSpan) -> Option<&str> {
+        let file = self.file(span.file);
+        let line = self.line_col(span)?.line;
+        let start = file.line_index.line_start(line)?;
+        let end = file.line_index.line_end(line)?;
+
+        Some(file.source[start..end].trim_end_matches('\r'))
+    }
+
+    /// Return the location of the start of `span` as `path:line:col` with a
+    /// 1-indexed line and a 1-indexed column in characters.
+    pub fn location(&self, span: FileSpan) -> String {
+        let file = self.file(span.file);
+
+        match file.line_index.line_col(span.span.start, ColumnUnit::Char) {
+            Some(pos) => format!("{}:{}:{}", file.path, pos.line + 1, pos.col + 1),
+            None => file.path.clone(),
+        }
+    }
+
+    /// Return the span of the whole file with id `id`.
+    pub fn file_span(&self, id: FileId) -> FileSpan {
+        Span::new(0, self.file(id).source.len()).in_file(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_files() {
+        let mut sources = SourceMap::new();
+        let token = sources.add_file("token.vy", "event A:\n    x: int128\n".to_string());
+        let other = sources.add_file("lib/é.vy", "# é\nevent B:\r\n".to_string());
+
+        assert_ne!(token, other);
+        assert_eq!(sources.find_file("lib/é.vy"), Some(other));
+        assert_eq!(sources.find_file("missing.vy"), None);
+        assert_eq!(sources.file(token).path, "token.vy");
+        assert_eq!(sources.file_span(other), Span::new(0, 15).in_file(other));
+
+        let x = Span::new(13, 14).in_file(token);
+        assert_eq!(sources.text(x), "x");
+        assert_eq!(sources.line_col(x), Some(LineCol::new(1, 4)));
+        assert_eq!(sources.line(x), Some("    x: int128"));
+        assert_eq!(sources.location(x), "token.vy:2:5");
+
+        let b = Span::new(11, 12).in_file(other);
+        assert_eq!(sources.text(b), "B");
+        assert_eq!(sources.line(b), Some("event B:"));
+        assert_eq!(sources.location(b), "lib/é.vy:2:7");
+
+        // Columns count characters rather than bytes
+        let comment = Span::new(2, 4).in_file(other);
+        assert_eq!(sources.location(comment), "lib/é.vy:1:3");
+        assert_eq!(sources.line_col(comment), Some(LineCol::new(0, 2)));
+    }
+}
@@ -3,6 +3,9 @@ use serde::{
     Serialize,
 };
 
+use crate::node_id::NodeId;
+use crate::source_map::FileId;
+
 /// An exclusive span of byte offsets in a source file.
 #[derive(Serialize, Deserialize, Debug, PartialEq, Copy, Clone)]
 pub struct Span {
@@ -16,6 +19,11 @@ impl Span {
     pub fn new(start: usize, end: usize) -> Self {
         Span { start, end }
     }
+
+    /// Attach the file with id `file` to this span.
+    pub fn in_file(self, file: FileId) -> FileSpan {
+        FileSpan { file, span: self }
+    }
 }
 
 impl From<(&Span, &Span)> for Span {
@@ -29,8 +37,43 @@ impl From<(&Span, &Span)> for Span {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+/// A span of byte offsets in the source file with id `file`.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Copy, Clone)]
+pub struct FileSpan {
+    pub file: FileId,
+    pub span: Span,
+}
+
+/// A node with its span and id.  Ids are not serialized and are ignored when
+/// comparing nodes, so that nodes parsed separately compare equal.  Clones are
+/// given fresh ids, as are the nodes they contain, so that ids stay unique.
+#[derive(Serialize, Deserialize, Debug)]
 pub struct Spanned<T> {
     pub node: T,
     pub span: Span,
+    #[serde(skip, default = "NodeId::fresh")]
+    pub id: NodeId,
+}
+
+impl<T> Spanned<T> {
+    /// Return a node with a fresh id.
+    pub fn new(node: T, span: Span) -> Self {
+        Spanned {
+            node,
+            span,
+            id: NodeId::fresh(),
+        }
+    }
+}
+
+impl<T: Clone> Clone for Spanned<T> {
+    fn clone(&self) -> Self {
+        Spanned::new(self.node.clone(), self.span)
+    }
+}
+
+impl<T: PartialEq> PartialEq for Spanned<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.node == other.node && self.span == other.span
+    }
 }
@@ -84,7 +84,8 @@ impl Position {
 
 /// Efficiently find the text positions (line, column tuples) of a monotonically
 /// increasing sequence of byte offsets in a string.  Non-monotonic sequences
-/// are also supported but are less efficient.
+/// are also supported but are less efficient.  For random lookups or columns
+/// in units other than bytes, use `line_index::LineIndex`.
 pub struct StringPositions<'a> {
     /// A string in which text positions should be calculated
     input: &'a str,
@@ -298,14 +299,14 @@ here
     #[test]
     fn test_file_positions() {
         // Empty string has expected behavior
-        let mut string_pos = StringPositions::new(&r#""#);
+        let mut string_pos = StringPositions::new(r#""#);
         assert_eq!(string_pos.get_pos(0), None);
         assert_eq!(string_pos.get_pos(1), None);
         assert_eq!(string_pos.get_last(), None);
         assert_eq!(string_pos.get_eof(), Position::new(1, 0));
 
         // Can get same position twice
-        let mut string_pos = StringPositions::new(&r#"asdf"#);
+        let mut string_pos = StringPositions::new(r#"asdf"#);
         assert_eq!(string_pos.get_pos(0), Some(Position::new(1, 0)));
         assert_eq!(string_pos.get_pos(0), Some(Position::new(1, 0)));
         assert_eq!(string_pos.get_pos(1), Some(Position::new(1, 1)));
@@ -314,20 +315,20 @@ here
         assert_eq!(string_pos.get_eof(), Position::new(1, 4));
 
         // Can get sequential positions
-        let mut string_pos = StringPositions::new(&r#"asdf"#);
+        let mut string_pos = StringPositions::new(r#"asdf"#);
         assert_eq!(string_pos.get_pos(0), Some(Position::new(1, 0)));
         assert_eq!(string_pos.get_pos(1), Some(Position::new(1, 1)));
         assert_eq!(string_pos.get_pos(2), Some(Position::new(1, 2)));
         assert_eq!(string_pos.get_pos(3), Some(Position::new(1, 3)));
 
         // Can get non-sequential positions
-        let mut string_pos = StringPositions::new(&r#"asdf"#);
+        let mut string_pos = StringPositions::new(r#"asdf"#);
         assert_eq!(string_pos.get_pos(0), Some(Position::new(1, 0)));
         assert_eq!(string_pos.get_pos(1), Some(Position::new(1, 1)));
         assert_eq!(string_pos.get_pos(0), Some(Position::new(1, 0)));
 
         // Can get sequential then invalid
-        let mut string_pos = StringPositions::new(&r#"asdf"#);
+        let mut string_pos = StringPositions::new(r#"asdf"#);
         assert_eq!(string_pos.get_pos(0), Some(Position::new(1, 0)));
         assert_eq!(string_pos.get_pos(1), Some(Position::new(1, 1)));
         assert_eq!(string_pos.get_pos(2), Some(Position::new(1, 2)));
@@ -336,12 +337,12 @@ here
         assert_eq!(string_pos.get_pos(5), None);
 
         // Can get invalid
-        let mut string_pos = StringPositions::new(&r#"asdf"#);
+        let mut string_pos = StringPositions::new(r#"asdf"#);
         assert_eq!(string_pos.get_pos(4), None);
 
         // Can get multiple line positions
         let mut string_pos = StringPositions::new(
-            &r#"i wrote this
+            r#"i wrote this
 thing that finds
 positions on lines"#,
         );
@@ -361,7 +362,7 @@ positions on lines"#,
 
         // EOF is after newline
         let mut string_pos = StringPositions::new(
-            &r#"i wrote this
+            r#"i wrote this
 thing that finds
 positions on lines
 "#,
@@ -0,0 +1,280 @@
+//! Interned strings.
+//!
+//! A `Symbol` is an index into a global table of strings.  Interning a string
+//! twice yields the same symbol, so symbols can be compared and hashed as
+//! integers.  Vyper keywords and built-in names are interned ahead of time and
+//! are available as constants in `sym`.  Interned strings are never freed.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{
+    Mutex,
+    OnceLock,
+};
+
+use serde::de::{
+    Deserialize,
+    Deserializer,
+};
+use serde::ser::{
+    Serialize,
+    Serializer,
+};
+
+/// An interned string.
+#[derive(PartialEq, Eq, Hash, PartialOrd, Ord, Copy, Clone)]
+pub struct Symbol(u32);
+
+/// Define the constants in `sym` along with the strings they are interned
+/// from.  Keywords come first so that symbols can be classified by index.
+macro_rules! symbols {
+    (
+        keywords { $($kw:ident: $kw_str:literal,)* }
+        builtins { $($builtin:ident: $builtin_str:literal,)* }
+    ) => {
+        const PREDEFINED: &[&str] = &[$($kw_str,)* $($builtin_str,)*];
+        const KEYWORD_COUNT: u32 = [$($kw_str),*].len() as u32;
+
+        /// The symbols of Vyper keywords and built-in names.
+        pub mod sym {
+            use super::Symbol;
+
+            symbols!(@consts 0; $($kw,)* $($builtin,)*);
+        }
+    };
+    (@consts $index:expr;) => {};
+    (@consts $index:expr; $name:ident, $($rest:ident,)*) => {
+        pub const $name: Symbol = Symbol($index);
+        symbols!(@consts $index + 1; $($rest,)*);
+    };
+}
+
+symbols! {
+    keywords {
+        AND: "and",
+        AS: "as",
+        ASSERT: "assert",
+        BREAK: "break",
+        CONTINUE: "continue",
+        CONTRACT: "contract",
+        DEF: "def",
+        ELIF: "elif",
+        ELSE: "else",
+        EVENT: "event",
+        FOR: "for",
+        FROM: "from",
+        IF: "if",
+        IMPLEMENTS: "implements",
+        IMPORT: "import",
+        IN: "in",
+        INTERFACE: "interface",
+        IS: "is",
+        LOG: "log",
+        NOT: "not",
+        OR: "or",
+        PASS: "pass",
+        RAISE: "raise",
+        RETURN: "return",
+        STRUCT: "struct",
+        WHILE: "while",
+    }
+    builtins {
+        // Types
+        ADDRESS: "address",
+        BOOL: "bool",
+        BYTES: "bytes",
+        BYTES32: "bytes32",
+        DECIMAL: "decimal",
+        INT128: "int128",
+        MAP: "map",
+        STRING: "string",
+        UINT256: "uint256",
+
+        // Type annotations
+        CONSTANT: "constant",
+        INDEXED: "indexed",
+        PUBLIC: "public",
+
+        // Decorators
+        EXTERNAL: "external",
+        INTERNAL: "internal",
+        NONPAYABLE: "nonpayable",
+        PAYABLE: "payable",
+        PURE: "pure",
+        VIEW: "view",
+
+        // Constants and environment variables
+        TRUE: "True",
+        FALSE: "False",
+        SELF: "self",
+        MSG: "msg",
+        BLOCK: "block",
+        TX: "tx",
+
+        // Functions
+        AS_WEI_VALUE: "as_wei_value",
+        BITWISE_AND: "bitwise_and",
+        BITWISE_NOT: "bitwise_not",
+        BITWISE_OR: "bitwise_or",
+        BITWISE_XOR: "bitwise_xor",
+        CONCAT: "concat",
+        CONVERT: "convert",
+        ECRECOVER: "ecrecover",
+        EMPTY: "empty",
+        KECCAK256: "keccak256",
+        LEN: "len",
+        MAX: "max",
+        MIN: "min",
+        RAW_CALL: "raw_call",
+        SEND: "send",
+        SHA256: "sha256",
+        SHIFT: "shift",
+        SLICE: "slice",
+    }
+}
+
+/// The table of interned strings.
+struct Interner {
+    symbols: HashMap<&'static str, Symbol>,
+    strings: Vec<&'static str>,
+}
+
+impl Interner {
+    fn new() -> Self {
+        let mut interner = Interner {
+            symbols: HashMap::new(),
+            strings: vec![],
+        };
+        for string in PREDEFINED {
+            interner.intern(string);
+        }
+
+        interner
+    }
+
+    fn intern(&mut self, string: &str) -> Symbol {
+        if let Some(symbol) = self.symbols.get(string) {
+            return *symbol;
+        }
+
+        let string: &'static str = Box::leak(string.to_string().into_boxed_str());
+        let symbol = Symbol(self.strings.len() as u32);
+        self.symbols.insert(string, symbol);
+        self.strings.push(string);
+
+        symbol
+    }
+}
+
+/// Apply `f` to the global interner.
+fn with_interner<T>(f: impl FnOnce(&mut Interner) -> T) -> T {
+    static INTERNER: OnceLock<Mutex<Interner>> = OnceLock::new();
+
+    let mut interner = INTERNER
+        .get_or_init(|| Mutex::new(Interner::new()))
+        .lock()
+        .unwrap_or_else(|err| err.into_inner());
+
+    f(&mut interner)
+}
+
+impl Symbol {
+    /// Return the symbol of `string`, interning it if necessary.
+    pub fn intern(string: &str) -> Self {
+        with_interner(|interner| interner.intern(string))
+    }
+
+    /// Return the symbol of `string` if it has been interned.  Unlike `intern`,
+    /// this does not grow the table, which makes it suitable for classifying
+    /// arbitrary names.
+    pub fn lookup(string: &str) -> Option<Self> {
+        with_interner(|interner| interner.symbols.get(string).copied())
+    }
+
+    /// Return the string from which a symbol was interned.
+    pub fn as_str(self) -> &'static str {
+        with_interner(|interner| interner.strings[self.0 as usize])
+    }
+
+    /// Return true if a symbol is a Vyper keyword.
+    pub fn is_keyword(self) -> bool {
+        self.0 < KEYWORD_COUNT
+    }
+
+    /// Return true if a symbol is a Vyper keyword or built-in name.
+    pub fn is_predefined(self) -> bool {
+        (self.0 as usize) < PREDEFINED.len()
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(string: &str) -> Self {
+        Symbol::intern(string)
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.as_str())
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Symbols are serialized as their strings since their indices depend on the
+/// order in which strings were interned.
+impl Serialize for Symbol {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Symbol {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let string = String::deserialize(deserializer)?;
+
+        Ok(Symbol::intern(&string))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_predefined() {
+        for (i, string) in PREDEFINED.iter().enumerate() {
+            assert_eq!(Symbol::intern(string), Symbol(i as u32), "{}", string);
+        }
+
+        assert_eq!(sym::AND.as_str(), "and");
+        assert_eq!(sym::WHILE.as_str(), "while");
+        assert_eq!(sym::ADDRESS.as_str(), "address");
+        assert_eq!(sym::SLICE.as_str(), "slice");
+
+        assert!(sym::EVENT.is_keyword());
+        assert!(sym::EVENT.is_predefined());
+        assert!(!sym::UINT256.is_keyword());
+        assert!(sym::UINT256.is_predefined());
+    }
+
+    #[test]
+    fn test_intern() {
+        let a = Symbol::intern("Transfer");
+        assert_eq!(Symbol::intern("Transfer"), a);
+        assert_eq!(Symbol::from("Transfer"), a);
+        assert_ne!(Symbol::intern("transfer"), a);
+        assert_eq!(a.as_str(), "Transfer");
+        assert_eq!(a.to_string(), "Transfer");
+        assert_eq!(format!("{:?}", a), "\"Transfer\"");
+        assert!(!a.is_keyword());
+        assert!(!a.is_predefined());
+
+        assert_eq!(Symbol::lookup("event"), Some(sym::EVENT));
+        assert_eq!(Symbol::lookup("never_interned_name"), None);
+    }
+}
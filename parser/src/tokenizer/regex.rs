@@ -292,7 +292,7 @@ mod tests {
         ];
 
         for (name, pattern) in patterns {
-            Regex::new(&pattern).expect(name);
+            Regex::new(pattern).expect(name);
         }
     }
 }
@@ -6,6 +6,7 @@ use crate::string_utils::{
     lstrip_slice,
     rstrip_slice,
 };
+use crate::symbol::Symbol;
 use crate::tokenizer::regex::{
     compile_anchored,
     get_pseudotoken_pattern,
@@ -25,7 +26,7 @@ const TABSIZE: usize = 8;
 
 #[inline]
 fn is_identifier_char(c: char) -> bool {
-    c == '_' || c.is_ascii_alphabetic() || c.is_digit(10)
+    c == '_' || c.is_ascii_alphabetic() || c.is_ascii_digit()
 }
 
 #[derive(Debug, PartialEq)]
@@ -126,6 +127,7 @@ pub fn tokenize<'a>(input: &'a str) -> Result<Vec<Token<'a>>, TokenizeError> {
                     string: &input[contstr_start_val..line_start + tok_end],
                     span: Span::new(contstr_start_val, line_start + tok_end),
                     line: &input[contline_start.unwrap()..line_end],
+                    sym: None,
                 });
 
                 contstr_start = None;
@@ -138,6 +140,7 @@ pub fn tokenize<'a>(input: &'a str) -> Result<Vec<Token<'a>>, TokenizeError> {
                     string: &input[contstr_start_val..line_end],
                     span: Span::new(contstr_start_val, line_end),
                     line: &input[contline_start.unwrap()..line_start],
+                    sym: None,
                 });
 
                 contstr_start = None;
@@ -193,6 +196,7 @@ pub fn tokenize<'a>(input: &'a str) -> Result<Vec<Token<'a>>, TokenizeError> {
                                 line_start + line_pos + comment_token_len,
                             ),
                             line,
+                            sym: None,
                         });
 
                         line_pos += comment_token_len;
@@ -203,6 +207,7 @@ pub fn tokenize<'a>(input: &'a str) -> Result<Vec<Token<'a>>, TokenizeError> {
                         string: &line[line_pos..],
                         span: Span::new(line_start + line_pos, line_end),
                         line,
+                        sym: None,
                     });
 
                     continue;
@@ -218,6 +223,7 @@ pub fn tokenize<'a>(input: &'a str) -> Result<Vec<Token<'a>>, TokenizeError> {
                     string: &line[..line_pos],
                     span: Span::new(line_start, rest_off),
                     line,
+                    sym: None,
                 });
             }
 
@@ -235,6 +241,7 @@ pub fn tokenize<'a>(input: &'a str) -> Result<Vec<Token<'a>>, TokenizeError> {
                     string: &line[line_pos..line_pos],
                     span: Span::new(rest_off, rest_off),
                     line,
+                    sym: None,
                 });
             }
         } else {
@@ -264,6 +271,7 @@ pub fn tokenize<'a>(input: &'a str) -> Result<Vec<Token<'a>>, TokenizeError> {
                         string: token,
                         span: Span::new(soff, eoff),
                         line,
+                        sym: None,
                     });
                 } else if initial == '\r' || initial == '\n' {
                     result.push(Token {
@@ -271,6 +279,7 @@ pub fn tokenize<'a>(input: &'a str) -> Result<Vec<Token<'a>>, TokenizeError> {
                         string: token,
                         span: Span::new(soff, eoff),
                         line,
+                        sym: None,
                     });
                 } else if initial == '#' {
                     result.push(Token {
@@ -278,6 +287,7 @@ pub fn tokenize<'a>(input: &'a str) -> Result<Vec<Token<'a>>, TokenizeError> {
                         string: token,
                         span: Span::new(soff, eoff),
                         line,
+                        sym: None,
                     });
                 } else if triple_quoted.contains(token) {
                     contstr_end_re = Some(get_contstr_end_re(token));
@@ -291,6 +301,7 @@ pub fn tokenize<'a>(input: &'a str) -> Result<Vec<Token<'a>>, TokenizeError> {
                             string: token,
                             span: Span::new(soff, line_start + line_pos),
                             line,
+                            sym: None,
                         });
                     } else {
                         contstr_start = Some(line_start + tok_start);
@@ -314,6 +325,7 @@ pub fn tokenize<'a>(input: &'a str) -> Result<Vec<Token<'a>>, TokenizeError> {
                             string: token,
                             span: Span::new(soff, eoff),
                             line,
+                            sym: None,
                         });
                     }
                 } else if is_identifier_char(initial) {
@@ -322,6 +334,7 @@ pub fn tokenize<'a>(input: &'a str) -> Result<Vec<Token<'a>>, TokenizeError> {
                         string: token,
                         span: Span::new(soff, eoff),
                         line,
+                        sym: Some(Symbol::intern(token)),
                     });
                 } else if initial == '\\' {
                     continued = true;
@@ -336,6 +349,7 @@ pub fn tokenize<'a>(input: &'a str) -> Result<Vec<Token<'a>>, TokenizeError> {
                         string: token,
                         span: Span::new(soff, eoff),
                         line,
+                        sym: None,
                     });
                 }
             } else {
@@ -345,6 +359,7 @@ pub fn tokenize<'a>(input: &'a str) -> Result<Vec<Token<'a>>, TokenizeError> {
                     string: &line[line_pos..line_pos + 1],
                     span: Span::new(line_start + line_pos, line_start + line_pos + 1),
                     line,
+                    sym: None,
                 });
                 line_pos += 1;
             }
@@ -387,6 +402,7 @@ pub fn tokenize<'a>(input: &'a str) -> Result<Vec<Token<'a>>, TokenizeError> {
                     input_len,
                 ),
                 line: empty_end_slice,
+                sym: None,
             });
         }
     }
@@ -396,6 +412,7 @@ pub fn tokenize<'a>(input: &'a str) -> Result<Vec<Token<'a>>, TokenizeError> {
             string: empty_end_slice,
             span: Span::new(input_len, input_len),
             line: empty_end_slice,
+            sym: None,
         });
     }
     result.push(Token {
@@ -403,6 +420,7 @@ pub fn tokenize<'a>(input: &'a str) -> Result<Vec<Token<'a>>, TokenizeError> {
         string: empty_end_slice,
         span: Span::new(input_len, input_len),
         line: empty_end_slice,
+        sym: None,
     });
 
     Ok(result)
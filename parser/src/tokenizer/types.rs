@@ -4,6 +4,7 @@ use serde::{
 };
 
 use crate::span::Span;
+use crate::symbol::Symbol;
 
 /// Indicates the basic syntactic element represented by a token.
 #[derive(Serialize, Deserialize, Debug, PartialEq, Copy, Clone)]
@@ -38,4 +39,8 @@ pub struct Token<'a> {
 
     /// The text content of the line from which a token was parsed.
     pub line: &'a str,
+
+    /// The interned text content of a name token, so that names and keywords
+    /// can be compared without comparing strings.
+    pub sym: Option<Symbol>,
 }
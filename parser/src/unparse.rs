@@ -0,0 +1,475 @@
+//! Printing of ASTs as Vyper source code.
+//!
+//! The printed source parses to an AST equal to the printed one except for
+//! spans.  Expressions are only parenthesized where the precedence and
+//! associativity encoded in the expression parsers require it.
+
+use crate::ast::{
+    BoolOperator,
+    CompOperator,
+    ConstExpr,
+    Expr,
+    FuncStmt,
+    Module,
+    ModuleStmt,
+    Operator,
+    TypeDesc,
+    UnaryOp,
+};
+use crate::span::Spanned;
+
+const INDENT: &str = "    ";
+
+/// The precedence of operands that need no parentheses in any position.
+const ATOM_PRECEDENCE: u8 = 9;
+/// The precedence of unary operations, which are parsed by `const_factor`.
+const UNARY_PRECEDENCE: u8 = 7;
+
+/// Return the source code of a module.  Statements are separated by blank
+/// lines.
+pub fn unparse_module(module: &Module) -> String {
+    module
+        .body
+        .iter()
+        .map(|stmt| unparse_module_stmt(&stmt.node))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Return the source code of a module statement including its final newline.
+pub fn unparse_module_stmt(stmt: &ModuleStmt) -> String {
+    match stmt {
+        ModuleStmt::EventDef { name, fields } => {
+            let mut result = format!("event {}:\n", name);
+            for field in fields {
+                result += &format!(
+                    "{}{}: {}\n",
+                    INDENT,
+                    field.node.name,
+                    unparse_type_desc(&field.node.typ.node)
+                );
+            }
+
+            result
+        }
+        ModuleStmt::FieldDef { name, typ, value } => match value {
+            Some(value) => format!(
+                "{}: {} = {}\n",
+                name,
+                unparse_type_desc(&typ.node),
+                unparse_const_expr(&value.node)
+            ),
+            None => format!("{}: {}\n", name, unparse_type_desc(&typ.node)),
+        },
+        ModuleStmt::FuncDef {
+            decorators,
+            name,
+            args,
+            return_type,
+            body,
+        } => {
+            let mut result = String::new();
+            for decorator in decorators {
+                result += &format!("@{}\n", decorator.node);
+            }
+
+            let args: Vec<_> = args
+                .iter()
+                .map(|arg| {
+                    format!(
+                        "{}: {}",
+                        arg.node.name,
+                        unparse_type_desc(&arg.node.typ.node)
+                    )
+                })
+                .collect();
+            result += &format!("def {}({})", name, args.join(", "));
+            if let Some(return_type) = return_type {
+                result += &format!(" -> {}", unparse_type_desc(&return_type.node));
+            }
+            result += ":\n";
+            write_block(&mut result, body, 1);
+
+            result
+        }
+    }
+}
+
+/// Write the statements of a block indented by `depth` levels.
+fn write_block(result: &mut String, body: &[Spanned<FuncStmt>], depth: usize) {
+    for stmt in body {
+        write_func_stmt(result, &stmt.node, depth);
+    }
+}
+
+/// Return the source code of a statement in a function body including its
+/// final newline.
+pub fn unparse_func_stmt(stmt: &FuncStmt) -> String {
+    let mut result = String::new();
+    write_func_stmt(&mut result, stmt, 0);
+
+    result
+}
+
+fn write_func_stmt(result: &mut String, stmt: &FuncStmt, depth: usize) {
+    let indent = INDENT.repeat(depth);
+    let line = match stmt {
+        FuncStmt::Return { value: Some(value) } => format!("return {}", unparse_expr(&value.node)),
+        FuncStmt::Return { value: None } => "return".to_string(),
+        FuncStmt::VarDecl { name, typ, value } => match value {
+            Some(value) => format!(
+                "{}: {} = {}",
+                name,
+                unparse_type_desc(&typ.node),
+                unparse_expr(&value.node)
+            ),
+            None => format!("{}: {}", name, unparse_type_desc(&typ.node)),
+        },
+        FuncStmt::Assign { target, value } => format!(
+            "{} = {}",
+            unparse_expr(&target.node),
+            unparse_expr(&value.node)
+        ),
+        FuncStmt::AugAssign { target, op, value } => format!(
+            "{} {}= {}",
+            unparse_expr(&target.node),
+            binary_op_string(op),
+            unparse_expr(&value.node)
+        ),
+        FuncStmt::For { target, iter, body } => {
+            *result += &format!(
+                "{}for {} in {}:\n",
+                indent,
+                target,
+                unparse_expr(&iter.node)
+            );
+            write_block(result, body, depth + 1);
+            return;
+        }
+        FuncStmt::If { .. } => {
+            write_if(result, stmt, depth, "if");
+            return;
+        }
+        FuncStmt::Assert { test, msg } => match msg {
+            Some(msg) => format!(
+                "assert {}, {}",
+                unparse_expr(&test.node),
+                unparse_expr(&msg.node)
+            ),
+            None => format!("assert {}", unparse_expr(&test.node)),
+        },
+        FuncStmt::Raise { msg: Some(msg) } => format!("raise {}", unparse_expr(&msg.node)),
+        FuncStmt::Raise { msg: None } => "raise".to_string(),
+        FuncStmt::Log { event, args } => format!("log {}({})", event, unparse_args(args)),
+        FuncStmt::Expr { value } => unparse_expr(&value.node),
+        FuncStmt::Pass => "pass".to_string(),
+        FuncStmt::Break => "break".to_string(),
+        FuncStmt::Continue => "continue".to_string(),
+    };
+
+    *result += &format!("{}{}\n", indent, line);
+}
+
+/// Write an if statement introduced by `keyword`.  An `or_else` holding a
+/// single if statement is written as an `elif` clause.
+fn write_if(result: &mut String, stmt: &FuncStmt, depth: usize, keyword: &str) {
+    let indent = INDENT.repeat(depth);
+    if let FuncStmt::If {
+        test,
+        body,
+        or_else,
+    } = stmt
+    {
+        *result += &format!("{}{} {}:\n", indent, keyword, unparse_expr(&test.node));
+        write_block(result, body, depth + 1);
+
+        match or_else.as_slice() {
+            [] => {}
+            [elif @ Spanned {
+                node: FuncStmt::If { .. },
+                ..
+            }] => write_if(result, &elif.node, depth, "elif"),
+            _ => {
+                *result += &format!("{}else:\n", indent);
+                write_block(result, or_else, depth + 1);
+            }
+        }
+    }
+}
+
+/// Return the source code of a type description.
+pub fn unparse_type_desc(typ: &TypeDesc) -> String {
+    let mut result = typ.base.to_string();
+    if !typ.params.is_empty() {
+        let params: Vec<_> = typ
+            .params
+            .iter()
+            .map(|param| unparse_type_desc(&param.node))
+            .collect();
+        result += &format!("({})", params.join(", "));
+    }
+    for dim in &typ.dimensions {
+        result += &format!("[{}]", unparse_const_expr(&dim.node));
+    }
+
+    // Annotations are stored from the outside in
+    for annotation in typ.annotations.iter().rev() {
+        result = format!("{}({})", annotation, result);
+    }
+
+    result
+}
+
+/// Return the source code of a constant expression.
+pub fn unparse_const_expr(expr: &ConstExpr) -> String {
+    let mut result = String::new();
+    write_const_expr(&mut result, expr, 0);
+
+    result
+}
+
+/// Return the source string of a binary operator.
+pub fn binary_op_string(op: &Operator) -> &'static str {
+    match op {
+        Operator::Add => "+",
+        Operator::Sub => "-",
+        Operator::Mult => "*",
+        Operator::Div => "/",
+        Operator::Mod => "%",
+        Operator::Pow => "**",
+        Operator::LShift => "<<",
+        Operator::RShift => ">>",
+        Operator::BitOr => "|",
+        Operator::BitXor => "^",
+        Operator::BitAnd => "&",
+    }
+}
+
+/// Return the source string of a unary operator.
+pub fn unary_op_string(op: &UnaryOp) -> &'static str {
+    match op {
+        UnaryOp::Invert => "~",
+        UnaryOp::Not => "not",
+        UnaryOp::UAdd => "+",
+        UnaryOp::USub => "-",
+    }
+}
+
+/// Return the precedence of a binary operator.  Bitwise operators are not
+/// parsed in constant expressions and are ranked as in Python.
+fn operator_precedence(op: &Operator) -> u8 {
+    match op {
+        Operator::BitOr => 1,
+        Operator::BitXor => 2,
+        Operator::BitAnd => 3,
+        Operator::LShift | Operator::RShift => 4,
+        Operator::Add | Operator::Sub => 5,
+        Operator::Mult | Operator::Div | Operator::Mod => 6,
+        Operator::Pow => 8,
+    }
+}
+
+fn precedence(expr: &ConstExpr) -> u8 {
+    match expr {
+        ConstExpr::BinOp { op, .. } => operator_precedence(op),
+        ConstExpr::UnaryOp {
+            op: UnaryOp::Not, ..
+        } => 0,
+        ConstExpr::UnaryOp { .. } => UNARY_PRECEDENCE,
+        ConstExpr::Name { .. } | ConstExpr::Num { .. } | ConstExpr::Call { .. } => ATOM_PRECEDENCE,
+    }
+}
+
+/// Write `expr` to `result`, parenthesizing it if its precedence is lower than
+/// `min_precedence`.
+fn write_const_expr(result: &mut String, expr: &ConstExpr, min_precedence: u8) {
+    let prec = precedence(expr);
+    if prec < min_precedence {
+        result.push('(');
+    }
+
+    match expr {
+        ConstExpr::BinOp { left, op, right } => {
+            // Powers are right associative and only take an atom as their
+            // base.  All other operators are left associative.
+            let (left_prec, right_prec) = match op {
+                Operator::Pow => (ATOM_PRECEDENCE, UNARY_PRECEDENCE),
+                _ => (prec, prec + 1),
+            };
+
+            write_spanned(result, left, left_prec);
+            result.push(' ');
+            result.push_str(binary_op_string(op));
+            result.push(' ');
+            write_spanned(result, right, right_prec);
+        }
+        ConstExpr::UnaryOp { op, operand } => {
+            result.push_str(unary_op_string(op));
+            if *op == UnaryOp::Not {
+                result.push(' ');
+            }
+            write_spanned(result, operand, prec);
+        }
+        ConstExpr::Name { name } => result.push_str(name),
+        ConstExpr::Num { num } => result.push_str(num),
+        ConstExpr::Call { func, args } => {
+            result.push_str(func);
+            result.push('(');
+            for (i, arg) in args.iter().enumerate() {
+                if i > 0 {
+                    result.push_str(", ");
+                }
+                write_spanned(result, arg, 0);
+            }
+            result.push(')');
+        }
+    }
+
+    if prec < min_precedence {
+        result.push(')');
+    }
+}
+
+fn write_spanned(result: &mut String, expr: &Spanned<ConstExpr>, min_precedence: u8) {
+    write_const_expr(result, &expr.node, min_precedence)
+}
+
+/// The offset of the precedence of an operator in a function body from its
+/// precedence in a constant expression, which makes room for the boolean
+/// operators and comparisons binding more loosely than all others.
+const EXPR_PRECEDENCE_OFFSET: u8 = 4;
+
+/// Return the source code of an expression in a function body.
+pub fn unparse_expr(expr: &Expr) -> String {
+    let mut result = String::new();
+    write_expr(&mut result, expr, 0);
+
+    result
+}
+
+/// Return the source string of a boolean operator.
+pub fn bool_op_string(op: &BoolOperator) -> &'static str {
+    match op {
+        BoolOperator::And => "and",
+        BoolOperator::Or => "or",
+    }
+}
+
+/// Return the source string of a comparison operator.
+pub fn comp_op_string(op: &CompOperator) -> &'static str {
+    match op {
+        CompOperator::Eq => "==",
+        CompOperator::NotEq => "!=",
+        CompOperator::Lt => "<",
+        CompOperator::LtE => "<=",
+        CompOperator::Gt => ">",
+        CompOperator::GtE => ">=",
+    }
+}
+
+fn unparse_args(args: &[Spanned<Expr>]) -> String {
+    args.iter()
+        .map(|arg| unparse_expr(&arg.node))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn expr_precedence(expr: &Expr) -> u8 {
+    match expr {
+        Expr::BoolOp {
+            op: BoolOperator::Or,
+            ..
+        } => 1,
+        Expr::BoolOp {
+            op: BoolOperator::And,
+            ..
+        } => 2,
+        Expr::UnaryOp {
+            op: UnaryOp::Not, ..
+        } => 3,
+        Expr::CompOp { .. } => 4,
+        Expr::BinOp { op, .. } => operator_precedence(op) + EXPR_PRECEDENCE_OFFSET,
+        Expr::UnaryOp { .. } => UNARY_PRECEDENCE + EXPR_PRECEDENCE_OFFSET,
+        Expr::Call { .. }
+        | Expr::Attribute { .. }
+        | Expr::Subscript { .. }
+        | Expr::Name { .. }
+        | Expr::Num { .. }
+        | Expr::Str { .. } => ATOM_PRECEDENCE + EXPR_PRECEDENCE_OFFSET,
+    }
+}
+
+/// Write `expr` to `result`, parenthesizing it if its precedence is lower than
+/// `min_precedence`.
+fn write_expr(result: &mut String, expr: &Expr, min_precedence: u8) {
+    const PRIMARY_PRECEDENCE: u8 = ATOM_PRECEDENCE + EXPR_PRECEDENCE_OFFSET;
+
+    let prec = expr_precedence(expr);
+    if prec < min_precedence {
+        result.push('(');
+    }
+
+    match expr {
+        Expr::BoolOp { left, op, right } => {
+            write_expr(result, &left.node, prec);
+            result.push(' ');
+            result.push_str(bool_op_string(op));
+            result.push(' ');
+            write_expr(result, &right.node, prec + 1);
+        }
+        Expr::BinOp { left, op, right } => {
+            let (left_prec, right_prec) = match op {
+                Operator::Pow => (
+                    PRIMARY_PRECEDENCE,
+                    UNARY_PRECEDENCE + EXPR_PRECEDENCE_OFFSET,
+                ),
+                _ => (prec, prec + 1),
+            };
+
+            write_expr(result, &left.node, left_prec);
+            result.push(' ');
+            result.push_str(binary_op_string(op));
+            result.push(' ');
+            write_expr(result, &right.node, right_prec);
+        }
+        Expr::UnaryOp { op, operand } => {
+            result.push_str(unary_op_string(op));
+            if *op == UnaryOp::Not {
+                result.push(' ');
+            }
+            write_expr(result, &operand.node, prec);
+        }
+        // Comparisons cannot be chained, so neither operand may be one
+        Expr::CompOp { left, op, right } => {
+            write_expr(result, &left.node, prec + 1);
+            result.push(' ');
+            result.push_str(comp_op_string(op));
+            result.push(' ');
+            write_expr(result, &right.node, prec + 1);
+        }
+        Expr::Call { func, args } => {
+            write_expr(result, &func.node, PRIMARY_PRECEDENCE);
+            result.push('(');
+            result.push_str(&unparse_args(args));
+            result.push(')');
+        }
+        Expr::Attribute { value, attr } => {
+            write_expr(result, &value.node, PRIMARY_PRECEDENCE);
+            result.push('.');
+            result.push_str(attr);
+        }
+        Expr::Subscript { value, index } => {
+            write_expr(result, &value.node, PRIMARY_PRECEDENCE);
+            result.push('[');
+            write_expr(result, &index.node, 0);
+            result.push(']');
+        }
+        Expr::Name { name } => result.push_str(name),
+        Expr::Num { num } => result.push_str(num),
+        Expr::Str { string } => result.push_str(string),
+    }
+
+    if prec < min_precedence {
+        result.push(')');
+    }
+}
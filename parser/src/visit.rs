@@ -0,0 +1,408 @@
+//! Traversal of the AST.
+//!
+//! A `Visitor` is called on every node of an AST along with its span.  Each
+//! `visit_*` method defaults to the corresponding `walk_*` function, which
+//! visits the children of a node.  Implementations override the methods for the
+//! nodes they are interested in and call the `walk_*` function from them to
+//! continue into the children.  `VisitorMut` does the same for mutable ASTs.
+
+use crate::ast::{
+    ConstExpr,
+    EventField,
+    Expr,
+    FuncArg,
+    FuncStmt,
+    Module,
+    ModuleStmt,
+    TypeDesc,
+};
+use crate::span::Spanned;
+
+pub trait Visitor<'a> {
+    fn visit_module(&mut self, module: &Spanned<Module<'a>>) {
+        walk_module(self, module);
+    }
+
+    fn visit_module_stmt(&mut self, stmt: &Spanned<ModuleStmt<'a>>) {
+        walk_module_stmt(self, stmt);
+    }
+
+    fn visit_event_field(&mut self, field: &Spanned<EventField<'a>>) {
+        walk_event_field(self, field);
+    }
+
+    fn visit_type_desc(&mut self, typ: &Spanned<TypeDesc<'a>>) {
+        walk_type_desc(self, typ);
+    }
+
+    fn visit_const_expr(&mut self, expr: &Spanned<ConstExpr<'a>>) {
+        walk_const_expr(self, expr);
+    }
+
+    fn visit_func_arg(&mut self, arg: &Spanned<FuncArg<'a>>) {
+        walk_func_arg(self, arg);
+    }
+
+    fn visit_func_stmt(&mut self, stmt: &Spanned<FuncStmt<'a>>) {
+        walk_func_stmt(self, stmt);
+    }
+
+    fn visit_expr(&mut self, expr: &Spanned<Expr<'a>>) {
+        walk_expr(self, expr);
+    }
+}
+
+pub fn walk_module<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, module: &Spanned<Module<'a>>) {
+    for stmt in &module.node.body {
+        visitor.visit_module_stmt(stmt);
+    }
+}
+
+pub fn walk_module_stmt<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    stmt: &Spanned<ModuleStmt<'a>>,
+) {
+    match &stmt.node {
+        ModuleStmt::EventDef { fields, .. } => {
+            for field in fields {
+                visitor.visit_event_field(field);
+            }
+        }
+        ModuleStmt::FieldDef { typ, value, .. } => {
+            visitor.visit_type_desc(typ);
+            if let Some(value) = value {
+                visitor.visit_const_expr(value);
+            }
+        }
+        ModuleStmt::FuncDef {
+            args,
+            return_type,
+            body,
+            ..
+        } => {
+            for arg in args {
+                visitor.visit_func_arg(arg);
+            }
+            if let Some(return_type) = return_type {
+                visitor.visit_type_desc(return_type);
+            }
+            for stmt in body {
+                visitor.visit_func_stmt(stmt);
+            }
+        }
+    }
+}
+
+pub fn walk_event_field<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    field: &Spanned<EventField<'a>>,
+) {
+    visitor.visit_type_desc(&field.node.typ);
+}
+
+pub fn walk_type_desc<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, typ: &Spanned<TypeDesc<'a>>) {
+    for param in &typ.node.params {
+        visitor.visit_type_desc(param);
+    }
+    for dim in &typ.node.dimensions {
+        visitor.visit_const_expr(dim);
+    }
+}
+
+pub fn walk_const_expr<'a, V: Visitor<'a> + ?Sized>(
+    visitor: &mut V,
+    expr: &Spanned<ConstExpr<'a>>,
+) {
+    match &expr.node {
+        ConstExpr::BinOp { left, right, .. } => {
+            visitor.visit_const_expr(left);
+            visitor.visit_const_expr(right);
+        }
+        ConstExpr::UnaryOp { operand, .. } => visitor.visit_const_expr(operand),
+        ConstExpr::Call { args, .. } => {
+            for arg in args {
+                visitor.visit_const_expr(arg);
+            }
+        }
+        ConstExpr::Name { .. } | ConstExpr::Num { .. } => {}
+    }
+}
+
+pub trait VisitorMut<'a> {
+    fn visit_module_mut(&mut self, module: &mut Spanned<Module<'a>>) {
+        walk_module_mut(self, module);
+    }
+
+    fn visit_module_stmt_mut(&mut self, stmt: &mut Spanned<ModuleStmt<'a>>) {
+        walk_module_stmt_mut(self, stmt);
+    }
+
+    fn visit_event_field_mut(&mut self, field: &mut Spanned<EventField<'a>>) {
+        walk_event_field_mut(self, field);
+    }
+
+    fn visit_type_desc_mut(&mut self, typ: &mut Spanned<TypeDesc<'a>>) {
+        walk_type_desc_mut(self, typ);
+    }
+
+    fn visit_const_expr_mut(&mut self, expr: &mut Spanned<ConstExpr<'a>>) {
+        walk_const_expr_mut(self, expr);
+    }
+
+    fn visit_func_arg_mut(&mut self, arg: &mut Spanned<FuncArg<'a>>) {
+        walk_func_arg_mut(self, arg);
+    }
+
+    fn visit_func_stmt_mut(&mut self, stmt: &mut Spanned<FuncStmt<'a>>) {
+        walk_func_stmt_mut(self, stmt);
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Spanned<Expr<'a>>) {
+        walk_expr_mut(self, expr);
+    }
+}
+
+pub fn walk_module_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    module: &mut Spanned<Module<'a>>,
+) {
+    for stmt in &mut module.node.body {
+        visitor.visit_module_stmt_mut(stmt);
+    }
+}
+
+pub fn walk_module_stmt_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    stmt: &mut Spanned<ModuleStmt<'a>>,
+) {
+    match &mut stmt.node {
+        ModuleStmt::EventDef { fields, .. } => {
+            for field in fields {
+                visitor.visit_event_field_mut(field);
+            }
+        }
+        ModuleStmt::FieldDef { typ, value, .. } => {
+            visitor.visit_type_desc_mut(typ);
+            if let Some(value) = value {
+                visitor.visit_const_expr_mut(value);
+            }
+        }
+        ModuleStmt::FuncDef {
+            args,
+            return_type,
+            body,
+            ..
+        } => {
+            for arg in args {
+                visitor.visit_func_arg_mut(arg);
+            }
+            if let Some(return_type) = return_type {
+                visitor.visit_type_desc_mut(return_type);
+            }
+            for stmt in body {
+                visitor.visit_func_stmt_mut(stmt);
+            }
+        }
+    }
+}
+
+pub fn walk_event_field_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    field: &mut Spanned<EventField<'a>>,
+) {
+    visitor.visit_type_desc_mut(&mut field.node.typ);
+}
+
+pub fn walk_type_desc_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    typ: &mut Spanned<TypeDesc<'a>>,
+) {
+    for param in &mut typ.node.params {
+        visitor.visit_type_desc_mut(param);
+    }
+    for dim in &mut typ.node.dimensions {
+        visitor.visit_const_expr_mut(dim);
+    }
+}
+
+pub fn walk_const_expr_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    expr: &mut Spanned<ConstExpr<'a>>,
+) {
+    match &mut expr.node {
+        ConstExpr::BinOp { left, right, .. } => {
+            visitor.visit_const_expr_mut(left);
+            visitor.visit_const_expr_mut(right);
+        }
+        ConstExpr::UnaryOp { operand, .. } => visitor.visit_const_expr_mut(operand),
+        ConstExpr::Call { args, .. } => {
+            for arg in args {
+                visitor.visit_const_expr_mut(arg);
+            }
+        }
+        ConstExpr::Name { .. } | ConstExpr::Num { .. } => {}
+    }
+}
+
+pub fn walk_func_arg<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, arg: &Spanned<FuncArg<'a>>) {
+    visitor.visit_type_desc(&arg.node.typ);
+}
+
+pub fn walk_func_stmt<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, stmt: &Spanned<FuncStmt<'a>>) {
+    match &stmt.node {
+        FuncStmt::Return { value } | FuncStmt::Raise { msg: value } => {
+            if let Some(value) = value {
+                visitor.visit_expr(value);
+            }
+        }
+        FuncStmt::VarDecl { typ, value, .. } => {
+            visitor.visit_type_desc(typ);
+            if let Some(value) = value {
+                visitor.visit_expr(value);
+            }
+        }
+        FuncStmt::Assign { target, value } | FuncStmt::AugAssign { target, value, .. } => {
+            visitor.visit_expr(target);
+            visitor.visit_expr(value);
+        }
+        FuncStmt::For { iter, body, .. } => {
+            visitor.visit_expr(iter);
+            for stmt in body {
+                visitor.visit_func_stmt(stmt);
+            }
+        }
+        FuncStmt::If {
+            test,
+            body,
+            or_else,
+        } => {
+            visitor.visit_expr(test);
+            for stmt in body.iter().chain(or_else) {
+                visitor.visit_func_stmt(stmt);
+            }
+        }
+        FuncStmt::Assert { test, msg } => {
+            visitor.visit_expr(test);
+            if let Some(msg) = msg {
+                visitor.visit_expr(msg);
+            }
+        }
+        FuncStmt::Log { args, .. } => {
+            for arg in args {
+                visitor.visit_expr(arg);
+            }
+        }
+        FuncStmt::Expr { value } => visitor.visit_expr(value),
+        FuncStmt::Pass | FuncStmt::Break | FuncStmt::Continue => {}
+    }
+}
+
+pub fn walk_expr<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, expr: &Spanned<Expr<'a>>) {
+    match &expr.node {
+        Expr::BoolOp { left, right, .. }
+        | Expr::BinOp { left, right, .. }
+        | Expr::CompOp { left, right, .. } => {
+            visitor.visit_expr(left);
+            visitor.visit_expr(right);
+        }
+        Expr::UnaryOp { operand, .. } => visitor.visit_expr(operand),
+        Expr::Call { func, args } => {
+            visitor.visit_expr(func);
+            for arg in args {
+                visitor.visit_expr(arg);
+            }
+        }
+        Expr::Attribute { value, .. } => visitor.visit_expr(value),
+        Expr::Subscript { value, index } => {
+            visitor.visit_expr(value);
+            visitor.visit_expr(index);
+        }
+        Expr::Name { .. } | Expr::Num { .. } | Expr::Str { .. } => {}
+    }
+}
+
+pub fn walk_func_arg_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    arg: &mut Spanned<FuncArg<'a>>,
+) {
+    visitor.visit_type_desc_mut(&mut arg.node.typ);
+}
+
+pub fn walk_func_stmt_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    stmt: &mut Spanned<FuncStmt<'a>>,
+) {
+    match &mut stmt.node {
+        FuncStmt::Return { value } | FuncStmt::Raise { msg: value } => {
+            if let Some(value) = value {
+                visitor.visit_expr_mut(value);
+            }
+        }
+        FuncStmt::VarDecl { typ, value, .. } => {
+            visitor.visit_type_desc_mut(typ);
+            if let Some(value) = value {
+                visitor.visit_expr_mut(value);
+            }
+        }
+        FuncStmt::Assign { target, value } | FuncStmt::AugAssign { target, value, .. } => {
+            visitor.visit_expr_mut(target);
+            visitor.visit_expr_mut(value);
+        }
+        FuncStmt::For { iter, body, .. } => {
+            visitor.visit_expr_mut(iter);
+            for stmt in body {
+                visitor.visit_func_stmt_mut(stmt);
+            }
+        }
+        FuncStmt::If {
+            test,
+            body,
+            or_else,
+        } => {
+            visitor.visit_expr_mut(test);
+            for stmt in body.iter_mut().chain(or_else) {
+                visitor.visit_func_stmt_mut(stmt);
+            }
+        }
+        FuncStmt::Assert { test, msg } => {
+            visitor.visit_expr_mut(test);
+            if let Some(msg) = msg {
+                visitor.visit_expr_mut(msg);
+            }
+        }
+        FuncStmt::Log { args, .. } => {
+            for arg in args {
+                visitor.visit_expr_mut(arg);
+            }
+        }
+        FuncStmt::Expr { value } => visitor.visit_expr_mut(value),
+        FuncStmt::Pass | FuncStmt::Break | FuncStmt::Continue => {}
+    }
+}
+
+pub fn walk_expr_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    visitor: &mut V,
+    expr: &mut Spanned<Expr<'a>>,
+) {
+    match &mut expr.node {
+        Expr::BoolOp { left, right, .. }
+        | Expr::BinOp { left, right, .. }
+        | Expr::CompOp { left, right, .. } => {
+            visitor.visit_expr_mut(left);
+            visitor.visit_expr_mut(right);
+        }
+        Expr::UnaryOp { operand, .. } => visitor.visit_expr_mut(operand),
+        Expr::Call { func, args } => {
+            visitor.visit_expr_mut(func);
+            for arg in args {
+                visitor.visit_expr_mut(arg);
+            }
+        }
+        Expr::Attribute { value, .. } => visitor.visit_expr_mut(value),
+        Expr::Subscript { value, index } => {
+            visitor.visit_expr_mut(value);
+            visitor.visit_expr_mut(index);
+        }
+        Expr::Name { .. } | Expr::Num { .. } | Expr::Str { .. } => {}
+    }
+}
@@ -0,0 +1,40 @@
+
+
+owner: address
+
+
+
+total: uint256
+event Approval:
+
+    owner: indexed(address)
+    value: uint256
+balances: map(address, uint256)
+@public
+
+@constant
+def total_supply() -> uint256:
+
+    x: uint256 = 1
+
+
+    return self.total
+
+
+---
+owner: address
+
+total: uint256
+
+event Approval:
+    owner: indexed(address)
+    value: uint256
+
+balances: map(address, uint256)
+
+@public
+@constant
+def total_supply() -> uint256:
+    x: uint256 = 1
+
+    return self.total
@@ -0,0 +1,34 @@
+# A token contract
+owner: address # the owner
+# Emitted on transfers
+event Transfer:
+  # the sender
+  sender: indexed(address)   # indexed
+      # a misaligned comment
+  value: uint256
+# Trailing comment
+@public
+def foo(
+        a: int128,  # first
+    b: int128
+) -> int128:
+    return a + \
+        b
+---
+# A token contract
+owner: address  # the owner
+
+# Emitted on transfers
+event Transfer:
+    # the sender
+    sender: indexed(address)  # indexed
+    # a misaligned comment
+    value: uint256
+
+# Trailing comment
+@public
+def foo(
+    a: int128,  # first
+    b: int128
+) -> int128:
+    return a + b
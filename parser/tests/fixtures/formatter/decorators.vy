@@ -0,0 +1,16 @@
+@ public
+@  payable
+def deposit():
+    pass
+@private
+def helper(x: uint256) -> uint256:
+    return x
+---
+@public
+@payable
+def deposit():
+    pass
+
+@private
+def helper(x: uint256) -> uint256:
+    return x
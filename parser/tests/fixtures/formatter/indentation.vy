@@ -0,0 +1,27 @@
+event Transfer:
+  sender: indexed(address)
+  receiver: indexed(address)
+  value: uint256
+struct Point:
+	x: int128
+	y: int128
+@public
+def foo(a: int128) -> int128:
+        if a > 0:
+              return a
+        return -a
+---
+event Transfer:
+    sender: indexed(address)
+    receiver: indexed(address)
+    value: uint256
+
+struct Point:
+    x: int128
+    y: int128
+
+@public
+def foo(a: int128) -> int128:
+    if a > 0:
+        return a
+    return -a
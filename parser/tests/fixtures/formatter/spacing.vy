@@ -0,0 +1,28 @@
+x:int128=5
+y : uint256[3]
+def foo(a :int128,b: int128=-1)->int128 :
+    c=a+b*2-(a**2)
+    d = self.balances [msg.sender]
+    e=foo(a,b=c)
+    f = d[1 : 3]
+    g = {a:b , c : d}
+    h = - a
+    i = a - - b
+    j = not(a) and b in [ 1,2 ]
+    assert(a >= b)
+    return c%2
+---
+x: int128 = 5
+y: uint256[3]
+
+def foo(a: int128, b: int128 = -1) -> int128:
+    c = a + b * 2 - (a ** 2)
+    d = self.balances[msg.sender]
+    e = foo(a, b=c)
+    f = d[1:3]
+    g = {a: b, c: d}
+    h = -a
+    i = a - -b
+    j = not (a) and b in [1, 2]
+    assert (a >= b)
+    return c % 2
@@ -0,0 +1,180 @@
+extern crate wasm_bindgen_test;
+
+#[macro_use]
+extern crate vyper_test_utils;
+
+use nom::error::VerboseError;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use vyper_parser::cst::{
+    Cst,
+    CstError,
+    Element,
+    Node,
+    SyntaxKind,
+};
+use vyper_parser::parsers::{
+    file_input,
+    get_parse_tokens,
+};
+
+const COMMENTED: &str = r#"# Events of the token
+# contract
+
+# Emitted on transfers
+event Transfer:
+    # The sender
+    sender: indexed(address)  # indexed
+    value: uint256[2]
+# Emitted on approvals
+event Approval:
+    owner: address
+"#;
+
+/// Return the text of the node `node` in the tree `cst`.
+fn node_text(cst: &Cst, node: &Node) -> String {
+    node.children
+        .iter()
+        .map(|el| match el {
+            Element::Node(child) => node_text(cst, child),
+            Element::Token(i) => {
+                let span = cst.tokens[*i].span;
+                cst.source[span.start..span.end].to_string()
+            }
+            Element::Whitespace(span) => cst.source[span.start..span.end].to_string(),
+        })
+        .collect()
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_round_trip_and_projection() {
+    do_with_fixtures!(
+        |filename, input, _| {
+            let cst = Cst::parse(input).unwrap();
+            assert_strings_eq!(
+                input,
+                cst.to_string(),
+                "\nRound trip failed for {}",
+                filename
+            );
+
+            let tokens = get_parse_tokens(input).unwrap();
+            let (_, module) = file_input::<VerboseError<_>>(&tokens[..]).unwrap();
+            assert_eq!(
+                cst.to_module(),
+                module.node,
+                "projection failed for {}",
+                filename
+            );
+        },
+        "fixtures/parsers/file_input/erc20_events.ron",
+        "fixtures/parsers/file_input/fields.ron",
+        "fixtures/parsers/file_input/func_stmts.ron",
+        "fixtures/parsers/file_input/greeter.ron",
+        "fixtures/parsers/file_input/many_stmt_leading_trailing.ron",
+        "fixtures/parsers/file_input/many_stmt_leading_whitespace.ron",
+        "fixtures/parsers/file_input/many_stmt_lots_of_whitespace.ron",
+        "fixtures/parsers/file_input/many_stmt_no_whitespace.ron",
+        "fixtures/parsers/file_input/map_fields.ron",
+        "fixtures/parsers/file_input/one_stmt_form_feed.ron",
+        "fixtures/parsers/file_input/one_stmt_leading_trailing.ron",
+        "fixtures/parsers/file_input/one_stmt_leading_whitespace.ron",
+        "fixtures/parsers/file_input/one_stmt_no_whitespace.ron",
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_projection_of_dimensions() {
+    let source = "event A:\n    x: indexed(uint256[N][2 * (M + 1)])  # x\n    y: bytes[ 2 ** 8 ]\n    z: bytes[max( 2 , N )]\n";
+    let cst = Cst::parse(source).unwrap();
+
+    let tokens = get_parse_tokens(source).unwrap();
+    let (_, module) = file_input::<VerboseError<_>>(&tokens[..]).unwrap();
+    assert_eq!(cst.to_module(), module.node);
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_round_trip_trivia() {
+    for source in &[
+        "",
+        "\n\n",
+        "# only a comment\n",
+        COMMENTED,
+        "event A:\n    x: int128   \n  \n\n# end\n",
+        "event A:\r\n\tx: int128\r\n",
+    ] {
+        let cst = Cst::parse(source).unwrap();
+
+        assert_eq!(&cst.to_string(), source);
+        assert_eq!(node_text(&cst, &cst.root), *source);
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_trivia_attachment() {
+    let cst = Cst::parse(COMMENTED).unwrap();
+    let stmts: Vec<_> = cst.root.child_nodes().collect();
+
+    assert_eq!(stmts.len(), 2);
+    assert!(stmts.iter().all(|stmt| stmt.kind == SyntaxKind::EventDef));
+
+    // Comments separated by a blank line still lead the statement
+    assert_eq!(
+        cst.leading_comments(stmts[0]),
+        vec![
+            "# Events of the token",
+            "# contract",
+            "# Emitted on transfers"
+        ],
+    );
+    assert_eq!(
+        cst.leading_comments(stmts[1]),
+        vec!["# Emitted on approvals"]
+    );
+
+    // Comments at the start of a block lead the block's statement
+    assert_eq!(
+        node_text(&cst, stmts[0]),
+        "# Events of the token\n# contract\n\n# Emitted on transfers\nevent Transfer:\n    # \
+         The sender\n    sender: indexed(address)  # indexed\n    value: uint256[2]\n",
+    );
+
+    let fields: Vec<_> = stmts[0].child_nodes().collect();
+    // The indentation of the first statement of a block is its INDENT token
+    assert_eq!(
+        node_text(&cst, fields[0]),
+        "sender: indexed(address)  # indexed\n"
+    );
+    assert_eq!(
+        node_text(&cst, fields[0].child_nodes().next().unwrap()),
+        "indexed(address)"
+    );
+    assert_eq!(node_text(&cst, fields[1]), "    value: uint256[2]\n");
+    assert_eq!(
+        cst.span(fields[1]).map(|s| &COMMENTED[s.start..s.end]),
+        Some("value: uint256[2]")
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_parse_errors() {
+    assert_eq!(
+        Cst::parse("event A:\n    x int128\n"),
+        Err(CstError {
+            msg: "invalid syntax",
+            offset: 15,
+        }),
+    );
+    assert_eq!(
+        Cst::parse("event A:\n    x: int128\n  y: bool\n"),
+        Err(CstError {
+            msg: "unindent does not match any outer indentation level",
+            offset: 25,
+        }),
+    );
+}
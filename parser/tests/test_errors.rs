@@ -0,0 +1,51 @@
+extern crate wasm_bindgen_test;
+
+use nom::error::{
+    VerboseError,
+    VerboseErrorKind,
+};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use vyper_parser::errors::{
+    format_user_error,
+    FormatError,
+};
+use vyper_parser::parsers::get_parse_tokens;
+use vyper_parser::source_map::SourceMap;
+use vyper_parser::span::Span;
+use vyper_parser::tokenizer::Token;
+
+#[test]
+#[wasm_bindgen_test]
+fn test_format_user_error_at_end() {
+    let source = "event Foo:\n    bar: uint256\n";
+    let mut sources = SourceMap::new();
+    let file = sources.add_file("foo.vy", source.to_string());
+
+    let tokens = get_parse_tokens(source).unwrap();
+    let end = &tokens[tokens.len() - 1..];
+
+    // An error at the endmarker after the final newline points into the empty
+    // last line
+    let err = VerboseError {
+        errors: vec![(end, VerboseErrorKind::Context("event definition"))],
+    };
+    assert_eq!(
+        format_user_error(&sources, file, err),
+        Ok("foo.vy:3:1: expected event definition:\n\n^\n".to_string()),
+    );
+
+    // Tokens from another source may lie outside of the file
+    let past_end = [Token {
+        span: Span::new(source.len() + 1, source.len() + 1),
+        ..tokens[tokens.len() - 1]
+    }];
+
+    let err = VerboseError {
+        errors: vec![(&past_end[..], VerboseErrorKind::Context("event definition"))],
+    };
+    assert!(matches!(
+        format_user_error(&sources, file, err),
+        Err(FormatError::NoLineFound(_))
+    ));
+}
@@ -0,0 +1,77 @@
+extern crate wasm_bindgen_test;
+
+#[macro_use]
+extern crate vyper_test_utils;
+
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use vyper_parser::formatter::{
+    format_source,
+    FormatError,
+};
+use vyper_parser::tokenizer::{
+    tokenize,
+    TokenType,
+};
+
+/// Return the text of the comments in `source`.
+fn comments(source: &str) -> Vec<String> {
+    tokenize(source)
+        .unwrap()
+        .into_iter()
+        .filter(|t| t.typ == TokenType::COMMENT)
+        .map(|t| t.string.trim_end().to_string())
+        .collect()
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_format_source() {
+    do_with_fixtures!(
+        |filename, input, expected| {
+            let expected = format!("{}\n", expected);
+            let actual = format_source(input).unwrap();
+
+            assert_strings_eq!(expected, actual, "\nFormatting failed for {}", filename);
+            assert_strings_eq!(
+                expected,
+                format_source(&actual).unwrap(),
+                "\nFormatting is not idempotent for {}",
+                filename,
+            );
+            assert_eq!(comments(input), comments(&actual));
+        },
+        "fixtures/formatter/blank_lines.vy",
+        "fixtures/formatter/comments.vy",
+        "fixtures/formatter/decorators.vy",
+        "fixtures/formatter/indentation.vy",
+        "fixtures/formatter/spacing.vy",
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_format_empty() {
+    assert_eq!(format_source(""), Ok("".to_string()));
+    assert_eq!(format_source("\n\n"), Ok("".to_string()));
+    assert_eq!(format_source("x: int128"), Ok("x: int128\n".to_string()));
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_format_errors() {
+    assert_eq!(
+        format_source("x = $"),
+        Err(FormatError {
+            msg: "invalid token",
+            offset: 4,
+        }),
+    );
+    assert_eq!(
+        format_source("def foo():\n    x = 1\n  y = 2\n"),
+        Err(FormatError {
+            msg: "unindent does not match any outer indentation level",
+            offset: 23,
+        }),
+    );
+}
@@ -0,0 +1,156 @@
+extern crate wasm_bindgen_test;
+
+use std::collections::HashSet;
+
+use nom::error::VerboseError;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use vyper_parser::ast::{
+    ConstExpr,
+    ModuleStmt,
+};
+use vyper_parser::node_id::{
+    NodeKind,
+    NodeMap,
+    ParentMap,
+};
+use vyper_parser::owned_ast;
+use vyper_parser::parsers::{
+    const_expr,
+    file_input,
+    get_parse_tokens,
+};
+use vyper_parser::span::Spanned;
+
+const SOURCE: &str = "event Transfer:
+    sender: indexed(address)
+    value: uint256
+
+event Approval:
+    owner: address
+";
+
+#[test]
+#[wasm_bindgen_test]
+fn test_parent_map() {
+    let tokens = get_parse_tokens(SOURCE).unwrap();
+    let (_, module) = file_input::<VerboseError<_>>(&tokens[..]).unwrap();
+    let parents = ParentMap::from_module(&module);
+
+    let ModuleStmt::EventDef { fields, .. } = &module.node.body[1].node else {
+        panic!("expected an event definition")
+    };
+    let owner = &fields[0];
+    let typ = &owner.node.typ;
+
+    assert_eq!(parents.parent(module.id), None);
+    assert_eq!(parents.parent(typ.id), Some(owner.id));
+    assert_eq!(
+        parents.ancestors(typ.id).collect::<Vec<_>>(),
+        vec![owner.id, module.node.body[1].id, module.id],
+    );
+    assert_eq!(parents.kind(typ.id), Some(NodeKind::TypeDesc));
+    assert_eq!(parents.span(owner.id), Some(owner.span));
+
+    // Find the event enclosing an offset
+    let offset = SOURCE.find("indexed").unwrap();
+    let ModuleStmt::EventDef { fields, .. } = &module.node.body[0].node else {
+        panic!("expected an event definition")
+    };
+    assert_eq!(parents.node_at(offset), Some(fields[0].node.typ.id));
+    assert_eq!(
+        parents.enclosing(offset, NodeKind::EventDef),
+        Some(module.node.body[0].id),
+    );
+    let offset = SOURCE.rfind("address").unwrap();
+    assert_eq!(parents.node_at(offset), Some(typ.id));
+    assert_eq!(
+        parents.enclosing(offset, NodeKind::EventDef),
+        Some(module.node.body[1].id),
+    );
+    assert_eq!(parents.enclosing(offset, NodeKind::ConstExpr), None);
+    assert_eq!(parents.node_at(SOURCE.len() + 1), None);
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_unique_ids() {
+    let source = "-(a + 2) * b ** 3";
+    let tokens = get_parse_tokens(source).unwrap();
+    let (_, expr) = const_expr::<VerboseError<_>>(&tokens[..]).unwrap();
+    let (_, reparsed) = const_expr::<VerboseError<_>>(&tokens[..]).unwrap();
+
+    // Ids are ignored when comparing nodes
+    assert_eq!(expr, reparsed);
+    assert_ne!(expr.id, reparsed.id);
+
+    let parents = ParentMap::from_const_expr(&expr);
+    let ids: HashSet<_> = (0..=source.len())
+        .filter_map(|offset| parents.node_at(offset))
+        .chain(std::iter::once(expr.id))
+        .collect();
+    assert!(!ids.contains(&reparsed.id));
+
+    // Attach information to nodes in a side table
+    let mut names = NodeMap::new();
+    for id in &ids {
+        names.insert(*id, parents.kind(*id).unwrap());
+    }
+    assert!(names.values().all(|kind| *kind == NodeKind::ConstExpr));
+
+    if let ConstExpr::BinOp { left, right, .. } = &expr.node {
+        assert_eq!(parents.parent(left.id), Some(expr.id));
+        assert_eq!(parents.parent(right.id), Some(expr.id));
+        assert!(ids.contains(&left.id) && ids.contains(&right.id));
+    } else {
+        panic!("expected a binary operation");
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_call_parents() {
+    let source = "max(a, 2)";
+    let tokens = get_parse_tokens(source).unwrap();
+    let (_, expr) = const_expr::<VerboseError<_>>(&tokens[..]).unwrap();
+    let parents = ParentMap::from_const_expr(&expr);
+
+    if let ConstExpr::Call { args, .. } = &expr.node {
+        for arg in args {
+            assert_eq!(parents.parent(arg.id), Some(expr.id));
+        }
+        assert_eq!(parents.node_at(4), Some(args[0].id));
+    } else {
+        panic!("expected a call");
+    }
+    assert_eq!(parents.node_at(1), Some(expr.id));
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_clone_ids() {
+    let source = "-(a + 2) * b ** 3";
+    let tokens = get_parse_tokens(source).unwrap();
+    let (_, expr) = const_expr::<VerboseError<_>>(&tokens[..]).unwrap();
+    let owned = Spanned::new(owned_ast::ConstExpr::from(&expr.node), expr.span);
+    let cloned = owned.clone();
+
+    // Clones compare equal but neither they nor their children share ids
+    assert_eq!(owned, cloned);
+    assert_ne!(owned.id, cloned.id);
+
+    let ids = |expr: &Spanned<owned_ast::ConstExpr>| {
+        let borrowed = Spanned {
+            node: expr.node.as_borrowed(),
+            span: expr.span,
+            id: expr.id,
+        };
+        let parents = ParentMap::from_const_expr(&borrowed);
+        (0..=source.len())
+            .filter_map(|offset| parents.node_at(offset))
+            .collect::<HashSet<_>>()
+    };
+    let (original_ids, cloned_ids) = (ids(&owned), ids(&cloned));
+    assert_eq!(original_ids.len(), cloned_ids.len());
+    assert!(original_ids.is_disjoint(&cloned_ids));
+}
@@ -0,0 +1,111 @@
+extern crate wasm_bindgen_test;
+
+#[macro_use]
+extern crate vyper_test_utils;
+
+use std::thread;
+
+use nom::error::VerboseError;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use vyper_parser::owned_ast;
+use vyper_parser::parsers::{
+    ann_type_desc,
+    const_expr,
+    file_input,
+    get_parse_tokens,
+};
+use vyper_parser::span::Spanned;
+use vyper_parser::symbol::sym;
+use vyper_test_utils::to_ron_string_pretty;
+
+#[test]
+#[wasm_bindgen_test]
+fn test_module_conversion() {
+    do_with_fixtures!(
+        |filename, input, expected_ser: &str| {
+            let tokens = get_parse_tokens(input).unwrap();
+            let (_, module) = file_input::<VerboseError<_>>(&tokens[..]).unwrap();
+            let owned = Spanned::new(owned_ast::Module::from(&module.node), module.span);
+
+            // Owned modules serialize like borrowed ones
+            assert_strings_eq!(
+                to_ron_string_pretty(&owned).unwrap(),
+                expected_ser,
+                "\nSerialization did not match for {}",
+                filename,
+            );
+            assert_eq!(owned.node.as_borrowed(), module.node, "{}", filename);
+        },
+        "fixtures/parsers/file_input/erc20_events.ron",
+        "fixtures/parsers/file_input/greeter.ron",
+        "fixtures/parsers/file_input/func_stmts.ron",
+        "fixtures/parsers/file_input/many_stmt_leading_trailing.ron",
+        "fixtures/parsers/file_input/one_stmt_form_feed.ron",
+        "fixtures/parsers/file_input/one_stmt_no_whitespace.ron",
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_expr_conversion() {
+    do_with_fixtures!(
+        |filename, input, _| {
+            let tokens = get_parse_tokens(input).unwrap();
+            let (_, expr) = const_expr::<VerboseError<_>>(&tokens[..]).unwrap();
+            let owned = owned_ast::ConstExpr::from(&expr.node);
+
+            assert_eq!(
+                to_ron_string_pretty(&owned).unwrap(),
+                to_ron_string_pretty(&expr.node).unwrap(),
+                "{}",
+                filename
+            );
+            assert_eq!(owned.as_borrowed(), expr.node, "{}", filename);
+        },
+        "fixtures/parsers/const_expr/lots_of_operators.ron",
+        "fixtures/parsers/const_expr/power_3.ron",
+        "fixtures/parsers/const_expr/unary_ops.ron",
+        "fixtures/parsers/const_expr/call_1.ron",
+    );
+
+    do_with_fixtures!(
+        |filename, input, _| {
+            let tokens = get_parse_tokens(input).unwrap();
+            let (_, typ) = ann_type_desc::<VerboseError<_>>(&tokens[..]).unwrap();
+            let owned = owned_ast::TypeDesc::from(&typ.node);
+
+            assert_eq!(owned.as_borrowed(), typ.node, "{}", filename);
+        },
+        "fixtures/parsers/ann_type_desc/dimensions.ron",
+        "fixtures/parsers/ann_type_desc/nested_annotations.ron",
+        "fixtures/parsers/ann_type_desc/map.ron",
+    );
+}
+
+#[test]
+fn test_outlives_source() {
+    let source = String::from("event Transfer:\n    value: uint256[2]\n");
+    let tokens = get_parse_tokens(&source).unwrap();
+    let (_, module) = file_input::<VerboseError<_>>(&tokens[..]).unwrap();
+    let owned = owned_ast::Module::from(&module.node);
+    drop(tokens);
+    drop(source);
+
+    let handle = thread::spawn(move || owned);
+    let owned = handle.join().unwrap();
+
+    match &owned.body[0].node {
+        owned_ast::ModuleStmt::EventDef { name, fields } => {
+            assert_eq!(name.as_str(), "Transfer");
+            assert_eq!(fields[0].node.typ.node.base, sym::UINT256);
+            assert_eq!(
+                fields[0].node.typ.node.dimensions[0].node,
+                owned_ast::ConstExpr::Num {
+                    num: "2".to_string()
+                },
+            );
+        }
+        stmt => panic!("expected an event definition, found {:?}", stmt),
+    }
+}
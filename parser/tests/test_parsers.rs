@@ -1,7 +1,7 @@
 extern crate wasm_bindgen_test;
 
 #[macro_use]
-mod utils;
+extern crate vyper_test_utils;
 
 use nom::error::{
     ErrorKind,
@@ -11,10 +11,6 @@ use nom::error::{
 use nom::Err as NomErr;
 use wasm_bindgen_test::wasm_bindgen_test;
 
-use utils::{
-    parse_test_example,
-    to_ron_string_pretty,
-};
 use vyper_parser::ast::Module;
 use vyper_parser::errors::format_debug_error;
 use vyper_parser::parsers::*;
@@ -22,6 +18,7 @@ use vyper_parser::span::{
     Span,
     Spanned,
 };
+use vyper_test_utils::to_ron_string_pretty;
 
 type SimpleError<I> = (I, ErrorKind);
 
@@ -87,13 +84,8 @@ macro_rules! assert_fixtures_parsed {
             let tokens = get_parse_tokens(inp).unwrap();
             let actual: TokenResult<_, VerboseError<_>> = $parser(&tokens[..]);
 
-            if let Err(err) = &actual {
-                match err {
-                    NomErr::Error(e) | NomErr::Failure(e) => {
-                        println!("Parsing trace:\n{}", format_debug_error(inp, e.clone()));
-                    }
-                    _ => (),
-                }
+            if let Err(NomErr::Error(e) | NomErr::Failure(e)) = &actual {
+                println!("Parsing trace:\n{}", format_debug_error(inp, e.clone()));
             }
 
             let (actual_remaining, actual_ast) = actual.unwrap();
@@ -123,6 +115,89 @@ fn test_const_expr_ok() {
         "fixtures/parsers/const_expr/power_3.ron",
         "fixtures/parsers/const_expr/lots_of_operators.ron",
         "fixtures/parsers/const_expr/unary_ops.ron",
+        "fixtures/parsers/const_expr/call_1.ron",
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_expr_ok() {
+    assert_fixtures_parsed!(
+        standalone(expr),
+        "fixtures/parsers/expr/operators.ron",
+        "fixtures/parsers/expr/comparison.ron",
+        "fixtures/parsers/expr/subscript.ron",
+        "fixtures/parsers/expr/call.ron",
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_expr_err() {
+    // Comparisons cannot be chained and strings cannot be called
+    for inp in &["a < b < c", "a == b != c", "f(,)", "x[]", "x.1", "not"] {
+        let tokens = get_parse_tokens(inp).unwrap();
+        let actual: TokenResult<_, SimpleError<_>> = standalone(expr)(&tokens[..]);
+
+        assert!(actual.is_err(), "{}", inp);
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_func_def_err() {
+    for inp in &[
+        // A body is required
+        "def f():\n",
+        "def f():\n    \n",
+        // Decorators are on their own lines
+        "@external def f():\n    pass\n",
+        // Arguments need types
+        "def f(x):\n    pass\n",
+        "def f(x: uint256,,):\n    pass\n",
+        // Statements end with a newline
+        "def f():\n    pass pass\n",
+        // Keywords are not names
+        "def f():\n    x = pass\n",
+        // Augmented assignments need a binary operator
+        "def f():\n    x //= 1\n",
+        // `else` follows a block
+        "def f():\n    else:\n        pass\n",
+    ] {
+        let tokens = get_parse_tokens(inp).unwrap();
+        let actual: TokenResult<_, SimpleError<_>> = file_input(&tokens[..]);
+
+        assert!(actual.is_err(), "{:?}", inp);
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_const_call_err() {
+    // A trailing comma must follow an argument
+    for inp in &["f(,)", "f(,1)", "f(1,,)", "f(1 2)"] {
+        let tokens = get_parse_tokens(inp).unwrap();
+        let actual: TokenResult<_, SimpleError<_>> = standalone(const_expr)(&tokens[..]);
+
+        assert!(actual.is_err(), "{}", inp);
+    }
+
+    let tokens = get_parse_tokens("f()").unwrap();
+    let actual: TokenResult<_, SimpleError<_>> = standalone(const_expr)(&tokens[..]);
+    assert!(actual.is_ok());
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_ann_type_desc_ok() {
+    assert_fixtures_parsed!(
+        standalone(ann_type_desc),
+        "fixtures/parsers/ann_type_desc/base_type.ron",
+        "fixtures/parsers/ann_type_desc/dimensions.ron",
+        "fixtures/parsers/ann_type_desc/const_dimensions.ron",
+        "fixtures/parsers/ann_type_desc/annotation.ron",
+        "fixtures/parsers/ann_type_desc/nested_annotations.ron",
+        "fixtures/parsers/ann_type_desc/map.ron",
     );
 }
 
@@ -132,35 +207,26 @@ fn test_file_input_empty_file() {
     // Empty file
     assert_parser_ok!(
         file_input,
-        vec![
+        [
             (
                 "",
                 Ok((
                     empty_slice!(),
-                    Spanned {
-                        node: Module { body: vec![] },
-                        span: Span::new(0, 0),
-                    }
+                    Spanned::new(Module { body: vec![] }, Span::new(0, 0))
                 ))
             ),
             (
                 "  \t ",
                 Ok((
                     empty_slice!(),
-                    Spanned {
-                        node: Module { body: vec![] },
-                        span: Span::new(4, 4),
-                    }
+                    Spanned::new(Module { body: vec![] }, Span::new(4, 4))
                 ))
             ),
             (
                 " \n\n   \t \n \t ",
                 Ok((
                     empty_slice!(),
-                    Spanned {
-                        node: Module { body: vec![] },
-                        span: Span::new(12, 12),
-                    }
+                    Spanned::new(Module { body: vec![] }, Span::new(12, 12))
                 ))
             ),
         ],
@@ -185,8 +251,13 @@ fn test_file_input_many_stmt() {
     assert_fixtures_parsed!(
         file_input,
         "fixtures/parsers/file_input/many_stmt_no_whitespace.ron",
+        "fixtures/parsers/file_input/fields.ron",
         "fixtures/parsers/file_input/many_stmt_leading_whitespace.ron",
         "fixtures/parsers/file_input/many_stmt_leading_trailing.ron",
         "fixtures/parsers/file_input/many_stmt_lots_of_whitespace.ron",
+        "fixtures/parsers/file_input/map_fields.ron",
+        "fixtures/parsers/file_input/erc20_events.ron",
+        "fixtures/parsers/file_input/greeter.ron",
+        "fixtures/parsers/file_input/func_stmts.ron",
     );
 }
@@ -1,13 +1,16 @@
 extern crate wasm_bindgen_test;
 
 #[macro_use]
-mod utils;
+extern crate vyper_test_utils;
 
 use serde::Serialize;
 use wasm_bindgen_test::wasm_bindgen_test;
 
-use utils::parse_test_example;
 use vyper_parser::string_utils::StringPositions;
+use vyper_parser::symbol::{
+    sym,
+    Symbol,
+};
 use vyper_parser::tokenizer::{
     tokenize,
     Token,
@@ -125,3 +128,23 @@ event Test:
         assert_eq!(tokenize(input), expected);
     }
 }
+
+#[test]
+#[wasm_bindgen_test]
+fn test_tokenize_symbols() {
+    let tokens = tokenize("event Foo: # event\n    bar: 'event'\n").unwrap();
+    let symbols: Vec<_> = tokens
+        .iter()
+        .filter_map(|tok| tok.sym.map(|sym| (tok.typ, sym)))
+        .collect();
+
+    // Only names are interned
+    assert_eq!(
+        symbols,
+        vec![
+            (TokenType::NAME, sym::EVENT),
+            (TokenType::NAME, Symbol::intern("Foo")),
+            (TokenType::NAME, Symbol::intern("bar")),
+        ]
+    );
+}
@@ -3,11 +3,10 @@
 extern crate wasm_bindgen_test;
 
 #[macro_use]
-mod utils;
+extern crate vyper_test_utils;
 
 use wasm_bindgen_test::wasm_bindgen_test;
 
-use utils::parse_test_example;
 use vyper_parser::tokenizer::wasm::tokenize;
 
 fn assert_fixture_is_valid(filename: &str, input: &str, expected_ser: &str) {
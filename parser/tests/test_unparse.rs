@@ -0,0 +1,236 @@
+extern crate wasm_bindgen_test;
+
+#[macro_use]
+extern crate vyper_test_utils;
+
+use nom::error::VerboseError;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use vyper_parser::ast::{
+    ConstExpr,
+    EventField,
+    Expr,
+    FuncArg,
+    FuncStmt,
+    Module,
+    ModuleStmt,
+    TypeDesc,
+};
+use vyper_parser::formatter::format_source;
+use vyper_parser::parsers::{
+    const_expr,
+    expr,
+    file_input,
+    get_parse_tokens,
+};
+use vyper_parser::span::{
+    Span,
+    Spanned,
+};
+use vyper_parser::unparse::{
+    unparse_const_expr,
+    unparse_expr,
+    unparse_module,
+};
+use vyper_parser::visit::{
+    walk_const_expr_mut,
+    walk_event_field_mut,
+    walk_expr_mut,
+    walk_func_arg_mut,
+    walk_func_stmt_mut,
+    walk_module_mut,
+    walk_module_stmt_mut,
+    walk_type_desc_mut,
+    VisitorMut,
+};
+
+/// Clears the spans of all nodes so that ASTs parsed from different sources
+/// can be compared.
+struct SpanEraser;
+
+impl<'a> VisitorMut<'a> for SpanEraser {
+    fn visit_module_mut(&mut self, module: &mut Spanned<Module<'a>>) {
+        module.span = Span::new(0, 0);
+        walk_module_mut(self, module);
+    }
+
+    fn visit_module_stmt_mut(&mut self, stmt: &mut Spanned<ModuleStmt<'a>>) {
+        stmt.span = Span::new(0, 0);
+        walk_module_stmt_mut(self, stmt);
+    }
+
+    fn visit_event_field_mut(&mut self, field: &mut Spanned<EventField<'a>>) {
+        field.span = Span::new(0, 0);
+        walk_event_field_mut(self, field);
+    }
+
+    fn visit_type_desc_mut(&mut self, typ: &mut Spanned<TypeDesc<'a>>) {
+        typ.span = Span::new(0, 0);
+        walk_type_desc_mut(self, typ);
+    }
+
+    fn visit_const_expr_mut(&mut self, expr: &mut Spanned<ConstExpr<'a>>) {
+        expr.span = Span::new(0, 0);
+        walk_const_expr_mut(self, expr);
+    }
+
+    fn visit_func_arg_mut(&mut self, arg: &mut Spanned<FuncArg<'a>>) {
+        arg.span = Span::new(0, 0);
+        walk_func_arg_mut(self, arg);
+    }
+
+    fn visit_func_stmt_mut(&mut self, stmt: &mut Spanned<FuncStmt<'a>>) {
+        stmt.span = Span::new(0, 0);
+        walk_func_stmt_mut(self, stmt);
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Spanned<Expr<'a>>) {
+        expr.span = Span::new(0, 0);
+        walk_expr_mut(self, expr);
+    }
+}
+
+/// Assert that the constant expression in `source` is printed as `expected`
+/// and that the printed expression parses to the same AST.
+fn assert_expr_unparsed(source: &str, expected: &str) {
+    let tokens = get_parse_tokens(source).unwrap();
+    let (_, mut expr) = const_expr::<VerboseError<_>>(&tokens[..]).unwrap();
+    SpanEraser.visit_const_expr_mut(&mut expr);
+
+    let printed = unparse_const_expr(&expr.node);
+    assert_eq!(printed, expected, "printing {:?}", source);
+
+    let tokens = get_parse_tokens(&printed).unwrap();
+    let (_, mut reparsed) = const_expr::<VerboseError<_>>(&tokens[..]).unwrap();
+    SpanEraser.visit_const_expr_mut(&mut reparsed);
+    assert_eq!(reparsed, expr, "reparsing {:?}", printed);
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_unparse_const_expr() {
+    for (source, expected) in &[
+        ("1", "1"),
+        ("(x)", "x"),
+        ("a+b*c", "a + b * c"),
+        ("(a + b) * c", "(a + b) * c"),
+        ("(a - b) - c", "a - b - c"),
+        ("a - (b - c)", "a - (b - c)"),
+        ("(a * b) % c", "a * b % c"),
+        ("a / (b * c)", "a / (b * c)"),
+        ("-(a + b)", "-(a + b)"),
+        ("-(-a)", "--a"),
+        ("~(a)", "~a"),
+        ("-a * b", "-a * b"),
+        ("-(a * b)", "-(a * b)"),
+        ("a ** (b ** c)", "a ** b ** c"),
+        ("(a ** b) ** c", "(a ** b) ** c"),
+        ("(-a) ** b", "(-a) ** b"),
+        ("-(a ** b)", "-a ** b"),
+        ("a ** -b", "a ** -b"),
+        ("(a + b) ** (c * d)", "(a + b) ** (c * d)"),
+        ("a * (b ** -(c))", "a * b ** -c"),
+        ("f()", "f()"),
+        ("max((a),b,)", "max(a, b)"),
+        ("-shift(x, -(1)) ** 2", "-shift(x, -1) ** 2"),
+        ("min(a + b, c) * d", "min(a + b, c) * d"),
+    ] {
+        assert_expr_unparsed(source, expected);
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_unparse_expr() {
+    for (source, expected) in &[
+        ("(a or b) or c", "a or b or c"),
+        ("a or (b or c)", "a or (b or c)"),
+        ("(a or b) and c", "(a or b) and c"),
+        ("not (a and b)", "not (a and b)"),
+        ("(not a) and b", "not a and b"),
+        ("not (a == b)", "not a == b"),
+        ("(a < b) == c", "(a < b) == c"),
+        ("a + 1 >= (b | c)", "a + 1 >= b | c"),
+        ("(a | b) & c", "(a | b) & c"),
+        ("a ^ (b & c)", "a ^ b & c"),
+        ("(a << b) + c", "(a << b) + c"),
+        ("-(x.y) ** 2", "-x.y ** 2"),
+        ("(-x).y", "(-x).y"),
+        ("(a + b)[i]", "(a + b)[i]"),
+        ("self.balances[msg.sender]", "self.balances[msg.sender]"),
+        ("f(a)(b)", "f(a)(b)"),
+        ("concat(s, \"a\",)", "concat(s, \"a\")"),
+    ] {
+        let tokens = get_parse_tokens(source).unwrap();
+        let (_, mut parsed) = expr::<VerboseError<_>>(&tokens[..]).unwrap();
+        SpanEraser.visit_expr_mut(&mut parsed);
+
+        let printed = unparse_expr(&parsed.node);
+        assert_eq!(&printed, expected, "printing {:?}", source);
+
+        let tokens = get_parse_tokens(&printed).unwrap();
+        let (_, mut reparsed) = expr::<VerboseError<_>>(&tokens[..]).unwrap();
+        SpanEraser.visit_expr_mut(&mut reparsed);
+        assert_eq!(reparsed, parsed, "reparsing {:?}", printed);
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_unparse_fixture_exprs() {
+    do_with_fixtures!(
+        |_, input: &str, _| {
+            let tokens = get_parse_tokens(input).unwrap();
+            let (_, mut expr) = const_expr::<VerboseError<_>>(&tokens[..]).unwrap();
+            SpanEraser.visit_const_expr_mut(&mut expr);
+
+            let printed = unparse_const_expr(&expr.node);
+            assert_expr_unparsed(&printed, &printed);
+        },
+        "fixtures/parsers/const_expr/lots_of_operators.ron",
+        "fixtures/parsers/const_expr/power_1.ron",
+        "fixtures/parsers/const_expr/power_2.ron",
+        "fixtures/parsers/const_expr/power_3.ron",
+        "fixtures/parsers/const_expr/unary_ops.ron",
+        "fixtures/parsers/const_expr/call_1.ron",
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_unparse_module() {
+    let source = "event Transfer :\n  sender:indexed(address)\n  value : uint256[2][3]\nevent Empty:\n    x: bytes[100]\n";
+    let tokens = get_parse_tokens(source).unwrap();
+    let (_, module) = file_input::<VerboseError<_>>(&tokens[..]).unwrap();
+
+    assert_strings_eq!(
+        unparse_module(&module.node),
+        "event Transfer:\n    sender: indexed(address)\n    value: uint256[2][3]\n\nevent Empty:\n    x: bytes[100]\n",
+    );
+
+    do_with_fixtures!(
+        |filename, input: &str, _| {
+            let tokens = get_parse_tokens(input).unwrap();
+            let (_, mut module) = file_input::<VerboseError<_>>(&tokens[..]).unwrap();
+            SpanEraser.visit_module_mut(&mut module);
+
+            let printed = unparse_module(&module.node);
+            let tokens = get_parse_tokens(&printed).unwrap();
+            let (_, mut reparsed) = file_input::<VerboseError<_>>(&tokens[..]).unwrap();
+            SpanEraser.visit_module_mut(&mut reparsed);
+
+            assert_eq!(reparsed, module, "reparsing {}", filename);
+            assert_eq!(unparse_module(&reparsed.node), printed, "{}", filename);
+
+            // Printed modules are already formatted
+            assert_eq!(format_source(&printed).unwrap(), printed, "{}", filename);
+        },
+        "fixtures/parsers/file_input/erc20_events.ron",
+        "fixtures/parsers/file_input/fields.ron",
+        "fixtures/parsers/file_input/func_stmts.ron",
+        "fixtures/parsers/file_input/greeter.ron",
+        "fixtures/parsers/file_input/many_stmt_lots_of_whitespace.ron",
+        "fixtures/parsers/file_input/map_fields.ron",
+        "fixtures/parsers/file_input/one_stmt_form_feed.ron",
+    );
+}
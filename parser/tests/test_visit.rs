@@ -0,0 +1,256 @@
+extern crate wasm_bindgen_test;
+
+use nom::error::VerboseError;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use vyper_parser::ast::{
+    ConstExpr,
+    EventField,
+    Expr,
+    FuncStmt,
+    ModuleStmt,
+    TypeDesc,
+};
+use vyper_parser::parsers::{
+    const_expr,
+    file_input,
+    get_parse_tokens,
+};
+use vyper_parser::span::{
+    Span,
+    Spanned,
+};
+use vyper_parser::visit::{
+    walk_const_expr,
+    walk_const_expr_mut,
+    walk_event_field,
+    walk_expr,
+    walk_func_stmt,
+    walk_module_stmt,
+    Visitor,
+    VisitorMut,
+};
+
+const EVENTS: &str = r#"event Transfer:
+    sender: indexed(address)
+    value: uint256
+event Note:
+    memo: bytes[100]
+"#;
+
+/// Records the names and spans of the nodes it visits.
+#[derive(Default)]
+struct Recorder {
+    visited: Vec<(String, Span)>,
+}
+
+impl<'a> Visitor<'a> for Recorder {
+    fn visit_module_stmt(&mut self, stmt: &Spanned<ModuleStmt<'a>>) {
+        match &stmt.node {
+            ModuleStmt::EventDef { name, .. } => {
+                self.visited.push((format!("event {}", name), stmt.span))
+            }
+            ModuleStmt::FieldDef { name, .. } => self
+                .visited
+                .push((format!("field def {}", name), stmt.span)),
+            ModuleStmt::FuncDef { name, .. } => {
+                self.visited.push((format!("def {}", name), stmt.span))
+            }
+        }
+        walk_module_stmt(self, stmt);
+    }
+
+    fn visit_event_field(&mut self, field: &Spanned<EventField<'a>>) {
+        self.visited
+            .push((format!("field {}", field.node.name), field.span));
+        walk_event_field(self, field);
+    }
+
+    fn visit_type_desc(&mut self, typ: &Spanned<TypeDesc<'a>>) {
+        self.visited
+            .push((format!("type {}", typ.node.base), typ.span));
+    }
+
+    fn visit_const_expr(&mut self, expr: &Spanned<ConstExpr<'a>>) {
+        match &expr.node {
+            ConstExpr::Name { name } => self.visited.push((format!("name {}", name), expr.span)),
+            ConstExpr::Num { num } => self.visited.push((format!("num {}", num), expr.span)),
+            _ => walk_const_expr(self, expr),
+        }
+    }
+
+    fn visit_func_stmt(&mut self, stmt: &Spanned<FuncStmt<'a>>) {
+        if let FuncStmt::For { target, .. } = &stmt.node {
+            self.visited.push((format!("for {}", target), stmt.span));
+        }
+        walk_func_stmt(self, stmt);
+    }
+
+    fn visit_expr(&mut self, expr: &Spanned<Expr<'a>>) {
+        match &expr.node {
+            Expr::Name { name } => self.visited.push((format!("name {}", name), expr.span)),
+            Expr::Num { num } => self.visited.push((format!("num {}", num), expr.span)),
+            _ => walk_expr(self, expr),
+        }
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_visit_module() {
+    let tokens = get_parse_tokens(EVENTS).unwrap();
+    let (_, module) = file_input::<VerboseError<_>>(&tokens[..]).unwrap();
+    let mut recorder = Recorder::default();
+    recorder.visit_module(&module);
+
+    let visited: Vec<_> = recorder
+        .visited
+        .iter()
+        .map(|(node, span)| (node.as_str(), &EVENTS[span.start..span.end]))
+        .collect();
+    assert_eq!(
+        visited,
+        vec![
+            (
+                "event Transfer",
+                "event Transfer:\n    sender: indexed(address)\n    value: uint256"
+            ),
+            ("field sender", "sender: indexed(address)"),
+            ("type address", "indexed(address)"),
+            ("field value", "value: uint256"),
+            ("type uint256", "uint256"),
+            ("event Note", "event Note:\n    memo: bytes[100]"),
+            ("field memo", "memo: bytes[100]"),
+            ("type bytes", "bytes[100]"),
+        ]
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_visit_func_def() {
+    let source = "@external\ndef f(x: uint256) -> uint256:\n    for i in range(x):\n        x += i\n    return x\n";
+    let tokens = get_parse_tokens(source).unwrap();
+    let (_, module) = file_input::<VerboseError<_>>(&tokens[..]).unwrap();
+    let mut recorder = Recorder::default();
+    recorder.visit_module(&module);
+
+    let visited: Vec<_> = recorder
+        .visited
+        .iter()
+        .map(|(node, span)| (node.as_str(), &source[span.start..span.end]))
+        .collect();
+    assert_eq!(
+        visited,
+        vec![
+            ("def f", source.trim_end()),
+            ("type uint256", "uint256"),
+            ("type uint256", "uint256"),
+            ("for i", "for i in range(x):\n        x += i"),
+            ("name range", "range"),
+            ("name x", "x"),
+            ("name x", "x"),
+            ("name i", "i"),
+            ("name x", "x"),
+        ]
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_visit_const_expr() {
+    let source = "-(a + 2) * b ** 3";
+    let tokens = get_parse_tokens(source).unwrap();
+    let (_, expr) = const_expr::<VerboseError<_>>(&tokens[..]).unwrap();
+    let mut recorder = Recorder::default();
+    recorder.visit_const_expr(&expr);
+
+    let visited: Vec<_> = recorder
+        .visited
+        .iter()
+        .map(|(node, span)| (node.as_str(), &source[span.start..span.end]))
+        .collect();
+    assert_eq!(
+        visited,
+        vec![
+            ("name a", "a"),
+            ("num 2", "2"),
+            ("name b", "b"),
+            ("num 3", "3"),
+        ]
+    );
+
+    // Arguments of calls are visited in order
+    let source = "max(a, min(2, b))";
+    let tokens = get_parse_tokens(source).unwrap();
+    let (_, expr) = const_expr::<VerboseError<_>>(&tokens[..]).unwrap();
+    let mut recorder = Recorder::default();
+    recorder.visit_const_expr(&expr);
+
+    let visited: Vec<_> = recorder
+        .visited
+        .iter()
+        .map(|(node, span)| (node.as_str(), &source[span.start..span.end]))
+        .collect();
+    assert_eq!(
+        visited,
+        vec![("name a", "a"), ("num 2", "2"), ("name b", "b")]
+    );
+}
+
+/// Renames names and types and clears the spans of types.
+struct Renamer;
+
+impl<'a> VisitorMut<'a> for Renamer {
+    fn visit_type_desc_mut(&mut self, typ: &mut Spanned<TypeDesc<'a>>) {
+        if typ.node.base == "uint256" {
+            typ.node.base = "int128";
+        }
+        typ.span = Span::new(0, 0);
+    }
+
+    fn visit_const_expr_mut(&mut self, expr: &mut Spanned<ConstExpr<'a>>) {
+        if let ConstExpr::Name { name } = &mut expr.node {
+            *name = "renamed";
+        }
+        walk_const_expr_mut(self, expr);
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_visit_mut() {
+    let tokens = get_parse_tokens(EVENTS).unwrap();
+    let (_, mut module) = file_input::<VerboseError<_>>(&tokens[..]).unwrap();
+    Renamer.visit_module_mut(&mut module);
+
+    let mut recorder = Recorder::default();
+    recorder.visit_module(&module);
+    let types: Vec<_> = recorder
+        .visited
+        .iter()
+        .filter(|(node, _)| node.starts_with("type"))
+        .cloned()
+        .collect();
+    assert_eq!(
+        types,
+        vec![
+            ("type address".to_string(), Span::new(0, 0)),
+            ("type int128".to_string(), Span::new(0, 0)),
+            ("type bytes".to_string(), Span::new(0, 0)),
+        ]
+    );
+
+    let tokens = get_parse_tokens("x + -y * 2").unwrap();
+    let (_, mut expr) = const_expr::<VerboseError<_>>(&tokens[..]).unwrap();
+    Renamer.visit_const_expr_mut(&mut expr);
+
+    let mut recorder = Recorder::default();
+    recorder.visit_const_expr(&expr);
+    let names: Vec<_> = recorder
+        .visited
+        .iter()
+        .map(|(node, _)| node.as_str())
+        .collect();
+    assert_eq!(names, vec!["name renamed", "name renamed", "num 2"]);
+}
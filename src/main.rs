@@ -1,6 +1,282 @@
-#![feature(external_doc)]
-#![doc(include = "../README.md")]
+#![doc = include_str!("../README.md")]
+
+use std::fs;
+use std::path::Path;
+use std::process;
+
+use clap::{
+    App,
+    AppSettings,
+    Arg,
+    SubCommand,
+};
+use nom::error::VerboseError;
+
+use vyper_analyzer::bounds::module_bounds;
+use vyper_compiler::abi::build_abi_json;
+use vyper_compiler::ast_json::build_ast_json;
+use vyper_compiler::errors::CompileError;
+use vyper_compiler::storage::build_storage_layout_json;
+use vyper_compiler::yul::compile_module;
+use vyper_parser::errors::format_user_error;
+use vyper_parser::formatter::format_source;
+use vyper_parser::parsers::{
+    file_input,
+    get_parse_tokens,
+};
+use vyper_parser::source_map::{
+    FileId,
+    SourceMap,
+};
+use vyper_parser::span::Span;
+use vyper_yul::assembler::{
+    assemble_to_hex,
+    assemble_with_source_maps,
+};
+use vyper_yul::parsers::source_location_at;
+use vyper_yul::solc::{
+    compile_with,
+    find_solc,
+    Diagnostic,
+    SolcError,
+};
+
+/// The kinds of output that can be emitted by the compiler.
+const EMIT_KINDS: &[&str] = &[
+    "abi",
+    "ast-json",
+    "bytecode",
+    "loop-bounds",
+    "source-map",
+    "storage-layout",
+    "yul",
+];
+
+/// Print an error message and exit with a failure status.
+fn fail(msg: &str) -> ! {
+    eprintln!("error: {}", msg.trim_end());
+    process::exit(1);
+}
+
+/// Format a compile error with the location at which it was found in the file
+/// `file`, followed by a note for each of its labels.
+fn format_compile_error(sources: &SourceMap, file: FileId, err: &CompileError) -> String {
+    let mut msg = format!("{}: {}", sources.location(err.span.in_file(file)), err.msg);
+    for label in &err.labels {
+        msg += &format!(
+            "\n{}: note: {}",
+            sources.location(label.span.in_file(file)),
+            label.msg
+        );
+    }
+
+    msg
+}
+
+/// Format a diagnostic reported by `solc` about the YUL source `yul` with the
+/// location in the file `file` of the code it refers to, if known.
+fn format_solc_diagnostic(
+    sources: &SourceMap,
+    file: FileId,
+    yul: &str,
+    diagnostic: &Diagnostic,
+) -> String {
+    let location = diagnostic
+        .span
+        .and_then(|span| source_location_at(yul, span.start));
+
+    match location {
+        Some(span) => format!(
+            "{}: {}: {}",
+            sources.location(span.in_file(file)),
+            diagnostic.severity,
+            diagnostic.message
+        ),
+        None => format!("{}: {}", diagnostic.severity, diagnostic.message),
+    }
+}
+
+/// Compile the Vyper source file `file` into the output of kind `emit` for a
+/// contract named `name`.  Bytecode is assembled by `solc` if `solc` is given,
+/// otherwise by the built-in assembler.  The `solc` executable is taken from
+/// the given path or else searched for on `PATH`.
+fn compile(
+    name: &str,
+    sources: &SourceMap,
+    file: FileId,
+    emit: &str,
+    solc: Option<Option<&Path>>,
+) -> Result<String, String> {
+    if solc.is_some() && emit != "bytecode" {
+        return Err("--solc can only be used with --emit bytecode".to_string());
+    }
+
+    let source = &sources.file(file).source;
+    let tokens = get_parse_tokens(source).map_err(|e| format!("{:?}", e))?;
+    let (_, module) = file_input::<VerboseError<_>>(&tokens[..]).map_err(|e| match e {
+        nom::Err::Error(e) | nom::Err::Failure(e) => {
+            format_user_error(sources, file, e).unwrap_or_else(|_| "parsing failed".to_string())
+        }
+        nom::Err::Incomplete(_) => "unexpected end of input".to_string(),
+    })?;
+
+    let result = match emit {
+        "abi" => build_abi_json(&module.node),
+        "ast-json" => Ok(build_ast_json(&sources.file(file).path, source, &module)),
+        "loop-bounds" => module_bounds(&module.node)
+            .map(|report| report.render(sources, file))
+            .map_err(CompileError::from),
+        "storage-layout" => build_storage_layout_json(&module.node),
+        "yul" => compile_module(name, &module.node).map(|object| object.to_string()),
+        "bytecode" => {
+            let object = compile_module(name, &module.node)
+                .map_err(|e| format_compile_error(sources, file, &e))?;
+
+            if let Some(solc) = solc {
+                let yul = object.to_string();
+                let output = find_solc(solc)
+                    .and_then(|solc| compile_with(&solc, &yul))
+                    .map_err(|e| match e {
+                        SolcError::Compile(errors) => {
+                            errors
+                                .iter()
+                                .fold("solc reported errors:".to_string(), |msg, err| {
+                                    msg + "\n" + &format_solc_diagnostic(sources, file, &yul, err)
+                                })
+                        }
+                        e => e.to_string(),
+                    })?;
+                for warning in &output.warnings {
+                    eprintln!("{}", format_solc_diagnostic(sources, file, &yul, warning));
+                }
+
+                return Ok(output.bytecode);
+            }
+
+            return assemble_to_hex(&object).map_err(|e| format!("{} {}", e.msg, e.name));
+        }
+        "source-map" => {
+            let object = compile_module(name, &module.node)
+                .map_err(|e| format_compile_error(sources, file, &e))?;
+            let assembled =
+                assemble_with_source_maps(&object).map_err(|e| format!("{} {}", e.msg, e.name))?;
+
+            // Source maps contain no characters that need escaping in JSON
+            return Ok(format!(
+                "{{\n  \"deploy\": \"{}\",\n  \"runtime\": \"{}\"\n}}",
+                assembled.source_map, assembled.objects[0].source_map,
+            ));
+        }
+        _ => unreachable!(),
+    };
+
+    result.map_err(|e| format_compile_error(sources, file, &e))
+}
+
+/// Format the Vyper source files at `paths` in place.  In check mode, files
+/// are left unchanged and the formatter exits with a failure status if any of
+/// them are not formatted.
+fn format_files<'a>(paths: impl Iterator<Item = &'a str>, check: bool) {
+    let mut sources = SourceMap::new();
+    let mut unformatted = false;
+
+    for path in paths {
+        let source = fs::read_to_string(path)
+            .unwrap_or_else(|e| fail(&format!("could not read {}: {}", path, e)));
+        let file = sources.add_file(path, source);
+        let source = &sources.file(file).source;
+        let formatted = format_source(source).unwrap_or_else(|e| {
+            let span = Span::new(e.offset, e.offset).in_file(file);
+            fail(&format!("{}: {}", sources.location(span), e.msg))
+        });
+
+        if &formatted == source {
+            continue;
+        }
+        if check {
+            println!("would reformat {}", path);
+            unformatted = true;
+        } else {
+            fs::write(path, formatted)
+                .unwrap_or_else(|e| fail(&format!("could not write {}: {}", path, e)));
+        }
+    }
+
+    if unformatted {
+        process::exit(1);
+    }
+}
 
 fn main() {
-    println!("Hello, world!");
+    let matches = App::new("vyper")
+        .about("Compiles Vyper smart contracts")
+        .setting(AppSettings::SubcommandsNegateReqs)
+        .subcommand(
+            SubCommand::with_name("fmt")
+                .about("Formats Vyper source files in place")
+                .arg(
+                    Arg::with_name("check")
+                        .long("check")
+                        .help("Report unformatted files and fail instead of rewriting them"),
+                )
+                .arg(
+                    Arg::with_name("files")
+                        .help("The Vyper source files to format")
+                        .required(true)
+                        .multiple(true),
+                ),
+        )
+        .arg(
+            Arg::with_name("input")
+                .help("The Vyper source file to compile")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("emit")
+                .long("emit")
+                .help("The kind of output to emit")
+                .takes_value(true)
+                .possible_values(EMIT_KINDS)
+                .default_value("bytecode"),
+        )
+        .arg(
+            Arg::with_name("solc")
+                .long("solc")
+                .help(
+                    "Assemble bytecode with solc, taken from this path or else searched for on \
+                     PATH",
+                )
+                .takes_value(true)
+                .min_values(0)
+                .value_name("path"),
+        )
+        .get_matches();
+
+    if let Some(matches) = matches.subcommand_matches("fmt") {
+        format_files(
+            matches.values_of("files").unwrap(),
+            matches.is_present("check"),
+        );
+        return;
+    }
+
+    let input = matches.value_of("input").unwrap();
+    let emit = matches.value_of("emit").unwrap();
+    let solc = matches
+        .is_present("solc")
+        .then(|| matches.value_of("solc").map(Path::new));
+
+    let source = fs::read_to_string(input)
+        .unwrap_or_else(|e| fail(&format!("could not read {}: {}", input, e)));
+    let mut sources = SourceMap::new();
+    let file = sources.add_file(input, source);
+    let name = Path::new(input)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("Contract");
+
+    match compile(name, &sources, file, emit, solc) {
+        Ok(output) => println!("{}", output.trim_end()),
+        Err(msg) => fail(&msg),
+    }
 }
@@ -1,3 +1,9 @@
+//! Helpers for tests that compare results against fixture files.
+//!
+//! A fixture file holds an input and its expected serialization separated by a
+//! line containing only `---`.  Fixture paths given to the macros are relative
+//! to the test file invoking them.
+
 use std::fmt;
 
 use difference::{
@@ -46,10 +52,10 @@ impl fmt::Display for Diff {
 
 /// Compare the given strings and panic when not equal with a colorized line
 /// diff.
-#[allow(unused_macros)]
+#[macro_export]
 macro_rules! assert_strings_eq {
     ($left:expr, $right:expr,) => {{
-        assert_strings_eq!($left, $right)
+        $crate::assert_strings_eq!($left, $right)
     }};
     ($left:expr, $right:expr) => {{
         match (&($left), &($right)) {
@@ -57,7 +63,7 @@ macro_rules! assert_strings_eq {
                 if *left_val != *right_val {
                     panic!(
                         "assertion failed: `(left == right)`\ndiff:\n{}",
-                        utils::Diff::new(left_val, right_val),
+                        $crate::Diff::new(left_val, right_val),
                     )
                 }
             }
@@ -70,7 +76,7 @@ macro_rules! assert_strings_eq {
                     panic!(
                         "assertion failed: `(left == right)`: {}\ndiff:\n{}",
                         format_args!($($args)*),
-                        utils::Diff::new(left_val, right_val),
+                        $crate::Diff::new(left_val, right_val),
                     )
                 }
             }
@@ -80,14 +86,15 @@ macro_rules! assert_strings_eq {
 
 /// Convenience function to serialize objects in RON format with custom pretty
 /// printing config and struct names.
-#[allow(dead_code)]
 pub fn to_ron_string_pretty<T>(value: &T) -> ron::ser::Result<String>
 where
     T: Serialize,
 {
-    let mut config = ron::ser::PrettyConfig::default();
-    // Indent with 2 spaces
-    config.indentor = "  ".to_string();
+    let config = ron::ser::PrettyConfig {
+        // Indent with 2 spaces
+        indentor: "  ".to_string(),
+        ..Default::default()
+    };
 
     let mut serializer = ron::ser::Serializer::new(Some(config), true);
     value.serialize(&mut serializer)?;
@@ -109,14 +116,14 @@ pub fn parse_test_example<'a>(name: &'static str, input: &'a str) -> (&'a str, &
 
         // If single trailing newline is present, clip off
         match parsed.chars().last() {
-            Some(c) if c == '\n' => (input, &parsed[..parsed.len() - 1]),
+            Some('\n') => (input, &parsed[..parsed.len() - 1]),
             _ => (input, parsed),
         }
     }
 }
 
 /// Empty slice syntax is so ugly :/
-#[allow(unused_macros)]
+#[macro_export]
 macro_rules! empty_slice {
     () => {
         &[][..]
@@ -124,23 +131,23 @@ macro_rules! empty_slice {
 }
 
 /// Include a test example file and parse it.
-#[allow(unused_macros)]
+#[macro_export]
 macro_rules! include_test_example {
     ($path:expr) => {{
-        parse_test_example($path, include_str!($path))
+        $crate::parse_test_example($path, include_str!($path))
     }};
 }
 
 /// Apply the function identified by `$func` to the fixture content in the files
 /// given in `$($path),+`.
-#[allow(unused_macros)]
+#[macro_export]
 macro_rules! do_with_fixtures {
     ($func:expr, $($path:expr),+,) => {{
-        do_with_fixtures!($func, $($path),+)
+        $crate::do_with_fixtures!($func, $($path),+)
     }};
     ($func:expr, $($path:expr),+) => {{
         let fixtures = vec![
-            $(($path, include_test_example!($path))),+
+            $(($path, $crate::include_test_example!($path))),+
         ];
 
         for (filename, (input, expected_ser)) in fixtures {
@@ -0,0 +1,292 @@
+use std::fs;
+use std::path::Path;
+use std::process::{
+    Command,
+    Output,
+};
+
+use tempfile::TempDir;
+
+const TOKEN: &str = "event Transfer:
+    sender: indexed(address)
+    value: uint256
+
+DECIMALS: constant(uint256) = 2
+totalSupply: uint256
+balances: uint256[DECIMALS]
+allowed: map(address, uint256)
+";
+
+/// Run the compiler binary with the arguments `args`.
+fn vyper(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_vyper"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// Write the source `source` to `name` in a new temporary directory.
+fn write_source(name: &str, source: &str) -> (TempDir, String) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join(name);
+    fs::write(&path, source).unwrap();
+
+    (dir, path.to_str().unwrap().to_string())
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(output.status.success(), "{:?}", output);
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    assert!(!output.status.success(), "{:?}", output);
+    String::from_utf8(output.stderr.clone()).unwrap()
+}
+
+#[test]
+fn test_emit() {
+    let (_dir, path) = write_source("Token.vy", TOKEN);
+
+    let bytecode = stdout(&vyper(&[&path]));
+    assert!(bytecode.trim_end().chars().all(|c| c.is_ascii_hexdigit()));
+    assert_eq!(bytecode, stdout(&vyper(&[&path, "--emit", "bytecode"])));
+
+    let yul = stdout(&vyper(&[&path, "--emit", "yul"]));
+    assert!(yul.starts_with("object \"Token\" {"), "{}", yul);
+    assert!(yul.contains("object \"Token_deployed\""), "{}", yul);
+
+    let abi: serde_json::Value =
+        serde_json::from_str(&stdout(&vyper(&[&path, "--emit", "abi"]))).unwrap();
+    assert_eq!(abi[0]["type"], "event");
+    assert_eq!(abi[0]["name"], "Transfer");
+
+    let ast: serde_json::Value =
+        serde_json::from_str(&stdout(&vyper(&[&path, "--emit", "ast-json"]))).unwrap();
+    assert_eq!(ast["sourcePath"], path);
+    assert_eq!(ast["root"]["body"][0]["nodeType"], "EventDef");
+
+    let layout: serde_json::Value =
+        serde_json::from_str(&stdout(&vyper(&[&path, "--emit", "storage-layout"]))).unwrap();
+    let slots: Vec<_> = layout
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| {
+            (
+                f["name"].as_str().unwrap(),
+                f["type"].as_str().unwrap(),
+                f["slot"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        slots,
+        vec![
+            ("totalSupply", "uint256", 0),
+            ("balances", "uint256[2]", 1),
+            ("allowed", "map(address, uint256)", 3),
+        ]
+    );
+
+    let maps: serde_json::Value =
+        serde_json::from_str(&stdout(&vyper(&[&path, "--emit", "source-map"]))).unwrap();
+    assert!(maps["deploy"].is_string() && maps["runtime"].is_string());
+}
+
+#[test]
+fn test_emit_loop_bounds() {
+    let (_dir, path) = write_source(
+        "Loops.vy",
+        "STEPS: constant(int128) = 4
+
+@external
+def f(x: int128):
+    for i in range(x, x + STEPS):
+        for j in range(STEPS * 2):
+            pass
+",
+    );
+    assert_eq!(
+        stdout(&vyper(&[&path, "--emit", "loop-bounds"])),
+        format!(
+            "{0}:3:1: function f: at most 36 loop iterations\n  \
+             {0}:5:5: loop: 4 iterations, 4 in the worst case\n    \
+             {0}:6:9: loop: 8 iterations, 32 in the worst case\n",
+            path
+        ),
+    );
+
+    let (_dir, path) = write_source(
+        "Unbounded.vy",
+        "@external\ndef f(n: int128):\n    for i in range(n):\n        pass\n",
+    );
+    assert_eq!(
+        stderr(&vyper(&[&path, "--emit", "loop-bounds"])),
+        format!(
+            "error: {}:3:20: name is not a compile-time constant\n",
+            path
+        ),
+    );
+}
+
+#[test]
+fn test_errors() {
+    let (_dir, path) = write_source("Bad.vy", "event Foo:\n    a: uint8\n");
+    assert_eq!(
+        stderr(&vyper(&[&path, "--emit", "abi"])),
+        format!("error: {}:2:8: unknown type\n", path),
+    );
+
+    let (_dir, path) = write_source("Bad.vy", "event Foo:\n    a uint256\n");
+    assert_eq!(
+        stderr(&vyper(&[&path])),
+        format!(
+            "error: {}:1:1: expected event definition:\nevent Foo:\n^\n",
+            path
+        ),
+    );
+
+    // Errors print a note for each related location
+    let (_dir, path) = write_source("Bad.vy", "f8491: public(uint256)\nf130736: public(bool)\n");
+    assert_eq!(
+        stderr(&vyper(&[&path, "--emit", "abi"])),
+        format!(
+            "error: {0}:2:1: function selector 0x62018627 of f130736() collides with f8491()\n\
+             {0}:1:1: note: f8491() is defined here\n",
+            path
+        ),
+    );
+
+    let missing = Path::new(&path).with_file_name("Missing.vy");
+    let missing = missing.to_str().unwrap();
+    assert!(stderr(&vyper(&[missing])).starts_with(&format!("error: could not read {}", missing)));
+
+    assert!(!vyper(&[&path, "--emit", "wasm"]).status.success());
+}
+
+/// Write an executable shell script with the body `body` to `dir/solc` and
+/// return its path.
+#[cfg(unix)]
+fn stub_solc(dir: &Path, body: &str) -> String {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = dir.join("solc");
+    fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+
+    path.to_str().unwrap().to_string()
+}
+
+#[test]
+#[cfg(unix)]
+fn test_solc() {
+    let (dir, path) = write_source("Token.vy", TOKEN);
+    let input = dir.path().join("input.json");
+    let solc = stub_solc(
+        dir.path(),
+        &format!(
+            r#"test "$1" = --standard-json || exit 1
+cat > {}
+echo '{{"errors": [{{"severity": "warning", "message": "Unused"}}], "contracts": {{"input.yul": {{"Token": {{"evm": {{"bytecode": {{"object": "600160005500"}}}}}}}}}}}}'"#,
+            input.display(),
+        ),
+    );
+
+    let output = vyper(&[&path, "--solc", &solc]);
+    assert_eq!(stdout(&output), "600160005500\n");
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "warning: Unused\n"
+    );
+
+    // The YUL object of the contract was passed as standard JSON input
+    let yul = stdout(&vyper(&[&path, "--emit", "yul"]));
+    let received: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&input).unwrap()).unwrap();
+    assert_eq!(received["language"], "Yul");
+    assert_eq!(
+        received["sources"]["input.yul"]["content"],
+        serde_json::Value::String(yul.clone())
+    );
+
+    assert_eq!(
+        stderr(&vyper(&[&path, "--emit", "abi", "--solc", &solc])),
+        "error: --solc can only be used with --emit bytecode\n"
+    );
+
+    let missing = dir.path().join("missing");
+    let missing = missing.to_str().unwrap();
+    assert_eq!(
+        stderr(&vyper(&[&path, "--solc", missing])),
+        format!("error: solc executable not found at {}\n", missing)
+    );
+
+    let solc = stub_solc(
+        dir.path(),
+        r#"cat > /dev/null
+echo '{"errors": [{"severity": "error", "message": "Invalid"}]}'"#,
+    );
+    assert_eq!(
+        stderr(&vyper(&[&path, "--solc", &solc])),
+        "error: solc reported errors:\nerror: Invalid\n"
+    );
+
+    // Diagnostics are located at the Vyper code from which the YUL code they
+    // refer to was compiled
+    let start = yul.find("let ptr").unwrap();
+    let solc = stub_solc(
+        dir.path(),
+        &format!(
+            r#"cat > /dev/null
+echo '{{"errors": [{{"severity": "error", "message": "Invalid", "sourceLocation": {{"file": "input.yul", "start": {}, "end": {}}}}}, {{"severity": "error", "message": "Unlocated", "sourceLocation": {{"file": "input.yul", "start": 0, "end": 1}}}}]}}'"#,
+            start,
+            start + 7,
+        ),
+    );
+    assert_eq!(
+        stderr(&vyper(&[&path, "--solc", &solc])),
+        format!(
+            "error: solc reported errors:\n{}:1:1: error: Invalid\nerror: Unlocated\n",
+            path
+        )
+    );
+
+    // Without a path, solc is searched for on PATH
+    let with_path = |paths: &str| {
+        Command::new(env!("CARGO_BIN_EXE_vyper"))
+            .args([&path, "--solc"])
+            .env("PATH", paths)
+            .output()
+            .unwrap()
+    };
+    let paths = format!(
+        "{}:{}",
+        dir.path().display(),
+        std::env::var("PATH").unwrap_or_default()
+    );
+    assert!(stderr(&with_path(&paths)).contains("error: Invalid"));
+    assert_eq!(
+        stderr(&with_path("")),
+        "error: solc executable not found on PATH; install solc or configure its path\n"
+    );
+}
+
+#[test]
+fn test_fmt() {
+    let (_dir, path) = write_source("Token.vy", "event Transfer :\n  sender:indexed(address)\n");
+
+    let output = vyper(&["fmt", "--check", &path]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!("would reformat {}\n", path),
+    );
+
+    stdout(&vyper(&["fmt", &path]));
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "event Transfer:\n    sender: indexed(address)\n",
+    );
+    assert_eq!(stdout(&vyper(&["fmt", "--check", &path])), "");
+}
@@ -0,0 +1,198 @@
+//! Linear EVM assembly and its resolution into bytecode.
+
+use std::collections::HashMap;
+
+use vyper_parser::span::Span;
+
+use crate::assembler::opcodes::{
+    JUMPDEST,
+    PUSH1,
+};
+use crate::errors::AssemblyError;
+
+/// An identifier for a jump destination in an assembly item list.
+pub type Label = usize;
+
+/// A single item of EVM assembly.  Items other than `Op` and `Push` refer to
+/// code locations or data whose values are only known once an object's code
+/// has been laid out.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Item {
+    /// A single opcode without immediate data.
+    Op(u8),
+    /// Push a value given as big-endian bytes without leading zeros.  An empty
+    /// value pushes zero.
+    Push(Vec<u8>),
+    /// Push the code offset of a label.
+    PushLabel(Label),
+    /// Mark a jump destination.
+    Label(Label),
+    /// Push the size of a sub-object or data section.
+    PushDataSize(String),
+    /// Push the code offset of a sub-object or data section.
+    PushDataOffset(String),
+}
+
+/// The kind of jump performed by an instruction as recorded in source maps.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum JumpType {
+    /// A jump into a function.
+    Into,
+    /// A jump returning from a function.
+    Out,
+    /// Any other instruction.
+    Regular,
+}
+
+/// The origin of an assembly item in Vyper source code.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct SourceEntry {
+    /// The span of source code from which an item was generated, if any.
+    pub span: Option<Span>,
+    pub jump: JumpType,
+}
+
+/// Format the source entries of a sequence of instructions as a compressed
+/// source map in the format used by solc.  Each instruction is described by
+/// `s:l:f:j`, i.e. the start offset and length of its source span, the index
+/// of the source file, and its jump type.  Instructions are separated by `;`.
+/// Fields that are equal to those of the preceding instruction are omitted,
+/// as are trailing colons.  Instructions without a source span use `-1` for
+/// `s`, `l` and `f`.
+pub fn compress_source_map(entries: &[SourceEntry]) -> String {
+    let mut previous: Option<[String; 4]> = None;
+    let mut result = vec![];
+
+    for entry in entries {
+        let (start, len, file) = match entry.span {
+            Some(span) => (
+                span.start.to_string(),
+                (span.end - span.start).to_string(),
+                "0".to_string(),
+            ),
+            None => ("-1".to_string(), "-1".to_string(), "-1".to_string()),
+        };
+        let jump = match entry.jump {
+            JumpType::Into => "i",
+            JumpType::Out => "o",
+            JumpType::Regular => "-",
+        };
+        let fields = [start, len, file, jump.to_string()];
+
+        let mut element: Vec<&str> = fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| match &previous {
+                Some(prev) if prev[i] == *field => "",
+                _ => field.as_str(),
+            })
+            .collect();
+        while element.last() == Some(&"") {
+            element.pop();
+        }
+
+        result.push(element.join(":"));
+        previous = Some(fields);
+    }
+
+    result.join(";")
+}
+
+/// Return the value `value` as big-endian bytes without leading zeros.
+pub fn minimal_bytes(value: usize) -> Vec<u8> {
+    let bytes = (value as u64).to_be_bytes();
+    let first = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+
+    bytes[first..].to_vec()
+}
+
+/// Return the size of the bytecode for an item given the width in bytes of
+/// pushed code offsets.
+fn item_size(item: &Item, width: usize, data: &[(String, Vec<u8>)]) -> usize {
+    match item {
+        Item::Op(_) | Item::Label(_) => 1,
+        Item::Push(value) => 1 + value.len().max(1),
+        Item::PushLabel(_) | Item::PushDataOffset(_) => 1 + width,
+        Item::PushDataSize(name) => {
+            let size = data
+                .iter()
+                .find(|(n, _)| n == name)
+                .map_or(0, |(_, bytes)| bytes.len());
+
+            1 + minimal_bytes(size).len().max(1)
+        }
+    }
+}
+
+/// Append a push of `value` padded to `width` bytes.
+fn push_padded(code: &mut Vec<u8>, value: &[u8], width: usize) {
+    code.push(PUSH1 + width as u8 - 1);
+    code.resize(code.len() + width - value.len(), 0);
+    code.extend_from_slice(value);
+}
+
+/// Resolve the assembly items in `items` into bytecode followed by the named
+/// sub-objects and data sections in `data`.  Code offsets are pushed with the
+/// smallest width that can address the entire result.
+pub fn link(items: &[Item], data: &[(String, Vec<u8>)]) -> Result<Vec<u8>, AssemblyError> {
+    let data_size: usize = data.iter().map(|(_, bytes)| bytes.len()).sum();
+
+    // Find the smallest width that can address every offset
+    let mut width = 1;
+    let code_size = loop {
+        let code_size: usize = items.iter().map(|i| item_size(i, width, data)).sum();
+
+        if minimal_bytes(code_size + data_size).len() <= width {
+            break code_size;
+        }
+        width += 1;
+    };
+
+    let mut labels = HashMap::new();
+    let mut offset = 0;
+    for item in items {
+        if let Item::Label(label) = item {
+            labels.insert(*label, offset);
+        }
+        offset += item_size(item, width, data);
+    }
+
+    let find_data = |name: &str| {
+        let mut offset = code_size;
+        for (n, bytes) in data {
+            if n == name {
+                return Ok((offset, bytes.len()));
+            }
+            offset += bytes.len();
+        }
+
+        Err(AssemblyError::new("unknown object or data section", name))
+    };
+
+    let mut code = Vec::with_capacity(code_size + data_size);
+    for item in items {
+        match item {
+            Item::Op(op) => code.push(*op),
+            Item::Push(value) => push_padded(&mut code, value, value.len().max(1)),
+            Item::PushLabel(label) => {
+                let offset = labels[label];
+                push_padded(&mut code, &minimal_bytes(offset), width);
+            }
+            Item::Label(_) => code.push(JUMPDEST),
+            Item::PushDataSize(name) => {
+                let size = minimal_bytes(find_data(name)?.1);
+                push_padded(&mut code, &size, size.len().max(1));
+            }
+            Item::PushDataOffset(name) => {
+                let offset = find_data(name)?.0;
+                push_padded(&mut code, &minimal_bytes(offset), width);
+            }
+        }
+    }
+
+    for (_, bytes) in data {
+        code.extend_from_slice(bytes);
+    }
+
+    Ok(code)
+}
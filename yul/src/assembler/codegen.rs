@@ -0,0 +1,642 @@
+//! Translation of YUL code blocks into EVM assembly.
+//!
+//! Variables live on the stack and are accessed with `DUP` and `SWAP`
+//! opcodes, so the code generator keeps a model of the stack slots that are in
+//! use.  Variables are removed from the stack at the end of the block that
+//! declares them.
+//!
+//! Function definitions are hoisted to the start of their block and jumped
+//! over.  A call pushes a return label followed by its arguments in reverse
+//! order and jumps to the function.  On entry, the function pushes its return
+//! variables initialized to zero.  On exit, it moves the return variables into
+//! the place of the return label and arguments and jumps back to the caller,
+//! leaving the first return value deepest on the stack.
+
+use std::collections::HashMap;
+
+use num_bigint::BigUint;
+use num_traits::Num;
+
+use vyper_parser::span::Span;
+
+use crate::assembler::assembly::{
+    Item,
+    JumpType,
+    Label,
+    SourceEntry,
+};
+use crate::assembler::opcodes::*;
+use crate::ast::*;
+use crate::errors::AssemblyError;
+
+/// The label and signature of a user-defined function.
+#[derive(Clone, Copy)]
+struct FunctionInfo {
+    label: Label,
+    params: usize,
+    returns: usize,
+}
+
+/// The labels and stack height of the innermost enclosing for loop.
+struct LoopInfo {
+    continue_label: Label,
+    break_label: Label,
+    height: usize,
+}
+
+/// The exit label and stack height of the function whose body is being
+/// generated.
+struct FrameInfo {
+    exit_label: Label,
+    height: usize,
+}
+
+struct CodeGen<'a> {
+    items: Vec<Item>,
+    /// The source entry of each item.
+    sources: Vec<SourceEntry>,
+    /// The source location attributed to generated items.
+    location: Option<Span>,
+    next_label: Label,
+    /// Stack slots from bottom to top.  Slots holding variables are named.
+    stack: Vec<Option<&'a str>>,
+    /// Functions visible in each enclosing block, innermost last.
+    functions: Vec<HashMap<&'a str, FunctionInfo>>,
+    loops: Vec<LoopInfo>,
+    frame: Option<FrameInfo>,
+}
+
+/// Return the value of a literal as big-endian bytes without leading zeros.
+/// String literals are left-aligned in a 32 byte word.
+pub fn literal_value(literal: &Literal) -> Result<Vec<u8>, AssemblyError> {
+    let value = match literal {
+        Literal::Number(digits) => {
+            let value = match digits.strip_prefix("0x") {
+                Some(hex) => BigUint::from_str_radix(hex, 16),
+                None => BigUint::from_str_radix(digits, 10),
+            };
+            let value = value.map_err(|_| AssemblyError::new("invalid number literal", digits))?;
+
+            if value.bits() > 256 {
+                return Err(AssemblyError::new("number literal is too large", digits));
+            }
+            value
+        }
+        Literal::String(string) => left_aligned(string.as_bytes(), string)?,
+        Literal::Hex(bytes) => left_aligned(bytes, &crate::printer::hex_literal(bytes))?,
+        Literal::True => BigUint::from(1u8),
+        Literal::False => BigUint::from(0u8),
+    };
+
+    if value == BigUint::from(0u8) {
+        Ok(vec![])
+    } else {
+        Ok(value.to_bytes_be())
+    }
+}
+
+/// Interpret up to 32 bytes as the leading bytes of a word.
+fn left_aligned(bytes: &[u8], text: &str) -> Result<BigUint, AssemblyError> {
+    if bytes.len() > 32 {
+        return Err(AssemblyError::new("literal is longer than 32 bytes", text));
+    }
+
+    let mut word = bytes.to_vec();
+    word.resize(32, 0);
+
+    Ok(BigUint::from_bytes_be(&word))
+}
+
+impl<'a> CodeGen<'a> {
+    fn new() -> Self {
+        CodeGen {
+            items: vec![],
+            sources: vec![],
+            location: None,
+            next_label: 0,
+            stack: vec![],
+            functions: vec![],
+            loops: vec![],
+            frame: None,
+        }
+    }
+
+    fn new_label(&mut self) -> Label {
+        self.next_label += 1;
+        self.next_label - 1
+    }
+
+    /// Append an item attributed to the current source location.
+    fn emit(&mut self, item: Item) {
+        self.items.push(item);
+        self.sources.push(SourceEntry {
+            span: self.location,
+            jump: JumpType::Regular,
+        });
+    }
+
+    /// Set the jump type of the last emitted item.
+    fn mark_jump(&mut self, jump: JumpType) {
+        self.sources.last_mut().unwrap().jump = jump;
+    }
+
+    fn op(&mut self, op: u8) {
+        self.emit(Item::Op(op));
+    }
+
+    fn jump_to(&mut self, label: Label) {
+        self.emit(Item::PushLabel(label));
+        self.op(JUMP);
+    }
+
+    /// Push an unnamed value onto the stack.
+    fn push(&mut self, value: Vec<u8>) {
+        self.emit(Item::Push(value));
+        self.stack.push(None);
+    }
+
+    /// Emit `POP` opcodes for the slots above the stack height `height`
+    /// without updating the stack model.
+    fn emit_pops_to(&mut self, height: usize) {
+        for _ in height..self.stack.len() {
+            self.op(POP);
+        }
+    }
+
+    /// Remove the slots above the stack height `height`.
+    fn pop_to(&mut self, height: usize) {
+        self.emit_pops_to(height);
+        self.stack.truncate(height);
+    }
+
+    /// Return the depth of a variable counting from one at the top of the
+    /// stack.
+    fn depth(&self, name: &str) -> Result<usize, AssemblyError> {
+        match self.stack.iter().rposition(|s| *s == Some(name)) {
+            Some(pos) => Ok(self.stack.len() - pos),
+            None => Err(AssemblyError::new("undeclared variable", name)),
+        }
+    }
+
+    fn dup(&mut self, depth: usize, name: &str) -> Result<(), AssemblyError> {
+        if depth > MAX_STACK_ACCESS {
+            return Err(AssemblyError::new("stack too deep", name));
+        }
+        self.op(DUP1 + depth as u8 - 1);
+        self.stack.push(None);
+
+        Ok(())
+    }
+
+    /// Swap the value on top of the stack with the value `depth` slots below
+    /// it.  The stack model is not updated since slots keep their names when
+    /// values are assigned.
+    fn swap(&mut self, depth: usize, name: &str) -> Result<(), AssemblyError> {
+        if depth > MAX_STACK_ACCESS {
+            return Err(AssemblyError::new("stack too deep", name));
+        }
+        self.op(SWAP1 + depth as u8 - 1);
+
+        Ok(())
+    }
+
+    fn find_function(&self, name: &str) -> Option<FunctionInfo> {
+        self.functions
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).copied())
+    }
+
+    /// Generate code for an expression and return the number of values it
+    /// pushes.
+    fn expression(&mut self, expr: &'a Expression) -> Result<usize, AssemblyError> {
+        match expr {
+            Expression::Literal(literal) => {
+                self.push(literal_value(literal)?);
+                Ok(1)
+            }
+            Expression::Identifier(name) => {
+                let depth = self.depth(name)?;
+                self.dup(depth, name)?;
+                Ok(1)
+            }
+            Expression::FunctionCall { name, args } => self.call(name, args),
+        }
+    }
+
+    /// Generate code for an expression that must push exactly one value.
+    fn single_value(&mut self, expr: &'a Expression) -> Result<(), AssemblyError> {
+        let count = self.expression(expr)?;
+
+        if count != 1 {
+            let name = match expr {
+                Expression::FunctionCall { name, .. } => name.as_str(),
+                _ => "",
+            };
+            return Err(AssemblyError::new(
+                "expression must evaluate to a single value",
+                name,
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Generate code for the arguments of a call in reverse order so that the
+    /// first argument ends up on top of the stack.
+    fn arguments(
+        &mut self,
+        name: &str,
+        args: &'a [Expression],
+        expected: usize,
+    ) -> Result<(), AssemblyError> {
+        if args.len() != expected {
+            return Err(AssemblyError::new("wrong number of arguments", name));
+        }
+        for arg in args.iter().rev() {
+            self.single_value(arg)?;
+        }
+
+        Ok(())
+    }
+
+    fn call(&mut self, name: &'a str, args: &'a [Expression]) -> Result<usize, AssemblyError> {
+        if name == "datasize" || name == "dataoffset" {
+            let data_name = match args {
+                [Expression::Literal(Literal::String(data_name))] => data_name.clone(),
+                _ => {
+                    return Err(AssemblyError::new(
+                        "expected a single string literal argument",
+                        name,
+                    ))
+                }
+            };
+
+            self.emit(if name == "datasize" {
+                Item::PushDataSize(data_name)
+            } else {
+                Item::PushDataOffset(data_name)
+            });
+            self.stack.push(None);
+
+            return Ok(1);
+        }
+
+        if let Some(func) = self.find_function(name) {
+            let height = self.stack.len();
+            let return_label = self.new_label();
+
+            self.emit(Item::PushLabel(return_label));
+            self.stack.push(None);
+            self.arguments(name, args, func.params)?;
+            self.jump_to(func.label);
+            self.mark_jump(JumpType::Into);
+            self.emit(Item::Label(return_label));
+
+            self.stack.truncate(height);
+            self.stack.resize(height + func.returns, None);
+
+            return Ok(func.returns);
+        }
+
+        match builtin(name) {
+            Some(builtin) => {
+                self.arguments(name, args, builtin.args)?;
+                self.op(builtin.opcode);
+
+                let height = self.stack.len() - builtin.args;
+                self.stack.truncate(height);
+                self.stack.resize(height + builtin.returns, None);
+
+                Ok(builtin.returns)
+            }
+            None => Err(AssemblyError::new("unknown function", name)),
+        }
+    }
+
+    /// Generate code for a block and remove its variables from the stack.
+    /// Source locations set in the block do not apply after it.
+    fn block(&mut self, block: &'a Block) -> Result<(), AssemblyError> {
+        let height = self.stack.len();
+        let location = self.location;
+
+        self.statements(&block.statements)?;
+        self.functions.pop();
+        self.pop_to(height);
+        self.location = location;
+
+        Ok(())
+    }
+
+    /// Generate code for the statements of a block.  Function definitions are
+    /// emitted first with the source location in effect at their definition.
+    /// The caller is responsible for removing the block's function scope and
+    /// variables.
+    fn statements(&mut self, statements: &'a [Statement]) -> Result<(), AssemblyError> {
+        let mut scope = HashMap::new();
+        let mut definitions = vec![];
+        let mut location = self.location;
+
+        for stmt in statements {
+            if let Statement::SourceLocation(stmt_location) = stmt {
+                location = *stmt_location;
+            }
+
+            if let Statement::FunctionDefinition {
+                name,
+                params,
+                returns,
+                body,
+            } = stmt
+            {
+                if builtin(name).is_some() || name == "datasize" || name == "dataoffset" {
+                    return Err(AssemblyError::new("function name is reserved", name));
+                }
+
+                let info = FunctionInfo {
+                    label: self.new_label(),
+                    params: params.len(),
+                    returns: returns.len(),
+                };
+                if scope.insert(name.as_str(), info).is_some() {
+                    return Err(AssemblyError::new("duplicate function definition", name));
+                }
+                definitions.push((info.label, params, returns, body, location));
+            }
+        }
+        self.functions.push(scope);
+
+        if !definitions.is_empty() {
+            let skip_label = self.new_label();
+
+            self.jump_to(skip_label);
+            for (label, params, returns, body, location) in definitions {
+                let outer_location = std::mem::replace(&mut self.location, location);
+                self.function(label, params, returns, body)?;
+                self.location = outer_location;
+            }
+            self.emit(Item::Label(skip_label));
+        }
+
+        for stmt in statements {
+            self.statement(stmt)?;
+        }
+
+        Ok(())
+    }
+
+    /// Generate code for the body of a function.
+    fn function(
+        &mut self,
+        label: Label,
+        params: &'a [String],
+        returns: &'a [String],
+        body: &'a Block,
+    ) -> Result<(), AssemblyError> {
+        // Variables of enclosing blocks are not visible in function bodies
+        let outer_stack = std::mem::replace(&mut self.stack, vec![None]);
+        let outer_loops = std::mem::take(&mut self.loops);
+        let exit_label = self.new_label();
+
+        self.emit(Item::Label(label));
+        self.stack
+            .extend(params.iter().rev().map(|p| Some(p.as_str())));
+        for ret in returns {
+            self.push(vec![]);
+            *self.stack.last_mut().unwrap() = Some(ret);
+        }
+
+        let outer_frame = self.frame.replace(FrameInfo {
+            exit_label,
+            height: self.stack.len(),
+        });
+
+        self.block(body)?;
+        self.emit(Item::Label(exit_label));
+
+        // Move the return variables to the bottom of the frame followed by the
+        // return label, then remove the arguments
+        let mut target: Vec<_> = returns.iter().map(|r| Some(r.as_str())).collect();
+        target.push(None);
+
+        for (i, slot) in target.iter().enumerate() {
+            let pos = self.stack.iter().rposition(|s| s == slot).unwrap();
+            let top = self.stack.len() - 1;
+            let name = slot.unwrap_or("");
+
+            if pos == i {
+                continue;
+            }
+            if pos != top {
+                self.swap(top - pos, name)?;
+                self.stack.swap(pos, top);
+            }
+            if i != top {
+                self.swap(top - i, name)?;
+                self.stack.swap(i, top);
+            }
+        }
+        self.pop_to(target.len());
+        self.op(JUMP);
+        self.mark_jump(JumpType::Out);
+
+        self.stack = outer_stack;
+        self.loops = outer_loops;
+        self.frame = outer_frame;
+
+        Ok(())
+    }
+
+    fn statement(&mut self, stmt: &'a Statement) -> Result<(), AssemblyError> {
+        match stmt {
+            Statement::Block(block) => self.block(block)?,
+            Statement::FunctionDefinition { .. } => {}
+            Statement::SourceLocation(location) => self.location = *location,
+            Statement::VariableDeclaration { names, value } => {
+                for name in names {
+                    if self.depth(name).is_ok() {
+                        return Err(AssemblyError::new("variable already declared", name));
+                    }
+                }
+
+                match value {
+                    Some(value) => {
+                        if self.expression(value)? != names.len() {
+                            return Err(AssemblyError::new(
+                                "wrong number of values in variable declaration",
+                                &names[0],
+                            ));
+                        }
+                    }
+                    None => {
+                        for _ in names {
+                            self.push(vec![]);
+                        }
+                    }
+                }
+
+                let first = self.stack.len() - names.len();
+                for (slot, name) in self.stack[first..].iter_mut().zip(names) {
+                    *slot = Some(name);
+                }
+            }
+            Statement::Assignment { names, value } => {
+                if self.expression(value)? != names.len() {
+                    return Err(AssemblyError::new(
+                        "wrong number of values in assignment",
+                        &names[0],
+                    ));
+                }
+
+                // The last value is on top of the stack
+                for name in names.iter().rev() {
+                    let depth = self.depth(name)?;
+                    self.swap(depth - 1, name)?;
+                    self.op(POP);
+                    self.stack.pop();
+                }
+            }
+            Statement::If { condition, body } => {
+                let end_label = self.new_label();
+
+                self.single_value(condition)?;
+                self.op(ISZERO);
+                self.emit(Item::PushLabel(end_label));
+                self.op(JUMPI);
+                self.stack.pop();
+
+                self.block(body)?;
+                self.emit(Item::Label(end_label));
+            }
+            Statement::Expression(expr) => {
+                if self.expression(expr)? != 0 {
+                    let name = match expr {
+                        Expression::FunctionCall { name, .. } => name.as_str(),
+                        _ => "",
+                    };
+                    return Err(AssemblyError::new(
+                        "expression statements must not return values",
+                        name,
+                    ));
+                }
+            }
+            Statement::Switch {
+                expression,
+                cases,
+                default,
+            } => {
+                let end_label = self.new_label();
+                let case_labels: Vec<_> = cases.iter().map(|_| self.new_label()).collect();
+
+                self.single_value(expression)?;
+
+                for (case, label) in cases.iter().zip(&case_labels) {
+                    self.emit(Item::Op(DUP1));
+                    self.emit(Item::Push(literal_value(&case.literal)?));
+                    self.op(EQ);
+                    self.emit(Item::PushLabel(*label));
+                    self.op(JUMPI);
+                }
+                if let Some(default) = default {
+                    self.block(default)?;
+                }
+                self.jump_to(end_label);
+
+                for (case, label) in cases.iter().zip(case_labels) {
+                    self.emit(Item::Label(label));
+                    self.block(&case.body)?;
+                    self.jump_to(end_label);
+                }
+
+                self.emit(Item::Label(end_label));
+                self.pop_to(self.stack.len() - 1);
+            }
+            Statement::ForLoop {
+                pre,
+                condition,
+                post,
+                body,
+            } => {
+                let height = self.stack.len();
+                let location = self.location;
+                let start_label = self.new_label();
+                let continue_label = self.new_label();
+                let break_label = self.new_label();
+
+                // Variables declared in the initialization block are visible
+                // in the rest of the loop
+                self.statements(&pre.statements)?;
+
+                self.emit(Item::Label(start_label));
+                self.single_value(condition)?;
+                self.op(ISZERO);
+                self.emit(Item::PushLabel(break_label));
+                self.op(JUMPI);
+                self.stack.pop();
+
+                self.loops.push(LoopInfo {
+                    continue_label,
+                    break_label,
+                    height: self.stack.len(),
+                });
+                self.block(body)?;
+                self.loops.pop();
+
+                self.emit(Item::Label(continue_label));
+                self.block(post)?;
+                self.jump_to(start_label);
+
+                self.emit(Item::Label(break_label));
+                self.functions.pop();
+                self.pop_to(height);
+                self.location = location;
+            }
+            Statement::Break | Statement::Continue => {
+                let (height, label) = match self.loops.last() {
+                    Some(LoopInfo {
+                        height,
+                        continue_label,
+                        break_label,
+                    }) => {
+                        let label = match stmt {
+                            Statement::Break => *break_label,
+                            _ => *continue_label,
+                        };
+                        (*height, label)
+                    }
+                    None => {
+                        return Err(AssemblyError::new(
+                            "break or continue outside of a for loop",
+                            "",
+                        ))
+                    }
+                };
+
+                self.emit_pops_to(height);
+                self.jump_to(label);
+            }
+            Statement::Leave => {
+                let (height, label) = match &self.frame {
+                    Some(frame) => (frame.height, frame.exit_label),
+                    None => return Err(AssemblyError::new("leave outside of a function", "")),
+                };
+
+                self.emit_pops_to(height);
+                self.jump_to(label);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Generate assembly for the code block of an object along with the source
+/// entry of each item.  Execution stops at the end of the block.
+pub fn generate(code: &Block) -> Result<(Vec<Item>, Vec<SourceEntry>), AssemblyError> {
+    let mut gen = CodeGen::new();
+
+    gen.block(code)?;
+    gen.op(STOP);
+
+    Ok((gen.items, gen.sources))
+}
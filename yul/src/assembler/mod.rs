@@ -0,0 +1,85 @@
+//! Assembly of YUL objects into EVM bytecode.
+//!
+//! The code of an object is followed by its sub-objects and data sections in
+//! the order in which they are defined.  The built-in functions `datasize` and
+//! `dataoffset` refer to the size and code offset of these by name and
+//! `datacopy` copies from the code of the running object.  A contract is
+//! therefore typically deployed with an object whose code copies a nested
+//! runtime object into memory and returns it.
+//!
+//! Assembly also produces a source map for the code of each object in the
+//! compressed `s:l:f:j` format used by solc.  Instructions are mapped to the
+//! span of the most recent `/// @src` comment in effect in their block.
+
+use crate::ast::{
+    DataValue,
+    Object,
+};
+use crate::errors::AssemblyError;
+
+pub mod assembly;
+pub mod codegen;
+pub mod opcodes;
+
+/// The bytecode of an assembled object along with source maps of its code
+/// and the code of its sub-objects.
+#[derive(Debug, PartialEq)]
+pub struct Assembled {
+    pub name: String,
+    /// The bytecode of the object including its sub-objects and data.
+    pub bytecode: Vec<u8>,
+    /// The compressed source map of the object's code.  Sub-objects and data
+    /// sections following the code are not covered.
+    pub source_map: String,
+    pub objects: Vec<Assembled>,
+}
+
+/// Assemble the object `object` and its sub-objects into bytecode and source
+/// maps.
+pub fn assemble_with_source_maps(object: &Object) -> Result<Assembled, AssemblyError> {
+    let (items, sources) = codegen::generate(&object.code)?;
+
+    let mut objects = vec![];
+    let mut data = vec![];
+    for sub_object in &object.objects {
+        let assembled = assemble_with_source_maps(sub_object)?;
+
+        data.push((sub_object.name.clone(), assembled.bytecode.clone()));
+        objects.push(assembled);
+    }
+    for section in &object.data {
+        let bytes = match &section.value {
+            DataValue::Hex(bytes) => bytes.clone(),
+            DataValue::String(string) => string.as_bytes().to_vec(),
+        };
+        data.push((section.name.clone(), bytes));
+    }
+
+    for (i, (name, _)) in data.iter().enumerate() {
+        if data[..i].iter().any(|(n, _)| n == name) {
+            return Err(AssemblyError::new("duplicate object or data section", name));
+        }
+    }
+
+    Ok(Assembled {
+        name: object.name.clone(),
+        bytecode: assembly::link(&items, &data)?,
+        source_map: assembly::compress_source_map(&sources),
+        objects,
+    })
+}
+
+/// Assemble the object `object` and its sub-objects into bytecode.
+pub fn assemble(object: &Object) -> Result<Vec<u8>, AssemblyError> {
+    Ok(assemble_with_source_maps(object)?.bytecode)
+}
+
+/// Format bytecode as a string of lowercase hex digits without a `0x` prefix.
+pub fn to_hex(bytecode: &[u8]) -> String {
+    bytecode.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Assemble the object `object` into bytecode formatted as hex digits.
+pub fn assemble_to_hex(object: &Object) -> Result<String, AssemblyError> {
+    Ok(to_hex(&assemble(object)?))
+}
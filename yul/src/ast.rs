@@ -0,0 +1,188 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use vyper_parser::span::Span;
+
+/// A YUL object containing code and, optionally, nested sub-objects and data
+/// sections.  Contracts are typically compiled into an object with a deployment
+/// code block and a single nested object containing the runtime code.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Object {
+    pub name: String,
+    pub code: Block,
+    pub objects: Vec<Object>,
+    pub data: Vec<Data>,
+}
+
+/// A named data section in a YUL object.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Data {
+    pub name: String,
+    pub value: DataValue,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub enum DataValue {
+    /// Data given as a `hex"..."` literal.
+    Hex(Vec<u8>),
+    /// Data given as a string literal.
+    String(String),
+}
+
+/// A sequence of statements enclosed in curly braces.  Blocks also delimit the
+/// scope of variable and function definitions.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+pub struct Block {
+    pub statements: Vec<Statement>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub enum Statement {
+    Block(Block),
+    FunctionDefinition {
+        name: String,
+        params: Vec<String>,
+        returns: Vec<String>,
+        body: Block,
+    },
+    VariableDeclaration {
+        names: Vec<String>,
+        value: Option<Expression>,
+    },
+    Assignment {
+        names: Vec<String>,
+        value: Expression,
+    },
+    If {
+        condition: Expression,
+        body: Block,
+    },
+    Expression(Expression),
+    Switch {
+        expression: Expression,
+        cases: Vec<Case>,
+        default: Option<Block>,
+    },
+    ForLoop {
+        pre: Block,
+        condition: Expression,
+        post: Block,
+        body: Block,
+    },
+    Break,
+    Continue,
+    Leave,
+    /// Set the span of Vyper source code from which the code generated for the
+    /// following statements of the enclosing block originates.  `None` marks
+    /// code that has no corresponding source.  Written as a `/// @src` comment
+    /// in YUL source code.
+    SourceLocation(Option<Span>),
+}
+
+/// A single case in a switch statement.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Case {
+    pub literal: Literal,
+    pub body: Block,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub enum Expression {
+    FunctionCall { name: String, args: Vec<Expression> },
+    Identifier(String),
+    Literal(Literal),
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub enum Literal {
+    /// A decimal or hexadecimal number literal.  The literal's text is stored
+    /// as written.
+    Number(String),
+    /// A string literal.  The string's content is stored without escapes.
+    String(String),
+    /// A `hex"..."` literal.
+    Hex(Vec<u8>),
+    True,
+    False,
+}
+
+impl Block {
+    pub fn new(statements: Vec<Statement>) -> Self {
+        Block { statements }
+    }
+}
+
+impl Statement {
+    /// Create a declaration of a single variable.
+    pub fn declare(name: &str, value: Expression) -> Self {
+        Statement::VariableDeclaration {
+            names: vec![name.to_string()],
+            value: Some(value),
+        }
+    }
+
+    /// Create an assignment to a single variable.
+    pub fn assign(name: &str, value: Expression) -> Self {
+        Statement::Assignment {
+            names: vec![name.to_string()],
+            value,
+        }
+    }
+
+    /// Create an if statement.
+    pub fn if_(condition: Expression, body: Vec<Statement>) -> Self {
+        Statement::If {
+            condition,
+            body: Block::new(body),
+        }
+    }
+
+    /// Create a function definition.
+    pub fn function(name: &str, params: &[&str], returns: &[&str], body: Vec<Statement>) -> Self {
+        Statement::FunctionDefinition {
+            name: name.to_string(),
+            params: params.iter().map(|p| p.to_string()).collect(),
+            returns: returns.iter().map(|r| r.to_string()).collect(),
+            body: Block::new(body),
+        }
+    }
+}
+
+impl Expression {
+    /// Create a function call expression.
+    pub fn call(name: &str, args: Vec<Expression>) -> Self {
+        Expression::FunctionCall {
+            name: name.to_string(),
+            args,
+        }
+    }
+
+    /// Create an identifier expression.
+    pub fn ident(name: &str) -> Self {
+        Expression::Identifier(name.to_string())
+    }
+
+    /// Create a decimal number literal expression.
+    pub fn num(value: u64) -> Self {
+        Expression::Literal(Literal::Number(value.to_string()))
+    }
+
+    /// Create a hexadecimal number literal expression from big-endian bytes.
+    pub fn hex_num(bytes: &[u8]) -> Self {
+        let digits: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+
+        Expression::Literal(Literal::Number(format!("0x{}", digits)))
+    }
+
+    /// Create a string literal expression.
+    pub fn string(value: &str) -> Self {
+        Expression::Literal(Literal::String(value.to_string()))
+    }
+}
+
+impl From<Expression> for Statement {
+    fn from(expr: Expression) -> Self {
+        Statement::Expression(expr)
+    }
+}
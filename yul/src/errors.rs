@@ -0,0 +1,17 @@
+/// An error encountered while assembling a YUL object into EVM bytecode.
+#[derive(Debug, PartialEq)]
+pub struct AssemblyError {
+    /// A description of the error.
+    pub msg: &'static str,
+    /// The name of the variable, function, or object involved in the error.
+    pub name: String,
+}
+
+impl AssemblyError {
+    pub fn new(msg: &'static str, name: &str) -> Self {
+        AssemblyError {
+            msg,
+            name: name.to_string(),
+        }
+    }
+}
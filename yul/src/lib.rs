@@ -0,0 +1,10 @@
+//! Data types and routines for working with the [YUL intermediate
+//! representation](https://solidity.readthedocs.io/en/v0.5.13/yul.html).
+
+pub mod assembler;
+pub mod ast;
+pub mod errors;
+pub mod parsers;
+pub mod printer;
+pub mod solc;
+pub mod tokenizer;
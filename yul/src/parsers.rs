@@ -0,0 +1,688 @@
+//! Parsers that build YUL AST objects from the tokens produced by
+//! [`crate::tokenizer::tokenize`].
+//!
+//! The grammar follows the [YUL
+//! specification](https://solidity.readthedocs.io/en/v0.5.13/yul.html#specification-of-yul)
+//! for the untyped EVM dialect.  The AST does not record source positions so
+//! only the outermost node produced by a parser is spanned.
+
+use nom::branch::alt;
+use nom::combinator::{
+    map,
+    map_opt,
+    opt,
+    verify,
+};
+use nom::error::{
+    context,
+    ErrorKind,
+    ParseError,
+};
+use nom::multi::{
+    many0,
+    many1,
+    separated_nonempty_list,
+};
+use nom::sequence::{
+    pair,
+    preceded,
+};
+use nom::IResult;
+
+use vyper_parser::errors::make_error;
+use vyper_parser::span::{
+    Span,
+    Spanned,
+};
+
+use crate::ast::*;
+use crate::tokenizer::{
+    tokenize,
+    Token,
+    TokenType,
+    SOURCE_LOCATION_PREFIX,
+};
+
+pub type TokenSlice<'a> = &'a [Token<'a>];
+pub type TokenResult<'a, O, E> = IResult<TokenSlice<'a>, O, E>;
+
+/// Names that may not be used as identifiers.
+const KEYWORDS: [&str; 12] = [
+    "break", "case", "continue", "default", "false", "for", "function", "if", "leave", "let",
+    "switch", "true",
+];
+
+/// Parse a single token from a token slice.
+pub fn one_token<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, &'a Token<'a>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    match input.iter().next() {
+        None => make_error(input, ErrorKind::Eof),
+        Some(token) => Ok((&input[1..], token)),
+    }
+}
+
+/// Parse a token of a specific type from a token slice.
+pub fn token<'a, E>(typ: TokenType) -> impl Fn(TokenSlice<'a>) -> TokenResult<'a, &'a Token<'a>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    verify(one_token, move |t: &Token| t.typ == typ)
+}
+
+/// Parse a name token containing a specific string from a token slice.
+pub fn name_string<'a, E>(
+    string: &'a str,
+) -> impl Fn(TokenSlice<'a>) -> TokenResult<'a, &'a Token<'a>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    verify(token(TokenType::NAME), move |t: &Token| t.string == string)
+}
+
+/// Parse an op token containing a specific string from a token slice.
+pub fn op_string<'a, E>(
+    string: &'a str,
+) -> impl Fn(TokenSlice<'a>) -> TokenResult<'a, &'a Token<'a>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    verify(token(TokenType::OP), move |t: &Token| t.string == string)
+}
+
+/// Parse an endmarker token from a token slice.
+pub fn endmarker_token<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, &'a Token<'a>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    token(TokenType::ENDMARKER)(input)
+}
+
+/// Parse an identifier.  Keywords are not identifiers.
+pub fn identifier<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<String>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    map(
+        verify(token(TokenType::NAME), |t: &Token| {
+            !KEYWORDS.contains(&t.string)
+        }),
+        |t| Spanned::new(t.string.to_string(), t.span),
+    )(input)
+}
+
+/// Parse a comma-separated list of one or more identifiers.
+pub fn identifier_list<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<Vec<String>>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    let (input, names) = separated_nonempty_list(op_string(","), identifier)(input)?;
+
+    let span = (&names.first().unwrap().span, &names.last().unwrap().span).into();
+
+    Ok((
+        input,
+        Spanned::new(names.into_iter().map(|n| n.node).collect(), span),
+    ))
+}
+
+/// An item that may follow the code block of an object.
+enum ObjectItem {
+    Object(Object),
+    Data(Data),
+}
+
+/// Parse a YUL object as a standalone source file.
+pub fn object_input<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<Object>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    let (input, obj) = object(input)?;
+    let (input, _) = endmarker_token(input)?;
+
+    Ok((input, obj))
+}
+
+/// Parse an object definition.
+pub fn object<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<Object>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    // 'object' StringLiteral '{' Code ( Object | Data )* '}'
+    let (input, object_kw) = name_string("object")(input)?;
+    let (input, name) = context("object name", string_literal)(input)?;
+    let (input, _) = op_string("{")(input)?;
+
+    // 'code' Block
+    let (input, _) = name_string("code")(input)?;
+    let (input, code) = context("object code", block)(input)?;
+
+    let (input, items) = many0(alt((
+        map(object, |o| ObjectItem::Object(o.node)),
+        map(data, |d| ObjectItem::Data(d.node)),
+    )))(input)?;
+    let (input, r_brace) = op_string("}")(input)?;
+
+    let mut objects = vec![];
+    let mut data_sections = vec![];
+    for item in items {
+        match item {
+            ObjectItem::Object(o) => objects.push(o),
+            ObjectItem::Data(d) => data_sections.push(d),
+        }
+    }
+
+    Ok((
+        input,
+        Spanned::new(
+            Object {
+                name: name.node,
+                code: code.node,
+                objects,
+                data: data_sections,
+            },
+            (&object_kw.span, &r_brace.span).into(),
+        ),
+    ))
+}
+
+/// Parse a data section of an object.
+pub fn data<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<Data>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    // 'data' StringLiteral ( HexLiteral | StringLiteral )
+    let (input, data_kw) = name_string("data")(input)?;
+    let (input, name) = string_literal(input)?;
+    let (input, value) = alt((
+        map(hex_literal, |h| {
+            Spanned::new(DataValue::Hex(h.node), h.span)
+        }),
+        map(string_literal, |s| {
+            Spanned::new(DataValue::String(s.node), s.span)
+        }),
+    ))(input)?;
+
+    Ok((
+        input,
+        Spanned::new(
+            Data {
+                name: name.node,
+                value: value.node,
+            },
+            (&data_kw.span, &value.span).into(),
+        ),
+    ))
+}
+
+/// Parse a block of statements enclosed in curly braces.
+pub fn block<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<Block>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    let (input, l_brace) = op_string("{")(input)?;
+    let (input, statements) = many0(statement)(input)?;
+    let (input, r_brace) = op_string("}")(input)?;
+
+    Ok((
+        input,
+        Spanned::new(
+            Block {
+                statements: statements.into_iter().map(|s| s.node).collect(),
+            },
+            (&l_brace.span, &r_brace.span).into(),
+        ),
+    ))
+}
+
+/// Parse a statement.
+pub fn statement<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<Statement>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    let keyword_stmt = |input: TokenSlice<'a>| {
+        let (input, tok) = alt((
+            name_string("break"),
+            name_string("continue"),
+            name_string("leave"),
+        ))(input)?;
+
+        let stmt = match tok.string {
+            "break" => Statement::Break,
+            "continue" => Statement::Continue,
+            _ => Statement::Leave,
+        };
+
+        Ok((input, Spanned::new(stmt, tok.span)))
+    };
+
+    alt((
+        map(block, |b| Spanned::new(Statement::Block(b.node), b.span)),
+        context("function definition", function_definition),
+        context("variable declaration", variable_declaration),
+        context("if statement", if_statement),
+        context("switch statement", switch_statement),
+        context("for loop", for_loop),
+        keyword_stmt,
+        source_location,
+        assignment,
+        map(function_call, |c| {
+            Spanned::new(Statement::Expression(c.node), c.span)
+        }),
+    ))(input)
+}
+
+/// Parse the location in a `/// @src` comment.  The location is given as
+/// `<source index>:<start>:<end>` and may be followed by other text.  Code
+/// without a source location is marked with `-1:-1:-1`.
+fn parse_source_location(comment: &str) -> Option<Option<Span>> {
+    let location = comment
+        .strip_prefix(SOURCE_LOCATION_PREFIX)?
+        .split_whitespace()
+        .next()?;
+
+    if location == "-1:-1:-1" {
+        return Some(None);
+    }
+
+    let mut parts = location.split(':');
+    let (source, start, end) = (parts.next()?, parts.next()?, parts.next()?);
+    if source != "0" || parts.next().is_some() {
+        return None;
+    }
+
+    let start: usize = start.parse().ok()?;
+    let end: usize = end.parse().ok()?;
+    if start > end {
+        return None;
+    }
+
+    Some(Some(Span::new(start, end)))
+}
+
+/// Parse a `/// @src` comment annotating the following statements with a
+/// source location.
+pub fn source_location<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<Statement>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    map_opt(token(TokenType::SRCLOC), |tok: &Token| {
+        parse_source_location(tok.string)
+            .map(|location| Spanned::new(Statement::SourceLocation(location), tok.span))
+    })(input)
+}
+
+/// Return the source location in effect at byte offset `offset` of the YUL
+/// source `source`, as given by the last `/// @src` comment before it.
+/// Locations annotated inside a block end with the block.  Returns None for
+/// code without a source location or source that cannot be tokenized.
+pub fn source_location_at(source: &str, offset: usize) -> Option<Span> {
+    let mut location = None;
+    let mut outer = vec![];
+
+    for tok in tokenize(source).ok()? {
+        if tok.span.start > offset {
+            break;
+        }
+
+        match (tok.typ, tok.string) {
+            (TokenType::SRCLOC, comment) => {
+                if let Some(annotated) = parse_source_location(comment) {
+                    location = annotated;
+                }
+            }
+            (TokenType::OP, "{") => outer.push(location),
+            (TokenType::OP, "}") => location = outer.pop().flatten(),
+            _ => {}
+        }
+    }
+
+    location
+}
+
+/// Parse a function definition.
+pub fn function_definition<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<Statement>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    // 'function' Identifier '(' IdentifierList? ')' ( '->' IdentifierList )? Block
+    let (input, function_kw) = name_string("function")(input)?;
+    let (input, name) = identifier(input)?;
+    let (input, _) = op_string("(")(input)?;
+    let (input, params) = opt(identifier_list)(input)?;
+    let (input, _) = op_string(")")(input)?;
+    let (input, returns) = opt(preceded(op_string("->"), identifier_list))(input)?;
+    let (input, body) = block(input)?;
+
+    Ok((
+        input,
+        Spanned::new(
+            Statement::FunctionDefinition {
+                name: name.node,
+                params: params.map(|p| p.node).unwrap_or_default(),
+                returns: returns.map(|r| r.node).unwrap_or_default(),
+                body: body.node,
+            },
+            (&function_kw.span, &body.span).into(),
+        ),
+    ))
+}
+
+/// Parse a variable declaration with an optional initial value.
+pub fn variable_declaration<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<Statement>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    // 'let' IdentifierList ( ':=' Expression )?
+    let (input, let_kw) = name_string("let")(input)?;
+    let (input, names) = identifier_list(input)?;
+    let (input, value) = opt(preceded(op_string(":="), expression))(input)?;
+
+    let span = match &value {
+        Some(value) => (&let_kw.span, &value.span).into(),
+        None => (&let_kw.span, &names.span).into(),
+    };
+
+    Ok((
+        input,
+        Spanned::new(
+            Statement::VariableDeclaration {
+                names: names.node,
+                value: value.map(|v| v.node),
+            },
+            span,
+        ),
+    ))
+}
+
+/// Parse an assignment to one or more variables.
+pub fn assignment<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<Statement>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    // IdentifierList ':=' Expression
+    let (input, names) = identifier_list(input)?;
+    let (input, _) = op_string(":=")(input)?;
+    let (input, value) = expression(input)?;
+
+    Ok((
+        input,
+        Spanned::new(
+            Statement::Assignment {
+                names: names.node,
+                value: value.node,
+            },
+            (&names.span, &value.span).into(),
+        ),
+    ))
+}
+
+/// Parse an if statement.
+pub fn if_statement<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<Statement>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    // 'if' Expression Block
+    let (input, if_kw) = name_string("if")(input)?;
+    let (input, condition) = expression(input)?;
+    let (input, body) = block(input)?;
+
+    Ok((
+        input,
+        Spanned::new(
+            Statement::If {
+                condition: condition.node,
+                body: body.node,
+            },
+            (&if_kw.span, &body.span).into(),
+        ),
+    ))
+}
+
+/// Parse a switch statement.
+pub fn switch_statement<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<Statement>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    // 'case' Literal Block
+    let case = |input: TokenSlice<'a>| {
+        let (input, _) = name_string("case")(input)?;
+        let (input, lit) = literal(input)?;
+        let (input, body) = block(input)?;
+
+        Ok((
+            input,
+            Spanned::new(
+                Case {
+                    literal: lit.node,
+                    body: body.node,
+                },
+                body.span,
+            ),
+        ))
+    };
+    // 'default' Block
+    let default = preceded(name_string("default"), block);
+
+    // 'switch' Expression ( Case+ Default? | Default )
+    let (input, switch_kw) = name_string("switch")(input)?;
+    let (input, expr) = expression(input)?;
+    let (input, (cases, default)) = alt((
+        pair(many1(case), opt(&default)),
+        map(&default, |d| (vec![], Some(d))),
+    ))(input)?;
+
+    let last_span = match (&default, cases.last()) {
+        (Some(d), _) => d.span,
+        (None, Some(c)) => c.span,
+        (None, None) => unreachable!(),
+    };
+
+    Ok((
+        input,
+        Spanned::new(
+            Statement::Switch {
+                expression: expr.node,
+                cases: cases.into_iter().map(|c| c.node).collect(),
+                default: default.map(|d| d.node),
+            },
+            (&switch_kw.span, &last_span).into(),
+        ),
+    ))
+}
+
+/// Parse a for loop.
+pub fn for_loop<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<Statement>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    // 'for' Block Expression Block Block
+    let (input, for_kw) = name_string("for")(input)?;
+    let (input, pre) = block(input)?;
+    let (input, condition) = expression(input)?;
+    let (input, post) = block(input)?;
+    let (input, body) = block(input)?;
+
+    Ok((
+        input,
+        Spanned::new(
+            Statement::ForLoop {
+                pre: pre.node,
+                condition: condition.node,
+                post: post.node,
+                body: body.node,
+            },
+            (&for_kw.span, &body.span).into(),
+        ),
+    ))
+}
+
+/// Parse an expression.
+pub fn expression<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<Expression>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    alt((
+        function_call,
+        map(identifier, |i| {
+            Spanned::new(Expression::Identifier(i.node), i.span)
+        }),
+        map(literal, |l| {
+            Spanned::new(Expression::Literal(l.node), l.span)
+        }),
+    ))(input)
+}
+
+/// Parse a function call expression.
+pub fn function_call<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<Expression>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    // Identifier '(' ( Expression ( ',' Expression )* )? ')'
+    let (input, name) = identifier(input)?;
+    let (input, _) = op_string("(")(input)?;
+    let (input, args) = opt(separated_nonempty_list(op_string(","), expression))(input)?;
+    let (input, r_paren) = op_string(")")(input)?;
+
+    Ok((
+        input,
+        Spanned::new(
+            Expression::FunctionCall {
+                name: name.node,
+                args: args
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|a| a.node)
+                    .collect(),
+            },
+            (&name.span, &r_paren.span).into(),
+        ),
+    ))
+}
+
+/// Parse a literal.
+pub fn literal<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<Literal>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    let spanned = |node, span: Span| Spanned::new(node, span);
+
+    alt((
+        map(number_literal, move |t| {
+            spanned(Literal::Number(t.node), t.span)
+        }),
+        map(string_literal, move |t| {
+            spanned(Literal::String(t.node), t.span)
+        }),
+        map(hex_literal, move |t| spanned(Literal::Hex(t.node), t.span)),
+        map(name_string("true"), move |t| spanned(Literal::True, t.span)),
+        map(name_string("false"), move |t| {
+            spanned(Literal::False, t.span)
+        }),
+    ))(input)
+}
+
+/// Parse a decimal or hexadecimal number literal.
+pub fn number_literal<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<String>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    let is_valid = |t: &Token| match t.string.strip_prefix("0x") {
+        Some(digits) => !digits.is_empty() && digits.bytes().all(|c| c.is_ascii_hexdigit()),
+        None => t.string.bytes().all(|c| c.is_ascii_digit()),
+    };
+
+    map(verify(token(TokenType::NUMBER), is_valid), |t| {
+        Spanned::new(t.string.to_string(), t.span)
+    })(input)
+}
+
+/// Parse a string literal and resolve its escape sequences.  Fails if the
+/// resulting bytes are not valid UTF-8.
+pub fn string_literal<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<String>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    map_opt(token(TokenType::STRING), |t| {
+        let content = &t.string[1..t.string.len() - 1];
+        let bytes = unescape(content)?;
+
+        Some(Spanned::new(String::from_utf8(bytes).ok()?, t.span))
+    })(input)
+}
+
+/// Parse a `hex"..."` literal.
+pub fn hex_literal<'a, E>(input: TokenSlice<'a>) -> TokenResult<'a, Spanned<Vec<u8>>, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+{
+    map_opt(token(TokenType::HEXSTRING), |t| {
+        let digits = &t.string[4..t.string.len() - 1];
+
+        Some(Spanned::new(parse_hex(digits)?, t.span))
+    })(input)
+}
+
+/// Parse a string of hex digits, optionally separated into groups of bytes by
+/// underscores, into bytes.  Returns `None` if the digits are malformed.
+fn parse_hex(digits: &str) -> Option<Vec<u8>> {
+    let mut bytes = vec![];
+
+    for group in digits.split('_') {
+        if group.len() % 2 != 0 || (group.is_empty() && !digits.is_empty()) {
+            return None;
+        }
+        if !group.bytes().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        for i in (0..group.len()).step_by(2) {
+            bytes.push(u8::from_str_radix(&group[i..i + 2], 16).ok()?);
+        }
+    }
+
+    Some(bytes)
+}
+
+/// Resolve the escape sequences in the content of a string literal into bytes.
+/// Returns `None` if an escape sequence is malformed.
+fn unescape(content: &str) -> Option<Vec<u8>> {
+    let mut bytes = vec![];
+    let mut chars = content.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+
+        match chars.next()? {
+            '\\' => bytes.push(b'\\'),
+            '"' => bytes.push(b'"'),
+            '\'' => bytes.push(b'\''),
+            'n' => bytes.push(b'\n'),
+            'r' => bytes.push(b'\r'),
+            't' => bytes.push(b'\t'),
+            'x' => {
+                let digits: String = chars.by_ref().take(2).collect();
+                if digits.len() != 2 || !digits.bytes().all(|c| c.is_ascii_hexdigit()) {
+                    return None;
+                }
+                bytes.push(u8::from_str_radix(&digits, 16).ok()?);
+            }
+            'u' => {
+                let digits: String = chars.by_ref().take(4).collect();
+                if digits.len() != 4 || !digits.bytes().all(|c| c.is_ascii_hexdigit()) {
+                    return None;
+                }
+                let c = std::char::from_u32(u32::from_str_radix(&digits, 16).ok()?)?;
+                let mut buf = [0; 4];
+                bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            }
+            _ => return None,
+        }
+    }
+
+    Some(bytes)
+}
@@ -0,0 +1,279 @@
+//! Printing of YUL AST objects as YUL source code.
+//!
+//! All AST types implement `Display` by way of the routines in this module.
+//! Blocks are printed with one statement per line and four spaces of
+//! indentation per level of nesting.
+
+use std::fmt;
+
+use vyper_parser::span::Span;
+
+use crate::ast::*;
+
+const INDENT: &str = "    ";
+
+/// Return true if a statement contains no nested blocks and can therefore be
+/// printed on a single line.
+fn is_simple(stmt: &Statement) -> bool {
+    matches!(
+        stmt,
+        Statement::VariableDeclaration { .. }
+            | Statement::Assignment { .. }
+            | Statement::Expression(_)
+            | Statement::Break
+            | Statement::Continue
+            | Statement::Leave
+    )
+}
+
+/// Quote and escape the string `string` as a YUL string literal.  Characters
+/// other than printable ASCII characters are escaped byte-wise.
+pub fn quote(string: &str) -> String {
+    let mut result = String::with_capacity(string.len() + 2);
+
+    result.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            ' '..='~' => result.push(c),
+            _ => {
+                let mut buf = [0; 4];
+                for b in c.encode_utf8(&mut buf).bytes() {
+                    result.push_str(&format!("\\x{:02x}", b));
+                }
+            }
+        }
+    }
+    result.push('"');
+
+    result
+}
+
+/// Format the bytes in `bytes` as a YUL hex literal.
+pub fn hex_literal(bytes: &[u8]) -> String {
+    let digits: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+
+    format!("hex\"{}\"", digits)
+}
+
+/// Format a source location as a `/// @src` comment.  Locations refer to the
+/// source with index zero.
+pub fn source_location_comment(location: &Option<Span>) -> String {
+    match location {
+        Some(span) => format!("/// @src 0:{}:{}", span.start, span.end),
+        None => "/// @src -1:-1:-1".to_string(),
+    }
+}
+
+/// Join the given items into a comma-separated list.
+fn comma_list<T: fmt::Display>(items: &[T]) -> String {
+    items
+        .iter()
+        .map(|i| i.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Format a block containing only simple statements on a single line.
+fn inline_block(block: &Block) -> String {
+    if block.statements.is_empty() {
+        "{ }".to_string()
+    } else {
+        let stmts: Vec<_> = block.statements.iter().map(simple_statement).collect();
+
+        format!("{{ {} }}", stmts.join(" "))
+    }
+}
+
+/// Format a statement that contains no nested blocks.
+fn simple_statement(stmt: &Statement) -> String {
+    match stmt {
+        Statement::VariableDeclaration { names, value } => match value {
+            Some(value) => format!("let {} := {}", names.join(", "), value),
+            None => format!("let {}", names.join(", ")),
+        },
+        Statement::Assignment { names, value } => format!("{} := {}", names.join(", "), value),
+        Statement::Expression(expr) => expr.to_string(),
+        Statement::Break => "break".to_string(),
+        Statement::Continue => "continue".to_string(),
+        Statement::Leave => "leave".to_string(),
+        _ => unreachable!("statement contains nested blocks"),
+    }
+}
+
+/// Accumulates indented lines of YUL source code.
+#[derive(Default)]
+struct Printer {
+    out: String,
+    level: usize,
+}
+
+impl Printer {
+    fn line(&mut self, text: &str) {
+        for _ in 0..self.level {
+            self.out.push_str(INDENT);
+        }
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    fn statements(&mut self, stmts: &[Statement]) {
+        self.level += 1;
+        for stmt in stmts {
+            self.statement(stmt);
+        }
+        self.level -= 1;
+    }
+
+    /// Print a block preceded on its first line by the text in `header`.
+    fn block(&mut self, header: &str, block: &Block) {
+        if block.statements.is_empty() {
+            self.line(&format!("{}{{ }}", header));
+        } else {
+            self.line(&format!("{}{{", header));
+            self.statements(&block.statements);
+            self.line("}");
+        }
+    }
+
+    fn object(&mut self, object: &Object) {
+        self.line(&format!("object {} {{", quote(&object.name)));
+        self.level += 1;
+
+        self.block("code ", &object.code);
+        for sub_object in &object.objects {
+            self.object(sub_object);
+        }
+        for data in &object.data {
+            let value = match &data.value {
+                DataValue::Hex(bytes) => hex_literal(bytes),
+                DataValue::String(string) => quote(string),
+            };
+            self.line(&format!("data {} {}", quote(&data.name), value));
+        }
+
+        self.level -= 1;
+        self.line("}");
+    }
+
+    fn statement(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::Block(block) => self.block("", block),
+            Statement::FunctionDefinition {
+                name,
+                params,
+                returns,
+                body,
+            } => {
+                let header = if returns.is_empty() {
+                    format!("function {}({}) ", name, params.join(", "))
+                } else {
+                    format!(
+                        "function {}({}) -> {} ",
+                        name,
+                        params.join(", "),
+                        returns.join(", "),
+                    )
+                };
+                self.block(&header, body);
+            }
+            Statement::If { condition, body } => {
+                self.block(&format!("if {} ", condition), body);
+            }
+            Statement::Switch {
+                expression,
+                cases,
+                default,
+            } => {
+                self.line(&format!("switch {}", expression));
+                for case in cases {
+                    self.block(&format!("case {} ", case.literal), &case.body);
+                }
+                if let Some(default) = default {
+                    self.block("default ", default);
+                }
+            }
+            Statement::ForLoop {
+                pre,
+                condition,
+                post,
+                body,
+            } => {
+                let pre_simple = pre.statements.iter().all(is_simple);
+                let post_simple = post.statements.iter().all(is_simple);
+
+                if pre_simple && post_simple {
+                    let header = format!(
+                        "for {} {} {} ",
+                        inline_block(pre),
+                        condition,
+                        inline_block(post),
+                    );
+                    self.block(&header, body);
+                } else {
+                    self.line("for {");
+                    self.statements(&pre.statements);
+                    self.line(&format!("}} {} {{", condition));
+                    self.statements(&post.statements);
+                    self.line("}");
+                    self.block("", body);
+                }
+            }
+            Statement::SourceLocation(location) => self.line(&source_location_comment(location)),
+            _ => self.line(&simple_statement(stmt)),
+        }
+    }
+}
+
+impl fmt::Display for Object {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut printer = Printer::default();
+        printer.object(self);
+
+        write!(f, "{}", printer.out)
+    }
+}
+
+impl fmt::Display for Block {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut printer = Printer::default();
+        printer.block("", self);
+
+        write!(f, "{}", printer.out)
+    }
+}
+
+impl fmt::Display for Statement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut printer = Printer::default();
+        printer.statement(self);
+
+        write!(f, "{}", printer.out)
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expression::FunctionCall { name, args } => write!(f, "{}({})", name, comma_list(args)),
+            Expression::Identifier(name) => write!(f, "{}", name),
+            Expression::Literal(literal) => write!(f, "{}", literal),
+        }
+    }
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Literal::Number(num) => write!(f, "{}", num),
+            Literal::String(string) => write!(f, "{}", quote(string)),
+            Literal::Hex(bytes) => write!(f, "{}", hex_literal(bytes)),
+            Literal::True => write!(f, "true"),
+            Literal::False => write!(f, "false"),
+        }
+    }
+}
@@ -0,0 +1,213 @@
+//! Compilation of YUL source code with an external Solidity compiler.
+//!
+//! YUL source is passed to `solc --standard-json` with the `Yul` input
+//! language, which is the JSON interface to `solc --strict-assembly`.  The
+//! `solc` executable is optional.  It is located either through an explicitly
+//! configured path or by searching `PATH` and a descriptive error is returned
+//! if it cannot be found.
+
+use std::env;
+use std::ffi::OsStr;
+use std::fmt;
+use std::io::{
+    ErrorKind,
+    Write,
+};
+use std::path::{
+    Path,
+    PathBuf,
+};
+use std::process::{
+    Command,
+    Stdio,
+};
+
+use serde_json::{
+    json,
+    Value,
+};
+use vyper_parser::span::Span;
+
+/// The name under which YUL source is passed to `solc`.
+const SOURCE_NAME: &str = "input.yul";
+
+/// A message reported by `solc` about the compiled source.
+#[derive(Debug, PartialEq)]
+pub struct Diagnostic {
+    /// The severity of the message such as "error" or "warning".
+    pub severity: String,
+    pub message: String,
+    /// The span of YUL source text to which the message refers, if any.
+    pub span: Option<Span>,
+}
+
+/// An error encountered while compiling YUL source with `solc`.
+#[derive(Debug, PartialEq)]
+pub enum SolcError {
+    /// No `solc` executable was found.  Contains the configured path, if any.
+    NotFound(Option<PathBuf>),
+    /// The `solc` executable could not be run.
+    Io(String),
+    /// The output of `solc` could not be understood.
+    InvalidOutput(String),
+    /// Compilation failed with the given errors.
+    Compile(Vec<Diagnostic>),
+}
+
+impl fmt::Display for SolcError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolcError::NotFound(Some(path)) => {
+                write!(f, "solc executable not found at {}", path.display())
+            }
+            SolcError::NotFound(None) => write!(
+                f,
+                "solc executable not found on PATH; install solc or configure its path",
+            ),
+            SolcError::Io(msg) => write!(f, "failed to run solc: {}", msg),
+            SolcError::InvalidOutput(msg) => write!(f, "invalid output from solc: {}", msg),
+            SolcError::Compile(errors) => {
+                write!(f, "solc reported errors:")?;
+                for err in errors {
+                    write!(f, "\n{}: {}", err.severity, err.message)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The result of a successful compilation.
+#[derive(Debug, PartialEq)]
+pub struct SolcOutput {
+    /// The bytecode of the compiled object as hex digits.
+    pub bytecode: String,
+    /// Warnings and other messages that did not prevent compilation.
+    pub warnings: Vec<Diagnostic>,
+}
+
+/// Search the directories in the `PATH`-style list `paths` for an executable
+/// file with the name `name`.
+pub fn find_in_path(name: &str, paths: &OsStr) -> Option<PathBuf> {
+    env::split_paths(paths)
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+/// Locate the `solc` executable.  The configured path is used if given,
+/// otherwise `PATH` is searched.
+pub fn find_solc(configured: Option<&Path>) -> Result<PathBuf, SolcError> {
+    match configured {
+        Some(path) if path.is_file() => Ok(path.to_path_buf()),
+        Some(path) => Err(SolcError::NotFound(Some(path.to_path_buf()))),
+        None => env::var_os("PATH")
+            .and_then(|paths| find_in_path("solc", &paths))
+            .ok_or(SolcError::NotFound(None)),
+    }
+}
+
+/// Build the standard JSON input for compiling the YUL source `source`.
+pub fn standard_json_input(source: &str) -> Value {
+    json!({
+        "language": "Yul",
+        "sources": {
+            SOURCE_NAME: { "content": source },
+        },
+        "settings": {
+            "outputSelection": {
+                "*": { "*": ["evm.bytecode.object"] },
+            },
+        },
+    })
+}
+
+/// Convert an entry of the `errors` list of standard JSON output into a
+/// diagnostic.
+fn diagnostic(error: &Value) -> Diagnostic {
+    let message = error["formattedMessage"]
+        .as_str()
+        .or_else(|| error["message"].as_str())
+        .unwrap_or("unknown error");
+
+    let location = &error["sourceLocation"];
+    let span = match (location["start"].as_u64(), location["end"].as_u64()) {
+        (Some(start), Some(end)) if location["file"] == SOURCE_NAME => {
+            Some(Span::new(start as usize, end as usize))
+        }
+        _ => None,
+    };
+
+    Diagnostic {
+        severity: error["severity"].as_str().unwrap_or("error").to_string(),
+        message: message.trim_end().to_string(),
+        span,
+    }
+}
+
+/// Interpret the standard JSON output of `solc`.
+pub fn parse_standard_json_output(output: &str) -> Result<SolcOutput, SolcError> {
+    let output: Value =
+        serde_json::from_str(output).map_err(|e| SolcError::InvalidOutput(e.to_string()))?;
+
+    let (errors, warnings): (Vec<_>, Vec<_>) = output["errors"]
+        .as_array()
+        .map_or(&[][..], Vec::as_slice)
+        .iter()
+        .map(diagnostic)
+        .partition(|d| d.severity == "error");
+
+    if !errors.is_empty() {
+        return Err(SolcError::Compile(errors));
+    }
+
+    let bytecode = output["contracts"][SOURCE_NAME]
+        .as_object()
+        .and_then(|objects| objects.values().next())
+        .and_then(|object| object["evm"]["bytecode"]["object"].as_str())
+        .ok_or_else(|| SolcError::InvalidOutput("no bytecode in output".to_string()))?;
+
+    Ok(SolcOutput {
+        bytecode: bytecode.to_string(),
+        warnings,
+    })
+}
+
+/// Compile the YUL source `source` with the `solc` executable at `solc`.
+pub fn compile_with(solc: &Path, source: &str) -> Result<SolcOutput, SolcError> {
+    let mut child = Command::new(solc)
+        .arg("--standard-json")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| SolcError::Io(e.to_string()))?;
+
+    // A process that exits without reading its input is reported through its
+    // exit status below
+    let input = standard_json_input(source).to_string();
+    match child.stdin.take().unwrap().write_all(input.as_bytes()) {
+        Err(e) if e.kind() != ErrorKind::BrokenPipe => return Err(SolcError::Io(e.to_string())),
+        _ => {}
+    }
+
+    let output = child
+        .wait_with_output()
+        .map_err(|e| SolcError::Io(e.to_string()))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(SolcError::Io(format!(
+            "solc exited with {}: {}",
+            output.status,
+            stderr.trim_end(),
+        )));
+    }
+
+    parse_standard_json_output(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Compile the YUL source `source` with `solc`.  The configured path to `solc`
+/// is used if given, otherwise `PATH` is searched.
+pub fn compile(configured: Option<&Path>, source: &str) -> Result<SolcOutput, SolcError> {
+    compile_with(&find_solc(configured)?, source)
+}
@@ -0,0 +1,163 @@
+//! Tokenization of YUL source code.
+//!
+//! Whitespace and comments are skipped so that the resulting tokens can be
+//! passed directly to the parsers in [`crate::parsers`].  The exception are
+//! `/// @src` comments, which annotate code with source locations and are
+//! returned as tokens.  Token strings refer to the source text as written;
+//! escape sequences in string literals are resolved during parsing.
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use vyper_parser::span::Span;
+
+/// Indicates the basic syntactic element represented by a token.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Copy, Clone)]
+pub enum TokenType {
+    NAME,
+    NUMBER,
+    STRING,
+    HEXSTRING,
+    OP,
+    /// A `/// @src` comment up to the end of its line.
+    SRCLOC,
+    ENDMARKER,
+}
+
+/// A token parsed from a YUL source string.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct Token<'a> {
+    /// The type of a token.
+    pub typ: TokenType,
+
+    /// The text content of a parsed token.
+    pub string: &'a str,
+
+    /// The span of source text covered by a token.
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct TokenizeError {
+    pub msg: &'static str,
+    pub offset: usize,
+}
+
+/// Multi-character operators.  These must be checked before single character
+/// operators.
+const LONG_OPS: [&str; 2] = [":=", "->"];
+
+/// The prefix of comments that annotate code with source locations.
+pub const SOURCE_LOCATION_PREFIX: &str = "/// @src ";
+
+/// Single character operators.
+const SHORT_OPS: &str = "{}(),";
+
+fn is_name_start(c: u8) -> bool {
+    c.is_ascii_alphabetic() || c == b'_' || c == b'$'
+}
+
+fn is_name_continue(c: u8) -> bool {
+    is_name_start(c) || c.is_ascii_digit() || c == b'.'
+}
+
+/// Return the offset just past the closing quote of the string literal
+/// starting at `start`.  Escaped characters are skipped over but otherwise not
+/// interpreted.
+fn string_end(bytes: &[u8], start: usize) -> Result<usize, TokenizeError> {
+    let quote = bytes[start];
+    let mut pos = start + 1;
+
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'\\' => pos += 2,
+            b'\n' | b'\r' => break,
+            c if c == quote => return Ok(pos + 1),
+            _ => pos += 1,
+        }
+    }
+
+    Err(TokenizeError {
+        msg: "unterminated string literal",
+        offset: start,
+    })
+}
+
+/// Parse a YUL source string into a vector of tokens.  The last token is
+/// always an `ENDMARKER` token.
+pub fn tokenize<'a>(input: &'a str) -> Result<Vec<Token<'a>>, TokenizeError> {
+    let bytes = input.as_bytes();
+    let mut result = vec![];
+    let mut pos = 0;
+
+    let mut push = |typ, start, end| {
+        result.push(Token {
+            typ,
+            string: &input[start..end],
+            span: Span::new(start, end),
+        })
+    };
+
+    while pos < bytes.len() {
+        let c = bytes[pos];
+        let rest = &input[pos..];
+        let start = pos;
+
+        if c.is_ascii_whitespace() {
+            pos += 1;
+        } else if rest.starts_with("//") {
+            pos = match rest.find('\n') {
+                Some(len) => pos + len,
+                None => bytes.len(),
+            };
+            if rest.starts_with(SOURCE_LOCATION_PREFIX) {
+                push(TokenType::SRCLOC, start, pos);
+            }
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            pos = match comment.find("*/") {
+                Some(len) => pos + len + 4,
+                None => {
+                    return Err(TokenizeError {
+                        msg: "unterminated comment",
+                        offset: start,
+                    })
+                }
+            };
+        } else if c == b'"' || c == b'\'' {
+            pos = string_end(bytes, pos)?;
+            push(TokenType::STRING, start, pos);
+        } else if rest.starts_with("hex\"") || rest.starts_with("hex'") {
+            pos = string_end(bytes, pos + 3)?;
+            push(TokenType::HEXSTRING, start, pos);
+        } else if is_name_start(c) {
+            while pos < bytes.len() && is_name_continue(bytes[pos]) {
+                pos += 1;
+            }
+            push(TokenType::NAME, start, pos);
+        } else if c.is_ascii_digit() {
+            if rest.starts_with("0x") {
+                pos += 2;
+            }
+            while pos < bytes.len() && bytes[pos].is_ascii_alphanumeric() {
+                pos += 1;
+            }
+            push(TokenType::NUMBER, start, pos);
+        } else if let Some(op) = LONG_OPS.iter().find(|op| rest.starts_with(*op)) {
+            pos += op.len();
+            push(TokenType::OP, start, pos);
+        } else if SHORT_OPS.as_bytes().contains(&c) {
+            pos += 1;
+            push(TokenType::OP, start, pos);
+        } else {
+            return Err(TokenizeError {
+                msg: "unexpected character",
+                offset: start,
+            });
+        }
+    }
+
+    push(TokenType::ENDMARKER, pos, pos);
+
+    Ok(result)
+}
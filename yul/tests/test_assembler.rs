@@ -0,0 +1,312 @@
+extern crate wasm_bindgen_test;
+
+use nom::error::VerboseError;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use vyper_parser::span::Span;
+use vyper_yul::assembler::assembly::{
+    compress_source_map,
+    JumpType,
+    SourceEntry,
+};
+use vyper_yul::assembler::{
+    assemble_to_hex,
+    assemble_with_source_maps,
+};
+use vyper_yul::ast::Object;
+use vyper_yul::errors::AssemblyError;
+use vyper_yul::parsers::object_input;
+use vyper_yul::tokenizer::tokenize;
+
+/// Parse the YUL object in `source`.
+fn parse(source: &str) -> Object {
+    let tokens = tokenize(source).unwrap();
+    let (_, object) = object_input::<VerboseError<_>>(&tokens[..]).unwrap();
+
+    object.node
+}
+
+/// Assemble the YUL code block in `code` wrapped in an object.
+fn assemble_code(code: &str) -> Result<String, AssemblyError> {
+    assemble_to_hex(&parse(&format!("object \"Test\" {{ code {} }}", code)))
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_assemble_code() {
+    let examples = vec![
+        // PUSH1 1, PUSH1 0, SSTORE, STOP
+        ("{ sstore(0, 1) }", "600160005500"),
+        // Variables are accessed with DUP and removed at the end of the block
+        (
+            "{ let x := 2 let y := add(x, 1) sstore(x, y) }",
+            "600260018101808255505000",
+        ),
+        // Assignments swap the new value into place
+        ("{ let x := 1 x := 2 }", "6001600290505000"),
+        (
+            "{ let x := 1 x := 2 sstore(x, x) }",
+            "6001600290508081555000",
+        ),
+        ("{ let a, b }", "60006000505000"),
+        // Strings are left-aligned and booleans are numbers
+        (
+            r#"{ pop("ab") pop(true) pop(false) }"#,
+            "7f616200000000000000000000000000000000000000000000000000000000000050600150600050\
+             00",
+        ),
+        ("{ if calldatasize() { stop() } }", "3615600657005b00"),
+        (
+            "{ switch calldatasize() case 0 { stop() } default { invalid() } }",
+            "36806000146\
+             00c57fe6011565b006011565b5000",
+        ),
+        (
+            "{ for { let i := 0 } lt(i, 3) { i := add(i, 1) } { sstore(i, i) } }",
+            "60005b6003811015601857808155\
+             5b600181019050600256\
+             5b5000",
+        ),
+    ];
+
+    for (code, expected) in examples {
+        assert_eq!(assemble_code(code), Ok(expected.to_string()), "{}", code);
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_assemble_functions() {
+    let code = "{
+        function inc(a) -> b { b := add(a, 1) }
+        sstore(0, inc(41))
+    }";
+
+    // Jump over the function definition
+    let skip = "6011565b";
+    // Initialize the return variable and compute it
+    let body = "6000600182019050";
+    // Move the return value and label into place and return
+    let exit = "5b91905056";
+    // Push the return label and argument then jump to the function
+    let call = "5b601960296003565b";
+    let store = "60005500";
+
+    assert_eq!(
+        assemble_code(code),
+        Ok([skip, body, exit, call, store].concat())
+    );
+
+    // Functions may be called before their definition, return several values
+    // and use `leave`
+    let code = "{
+        let a, b := pair(1)
+        sstore(a, b)
+        function pair(x) -> y, z {
+            y := x
+            if x { leave }
+            z := 2
+        }
+    }";
+    assert!(assemble_code(code).is_ok());
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_assemble_objects() {
+    let object = parse(
+        r#"object "C" {
+            code {
+                datacopy(0, dataoffset("R"), datasize("R"))
+                return(0, datasize("R"))
+            }
+            object "R" {
+                code { stop() }
+            }
+            data "D" hex"abcd"
+        }"#,
+    );
+
+    // The runtime object and data section follow the deployment code
+    assert_eq!(
+        assemble_to_hex(&object),
+        Ok("6002600d60003960026000f3000000abcd".to_string())
+    );
+
+    // Offsets are pushed with a larger width once code exceeds 255 bytes
+    let object = parse(&format!(
+        r#"object "C" {{ code {{ pop(dataoffset("D")) }} data "D" hex"{}" }}"#,
+        "00".repeat(300),
+    ));
+    assert_eq!(
+        assemble_to_hex(&object),
+        Ok(format!("6100055000{}", "00".repeat(300)))
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_assemble_errors() {
+    let many_vars: String = (0..17).map(|i| format!("let x{} := {} ", i, i)).collect();
+    let stack_too_deep = format!("{{ {} pop(x0) }}", many_vars);
+
+    let examples = vec![
+        (stack_too_deep.as_str(), "stack too deep", "x0"),
+        ("{ foo() }", "unknown function", "foo"),
+        ("{ pop(add(1)) }", "wrong number of arguments", "add"),
+        ("{ pop(x) }", "undeclared variable", "x"),
+        ("{ let x let x }", "variable already declared", "x"),
+        (
+            "{ let a, b := 1 }",
+            "wrong number of values in variable declaration",
+            "a",
+        ),
+        (
+            r#"{ pop(datasize("X")) }"#,
+            "unknown object or data section",
+            "X",
+        ),
+        (
+            "{ pop(datasize(1)) }",
+            "expected a single string literal argument",
+            "datasize",
+        ),
+        (
+            "{ add(1, 2) }",
+            "expression statements must not return values",
+            "add",
+        ),
+        (
+            "{ function f() -> a, b { } sstore(f(), 1) }",
+            "expression must evaluate to a single value",
+            "f",
+        ),
+        (
+            "{ pop(0x10000000000000000000000000000000000000000000000000000000000000000) }",
+            "number literal is too large",
+            "0x10000000000000000000000000000000000000000000000000000000000000000",
+        ),
+        (
+            r#"{ pop("0123456789012345678901234567890123") }"#,
+            "literal is longer than 32 bytes",
+            "0123456789012345678901234567890123",
+        ),
+        ("{ break }", "break or continue outside of a for loop", ""),
+        ("{ leave }", "leave outside of a function", ""),
+        (
+            "{ function f() { } function f() { } }",
+            "duplicate function definition",
+            "f",
+        ),
+        ("{ function add() { } }", "function name is reserved", "add"),
+        (
+            "{ let x function f() { pop(x) } }",
+            "undeclared variable",
+            "x",
+        ),
+    ];
+
+    for (code, msg, name) in examples {
+        assert_eq!(
+            assemble_code(code),
+            Err(AssemblyError::new(msg, name)),
+            "{}",
+            code
+        );
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_compress_source_map() {
+    let entry = |span: Option<(usize, usize)>, jump| SourceEntry {
+        span: span.map(|(start, end)| Span::new(start, end)),
+        jump,
+    };
+
+    assert_eq!(compress_source_map(&[]), "");
+    assert_eq!(
+        compress_source_map(&[
+            entry(None, JumpType::Regular),
+            entry(Some((5, 10)), JumpType::Regular),
+            entry(Some((5, 10)), JumpType::Regular),
+            entry(Some((5, 12)), JumpType::Into),
+            entry(Some((2, 9)), JumpType::Into),
+            entry(Some((2, 9)), JumpType::Out),
+            entry(None, JumpType::Regular),
+        ]),
+        "-1:-1:-1:-;5:5:0;;:7::i;2;:::o;-1:-1:-1:-"
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_assemble_source_maps() {
+    let object = parse(
+        r#"object "C" {
+            code {
+                /// @src 0:0:50
+                datacopy(0, dataoffset("R"), datasize("R"))
+                return(0, datasize("R"))
+            }
+            object "R" {
+                code {
+                    function f() {
+                        /// @src 0:20:30
+                        sstore(0, 1)
+                    }
+                    /// @src 0:10:40
+                    f()
+                    {
+                        /// @src -1:-1:-1
+                        pop(1)
+                    }
+                    stop()
+                }
+            }
+        }"#,
+    );
+
+    let assembled = assemble_with_source_maps(&object).unwrap();
+    assert_eq!(assembled.name, "C");
+    assert_eq!(assembled.objects.len(), 1);
+    assert_eq!(assembled.objects[0].name, "R");
+
+    // The deployment code stems from the same span except for the final
+    // `STOP` instruction added by the assembler
+    assert_eq!(assembled.source_map, "0:50:0:-;;;;;;;-1:-1:-1");
+
+    // The function body is generated before the code that calls it.  Its
+    // location is set within the function body and does not carry over.
+    // Locations set in nested blocks only apply inside them.
+    let runtime = &assembled.objects[0];
+    assert_eq!(
+        runtime.source_map,
+        [
+            // Jump over the function definition
+            "-1:-1:-1:-",
+            "",
+            // The function body
+            "",
+            "20:10:0",
+            "",
+            "",
+            // The function exit
+            "-1:-1:-1",
+            ":::o",
+            ":::-",
+            // The call
+            "10:30:0",
+            "",
+            ":::i",
+            ":::-",
+            // The nested block
+            "-1:-1:-1",
+            "",
+            // The rest of the code and the final `STOP`
+            "10:30:0",
+            "-1:-1:-1",
+        ]
+        .join(";")
+    );
+}
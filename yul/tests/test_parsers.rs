@@ -0,0 +1,280 @@
+extern crate wasm_bindgen_test;
+
+#[macro_use]
+extern crate vyper_test_utils;
+
+use nom::error::{
+    ErrorKind,
+    ParseError,
+    VerboseError,
+};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use vyper_parser::span::Span;
+use vyper_test_utils::to_ron_string_pretty;
+use vyper_yul::parsers::*;
+use vyper_yul::tokenizer::{
+    tokenize,
+    TokenType,
+    TokenizeError,
+};
+
+type SimpleError<I> = (I, ErrorKind);
+
+/// Convert a parser into one that must consume all tokens up to the
+/// `ENDMARKER` token.
+fn standalone<'a, O, E, F>(parser: F) -> impl Fn(TokenSlice<'a>) -> TokenResult<'a, O, E>
+where
+    E: ParseError<TokenSlice<'a>>,
+    F: Fn(TokenSlice<'a>) -> TokenResult<'a, O, E>,
+{
+    move |input: TokenSlice<'a>| {
+        let (input, o) = parser(input)?;
+        let (input, _) = endmarker_token(input)?;
+
+        Ok((input, o))
+    }
+}
+
+/// Assert that `$parser` succeeds and parses all tokens when applied to the
+/// given input.  Expected results are defined as serializations.  Also assert
+/// that printing the parsed AST and parsing the result again produces the same
+/// AST.
+macro_rules! assert_fixtures_parsed {
+    ($parser:expr, $($path:expr),+,) => {{
+        assert_fixtures_parsed!($parser, $($path),+)
+    }};
+    ($parser:expr, $($path:expr),+) => {{
+        let test_files = vec![
+            $(($path, include_test_example!($path))),+
+        ];
+
+        for (filename, (inp, expected_ser)) in test_files {
+            let tokens = tokenize(inp).unwrap();
+            let actual: TokenResult<_, VerboseError<_>> = $parser(&tokens[..]);
+
+            if let Err(err) = &actual {
+                println!("Parsing error:\n{:#?}", err);
+            }
+
+            let (actual_remaining, actual_ast) = actual.unwrap();
+            let actual_ser = to_ron_string_pretty(&actual_ast).unwrap();
+
+            assert_eq!(actual_remaining, empty_slice!());
+            assert_strings_eq!(
+                actual_ser,
+                expected_ser,
+                "\nParsing results did not match for {}",
+                filename,
+            );
+
+            // Round trip through the printer
+            let printed = actual_ast.node.to_string();
+            let tokens = tokenize(&printed).unwrap();
+            let reparsed: TokenResult<_, SimpleError<_>> = $parser(&tokens[..]);
+
+            assert_eq!(
+                reparsed.unwrap().1.node,
+                actual_ast.node,
+                "\nRound trip failed for {} printed as:\n{}",
+                filename,
+                printed,
+            );
+        }
+    }};
+}
+
+/// Assert that `$parser` fails when applied to each of the given inputs.
+macro_rules! assert_parser_err {
+    ($parser:expr, $examples:expr) => {{
+        for inp in $examples.iter() {
+            let tokens = tokenize(inp).unwrap();
+            let actual: TokenResult<_, SimpleError<_>> = $parser(&tokens[..]);
+
+            assert!(actual.is_err(), "expected error for {:?}", inp);
+        }
+    }};
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_expression_ok() {
+    assert_fixtures_parsed!(
+        standalone(expression),
+        "fixtures/parsers/expression/number.ron",
+        "fixtures/parsers/expression/identifier.ron",
+        "fixtures/parsers/expression/call.ron",
+        "fixtures/parsers/expression/string.ron",
+        "fixtures/parsers/expression/hex_string.ron",
+        "fixtures/parsers/expression/bool.ron",
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_statement_ok() {
+    assert_fixtures_parsed!(
+        standalone(statement),
+        "fixtures/parsers/statement/variable_declaration.ron",
+        "fixtures/parsers/statement/uninitialized_declaration.ron",
+        "fixtures/parsers/statement/assignment.ron",
+        "fixtures/parsers/statement/function_definition.ron",
+        "fixtures/parsers/statement/no_params_or_returns.ron",
+        "fixtures/parsers/statement/switch.ron",
+        "fixtures/parsers/statement/switch_default_only.ron",
+        "fixtures/parsers/statement/for_loop.ron",
+        "fixtures/parsers/statement/nested_blocks.ron",
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_object_ok() {
+    assert_fixtures_parsed!(
+        object_input,
+        "fixtures/parsers/object/code_only.ron",
+        "fixtures/parsers/object/comments.ron",
+        "fixtures/parsers/object/nested_objects_and_data.ron",
+        "fixtures/parsers/object/source_locations.ron",
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_expression_err() {
+    assert_parser_err!(
+        standalone(expression),
+        [
+            // Keywords are not identifiers
+            "let",
+            "leave()",
+            // Malformed number literals
+            "0x",
+            "12ab",
+            // Malformed string escapes
+            r#""\q""#,
+            r#""\x4""#,
+            r#""\xff""#,
+            // Malformed hex literals
+            r#"hex"abc""#,
+            r#"hex"ab__cd""#,
+            r#"hex"zz""#,
+            // Missing commas
+            "add(1 2)",
+        ]
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_statement_err() {
+    assert_parser_err!(
+        standalone(statement),
+        [
+            // Only function calls may be used as statements
+            "x",
+            "1",
+            "let := 1",
+            "a, := 1",
+            "switch x",
+            "switch x default { } case 1 { }",
+            "function f(a,) { }",
+            "for { } 1 { }",
+            // Malformed source locations
+            "/// @src 1:2:3",
+            "/// @src 0:5:2",
+            "/// @src 0:a:2",
+            "/// @src 0:1",
+            "/// @src 0:1:2:3",
+        ]
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_object_err() {
+    assert_parser_err!(
+        object_input,
+        [
+            "{ }",
+            "object Foo { code { } }",
+            r#"object "Foo" { }"#,
+            r#"object "Foo" { code { } data "x" }"#,
+            r#"object "Foo" { code { } } { }"#,
+        ]
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_tokenize_errors() {
+    let examples = vec![
+        (
+            "let x := \"abc",
+            Err(TokenizeError {
+                msg: "unterminated string literal",
+                offset: 9,
+            }),
+        ),
+        (
+            "let x := hex\"ab\n\"",
+            Err(TokenizeError {
+                msg: "unterminated string literal",
+                offset: 12,
+            }),
+        ),
+        (
+            "{ /* x }",
+            Err(TokenizeError {
+                msg: "unterminated comment",
+                offset: 2,
+            }),
+        ),
+        (
+            "let x := 1 + 2",
+            Err(TokenizeError {
+                msg: "unexpected character",
+                offset: 11,
+            }),
+        ),
+    ];
+
+    for (input, expected) in examples {
+        assert_eq!(tokenize(input), expected);
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_tokenize_source_locations() {
+    let tokens = tokenize("// @src 0:1:2\n/// @src 0:1:2 \"a\"\nstop()").unwrap();
+    let types: Vec<_> = tokens.iter().map(|t| (t.typ, t.string)).collect();
+
+    assert_eq!(
+        types,
+        vec![
+            (TokenType::SRCLOC, "/// @src 0:1:2 \"a\""),
+            (TokenType::NAME, "stop"),
+            (TokenType::OP, "("),
+            (TokenType::OP, ")"),
+            (TokenType::ENDMARKER, ""),
+        ]
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_source_location_at() {
+    let source =
+        "{\n/// @src 0:1:2\nmstore(0, 1)\n{\n/// @src 0:5:9\nstop()\n}\nreturn(0, 0)\n/// \
+                  @src -1:-1:-1\ninvalid()\n}";
+    let at = |code: &str| source_location_at(source, source.find(code).unwrap());
+
+    assert_eq!(source_location_at(source, 0), None);
+    assert_eq!(at("mstore"), Some(Span::new(1, 2)));
+    assert_eq!(at("stop"), Some(Span::new(5, 9)));
+    // Locations annotated in a block end with the block
+    assert_eq!(at("return"), Some(Span::new(1, 2)));
+    assert_eq!(at("invalid"), None);
+    assert_eq!(source_location_at("\"", 0), None);
+}
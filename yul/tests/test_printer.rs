@@ -0,0 +1,257 @@
+extern crate wasm_bindgen_test;
+
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use vyper_yul::ast::*;
+
+fn assign(name: &str, value: Expression) -> Statement {
+    Statement::Assignment {
+        names: vec![name.to_string()],
+        value,
+    }
+}
+
+fn let_(name: &str, value: Expression) -> Statement {
+    Statement::VariableDeclaration {
+        names: vec![name.to_string()],
+        value: Some(value),
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_print_expressions() {
+    let examples = vec![
+        (Expression::num(42), "42"),
+        (Expression::ident("foo.bar"), "foo.bar"),
+        (Expression::call("caller", vec![]), "caller()"),
+        (
+            Expression::call(
+                "add",
+                vec![
+                    Expression::ident("x"),
+                    Expression::call("mul", vec![Expression::num(2), Expression::ident("y")]),
+                ],
+            ),
+            "add(x, mul(2, y))",
+        ),
+        (
+            Expression::Literal(Literal::Number("0xff".to_string())),
+            "0xff",
+        ),
+        (Expression::Literal(Literal::True), "true"),
+        (Expression::Literal(Literal::False), "false"),
+        (
+            Expression::Literal(Literal::String("a \"quoted\"\\ string\n".to_string())),
+            r#""a \"quoted\"\\ string\n""#,
+        ),
+        (
+            Expression::Literal(Literal::String("\u{7f}é".to_string())),
+            r#""\x7f\xc3\xa9""#,
+        ),
+        (
+            Expression::Literal(Literal::Hex(vec![0x00, 0xab, 0x12])),
+            r#"hex"00ab12""#,
+        ),
+    ];
+
+    for (expr, expected) in examples {
+        assert_eq!(expr.to_string(), expected);
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_print_simple_statements() {
+    let examples = vec![
+        (let_("x", Expression::num(1)), "let x := 1\n"),
+        (
+            Statement::VariableDeclaration {
+                names: vec!["a".to_string(), "b".to_string()],
+                value: None,
+            },
+            "let a, b\n",
+        ),
+        (
+            Statement::Assignment {
+                names: vec!["a".to_string(), "b".to_string()],
+                value: Expression::call("f", vec![]),
+            },
+            "a, b := f()\n",
+        ),
+        (
+            Expression::call("sstore", vec![Expression::num(0), Expression::num(1)]).into(),
+            "sstore(0, 1)\n",
+        ),
+        (Statement::Break, "break\n"),
+        (Statement::Continue, "continue\n"),
+        (Statement::Leave, "leave\n"),
+        (Statement::Block(Block::default()), "{ }\n"),
+    ];
+
+    for (stmt, expected) in examples {
+        assert_eq!(stmt.to_string(), expected);
+    }
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_print_compound_statements() {
+    let func = Statement::FunctionDefinition {
+        name: "add_one".to_string(),
+        params: vec!["a".to_string()],
+        returns: vec!["b".to_string()],
+        body: Block::new(vec![
+            assign(
+                "b",
+                Expression::call("add", vec![Expression::ident("a"), Expression::num(1)]),
+            ),
+            Statement::If {
+                condition: Expression::call(
+                    "lt",
+                    vec![Expression::ident("b"), Expression::ident("a")],
+                ),
+                body: Block::new(vec![Expression::call(
+                    "revert",
+                    vec![Expression::num(0), Expression::num(0)],
+                )
+                .into()]),
+            },
+        ]),
+    };
+    assert_eq!(
+        func.to_string(),
+        r#"function add_one(a) -> b {
+    b := add(a, 1)
+    if lt(b, a) {
+        revert(0, 0)
+    }
+}
+"#,
+    );
+
+    let func = Statement::FunctionDefinition {
+        name: "noop".to_string(),
+        params: vec![],
+        returns: vec![],
+        body: Block::default(),
+    };
+    assert_eq!(func.to_string(), "function noop() { }\n");
+
+    let switch = Statement::Switch {
+        expression: Expression::ident("x"),
+        cases: vec![
+            Case {
+                literal: Literal::Number("0".to_string()),
+                body: Block::new(vec![assign("y", Expression::num(1))]),
+            },
+            Case {
+                literal: Literal::String("abc".to_string()),
+                body: Block::default(),
+            },
+        ],
+        default: Some(Block::new(vec![Statement::Leave])),
+    };
+    assert_eq!(
+        switch.to_string(),
+        r#"switch x
+case 0 {
+    y := 1
+}
+case "abc" { }
+default {
+    leave
+}
+"#,
+    );
+
+    let for_loop = Statement::ForLoop {
+        pre: Block::new(vec![let_("i", Expression::num(0))]),
+        condition: Expression::call("lt", vec![Expression::ident("i"), Expression::num(10)]),
+        post: Block::new(vec![assign(
+            "i",
+            Expression::call("add", vec![Expression::ident("i"), Expression::num(1)]),
+        )]),
+        body: Block::new(vec![Statement::Continue]),
+    };
+    assert_eq!(
+        for_loop.to_string(),
+        r#"for { let i := 0 } lt(i, 10) { i := add(i, 1) } {
+    continue
+}
+"#,
+    );
+
+    let for_loop = Statement::ForLoop {
+        pre: Block::new(vec![Statement::Block(Block::default())]),
+        condition: Expression::num(1),
+        post: Block::default(),
+        body: Block::new(vec![Statement::Break]),
+    };
+    assert_eq!(
+        for_loop.to_string(),
+        r#"for {
+    { }
+} 1 {
+}
+{
+    break
+}
+"#,
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_print_object() {
+    let object = Object {
+        name: "Greeter".to_string(),
+        code: Block::new(vec![Expression::call(
+            "datacopy",
+            vec![
+                Expression::num(0),
+                Expression::call(
+                    "dataoffset",
+                    vec![Expression::Literal(Literal::String(
+                        "Greeter_deployed".to_string(),
+                    ))],
+                ),
+                Expression::call(
+                    "datasize",
+                    vec![Expression::Literal(Literal::String(
+                        "Greeter_deployed".to_string(),
+                    ))],
+                ),
+            ],
+        )
+        .into()]),
+        objects: vec![Object {
+            name: "Greeter_deployed".to_string(),
+            code: Block::default(),
+            objects: vec![],
+            data: vec![Data {
+                name: "greeting".to_string(),
+                value: DataValue::String("hello".to_string()),
+            }],
+        }],
+        data: vec![Data {
+            name: "table".to_string(),
+            value: DataValue::Hex(vec![1, 2, 255]),
+        }],
+    };
+
+    assert_eq!(
+        object.to_string(),
+        r#"object "Greeter" {
+    code {
+        datacopy(0, dataoffset("Greeter_deployed"), datasize("Greeter_deployed"))
+    }
+    object "Greeter_deployed" {
+        code { }
+        data "greeting" "hello"
+    }
+    data "table" hex"0102ff"
+}
+"#,
+    );
+}
@@ -0,0 +1,189 @@
+extern crate wasm_bindgen_test;
+
+use std::fs;
+use std::path::{
+    Path,
+    PathBuf,
+};
+
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use vyper_parser::span::Span;
+use vyper_yul::solc::*;
+
+const SOURCE: &str = "object \"Test\" { code { sstore(0, 1) } }";
+
+#[test]
+#[wasm_bindgen_test]
+fn test_standard_json_input() {
+    let input = standard_json_input(SOURCE);
+
+    assert_eq!(input["language"], "Yul");
+    assert_eq!(input["sources"]["input.yul"]["content"], SOURCE);
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_parse_output() {
+    let output = r#"{
+        "contracts": {
+            "input.yul": {
+                "Test": { "evm": { "bytecode": { "object": "600160005500" } } }
+            }
+        },
+        "errors": [
+            {
+                "severity": "warning",
+                "message": "Unused variable",
+                "sourceLocation": { "file": "input.yul", "start": 4, "end": 10 }
+            }
+        ]
+    }"#;
+
+    assert_eq!(
+        parse_standard_json_output(output),
+        Ok(SolcOutput {
+            bytecode: "600160005500".to_string(),
+            warnings: vec![Diagnostic {
+                severity: "warning".to_string(),
+                message: "Unused variable".to_string(),
+                span: Some(Span::new(4, 10)),
+            }],
+        })
+    );
+}
+
+#[test]
+#[wasm_bindgen_test]
+fn test_parse_output_errors() {
+    let output = r#"{
+        "errors": [
+            {
+                "severity": "error",
+                "message": "Function not found.",
+                "formattedMessage": "DeclarationError: Function not found.\n",
+                "sourceLocation": { "file": "input.yul", "start": 24, "end": 27 }
+            },
+            {
+                "severity": "error",
+                "message": "Unknown exception during compilation."
+            }
+        ]
+    }"#;
+
+    assert_eq!(
+        parse_standard_json_output(output),
+        Err(SolcError::Compile(vec![
+            Diagnostic {
+                severity: "error".to_string(),
+                message: "DeclarationError: Function not found.".to_string(),
+                span: Some(Span::new(24, 27)),
+            },
+            Diagnostic {
+                severity: "error".to_string(),
+                message: "Unknown exception during compilation.".to_string(),
+                span: None,
+            },
+        ]))
+    );
+
+    assert!(matches!(
+        parse_standard_json_output("not json"),
+        Err(SolcError::InvalidOutput(_))
+    ));
+    assert_eq!(
+        parse_standard_json_output("{}"),
+        Err(SolcError::InvalidOutput(
+            "no bytecode in output".to_string()
+        ))
+    );
+}
+
+#[test]
+fn test_solc_not_found() {
+    let dir = tempfile::tempdir().unwrap();
+    let missing = dir.path().join("solc");
+
+    let err = compile(Some(&missing), SOURCE).unwrap_err();
+    assert_eq!(err, SolcError::NotFound(Some(missing.clone())));
+    assert_eq!(
+        err.to_string(),
+        format!("solc executable not found at {}", missing.display())
+    );
+
+    assert_eq!(find_in_path("solc", dir.path().as_os_str()), None);
+    assert_eq!(
+        SolcError::NotFound(None).to_string(),
+        "solc executable not found on PATH; install solc or configure its path"
+    );
+}
+
+/// Write an executable shell script with the body `body` to `dir/solc` and
+/// return its path.
+#[cfg(unix)]
+fn stub_solc(dir: &Path, body: &str) -> PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = dir.join("solc");
+    fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+
+    path
+}
+
+#[test]
+#[cfg(unix)]
+fn test_stub_solc() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("input.json");
+    let solc = stub_solc(
+        dir.path(),
+        &format!(
+            r#"test "$1" = --standard-json || exit 1
+cat > {}
+echo '{{"contracts": {{"input.yul": {{"Test": {{"evm": {{"bytecode": {{"object": "600160005500"}}}}}}}}}}}}'"#,
+            input.display(),
+        ),
+    );
+
+    // The stub is found on a `PATH`-style list of directories
+    let paths = std::env::join_paths([Path::new("/nonexistent"), dir.path()]).unwrap();
+    assert_eq!(find_in_path("solc", &paths), Some(solc.clone()));
+
+    assert_eq!(
+        compile(Some(&solc), SOURCE),
+        Ok(SolcOutput {
+            bytecode: "600160005500".to_string(),
+            warnings: vec![],
+        })
+    );
+
+    // The YUL source was passed as standard JSON input
+    let received: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&input).unwrap()).unwrap();
+    assert_eq!(received, standard_json_input(SOURCE));
+}
+
+#[test]
+#[cfg(unix)]
+fn test_stub_solc_errors() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let solc = stub_solc(
+        dir.path(),
+        r#"cat > /dev/null
+echo '{"errors": [{"severity": "error", "message": "Invalid", "sourceLocation": {"file": "input.yul", "start": 24, "end": 30}}]}'"#,
+    );
+    assert_eq!(
+        compile(Some(&solc), SOURCE),
+        Err(SolcError::Compile(vec![Diagnostic {
+            severity: "error".to_string(),
+            message: "Invalid".to_string(),
+            span: Some(Span::new(24, 30)),
+        }]))
+    );
+
+    let solc = stub_solc(dir.path(), "echo 'crashed' >&2\nexit 3");
+    let err = compile(Some(&solc), SOURCE).unwrap_err();
+    assert!(matches!(&err, SolcError::Io(msg) if msg.ends_with(": crashed")));
+}